@@ -58,8 +58,13 @@ use unc_primitives::challenge::{
     MaybeEncodedShardChunk, PartialState, SlashedValidator,
 };
 use unc_primitives::checked_feature;
+use unc_primitives::epoch_manager::block_info::BlockInfo;
+use unc_primitives::epoch_manager::epoch_info::EpochInfo;
 #[cfg(feature = "new_epoch_sync")]
 use unc_primitives::epoch_manager::epoch_sync::EpochSyncInfo;
+use unc_primitives::epoch_sync::{
+    light_client_block_hash, EpochSyncFinalizationResponse, EpochSyncResponse,
+};
 #[cfg(feature = "new_epoch_sync")]
 use unc_primitives::errors::epoch_sync::EpochSyncHashType;
 use unc_primitives::errors::EpochError;
@@ -2771,6 +2776,9 @@ impl Chain {
         Ok(())
     }
 
+    /// Removes the downloaded state parts together with the state header, so
+    /// that state sync to the same sync hash starts from the very beginning
+    /// instead of resuming from the stored data.
     pub fn clear_downloaded_parts(
         &mut self,
         shard_id: ShardId,
@@ -2779,6 +2787,7 @@ impl Chain {
     ) -> Result<(), Error> {
         let mut chain_store_update = self.mut_chain_store().store_update();
         chain_store_update.gc_col_state_parts(sync_hash, shard_id, num_parts)?;
+        chain_store_update.gc_col_state_header(sync_hash, shard_id)?;
         chain_store_update.commit()
     }
 
@@ -4388,6 +4397,27 @@ impl Chain {
         Ok(headers)
     }
 
+    /// Retrieve headers on the main chain with heights in `start_height..=end_height`, ordered by
+    /// height. At most `max_headers_returned` heights are looked at, so that a peer can't make us
+    /// scan an arbitrarily long range.
+    pub fn retrieve_headers_in_range(
+        &self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+        max_headers_returned: u64,
+    ) -> Result<Vec<BlockHeader>, Error> {
+        let end_height = end_height
+            .min(start_height.saturating_add(max_headers_returned.saturating_sub(1)))
+            .min(self.header_head()?.height);
+        let mut headers = vec![];
+        for h in start_height..=end_height {
+            if let Ok(header) = self.get_block_header_by_height(h) {
+                headers.push(header);
+            }
+        }
+        Ok(headers)
+    }
+
     /// Returns a vector of chunk headers, each of which corresponds to the previous chunk of
     /// a chunk in the block after `prev_block`
     /// This function is important when the block after `prev_block` has different number of chunks
@@ -4478,6 +4508,153 @@ impl Chain {
     }
 }
 
+/// Epoch Sync over light client blocks.
+impl Chain {
+    /// Returns the light client block of `epoch_id`, which proves block producers of the next
+    /// epoch, or `UpToDate` if `epoch_id` is the epoch of the head.
+    pub fn get_epoch_sync_response(&self, epoch_id: &EpochId) -> Result<EpochSyncResponse, Error> {
+        if epoch_id == &self.head()?.epoch_id {
+            return Ok(EpochSyncResponse::UpToDate);
+        }
+        let light_client_block = self.chain_store.get_epoch_light_client_block(&epoch_id.0)?;
+        Ok(EpochSyncResponse::Advance {
+            light_client_block_view: Box::new(LightClientBlockView::clone(&light_client_block)),
+        })
+    }
+
+    /// Returns headers linking the light client block of the epoch before `epoch_id` to the
+    /// first block of `epoch_id`, together with epoch data required to continue with Header
+    /// Sync and State Sync from that block.
+    pub fn get_epoch_sync_finalization_response(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<EpochSyncFinalizationResponse, Error> {
+        let head = self.head()?;
+        let epoch_block_hash = if epoch_id == &head.epoch_id {
+            head.last_block_hash
+        } else {
+            let light_client_block = self.chain_store.get_epoch_light_client_block(&epoch_id.0)?;
+            light_client_block_hash(&light_client_block)
+        };
+        let epoch_block_info = self.epoch_manager.get_block_info(&epoch_block_hash)?;
+        let cur_epoch_header = self.get_block_header(epoch_block_info.epoch_first_block())?;
+        let prev_epoch_id = self.get_block_header(cur_epoch_header.prev_hash())?.epoch_id().clone();
+        let prev_light_client_block =
+            self.chain_store.get_epoch_light_client_block(&prev_epoch_id.0)?;
+        let prev_light_client_block_hash = light_client_block_hash(&prev_light_client_block);
+
+        let mut prev_epoch_headers = vec![];
+        let mut header_hash = *cur_epoch_header.prev_hash();
+        loop {
+            let header = self.get_block_header(&header_hash)?;
+            if header.height() < prev_light_client_block.inner_lite.height {
+                return Err(Error::Other(format!(
+                    "Light client block of epoch {:?} is not on the chain of {}",
+                    prev_epoch_id,
+                    cur_epoch_header.hash()
+                )));
+            }
+            header_hash = *header.prev_hash();
+            let is_light_client_block = header.hash() == &prev_light_client_block_hash;
+            prev_epoch_headers.push(header);
+            if is_light_client_block {
+                break;
+            }
+        }
+        prev_epoch_headers.reverse();
+
+        let (
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            prev_epoch_info,
+            cur_epoch_info,
+            next_epoch_info,
+        ) = self.epoch_manager.get_epoch_sync_data(
+            cur_epoch_header.prev_hash(),
+            cur_epoch_header.epoch_id(),
+            cur_epoch_header.next_epoch_id(),
+        )?;
+        let header_sync_init_header_tree = PartialMerkleTree::clone(
+            &self.chain_store.get_block_merkle_tree(cur_epoch_header.hash())?,
+        );
+        Ok(EpochSyncFinalizationResponse {
+            cur_epoch_header,
+            prev_epoch_headers,
+            header_sync_init_header_tree,
+            prev_epoch_first_block_info: BlockInfo::clone(&prev_epoch_first_block_info),
+            prev_epoch_prev_last_block_info: BlockInfo::clone(&prev_epoch_prev_last_block_info),
+            prev_epoch_last_block_info: BlockInfo::clone(&prev_epoch_last_block_info),
+            prev_epoch_info: EpochInfo::clone(&prev_epoch_info),
+            cur_epoch_info: EpochInfo::clone(&cur_epoch_info),
+            next_epoch_info: EpochInfo::clone(&next_epoch_info),
+        })
+    }
+
+    /// Saves a light client block validated during Epoch Sync, so that it can be served to
+    /// other nodes.
+    pub fn save_epoch_sync_light_client_block(
+        &mut self,
+        epoch_id: &EpochId,
+        light_client_block: LightClientBlockView,
+    ) -> Result<(), Error> {
+        let mut chain_store_update = self.chain_store.store_update();
+        chain_store_update.save_epoch_light_client_block(&epoch_id.0, light_client_block);
+        chain_store_update.commit()
+    }
+
+    /// Finishes Epoch Sync: saves headers of the validated `response`, initializes epoch
+    /// manager with its epoch data and moves header head to the first block of the epoch, so
+    /// that Header Sync continues from there.
+    /// Validity of `response` is checked by Epoch Sync methods.
+    pub fn apply_epoch_sync_finalization(
+        &mut self,
+        response: EpochSyncFinalizationResponse,
+    ) -> Result<(), Error> {
+        let EpochSyncFinalizationResponse {
+            cur_epoch_header,
+            prev_epoch_headers,
+            header_sync_init_header_tree,
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            prev_epoch_info,
+            cur_epoch_info,
+            next_epoch_info,
+        } = response;
+        let prev_epoch_id = prev_epoch_last_block_info.epoch_id().clone();
+
+        let mut chain_store_update = self.chain_store.store_update();
+        for header in prev_epoch_headers {
+            chain_store_update.save_block_header_no_update_tree(header)?;
+        }
+        chain_store_update.save_block_header_no_update_tree(cur_epoch_header.clone())?;
+        chain_store_update
+            .save_block_merkle_tree(*cur_epoch_header.hash(), header_sync_init_header_tree);
+        chain_store_update.save_epoch_sync_header_index(&cur_epoch_header)?;
+        chain_store_update.force_save_header_head(&Tip::from_header(&cur_epoch_header))?;
+
+        self.epoch_manager.epoch_sync_init_epoch_manager(
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            &prev_epoch_id,
+            prev_epoch_info,
+            cur_epoch_header.epoch_id(),
+            cur_epoch_info,
+            cur_epoch_header.next_epoch_id(),
+            next_epoch_info,
+        )?;
+        let last_finalized_height =
+            chain_store_update.get_block_height(cur_epoch_header.last_final_block())?;
+        let epoch_manager_update = self.epoch_manager.add_validator_proposals_for_blocks(
+            BlockHeaderInfo::new(&cur_epoch_header, last_finalized_height),
+        )?;
+        chain_store_update.merge(epoch_manager_update);
+        chain_store_update.commit()
+    }
+}
+
 /// Epoch sync specific functions.
 #[cfg(feature = "new_epoch_sync")]
 impl Chain {
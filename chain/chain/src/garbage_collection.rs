@@ -1,17 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::{fmt, io};
 
-use unc_chain_configs::GCConfig;
+use unc_chain_configs::{AccountPattern, GCConfig};
 use unc_chain_primitives::Error;
 use unc_epoch_manager::EpochManagerAdapter;
 use unc_primitives::block::Block;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::shard_layout::get_block_shard_uid;
 use unc_primitives::state_sync::{StateHeaderKey, StatePartKey};
+use unc_primitives::transaction::ExecutionOutcomeWithProof;
 use unc_primitives::types::{BlockHeight, BlockHeightDelta, EpochId, NumBlocks, ShardId};
 use unc_primitives::utils::{get_block_shard_id, get_outcome_id_block_hash, index_to_bytes};
 use unc_store::flat::store_helper;
+use unc_store::trie::archived_accounts::is_archived_trie_key;
 use unc_store::{DBCol, KeyForStateChanges, ShardTries, ShardUId};
 
 use crate::types::RuntimeAdapter;
@@ -177,6 +179,7 @@ impl Chain {
                         epoch_manager.as_ref(),
                         *block_hash,
                         GCMode::Canonical(tries.clone()),
+                        &gc_config.archived_accounts,
                     )?;
                     chain_store_update.clear_resharding_data(
                         runtime.as_ref(),
@@ -255,6 +258,7 @@ impl Chain {
                         epoch_manager.as_ref(),
                         current_hash,
                         GCMode::Fork(tries.clone()),
+                        &[],
                     )?;
                     chain_store_update.commit()?;
                     *gc_blocks_remaining -= 1;
@@ -302,6 +306,7 @@ impl Chain {
                             epoch_manager.as_ref(),
                             prev_block_hash,
                             GCMode::StateSync { clear_block_info: true },
+                            &[],
                         )?;
                     }
                     tail_prev_block_cleaned = true;
@@ -310,6 +315,7 @@ impl Chain {
                     epoch_manager.as_ref(),
                     block_hash,
                     GCMode::StateSync { clear_block_info: block_hash != prev_hash },
+                    &[],
                 )?;
                 chain_store_update.commit()?;
             }
@@ -462,6 +468,30 @@ impl<'a> ChainStoreUpdate<'a> {
         shard_uids_to_gc
     }
 
+    /// Shards which may contain accounts matching `archived_accounts`, among
+    /// the shards returned by `get_shard_uids_to_gc`.
+    fn get_archived_shard_uids(
+        &mut self,
+        epoch_manager: &dyn EpochManagerAdapter,
+        block_hash: &CryptoHash,
+        archived_accounts: &[AccountPattern],
+    ) -> HashSet<ShardUId> {
+        let block_header = self.get_block_header(block_hash).expect("block header must exist");
+        let shard_layout =
+            epoch_manager.get_shard_layout(block_header.epoch_id()).expect("epoch info must exist");
+        let next_shard_layout = epoch_manager
+            .get_shard_layout(block_header.next_epoch_id())
+            .expect("epoch info must exist");
+        archived_accounts
+            .iter()
+            .flat_map(|pattern| {
+                let mut shard_uids = pattern.shard_uids(&shard_layout);
+                shard_uids.extend(pattern.shard_uids(&next_shard_layout));
+                shard_uids
+            })
+            .collect()
+    }
+
     /// GC trie state and flat state data after a resharding event
     /// Most of the work happens on the last block of the epoch when resharding is COMPLETED
     /// During GC, when we detect a change in shard layout, we can clear off all entries from
@@ -517,11 +547,14 @@ impl<'a> ChainStoreUpdate<'a> {
 
     // Clearing block data of `block_hash`, if on a fork.
     // Clearing block data of `block_hash.prev`, if on the Canonical Chain.
+    // On the Canonical Chain, state, state changes and outcomes of accounts
+    // matching `archived_accounts` are kept.
     pub fn clear_block_data(
         &mut self,
         epoch_manager: &dyn EpochManagerAdapter,
         mut block_hash: CryptoHash,
         gc_mode: GCMode,
+        archived_accounts: &[AccountPattern],
     ) -> Result<(), Error> {
         let mut store_update = self.store().store_update();
 
@@ -555,7 +588,16 @@ impl<'a> ChainStoreUpdate<'a> {
                             &get_block_shard_uid(&block_hash, &shard_uid),
                         )?;
                         if let Some(trie_changes) = trie_changes {
-                            tries.apply_deletions(&trie_changes, shard_uid, &mut store_update);
+                            if archived_accounts.is_empty() {
+                                tries.apply_deletions(&trie_changes, shard_uid, &mut store_update);
+                            } else {
+                                tries.apply_deletions_keeping_archived(
+                                    &trie_changes,
+                                    shard_uid,
+                                    archived_accounts,
+                                    &mut store_update,
+                                )?;
+                            }
                             self.gc_col(
                                 DBCol::TrieChanges,
                                 &get_block_shard_uid(&block_hash, &shard_uid),
@@ -602,7 +644,19 @@ impl<'a> ChainStoreUpdate<'a> {
             }
         }
         // gc DBCol::ChunkExtra based on shard_uid since it's indexed by shard_uid in the storage
+        // Archived accounts need it to find state root of the block when queried, so it's
+        // kept for the shards which may contain them.
+        let keep_archived =
+            matches!(gc_mode, GCMode::Canonical(_)) && !archived_accounts.is_empty();
+        let archived_shard_uids = if keep_archived {
+            self.get_archived_shard_uids(epoch_manager, &block_hash, archived_accounts)
+        } else {
+            HashSet::new()
+        };
         for shard_uid in self.get_shard_uids_to_gc(epoch_manager, &block_hash) {
+            if archived_shard_uids.contains(&shard_uid) {
+                continue;
+            }
             let block_shard_uid = get_block_shard_uid(&block_hash, &shard_uid);
             self.gc_col(DBCol::ChunkExtra, &block_shard_uid);
         }
@@ -620,10 +674,14 @@ impl<'a> ChainStoreUpdate<'a> {
             .map(|item| item.map(|(key, _)| key))
             .collect::<io::Result<Vec<_>>>()?;
         for key in stored_state_changes {
+            let trie_key = &key[storage_key.as_ref().len()..];
+            if keep_archived && is_archived_trie_key(archived_accounts, trie_key) {
+                continue;
+            }
             self.gc_col(DBCol::StateChanges, &key);
         }
         self.gc_col(DBCol::BlockRefCount, block_hash.as_bytes());
-        self.gc_outcomes(&block)?;
+        self.gc_outcomes(&block, if keep_archived { archived_accounts } else { &[] })?;
         match gc_mode {
             GCMode::StateSync { clear_block_info: false } => {}
             _ => self.gc_col(DBCol::BlockInfo, block_hash.as_bytes()),
@@ -722,7 +780,7 @@ impl<'a> ChainStoreUpdate<'a> {
             self.gc_col(DBCol::StateChanges, &key);
         }
         self.gc_col(DBCol::BlockRefCount, block_hash.as_bytes());
-        self.gc_outcomes(&block)?;
+        self.gc_outcomes(&block, &[])?;
         self.gc_col(DBCol::BlockInfo, block_hash.as_bytes());
         self.gc_col(DBCol::StateDlInfos, block_hash.as_bytes());
 
@@ -811,6 +869,16 @@ impl<'a> ChainStoreUpdate<'a> {
         Ok(())
     }
 
+    pub fn gc_col_state_header(
+        &mut self,
+        sync_hash: CryptoHash,
+        shard_id: ShardId,
+    ) -> Result<(), Error> {
+        let key = borsh::to_vec(&StateHeaderKey(shard_id, sync_hash))?;
+        self.gc_col(DBCol::StateHeaders, &key);
+        Ok(())
+    }
+
     fn gc_outgoing_receipts(&mut self, block_hash: &CryptoHash, shard_id: ShardId) {
         let mut store_update = self.store().store_update();
         match self
@@ -843,22 +911,47 @@ impl<'a> ChainStoreUpdate<'a> {
         self.merge(store_update);
     }
 
-    fn gc_outcomes(&mut self, block: &Block) -> Result<(), Error> {
+    /// Deletes execution outcomes of the block except for outcomes of
+    /// transactions and receipts executed by accounts matching
+    /// `archived_accounts`.
+    fn gc_outcomes(
+        &mut self,
+        block: &Block,
+        archived_accounts: &[AccountPattern],
+    ) -> Result<(), Error> {
         let block_hash = block.hash();
-        let store_update = self.store().store_update();
+        let mut store_update = self.store().store_update();
         for chunk_header in
             block.chunks().iter().filter(|h| h.height_included() == block.header().height())
         {
             let shard_id = chunk_header.shard_id();
             let outcome_ids =
                 self.chain_store().get_outcomes_by_block_hash_and_shard_id(block_hash, shard_id)?;
+            let mut archived_outcome_ids = vec![];
             for outcome_id in outcome_ids {
-                self.gc_col(
-                    DBCol::TransactionResultForBlock,
-                    &get_outcome_id_block_hash(&outcome_id, block_hash),
-                );
+                let key = get_outcome_id_block_hash(&outcome_id, block_hash);
+                if !archived_accounts.is_empty() {
+                    let outcome = self.store().get_ser::<ExecutionOutcomeWithProof>(
+                        DBCol::TransactionResultForBlock,
+                        &key,
+                    )?;
+                    if outcome.is_some_and(|outcome| {
+                        archived_accounts
+                            .iter()
+                            .any(|pattern| pattern.matches(outcome.outcome.executor_id.as_str()))
+                    }) {
+                        archived_outcome_ids.push(outcome_id);
+                        continue;
+                    }
+                }
+                self.gc_col(DBCol::TransactionResultForBlock, &key);
+            }
+            let key = get_block_shard_id(block_hash, shard_id);
+            if archived_outcome_ids.is_empty() {
+                self.gc_col(DBCol::OutcomeIds, &key);
+            } else {
+                store_update.set_ser(DBCol::OutcomeIds, &key, &archived_outcome_ids)?;
             }
-            self.gc_col(DBCol::OutcomeIds, &get_block_shard_id(block_hash, shard_id));
         }
         self.merge(store_update);
         Ok(())
@@ -998,6 +1091,8 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::FlatState
             | DBCol::FlatStateChanges
             | DBCol::FlatStateDeltaMetadata
+            | DBCol::FlatStateHistory
+            | DBCol::AccountStorageFootprint
             | DBCol::FlatStorageStatus
             | DBCol::Misc
             => unreachable!(),
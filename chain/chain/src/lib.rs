@@ -27,6 +27,7 @@ pub mod resharding;
 mod state_request_tracker;
 pub mod state_snapshot_actor;
 mod store;
+pub mod store_scrubber;
 pub mod store_validator;
 pub mod test_utils;
 pub mod types;
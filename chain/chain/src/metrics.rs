@@ -1,8 +1,9 @@
 use once_cell::sync::Lazy;
 use unc_o11y::metrics::{
     exponential_buckets, processing_time_buckets, try_create_histogram, try_create_histogram_vec,
-    try_create_histogram_with_buckets, try_create_int_counter, try_create_int_gauge,
-    try_create_int_gauge_vec, Histogram, HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    try_create_histogram_with_buckets, try_create_int_counter, try_create_int_counter_vec,
+    try_create_int_gauge, try_create_int_gauge_vec, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec,
 };
 
 pub static BLOCK_PROCESSING_ATTEMPTS_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
@@ -237,3 +238,30 @@ pub(crate) static RESHARDING_STATUS: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub(crate) static STORE_SCRUBBER_KEYS_CHECKED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_store_scrubber_keys_checked_total",
+        "Number of database entries checked by the store scrubber.",
+        &["column", "temperature"],
+    )
+    .unwrap()
+});
+
+pub(crate) static STORE_SCRUBBER_FINDINGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_store_scrubber_findings_total",
+        "Number of inconsistent or corrupt database entries found by the store scrubber.",
+        &["column", "temperature", "check"],
+    )
+    .unwrap()
+});
+
+pub(crate) static STORE_SCRUBBER_QUARANTINED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_store_scrubber_quarantined_total",
+        "Number of corrupt database entries moved out of the hot database by the store scrubber.",
+        &["column"],
+    )
+    .unwrap()
+});
@@ -27,7 +27,7 @@ use unc_store::flat::{
     store_helper, BlockInfo, FlatStorageError, FlatStorageManager, FlatStorageReadyStatus,
     FlatStorageStatus,
 };
-use unc_store::resharding::get_delayed_receipts;
+use unc_store::resharding::{get_delayed_receipts, get_promise_yield_timeouts};
 use unc_store::trie::SnapshotError;
 use unc_store::{ShardTries, ShardUId, StorageError, Store, Trie, TrieDBStorage, TrieStorage};
 
@@ -165,6 +165,35 @@ fn apply_delayed_receipts<'a>(
     Ok(new_state_roots)
 }
 
+fn apply_promise_yield_timeouts<'a>(
+    config: &ReshardingConfig,
+    tries: &ShardTries,
+    orig_shard_uid: ShardUId,
+    orig_state_root: StateRoot,
+    state_roots: HashMap<ShardUId, StateRoot>,
+    account_id_to_shard_uid: &(dyn Fn(&AccountId) -> ShardUId + 'a),
+) -> Result<HashMap<ShardUId, StateRoot>, Error> {
+    let orig_trie_update = tries.new_trie_update_view(orig_shard_uid, orig_state_root);
+
+    let mut start_index = None;
+    let mut new_state_roots = state_roots;
+    while let Some((next_index, timeouts)) =
+        get_promise_yield_timeouts(&orig_trie_update, start_index, config.batch_size)?
+    {
+        let (store_update, updated_state_roots) = tries
+            .apply_promise_yield_timeouts_to_children_states(
+                &new_state_roots,
+                &timeouts,
+                account_id_to_shard_uid,
+            )?;
+        new_state_roots = updated_state_roots;
+        start_index = Some(next_index);
+        store_update.commit()?;
+    }
+
+    Ok(new_state_roots)
+}
+
 // function to set up flat storage status to Ready after a resharding event
 // TODO(resharding) : Consolidate this with setting up flat storage during state sync logic
 fn set_flat_storage_state(
@@ -416,6 +445,15 @@ impl Chain {
             &checked_account_id_to_shard_uid,
         )?;
 
+        state_roots = apply_promise_yield_timeouts(
+            &config.get(),
+            &tries,
+            shard_uid,
+            state_root,
+            state_roots,
+            &checked_account_id_to_shard_uid,
+        )?;
+
         tracing::debug!(target: "resharding", ?shard_uid, "build_state_for_split_shards_impl finished");
         Ok(state_roots)
     }
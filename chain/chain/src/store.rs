@@ -2010,6 +2010,19 @@ impl<'a> ChainStoreUpdate<'a> {
         Ok(())
     }
 
+    /// Used only in Epoch Sync finalization
+    /// Indexes the header Epoch Sync finishes at by height and ordinal, so that Header Sync can
+    /// continue from it. Block merkle tree of the header must be already saved.
+    pub fn save_epoch_sync_header_index(&mut self, header: &BlockHeader) -> Result<(), Error> {
+        let block_ordinal = self.get_block_merkle_tree(header.hash())?.size();
+        self.chain_store_cache_update.block_ordinal_to_hash.insert(block_ordinal, *header.hash());
+        self.chain_store_cache_update
+            .height_to_hashes
+            .insert(header.height(), Some(*header.hash()));
+        self.chain_store_cache_update.next_block_hashes.insert(*header.prev_hash(), *header.hash());
+        Ok(())
+    }
+
     pub fn save_block_header(&mut self, header: BlockHeader) -> Result<(), Error> {
         self.update_and_save_block_merkle_tree(&header)?;
         self.chain_store_cache_update.headers.insert(*header.hash(), header);
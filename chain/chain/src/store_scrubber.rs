@@ -0,0 +1,661 @@
+//! Background scrubber continuously checking consistency of the databases.
+//!
+//! Unlike [`crate::StoreValidator`] which validates the whole database at
+//! once, the scrubber walks columns incrementally at a limited rate so that it
+//! can run alongside a live node.  Its progress and most recent findings are
+//! persisted in `DBCol::Misc` so that scrubbing resumes after a restart and
+//! findings can be served on the debug page.
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use unc_chain_configs::StoreScrubberConfig;
+use unc_primitives::block::{Block, BlockHeader};
+use unc_primitives::hash::{hash, CryptoHash};
+use unc_primitives::shard_layout::{get_block_shard_uid, get_block_shard_uid_rev, ShardUId};
+use unc_primitives::sharding::{PartialEncodedChunk, ShardChunk};
+use unc_primitives::state::FlatStateValue;
+use unc_primitives::static_clock::StaticClock;
+use unc_primitives::types::chunk_extra::ChunkExtra;
+use unc_primitives::types::BlockHeight;
+use unc_primitives::views::{
+    ScrubbedColumnView, StoreScrubberFindingView, StoreScrubberStatusView,
+};
+use unc_store::db::refcount::decode_value_with_rc;
+use unc_store::db::STORE_SCRUBBER_STATUS_KEY;
+use unc_store::flat::store_helper::{decode_flat_state_db_key, get_flat_storage_status};
+use unc_store::flat::FlatStorageStatus;
+use unc_store::{DBCol, KeyLookupMode, Store, Trie, TrieDBStorage};
+
+use crate::metrics;
+
+/// Columns scrubbed in the hot database, in order.
+const HOT_COLUMNS: [DBCol; 8] = [
+    DBCol::BlockHeader,
+    DBCol::Block,
+    DBCol::BlockHeight,
+    DBCol::Chunks,
+    DBCol::PartialChunks,
+    DBCol::ChunkExtra,
+    DBCol::State,
+    DBCol::FlatState,
+];
+
+/// Columns scrubbed in the cold database, in order.
+const COLD_COLUMNS: [DBCol; 5] =
+    [DBCol::Block, DBCol::Chunks, DBCol::PartialChunks, DBCol::ChunkExtra, DBCol::State];
+
+/// Number of entries read from the database at once.
+const BATCH_SIZE: usize = 100;
+
+/// Number of findings kept for the debug page.
+const MAX_FINDINGS: usize = 100;
+
+/// Column of either database walked by the scrubber.
+#[derive(Clone, Copy, Debug)]
+struct Target {
+    col: DBCol,
+    cold: bool,
+}
+
+impl Target {
+    fn temperature(&self) -> &'static str {
+        if self.cold {
+            "cold"
+        } else {
+            "hot"
+        }
+    }
+}
+
+/// Problem found with a single entry.
+struct Issue {
+    check: &'static str,
+    error: String,
+    /// Whether the entry itself is damaged, as opposed to being inconsistent
+    /// with other entries.  Only damaged entries are quarantined.
+    corrupt: bool,
+}
+
+impl Issue {
+    fn corrupt(check: &'static str, error: impl ToString) -> Self {
+        Self { check, error: error.to_string(), corrupt: true }
+    }
+
+    fn inconsistent(check: &'static str, error: impl ToString) -> Self {
+        Self { check, error: error.to_string(), corrupt: false }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+struct ColumnProgress {
+    column: String,
+    cold: bool,
+    passes: u64,
+    keys_checked: u64,
+    /// Key the current pass resumes from, `None` at the start of a pass.
+    cursor: Option<Vec<u8>>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+struct Finding {
+    column: String,
+    cold: bool,
+    key: Vec<u8>,
+    check: String,
+    error: String,
+    quarantined: bool,
+    timestamp_ms: i64,
+}
+
+/// State of the scrubber persisted under [`STORE_SCRUBBER_STATUS_KEY`].
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default)]
+struct ScrubberState {
+    columns: Vec<ColumnProgress>,
+    findings: Vec<Finding>,
+}
+
+impl From<ScrubberState> for StoreScrubberStatusView {
+    fn from(state: ScrubberState) -> Self {
+        let columns = state
+            .columns
+            .into_iter()
+            .map(|progress| ScrubbedColumnView {
+                column: progress.column,
+                cold: progress.cold,
+                passes: progress.passes,
+                keys_checked: progress.keys_checked,
+                cursor: progress.cursor.map(hex::encode),
+            })
+            .collect();
+        let findings = state
+            .findings
+            .into_iter()
+            .map(|finding| StoreScrubberFindingView {
+                column: finding.column,
+                cold: finding.cold,
+                key: hex::encode(finding.key),
+                check: finding.check,
+                error: finding.error,
+                quarantined: finding.quarantined,
+                timestamp: chrono::DateTime::from_timestamp_millis(finding.timestamp_ms)
+                    .unwrap_or_default(),
+            })
+            .collect();
+        Self { columns, findings }
+    }
+}
+
+/// Returns progress and recent findings of the scrubber running on the node
+/// whose hot database is `store`.
+pub fn get_store_scrubber_status(store: &Store) -> io::Result<StoreScrubberStatusView> {
+    let state: ScrubberState =
+        store.get_ser(DBCol::Misc, STORE_SCRUBBER_STATUS_KEY)?.unwrap_or_default();
+    Ok(state.into())
+}
+
+pub struct StoreScrubber {
+    config: StoreScrubberConfig,
+    hot_store: Store,
+    cold_store: Option<Store>,
+    /// Absolute path of [`StoreScrubberConfig::quarantine_dir`].
+    quarantine_dir: PathBuf,
+    state: ScrubberState,
+}
+
+impl StoreScrubber {
+    pub fn new(
+        config: StoreScrubberConfig,
+        hot_store: Store,
+        cold_store: Option<Store>,
+        quarantine_dir: PathBuf,
+    ) -> io::Result<Self> {
+        let mut state: ScrubberState =
+            hot_store.get_ser(DBCol::Misc, STORE_SCRUBBER_STATUS_KEY)?.unwrap_or_default();
+        let cold_columns: &[DBCol] = if cold_store.is_some() { &COLD_COLUMNS } else { &[] };
+        let targets = HOT_COLUMNS
+            .iter()
+            .map(|&col| Target { col, cold: false })
+            .chain(cold_columns.iter().map(|&col| Target { col, cold: true }));
+        // Columns scrubbed previously keep their progress.  Columns which are
+        // new, e.g. after cold storage got configured, start at the pass
+        // number of the other columns so that they don't get scrubbed
+        // repeatedly to catch up.
+        let passes = state.columns.iter().map(|progress| progress.passes).max().unwrap_or(0);
+        state.columns = targets
+            .map(|target| {
+                let column = target.col.to_string();
+                state
+                    .columns
+                    .iter()
+                    .find(|progress| progress.column == column && progress.cold == target.cold)
+                    .cloned()
+                    .unwrap_or(ColumnProgress {
+                        column,
+                        cold: target.cold,
+                        passes,
+                        keys_checked: 0,
+                        cursor: None,
+                    })
+            })
+            .collect();
+        Ok(Self { config, hot_store, cold_store, quarantine_dir, state })
+    }
+
+    fn target(&self, index: usize) -> Target {
+        let progress = &self.state.columns[index];
+        let col = if progress.cold { COLD_COLUMNS.iter() } else { HOT_COLUMNS.iter() }
+            .copied()
+            .find(|col| col.to_string() == progress.column)
+            .expect("progress is only kept for scrubbed columns");
+        Target { col, cold: progress.cold }
+    }
+
+    fn store(&self, target: Target) -> &Store {
+        if target.cold {
+            self.cold_store.as_ref().expect("cold columns are scrubbed only with cold store")
+        } else {
+            &self.hot_store
+        }
+    }
+
+    fn save_state(&self) -> io::Result<()> {
+        let mut store_update = self.hot_store.store_update();
+        store_update.set_ser(DBCol::Misc, STORE_SCRUBBER_STATUS_KEY, &self.state)?;
+        store_update.commit()
+    }
+
+    /// Runs the scrubber until `keep_going` is cleared.
+    pub fn run(mut self, keep_going: &AtomicBool) {
+        let keys_per_second = self.config.keys_per_second.max(1) as f64;
+        while keep_going.load(Ordering::Relaxed) {
+            // Scrub the column with the fewest completed passes.  Columns are
+            // thus scrubbed in order and once all of them complete a pass the
+            // scrubber waits for `pass_interval`.
+            let Some(index) =
+                (0..self.state.columns.len()).min_by_key(|&index| self.state.columns[index].passes)
+            else {
+                return;
+            };
+            let start = StaticClock::instant();
+            let (checked, finished) = match self.scrub_batch(index, BATCH_SIZE) {
+                Ok(result) => result,
+                Err(err) => {
+                    tracing::error!(target: "store_scrubber", ?err, "Failed to scrub store");
+                    (0, false)
+                }
+            };
+            if let Err(err) = self.save_state() {
+                tracing::error!(target: "store_scrubber", ?err, "Failed to save scrubber status");
+            }
+            let budget = Duration::from_secs_f64(checked as f64 / keys_per_second);
+            let mut pause = budget.saturating_sub(start.elapsed());
+            let passes = self.state.columns[index].passes;
+            if finished && self.state.columns.iter().all(|progress| progress.passes == passes) {
+                tracing::info!(target: "store_scrubber", passes, "Finished scrubbing pass");
+                pause = self.config.pass_interval;
+            } else if checked == 0 && !finished {
+                // Reading the database failed, retry later.
+                pause = Duration::from_secs(60);
+            }
+            sleep(keep_going, pause);
+        }
+    }
+
+    /// Checks up to `limit` entries of the column at `index` in
+    /// `self.state.columns` starting from where the previous batch stopped.
+    ///
+    /// Returns the number of checked entries and whether the pass over the
+    /// column has finished.
+    fn scrub_batch(&mut self, index: usize, limit: usize) -> io::Result<(usize, bool)> {
+        let target = self.target(index);
+        let cursor = self.state.columns[index].cursor.clone();
+        let entries = self
+            .store(target)
+            .iter_range_raw_bytes(target.col, cursor.as_deref(), None)
+            .take(limit)
+            .collect::<io::Result<Vec<_>>>()?;
+        let labels = [target.col.into(), target.temperature()];
+        for (key, value) in &entries {
+            metrics::STORE_SCRUBBER_KEYS_CHECKED.with_label_values(&labels).inc();
+            for issue in self.check_entry(target, key, value) {
+                self.record_issue(target, key, value, issue);
+            }
+        }
+        let finished = entries.len() < limit;
+        let progress = &mut self.state.columns[index];
+        progress.keys_checked += entries.len() as u64;
+        if finished {
+            progress.passes += 1;
+            progress.keys_checked = 0;
+            progress.cursor = None;
+        } else if let Some((key, _)) = entries.last() {
+            // The smallest key following the last checked one.
+            let mut cursor = key.to_vec();
+            cursor.push(0);
+            progress.cursor = Some(cursor);
+        }
+        Ok((entries.len(), finished))
+    }
+
+    fn record_issue(&mut self, target: Target, key: &[u8], value: &[u8], issue: Issue) {
+        tracing::warn!(
+            target: "store_scrubber",
+            column = %target.col,
+            temperature = target.temperature(),
+            key = %hex::encode(key),
+            check = issue.check,
+            error = %issue.error,
+            "Found inconsistent store entry"
+        );
+        metrics::STORE_SCRUBBER_FINDINGS
+            .with_label_values(&[target.col.into(), target.temperature(), issue.check])
+            .inc();
+        // Cold storage is append-only so entries can be quarantined only in
+        // the hot one.
+        let quarantined = issue.corrupt
+            && !target.cold
+            && self.config.quarantine
+            && match self.quarantine(target.col, key, value) {
+                Ok(()) => true,
+                Err(err) => {
+                    tracing::error!(target: "store_scrubber", ?err, "Failed to quarantine entry");
+                    false
+                }
+            };
+        if self.state.findings.len() >= MAX_FINDINGS {
+            self.state.findings.remove(0);
+        }
+        self.state.findings.push(Finding {
+            column: target.col.to_string(),
+            cold: target.cold,
+            key: key.to_vec(),
+            check: issue.check.to_string(),
+            error: issue.error,
+            quarantined,
+            timestamp_ms: StaticClock::utc().timestamp_millis(),
+        });
+    }
+
+    /// Moves the entry into a file in the quarantine directory.
+    fn quarantine(&self, col: DBCol, key: &[u8], value: &[u8]) -> io::Result<()> {
+        let dir = self.quarantine_dir.join(col.to_string());
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(hex::encode(key)), value)?;
+        // Deleting a range works for reference-counted columns as well.
+        let mut end = key.to_vec();
+        end.push(0);
+        let mut store_update = self.hot_store.store_update();
+        store_update.delete_range(col, key, &end);
+        store_update.commit()?;
+        metrics::STORE_SCRUBBER_QUARANTINED.with_label_values(&[<&str>::from(col)]).inc();
+        Ok(())
+    }
+
+    fn check_entry(&self, target: Target, key: &[u8], value: &[u8]) -> Vec<Issue> {
+        let mut issues = vec![];
+        let result = match target.col {
+            DBCol::BlockHeader => self.check_block_header(key, value),
+            DBCol::Block => self.check_block(target, key, value, &mut issues),
+            DBCol::BlockHeight => self.check_block_height(key, value),
+            DBCol::Chunks => self.check_chunk(target, key, value, &mut issues),
+            DBCol::PartialChunks => self.check_partial_chunk(target, key, value, &mut issues),
+            DBCol::ChunkExtra => check_chunk_extra(key, value),
+            DBCol::State => check_state(key, value),
+            DBCol::FlatState => self.check_flat_state(key, value),
+            _ => Ok(()),
+        };
+        if let Err(issue) = result {
+            issues.push(issue);
+        }
+        issues
+    }
+
+    fn check_block_header(&self, key: &[u8], value: &[u8]) -> Result<(), Issue> {
+        let header =
+            BlockHeader::try_from_slice(value).map_err(|err| Issue::corrupt("decode", err))?;
+        if header.hash().as_bytes() != key {
+            return Err(Issue::corrupt("hash", format!("header hashes to {}", header.hash())));
+        }
+        Ok(())
+    }
+
+    fn check_block(
+        &self,
+        target: Target,
+        key: &[u8],
+        value: &[u8],
+        issues: &mut Vec<Issue>,
+    ) -> Result<(), Issue> {
+        let block = Block::try_from_slice(value).map_err(|err| Issue::corrupt("decode", err))?;
+        if block.hash().as_bytes() != key {
+            return Err(Issue::corrupt("hash", format!("block hashes to {}", block.hash())));
+        }
+        // Headers are never garbage collected so they are always in the hot
+        // database.
+        match self.hot_store.exists(DBCol::BlockHeader, key) {
+            Ok(true) => {}
+            Ok(false) => issues.push(Issue::inconsistent("block_header_exists", "header missing")),
+            Err(err) => issues.push(Issue::inconsistent("block_header_exists", err)),
+        }
+        self.check_cold_overlap(target, DBCol::Block, key, value)
+    }
+
+    fn check_block_height(&self, key: &[u8], value: &[u8]) -> Result<(), Issue> {
+        let height =
+            BlockHeight::try_from_slice(key).map_err(|err| Issue::corrupt("decode", err))?;
+        let block_hash =
+            CryptoHash::try_from_slice(value).map_err(|err| Issue::corrupt("decode", err))?;
+        let header = self
+            .hot_store
+            .get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_ref())
+            .map_err(|err| Issue::inconsistent("canonical_header", err))?
+            .ok_or_else(|| {
+                Issue::inconsistent("canonical_header", format!("header {block_hash} missing"))
+            })?;
+        if header.height() != height {
+            return Err(Issue::inconsistent(
+                "canonical_header",
+                format!("header {block_hash} is at height {}", header.height()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_chunk(
+        &self,
+        target: Target,
+        key: &[u8],
+        value: &[u8],
+        issues: &mut Vec<Issue>,
+    ) -> Result<(), Issue> {
+        let chunk =
+            ShardChunk::try_from_slice(value).map_err(|err| Issue::corrupt("decode", err))?;
+        let chunk_hash = chunk.cloned_header().chunk_hash();
+        if chunk_hash.0.as_bytes() != key {
+            issues.push(Issue::corrupt("hash", format!("chunk hashes to {}", chunk_hash.0)));
+        }
+        self.check_cold_overlap(target, DBCol::Chunks, key, value)
+    }
+
+    fn check_partial_chunk(
+        &self,
+        target: Target,
+        key: &[u8],
+        value: &[u8],
+        issues: &mut Vec<Issue>,
+    ) -> Result<(), Issue> {
+        let chunk = PartialEncodedChunk::try_from_slice(value)
+            .map_err(|err| Issue::corrupt("decode", err))?;
+        let chunk_hash = chunk.chunk_hash();
+        if chunk_hash.0.as_bytes() != key {
+            issues.push(Issue::corrupt("hash", format!("chunk hashes to {}", chunk_hash.0)));
+        }
+        self.check_cold_overlap(target, DBCol::PartialChunks, key, value)
+    }
+
+    /// Checks that an entry of an insert-only column which has already been
+    /// copied to the cold database is identical in both databases.
+    fn check_cold_overlap(
+        &self,
+        target: Target,
+        col: DBCol,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Issue> {
+        debug_assert!(col.is_insert_only() && col.is_cold());
+        let Some(cold_store) = self.cold_store.as_ref().filter(|_| !target.cold) else {
+            return Ok(());
+        };
+        match cold_store.get(col, key) {
+            Ok(Some(cold_value)) if cold_value.as_ref() != value => {
+                Err(Issue::inconsistent("cold_hot_overlap", "value differs in cold store"))
+            }
+            Ok(_) => Ok(()),
+            Err(err) => Err(Issue::inconsistent("cold_hot_overlap", err)),
+        }
+    }
+
+    /// Checks that a flat storage entry matches the value in the trie at the
+    /// flat storage head.
+    fn check_flat_state(&self, key: &[u8], value: &[u8]) -> Result<(), Issue> {
+        let (shard_uid, trie_key) =
+            decode_flat_state_db_key(key).map_err(|err| Issue::corrupt("decode", err))?;
+        let flat_value =
+            FlatStateValue::try_from_slice(value).map_err(|err| Issue::corrupt("decode", err))?;
+        let Some(flat_head) = self.flat_head(shard_uid)? else {
+            return Ok(());
+        };
+        let chunk_extra = self
+            .hot_store
+            .get_ser::<ChunkExtra>(DBCol::ChunkExtra, &get_block_shard_uid(&flat_head, &shard_uid))
+            .map_err(|err| Issue::inconsistent("flat_state_trie", err))?
+            .ok_or_else(|| {
+                Issue::inconsistent("flat_state_trie", format!("no chunk extra at {flat_head}"))
+            })?;
+        let storage = Rc::new(TrieDBStorage::new(self.hot_store.clone(), shard_uid));
+        let trie = Trie::new(storage, *chunk_extra.state_root(), None);
+        let trie_value = trie.get_optimized_ref(&trie_key, KeyLookupMode::Trie);
+        // Flat storage moves its head while the node is running.  Entries
+        // read across a head update can't be compared.
+        if self.flat_head(shard_uid)? != Some(flat_head) {
+            return Ok(());
+        }
+        match trie_value {
+            Ok(Some(trie_value)) if trie_value.into_value_ref() == flat_value.to_value_ref() => {
+                Ok(())
+            }
+            Ok(Some(_)) => Err(Issue::inconsistent("flat_state_trie", "value differs in trie")),
+            Ok(None) => Err(Issue::inconsistent("flat_state_trie", "key missing in trie")),
+            Err(err) => Err(Issue::inconsistent("flat_state_trie", err)),
+        }
+    }
+
+    fn flat_head(&self, shard_uid: ShardUId) -> Result<Option<CryptoHash>, Issue> {
+        match get_flat_storage_status(&self.hot_store, shard_uid) {
+            Ok(FlatStorageStatus::Ready(status)) => Ok(Some(status.flat_head.hash)),
+            Ok(_) => Ok(None),
+            Err(err) => Err(Issue::inconsistent("flat_state_trie", err)),
+        }
+    }
+}
+
+fn check_chunk_extra(key: &[u8], value: &[u8]) -> Result<(), Issue> {
+    get_block_shard_uid_rev(key).map_err(|err| Issue::corrupt("decode", err))?;
+    ChunkExtra::try_from_slice(value).map_err(|err| Issue::corrupt("decode", err))?;
+    Ok(())
+}
+
+/// Checks that a trie node or value is stored under its hash with a positive
+/// reference count.
+fn check_state(key: &[u8], value: &[u8]) -> Result<(), Issue> {
+    let Some(hash_bytes) = key.get(8..) else {
+        return Err(Issue::corrupt("decode", "key too short"));
+    };
+    let key_hash = CryptoHash::try_from(hash_bytes).map_err(|err| Issue::corrupt("decode", err))?;
+    if !value.is_empty() && value.len() < 8 {
+        return Err(Issue::corrupt("decode", "value too short to hold reference count"));
+    }
+    let (data, rc) = decode_value_with_rc(value);
+    let Some(data) = data else {
+        return Err(Issue::inconsistent("refcount", format!("reference count is {rc}")));
+    };
+    if hash(data) != key_hash {
+        return Err(Issue::corrupt("hash", format!("value hashes to {}", hash(data))));
+    }
+    Ok(())
+}
+
+/// Sleeps for `duration` waking up early if `keep_going` gets cleared.
+fn sleep(keep_going: &AtomicBool, duration: Duration) {
+    let deadline = StaticClock::instant() + duration;
+    while keep_going.load(Ordering::Relaxed) {
+        let remaining = deadline.saturating_duration_since(StaticClock::instant());
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+}
+
+pub struct StoreScrubberHandle {
+    join_handle: std::thread::JoinHandle<()>,
+    keep_going: Arc<AtomicBool>,
+}
+
+impl StoreScrubberHandle {
+    pub fn stop(self) {
+        self.keep_going.store(false, Ordering::Relaxed);
+        if self.join_handle.join().is_err() {
+            tracing::error!(target: "store_scrubber", "Failed to join the store scrubber thread");
+        }
+    }
+}
+
+/// Spawns the scrubber in a background thread.
+///
+/// Like the cold store loop, the scrubber runs in a native thread since it
+/// does blocking database reads.
+pub fn spawn_store_scrubber(
+    config: StoreScrubberConfig,
+    hot_store: Store,
+    cold_store: Option<Store>,
+    quarantine_dir: PathBuf,
+) -> io::Result<StoreScrubberHandle> {
+    let scrubber = StoreScrubber::new(config, hot_store, cold_store, quarantine_dir)?;
+    let keep_going = Arc::new(AtomicBool::new(true));
+    let keep_going_clone = keep_going.clone();
+    tracing::info!(target: "store_scrubber", "Spawning the store scrubber");
+    let join_handle = std::thread::Builder::new()
+        .name("store_scrubber".to_string())
+        .spawn(move || scrubber.run(&keep_going_clone))?;
+    Ok(StoreScrubberHandle { join_handle, keep_going })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unc_store::test_utils::create_test_store;
+
+    fn state_key(value: &[u8]) -> Vec<u8> {
+        let mut key = ShardUId::single_shard().to_bytes().to_vec();
+        key.extend_from_slice(hash(value).as_ref());
+        key
+    }
+
+    #[test]
+    fn test_scrub_state_quarantines_corrupt_entries() {
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        store_update.increment_refcount(DBCol::State, &state_key(b"good"), b"good");
+        store_update.increment_refcount(DBCol::State, &state_key(b"bad"), b"flipped");
+        store_update.commit().unwrap();
+
+        let quarantine_dir = tempfile::tempdir().unwrap();
+        let config = StoreScrubberConfig { quarantine: true, ..Default::default() };
+        let mut scrubber =
+            StoreScrubber::new(config, store.clone(), None, quarantine_dir.path().to_path_buf())
+                .unwrap();
+        let index = HOT_COLUMNS.iter().position(|&col| col == DBCol::State).unwrap();
+        assert_eq!(scrubber.scrub_batch(index, BATCH_SIZE).unwrap(), (2, true));
+
+        assert_eq!(scrubber.state.findings.len(), 1);
+        let finding = &scrubber.state.findings[0];
+        assert_eq!((finding.check.as_str(), finding.quarantined), ("hash", true));
+        assert_eq!(finding.key, state_key(b"bad"));
+        assert!(store.get(DBCol::State, &state_key(b"good")).unwrap().is_some());
+        assert!(store.get(DBCol::State, &state_key(b"bad")).unwrap().is_none());
+        let file = quarantine_dir.path().join("State").join(hex::encode(state_key(b"bad")));
+        assert!(std::fs::read(file).unwrap().starts_with(b"flipped"));
+    }
+
+    #[test]
+    fn test_scrub_resumes_from_cursor() {
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        for value in [b"a", b"b", b"c", b"d", b"e"] {
+            store_update.increment_refcount(DBCol::State, &state_key(value), value);
+        }
+        store_update.commit().unwrap();
+
+        let index = HOT_COLUMNS.iter().position(|&col| col == DBCol::State).unwrap();
+        let new_scrubber =
+            || StoreScrubber::new(Default::default(), store.clone(), None, PathBuf::new()).unwrap();
+        let mut scrubber = new_scrubber();
+        assert_eq!(scrubber.scrub_batch(index, 2).unwrap(), (2, false));
+        assert_eq!(scrubber.scrub_batch(index, 2).unwrap(), (2, false));
+        scrubber.save_state().unwrap();
+
+        // Progress survives restarts.
+        let mut scrubber = new_scrubber();
+        assert_eq!(scrubber.state.columns[index].keys_checked, 4);
+        assert_eq!(scrubber.scrub_batch(index, 2).unwrap(), (1, true));
+        assert_eq!(scrubber.state.columns[index].passes, 1);
+        assert_eq!(scrubber.state.columns[index].cursor, None);
+        assert!(scrubber.state.findings.is_empty());
+    }
+}
@@ -827,7 +827,7 @@ fn test_clear_old_data_fixed_height() {
     let trie = chain.runtime_adapter.get_tries();
     let mut store_update = chain.mut_chain_store().store_update();
     assert!(store_update
-        .clear_block_data(epoch_manager.as_ref(), *blocks[5].hash(), GCMode::Canonical(trie))
+        .clear_block_data(epoch_manager.as_ref(), *blocks[5].hash(), GCMode::Canonical(trie), &[])
         .is_ok());
     store_update.commit().unwrap();
 
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use unc_primitives::types::EpochId;
 use unc_primitives::views::{
     CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, RequestedStatePartsView,
-    SyncStatusView,
+    StoreScrubberStatusView, SyncStatusView,
 };
 use unc_primitives::{
     block_header::ApprovalInner,
@@ -185,6 +185,8 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Progress and findings of the store scrubber.
+    StoreScrubberStatus,
 }
 
 impl actix::Message for DebugStatus {
@@ -206,4 +208,6 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Progress and findings of the store scrubber.
+    StoreScrubberStatus(StoreScrubberStatusView),
 }
@@ -11,17 +11,18 @@ use unc_primitives::hash::CryptoHash;
 use unc_primitives::merkle::{MerklePath, PartialMerkleTree};
 use unc_primitives::network::PeerId;
 use unc_primitives::sharding::ChunkHash;
+use unc_primitives::static_clock::StaticClock;
 use unc_primitives::types::{
     AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
     TransactionOrReceiptId,
 };
 use unc_primitives::views::validator_power_and_pledge_view::ValidatorPowerAndPledgeView;
 use unc_primitives::views::{
-    AllMinersView, BlockView, ChunkView, DownloadStatusView, EpochValidatorInfo,
-    ExecutionOutcomeWithIdView, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
-    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView,
-    SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView, StateChangesView,
-    SyncStatusView, TxStatusView,
+    AccountStorageFootprintView, AllMinersView, BlockView, ChunkView, DownloadStatusView,
+    EpochValidatorInfo, ExecutionOutcomeWithIdView, GasPriceView, LightClientBlockLiteView,
+    LightClientBlockView, MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView,
+    ShardSyncDownloadView, SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView,
+    StateChangesView, SyncStatusView, TxStatusView,
 };
 pub use unc_primitives::views::{StatusResponse, StatusSyncInfo};
 use yansi::Color::Magenta;
@@ -150,9 +151,14 @@ impl From<&DownloadStatus> for DownloadStatusView {
 
 impl From<ShardSyncDownload> for ShardSyncDownloadView {
     fn from(download: ShardSyncDownload) -> Self {
+        let eta_secs = download
+            .estimate_remaining_time(StaticClock::utc())
+            .map(|eta| eta.num_seconds().max(0) as u64);
         ShardSyncDownloadView {
             downloads: download.downloads.iter().map(|x| x.into()).collect(),
             status: download.status.to_string(),
+            num_parts_resumed: download.num_parts_resumed(),
+            eta_secs,
         }
     }
 }
@@ -188,6 +194,30 @@ impl ShardSyncDownload {
         }
         Self { downloads, status: ShardSyncStatus::StateDownloadParts }
     }
+
+    /// Number of parts which were downloaded before the node restarted, and
+    /// didn't have to be requested again.
+    pub fn num_parts_resumed(&self) -> u64 {
+        self.downloads.iter().filter(|d| d.done && d.state_requests_count == 0).count() as u64
+    }
+
+    /// Estimates the time needed to download the remaining state parts, based
+    /// on the rate at which the parts were downloaded so far. Returns None if
+    /// not downloading parts, or if no part has been downloaded yet.
+    pub fn estimate_remaining_time(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        if self.status != ShardSyncStatus::StateDownloadParts {
+            return None;
+        }
+        let start_time = self.downloads.iter().map(|d| d.start_time).min()?;
+        let num_parts_done = self.downloads.iter().filter(|d| d.done).count() as i64;
+        let num_parts_downloaded = num_parts_done - self.num_parts_resumed() as i64;
+        if num_parts_downloaded <= 0 {
+            return None;
+        }
+        let num_parts_remaining = self.downloads.len() as i64 - num_parts_done;
+        let elapsed = now - start_time;
+        Some(elapsed * num_parts_remaining as i32 / num_parts_downloaded as i32)
+    }
 }
 
 pub fn format_shard_sync_phase_per_shard(
@@ -1246,6 +1276,34 @@ impl From<std::io::Error> for GetSplitStorageInfoError {
     }
 }
 
+#[derive(Debug)]
+pub struct GetAccountStorageFootprint {
+    pub account_id: AccountId,
+}
+
+impl Message for GetAccountStorageFootprint {
+    type Result = Result<AccountStorageFootprintView, GetAccountStorageFootprintError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetAccountStorageFootprintError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Account storage footprints are not maintained for shard {0} on this node")]
+    UnavailableShard(ShardId),
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/utnet-org/utility/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<unc_chain_primitives::Error> for GetAccountStorageFootprintError {
+    fn from(error: unc_chain_primitives::Error) -> Self {
+        match error {
+            unc_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
 #[cfg(feature = "sandbox")]
 #[derive(Debug)]
 pub enum SandboxMessage {
@@ -5,6 +5,7 @@ use unc_o11y::WithSpanContextExt;
 use unc_primitives::block::{Approval, Block, BlockHeader};
 use unc_primitives::challenge::Challenge;
 use unc_primitives::chunk_validation::{ChunkEndorsement, ChunkStateWitness};
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::errors::InvalidTxError;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::network::{AnnounceAccount, PeerId};
@@ -75,6 +76,11 @@ pub struct BlockApproval(pub Approval, pub PeerId);
 #[rtype(result = "Option<Vec<BlockHeader>>")]
 pub(crate) struct BlockHeadersRequest(pub Vec<CryptoHash>);
 
+/// Request headers of the canonical chain with heights in the given inclusive range.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "Option<Vec<BlockHeader>>")]
+pub(crate) struct BlockHeadersRangeRequest(pub BlockHeight, pub BlockHeight);
+
 /// Headers response.
 #[derive(actix::Message, Debug)]
 #[rtype(result = "Result<(),ReasonForBan>")]
@@ -102,6 +108,29 @@ pub struct StateRequestPart {
 #[rtype(result = "()")]
 pub struct StateResponse(pub Box<StateResponseInfo>);
 
+/// Request the light client block of an epoch for Epoch Sync.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "Option<Box<EpochSyncResponse>>")]
+pub(crate) struct EpochSyncRequest(pub EpochId);
+
+/// Epoch Sync response.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "Result<(),ReasonForBan>")]
+pub(crate) struct RecvEpochSyncResponse(pub EpochSyncResponse, pub PeerId);
+
+/// Request data finishing Epoch Sync at the first block of an epoch.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "Option<Box<EpochSyncFinalizationResponse>>")]
+pub(crate) struct EpochSyncFinalizationRequest(pub EpochId);
+
+/// Epoch Sync finalization response.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "Result<(),ReasonForBan>")]
+pub(crate) struct RecvEpochSyncFinalizationResponse(
+    pub Box<EpochSyncFinalizationResponse>,
+    pub PeerId,
+);
+
 /// Account announcements that needs to be validated before being processed.
 /// They are paired with last epoch id known to this announcement, in order to accept only
 /// newer announcements.
@@ -290,6 +319,24 @@ impl unc_network::client::Client for Adapter {
         }
     }
 
+    async fn block_headers_range_request(
+        &self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> Option<Vec<BlockHeader>> {
+        match self
+            .view_client_addr
+            .send(BlockHeadersRangeRequest(start_height, end_height).with_span_context())
+            .await
+        {
+            Ok(headers) => headers,
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                None
+            }
+        }
+    }
+
     async fn block(&self, block: Block, peer_id: PeerId, was_requested: bool) {
         match self
             .client_addr
@@ -361,4 +408,69 @@ impl unc_network::client::Client for Adapter {
             Err(err) => tracing::error!("mailbox error: {err}"),
         }
     }
+
+    async fn epoch_sync_request(&self, epoch_id: EpochId) -> Option<Box<EpochSyncResponse>> {
+        match self.view_client_addr.send(EpochSyncRequest(epoch_id).with_span_context()).await {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                None
+            }
+        }
+    }
+
+    async fn epoch_sync_response(
+        &self,
+        response: EpochSyncResponse,
+        peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        match self
+            .client_addr
+            .send(RecvEpochSyncResponse(response, peer_id).with_span_context())
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                Ok(())
+            }
+        }
+    }
+
+    async fn epoch_sync_finalization_request(
+        &self,
+        epoch_id: EpochId,
+    ) -> Option<Box<EpochSyncFinalizationResponse>> {
+        match self
+            .view_client_addr
+            .send(EpochSyncFinalizationRequest(epoch_id).with_span_context())
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                None
+            }
+        }
+    }
+
+    async fn epoch_sync_finalization_response(
+        &self,
+        response: EpochSyncFinalizationResponse,
+        peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        match self
+            .client_addr
+            .send(
+                RecvEpochSyncFinalizationResponse(Box::new(response), peer_id).with_span_context(),
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                Ok(())
+            }
+        }
+    }
 }
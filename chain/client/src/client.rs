@@ -273,7 +273,6 @@ impl Client {
         let epoch_sync = EpochSync::new(
             network_adapter.clone(),
             genesis_block.header().epoch_id().clone(),
-            genesis_block.header().next_epoch_id().clone(),
             epoch_manager
                 .get_epoch_block_producers_ordered(
                     genesis_block.header().epoch_id(),
@@ -291,6 +290,7 @@ impl Client {
             config.header_sync_progress_timeout,
             config.header_sync_stall_ban_timeout,
             config.header_sync_expected_height_per_second,
+            config.header_sync_max_parallel_ranges,
         );
         let block_sync = BlockSync::new(
             network_adapter.clone(),
@@ -1444,6 +1444,15 @@ impl Client {
         Ok(())
     }
 
+    /// Adds header ranges downloaded in parallel to the chain once they connect to known headers.
+    pub fn process_header_ranges(&mut self) {
+        while let Some((peer_id, headers)) = self.header_sync.pop_connected_range(&self.chain) {
+            if let Err(err) = self.sync_block_headers(headers) {
+                self.header_sync.reject_range(&peer_id, &err);
+            }
+        }
+    }
+
     /// Checks if the latest hash known to Doomslug matches the current head, and updates it if not.
     pub fn check_and_update_doomslug_tip(&mut self) -> Result<(), Error> {
         let tip = self.chain.head()?;
@@ -7,8 +7,8 @@
 
 use crate::adapter::{
     BlockApproval, BlockHeadersResponse, BlockResponse, ChunkEndorsementMessage,
-    ChunkStateWitnessMessage, ProcessTxRequest, ProcessTxResponse, RecvChallenge, SetNetworkInfo,
-    StateResponse,
+    ChunkStateWitnessMessage, ProcessTxRequest, ProcessTxResponse, RecvChallenge,
+    RecvEpochSyncFinalizationResponse, RecvEpochSyncResponse, SetNetworkInfo, StateResponse,
 };
 #[cfg(feature = "test_features")]
 use crate::client::AdvProduceBlocksMode;
@@ -17,6 +17,7 @@ use crate::config_updater::ConfigUpdater;
 use crate::debug::new_network_info_view;
 use crate::info::{display_sync_status, InfoHelper};
 use crate::sync::adapter::{SyncMessage, SyncShardInfo};
+use crate::sync::header::ReceivedHeaders;
 use crate::sync::state::{StateSync, StateSyncResult};
 use crate::sync_jobs_actor::{create_sync_job_scheduler, SyncJobsActor};
 use crate::{metrics, StatusResponse, SyncAdapter};
@@ -543,6 +544,42 @@ impl Handler<WithSpanContext<StateResponse>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<RecvEpochSyncResponse>> for ClientActor {
+    type Result = Result<(), ReasonForBan>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<RecvEpochSyncResponse>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.wrap(msg, ctx, "RecvEpochSyncResponse", |this, msg| {
+            let RecvEpochSyncResponse(response, peer_id) = msg;
+            this.client.epoch_sync.on_response(&mut this.client.chain, response, peer_id)
+        })
+    }
+}
+
+impl Handler<WithSpanContext<RecvEpochSyncFinalizationResponse>> for ClientActor {
+    type Result = Result<(), ReasonForBan>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<RecvEpochSyncFinalizationResponse>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.wrap(msg, ctx, "RecvEpochSyncFinalizationResponse", |this, msg| {
+            let RecvEpochSyncFinalizationResponse(response, peer_id) = msg;
+            this.client.epoch_sync.on_finalization_response(
+                &mut this.client.chain,
+                *response,
+                peer_id,
+            )
+        })
+    }
+}
+
 impl Handler<WithSpanContext<RecvChallenge>> for ClientActor {
     type Result = ();
 
@@ -1381,12 +1418,25 @@ impl ClientActor {
         let _span =
             debug_span!(target: "client", "receive_headers", num_headers = headers.len(), ?peer_id)
                 .entered();
+        let headers = match self.client.header_sync.receive_headers(&peer_id, headers) {
+            ReceivedHeaders::NotRequested(headers) => headers,
+            ReceivedHeaders::Stored => {
+                self.client.process_header_ranges();
+                return true;
+            }
+            ReceivedHeaders::Empty => return true,
+            ReceivedHeaders::Invalid => return false,
+        };
         if headers.is_empty() {
             info!(target: "client", "Received an empty set of block headers");
             return true;
         }
         match self.client.sync_block_headers(headers) {
-            Ok(_) => true,
+            Ok(_) => {
+                // The batch may have connected ranges downloaded from other peers.
+                self.client.process_header_ranges();
+                true
+            }
             Err(err) => {
                 if err.is_bad_data() {
                     error!(target: "client", ?err, "Error processing sync blocks");
@@ -1613,18 +1663,36 @@ impl ClientActor {
                     info!(target: "client", ?sync, "enabling sync");
                 }
                 // Run each step of syncing separately.
-                unwrap_and_report!(self.client.header_sync.run(
-                    &mut self.client.sync_status,
-                    &mut self.client.chain,
-                    highest_height,
-                    &self.network_info.highest_height_peers
-                ));
+                if self.client.config.epoch_sync_enabled
+                    && unwrap_and_report!(self.client.epoch_sync.run(
+                        &mut self.client.sync_status,
+                        &self.client.chain,
+                        highest_height,
+                        &self.network_info.highest_height_peers
+                    ))
+                {
+                    return;
+                }
+                // Epoch Sync which finished with finalization hands off to State Sync directly.
+                let epoch_sync_hash = match self.client.sync_status {
+                    SyncStatus::EpochSync { .. } => self.client.epoch_sync.sync_hash,
+                    _ => None,
+                };
+                if epoch_sync_hash.is_none() {
+                    unwrap_and_report!(self.client.header_sync.run(
+                        &mut self.client.sync_status,
+                        &mut self.client.chain,
+                        highest_height,
+                        &self.network_info.highest_height_peers
+                    ));
+                }
                 // Only body / state sync if header height is close to the latest.
                 let header_head = unwrap_and_report!(self.client.chain.header_head());
 
                 // Sync state if already running sync state or if block sync is too far.
                 let sync_state = match self.client.sync_status {
                     SyncStatus::StateSync(_) => true,
+                    _ if epoch_sync_hash.is_some() => true,
                     _ if header_head.height
                         >= highest_height
                             .saturating_sub(self.client.config.block_header_fetch_horizon) =>
@@ -1642,7 +1710,10 @@ impl ClientActor {
                     match self.client.sync_status {
                         SyncStatus::StateSync(_) => (),
                         _ => {
-                            let sync_hash = unwrap_and_report!(self.find_sync_hash());
+                            let sync_hash = match epoch_sync_hash {
+                                Some(sync_hash) => sync_hash,
+                                None => unwrap_and_report!(self.find_sync_hash()),
+                            };
                             if !self.client.config.archive {
                                 unwrap_and_report!(self
                                     .client
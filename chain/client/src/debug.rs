@@ -7,6 +7,7 @@ use itertools::Itertools;
 use std::cmp::{max, min};
 use std::collections::{HashMap, HashSet};
 use unc_chain::crypto_hash_timer::CryptoHashTimer;
+use unc_chain::store_scrubber::get_store_scrubber_status;
 use unc_chain::{unc_chain_primitives, Chain, ChainStoreAccess};
 use unc_client_primitives::debug::{
     ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
@@ -179,6 +180,10 @@ impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::StoreScrubberStatus => Ok(DebugStatusResponse::StoreScrubberStatus(
+                get_store_scrubber_status(self.client.chain.chain_store().store())
+                    .map_err(|err| StatusError::InternalError { error_message: err.to_string() })?,
+            )),
         }
     }
 }
@@ -666,6 +671,7 @@ fn new_peer_info_view(chain: &Chain, connected_peer_info: &ConnectedPeerInfo) ->
             .elapsed()
             .whole_milliseconds() as u64,
         is_outbound_peer: connected_peer_info.peer_type == PeerType::Outbound,
+        reputation_score: connected_peer_info.reputation_score.round() as i64,
         nonce: connected_peer_info.nonce,
     }
 }
@@ -446,6 +446,15 @@ pub(crate) static STATE_SYNC_PARTS_TOTAL: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub(crate) static STATE_SYNC_RESUMED_PARTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_state_sync_resumed_parts_total",
+        "Number of parts downloaded before a restart, which didn't have to be downloaded again",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
 pub(crate) static STATE_SYNC_DISCARD_PARTS: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "unc_state_sync_discard_parts_total",
@@ -513,6 +522,33 @@ pub(crate) static STATE_SYNC_DUMP_LIST_OBJECT_ELAPSED: Lazy<HistogramVec> = Lazy
     .unwrap()
 });
 
+pub(crate) static HEADER_SYNC_PEER_HEADERS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_header_sync_peer_headers_total",
+        "Number of block headers received from a peer during header sync",
+        &["peer_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static HEADER_SYNC_PEER_HEADERS_PER_SECOND: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "unc_header_sync_peer_headers_per_second",
+        "Rate at which a peer served the latest requested range of block headers",
+        &["peer_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static HEADER_SYNC_RANGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_header_sync_ranges_total",
+        "Number of header ranges requested from peers in parallel, by outcome",
+        &["peer_id", "result"],
+    )
+    .unwrap()
+});
+
 pub(crate) static SYNC_REQUIREMENT: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "unc_sync_requirements_total",
@@ -1,16 +1,33 @@
 use chrono::{DateTime, Duration, Utc};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use std::collections::{HashMap, HashSet};
 use std::time::Duration as TimeDuration;
-use unc_network::types::PeerManagerAdapter;
+use tracing::{debug, error, info, warn};
+use unc_async::messaging::CanSend;
+use unc_chain::Chain;
+use unc_client_primitives::types::SyncStatus;
+use unc_network::types::{
+    HighestHeightPeerInfo, NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest,
+    ReasonForBan,
+};
+use unc_primitives::epoch_sync::{
+    validate_light_client_block, EpochSyncError, EpochSyncFinalizationResponse, EpochSyncResponse,
+};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::network::PeerId;
 use unc_primitives::static_clock::StaticClock;
 use unc_primitives::types::validator_power_and_pledge::ValidatorPowerAndPledge;
-use unc_primitives::types::EpochId;
+use unc_primitives::types::{BlockHeight, EpochId};
+use unc_primitives::views::LightClientBlockView;
 
-/// Helper to keep track of the Epoch Sync
-// TODO #3488
-#[allow(dead_code)]
+/// Helper to keep track of the Epoch Sync.
+///
+/// Starting from the genesis epoch, requests the light client block of every epoch and validates
+/// it against the block producers of that epoch, which yields the block producers of the next
+/// epoch. Once the majority of peers report that we are up to date, requests headers linking the
+/// last validated light client block to the first block of the current epoch and finishes there,
+/// so that State Sync can start at that block.
 pub struct EpochSync {
     network_adapter: PeerManagerAdapter,
     /// Datastructure to keep track of when the last request to each peer was made.
@@ -21,12 +38,15 @@ pub struct EpochSync {
     peers_reporting_up_to_date: HashSet<PeerId>,
     /// The last epoch we are synced to
     current_epoch_id: EpochId,
-    /// The next epoch id we need to sync
-    next_epoch_id: EpochId,
-    /// The block producers set to validate the light client block view for the next epoch
+    /// The block producers set to validate the light client block view of `current_epoch_id`,
+    /// which proves block producers of the next epoch
     next_block_producers: Vec<ValidatorPowerAndPledge>,
-    /// The last epoch id that we have requested
-    requested_epoch_id: EpochId,
+    /// The light client block of the epoch before `current_epoch_id` and the block producers
+    /// it was validated with. Epoch Sync finalization is validated against them.
+    prev_epoch_light_client_block:
+        Option<(Box<LightClientBlockView>, Vec<ValidatorPowerAndPledge>)>,
+    /// Number of epochs synced so far
+    epoch_ord: u64,
     /// When and to whom was the last request made
     last_request_time: DateTime<Utc>,
     last_request_peer_id: Option<PeerId>,
@@ -43,8 +63,12 @@ pub struct EpochSync {
     /// Current state machine allows for only one Epoch Sync.
     pub done: bool,
 
-    pub sync_hash: CryptoHash,
+    /// The first block of the epoch Epoch Sync finished in, if it finished with finalization.
+    /// State Sync starts at this block.
+    pub sync_hash: Option<CryptoHash>,
 
+    /// Whether a response to the last request was received, so the next one can be sent
+    /// without waiting for `request_timeout`.
     received_epoch: bool,
 
     is_just_started: bool,
@@ -54,7 +78,6 @@ impl EpochSync {
     pub fn new(
         network_adapter: PeerManagerAdapter,
         genesis_epoch_id: EpochId,
-        genesis_next_epoch_id: EpochId,
         first_epoch_block_producers: Vec<ValidatorPowerAndPledge>,
         request_timeout: TimeDuration,
         peer_timeout: TimeDuration,
@@ -63,10 +86,10 @@ impl EpochSync {
             network_adapter,
             peer_to_last_request_time: HashMap::new(),
             peers_reporting_up_to_date: HashSet::new(),
-            current_epoch_id: genesis_epoch_id.clone(),
-            next_epoch_id: genesis_next_epoch_id,
+            current_epoch_id: genesis_epoch_id,
             next_block_producers: first_epoch_block_producers,
-            requested_epoch_id: genesis_epoch_id,
+            prev_epoch_light_client_block: None,
+            epoch_ord: 0,
             last_request_time: StaticClock::utc(),
             last_request_peer_id: None,
             request_timeout: Duration::from_std(request_timeout).unwrap(),
@@ -74,8 +97,183 @@ impl EpochSync {
             received_epoch: false,
             have_all_epochs: false,
             done: false,
-            sync_hash: CryptoHash::default(),
+            sync_hash: None,
             is_just_started: true,
         }
     }
+
+    /// Can update `sync_status` to `EpochSync`.
+    /// Can request the next light client block or the finalization data from a peer.
+    /// Returns true while Epoch Sync is in progress and other kinds of sync have to wait.
+    pub fn run(
+        &mut self,
+        sync_status: &mut SyncStatus,
+        chain: &Chain,
+        highest_height: BlockHeight,
+        highest_height_peers: &[HighestHeightPeerInfo],
+    ) -> Result<bool, unc_chain::Error> {
+        let _span = tracing::debug_span!(target: "sync", "run", sync = "EpochSync").entered();
+        if self.done {
+            return Ok(false);
+        }
+        if !matches!(sync_status, SyncStatus::EpochSync { .. }) {
+            // Epoch Sync only pays off for a node which hasn't synced anything yet and is
+            // more than a couple of epochs behind.
+            let header_head = chain.header_head()?;
+            if header_head.height != chain.genesis().height()
+                || highest_height <= header_head.height + 2 * chain.epoch_length
+            {
+                self.done = true;
+                return Ok(false);
+            }
+            info!(target: "sync", highest_height, "Starting Epoch Sync");
+        }
+        sync_status.update(SyncStatus::EpochSync { epoch_ord: self.epoch_ord });
+
+        if !self.have_all_epochs
+            && !highest_height_peers.is_empty()
+            && self.peers_reporting_up_to_date.len() * 2 > highest_height_peers.len()
+        {
+            if self.prev_epoch_light_client_block.is_none() {
+                // Peers are still in the epoch we started from, Header Sync will do.
+                info!(target: "sync", "Epoch Sync is not needed, peers are in the genesis epoch");
+                self.done = true;
+                return Ok(false);
+            }
+            debug!(target: "sync", epoch_id = ?self.current_epoch_id, "Synced all epochs, finalizing Epoch Sync");
+            self.have_all_epochs = true;
+            self.received_epoch = true;
+        }
+
+        let now = StaticClock::utc();
+        if self.is_just_started {
+            self.is_just_started = false;
+            self.received_epoch = true;
+        }
+        if !self.received_epoch && now - self.last_request_time < self.request_timeout {
+            // A request is in-flight.
+            return Ok(true);
+        }
+
+        let peers: Vec<_> = highest_height_peers
+            .iter()
+            .filter(|peer| {
+                let peer_id = &peer.peer_info.id;
+                (self.have_all_epochs || !self.peers_reporting_up_to_date.contains(peer_id))
+                    && self
+                        .peer_to_last_request_time
+                        .get(peer_id)
+                        .map_or(true, |time| now - *time >= self.peer_timeout)
+            })
+            .collect();
+        let Some(peer) = peers.choose(&mut thread_rng()) else {
+            return Ok(true);
+        };
+        let peer_id = peer.peer_info.id.clone();
+        let epoch_id = self.current_epoch_id.clone();
+        debug!(target: "sync", ?epoch_id, ?peer_id, finalization = self.have_all_epochs, "Sync: request epoch");
+        let request = if self.have_all_epochs {
+            NetworkRequests::EpochSyncFinalizationRequest { epoch_id, peer_id: peer_id.clone() }
+        } else {
+            NetworkRequests::EpochSyncRequest { epoch_id, peer_id: peer_id.clone() }
+        };
+        self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(request));
+        self.peer_to_last_request_time.insert(peer_id.clone(), now);
+        self.last_request_time = now;
+        self.last_request_peer_id = Some(peer_id);
+        self.received_epoch = false;
+        Ok(true)
+    }
+
+    /// Processes the light client block of the current epoch received from `peer_id`.
+    /// A valid block moves Epoch Sync to the next epoch and is saved, so that the node can serve
+    /// it to other nodes.
+    pub fn on_response(
+        &mut self,
+        chain: &mut Chain,
+        response: EpochSyncResponse,
+        peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        if self.done || self.have_all_epochs {
+            return Ok(());
+        }
+        let light_client_block = match response {
+            EpochSyncResponse::UpToDate => {
+                debug!(target: "sync", epoch_id = ?self.current_epoch_id, ?peer_id, "Peer reports we are up to date");
+                self.received_epoch |= self.last_request_peer_id.as_ref() == Some(&peer_id);
+                self.peers_reporting_up_to_date.insert(peer_id);
+                return Ok(());
+            }
+            EpochSyncResponse::Advance { light_client_block_view } => light_client_block_view,
+        };
+        let next_block_producers = match validate_light_client_block(
+            &self.current_epoch_id,
+            &self.next_block_producers,
+            &light_client_block,
+        ) {
+            Ok(next_block_producers) => next_block_producers,
+            Err(EpochSyncError::UnexpectedEpoch { .. }) => {
+                // Late response to one of the previous requests.
+                return Ok(());
+            }
+            Err(err) => {
+                warn!(target: "sync", ?peer_id, ?err, "Banning peer for invalid epoch sync response");
+                return Err(ReasonForBan::BadEpochSyncResponse);
+            }
+        };
+        if let Err(err) = chain.save_epoch_sync_light_client_block(
+            &self.current_epoch_id,
+            LightClientBlockView::clone(&light_client_block),
+        ) {
+            error!(target: "sync", ?err, "Failed to save light client block");
+            return Ok(());
+        }
+
+        let next_epoch_id = EpochId(light_client_block.inner_lite.next_epoch_id);
+        debug!(target: "sync", epoch_id = ?self.current_epoch_id, ?next_epoch_id, "Synced epoch");
+        let block_producers =
+            std::mem::replace(&mut self.next_block_producers, next_block_producers);
+        self.prev_epoch_light_client_block = Some((light_client_block, block_producers));
+        self.current_epoch_id = next_epoch_id;
+        self.epoch_ord += 1;
+        self.peers_reporting_up_to_date.clear();
+        self.received_epoch = true;
+        Ok(())
+    }
+
+    /// Processes headers and epoch data finishing Epoch Sync received from `peer_id`.
+    /// A valid response is applied to the chain and sets `sync_hash` for State Sync.
+    pub fn on_finalization_response(
+        &mut self,
+        chain: &mut Chain,
+        response: EpochSyncFinalizationResponse,
+        peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        if self.done || !self.have_all_epochs {
+            return Ok(());
+        }
+        let Some((light_client_block, block_producers)) = &self.prev_epoch_light_client_block
+        else {
+            return Ok(());
+        };
+        match response.validate(light_client_block, block_producers) {
+            Ok(()) => {}
+            Err(EpochSyncError::UnexpectedEpoch { .. }) => return Ok(()),
+            Err(err) => {
+                warn!(target: "sync", ?peer_id, ?err, "Banning peer for invalid epoch sync finalization");
+                return Err(ReasonForBan::BadEpochSyncResponse);
+            }
+        }
+
+        let sync_hash = *response.cur_epoch_header.hash();
+        if let Err(err) = chain.apply_epoch_sync_finalization(response) {
+            error!(target: "sync", ?err, "Failed to apply epoch sync finalization");
+            self.received_epoch = true;
+            return Ok(());
+        }
+        info!(target: "sync", epoch_id = ?self.current_epoch_id, ?sync_hash, epochs = self.epoch_ord, "Epoch Sync finished");
+        self.done = true;
+        self.sync_hash = Some(sync_hash);
+        Ok(())
+    }
 }
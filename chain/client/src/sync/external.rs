@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
+use unc_primitives::hash::{hash, CryptoHash};
 use unc_primitives::types::{EpochId, ShardId};
 
 /// Connection to the external storage.
@@ -24,6 +25,61 @@ pub enum ExternalConnection {
         reqwest_client: Arc<reqwest::Client>,
         bucket: String,
     },
+    /// Read-only storage served by any HTTP(S) server, including the RPC port of a node
+    /// mirroring its state dumps. Listing relies on the manifest files.
+    Http {
+        reqwest_client: Arc<reqwest::Client>,
+        /// Url the locations of state parts are relative to, without the trailing slash.
+        base_url: String,
+    },
+}
+
+/// Path on the RPC port under which a node mirrors its state dumps.
+pub const STATE_PARTS_MIRROR_PATH: &str = "state_parts";
+
+/// Name of the file listing checksums of all state parts of a shard in an epoch.
+/// Published in the same directory as the state parts once all of them are dumped.
+pub const STATE_PARTS_MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Checksums of all state parts of a shard in an epoch.
+/// State parts downloaded from external storage are checked against the manifest before they
+/// are applied.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StatePartsManifest {
+    pub num_parts: u64,
+    /// Hash of every state part, indexed by part id.
+    pub part_hashes: Vec<CryptoHash>,
+}
+
+impl StatePartsManifest {
+    pub fn new(part_hashes: Vec<CryptoHash>) -> Self {
+        Self { num_parts: part_hashes.len() as u64, part_hashes }
+    }
+
+    /// Checks that `data` is the state part `part_id` out of `num_parts` listed in the manifest.
+    pub fn verify_part(
+        &self,
+        part_id: u64,
+        num_parts: u64,
+        data: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        if self.num_parts != num_parts || self.part_hashes.len() as u64 != num_parts {
+            anyhow::bail!(
+                "Manifest lists {} parts, expected {}",
+                self.part_hashes.len(),
+                num_parts
+            );
+        }
+        let expected = self
+            .part_hashes
+            .get(part_id as usize)
+            .ok_or_else(|| anyhow::anyhow!("Part {} is not in the manifest", part_id))?;
+        let actual = hash(data);
+        if actual != *expected {
+            anyhow::bail!("Checksum mismatch for part {}: {} != {}", part_id, actual, expected);
+        }
+        Ok(())
+    }
 }
 
 const GCS_ENCODE_SET: &percent_encoding::AsciiSet =
@@ -75,9 +131,54 @@ impl ExternalConnection {
                     }
                 }
             }
+            ExternalConnection::Http { reqwest_client, base_url } => {
+                let url = format!("{}/{}", base_url, location);
+                let response = reqwest_client.get(&url).send().await?.error_for_status();
+                match response {
+                    Err(e) => {
+                        tracing::debug!(target: "sync", %shard_id, location, error = ?e, "HTTP state_part request failed");
+                        Err(e.into())
+                    }
+                    Ok(r) => {
+                        let bytes = r.bytes().await?.to_vec();
+                        tracing::debug!(target: "sync", %shard_id, location, num_bytes = bytes.len(), "HTTP state_part request finished");
+                        Ok(bytes)
+                    }
+                }
+            }
         }
     }
 
+    /// Whether state parts from this storage are only accepted if they are listed in a manifest.
+    /// HTTP storage can be any node mirroring its state dumps, whereas the other storages are
+    /// set up by the node operator and their parts are only checked against a published manifest.
+    pub fn requires_manifest(&self) -> bool {
+        matches!(self, ExternalConnection::Http { .. })
+    }
+
+    /// Downloads the manifest of the state parts in the given directory.
+    pub async fn get_manifest(
+        &self,
+        shard_id: ShardId,
+        directory_path: &str,
+    ) -> Result<StatePartsManifest, anyhow::Error> {
+        let location = format!("{}/{}", directory_path, STATE_PARTS_MANIFEST_FILENAME);
+        let data = self.get_part(shard_id, &location).await?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Uploads the manifest of the state parts in the given directory.
+    pub async fn put_manifest(
+        &self,
+        manifest: &StatePartsManifest,
+        shard_id: ShardId,
+        directory_path: &str,
+    ) -> Result<(), anyhow::Error> {
+        let location = format!("{}/{}", directory_path, STATE_PARTS_MANIFEST_FILENAME);
+        let data = serde_json::to_vec(manifest)?;
+        self.put_state_part_impl(&data, shard_id, &location).await
+    }
+
     /// Uploads the given state part to external storage.
     // Wrapper for adding is_ok to the metric labels.
     pub async fn put_state_part(
@@ -131,6 +232,9 @@ impl ExternalConnection {
                 tracing::debug!(target: "state_sync_dump", shard_id, part_length = state_part.len(), ?location, "Wrote a state part to GCS");
                 Ok(())
             }
+            ExternalConnection::Http { .. } => {
+                anyhow::bail!("HTTP external storage is read-only")
+            }
         }
     }
 
@@ -145,6 +249,7 @@ impl ExternalConnection {
     /// When using GCS external connection, this function requires credentials.
     /// Thus, this function shouldn't be used for sync node that is expected to operate anonymously.
     /// Only dump nodes should use this function.
+    /// Returns only the names of state part files, other files such as the manifest are skipped.
     pub async fn list_state_parts(
         &self,
         shard_id: ShardId,
@@ -153,6 +258,15 @@ impl ExternalConnection {
         let _timer = metrics::STATE_SYNC_DUMP_LIST_OBJECT_ELAPSED
             .with_label_values(&[&shard_id.to_string()])
             .start_timer();
+        let file_names = self.list_files(shard_id, directory_path).await?;
+        Ok(file_names.into_iter().filter(|file_name| is_part_filename(file_name)).collect())
+    }
+
+    async fn list_files(
+        &self,
+        shard_id: ShardId,
+        directory_path: &str,
+    ) -> Result<Vec<String>, anyhow::Error> {
         match self {
             ExternalConnection::S3 { bucket } => {
                 let prefix = format!("{}/", directory_path);
@@ -201,6 +315,14 @@ impl ExternalConnection {
                     .flatten()
                     .collect())
             }
+            ExternalConnection::Http { .. } => {
+                // Static file servers don't support listing, the manifest lists all parts.
+                tracing::debug!(target: "state_sync_dump", shard_id, ?directory_path, "List state parts in the manifest");
+                let manifest = self.get_manifest(shard_id, directory_path).await?;
+                Ok((0..manifest.num_parts)
+                    .map(|part_id| part_filename(part_id, manifest.num_parts))
+                    .collect())
+            }
         }
     }
 }
@@ -333,7 +455,7 @@ fn create_bucket(
 mod test {
     use crate::sync::external::{
         get_num_parts_from_filename, get_part_id_from_filename, is_part_filename, part_filename,
-        ExternalConnection,
+        ExternalConnection, StatePartsManifest,
     };
     use rand::distributions::{Alphanumeric, DistString};
     use unc_o11y::testonly::init_test_logger;
@@ -355,6 +477,43 @@ mod test {
         assert_eq!(get_part_id_from_filename("123123"), None);
     }
 
+    #[test]
+    fn test_manifest_verify_part() {
+        let parts: Vec<Vec<u8>> = (0..3).map(|i| vec![i; 10]).collect();
+        let manifest = StatePartsManifest::new(
+            parts.iter().map(|part| unc_primitives::hash::hash(part)).collect(),
+        );
+        for (part_id, part) in parts.iter().enumerate() {
+            manifest.verify_part(part_id as u64, 3, part).unwrap();
+        }
+        assert!(manifest.verify_part(0, 3, &parts[1]).is_err());
+        assert!(manifest.verify_part(0, 4, &parts[0]).is_err());
+        assert!(manifest.verify_part(3, 3, &parts[0]).is_err());
+
+        let json = serde_json::to_vec(&manifest).unwrap();
+        assert_eq!(serde_json::from_slice::<StatePartsManifest>(&json).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_filesystem_manifest_is_not_listed() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let connection = ExternalConnection::Filesystem { root_dir: tmp_dir.path().to_path_buf() };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let dir = "chain_id=test/epoch_height=1/epoch_id=test/shard_id=0";
+        let part = vec![7u8; 10];
+        let manifest = StatePartsManifest::new(vec![unc_primitives::hash::hash(&part)]);
+        rt.block_on(async {
+            let location = format!("{}/{}", dir, part_filename(0, 1));
+            connection.put_state_part(&part, 0, &location).await.unwrap();
+            connection.put_manifest(&manifest, 0, dir).await.unwrap();
+            assert_eq!(
+                connection.list_state_parts(0, dir).await.unwrap(),
+                vec![part_filename(0, 1)]
+            );
+            assert_eq!(connection.get_manifest(0, dir).await.unwrap(), manifest);
+        });
+    }
+
     /// This test should be ignored by default, as it requires gcloud credentials to run.
     /// Specify the path to service account json  in `SERVICE_ACCOUNT` variable to run the test.
     #[test]
@@ -1,16 +1,20 @@
+use crate::metrics;
 use chrono::{DateTime, Duration, Utc};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use std::cmp::min;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::Duration as TimeDuration;
 use tracing::{debug, warn};
 use unc_async::messaging::CanSend;
 use unc_chain::{Chain, ChainStoreAccess};
 use unc_client_primitives::types::SyncStatus;
-use unc_network::types::PeerManagerMessageRequest;
 use unc_network::types::{HighestHeightPeerInfo, NetworkRequests, PeerManagerAdapter};
+use unc_network::types::{PeerManagerMessageRequest, ReasonForBan};
 use unc_primitives::block::Tip;
+use unc_primitives::block_header::BlockHeader;
 use unc_primitives::hash::CryptoHash;
+use unc_primitives::network::PeerId;
 use unc_primitives::static_clock::StaticClock;
 use unc_primitives::types::BlockHeight;
 
@@ -33,6 +37,35 @@ struct BatchProgress {
     highest_height_of_peers: BlockHeight,
 }
 
+/// A range of headers requested from a peer other than the syncing peer.
+/// Ranges are aligned to multiples of `MAX_BLOCK_HEADERS` and keyed by their start height.
+enum HeaderRange {
+    /// The request is in flight.
+    Requested { peer_id: PeerId, requested_at: DateTime<Utc> },
+    /// The headers are downloaded and wait for the preceding headers to be added to the chain.
+    Downloaded { peer_id: PeerId, headers: Vec<BlockHeader> },
+}
+
+/// What `HeaderSync` made of a batch of headers received from a peer.
+pub enum ReceivedHeaders {
+    /// The headers don't answer a range request and should be added to the chain directly.
+    NotRequested(Vec<BlockHeader>),
+    /// The headers answer a range request and are kept until they connect to the chain.
+    Stored,
+    /// The peer has no headers in the range requested from it.
+    Empty,
+    /// The headers don't match the range requested from the peer.
+    Invalid,
+}
+
+/// Last height of the range starting at `start`.
+fn range_end(start: BlockHeight) -> BlockHeight {
+    start + MAX_BLOCK_HEADERS - 1
+}
+
+/// Values of the `result` label of `metrics::HEADER_SYNC_RANGES`.
+const RANGE_RESULTS: [&str; 5] = ["ok", "empty", "invalid", "timeout", "unlinked"];
+
 /// Helper to keep track of sync headers.
 /// Handles major re-orgs by finding closest header that matches and re-downloading headers from that point.
 pub struct HeaderSync {
@@ -59,6 +92,21 @@ pub struct HeaderSync {
 
     /// Expected increase of header head height per second during header sync
     expected_height_per_second: u64,
+
+    /// Maximum number of header ranges requested from other peers in parallel with the batch
+    /// requested from `syncing_peer`.
+    max_parallel_ranges: usize,
+
+    /// Header ranges above the requested batch, keyed by their start height.
+    ranges: BTreeMap<BlockHeight, HeaderRange>,
+
+    /// Peers which had no headers in a range requested from them, with the start of the lowest
+    /// such range. They aren't requested ranges starting at or above it, until the header head
+    /// reaches it.
+    empty_ranges: HashMap<PeerId, BlockHeight>,
+
+    /// Peers labelling the per-peer metrics. The labels are removed once the peers disconnect.
+    metric_peers: HashSet<PeerId>,
 }
 
 impl HeaderSync {
@@ -68,6 +116,7 @@ impl HeaderSync {
         progress_timeout: TimeDuration,
         stall_ban_timeout: TimeDuration,
         expected_height_per_second: u64,
+        max_parallel_ranges: usize,
     ) -> Self {
         HeaderSync {
             network_adapter,
@@ -83,11 +132,16 @@ impl HeaderSync {
             progress_timeout: Duration::from_std(progress_timeout).unwrap(),
             stall_ban_timeout: Duration::from_std(stall_ban_timeout).unwrap(),
             expected_height_per_second,
+            max_parallel_ranges,
+            ranges: BTreeMap::new(),
+            empty_ranges: HashMap::new(),
+            metric_peers: HashSet::new(),
         }
     }
 
     /// Can update `sync_status` to `HeaderSync`.
-    /// Can request a new batch of headers from a peer.
+    /// Can request a new batch of headers from a peer, and ranges of headers above that batch
+    /// from other peers.
     /// This function won't tell you that header sync is complete.
     pub fn run(
         &mut self,
@@ -97,6 +151,22 @@ impl HeaderSync {
         highest_height_peers: &[HighestHeightPeerInfo],
     ) -> Result<(), unc_chain::Error> {
         let _span = tracing::debug_span!(target: "sync", "run", sync = "HeaderSync").entered();
+        self.remove_disconnected_peer_metrics(highest_height_peers);
+        self.run_batch(sync_status, chain, highest_height, highest_height_peers)?;
+        if let SyncStatus::HeaderSync { .. } = sync_status {
+            self.request_ranges(&chain.header_head()?, highest_height, highest_height_peers);
+        }
+        Ok(())
+    }
+
+    /// Requests the next batch of headers from `syncing_peer` if the previous batch is done.
+    fn run_batch(
+        &mut self,
+        sync_status: &mut SyncStatus,
+        chain: &Chain,
+        highest_height: BlockHeight,
+        highest_height_peers: &[HighestHeightPeerInfo],
+    ) -> Result<(), unc_chain::Error> {
         let head = chain.head()?;
         let header_head = chain.header_head()?;
 
@@ -142,8 +212,14 @@ impl HeaderSync {
         });
 
         self.syncing_peer = None;
-        // Pick a new random peer to request the next batch of headers.
-        if let Some(peer) = highest_height_peers.choose(&mut thread_rng()).cloned() {
+        // Pick a new random peer to request the next batch of headers. Peers with a range
+        // request in flight are skipped, so that their responses can't be mistaken for the batch.
+        let range_peers = self.range_peers();
+        let peers: Vec<_> = highest_height_peers
+            .iter()
+            .filter(|peer| !range_peers.contains(&peer.peer_info.id))
+            .collect();
+        if let Some(peer) = peers.choose(&mut thread_rng()).map(|peer| (*peer).clone()) {
             // TODO: This condition should always be true, otherwise we can already complete header sync.
             if peer.highest_block_height > header_head.height {
                 self.syncing_peer = self.request_headers(chain, peer);
@@ -152,6 +228,218 @@ impl HeaderSync {
         Ok(())
     }
 
+    /// Requests ranges of headers above the batch requested from `syncing_peer` from other peers,
+    /// at most one range per peer. Drops requests that timed out and ranges that are no longer
+    /// needed.
+    fn request_ranges(
+        &mut self,
+        header_head: &Tip,
+        highest_height: BlockHeight,
+        highest_height_peers: &[HighestHeightPeerInfo],
+    ) {
+        let now = StaticClock::utc();
+        let timeout = self.initial_timeout;
+        self.drop_covered_ranges(header_head.height);
+        self.ranges.retain(|_, range| match range {
+            HeaderRange::Requested { peer_id, requested_at } if now > *requested_at + timeout => {
+                debug!(target: "sync", ?peer_id, "Sync: header range request timed out");
+                metrics::HEADER_SYNC_RANGES
+                    .with_label_values(&[&peer_id.to_string(), "timeout"])
+                    .inc();
+                false
+            }
+            _ => true,
+        });
+        if self.max_parallel_ranges == 0 {
+            return;
+        }
+
+        let mut busy_peers = self.range_peers();
+        if let Some(peer) = &self.syncing_peer {
+            busy_peers.insert(&peer.peer_info.id);
+        }
+        let mut peers: Vec<&HighestHeightPeerInfo> = highest_height_peers
+            .iter()
+            .filter(|peer| !busy_peers.contains(&peer.peer_info.id))
+            .collect();
+        peers.shuffle(&mut thread_rng());
+
+        // The batch requested from `syncing_peer` covers at least `MAX_BLOCK_HEADERS` heights
+        // above the header head, start from the first aligned range above it.
+        let mut start = (header_head.height / MAX_BLOCK_HEADERS + 2) * MAX_BLOCK_HEADERS;
+        let mut requests = vec![];
+        while start <= highest_height
+            && self.ranges.len() + requests.len() < self.max_parallel_ranges
+        {
+            if !self.ranges.contains_key(&start) {
+                let Some(index) = peers.iter().position(|peer| {
+                    peer.highest_block_height >= start
+                        && self
+                            .empty_ranges
+                            .get(&peer.peer_info.id)
+                            .map_or(true, |empty_start| start < *empty_start)
+                }) else {
+                    break;
+                };
+                requests.push((start, peers.swap_remove(index).peer_info.id.clone()));
+            }
+            start += MAX_BLOCK_HEADERS;
+        }
+        for (start_height, peer_id) in requests {
+            debug!(target: "sync", ?peer_id, start_height, "Sync: request header range");
+            self.metric_peers.insert(peer_id.clone());
+            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::BlockHeadersRangeRequest {
+                    start_height,
+                    end_height: range_end(start_height),
+                    peer_id: peer_id.clone(),
+                },
+            ));
+            self.ranges.insert(start_height, HeaderRange::Requested { peer_id, requested_at: now });
+        }
+    }
+
+    /// Peers with a range request in flight.
+    fn range_peers(&self) -> HashSet<&PeerId> {
+        self.ranges
+            .values()
+            .filter_map(|range| match range {
+                HeaderRange::Requested { peer_id, .. } => Some(peer_id),
+                HeaderRange::Downloaded { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Forgets ranges that are entirely at or below the header head.
+    fn drop_covered_ranges(&mut self, header_head_height: BlockHeight) {
+        self.ranges.retain(|start, _| range_end(*start) > header_head_height);
+        self.empty_ranges.retain(|_, start| *start > header_head_height);
+    }
+
+    /// Removes the labels of the per-peer metrics of the peers which are no longer connected.
+    fn remove_disconnected_peer_metrics(&mut self, highest_height_peers: &[HighestHeightPeerInfo]) {
+        let connected: HashSet<_> = highest_height_peers.iter().map(|p| &p.peer_info.id).collect();
+        self.metric_peers.retain(|peer_id| {
+            if connected.contains(peer_id) {
+                return true;
+            }
+            let peer_id = peer_id.to_string();
+            let _ = metrics::HEADER_SYNC_PEER_HEADERS.remove_label_values(&[&peer_id]);
+            let _ = metrics::HEADER_SYNC_PEER_HEADERS_PER_SECOND.remove_label_values(&[&peer_id]);
+            for result in RANGE_RESULTS {
+                let _ = metrics::HEADER_SYNC_RANGES.remove_label_values(&[&peer_id, result]);
+            }
+            false
+        });
+    }
+
+    /// Matches headers received from a peer against the range requested from it.
+    /// A range response must contain headers of increasing heights within the requested range,
+    /// each linking to the previous one.
+    /// Headers starting below the range answer a locator request and aren't checked here.
+    pub fn receive_headers(
+        &mut self,
+        peer_id: &PeerId,
+        headers: Vec<BlockHeader>,
+    ) -> ReceivedHeaders {
+        self.metric_peers.insert(peer_id.clone());
+        metrics::HEADER_SYNC_PEER_HEADERS
+            .with_label_values(&[&peer_id.to_string()])
+            .inc_by(headers.len() as u64);
+        let Some((&start, requested_at)) =
+            self.ranges.iter().find_map(|(start, range)| match range {
+                HeaderRange::Requested { peer_id: id, requested_at } if id == peer_id => {
+                    Some((start, *requested_at))
+                }
+                _ => None,
+            })
+        else {
+            return ReceivedHeaders::NotRequested(headers);
+        };
+        if headers.first().is_some_and(|header| header.height() < start) {
+            // A response to a batch requested with a locator, possibly from the time when this
+            // peer was the syncing peer. These always start below the range.
+            return ReceivedHeaders::NotRequested(headers);
+        }
+        if headers.is_empty() {
+            // The peer claimed to have the range, but its height may be out of date. Don't ask
+            // it for this range again, so that the range can be served by another peer.
+            debug!(target: "sync", ?peer_id, start, "Sync: received an empty header range");
+            metrics::HEADER_SYNC_RANGES.with_label_values(&[&peer_id.to_string(), "empty"]).inc();
+            self.ranges.remove(&start);
+            let empty_start = self.empty_ranges.entry(peer_id.clone()).or_insert(start);
+            *empty_start = min(*empty_start, start);
+            return ReceivedHeaders::Empty;
+        }
+        let end = range_end(start);
+        let valid = headers.len() as u64 <= MAX_BLOCK_HEADERS
+            && headers.last().map_or(true, |header| header.height() <= end)
+            && headers.windows(2).all(|pair| {
+                pair[0].height() < pair[1].height() && pair[1].prev_hash() == pair[0].hash()
+            });
+        if !valid {
+            warn!(target: "sync", ?peer_id, start, end, num_headers = headers.len(), "Sync: received invalid header range");
+            metrics::HEADER_SYNC_RANGES.with_label_values(&[&peer_id.to_string(), "invalid"]).inc();
+            self.ranges.remove(&start);
+            return ReceivedHeaders::Invalid;
+        }
+
+        let elapsed_ms = (StaticClock::utc() - requested_at).num_milliseconds().max(1);
+        metrics::HEADER_SYNC_PEER_HEADERS_PER_SECOND
+            .with_label_values(&[&peer_id.to_string()])
+            .set(headers.len() as i64 * 1000 / elapsed_ms);
+        metrics::HEADER_SYNC_RANGES.with_label_values(&[&peer_id.to_string(), "ok"]).inc();
+        self.ranges.insert(start, HeaderRange::Downloaded { peer_id: peer_id.clone(), headers });
+        ReceivedHeaders::Stored
+    }
+
+    /// Takes the lowest downloaded range if its first header follows a header known to the chain.
+    /// Ranges that can no longer connect to the header head are dropped.
+    pub fn pop_connected_range(&mut self, chain: &Chain) -> Option<(PeerId, Vec<BlockHeader>)> {
+        let header_head_height = chain.header_head().ok()?.height;
+        self.drop_covered_ranges(header_head_height);
+        loop {
+            let entry = self.ranges.first_entry()?;
+            let HeaderRange::Downloaded { headers, .. } = entry.get() else {
+                return None;
+            };
+            // Empty ranges are never stored.
+            let first = &headers[0];
+            let connected = chain.get_block_header(first.prev_hash()).is_ok();
+            let stale = first.height() <= header_head_height;
+            if !connected && !stale {
+                return None;
+            }
+            let HeaderRange::Downloaded { peer_id, headers } = entry.remove() else {
+                unreachable!();
+            };
+            if connected {
+                return Some((peer_id, headers));
+            }
+            debug!(target: "sync", ?peer_id, "Sync: header range doesn't connect to the header head");
+            metrics::HEADER_SYNC_RANGES
+                .with_label_values(&[&peer_id.to_string(), "unlinked"])
+                .inc();
+        }
+    }
+
+    /// Handles a range of headers refused by the chain. Bans the peer that served it if the
+    /// headers are invalid.
+    pub fn reject_range(&self, peer_id: &PeerId, err: &unc_chain::Error) {
+        if err.is_bad_data() {
+            warn!(target: "sync", ?peer_id, ?err, "Sync: ban a peer for providing an invalid header range");
+            metrics::HEADER_SYNC_RANGES.with_label_values(&[&peer_id.to_string(), "invalid"]).inc();
+            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::BanPeer {
+                    peer_id: peer_id.clone(),
+                    ban_reason: ReasonForBan::BadBlockHeader,
+                },
+            ));
+        } else {
+            debug!(target: "sync", ?peer_id, ?err, "Sync: header range refused by chain");
+        }
+    }
+
     /// Returns the height that we expect to reach starting from `old_height` after `time_delta`.
     fn compute_expected_height(
         &self,
@@ -245,7 +533,7 @@ impl HeaderSync {
                                         PeerManagerMessageRequest::NetworkRequests(
                                             NetworkRequests::BanPeer {
                                                 peer_id: peer.peer_info.id.clone(),
-                                                ban_reason: unc_network::types::ReasonForBan::ProvidedNotEnoughHeaders,
+                                                ban_reason: ReasonForBan::ProvidedNotEnoughHeaders,
                                             },
                                         ),
                                     );
@@ -342,8 +630,12 @@ impl HeaderSync {
         let ordinals = get_locator_ordinals(final_head_ordinal, tip_ordinal);
         let mut locator: Vec<CryptoHash> = vec![];
         for ordinal in &ordinals {
-            let block_hash = store.get_block_hash_from_ordinal(*ordinal)?;
-            locator.push(block_hash);
+            // Headers skipped by Epoch Sync have no ordinals.
+            match store.get_block_hash_from_ordinal(*ordinal) {
+                Ok(block_hash) => locator.push(block_hash),
+                Err(unc_chain::Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err),
+            }
         }
         debug!(target: "sync", "Sync: locator: {:?} ordinals: {:?}", locator, ordinals);
         Ok(locator)
@@ -441,6 +733,7 @@ mod test {
             TimeDuration::from_secs(2),
             TimeDuration::from_secs(120),
             1_000_000_000,
+            0,
         );
         let (mut chain, _, _, signer) = setup();
         for _ in 0..3 {
@@ -527,6 +820,7 @@ mod test {
             TimeDuration::from_secs(2),
             TimeDuration::from_secs(120),
             1_000_000_000,
+            0,
         );
         let (mut chain, _, _, signer) = setup();
         let (mut chain2, _, _, signer2) = setup();
@@ -637,6 +931,7 @@ mod test {
             TimeDuration::from_secs(1),
             TimeDuration::from_secs(3),
             25,
+            0,
         );
 
         let set_syncing_peer = |header_sync: &mut HeaderSync| {
@@ -731,6 +1026,7 @@ mod test {
             TimeDuration::from_secs(2),
             TimeDuration::from_secs(120),
             1_000_000_000,
+            0,
         );
 
         let vs = ValidatorSchedule::new()
@@ -868,4 +1164,263 @@ mod test {
         let new_tip = chain.header_head().unwrap();
         assert_eq!(new_tip.last_block_hash, chain2.head().unwrap().last_block_hash);
     }
+
+    /// Sets up an empty chain and a chain with 20 blocks 100 heights apart, and three peers
+    /// at the head of the second chain.
+    fn setup_header_ranges() -> (Chain, Chain, Vec<HighestHeightPeerInfo>) {
+        let vs = ValidatorSchedule::new()
+            .block_producers_per_epoch(vec![vec!["test0".parse().unwrap()]]);
+        let genesis_time = StaticClock::utc();
+        let (chain, _, _, _) =
+            setup_with_validators_and_start_time(vs.clone(), 10000, 100, genesis_time);
+        let (mut chain2, _, _, signers2) =
+            setup_with_validators_and_start_time(vs, 10000, 100, genesis_time);
+        for _ in 0..20 {
+            let prev = chain2.get_block(&chain2.head().unwrap().last_block_hash).unwrap();
+            // Large gaps spread a few blocks over several header ranges.
+            let block = TestBlockBuilder::new(&prev, signers2[0].clone())
+                .height(prev.header().height() + 100)
+                .build();
+            process_block_sync(
+                &mut chain2,
+                &None,
+                block.into(),
+                Provenance::PRODUCED,
+                &mut BlockProcessingArtifact::default(),
+            )
+            .unwrap();
+        }
+        let peers = (0..3)
+            .map(|_| {
+                let peer = FullPeerInfo {
+                    peer_info: PeerInfo::random(),
+                    chain_info: unc_network::types::PeerChainInfo {
+                        genesis_id: GenesisId {
+                            chain_id: "unittest".to_string(),
+                            hash: *chain.genesis().hash(),
+                        },
+                        tracked_shards: vec![],
+                        archival: false,
+                        last_block: Some(BlockInfo {
+                            height: chain2.head().unwrap().height,
+                            hash: chain2.head().unwrap().last_block_hash,
+                        }),
+                    },
+                };
+                <FullPeerInfo as Into<Option<_>>>::into(peer).unwrap()
+            })
+            .collect();
+        (chain, chain2, peers)
+    }
+
+    /// Splits the requests sent by `HeaderSync` into the batch and the header ranges.
+    fn pop_header_requests(
+        mock_adapter: &MockPeerManagerAdapter,
+    ) -> (Option<(Vec<CryptoHash>, PeerId)>, Vec<(BlockHeight, BlockHeight, PeerId)>) {
+        let mut batch = None;
+        let mut ranges = vec![];
+        while let Some(request) = mock_adapter.pop() {
+            match request.as_network_requests() {
+                NetworkRequests::BlockHeadersRequest { hashes, peer_id } => {
+                    batch = Some((hashes, peer_id))
+                }
+                NetworkRequests::BlockHeadersRangeRequest { start_height, end_height, peer_id } => {
+                    ranges.push((start_height, end_height, peer_id))
+                }
+                request => panic!("Unexpected network request: {:?}", request),
+            }
+        }
+        (batch, ranges)
+    }
+
+    fn process_blocks_from(chain: &mut Chain, chain2: &Chain, headers: &[BlockHeader]) {
+        for header in headers {
+            process_block_sync(
+                chain,
+                &None,
+                chain2.get_block(header.hash()).unwrap().into(),
+                Provenance::PRODUCED,
+                &mut BlockProcessingArtifact::default(),
+            )
+            .unwrap();
+        }
+    }
+
+    /// Checks that ranges above the batch are requested from the other peers, and that they are
+    /// handed out in order once the batch connects them to the chain.
+    #[test]
+    fn test_sync_header_ranges() {
+        let mock_adapter = Arc::new(MockPeerManagerAdapter::default());
+        let mut header_sync = HeaderSync::new(
+            mock_adapter.clone().into(),
+            TimeDuration::from_secs(10),
+            TimeDuration::from_secs(2),
+            TimeDuration::from_secs(120),
+            1_000_000_000,
+            8,
+        );
+        let (mut chain, chain2, peers) = setup_header_ranges();
+        let highest_height = chain2.head().unwrap().height;
+        let mut sync_status = SyncStatus::NoSync;
+        header_sync.run(&mut sync_status, &chain, highest_height, &peers).unwrap();
+
+        let (batch, ranges) = pop_header_requests(&mock_adapter);
+        let (hashes, batch_peer_id) = batch.unwrap();
+        assert_eq!(
+            ranges.iter().map(|(start, end, _)| (*start, *end)).collect::<Vec<_>>(),
+            vec![(1024, 1535), (1536, 2047)]
+        );
+        let range_peer_ids: HashSet<_> =
+            ranges.iter().map(|(_, _, peer_id)| peer_id.clone()).collect();
+        assert_eq!(range_peer_ids.len(), 2);
+        assert!(!range_peer_ids.contains(&batch_peer_id));
+
+        for (start_height, end_height, peer_id) in &ranges {
+            let headers = chain2
+                .retrieve_headers_in_range(*start_height, *end_height, MAX_BLOCK_HEADERS)
+                .unwrap();
+            assert!(matches!(
+                header_sync.receive_headers(peer_id, headers),
+                ReceivedHeaders::Stored
+            ));
+        }
+        // The ranges don't connect to the chain yet.
+        assert!(header_sync.pop_connected_range(&chain).is_none());
+
+        // Let the batch stop right below the first range.
+        let headers = chain2.retrieve_headers(hashes, MAX_BLOCK_HEADERS, None).unwrap();
+        let headers: Vec<_> = headers.into_iter().filter(|header| header.height() < 1024).collect();
+        process_blocks_from(&mut chain, &chain2, &headers);
+        for (_, _, peer_id) in &ranges {
+            let (range_peer_id, headers) = header_sync.pop_connected_range(&chain).unwrap();
+            assert_eq!(&range_peer_id, peer_id);
+            process_blocks_from(&mut chain, &chain2, &headers);
+        }
+        assert!(header_sync.pop_connected_range(&chain).is_none());
+        assert_eq!(
+            chain.header_head().unwrap().last_block_hash,
+            chain2.head().unwrap().last_block_hash
+        );
+    }
+
+    /// Checks that the next batch isn't requested from the peers with a range request in flight,
+    /// so that their responses are matched to the right request.
+    #[test]
+    fn test_sync_batch_skips_range_peers() {
+        let mock_adapter = Arc::new(MockPeerManagerAdapter::default());
+        let mut header_sync = HeaderSync::new(
+            mock_adapter.clone().into(),
+            TimeDuration::from_secs(10),
+            TimeDuration::from_secs(2),
+            TimeDuration::from_secs(120),
+            1_000_000_000,
+            8,
+        );
+        let (chain, chain2, peers) = setup_header_ranges();
+        let highest_height = chain2.head().unwrap().height;
+        header_sync.run(&mut SyncStatus::NoSync, &chain, highest_height, &peers).unwrap();
+        let (batch, ranges) = pop_header_requests(&mock_adapter);
+        let (_, batch_peer_id) = batch.unwrap();
+        assert_eq!(ranges.len(), 2);
+
+        // Force new batches while the ranges are still in flight.
+        for _ in 0..10 {
+            header_sync.run(&mut SyncStatus::NoSync, &chain, highest_height, &peers).unwrap();
+            let (batch, new_ranges) = pop_header_requests(&mock_adapter);
+            assert_eq!(batch.unwrap().1, batch_peer_id);
+            assert!(new_ranges.is_empty());
+        }
+    }
+
+    /// Checks that a range isn't requested again from a peer which had no headers in it.
+    #[test]
+    fn test_empty_header_ranges() {
+        let mock_adapter = Arc::new(MockPeerManagerAdapter::default());
+        let mut header_sync = HeaderSync::new(
+            mock_adapter.clone().into(),
+            TimeDuration::from_secs(10),
+            TimeDuration::from_secs(2),
+            TimeDuration::from_secs(120),
+            1_000_000_000,
+            8,
+        );
+        let (chain, chain2, peers) = setup_header_ranges();
+        let highest_height = chain2.head().unwrap().height;
+        let mut sync_status = SyncStatus::NoSync;
+        header_sync.run(&mut sync_status, &chain, highest_height, &peers).unwrap();
+        let (_, ranges) = pop_header_requests(&mock_adapter);
+        assert_eq!(ranges.len(), 2);
+
+        let (_, _, peer_id) = &ranges[0];
+        assert!(matches!(header_sync.receive_headers(peer_id, vec![]), ReceivedHeaders::Empty));
+        // The other peers are busy, and the peer which had no headers isn't asked again.
+        header_sync.run(&mut sync_status, &chain, highest_height, &peers).unwrap();
+        let (batch, new_ranges) = pop_header_requests(&mock_adapter);
+        assert!(batch.is_none());
+        assert!(new_ranges.is_empty());
+
+        // Once the range is free, it is requested from another peer.
+        let (start_height, end_height, other_peer_id) = &ranges[1];
+        let headers = chain2
+            .retrieve_headers_in_range(*start_height, *end_height, MAX_BLOCK_HEADERS)
+            .unwrap();
+        assert!(matches!(
+            header_sync.receive_headers(other_peer_id, headers),
+            ReceivedHeaders::Stored
+        ));
+        header_sync.run(&mut sync_status, &chain, highest_height, &peers).unwrap();
+        let (_, new_ranges) = pop_header_requests(&mock_adapter);
+        assert_eq!(new_ranges, vec![(ranges[0].0, ranges[0].1, other_peer_id.clone())]);
+    }
+
+    /// Checks that headers that don't form the requested range are rejected, and that the range
+    /// is requested again.
+    #[test]
+    fn test_invalid_header_ranges() {
+        let mock_adapter = Arc::new(MockPeerManagerAdapter::default());
+        let mut header_sync = HeaderSync::new(
+            mock_adapter.clone().into(),
+            TimeDuration::from_secs(10),
+            TimeDuration::from_secs(2),
+            TimeDuration::from_secs(120),
+            1_000_000_000,
+            8,
+        );
+        let (chain, chain2, peers) = setup_header_ranges();
+        let highest_height = chain2.head().unwrap().height;
+        let mut sync_status = SyncStatus::NoSync;
+        header_sync.run(&mut sync_status, &chain, highest_height, &peers).unwrap();
+        let (_, ranges) = pop_header_requests(&mock_adapter);
+        assert_eq!(ranges.len(), 2);
+
+        // Headers beyond the end of the range.
+        let (start_height, _, peer_id) = &ranges[0];
+        let headers =
+            chain2.retrieve_headers_in_range(*start_height, highest_height, u64::MAX).unwrap();
+        assert!(matches!(header_sync.receive_headers(peer_id, headers), ReceivedHeaders::Invalid));
+
+        // Headers that don't link to each other.
+        let (start_height, end_height, peer_id) = &ranges[1];
+        let mut headers = chain2
+            .retrieve_headers_in_range(*start_height, *end_height, MAX_BLOCK_HEADERS)
+            .unwrap();
+        headers.remove(1);
+        assert!(matches!(header_sync.receive_headers(peer_id, headers), ReceivedHeaders::Invalid));
+
+        // Headers from a peer without a range request are left to the chain to validate.
+        let headers = chain2.retrieve_headers_in_range(1, highest_height, u64::MAX).unwrap();
+        assert!(matches!(
+            header_sync.receive_headers(&PeerId::random(), headers),
+            ReceivedHeaders::NotRequested(_)
+        ));
+
+        // Both ranges are free to be requested again.
+        header_sync.run(&mut sync_status, &chain, highest_height, &peers).unwrap();
+        let (batch, ranges) = pop_header_requests(&mock_adapter);
+        assert!(batch.is_none());
+        assert_eq!(
+            ranges.iter().map(|(start, end, _)| (*start, *end)).collect::<Vec<_>>(),
+            vec![(1024, 1535), (1536, 2047)]
+        );
+    }
 }
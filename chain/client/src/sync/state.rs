@@ -12,7 +12,12 @@
 //! For downloading - the code is picking the potential target nodes (all direct peers that are tracking the shard
 //! (and are high enough) + validators from that epoch that were tracking the shard)
 //! Then for each part that we're missing, we're 'randomly' picking a target from whom we'll request it - but we make
-//! sure to not request more than MAX_STATE_PART_REQUESTS from each.
+//! sure to not request more than a batch of parts from each. The size of the batch starts at MAX_STATE_PART_REQUEST
+//! and adapts to the peer: it grows when the peer answers the whole batch and shrinks when the requests time out.
+//! If external storage is configured, the parts are downloaded from it and, optionally, from the peers at the same time.
+//!
+//! Downloaded parts are stored (only after being validated) and survive a restart of the node: when the node resumes
+//! state sync to the same sync hash, it only requests the parts it doesn't have yet.
 //!
 //! WARNING: with the current design, we're putting quite a load on the validators - as we request a lot of data from
 //!         them (if you assume that we have 100 validators and 30 peers - we send 100/130 of requests to validators).
@@ -22,7 +27,8 @@
 
 use crate::metrics;
 use crate::sync::external::{
-    create_bucket_readonly, external_storage_location, ExternalConnection,
+    create_bucket_readonly, external_storage_location, external_storage_location_directory,
+    ExternalConnection, StatePartsManifest, STATE_PARTS_MIRROR_PATH,
 };
 use actix_rt::ArbiterHandle;
 use chrono::{DateTime, Duration, Utc};
@@ -61,14 +67,27 @@ use unc_primitives::static_clock::StaticClock;
 use unc_primitives::types::{AccountId, EpochHeight, EpochId, ShardId, StateRoot};
 use unc_store::DBCol;
 
-/// Maximum number of state parts to request per peer on each round when node is trying to download the state.
+/// Initial number of state parts to request per peer on each round when node is trying to download the state.
 pub const MAX_STATE_PART_REQUEST: u64 = 16;
+/// Maximum number of state parts to request per peer on each round, for peers which keep up with the requests.
+pub const MAX_ADAPTIVE_STATE_PART_REQUEST: u64 = 128;
+/// Number of parts added to the batch of a peer which answered all the parts of its previous batch.
+const STATE_PART_REQUEST_STEP: u64 = 4;
+/// Number of peers for which the size of the batch of state part requests is remembered.
+const MAX_TRACKED_PEERS: usize = 1024;
 /// Number of state parts already requested stored as pending.
 /// This number should not exceed MAX_STATE_PART_REQUEST times (number of peers in the network).
 pub const MAX_PENDING_PART: u64 = MAX_STATE_PART_REQUEST * 10000;
 /// Time limit per state dump iteration.
 /// A node must check external storage for parts to dump again once time is up.
 pub const STATE_DUMP_ITERATION_TIME_LIMIT_SECS: u64 = 300;
+/// Number of manifests of state parts cached when syncing from external storage.
+/// One manifest per tracked shard is in use at a time.
+const MAX_CACHED_MANIFESTS: usize = 64;
+
+/// Manifests of state parts in external storage, by directory. `None` if the directory has no
+/// manifest and the storage doesn't require one.
+type ManifestCache = std::sync::Mutex<lru::LruCache<String, Option<Arc<StatePartsManifest>>>>;
 
 pub enum StateSyncResult {
     /// State sync still in progress. No action needed by the caller.
@@ -103,31 +122,79 @@ pub struct StateSyncGetPartResult {
     part_result: Result<u64, String>,
 }
 
-/// How to retrieve the state data.
-enum StateSyncInner {
-    /// Request both the state header and state parts from the peers.
-    Peers {
-        /// Which parts were requested from which peer and when.
-        last_part_id_requested: HashMap<(PeerId, ShardId), PendingRequestStatus>,
-        /// Map from which part we requested to whom.
-        requested_target: lru::LruCache<(u64, CryptoHash), PeerId>,
-    },
-    /// Requests the state header from peers but gets the state parts from an
-    /// external storage.
-    PartsFromExternal {
-        /// Chain ID.
-        chain_id: String,
-        /// This semaphore imposes a restriction on the maximum number of simultaneous downloads
-        semaphore: Arc<tokio::sync::Semaphore>,
-        /// Connection to the external storage.
-        external: ExternalConnection,
-    },
+/// Tracks the state parts requested from the peers.
+struct PeerRequests {
+    /// Which parts were requested from which peer and when.
+    last_part_id_requested: HashMap<(PeerId, ShardId), PendingRequestStatus>,
+    /// Map from which part we requested to whom.
+    requested_target: lru::LruCache<(u64, CryptoHash), PeerId>,
+    /// Number of parts to request from a peer in one batch.
+    /// Peers which aren't in the cache get MAX_STATE_PART_REQUEST parts.
+    batch_size: lru::LruCache<PeerId, u64>,
+}
+
+impl PeerRequests {
+    fn new() -> Self {
+        Self {
+            last_part_id_requested: Default::default(),
+            requested_target: lru::LruCache::new(MAX_PENDING_PART as usize),
+            batch_size: lru::LruCache::new(MAX_TRACKED_PEERS),
+        }
+    }
+
+    fn batch_size(&mut self, peer_id: &PeerId) -> u64 {
+        self.batch_size.get(peer_id).copied().unwrap_or(MAX_STATE_PART_REQUEST)
+    }
+
+    /// The peer answered all the parts of its batch, request more parts next time.
+    fn on_batch_done(&mut self, peer_id: &PeerId) {
+        let batch_size = self.batch_size(peer_id);
+        self.batch_size.put(
+            peer_id.clone(),
+            (batch_size + STATE_PART_REQUEST_STEP).min(MAX_ADAPTIVE_STATE_PART_REQUEST),
+        );
+    }
+
+    /// Removes the expired batches and requests fewer parts from their peers next time.
+    fn remove_expired(&mut self) {
+        let mut expired = vec![];
+        self.last_part_id_requested.retain(|(peer_id, _), request| {
+            if request.expired() {
+                expired.push(peer_id.clone());
+                false
+            } else {
+                true
+            }
+        });
+        for peer_id in expired {
+            let batch_size = self.batch_size(&peer_id);
+            self.batch_size.put(peer_id, (batch_size / 2).max(1));
+        }
+    }
+}
+
+/// External storage from which the state parts are downloaded.
+struct ExternalStorage {
+    /// Chain ID.
+    chain_id: String,
+    /// This semaphore imposes a restriction on the maximum number of simultaneous downloads
+    semaphore: Arc<tokio::sync::Semaphore>,
+    /// Connection to the external storage.
+    external: ExternalConnection,
+    /// Manifests of the recently downloaded state parts, by directory.
+    manifests: Arc<ManifestCache>,
+    /// Whether to also request the state parts from the peers.
+    request_parts_from_peers: bool,
 }
 
 /// Helper to track state sync.
 pub struct StateSync {
-    /// How to retrieve the state data.
-    inner: StateSyncInner,
+    /// State header and state part requests sent to the peers.
+    /// The state header is always requested from the peers.
+    peers: PeerRequests,
+
+    /// If set, the state parts are downloaded from an external storage.
+    external: Option<ExternalStorage>,
 
     /// Is used for communication with the peers.
     network_adapter: PeerManagerAdapter,
@@ -156,15 +223,13 @@ impl StateSync {
         sync_config: &SyncConfig,
         catchup: bool,
     ) -> Self {
-        let inner = match sync_config {
-            SyncConfig::Peers => StateSyncInner::Peers {
-                last_part_id_requested: Default::default(),
-                requested_target: lru::LruCache::new(MAX_PENDING_PART as usize),
-            },
+        let external = match sync_config {
+            SyncConfig::Peers => None,
             SyncConfig::ExternalStorage(ExternalStorageConfig {
                 location,
                 num_concurrent_requests,
                 num_concurrent_requests_during_catchup,
+                request_parts_from_peers,
             }) => {
                 let external = match location {
                     ExternalStorageLocation::S3 { bucket, region, .. } => {
@@ -182,23 +247,40 @@ impl StateSync {
                         reqwest_client: Arc::new(reqwest::Client::default()),
                         bucket: bucket.clone(),
                     },
+                    ExternalStorageLocation::Http { url } => ExternalConnection::Http {
+                        reqwest_client: Arc::new(reqwest::Client::default()),
+                        base_url: url.trim_end_matches('/').to_string(),
+                    },
+                    ExternalStorageLocation::Mirror { rpc_url } => ExternalConnection::Http {
+                        reqwest_client: Arc::new(reqwest::Client::default()),
+                        base_url: format!(
+                            "{}/{}",
+                            rpc_url.trim_end_matches('/'),
+                            STATE_PARTS_MIRROR_PATH
+                        ),
+                    },
                 };
                 let num_permits = if catchup {
                     *num_concurrent_requests_during_catchup
                 } else {
                     *num_concurrent_requests
                 } as usize;
-                StateSyncInner::PartsFromExternal {
+                Some(ExternalStorage {
                     chain_id: chain_id.to_string(),
                     semaphore: Arc::new(tokio::sync::Semaphore::new(num_permits)),
                     external,
-                }
+                    manifests: Arc::new(std::sync::Mutex::new(lru::LruCache::new(
+                        MAX_CACHED_MANIFESTS,
+                    ))),
+                    request_parts_from_peers: *request_parts_from_peers,
+                })
             }
         };
         let timeout = Duration::from_std(timeout).unwrap();
         let (tx, rx) = channel::<StateSyncGetPartResult>();
         StateSync {
-            inner,
+            peers: PeerRequests::new(),
+            external,
             network_adapter,
             timeout,
             state_parts_apply_results: HashMap::new(),
@@ -444,50 +526,41 @@ impl StateSync {
         shard_id: ShardId,
         sync_hash: CryptoHash,
     ) {
-        match &mut self.inner {
-            StateSyncInner::Peers { last_part_id_requested, requested_target } => {
-                let key = (part_id, sync_hash);
-                // Check that it came from the target that we requested it from.
-                if let Some(target) = requested_target.get(&key) {
-                    if last_part_id_requested.get_mut(&(target.clone(), shard_id)).map_or(
-                        false,
-                        |request| {
-                            request.missing_parts = request.missing_parts.saturating_sub(1);
-                            request.missing_parts == 0
-                        },
-                    ) {
-                        last_part_id_requested.remove(&(target.clone(), shard_id));
-                    }
-                }
-            }
-            StateSyncInner::PartsFromExternal { .. } => {
-                // Do nothing.
+        let key = (part_id, sync_hash);
+        // Check that it came from the target that we requested it from.
+        if let Some(target) = self.peers.requested_target.get(&key).cloned() {
+            if self.peers.last_part_id_requested.get_mut(&(target.clone(), shard_id)).map_or(
+                false,
+                |request| {
+                    request.missing_parts = request.missing_parts.saturating_sub(1);
+                    request.missing_parts == 0
+                },
+            ) {
+                self.peers.last_part_id_requested.remove(&(target.clone(), shard_id));
+                self.peers.on_batch_done(&target);
             }
         }
     }
 
     /// Avoids peers that already have outstanding requests for parts.
+    /// Returns the peers with the number of parts to request from each of them.
     fn select_peers(
         &mut self,
         highest_height_peers: &[HighestHeightPeerInfo],
         shard_id: ShardId,
-    ) -> Result<Vec<PeerId>, unc_chain::Error> {
-        let peers: Vec<PeerId> =
-            highest_height_peers.iter().map(|peer| peer.peer_info.id.clone()).collect();
-        let res = match &mut self.inner {
-            StateSyncInner::Peers { last_part_id_requested, .. } => {
-                last_part_id_requested.retain(|_, request| !request.expired());
-                peers
-                    .into_iter()
-                    .filter(|peer| {
-                        // If we still have a pending request from this node - don't add another one.
-                        !last_part_id_requested.contains_key(&(peer.clone(), shard_id))
-                    })
-                    .collect::<Vec<_>>()
+    ) -> Vec<(PeerId, u64)> {
+        self.peers.remove_expired();
+        let mut res = vec![];
+        for peer in highest_height_peers {
+            let peer_id = peer.peer_info.id.clone();
+            // If we still have a pending request from this node - don't add another one.
+            if self.peers.last_part_id_requested.contains_key(&(peer_id.clone(), shard_id)) {
+                continue;
             }
-            StateSyncInner::PartsFromExternal { .. } => peers,
-        };
-        Ok(res)
+            let batch_size = self.peers.batch_size(&peer_id);
+            res.push((peer_id, batch_size));
+        }
+        res
     }
 
     /// Returns new ShardSyncDownload if successful, otherwise returns given shard_sync_download
@@ -501,17 +574,15 @@ impl StateSync {
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         state_parts_arbiter_handle: &ArbiterHandle,
     ) -> Result<(), unc_chain::Error> {
-        let possible_targets = self.select_peers(highest_height_peers, shard_id)?;
-
-        if possible_targets.is_empty() {
-            tracing::debug!(target: "sync", "Can't request a state header: No possible targets");
-            // In most cases it means that all the targets are currently busy (that we have a pending request with them).
-            return Ok(());
-        }
-
         // Downloading strategy starts here
         match shard_sync_download.status {
             ShardSyncStatus::StateDownloadHeader => {
+                let possible_targets: Vec<PeerId> =
+                    highest_height_peers.iter().map(|peer| peer.peer_info.id.clone()).collect();
+                if possible_targets.is_empty() {
+                    tracing::debug!(target: "sync", "Can't request a state header: No possible targets");
+                    return Ok(());
+                }
                 self.request_shard_header(
                     shard_id,
                     sync_hash,
@@ -520,6 +591,12 @@ impl StateSync {
                 );
             }
             ShardSyncStatus::StateDownloadParts => {
+                let possible_targets = self.select_peers(highest_height_peers, shard_id);
+                if possible_targets.is_empty() && self.external.is_none() {
+                    tracing::debug!(target: "sync", "Can't request state parts: No possible targets");
+                    // In most cases it means that all the targets are currently busy (that we have a pending request with them).
+                    return Ok(());
+                }
                 self.request_shard_parts(
                     shard_id,
                     sync_hash,
@@ -570,11 +647,13 @@ impl StateSync {
     }
 
     /// Makes requests to download state parts for the given epoch of the given shard.
+    /// Parts are first scheduled for download from the external storage, as
+    /// long as it has free download slots, and the rest are requested from the peers.
     fn request_shard_parts(
         &mut self,
         shard_id: ShardId,
         sync_hash: CryptoHash,
-        possible_targets: Vec<PeerId>,
+        possible_targets: Vec<(PeerId, u64)>,
         new_shard_sync_download: &mut ShardSyncDownload,
         chain: &Chain,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
@@ -582,73 +661,74 @@ impl StateSync {
     ) {
         // Iterate over all parts that needs to be requested (i.e. download.run_me is true).
         // Parts are ordered such that its index match its part_id.
-        match &mut self.inner {
-            StateSyncInner::Peers { last_part_id_requested, requested_target } => {
-                // We'll select all the 'highest' peers + validators as candidates (excluding those that gave us timeout in the past).
-                // And for each one of them, we'll ask for up to 16 (MAX_STATE_PART_REQUEST) parts.
-                let possible_targets_sampler =
-                    SamplerLimited::new(possible_targets, MAX_STATE_PART_REQUEST);
-
-                // For every part that needs to be requested it is selected one
-                // peer (target) randomly to request the part from.
-                // IMPORTANT: here we use 'zip' with possible_target_sampler -
-                // which is limited. So at any moment we'll not request more
-                // than possible_targets.len() * MAX_STATE_PART_REQUEST parts.
-                for ((part_id, download), target) in
-                    parts_to_fetch(new_shard_sync_download).zip(possible_targets_sampler)
-                {
-                    sent_request_part(
-                        target.clone(),
-                        part_id,
-                        shard_id,
-                        sync_hash,
-                        last_part_id_requested,
-                        requested_target,
-                        self.timeout,
-                    );
-                    request_part_from_peers(
-                        part_id,
-                        target,
-                        download,
-                        shard_id,
-                        sync_hash,
-                        &self.network_adapter,
-                    );
-                }
-            }
-            StateSyncInner::PartsFromExternal { chain_id, semaphore, external } => {
-                let sync_block_header = chain.get_block_header(&sync_hash).unwrap();
-                let epoch_id = sync_block_header.epoch_id();
-                let epoch_info = chain.epoch_manager.get_epoch_info(epoch_id).unwrap();
-                let epoch_height = epoch_info.epoch_height();
-
-                let shard_state_header = chain.get_state_header(shard_id, sync_hash).unwrap();
-                let state_root = shard_state_header.chunk_prev_state_root();
-                let state_num_parts = shard_state_header.num_state_parts();
-
-                for (part_id, download) in parts_to_fetch(new_shard_sync_download) {
-                    request_part_from_external_storage(
-                        part_id,
-                        download,
-                        shard_id,
-                        sync_hash,
-                        epoch_id,
-                        epoch_height,
-                        state_num_parts,
-                        &chain_id.clone(),
-                        state_root,
-                        semaphore.clone(),
-                        external.clone(),
-                        runtime_adapter.clone(),
-                        state_parts_arbiter_handle,
-                        self.state_parts_mpsc_tx.clone(),
-                    );
-                    if semaphore.available_permits() == 0 {
-                        break;
-                    }
-                }
+        let mut parts = parts_to_fetch(new_shard_sync_download);
+        if let Some(ExternalStorage { chain_id, semaphore, external, manifests, .. }) =
+            &self.external
+        {
+            let sync_block_header = chain.get_block_header(&sync_hash).unwrap();
+            let epoch_id = sync_block_header.epoch_id();
+            let epoch_info = chain.epoch_manager.get_epoch_info(epoch_id).unwrap();
+            let epoch_height = epoch_info.epoch_height();
+
+            let shard_state_header = chain.get_state_header(shard_id, sync_hash).unwrap();
+            let state_root = shard_state_header.chunk_prev_state_root();
+            let state_num_parts = shard_state_header.num_state_parts();
+
+            while semaphore.available_permits() > 0 {
+                let Some((part_id, download)) = parts.next() else {
+                    break;
+                };
+                request_part_from_external_storage(
+                    part_id,
+                    download,
+                    shard_id,
+                    sync_hash,
+                    epoch_id,
+                    epoch_height,
+                    state_num_parts,
+                    chain_id,
+                    state_root,
+                    semaphore.clone(),
+                    external.clone(),
+                    manifests.clone(),
+                    runtime_adapter.clone(),
+                    state_parts_arbiter_handle,
+                    self.state_parts_mpsc_tx.clone(),
+                );
             }
         }
+        if self.external.as_ref().map_or(false, |external| !external.request_parts_from_peers) {
+            return;
+        }
+
+        // We'll select all the 'highest' peers + validators as candidates (excluding those that gave us timeout in the past).
+        // And for each one of them, we'll ask for a batch of parts, which size depends on how the peer handled the previous batches.
+        let possible_targets_sampler = SamplerLimited::new(possible_targets);
+
+        // For every part that needs to be requested it is selected one
+        // peer (target) randomly to request the part from.
+        // IMPORTANT: here we use 'zip' with possible_target_sampler -
+        // which is limited. So at any moment we'll not request more
+        // than the sum of the batch sizes of the peers.
+        for ((part_id, download), target) in parts.zip(possible_targets_sampler) {
+            sent_request_part(
+                target.clone(),
+                part_id,
+                shard_id,
+                sync_hash,
+                &mut self.peers.last_part_id_requested,
+                &mut self.peers.requested_target,
+                self.timeout,
+            );
+            request_part_from_peers(
+                part_id,
+                target,
+                download,
+                shard_id,
+                sync_hash,
+                &self.network_adapter,
+            );
+        }
     }
 
     /// The main 'step' function that should be called periodically to check and update the sync process.
@@ -788,6 +868,13 @@ impl StateSync {
         now: DateTime<Utc>,
     ) -> Result<(bool, bool), unc_chain::Error> {
         let download = &mut shard_sync_download.downloads[0];
+        // The header may have been downloaded before the node restarted.
+        // It is removed together with the parts if the state can't be
+        // finalized, so that the next attempt starts from the very beginning.
+        if !download.done && chain.get_state_header(shard_id, sync_hash).is_ok() {
+            tracing::debug!(target: "sync", %shard_id, %sync_hash, "state header is already known");
+            download.done = true;
+        }
         // StateDownloadHeader is the first step. We want to fetch the basic information about the state (its size, hash etc).
         if download.done {
             let shard_state_header = chain.get_state_header(shard_id, sync_hash)?;
@@ -796,6 +883,24 @@ impl StateSync {
             // Create the vector with entry for each part.
             *shard_sync_download =
                 ShardSyncDownload::new_download_state_parts(now, state_num_parts);
+            // Parts are stored only after they are validated, so the stored
+            // parts don't need to be downloaded again.
+            let cached_parts =
+                chain.get_cached_state_parts(sync_hash, shard_id, state_num_parts)?;
+            let mut num_parts_resumed = 0;
+            for (part_id, download) in shard_sync_download.downloads.iter_mut().enumerate() {
+                if cached_parts.contains(part_id as u64) {
+                    download.done = true;
+                    download.run_me.store(false, Ordering::SeqCst);
+                    num_parts_resumed += 1;
+                }
+            }
+            if num_parts_resumed > 0 {
+                tracing::info!(target: "sync", %shard_id, %sync_hash, num_parts_resumed, state_num_parts, "resuming state sync from the stored parts");
+                metrics::STATE_SYNC_RESUMED_PARTS
+                    .with_label_values(&[&shard_id.to_string()])
+                    .inc_by(num_parts_resumed);
+            }
             Ok((false, true))
         } else {
             let download_timeout = now - download.prev_update_time > self.timeout;
@@ -1044,6 +1149,7 @@ fn request_part_from_external_storage(
     state_root: StateRoot,
     semaphore: Arc<Semaphore>,
     external: ExternalConnection,
+    manifests: Arc<ManifestCache>,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
     state_parts_arbiter_handle: &ArbiterHandle,
     state_parts_mpsc_tx: Sender<StateSyncGetPartResult>,
@@ -1057,12 +1163,25 @@ fn request_part_from_external_storage(
 
     let location =
         external_storage_location(chain_id, epoch_id, epoch_height, shard_id, part_id, num_parts);
+    let directory_path =
+        external_storage_location_directory(chain_id, epoch_id, epoch_height, shard_id);
 
     match semaphore.try_acquire_owned() {
         Ok(permit) => {
             if state_parts_arbiter_handle.spawn({
                 async move {
-                    let result = external.get_part(shard_id, &location).await;
+                    let manifest = get_manifest(&external, &manifests, shard_id, directory_path);
+                    let result = match manifest.await {
+                        Ok(manifest) => external.get_part(shard_id, &location).await.and_then(
+                            |data| match manifest {
+                                Some(manifest) => {
+                                    manifest.verify_part(part_id, num_parts, &data).map(|_| data)
+                                }
+                                None => Ok(data),
+                            },
+                        ),
+                        Err(err) => Err(err),
+                    };
                     let part_id = PartId{ idx: part_id, total: num_parts };
                     let part_result = match result {
                         Ok(data) => {
@@ -1108,6 +1227,34 @@ fn request_part_from_external_storage(
     }
 }
 
+/// Returns the manifest of the state parts in `directory_path`, downloading it if not cached.
+/// The manifest is published once all parts are dumped. Storages that require a manifest aren't
+/// downloaded from before that, the others return `None` if there is no manifest.
+async fn get_manifest(
+    external: &ExternalConnection,
+    manifests: &ManifestCache,
+    shard_id: ShardId,
+    directory_path: String,
+) -> Result<Option<Arc<StatePartsManifest>>, anyhow::Error> {
+    // The lock isn't held over the download to not block the downloads of other parts.
+    if let Some(manifest) = manifests.lock().unwrap().get(&directory_path) {
+        return Ok(manifest.clone());
+    }
+    let manifest = match external.get_manifest(shard_id, &directory_path).await {
+        Ok(manifest) => {
+            tracing::debug!(target: "sync", %shard_id, directory_path, num_parts = manifest.num_parts, "Downloaded state parts manifest");
+            Some(Arc::new(manifest))
+        }
+        Err(err) if !external.requires_manifest() => {
+            tracing::debug!(target: "sync", %shard_id, directory_path, ?err, "No state parts manifest, parts are not checked against it");
+            None
+        }
+        Err(err) => return Err(err),
+    };
+    manifests.lock().unwrap().put(directory_path, manifest.clone());
+    Ok(manifest)
+}
+
 /// Asynchronously requests a state part from a suitable peer.
 fn request_part_from_peers(
     part_id: u64,
@@ -1194,24 +1341,24 @@ fn process_part_response(
 }
 
 /// Create an abstract collection of elements to be shuffled.
-/// Each element will appear in the shuffled output exactly `limit` times.
+/// Each element will appear in the shuffled output exactly as many times as its limit.
 /// Use it as an iterator to access the shuffled collection.
 ///
 /// ```rust,ignore
-/// let sampler = SamplerLimited::new(vec![1, 2, 3], 2);
+/// let sampler = SamplerLimited::new(vec![(1, 2), (2, 2), (3, 1)]);
 ///
 /// let res = sampler.collect::<Vec<_>>();
 ///
-/// assert!(res.len() == 6);
+/// assert!(res.len() == 5);
 /// assert!(res.iter().filter(|v| v == 1).count() == 2);
 /// assert!(res.iter().filter(|v| v == 2).count() == 2);
-/// assert!(res.iter().filter(|v| v == 3).count() == 2);
+/// assert!(res.iter().filter(|v| v == 3).count() == 1);
 /// ```
 ///
-/// Out of the 90 possible values of `res` in the code above on of them is:
+/// Out of the 30 possible values of `res` in the code above on of them is:
 ///
 /// ```
-/// vec![1, 2, 1, 3, 3, 2];
+/// vec![1, 2, 1, 3, 2];
 /// ```
 struct SamplerLimited<T> {
     data: Vec<T>,
@@ -1219,13 +1366,9 @@ struct SamplerLimited<T> {
 }
 
 impl<T> SamplerLimited<T> {
-    fn new(data: Vec<T>, limit: u64) -> Self {
-        if limit == 0 {
-            Self { data: vec![], limit: vec![] }
-        } else {
-            let len = data.len();
-            Self { data, limit: vec![limit; len] }
-        }
+    fn new(data: Vec<(T, u64)>) -> Self {
+        let (data, limit) = data.into_iter().filter(|(_, limit)| *limit > 0).unzip();
+        Self { data, limit }
     }
 }
 
@@ -1261,30 +1404,21 @@ mod test {
     use actix::System;
     use actix_rt::Arbiter;
     use unc_actix_test_utils::run_actix;
-    use unc_chain::test_utils;
+    use unc_chain::test_utils::{self, KeyValueRuntime, MockEpochManager};
+    use unc_chain::ChainStoreAccess;
     use unc_chain::{test_utils::process_block_sync, BlockProcessingArtifact, Provenance};
     use unc_crypto::SecretKey;
     use unc_epoch_manager::EpochManagerAdapter;
     use unc_network::test_utils::MockPeerManagerAdapter;
     use unc_network::types::PeerInfo;
     use unc_primitives::state_sync::{
-        CachedParts, ShardStateSyncResponseHeader, ShardStateSyncResponseV3,
+        CachedParts, ShardStateSyncResponseHeader, ShardStateSyncResponseV3, StateHeaderKey,
+        STATE_PART_MEMORY_LIMIT,
     };
     use unc_primitives::{test_utils::TestBlockBuilder, types::EpochId};
 
-    #[test]
-    // Start a new state sync - and check that it asks for a header.
-    fn test_ask_for_header() {
-        let mock_peer_manager = Arc::new(MockPeerManagerAdapter::default());
-        let mut state_sync = StateSync::new(
-            mock_peer_manager.clone().into(),
-            TimeDuration::from_secs(1),
-            "chain_id",
-            &SyncConfig::Peers,
-            false,
-        );
-        let mut new_shard_sync = HashMap::new();
-
+    /// Sets up a chain which has just started the second epoch.
+    fn setup_chain_to_sync() -> (Chain, Arc<MockEpochManager>, Arc<KeyValueRuntime>) {
         let (mut chain, kv, runtime, signer) = test_utils::setup();
 
         // TODO: lower the epoch length
@@ -1309,6 +1443,36 @@ mod test {
             )
             .unwrap();
         }
+        (chain, kv, runtime)
+    }
+
+    fn highest_height_peer(chain: &Chain) -> HighestHeightPeerInfo {
+        let secret_key = SecretKey::from_random(unc_crypto::KeyType::ED25519);
+        let peer_id = PeerId::new(secret_key.public_key());
+        HighestHeightPeerInfo {
+            peer_info: PeerInfo { id: peer_id, addr: None, account_id: None },
+            genesis_id: Default::default(),
+            highest_block_height: chain.epoch_length + 10,
+            highest_block_hash: Default::default(),
+            tracked_shards: vec![0],
+            archival: false,
+        }
+    }
+
+    #[test]
+    // Start a new state sync - and check that it asks for a header.
+    fn test_ask_for_header() {
+        let mock_peer_manager = Arc::new(MockPeerManagerAdapter::default());
+        let mut state_sync = StateSync::new(
+            mock_peer_manager.clone().into(),
+            TimeDuration::from_secs(1),
+            "chain_id",
+            &SyncConfig::Peers,
+            false,
+        );
+        let mut new_shard_sync = HashMap::new();
+
+        let (mut chain, kv, runtime) = setup_chain_to_sync();
 
         let request_hash = &chain.head().unwrap().last_block_hash;
         let state_sync_header = chain.get_state_response_header(0, *request_hash).unwrap();
@@ -1320,17 +1484,8 @@ mod test {
         let apply_parts_fn = move |_: ApplyStatePartsRequest| {};
         let resharding_fn = move |_: ReshardingRequest| {};
 
-        let secret_key = SecretKey::from_random(unc_crypto::KeyType::ED25519);
-        let public_key = secret_key.public_key();
-        let peer_id = PeerId::new(public_key);
-        let highest_height_peer_info = HighestHeightPeerInfo {
-            peer_info: PeerInfo { id: peer_id.clone(), addr: None, account_id: None },
-            genesis_id: Default::default(),
-            highest_block_height: chain.epoch_length + 10,
-            highest_block_hash: Default::default(),
-            tracked_shards: vec![0],
-            archival: false,
-        };
+        let highest_height_peer_info = highest_height_peer(&chain);
+        let peer_id = highest_height_peer_info.peer_info.id.clone();
 
         run_actix(async {
             state_sync
@@ -1403,4 +1558,188 @@ mod test {
             System::current().stop()
         });
     }
+
+    #[test]
+    // Restart state sync with some of the parts already stored - and check
+    // that only the missing parts are requested, until the state can't be
+    // finalized, after which it starts over from the header.
+    fn test_resume_from_stored_parts() {
+        let mock_peer_manager = Arc::new(MockPeerManagerAdapter::default());
+        let mut state_sync = StateSync::new(
+            mock_peer_manager.clone().into(),
+            TimeDuration::from_secs(1),
+            "chain_id",
+            &SyncConfig::Peers,
+            false,
+        );
+        let mut new_shard_sync = HashMap::new();
+
+        let (mut chain, kv, runtime) = setup_chain_to_sync();
+        let sync_hash = chain.head().unwrap().last_block_hash;
+
+        // Store the header and a part of the state, as if the node
+        // downloaded them before it restarted.
+        let num_parts = 4;
+        let mut header = chain.get_state_response_header(0, sync_hash).unwrap();
+        match &mut header {
+            ShardStateSyncResponseHeader::V1(header) => {
+                header.state_root_node.memory_usage = num_parts * STATE_PART_MEMORY_LIMIT.as_u64()
+            }
+            ShardStateSyncResponseHeader::V2(header) => {
+                header.state_root_node.memory_usage = num_parts * STATE_PART_MEMORY_LIMIT.as_u64()
+            }
+        }
+        let mut store_update = chain.chain_store().store().store_update();
+        let key = borsh::to_vec(&StateHeaderKey(0, sync_hash)).unwrap();
+        store_update.set_ser(DBCol::StateHeaders, &key, &header).unwrap();
+        for part_id in [0, 2] {
+            let key = borsh::to_vec(&StatePartKey(sync_hash, 0, part_id)).unwrap();
+            store_update.set(DBCol::StateParts, &key, b"part");
+        }
+        store_update.commit().unwrap();
+
+        let apply_parts_fn = move |_: ApplyStatePartsRequest| {};
+        let resharding_fn = move |_: ReshardingRequest| {};
+        let highest_height_peer_info = highest_height_peer(&chain);
+
+        run_actix(async {
+            state_sync
+                .run(
+                    &None,
+                    sync_hash,
+                    &mut new_shard_sync,
+                    &mut chain,
+                    kv.as_ref(),
+                    &[highest_height_peer_info],
+                    vec![0],
+                    &apply_parts_fn,
+                    &resharding_fn,
+                    &Arbiter::new().handle(),
+                    false,
+                    runtime,
+                )
+                .unwrap();
+
+            let download = new_shard_sync.get(&0).unwrap();
+            assert_eq!(download.status, ShardSyncStatus::StateDownloadParts);
+            let done: Vec<bool> = download.downloads.iter().map(|download| download.done).collect();
+            assert_eq!(done, vec![true, false, true, false]);
+
+            let mut requested_parts = vec![];
+            while let Some(request) = mock_peer_manager.pop() {
+                match request.as_network_requests() {
+                    NetworkRequests::StateRequestPart { part_id, .. } => {
+                        requested_parts.push(part_id)
+                    }
+                    request => panic!("unexpected request {request:?}"),
+                }
+            }
+            requested_parts.sort();
+            assert_eq!(requested_parts, vec![1, 3]);
+
+            // Failing to finalize the state discards both the parts and the
+            // header, so that they are all downloaded again.
+            let shard_sync_download = new_shard_sync.get_mut(&0).unwrap();
+            shard_sync_download.status = ShardSyncStatus::StateDownloadApplying;
+            state_sync.set_apply_result(0, Err(unc_chain::Error::Other("apply".to_string())));
+            state_sync
+                .sync_shards_download_applying_status(
+                    0,
+                    shard_sync_download,
+                    sync_hash,
+                    &mut chain,
+                    StaticClock::utc(),
+                )
+                .unwrap();
+            assert_eq!(shard_sync_download.status, ShardSyncStatus::StateDownloadHeader);
+            assert!(chain.get_state_header(0, sync_hash).is_err());
+            assert_eq!(
+                chain.get_cached_state_parts(sync_hash, 0, num_parts).unwrap(),
+                CachedParts::NoParts
+            );
+
+            System::current().stop()
+        });
+    }
+
+    #[test]
+    // Check that a manifest is only required by the storages which can't be trusted without it.
+    fn test_get_manifest_optional() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let filesystem = ExternalConnection::Filesystem { root_dir: tmp_dir.path().to_path_buf() };
+        let http = ExternalConnection::Http {
+            reqwest_client: Arc::new(reqwest::Client::new()),
+            base_url: "http://127.0.0.1:1".to_string(),
+        };
+        let manifests = ManifestCache::new(lru::LruCache::new(MAX_CACHED_MANIFESTS));
+        let manifest = StatePartsManifest::new(vec![unc_primitives::hash::hash(b"part")]);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let missing = get_manifest(&filesystem, &manifests, 0, "missing".to_string());
+            assert_eq!(missing.await.unwrap(), None);
+            assert!(get_manifest(&http, &manifests, 0, "http".to_string()).await.is_err());
+
+            filesystem.put_manifest(&manifest, 0, "published").await.unwrap();
+            let published = get_manifest(&filesystem, &manifests, 0, "published".to_string());
+            assert_eq!(published.await.unwrap().as_deref(), Some(&manifest));
+        });
+    }
+
+    #[test]
+    fn test_sampler_limited() {
+        let res = SamplerLimited::new(vec![(1, 2), (2, 3), (3, 0), (4, 1)]).collect::<Vec<u64>>();
+        assert_eq!(res.len(), 6);
+        assert_eq!(res.iter().filter(|&&v| v == 1).count(), 2);
+        assert_eq!(res.iter().filter(|&&v| v == 2).count(), 3);
+        assert_eq!(res.iter().filter(|&&v| v == 3).count(), 0);
+        assert_eq!(res.iter().filter(|&&v| v == 4).count(), 1);
+    }
+
+    #[test]
+    // Check that the batch size grows for peers which answer all the parts and shrinks for peers which time out.
+    fn test_adaptive_batch_size() {
+        let mut state_sync = StateSync::new(
+            Arc::new(MockPeerManagerAdapter::default()).into(),
+            TimeDuration::from_secs(1),
+            "chain_id",
+            &SyncConfig::Peers,
+            false,
+        );
+        let peer_id =
+            PeerId::new(SecretKey::from_random(unc_crypto::KeyType::ED25519).public_key());
+        let sync_hash = CryptoHash::default();
+        assert_eq!(state_sync.peers.batch_size(&peer_id), MAX_STATE_PART_REQUEST);
+
+        let request_parts = |state_sync: &mut StateSync, timeout: Duration| {
+            for part_id in 0..2 {
+                sent_request_part(
+                    peer_id.clone(),
+                    part_id,
+                    0,
+                    sync_hash,
+                    &mut state_sync.peers.last_part_id_requested,
+                    &mut state_sync.peers.requested_target,
+                    timeout,
+                );
+            }
+        };
+
+        request_parts(&mut state_sync, Duration::seconds(60));
+        state_sync.received_requested_part(0, 0, sync_hash);
+        assert_eq!(state_sync.peers.batch_size(&peer_id), MAX_STATE_PART_REQUEST);
+        state_sync.received_requested_part(1, 0, sync_hash);
+        assert_eq!(
+            state_sync.peers.batch_size(&peer_id),
+            MAX_STATE_PART_REQUEST + STATE_PART_REQUEST_STEP
+        );
+
+        // The requests have already expired.
+        request_parts(&mut state_sync, Duration::seconds(-1));
+        state_sync.peers.remove_expired();
+        assert!(state_sync.peers.last_part_id_requested.is_empty());
+        assert_eq!(
+            state_sync.peers.batch_size(&peer_id),
+            (MAX_STATE_PART_REQUEST + STATE_PART_REQUEST_STEP) / 2
+        );
+    }
 }
@@ -5,8 +5,10 @@
 use super::block_stats::BlockStats;
 use super::peer_manager_mock::PeerManagerMock;
 use crate::adapter::{
-    AnnounceAccountRequest, BlockApproval, BlockHeadersRequest, BlockHeadersResponse, BlockRequest,
-    BlockResponse, SetNetworkInfo, StateRequestHeader, StateRequestPart,
+    AnnounceAccountRequest, BlockApproval, BlockHeadersRangeRequest, BlockHeadersRequest,
+    BlockHeadersResponse, BlockRequest, BlockResponse, EpochSyncFinalizationRequest,
+    EpochSyncRequest, RecvEpochSyncFinalizationResponse, RecvEpochSyncResponse, SetNetworkInfo,
+    StateRequestHeader, StateRequestPart,
 };
 use crate::{start_view_client, Client, ClientActor, SyncAdapter, SyncStatus, ViewClientActor};
 use actix::{Actor, Addr, AsyncContext, Context};
@@ -523,6 +525,7 @@ pub fn setup_mock_all_validators(
                                 last_time_received_message: unc_async::time::Instant::now(),
                                 connection_established_time: unc_async::time::Instant::now(),
                                 peer_type: PeerType::Outbound,
+                                reputation_score: 0.,
                                 nonce: 3,
                             })
                             .collect();
@@ -681,6 +684,94 @@ pub fn setup_mock_all_validators(
                                 }
                             }
                         }
+                        NetworkRequests::BlockHeadersRangeRequest {
+                            start_height,
+                            end_height,
+                            peer_id,
+                        } => {
+                            for (i, peer_info) in key_pairs.iter().enumerate() {
+                                let peer_id = peer_id.clone();
+                                if peer_info.id == peer_id {
+                                    let me = connectors1[my_ord].client_actor.clone();
+                                    actix::spawn(
+                                        connectors1[i]
+                                            .view_client_actor
+                                            .send(
+                                                BlockHeadersRangeRequest(
+                                                    *start_height,
+                                                    *end_height,
+                                                )
+                                                .with_span_context(),
+                                            )
+                                            .then(move |response| {
+                                                let response = response.unwrap();
+                                                match response {
+                                                    Some(headers) => {
+                                                        me.do_send(
+                                                            BlockHeadersResponse(headers, peer_id)
+                                                                .with_span_context(),
+                                                        );
+                                                    }
+                                                    None => {}
+                                                }
+                                                future::ready(())
+                                            }),
+                                    );
+                                }
+                            }
+                        }
+                        NetworkRequests::EpochSyncRequest { epoch_id, peer_id } => {
+                            for (i, peer_info) in key_pairs.iter().enumerate() {
+                                let peer_id = peer_id.clone();
+                                if peer_info.id == peer_id {
+                                    let me = connectors1[my_ord].client_actor.clone();
+                                    actix::spawn(
+                                        connectors1[i]
+                                            .view_client_actor
+                                            .send(
+                                                EpochSyncRequest(epoch_id.clone())
+                                                    .with_span_context(),
+                                            )
+                                            .then(move |response| {
+                                                if let Some(response) = response.unwrap() {
+                                                    me.do_send(
+                                                        RecvEpochSyncResponse(*response, peer_id)
+                                                            .with_span_context(),
+                                                    );
+                                                }
+                                                future::ready(())
+                                            }),
+                                    );
+                                }
+                            }
+                        }
+                        NetworkRequests::EpochSyncFinalizationRequest { epoch_id, peer_id } => {
+                            for (i, peer_info) in key_pairs.iter().enumerate() {
+                                let peer_id = peer_id.clone();
+                                if peer_info.id == peer_id {
+                                    let me = connectors1[my_ord].client_actor.clone();
+                                    actix::spawn(
+                                        connectors1[i]
+                                            .view_client_actor
+                                            .send(
+                                                EpochSyncFinalizationRequest(epoch_id.clone())
+                                                    .with_span_context(),
+                                            )
+                                            .then(move |response| {
+                                                if let Some(response) = response.unwrap() {
+                                                    me.do_send(
+                                                        RecvEpochSyncFinalizationResponse(
+                                                            response, peer_id,
+                                                        )
+                                                        .with_span_context(),
+                                                    );
+                                                }
+                                                future::ready(())
+                                            }),
+                                    );
+                                }
+                            }
+                        }
                         NetworkRequests::StateRequestHeader {
                             shard_id,
                             sync_hash, ..
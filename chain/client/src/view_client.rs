@@ -2,7 +2,8 @@
 //! Useful for querying from RPC.
 
 use crate::adapter::{
-    AllMinersRequest, AnnounceAccountRequest, BlockHeadersRequest, BlockRequest, ProviderRequest,
+    AllMinersRequest, AnnounceAccountRequest, BlockHeadersRangeRequest, BlockHeadersRequest,
+    BlockRequest, EpochSyncFinalizationRequest, EpochSyncRequest, ProviderRequest,
     StateRequestHeader, StateRequestPart, StateResponse, TxStatusRequest, TxStatusResponse,
 };
 use crate::{
@@ -24,15 +25,15 @@ use unc_chain::{
 use unc_chain_configs::{ClientConfig, ProtocolConfigView};
 use unc_chain_primitives::error::EpochErrorResultToChainError;
 use unc_client_primitives::types::{
-    Error, GetAllMiners, GetAllMinersError, GetBlock, GetBlockError, GetBlockProof,
-    GetBlockProofError, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError,
-    GetExecutionOutcome, GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice,
-    GetGasPriceError, GetMaintenanceWindows, GetMaintenanceWindowsError,
-    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetProvider,
-    GetProviderError, GetReceipt, GetReceiptError, GetSplitStorageInfo, GetSplitStorageInfoError,
-    GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
-    TxStatus, TxStatusError,
+    Error, GetAccountStorageFootprint, GetAccountStorageFootprintError, GetAllMiners,
+    GetAllMinersError, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError,
+    GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome,
+    GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError,
+    GetMaintenanceWindows, GetMaintenanceWindowsError, GetNextLightClientBlockError,
+    GetProtocolConfig, GetProtocolConfigError, GetProvider, GetProviderError, GetReceipt,
+    GetReceiptError, GetSplitStorageInfo, GetSplitStorageInfoError, GetStateChangesError,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetValidatorInfoError, Query, QueryError, TxStatus, TxStatusError,
 };
 use unc_epoch_manager::shard_tracker::ShardTracker;
 use unc_epoch_manager::EpochManagerAdapter;
@@ -44,6 +45,7 @@ use unc_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithSp
 use unc_performance_metrics_macros::perf;
 use unc_primitives::block::{Block, BlockHeader};
 use unc_primitives::epoch_manager::epoch_info::EpochInfo;
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::merkle::{merklize, PartialMerkleTree};
 use unc_primitives::network::AnnounceAccount;
@@ -60,11 +62,11 @@ use unc_primitives::types::{
 };
 use unc_primitives::views::validator_power_and_pledge_view::ValidatorPowerAndPledgeView;
 use unc_primitives::views::{
-    AllMinersView, BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    ExecutionStatusView, FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum,
-    FinalExecutionStatus, GasPriceView, LightClientBlockView, MaintenanceWindowsView, QueryRequest,
-    QueryResponse, ReceiptView, SignedTransactionView, SplitStorageInfoView, StateChangesKindsView,
-    StateChangesView, TxExecutionStatus, TxStatusView,
+    AccountStorageFootprintView, AllMinersView, BlockView, ChunkView, EpochValidatorInfo,
+    ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionOutcomeView,
+    FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView, LightClientBlockView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, SignedTransactionView,
+    SplitStorageInfoView, StateChangesKindsView, StateChangesView, TxExecutionStatus, TxStatusView,
 };
 
 use unc_store::flat::{FlatStorageReadyStatus, FlatStorageStatus};
@@ -341,6 +343,25 @@ impl ViewClientActor {
             .shard_id_to_uid(shard_id, header.epoch_id())
             .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
 
+        // With `gc.archived_accounts` set chunk extras of garbage collected
+        // blocks are kept (and copied to cold storage) for the shards of
+        // archived accounts, but the state is only kept for archived accounts.
+        if !self.config.gc.archived_accounts.is_empty()
+            && !self.config.gc.is_archived_account(account_id.as_str())
+        {
+            let tip = self
+                .chain
+                .head()
+                .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
+            let gc_stop_height = self.runtime.get_gc_stop_height(&tip.last_block_hash);
+            if header.height() < gc_stop_height {
+                return Err(QueryError::GarbageCollectedBlock {
+                    block_height: header.height(),
+                    block_hash: *header.hash(),
+                });
+            }
+        }
+
         let tip = self.chain.head();
         let chunk_extra =
             self.chain.get_chunk_extra(header.hash(), &shard_uid).map_err(|err| match err {
@@ -585,6 +606,18 @@ impl ViewClientActor {
         self.chain.retrieve_headers(hashes, sync::header::MAX_BLOCK_HEADERS, None)
     }
 
+    fn retrieve_headers_in_range(
+        &mut self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> Result<Vec<BlockHeader>, unc_chain::Error> {
+        self.chain.retrieve_headers_in_range(
+            start_height,
+            end_height,
+            sync::header::MAX_BLOCK_HEADERS,
+        )
+    }
+
     fn check_signature_account_announce(
         &self,
         announce_account: &AnnounceAccount,
@@ -1413,6 +1446,85 @@ impl Handler<WithSpanContext<BlockHeadersRequest>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<BlockHeadersRangeRequest>> for ViewClientActor {
+    type Result = Option<Vec<BlockHeader>>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<BlockHeadersRangeRequest>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["BlockHeadersRangeRequest"])
+            .start_timer();
+        let BlockHeadersRangeRequest(start_height, end_height) = msg;
+
+        if self.adv.disable_header_sync() {
+            None
+        } else {
+            self.retrieve_headers_in_range(start_height, end_height).ok()
+        }
+    }
+}
+
+impl Handler<WithSpanContext<EpochSyncRequest>> for ViewClientActor {
+    type Result = Option<Box<EpochSyncResponse>>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<EpochSyncRequest>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["EpochSyncRequest"])
+            .start_timer();
+        let EpochSyncRequest(epoch_id) = msg;
+        match self.chain.get_epoch_sync_response(&epoch_id) {
+            Ok(response) => Some(Box::new(response)),
+            Err(err) => {
+                tracing::debug!(target: "sync", ?epoch_id, ?err, "Can't serve epoch sync request");
+                None
+            }
+        }
+    }
+}
+
+impl Handler<WithSpanContext<EpochSyncFinalizationRequest>> for ViewClientActor {
+    type Result = Option<Box<EpochSyncFinalizationResponse>>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<EpochSyncFinalizationRequest>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["EpochSyncFinalizationRequest"])
+            .start_timer();
+        let EpochSyncFinalizationRequest(epoch_id) = msg;
+        match self.chain.get_epoch_sync_finalization_response(&epoch_id) {
+            Ok(response) => Some(Box::new(response)),
+            Err(err) => {
+                tracing::debug!(
+                    target: "sync",
+                    ?epoch_id,
+                    ?err,
+                    "Can't serve epoch sync finalization request"
+                );
+                None
+            }
+        }
+    }
+}
+
 impl Handler<WithSpanContext<StateRequestHeader>> for ViewClientActor {
     type Result = Option<StateResponse>;
 
@@ -1698,6 +1810,55 @@ impl Handler<WithSpanContext<GetSplitStorageInfo>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<GetAccountStorageFootprint>> for ViewClientActor {
+    type Result = Result<AccountStorageFootprintView, GetAccountStorageFootprintError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetAccountStorageFootprint>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        tracing::debug!(target: "client", ?msg);
+
+        let account_id = msg.account_id;
+        let head = self.chain.head()?;
+        let shard_id = self
+            .epoch_manager
+            .account_id_to_shard_id(&account_id, &head.epoch_id)
+            .map_err(unc_chain::Error::from)?;
+        let shard_uid = self
+            .epoch_manager
+            .shard_id_to_uid(shard_id, &head.epoch_id)
+            .map_err(unc_chain::Error::from)?;
+        let footprint = self
+            .runtime
+            .get_flat_storage_manager()
+            .get_flat_storage_for_shard(shard_uid)
+            .map(|flat_storage| flat_storage.get_account_storage_footprint(&account_id))
+            .transpose()
+            .map_err(|err| GetAccountStorageFootprintError::IOError(err.to_string()))?
+            .flatten();
+        let Some((block, footprint)) = footprint else {
+            return Err(GetAccountStorageFootprintError::UnavailableShard(shard_id));
+        };
+        Ok(AccountStorageFootprintView {
+            account_id,
+            block_hash: block.hash,
+            block_height: block.height,
+            code_bytes: footprint.code_bytes,
+            contract_data_bytes: footprint.contract_data_bytes,
+            contract_data_records: footprint.contract_data_records,
+            access_key_bytes: footprint.access_key_bytes,
+            access_key_records: footprint.access_key_records,
+            rsa_key_bytes: footprint.rsa_key_bytes,
+            rsa_key_records: footprint.rsa_key_records,
+            total_bytes: footprint.total_bytes(),
+        })
+    }
+}
+
 /// Starts the View Client in a new arbiter (thread).
 pub fn start_view_client(
     validator_account_id: Option<AccountId>,
@@ -448,16 +448,26 @@ impl EpochManager {
         next_epoch_info: EpochInfo,
     ) -> Result<StoreUpdate, EpochError> {
         let mut store_update = self.store.store_update();
+        self.save_epoch_start(
+            &mut store_update,
+            prev_epoch_id,
+            prev_epoch_first_block_info.height(),
+        )?;
+        // Blocks of the previous epoch are not available, so aggregation of
+        // the current epoch has to start right after its last block.
+        self.epoch_info_aggregator =
+            EpochInfoAggregator::new(prev_epoch_id.clone(), *prev_epoch_last_block_info.hash());
+        self.largest_final_height = prev_epoch_last_block_info.height();
+        store_update
+            .set_ser(DBCol::EpochInfo, AGGREGATOR_KEY, &self.epoch_info_aggregator)
+            .map_err(EpochError::from)?;
         self.save_block_info(&mut store_update, Arc::new(prev_epoch_first_block_info))?;
         self.save_block_info(&mut store_update, Arc::new(prev_epoch_prev_last_block_info))?;
         self.save_block_info(&mut store_update, Arc::new(prev_epoch_last_block_info))?;
         self.save_epoch_info(&mut store_update, prev_epoch_id, Arc::new(prev_epoch_info))?;
         self.save_epoch_info(&mut store_update, epoch_id, Arc::new(epoch_info))?;
         self.save_epoch_info(&mut store_update, next_epoch_id, Arc::new(next_epoch_info))?;
-        // TODO #3488
-        // put unreachable! here to avoid warnings
-        unreachable!();
-        // Ok(store_update)
+        Ok(store_update)
     }
 
     /// When computing validators to kickout, we exempt some validators first so that
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use unc_primitives::types::{AccountId, ShardId};
+use unc_primitives::views::AccountStorageFootprintView;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcAccountStorageFootprintRequest {
+    pub account_id: AccountId,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcAccountStorageFootprintResponse {
+    #[serde(flatten)]
+    pub result: AccountStorageFootprintView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcAccountStorageFootprintError {
+    #[error("Account storage footprints are not maintained for shard {shard_id} on this node")]
+    UnavailableShard { shard_id: ShardId },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcAccountStorageFootprintError> for crate::errors::RpcError {
+    fn from(error: RpcAccountStorageFootprintError) -> Self {
+        let error_data = match &error {
+            RpcAccountStorageFootprintError::UnavailableShard { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+            RpcAccountStorageFootprintError::InternalError { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcAccountStorageFootprintError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -1,3 +1,4 @@
+pub mod account_storage_footprint;
 pub mod all_miners;
 pub mod blocks;
 pub mod changes;
@@ -4,9 +4,9 @@ use unc_client_primitives::debug::{
 };
 #[cfg(feature = "debug_types")]
 use unc_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, NetworkGraphView, NetworkRoutesView, PeerStoreView,
-    RecentOutboundConnectionsView, RequestedStatePartsView, SnapshotHostsView,
-    SplitStorageInfoView, SyncStatusView,
+    CatchupStatusView, ChainProcessingInfo, NetworkBandwidthView, NetworkGraphView,
+    NetworkRoutesView, PeerStoreView, RecentOutboundConnectionsView, RequestedStatePartsView,
+    SnapshotHostsView, SplitStorageInfoView, StoreScrubberStatusView, SyncStatusView,
 };
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -35,7 +35,9 @@ pub enum DebugStatusResponse {
     RecentOutboundConnections(RecentOutboundConnectionsView),
     Routes(NetworkRoutesView),
     SnapshotHosts(SnapshotHostsView),
+    NetworkBandwidth(NetworkBandwidthView),
     SplitStoreStatus(SplitStorageInfoView),
+    StoreScrubberStatus(StoreScrubberStatusView),
 }
 
 #[cfg(feature = "debug_types")]
@@ -3,9 +3,16 @@ use futures::{future, future::LocalBoxFuture, FutureExt, TryFutureExt};
 use std::time::Duration;
 use unc_jsonrpc_primitives::errors::RpcError;
 use unc_jsonrpc_primitives::message::{from_slice, Message};
+use unc_jsonrpc_primitives::types::account_storage_footprint::{
+    RpcAccountStorageFootprintRequest, RpcAccountStorageFootprintResponse,
+};
 use unc_jsonrpc_primitives::types::changes::{
     RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockByTypeResponse,
 };
+use unc_jsonrpc_primitives::types::light_client::{
+    RpcLightClientExecutionProofRequest, RpcLightClientExecutionProofResponse,
+    RpcLightClientNextBlockRequest, RpcLightClientNextBlockResponse,
+};
 use unc_jsonrpc_primitives::types::transactions::{
     RpcTransactionResponse, RpcTransactionStatusRequest,
 };
@@ -220,6 +227,20 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "tx", request)
     }
 
+    pub fn next_light_client_block(
+        &self,
+        request: RpcLightClientNextBlockRequest,
+    ) -> RpcRequest<RpcLightClientNextBlockResponse> {
+        call_method(&self.client, &self.server_addr, "next_light_client_block", request)
+    }
+
+    pub fn light_client_proof(
+        &self,
+        request: RpcLightClientExecutionProofRequest,
+    ) -> RpcRequest<RpcLightClientExecutionProofResponse> {
+        call_method(&self.client, &self.server_addr, "light_client_proof", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_changes(
         &self,
@@ -260,6 +281,19 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_split_storage_info", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_account_storage_footprint(
+        &self,
+        request: RpcAccountStorageFootprintRequest,
+    ) -> RpcRequest<RpcAccountStorageFootprintResponse> {
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_account_storage_footprint",
+            request,
+        )
+    }
+
     pub fn validators(
         &self,
         epoch_id_or_block_id: Option<EpochReference>,
@@ -0,0 +1,41 @@
+use serde_json::Value;
+
+use unc_client_primitives::types::GetAccountStorageFootprintError;
+use unc_jsonrpc_primitives::errors::RpcParseError;
+use unc_jsonrpc_primitives::types::account_storage_footprint::{
+    RpcAccountStorageFootprintError, RpcAccountStorageFootprintRequest,
+};
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcAccountStorageFootprintRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcAccountStorageFootprintError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetAccountStorageFootprintError> for RpcAccountStorageFootprintError {
+    fn rpc_from(error: GetAccountStorageFootprintError) -> Self {
+        match error {
+            GetAccountStorageFootprintError::IOError(error_message) => {
+                Self::InternalError { error_message }
+            }
+            GetAccountStorageFootprintError::UnavailableShard(shard_id) => {
+                Self::UnavailableShard { shard_id }
+            }
+            GetAccountStorageFootprintError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcAccountStorageFootprintError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
@@ -45,6 +45,9 @@ impl RpcFrom<unc_client_primitives::debug::DebugStatusResponse>
             unc_client_primitives::debug::DebugStatusResponse::ChainProcessingStatus(x) => {
                 unc_jsonrpc_primitives::types::status::DebugStatusResponse::ChainProcessingStatus(x)
             }
+            unc_client_primitives::debug::DebugStatusResponse::StoreScrubberStatus(x) => {
+                unc_jsonrpc_primitives::types::status::DebugStatusResponse::StoreScrubberStatus(x)
+            }
         }
     }
 }
@@ -69,6 +72,9 @@ impl RpcFrom<unc_network::debug::DebugStatus>
             unc_network::debug::DebugStatus::SnapshotHosts(x) => {
                 unc_jsonrpc_primitives::types::status::DebugStatusResponse::SnapshotHosts(x)
             }
+            unc_network::debug::DebugStatus::Bandwidth(x) => {
+                unc_jsonrpc_primitives::types::status::DebugStatusResponse::NetworkBandwidth(x)
+            }
         }
     }
 }
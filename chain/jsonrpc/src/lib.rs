@@ -16,6 +16,7 @@ use std::time::{Duration, Instant};
 use tokio::time::{sleep, timeout};
 use tracing::{error, info};
 use unc_chain_configs::GenesisConfig;
+use unc_client::sync::external::STATE_PARTS_MIRROR_PATH;
 use unc_client::{
     ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig,
     GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
@@ -23,10 +24,16 @@ use unc_client::{
     GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest,
     ProcessTxResponse, Query, Status, TxStatus, ViewClientActor,
 };
-use unc_client_primitives::types::{GetAllMiners, GetProvider, GetSplitStorageInfo};
+use unc_client_primitives::types::{
+    GetAccountStorageFootprint, GetAllMiners, GetProvider, GetSplitStorageInfo,
+};
 pub use unc_jsonrpc_client as client;
 use unc_jsonrpc_primitives::errors::RpcError;
 use unc_jsonrpc_primitives::message::{Message, Request};
+use unc_jsonrpc_primitives::types::account_storage_footprint::{
+    RpcAccountStorageFootprintError, RpcAccountStorageFootprintRequest,
+    RpcAccountStorageFootprintResponse,
+};
 use unc_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
 use unc_jsonrpc_primitives::types::entity_debug::{EntityDebugHandler, EntityQuery};
 use unc_jsonrpc_primitives::types::query::RpcQueryRequest;
@@ -96,6 +103,10 @@ pub struct RpcConfig {
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
     pub experimental_debug_pages_src_path: Option<String>,
+    // If provided, state parts dumped to this directory are served under `/state_parts/`, so
+    // that other nodes can state sync from this node using the `Mirror` external storage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_parts_mirror_dir: Option<PathBuf>,
 }
 
 impl Default for RpcConfig {
@@ -108,6 +119,7 @@ impl Default for RpcConfig {
             limits_config: Default::default(),
             enable_debug_rpc: false,
             experimental_debug_pages_src_path: None,
+            state_parts_mirror_dir: None,
         }
     }
 }
@@ -228,6 +240,7 @@ struct JsonRpcHandler {
     enable_debug_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
     entity_debug_handler: Arc<dyn EntityDebugHandler>,
+    state_parts_mirror_dir: Option<PathBuf>,
 }
 
 impl JsonRpcHandler {
@@ -385,6 +398,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_split_storage_info" => {
                 process_method_call(request, |params| self.split_storage_info(params)).await
             }
+            "EXPERIMENTAL_account_storage_footprint" => {
+                process_method_call(request, |params| self.account_storage_footprint(params)).await
+            }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -753,6 +769,9 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/store_scrubber_status" => {
+                        self.client_send(DebugStatus::StoreScrubberStatus).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(unc_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -775,6 +794,10 @@ impl JsonRpcHandler {
                         .peer_manager_send(unc_network::debug::GetDebugStatus::SnapshotHosts)
                         .await?
                         .rpc_into(),
+                    "/debug/api/network_bandwidth" => self
+                        .peer_manager_send(unc_network::debug::GetDebugStatus::Bandwidth)
+                        .await?
+                        .rpc_into(),
                     "/debug/api/split_store_info" => {
                         let split_storage_info: RpcSplitStorageInfoResponse = self
                             .split_storage_info(RpcSplitStorageInfoRequest {})
@@ -1105,6 +1128,17 @@ impl JsonRpcHandler {
         let split_storage = self.view_client_send(GetSplitStorageInfo {}).await?;
         Ok(RpcSplitStorageInfoResponse { result: split_storage })
     }
+
+    /// Returns the breakdown of state bytes attributed to the account at the flat head
+    /// of its shard. Only available if the node maintains account storage footprints.
+    async fn account_storage_footprint(
+        &self,
+        request: RpcAccountStorageFootprintRequest,
+    ) -> Result<RpcAccountStorageFootprintResponse, RpcAccountStorageFootprintError> {
+        let RpcAccountStorageFootprintRequest { account_id } = request;
+        let footprint = self.view_client_send(GetAccountStorageFootprint { account_id }).await?;
+        Ok(RpcAccountStorageFootprintResponse { result: footprint })
+    }
 }
 
 #[cfg(feature = "sandbox")]
@@ -1474,6 +1508,7 @@ async fn display_debug_html(
         "validator" => Some(debug_page_string!("validator.html", handler)),
         "validator.css" => Some(debug_page_string!("validator.css", handler)),
         "split_store" => Some(debug_page_string!("split_store.html", handler)),
+        "store_scrubber" => Some(debug_page_string!("store_scrubber.html", handler)),
         _ => None,
     };
 
@@ -1485,6 +1520,25 @@ async fn display_debug_html(
     }
 }
 
+/// Serves a file dumped to `state_parts_mirror_dir` by the state sync dump.
+async fn state_part_handler(
+    path: web::Path<String>,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    let Some(root_dir) = &handler.state_parts_mirror_dir else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let location = PathBuf::from(path.into_inner());
+    // Don't allow reading files outside of the directory.
+    if !location.components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+        return Ok(HttpResponse::BadRequest().finish());
+    }
+    match tokio::fs::read(root_dir.join(location)).await {
+        Ok(data) => Ok(HttpResponse::Ok().content_type("application/octet-stream").body(data)),
+        Err(_) => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
 fn tx_execution_status_meets_expectations(
     expected: &TxExecutionStatus,
     actual: &TxExecutionStatus,
@@ -1538,6 +1592,7 @@ pub fn start_http(
         limits_config,
         enable_debug_rpc,
         experimental_debug_pages_src_path: debug_pages_src_path,
+        state_parts_mirror_dir,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr.to_string());
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
@@ -1555,6 +1610,7 @@ pub fn start_http(
                 enable_debug_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
                 entity_debug_handler: entity_debug_handler.clone(),
+                state_parts_mirror_dir: state_parts_mirror_dir.clone(),
             }))
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
@@ -1582,6 +1638,10 @@ pub fn start_http(
             )
             .service(debug_html)
             .service(display_debug_html)
+            .service(
+                web::resource(format!("/{}/{{location:.*}}", STATE_PARTS_MIRROR_PATH))
+                    .route(web::get().to(state_part_handler)),
+            )
     });
 
     match listener.listen(addr.std_listener().unwrap()) {
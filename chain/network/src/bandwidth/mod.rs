@@ -0,0 +1,133 @@
+//! Bandwidth accounting and limits.
+//!
+//! Every connection counts the messages and bytes sent and received, per message type
+//! (as returned by PeerMessage::msg_variant(), so that routed messages are accounted by
+//! the type of their body). Additionally, the outbound traffic of responses to the requests of
+//! other nodes can be capped by limits shared by all TIER2 connections, per message type:
+//! responses which would exceed the limit are delayed until the limit allows sending them.
+//! It allows nodes on metered links to bound the bandwidth spent on serving blocks, chunks or
+//! state parts to other nodes. Messages broadcast by the node itself are never limited.
+
+use crate::concurrency::rate;
+use crate::network_protocol::{PeerMessage, RoutedMessageBody};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use strum::VariantNames as _;
+use unc_async::time;
+
+#[cfg(test)]
+mod tests;
+
+/// Limits of the outbound traffic, keyed by message type.
+/// `rate::Limit::qps` is the number of bytes per second,
+/// `rate::Limit::burst` is the maximal number of bytes sent at once.
+pub type Limits = BTreeMap<String, rate::Limit>;
+
+/// Types of the messages sent in response to the requests of other nodes.
+/// Only these can be limited.
+const RESPONSES: &[&str] = &[
+    "Block",
+    "BlockHeaders",
+    "VersionedStateResponse",
+    "EpochSyncResponse",
+    "EpochSyncFinalizationResponse",
+    "PartialEncodedChunkResponse",
+    "TxStatusResponse",
+];
+
+/// Responses which would be delayed longer than that are dropped instead, as the requesting
+/// node would have given up on them anyway (see `reputation::Config::response_timeout`).
+const MAX_DELAY: time::Duration = time::Duration::seconds(10);
+
+pub(crate) fn validate(limits: &Limits) -> anyhow::Result<()> {
+    for (msg_type, limit) in limits {
+        let msg_type = msg_type.as_str();
+        if !PeerMessage::VARIANTS.contains(&msg_type)
+            && !RoutedMessageBody::VARIANTS.contains(&msg_type)
+        {
+            anyhow::bail!("unknown message type {msg_type:?}");
+        }
+        if !RESPONSES.contains(&msg_type) {
+            anyhow::bail!("{msg_type} is not a response, only responses can be limited");
+        }
+        limit.validate().map_err(|err| anyhow::anyhow!("limit for {msg_type}: {err}"))?;
+    }
+    Ok(())
+}
+
+/// Traffic of a single message type.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Traffic {
+    pub received_messages: u64,
+    pub received_bytes: u64,
+    pub sent_messages: u64,
+    pub sent_bytes: u64,
+    /// Responses delayed or dropped, because they exceeded the bandwidth limit.
+    pub throttled_messages: u64,
+    pub throttled_bytes: u64,
+}
+
+/// Traffic of a single connection, per message type.
+#[derive(Default)]
+pub(crate) struct Counters(Mutex<HashMap<&'static str, Traffic>>);
+
+impl Counters {
+    pub fn record_received(&self, msg_type: &'static str, bytes: u64) {
+        let mut inner = self.0.lock();
+        let t = inner.entry(msg_type).or_default();
+        t.received_messages += 1;
+        t.received_bytes += bytes;
+    }
+
+    pub fn record_sent(&self, msg_type: &'static str, bytes: u64) {
+        let mut inner = self.0.lock();
+        let t = inner.entry(msg_type).or_default();
+        t.sent_messages += 1;
+        t.sent_bytes += bytes;
+    }
+
+    pub fn record_throttled(&self, msg_type: &'static str, bytes: u64) {
+        let mut inner = self.0.lock();
+        let t = inner.entry(msg_type).or_default();
+        t.throttled_messages += 1;
+        t.throttled_bytes += bytes;
+    }
+
+    /// Snapshot of the counters, sorted by message type.
+    pub fn load(&self) -> Vec<(&'static str, Traffic)> {
+        let mut traffic: Vec<_> = self.0.lock().iter().map(|(k, v)| (*k, *v)).collect();
+        traffic.sort_by_key(|(msg_type, _)| *msg_type);
+        traffic
+    }
+}
+
+/// Limits of the outbound traffic, shared by all TIER2 connections.
+pub(crate) struct Limiter(HashMap<String, Mutex<rate::Bucket>>);
+
+impl Limiter {
+    pub fn new(clock: &time::Clock, limits: &Limits) -> Self {
+        Self(
+            limits
+                .iter()
+                .map(|(msg_type, limit)| {
+                    (msg_type.clone(), Mutex::new(rate::Bucket::new(clock, *limit)))
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the delay after which a response of `msg_type` of size `bytes` can be sent
+    /// without exceeding the limit for that message type, or None if the response should be
+    /// dropped, because it would be delayed too long.
+    pub fn reserve(
+        &self,
+        clock: &time::Clock,
+        msg_type: &str,
+        bytes: u64,
+    ) -> Option<time::Duration> {
+        match self.0.get(msg_type) {
+            Some(bucket) => bucket.lock().reserve(clock, bytes, MAX_DELAY),
+            None => Some(time::Duration::ZERO),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+fn make_limits() -> Limits {
+    [
+        ("Block".to_string(), rate::Limit { qps: 100., burst: 1000 }),
+        ("VersionedStateResponse".to_string(), rate::Limit { qps: 10., burst: 100 }),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn validate_limits() {
+    validate(&make_limits()).unwrap();
+    // Routed messages are limited by the type of their body.
+    let mut limits = make_limits();
+    limits.insert("PartialEncodedChunkResponse".to_string(), rate::Limit { qps: 1., burst: 1 });
+    validate(&limits).unwrap();
+
+    let mut limits = make_limits();
+    limits.insert("NoSuchMessage".to_string(), rate::Limit { qps: 1., burst: 1 });
+    assert!(validate(&limits).is_err());
+
+    let mut limits = make_limits();
+    limits.insert("TxStatusResponse".to_string(), rate::Limit { qps: 0., burst: 1 });
+    assert!(validate(&limits).is_err());
+
+    // Only responses can be limited.
+    let mut limits = make_limits();
+    limits.insert("Transaction".to_string(), rate::Limit { qps: 1., burst: 1 });
+    assert!(validate(&limits).is_err());
+}
+
+#[test]
+fn limiter() {
+    let clock = time::FakeClock::default();
+    let limiter = Limiter::new(&clock.clock(), &make_limits());
+    let reserve = |msg_type, bytes| limiter.reserve(&clock.clock(), msg_type, bytes);
+    // Message types are limited independently.
+    assert_eq!(Some(time::Duration::ZERO), reserve("Block", 1000));
+    assert_eq!(Some(time::Duration::seconds(1)), reserve("Block", 100));
+    assert_eq!(Some(time::Duration::ZERO), reserve("VersionedStateResponse", 100));
+    assert_eq!(Some(time::Duration::seconds(1)), reserve("VersionedStateResponse", 10));
+    // Message types without a limit are never delayed.
+    assert_eq!(Some(time::Duration::ZERO), reserve("Transaction", 1_000_000));
+    // Responses which would be delayed too long are dropped.
+    assert_eq!(None, reserve("VersionedStateResponse", 100));
+
+    // Delayed responses have to be paid back before the next ones can be sent.
+    clock.advance(time::Duration::seconds(1));
+    assert_eq!(Some(time::Duration::seconds(9)), reserve("Block", 900));
+    assert_eq!(None, reserve("Block", 200));
+}
+
+#[test]
+fn counters() {
+    let counters = Counters::default();
+    counters.record_received("Block", 100);
+    counters.record_received("Block", 200);
+    counters.record_sent("Block", 50);
+    counters.record_sent("BlockRequest", 10);
+    counters.record_throttled("BlockRequest", 20);
+    assert_eq!(
+        vec![
+            (
+                "Block",
+                Traffic {
+                    received_messages: 2,
+                    received_bytes: 300,
+                    sent_messages: 1,
+                    sent_bytes: 50,
+                    ..Traffic::default()
+                }
+            ),
+            (
+                "BlockRequest",
+                Traffic {
+                    sent_messages: 1,
+                    sent_bytes: 10,
+                    throttled_messages: 1,
+                    throttled_bytes: 20,
+                    ..Traffic::default()
+                }
+            ),
+        ],
+        counters.load()
+    );
+}
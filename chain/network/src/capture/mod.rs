@@ -0,0 +1,275 @@
+//! Capture of the network traffic, for debugging.
+//!
+//! When enabled, every PeerMessage sent to or received from a connected peer
+//! (after the handshake) is appended, borsh-encoded and together with the time and
+//! the id of the peer, to a capture file. Capture files are rotated once they reach
+//! `Config::max_file_size` and only the `Config::max_files` most recent files are kept,
+//! so that the capture can be left enabled for a long time. A capture can be fed back
+//! into a node with `raw::Connection::replay`, to reproduce its behaviour offline.
+//!
+//! Each capture file is a sequence of records, each prefixed with its length (u32, little
+//! endian). Writing to disk happens on a dedicated thread; records which cannot be
+//! written fast enough are dropped, rather than slowing down the PeerActors.
+use crate::network_protocol::{Encoding, ParsePeerMessageError, PeerMessage};
+use crate::peer::stream::NETWORK_MESSAGE_MAX_SIZE_BYTES;
+use crate::stats::metrics;
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use unc_async::time;
+use unc_primitives::network::PeerId;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximal number of records waiting to be written to disk.
+const QUEUE_SIZE: usize = 10_000;
+
+/// Maximal size of an encoded record: a message of the maximal size accepted from
+/// the network, together with the timestamp, the peer id and the direction.
+const MAX_RECORD_SIZE: usize = NETWORK_MESSAGE_MAX_SIZE_BYTES + 1024;
+
+const FILE_PREFIX: &str = "capture-";
+const FILE_EXTENSION: &str = "bin";
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Directory to store the capture files in.
+    pub dir: PathBuf,
+    /// Size of a capture file, after which a new file is started.
+    pub max_file_size: u64,
+    /// Number of capture files to keep. The oldest files are removed.
+    pub max_files: usize,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Message received from the peer.
+    Inbound,
+    /// Message sent to the peer.
+    Outbound,
+}
+
+/// A single captured message.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Unix timestamp (in nanoseconds) at which the message has been sent/received.
+    pub timestamp_nanos: i128,
+    pub peer_id: PeerId,
+    pub direction: Direction,
+    /// Borsh-encoded PeerMessage.
+    pub message: Vec<u8>,
+}
+
+impl Record {
+    pub(crate) fn new(
+        clock: &time::Clock,
+        peer_id: PeerId,
+        direction: Direction,
+        msg: &PeerMessage,
+    ) -> Self {
+        Self {
+            timestamp_nanos: clock.now_utc().unix_timestamp_nanos(),
+            peer_id,
+            direction,
+            message: msg.serialize(Encoding::Borsh),
+        }
+    }
+
+    pub fn timestamp(&self) -> anyhow::Result<time::Utc> {
+        Ok(time::Utc::from_unix_timestamp_nanos(self.timestamp_nanos)?)
+    }
+
+    pub(crate) fn peer_message(&self) -> Result<PeerMessage, ParsePeerMessageError> {
+        PeerMessage::deserialize(Encoding::Borsh, &self.message)
+    }
+
+    /// Type of the captured message, as in PeerMessage::msg_variant().
+    pub fn msg_type(&self) -> anyhow::Result<&'static str> {
+        Ok(self.peer_message()?.msg_variant())
+    }
+}
+
+/// Lists the capture files in `dir`, ordered from the oldest to the newest.
+fn list_files(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |ext| ext != FILE_EXTENSION) {
+            continue;
+        }
+        let seq = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix(FILE_PREFIX))
+            .and_then(|seq| seq.parse().ok());
+        if let Some(seq) = seq {
+            files.push((seq, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn file_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{FILE_PREFIX}{seq:08}.{FILE_EXTENSION}"))
+}
+
+/// Appends records to the capture files, rotating them.
+pub struct Writer {
+    config: Config,
+    seq: u64,
+    file: BufWriter<fs::File>,
+    file_size: u64,
+}
+
+impl Writer {
+    pub fn new(config: Config) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        // Continue after the files of the previous runs, rather than overwriting them.
+        let seq = list_files(&config.dir)?.last().map_or(0, |(seq, _)| seq + 1);
+        let file = Self::create(&config, seq)?;
+        let this = Self { config, seq, file, file_size: 0 };
+        this.prune()?;
+        Ok(this)
+    }
+
+    fn create(config: &Config, seq: u64) -> io::Result<BufWriter<fs::File>> {
+        Ok(BufWriter::new(fs::File::create(file_path(&config.dir, seq))?))
+    }
+
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        if self.file_size >= self.config.max_file_size {
+            self.rotate()?;
+        }
+        let data = borsh::to_vec(record)?;
+        self.file.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.file.write_all(&data)?;
+        self.file_size += 4 + data.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.seq += 1;
+        self.file = Self::create(&self.config, self.seq)?;
+        self.file_size = 0;
+        self.prune()
+    }
+
+    /// Removes the oldest files, so that at most `config.max_files` remain.
+    fn prune(&self) -> io::Result<()> {
+        let files = list_files(&self.config.dir)?;
+        let excess = files.len().saturating_sub(self.config.max_files.max(1));
+        for (_, path) in &files[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Records the network traffic to the capture files.
+pub(crate) struct Recorder {
+    send: crossbeam_channel::Sender<Record>,
+}
+
+impl Recorder {
+    pub fn new(config: Config) -> io::Result<Self> {
+        let mut writer = Writer::new(config)?;
+        let (send, recv) = crossbeam_channel::bounded::<Record>(QUEUE_SIZE);
+        // The thread stops once the Recorder is dropped.
+        std::thread::spawn(move || {
+            let res = (|| {
+                for record in recv.iter() {
+                    writer.write(&record)?;
+                    if recv.is_empty() {
+                        writer.flush()?;
+                    }
+                }
+                writer.flush()
+            })();
+            if let Err(err) = res {
+                tracing::error!(target: "network", ?err, "failed to write network capture, capture stopped");
+            }
+        });
+        Ok(Self { send })
+    }
+
+    pub fn record(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        direction: Direction,
+        msg: &PeerMessage,
+    ) {
+        let record = Record::new(clock, peer_id.clone(), direction, msg);
+        if self.send.try_send(record).is_err() {
+            metrics::NETWORK_CAPTURE_DROPPED_RECORDS.inc();
+        }
+    }
+}
+
+/// Reads the records from the capture files in a directory, from the oldest to the newest.
+pub struct Reader {
+    files: VecDeque<PathBuf>,
+    current: Option<BufReader<fs::File>>,
+}
+
+impl Reader {
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        let files = list_files(dir)?.into_iter().map(|(_, path)| path).collect();
+        Ok(Self { files, current: None })
+    }
+
+    fn read_next(&mut self) -> io::Result<Option<Record>> {
+        loop {
+            let file = match &mut self.current {
+                Some(file) => file,
+                None => match self.files.pop_front() {
+                    Some(path) => self.current.insert(BufReader::new(fs::File::open(path)?)),
+                    None => return Ok(None),
+                },
+            };
+            // A record truncated at the end of a file (because the node stopped
+            // in the middle of writing it) is skipped.
+            let mut len = [0u8; 4];
+            if let Err(err) = file.read_exact(&mut len) {
+                if err.kind() != io::ErrorKind::UnexpectedEof {
+                    return Err(err);
+                }
+                self.current = None;
+                continue;
+            }
+            let len = u32::from_le_bytes(len) as usize;
+            if len > MAX_RECORD_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("record of {len} bytes exceeds the maximal size {MAX_RECORD_SIZE}"),
+                ));
+            }
+            let mut data = vec![0u8; len];
+            if let Err(err) = file.read_exact(&mut data) {
+                if err.kind() != io::ErrorKind::UnexpectedEof {
+                    return Err(err);
+                }
+                self.current = None;
+                continue;
+            }
+            return Ok(Some(Record::try_from_slice(&data)?));
+        }
+    }
+}
+
+impl Iterator for Reader {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+
+fn make_records(rng: &mut impl rand::Rng, clock: &time::Clock, n: usize) -> Vec<Record> {
+    let peer_id = data::make_peer_id(rng);
+    (0..n)
+        .map(|i| {
+            let direction = if i % 2 == 0 { Direction::Inbound } else { Direction::Outbound };
+            let msg = PeerMessage::BlockRequest(data::make_hash(rng));
+            Record::new(clock, peer_id.clone(), direction, &msg)
+        })
+        .collect()
+}
+
+fn read_all(dir: &Path) -> Vec<Record> {
+    Reader::new(dir).unwrap().collect::<io::Result<_>>().unwrap()
+}
+
+#[test]
+fn write_and_read() {
+    let mut rng = make_rng(8237429);
+    let clock = time::FakeClock::default();
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config { dir: dir.path().to_path_buf(), max_file_size: 1_000_000, max_files: 3 };
+    let records = make_records(&mut rng, &clock.clock(), 10);
+    let mut writer = Writer::new(config).unwrap();
+    for r in &records {
+        writer.write(r).unwrap();
+    }
+    writer.flush().unwrap();
+    let got = read_all(dir.path());
+    assert_eq!(records, got);
+    assert_eq!("BlockRequest", got[0].msg_type().unwrap());
+    assert_eq!(clock.now_utc(), got[0].timestamp().unwrap());
+}
+
+#[test]
+fn rotation() {
+    let mut rng = make_rng(2934823);
+    let clock = time::FakeClock::default();
+    let dir = tempfile::tempdir().unwrap();
+    // Every record starts a new file.
+    let config = Config { dir: dir.path().to_path_buf(), max_file_size: 1, max_files: 3 };
+    let records = make_records(&mut rng, &clock.clock(), 10);
+    let mut writer = Writer::new(config.clone()).unwrap();
+    for r in &records {
+        writer.write(r).unwrap();
+    }
+    writer.flush().unwrap();
+    // Only the most recent files are kept.
+    assert_eq!(3, list_files(dir.path()).unwrap().len());
+    assert_eq!(records[7..].to_vec(), read_all(dir.path()));
+
+    // A restarted writer continues after the existing files.
+    let more = make_records(&mut rng, &clock.clock(), 2);
+    let mut writer = Writer::new(config).unwrap();
+    for r in &more {
+        writer.write(r).unwrap();
+    }
+    writer.flush().unwrap();
+    let want: Vec<_> = records[9..].iter().chain(&more).cloned().collect();
+    assert_eq!(want, read_all(dir.path()));
+}
+
+#[test]
+fn truncated_record() {
+    let mut rng = make_rng(1239874);
+    let clock = time::FakeClock::default();
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config { dir: dir.path().to_path_buf(), max_file_size: 1_000_000, max_files: 3 };
+    let records = make_records(&mut rng, &clock.clock(), 3);
+    let mut writer = Writer::new(config).unwrap();
+    for r in &records {
+        writer.write(r).unwrap();
+    }
+    writer.flush().unwrap();
+    let (_, path) = list_files(dir.path()).unwrap().pop().unwrap();
+    let len = fs::metadata(&path).unwrap().len();
+    fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 5).unwrap();
+    assert_eq!(records[..2].to_vec(), read_all(dir.path()));
+}
+
+#[test]
+fn oversized_record() {
+    let mut rng = make_rng(5092341);
+    let clock = time::FakeClock::default();
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config { dir: dir.path().to_path_buf(), max_file_size: 1_000_000, max_files: 3 };
+    let records = make_records(&mut rng, &clock.clock(), 1);
+    let mut writer = Writer::new(config).unwrap();
+    writer.write(&records[0]).unwrap();
+    writer.flush().unwrap();
+    // A corrupted length prefix is reported rather than allocated.
+    let (_, path) = list_files(dir.path()).unwrap().pop().unwrap();
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    file.write_all(&[0u8; 16]).unwrap();
+    let mut reader = Reader::new(dir.path()).unwrap();
+    assert_eq!(records[0], reader.next().unwrap().unwrap());
+    assert_eq!(io::ErrorKind::InvalidData, reader.next().unwrap().unwrap_err().kind());
+}
@@ -5,10 +5,11 @@ use crate::types::{NetworkInfo, ReasonForBan};
 use unc_primitives::block::{Approval, Block, BlockHeader};
 use unc_primitives::challenge::Challenge;
 use unc_primitives::chunk_validation::{ChunkEndorsement, ChunkStateWitness};
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::network::{AnnounceAccount, PeerId};
 use unc_primitives::transaction::SignedTransaction;
-use unc_primitives::types::{AccountId, EpochId, ShardId};
+use unc_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
 use unc_primitives::views::FinalExecutionOutcomeView;
 
 /// A strongly typed asynchronous API for the Client logic.
@@ -47,6 +48,12 @@ pub trait Client: Send + Sync + 'static {
 
     async fn block_headers_request(&self, hashes: Vec<CryptoHash>) -> Option<Vec<BlockHeader>>;
 
+    async fn block_headers_range_request(
+        &self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> Option<Vec<BlockHeader>>;
+
     async fn block(&self, block: Block, peer_id: PeerId, was_requested: bool);
 
     async fn block_headers(
@@ -67,6 +74,25 @@ pub trait Client: Send + Sync + 'static {
     async fn chunk_state_witness(&self, witness: ChunkStateWitness);
 
     async fn chunk_endorsement(&self, endorsement: ChunkEndorsement);
+
+    async fn epoch_sync_request(&self, epoch_id: EpochId) -> Option<Box<EpochSyncResponse>>;
+
+    async fn epoch_sync_response(
+        &self,
+        response: EpochSyncResponse,
+        peer_id: PeerId,
+    ) -> Result<(), ReasonForBan>;
+
+    async fn epoch_sync_finalization_request(
+        &self,
+        epoch_id: EpochId,
+    ) -> Option<Box<EpochSyncFinalizationResponse>>;
+
+    async fn epoch_sync_finalization_response(
+        &self,
+        response: EpochSyncFinalizationResponse,
+        peer_id: PeerId,
+    ) -> Result<(), ReasonForBan>;
 }
 
 /// Implementation of Client which doesn't do anything and never returns errors.
@@ -114,6 +140,14 @@ impl Client for Noop {
         None
     }
 
+    async fn block_headers_range_request(
+        &self,
+        _start_height: BlockHeight,
+        _end_height: BlockHeight,
+    ) -> Option<Vec<BlockHeader>> {
+        None
+    }
+
     async fn block(&self, _block: Block, _peer_id: PeerId, _was_requested: bool) {}
 
     async fn block_headers(
@@ -138,4 +172,31 @@ impl Client for Noop {
     async fn chunk_state_witness(&self, _witness: ChunkStateWitness) {}
 
     async fn chunk_endorsement(&self, _endorsement: ChunkEndorsement) {}
+
+    async fn epoch_sync_request(&self, _epoch_id: EpochId) -> Option<Box<EpochSyncResponse>> {
+        None
+    }
+
+    async fn epoch_sync_response(
+        &self,
+        _response: EpochSyncResponse,
+        _peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        Ok(())
+    }
+
+    async fn epoch_sync_finalization_request(
+        &self,
+        _epoch_id: EpochId,
+    ) -> Option<Box<EpochSyncFinalizationResponse>> {
+        None
+    }
+
+    async fn epoch_sync_finalization_response(
+        &self,
+        _response: EpochSyncFinalizationResponse,
+        _peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        Ok(())
+    }
 }
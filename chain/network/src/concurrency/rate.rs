@@ -1,3 +1,5 @@
+use unc_async::time;
+
 /// Config of a rate limiter algorithm, which behaves like a semaphore
 /// - with maximal capacity `burst`
 /// - with a new ticket added automatically every 1/qps seconds (qps stands for "queries per
@@ -25,3 +27,46 @@ impl Limit {
         Ok(())
     }
 }
+
+/// Token bucket enforcing a Limit, which schedules requests rather than blocking them:
+/// a request for `n` tokens is admitted with a delay after which the tokens will be available,
+/// and the tokens are taken immediately, so that the requests are admitted in order.
+/// It is used for limits expressed in bytes, in which case `qps` is the number of bytes per second
+/// and `burst` the maximal number of bytes sent at once.
+pub struct Bucket {
+    limit: Limit,
+    /// Negative if the admitted requests haven't been paid back yet.
+    tokens: f64,
+    updated: time::Instant,
+}
+
+impl Bucket {
+    /// Constructs a full bucket.
+    pub fn new(clock: &time::Clock, limit: Limit) -> Self {
+        Self { limit, tokens: limit.burst as f64, updated: clock.now() }
+    }
+
+    /// Takes `n` tokens from the bucket and returns the delay after which they are available,
+    /// unless that delay would exceed `max_delay`, in which case no tokens are taken.
+    /// A request for more than `burst` tokens would never be admitted, so instead
+    /// it is admitted once the bucket is full, leaving the bucket in debt.
+    pub fn reserve(
+        &mut self,
+        clock: &time::Clock,
+        n: u64,
+        max_delay: time::Duration,
+    ) -> Option<time::Duration> {
+        let now = clock.now();
+        let burst = self.limit.burst as f64;
+        let elapsed = (now - self.updated).as_seconds_f64().max(0.);
+        self.tokens = (self.tokens + elapsed * self.limit.qps).min(burst);
+        self.updated = now;
+        let missing = (n as f64).min(burst) - self.tokens;
+        let delay = time::Duration::seconds_f64((missing / self.limit.qps).max(0.));
+        if delay > max_delay {
+            return None;
+        }
+        self.tokens -= n as f64;
+        Some(delay)
+    }
+}
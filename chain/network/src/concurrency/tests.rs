@@ -1,6 +1,7 @@
 use crate::concurrency::arc_mutex::ArcMutex;
 use crate::concurrency::demux;
 use crate::concurrency::rate;
+use unc_async::time;
 
 #[tokio::test]
 async fn test_demux() {
@@ -97,3 +98,40 @@ fn arc_mutex_update() {
     );
     assert_eq!(v3, *m.load());
 }
+
+#[test]
+fn rate_bucket() {
+    let clock = time::FakeClock::default();
+    let max_delay = time::Duration::seconds(10);
+    let mut bucket = rate::Bucket::new(&clock.clock(), rate::Limit { qps: 100., burst: 1000 });
+    let mut reserve = |n| bucket.reserve(&clock.clock(), n, max_delay);
+    // Bucket starts full.
+    assert_eq!(Some(time::Duration::ZERO), reserve(600));
+    assert_eq!(Some(time::Duration::ZERO), reserve(400));
+    // Requests are delayed until the tokens are refilled at the rate of qps,
+    // in the order in which they were made.
+    assert_eq!(Some(time::Duration::seconds(2)), reserve(200));
+    assert_eq!(Some(time::Duration::seconds(5)), reserve(300));
+    // Requests which would be delayed too long are rejected and don't take any tokens.
+    assert_eq!(None, reserve(600));
+    assert_eq!(Some(time::Duration::seconds(10)), reserve(500));
+}
+
+#[test]
+fn rate_bucket_refill() {
+    let clock = time::FakeClock::default();
+    let max_delay = time::Duration::seconds(100);
+    let mut bucket = rate::Bucket::new(&clock.clock(), rate::Limit { qps: 100., burst: 1000 });
+    assert_eq!(Some(time::Duration::ZERO), bucket.reserve(&clock.clock(), 1000, max_delay));
+    clock.advance(time::Duration::seconds(2));
+    assert_eq!(Some(time::Duration::ZERO), bucket.reserve(&clock.clock(), 200, max_delay));
+    // Refill doesn't exceed burst.
+    clock.advance(time::Duration::seconds(100));
+    assert_eq!(Some(time::Duration::ZERO), bucket.reserve(&clock.clock(), 1000, max_delay));
+    assert_eq!(Some(time::Duration::seconds(1)), bucket.reserve(&clock.clock(), 100, max_delay));
+    // Requests larger than burst are admitted once the bucket is full,
+    // and have to be paid back.
+    clock.advance(time::Duration::seconds(100));
+    assert_eq!(Some(time::Duration::ZERO), bucket.reserve(&clock.clock(), 1500, max_delay));
+    assert_eq!(Some(time::Duration::seconds(6)), bucket.reserve(&clock.clock(), 100, max_delay));
+}
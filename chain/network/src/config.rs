@@ -1,9 +1,12 @@
+use crate::bandwidth;
 use crate::blacklist;
+use crate::capture;
 use crate::concurrency::rate;
 use crate::network_protocol::PeerAddr;
 use crate::network_protocol::PeerInfo;
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_store;
+use crate::peer_manager::reputation;
 use crate::sink::Sink;
 use crate::snapshot_hosts;
 use crate::stun;
@@ -89,6 +92,21 @@ pub struct Tier1 {
     pub enable_outbound: bool,
 }
 
+#[derive(Clone)]
+pub struct Quic {
+    /// Support for gradual QUIC rollout:
+    /// - a node accepts QUIC connections on the UDP port of `node_addr` iff `enable_inbound`
+    ///   is true.
+    /// - a node tries to connect to peers over QUIC first iff `enable_outbound` is true.
+    ///   Peers which don't accept QUIC connections are contacted over TCP instead.
+    pub enable_inbound: bool,
+    pub enable_outbound: bool,
+    /// Timeout for establishing a QUIC connection, after which node falls back to TCP.
+    pub connect_timeout: time::Duration,
+    /// For how long a node connects over TCP to a peer which it failed to reach over QUIC.
+    pub fallback_period: time::Duration,
+}
+
 /// Validated configuration for the peer-to-peer manager.
 #[derive(Clone)]
 pub struct NetworkConfig {
@@ -97,6 +115,10 @@ pub struct NetworkConfig {
     pub validator: Option<ValidatorConfig>,
 
     pub peer_store: peer_store::Config,
+    pub reputation: reputation::Config,
+    pub bandwidth_limits: bandwidth::Limits,
+    /// If set, the network traffic is recorded to capture files.
+    pub capture: Option<capture::Config>,
     pub snapshot_hosts: snapshot_hosts::Config,
     pub whitelist_nodes: Vec<PeerInfo>,
     pub handshake_timeout: time::Duration,
@@ -157,6 +179,11 @@ pub struct NetworkConfig {
     pub routing_table_update_rate_limit: rate::Limit,
     /// Config of the TIER1 network.
     pub tier1: Option<Tier1>,
+    /// Config of the QUIC transport. If None, only TCP is used.
+    pub quic: Option<Quic>,
+    /// Connections are always encrypted if both peers support it.
+    /// If true, connections with peers which don't support encryption are rejected.
+    pub require_encryption: bool,
 
     // Whether to ignore tombstones some time after startup.
     //
@@ -287,6 +314,29 @@ impl NetworkConfig {
                 ban_window: cfg.ban_window.try_into()?,
                 peer_expiration_duration: cfg.peer_expiration_duration.try_into()?,
             },
+            reputation: reputation::Config {
+                half_life: time::Duration::minutes(10),
+                slow_response: time::Duration::seconds(2),
+                response_timeout: time::Duration::seconds(10),
+                deprioritize_threshold: -10.,
+                ban_threshold: cfg.experimental.reputation_ban_threshold,
+                cache_size: 1000,
+            },
+            bandwidth_limits: cfg
+                .experimental
+                .bandwidth_limits
+                .iter()
+                .map(|(msg_type, limit)| {
+                    let limit =
+                        rate::Limit { qps: limit.bytes_per_sec as f64, burst: limit.burst_bytes };
+                    (msg_type.clone(), limit)
+                })
+                .collect(),
+            capture: cfg.experimental.capture.as_ref().map(|c| capture::Config {
+                dir: c.dir.clone(),
+                max_file_size: c.max_file_size_bytes,
+                max_files: c.max_files,
+            }),
             snapshot_hosts: snapshot_hosts::Config {
                 snapshot_hosts_cache_size: cfg.snapshot_hosts_cache_size,
             },
@@ -334,6 +384,17 @@ impl NetworkConfig {
                 enable_inbound: cfg.experimental.tier1_enable_inbound,
                 enable_outbound: cfg.experimental.tier1_enable_outbound,
             }),
+            quic: if cfg.experimental.quic_enable_inbound || cfg.experimental.quic_enable_outbound {
+                Some(Quic {
+                    enable_inbound: cfg.experimental.quic_enable_inbound,
+                    enable_outbound: cfg.experimental.quic_enable_outbound,
+                    connect_timeout: time::Duration::seconds(1),
+                    fallback_period: time::Duration::minutes(10),
+                })
+            } else {
+                None
+            },
+            require_encryption: cfg.experimental.require_encryption,
             inbound_disabled: cfg.experimental.inbound_disabled,
             skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
                 Some(time::Duration::seconds(cfg.experimental.skip_sending_tombstones_seconds))
@@ -372,6 +433,16 @@ impl NetworkConfig {
                 peer_expiration_duration: time::Duration::seconds(60 * 60),
                 connect_only_to_boot_nodes: false,
             },
+            reputation: reputation::Config {
+                half_life: time::Duration::minutes(10),
+                slow_response: time::Duration::seconds(2),
+                response_timeout: time::Duration::seconds(10),
+                deprioritize_threshold: -10.,
+                ban_threshold: -50.,
+                cache_size: 1000,
+            },
+            bandwidth_limits: bandwidth::Limits::new(),
+            capture: None,
             snapshot_hosts: snapshot_hosts::Config { snapshot_hosts_cache_size: 1000 },
             whitelist_nodes: vec![],
             handshake_timeout: time::Duration::seconds(5),
@@ -406,6 +477,14 @@ impl NetworkConfig {
                 enable_inbound: true,
                 enable_outbound: true,
             }),
+            // Tests run over TCP, unless UNC_NETWORK_TEST_QUIC is set.
+            quic: std::env::var_os("UNC_NETWORK_TEST_QUIC").map(|_| Quic {
+                enable_inbound: true,
+                enable_outbound: true,
+                connect_timeout: time::Duration::seconds(1),
+                fallback_period: time::Duration::minutes(10),
+            }),
+            require_encryption: false,
             skip_tombstones: None,
             event_sink: Sink::null(),
         }
@@ -450,6 +529,20 @@ impl NetworkConfig {
             );
         }
 
+        if !(self.reputation.ban_threshold < self.reputation.deprioritize_threshold) {
+            anyhow::bail!(
+                "reputation ban_threshold({}) must be lower than deprioritize_threshold({})",
+                self.reputation.ban_threshold,
+                self.reputation.deprioritize_threshold
+            );
+        }
+
+        bandwidth::validate(&self.bandwidth_limits).context("bandwidth_limits")?;
+        if let Some(capture) = &self.capture {
+            if capture.max_file_size == 0 || capture.max_files == 0 {
+                anyhow::bail!("capture max_file_size and max_files have to be >0");
+            }
+        }
         self.accounts_data_broadcast_rate_limit
             .validate()
             .context("accounts_Data_broadcast_rate_limit")?;
@@ -1,7 +1,21 @@
 use crate::network_protocol::PeerAddr;
 use crate::stun;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
+/// Peers whose reputation score drops below this threshold are banned.
+fn default_reputation_ban_threshold() -> f64 {
+    -50.
+}
+/// Size of a network capture file, after which a new file is started.
+fn default_capture_max_file_size_bytes() -> u64 {
+    100_000_000
+}
+/// Number of network capture files to keep.
+fn default_capture_max_files() -> usize {
+    10
+}
 /// Time to persist Accounts Id in the router without removing them in seconds.
 pub const TTL_ACCOUNT_ID_ROUTER: u64 = 60 * 60;
 
@@ -222,11 +236,59 @@ pub struct ExperimentalConfig {
     /// See `unc_network::config::Tier1::new_connections_per_attempt`.
     pub tier1_new_connections_per_attempt: u64,
 
+    /// See `unc_network::config::Quic::enable_inbound`.
+    #[serde(default)]
+    pub quic_enable_inbound: bool,
+
+    /// See `unc_network::config::Quic::enable_outbound`.
+    #[serde(default)]
+    pub quic_enable_outbound: bool,
+
+    /// See `unc_network::config::NetworkConfig::require_encryption`.
+    #[serde(default)]
+    pub require_encryption: bool,
+
+    /// See `unc_network::peer_manager::reputation::Config::ban_threshold`.
+    #[serde(default = "default_reputation_ban_threshold")]
+    pub reputation_ban_threshold: f64,
+
+    /// Limits of the outbound bandwidth spent on responses to the requests of other nodes,
+    /// keyed by message type (e.g. "Block", "PartialEncodedChunkResponse",
+    /// "VersionedStateResponse"). Responses which would exceed the limit are delayed.
+    /// Applies to TIER2 connections only. No limits by default.
+    #[serde(default)]
+    pub bandwidth_limits: BTreeMap<String, BandwidthLimit>,
+
+    /// If set, all messages exchanged with the peers are recorded to files,
+    /// which can be replayed later with the mock-node tool.
+    /// See `unc_network::capture`.
+    #[serde(default)]
+    pub capture: Option<CaptureConfig>,
+
     /// See `NetworkConfig`.
     /// Fields set here will override the NetworkConfig fields.
     pub network_config_overrides: NetworkConfigOverrides,
 }
 
+/// Limit of the outbound bandwidth for a single message type, shared by all connections.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BandwidthLimit {
+    /// Sustained rate at which messages of the given type can be sent.
+    pub bytes_per_sec: u64,
+    /// Number of bytes which can be sent at once, after a period of inactivity.
+    pub burst_bytes: u64,
+}
+
+/// See `unc_network::capture::Config`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CaptureConfig {
+    pub dir: PathBuf,
+    #[serde(default = "default_capture_max_file_size_bytes")]
+    pub max_file_size_bytes: u64,
+    #[serde(default = "default_capture_max_files")]
+    pub max_files: usize,
+}
+
 /// Overrides values from NetworkConfig.
 /// This enables the user to override the hardcoded values.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
@@ -254,6 +316,12 @@ impl Default for ExperimentalConfig {
             tier1_enable_outbound: true,
             tier1_connect_interval: Duration::from_secs(60),
             tier1_new_connections_per_attempt: 50,
+            quic_enable_inbound: false,
+            quic_enable_outbound: false,
+            require_encryption: false,
+            reputation_ban_threshold: default_reputation_ban_threshold(),
+            bandwidth_limits: BTreeMap::new(),
+            capture: None,
             network_config_overrides: Default::default(),
         }
     }
@@ -2,10 +2,13 @@ pub use crate::peer_manager::peer_manager_actor::{Event, PeerManagerActor};
 
 mod accounts_data;
 mod announce_accounts;
+mod bandwidth;
 mod network_protocol;
+mod noise;
 mod peer;
 mod peer_manager;
 mod private_actix;
+mod quic;
 mod snapshot_hosts;
 mod stats;
 mod store;
@@ -13,6 +16,7 @@ mod stun;
 
 pub mod actix;
 pub mod blacklist;
+pub mod capture;
 pub mod client;
 pub mod concurrency;
 pub mod config;
@@ -14,6 +14,7 @@ impl From<&net::Handshake> for mem::Handshake {
             sender_chain_info: x.sender_chain_info.clone(),
             partial_edge_info: x.partial_edge_info.clone(),
             owned_account: None,
+            noise: None,
         }
     }
 }
@@ -253,6 +254,15 @@ impl From<&mem::PeerMessage> for net::PeerMessage {
                 net::PeerMessage::VersionedStateResponse(sri)
             }
             mem::PeerMessage::SyncSnapshotHosts(ssh) => net::PeerMessage::SyncSnapshotHosts(ssh),
+
+            // These messages are not supported, we translate them to an empty RoutingTableUpdate.
+            mem::PeerMessage::EpochSyncRequest(_)
+            | mem::PeerMessage::EpochSyncResponse(_)
+            | mem::PeerMessage::EpochSyncFinalizationRequest(_)
+            | mem::PeerMessage::EpochSyncFinalizationResponse(_)
+            | mem::PeerMessage::BlockHeadersRangeRequest(..) => {
+                net::PeerMessage::SyncRoutingTable(net::RoutingTableUpdate::default())
+            }
         }
     }
 }
@@ -39,6 +39,7 @@ use unc_crypto::Signature;
 use unc_o11y::OpenTelemetrySpanExt;
 use unc_primitives::block::{Approval, Block, BlockHeader, GenesisId};
 use unc_primitives::challenge::Challenge;
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::merkle::combine_hash;
 use unc_primitives::network::{AnnounceAccount, PeerId};
@@ -48,7 +49,7 @@ use unc_primitives::sharding::{
 use unc_primitives::state_sync::{ShardStateSyncResponse, ShardStateSyncResponseV1};
 use unc_primitives::transaction::SignedTransaction;
 use unc_primitives::types::AccountId;
-use unc_primitives::types::{BlockHeight, ShardId};
+use unc_primitives::types::{BlockHeight, EpochId, ShardId};
 use unc_primitives::validator_signer::ValidatorSigner;
 use unc_primitives::views::FinalExecutionOutcomeView;
 
@@ -350,6 +351,8 @@ pub struct Handshake {
     pub(crate) partial_edge_info: PartialEdgeInfo,
     /// Account owned by the sender.
     pub(crate) owned_account: Option<SignedOwnedAccount>,
+    /// Noise handshake message, see noise.rs.
+    pub(crate) noise: Option<Vec<u8>>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -414,6 +417,9 @@ pub enum PeerMessage {
 
     BlockHeadersRequest(Vec<CryptoHash>),
     BlockHeaders(Vec<BlockHeader>),
+    /// Requests the headers of the canonical chain with heights in the given inclusive range.
+    /// Responded with `BlockHeaders`.
+    BlockHeadersRangeRequest(BlockHeight, BlockHeight),
 
     BlockRequest(CryptoHash),
     Block(Block),
@@ -429,6 +435,11 @@ pub enum PeerMessage {
     StateRequestHeader(ShardId, CryptoHash),
     StateRequestPart(ShardId, CryptoHash, u64),
     VersionedStateResponse(StateResponseInfo),
+
+    EpochSyncRequest(EpochId),
+    EpochSyncResponse(Box<EpochSyncResponse>),
+    EpochSyncFinalizationRequest(EpochId),
+    EpochSyncFinalizationResponse(Box<EpochSyncFinalizationResponse>),
 }
 
 impl fmt::Display for PeerMessage {
@@ -501,7 +512,13 @@ impl PeerMessage {
 
 // TODO(#1313): Use Box
 #[derive(
-    borsh::BorshSerialize, borsh::BorshDeserialize, PartialEq, Eq, Clone, strum::IntoStaticStr,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    strum::IntoStaticStr,
+    strum::EnumVariantNames,
 )]
 pub enum RoutedMessageBody {
     BlockApproval(Approval),
@@ -802,6 +819,13 @@ impl StateResponseInfo {
         }
     }
 
+    pub fn part_id(&self) -> Option<u64> {
+        match self {
+            Self::V1(info) => info.state_response.part_id(),
+            Self::V2(info) => info.state_response.part_id(),
+        }
+    }
+
     pub fn take_state_response(self) -> ShardStateSyncResponse {
         match self {
             Self::V1(info) => ShardStateSyncResponse::V1(info.state_response),
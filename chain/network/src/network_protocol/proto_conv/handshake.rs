@@ -90,6 +90,7 @@ impl From<&Handshake> for proto::Handshake {
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
             owned_account: x.owned_account.as_ref().map(Into::into).into(),
+            noise: x.noise.clone().unwrap_or_default(),
             ..Self::default()
         }
     }
@@ -120,6 +121,7 @@ impl TryFrom<&proto::Handshake> for Handshake {
                 .map_err(Self::Error::PartialEdgeInfo)?,
             owned_account: try_from_optional(&p.owned_account)
                 .map_err(Self::Error::OwnedAccount)?,
+            noise: if p.noise.is_empty() { None } else { Some(p.noise.clone()) },
         })
     }
 }
@@ -16,7 +16,9 @@ use std::sync::Arc;
 use unc_async::time::error::ComponentRange;
 use unc_primitives::block::{Block, BlockHeader};
 use unc_primitives::challenge::Challenge;
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::transaction::SignedTransaction;
+use unc_primitives::types::EpochId;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ParseRoutingTableUpdateError {
@@ -283,6 +285,13 @@ impl From<&PeerMessage> for proto::PeerMessage {
                         ..Default::default()
                     })
                 }
+                PeerMessage::BlockHeadersRangeRequest(start_height, end_height) => {
+                    ProtoMT::BlockHeadersRangeRequest(proto::BlockHeadersRangeRequest {
+                        start_height: *start_height,
+                        end_height: *end_height,
+                        ..Default::default()
+                    })
+                }
                 PeerMessage::BlockRequest(bh) => ProtoMT::BlockRequest(proto::BlockRequest {
                     block_hash: MF::some(bh.into()),
                     ..Default::default()
@@ -331,6 +340,30 @@ impl From<&PeerMessage> for proto::PeerMessage {
                         ..Default::default()
                     })
                 }
+                PeerMessage::EpochSyncRequest(epoch_id) => {
+                    ProtoMT::EpochSyncRequest(proto::EpochSyncRequest {
+                        epoch_id: MF::some((&epoch_id.0).into()),
+                        ..Default::default()
+                    })
+                }
+                PeerMessage::EpochSyncResponse(esr) => {
+                    ProtoMT::EpochSyncResponse(proto::EpochSyncResponse {
+                        borsh: borsh::to_vec(&esr).unwrap(),
+                        ..Default::default()
+                    })
+                }
+                PeerMessage::EpochSyncFinalizationRequest(epoch_id) => {
+                    ProtoMT::EpochSyncFinalizationRequest(proto::EpochSyncFinalizationRequest {
+                        epoch_id: MF::some((&epoch_id.0).into()),
+                        ..Default::default()
+                    })
+                }
+                PeerMessage::EpochSyncFinalizationResponse(esfr) => {
+                    ProtoMT::EpochSyncFinalizationResponse(proto::EpochSyncFinalizationResponse {
+                        borsh: borsh::to_vec(&esfr).unwrap(),
+                        ..Default::default()
+                    })
+                }
             }),
             ..Default::default()
         }
@@ -341,6 +374,7 @@ pub type ParsePeersRequestError = std::io::Error;
 pub type ParseTransactionError = std::io::Error;
 pub type ParseRoutedError = std::io::Error;
 pub type ParseChallengeError = std::io::Error;
+pub type ParseEpochSyncResponseError = std::io::Error;
 
 #[derive(thiserror::Error, Debug)]
 pub enum ParsePeerMessageError {
@@ -386,6 +420,14 @@ pub enum ParsePeerMessageError {
     StateResponse(ParseRequiredError<ParseStateInfoError>),
     #[error("sync_snapshot_hosts: {0}")]
     SyncSnapshotHosts(ParseSyncSnapshotHostsError),
+    #[error("epoch_sync_request: {0}")]
+    EpochSyncRequest(ParseRequiredError<ParseCryptoHashError>),
+    #[error("epoch_sync_response: {0}")]
+    EpochSyncResponse(ParseEpochSyncResponseError),
+    #[error("epoch_sync_finalization_request: {0}")]
+    EpochSyncFinalizationRequest(ParseRequiredError<ParseCryptoHashError>),
+    #[error("epoch_sync_finalization_response: {0}")]
+    EpochSyncFinalizationResponse(ParseEpochSyncResponseError),
 }
 
 impl TryFrom<&proto::PeerMessage> for PeerMessage {
@@ -447,6 +489,9 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
             ProtoMT::BlockHeadersResponse(bhr) => PeerMessage::BlockHeaders(
                 try_from_slice(&bhr.block_headers).map_err(Self::Error::BlockHeadersResponse)?,
             ),
+            ProtoMT::BlockHeadersRangeRequest(bhrr) => {
+                PeerMessage::BlockHeadersRangeRequest(bhrr.start_height, bhrr.end_height)
+            }
             ProtoMT::BlockRequest(br) => PeerMessage::BlockRequest(
                 try_from_required(&br.block_hash).map_err(Self::Error::BlockRequest)?,
             ),
@@ -487,6 +532,25 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
             ProtoMT::SyncSnapshotHosts(srh) => PeerMessage::SyncSnapshotHosts(
                 srh.try_into().map_err(Self::Error::SyncSnapshotHosts)?,
             ),
+            ProtoMT::EpochSyncRequest(esr) => PeerMessage::EpochSyncRequest(EpochId(
+                try_from_required(&esr.epoch_id).map_err(Self::Error::EpochSyncRequest)?,
+            )),
+            ProtoMT::EpochSyncResponse(esr) => PeerMessage::EpochSyncResponse(Box::new(
+                EpochSyncResponse::try_from_slice(&esr.borsh)
+                    .map_err(Self::Error::EpochSyncResponse)?,
+            )),
+            ProtoMT::EpochSyncFinalizationRequest(esfr) => {
+                PeerMessage::EpochSyncFinalizationRequest(EpochId(
+                    try_from_required(&esfr.epoch_id)
+                        .map_err(Self::Error::EpochSyncFinalizationRequest)?,
+                ))
+            }
+            ProtoMT::EpochSyncFinalizationResponse(esfr) => {
+                PeerMessage::EpochSyncFinalizationResponse(Box::new(
+                    EpochSyncFinalizationResponse::try_from_slice(&esfr.borsh)
+                        .map_err(Self::Error::EpochSyncFinalizationResponse)?,
+                ))
+            }
         })
     }
 }
@@ -70,6 +70,10 @@ fn serialize_deserialize_protobuf_only() {
             incremental: true,
             requesting_full_sync: true,
         }),
+        PeerMessage::EpochSyncRequest(chain.blocks[5].header().epoch_id().clone()),
+        PeerMessage::EpochSyncResponse(Box::new(EpochSyncResponse::UpToDate)),
+        PeerMessage::EpochSyncFinalizationRequest(chain.blocks[5].header().epoch_id().clone()),
+        PeerMessage::BlockHeadersRangeRequest(5, 10),
     ];
     for m in msgs {
         let m2 = PeerMessage::deserialize(Encoding::Proto, &m.serialize(Encoding::Proto))
@@ -0,0 +1,195 @@
+//! Noise protocol encryption of peer connections.
+//!
+//! The Noise IK handshake is piggybacked on the Handshake messages exchanged by the peers:
+//! outbound peer puts the first handshake message in its Handshake, inbound peer puts the
+//! second one in its response. The static keys of the peers are derived from their node keys,
+//! so that completing the handshake proves that the peers own the keys behind their PeerIds.
+//! Peers which don't support encryption ignore the Handshake.noise field and the connection
+//! stays in plaintext, unless NetworkConfig::require_encryption is set.
+//! Handshake.noise is not signed, so it could be stripped by a man-in-the-middle: peers at
+//! ProtocolFeature::PeerEncryption or above are required to send it.
+use anyhow::Context as _;
+use unc_crypto::{PublicKey, SecretKey};
+use unc_primitives::network::PeerId;
+
+const NOISE_PARAMS: &str = "Noise_IK_25519_ChaChaPoly_BLAKE2s";
+/// Domain separation of the handshake.
+const PROLOGUE: &[u8] = b"unc-p2p-noise/1";
+/// Maximal length of a single Noise message.
+const MAX_MESSAGE_LEN: usize = 65535;
+/// Length of the authentication tag appended to every encrypted Noise message.
+const TAG_LEN: usize = 16;
+/// Maximal length of the plaintext encrypted into a single Noise message.
+/// Larger frames are split into multiple Noise messages.
+const MAX_CHUNK_LEN: usize = MAX_MESSAGE_LEN - TAG_LEN;
+
+/// X25519 private key corresponding to the ed25519 node key.
+fn static_private_key(node_key: &SecretKey) -> anyhow::Result<[u8; 32]> {
+    match node_key {
+        SecretKey::ED25519(key) => {
+            // ED25519SecretKey consists of the seed followed by the public key.
+            let seed = key.0[..ed25519_dalek::SECRET_KEY_LENGTH].try_into().unwrap();
+            Ok(ed25519_dalek::SigningKey::from_bytes(seed).to_scalar_bytes())
+        }
+        _ => anyhow::bail!("only ed25519 node keys are supported"),
+    }
+}
+
+/// X25519 public key corresponding to the ed25519 PeerId.
+fn static_public_key(peer_id: &PeerId) -> anyhow::Result<[u8; 32]> {
+    match peer_id.public_key() {
+        PublicKey::ED25519(key) => {
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&key.0)
+                .map_err(|err| anyhow::anyhow!("invalid peer id: {err}"))?;
+            Ok(key.to_montgomery().to_bytes())
+        }
+        _ => anyhow::bail!("only ed25519 peer ids are supported"),
+    }
+}
+
+fn builder<'a>() -> snow::Builder<'a> {
+    snow::Builder::new(NOISE_PARAMS.parse().unwrap()).prologue(PROLOGUE)
+}
+
+/// Outbound side of the handshake, awaiting the response.
+pub(crate) struct Initiator(snow::HandshakeState);
+
+impl Initiator {
+    /// Starts a handshake with the peer. Returns the message to be sent to the peer.
+    pub fn new(node_key: &SecretKey, peer_id: &PeerId) -> anyhow::Result<(Self, Vec<u8>)> {
+        let mut state = builder()
+            .local_private_key(&static_private_key(node_key)?)
+            .remote_public_key(&static_public_key(peer_id)?)
+            .build_initiator()?;
+        let mut msg = vec![0; MAX_MESSAGE_LEN];
+        let n = state.write_message(&[], &mut msg)?;
+        msg.truncate(n);
+        Ok((Self(state), msg))
+    }
+
+    /// Completes the handshake, given the response of the peer.
+    pub fn finish(mut self, msg: &[u8]) -> anyhow::Result<Cipher> {
+        self.0.read_message(msg, &mut vec![0; MAX_MESSAGE_LEN])?;
+        Ok(Cipher(self.0.into_stateless_transport_mode()?))
+    }
+}
+
+/// Inbound side of the handshake: verifies that the message has been sent by `peer_id`.
+/// Returns the established Cipher and the response to be sent to the peer.
+pub(crate) fn respond(
+    node_key: &SecretKey,
+    peer_id: &PeerId,
+    msg: &[u8],
+) -> anyhow::Result<(Cipher, Vec<u8>)> {
+    let mut state =
+        builder().local_private_key(&static_private_key(node_key)?).build_responder()?;
+    state.read_message(msg, &mut vec![0; MAX_MESSAGE_LEN])?;
+    let remote = state.get_remote_static().context("missing remote static key")?;
+    anyhow::ensure!(remote == static_public_key(peer_id)?, "static key doesn't match {peer_id}");
+    let mut resp = vec![0; MAX_MESSAGE_LEN];
+    let n = state.write_message(&[], &mut resp)?;
+    resp.truncate(n);
+    Ok((Cipher(state.into_stateless_transport_mode()?), resp))
+}
+
+/// Encryption of the frames of an established connection.
+/// Every Noise message is encrypted with a distinct nonce. Nonces are provided by the caller,
+/// so that the frames sent over independent streams (see quic::Lane) can use disjoint
+/// nonce ranges. Receiver has to decrypt the messages in the order they were encrypted.
+pub(crate) struct Cipher(snow::StatelessTransportState);
+
+impl Cipher {
+    /// Number of Noise messages (and therefore nonces) used to encrypt a frame of length `len`.
+    pub fn messages(len: usize) -> usize {
+        len.div_ceil(MAX_CHUNK_LEN).max(1)
+    }
+
+    /// Length of the encrypted frame of length `len`.
+    pub fn encrypted_len(len: usize) -> usize {
+        len + Self::messages(len) * TAG_LEN
+    }
+
+    /// Encrypts the frame, using nonces starting from `nonce`.
+    /// Returns the ciphertext and the number of nonces used.
+    pub fn encrypt(&self, nonce: u64, frame: &[u8]) -> (Vec<u8>, u64) {
+        let mut out = vec![0; Self::encrypted_len(frame.len())];
+        // chunks() of an empty slice is empty, but an empty frame still needs a message.
+        let chunks = frame.chunks(MAX_CHUNK_LEN).chain(frame.is_empty().then_some(&[][..]));
+        let mut offset = 0;
+        for (i, chunk) in chunks.enumerate() {
+            // Fails only if the output buffer is too small.
+            offset += self.0.write_message(nonce + i as u64, chunk, &mut out[offset..]).unwrap();
+        }
+        (out, Self::messages(frame.len()) as u64)
+    }
+
+    /// Decrypts the frame, using nonces starting from `nonce`.
+    /// Returns the plaintext and the number of nonces used.
+    pub fn decrypt(&self, nonce: u64, frame: &[u8]) -> Result<(Vec<u8>, u64), snow::Error> {
+        if frame.is_empty() {
+            return Err(snow::Error::Decrypt);
+        }
+        let messages = frame.len().div_ceil(MAX_MESSAGE_LEN);
+        let mut out = vec![0; frame.len().saturating_sub(messages * TAG_LEN)];
+        let mut offset = 0;
+        for (i, msg) in frame.chunks(MAX_MESSAGE_LEN).enumerate() {
+            offset += self.0.read_message(nonce + i as u64, msg, &mut out[offset..])?;
+        }
+        Ok((out, messages as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network_protocol::testonly as data;
+    use crate::testonly::make_rng;
+    use rand::Rng as _;
+
+    fn handshake(key1: &SecretKey, key2: &SecretKey) -> anyhow::Result<(Cipher, Cipher)> {
+        let id1 = PeerId::new(key1.public_key());
+        let id2 = PeerId::new(key2.public_key());
+        let (initiator, msg) = Initiator::new(key1, &id2)?;
+        let (responder, resp) = respond(key2, &id1, &msg)?;
+        Ok((initiator.finish(&resp)?, responder))
+    }
+
+    #[test]
+    fn encrypt_decrypt() {
+        let mut rng = make_rng(9823754);
+        let key1 = data::make_secret_key(&mut rng);
+        let key2 = data::make_secret_key(&mut rng);
+        let (c1, c2) = handshake(&key1, &key2).unwrap();
+        let mut nonce = 0;
+        for size in [0, 1, 1000, MAX_CHUNK_LEN, MAX_CHUNK_LEN + 1, 3 * MAX_MESSAGE_LEN] {
+            let mut frame = vec![0; size];
+            rng.fill(&mut frame[..]);
+            for (send, recv) in [(&c1, &c2), (&c2, &c1)] {
+                let (encrypted, n) = send.encrypt(nonce, &frame);
+                assert_eq!(encrypted.len(), Cipher::encrypted_len(size));
+                assert_eq!((frame.clone(), n), recv.decrypt(nonce, &encrypted).unwrap());
+                // Decryption with a different nonce fails.
+                assert!(recv.decrypt(nonce + 1, &encrypted).is_err());
+                // Decryption in the direction of the sender fails.
+                assert!(send.decrypt(nonce, &encrypted).is_err());
+            }
+            nonce += 1 << 20;
+        }
+    }
+
+    #[test]
+    fn wrong_key() {
+        let mut rng = make_rng(9823754);
+        let key1 = data::make_secret_key(&mut rng);
+        let key2 = data::make_secret_key(&mut rng);
+        let key3 = data::make_secret_key(&mut rng);
+        let id1 = PeerId::new(key1.public_key());
+        let id2 = PeerId::new(key2.public_key());
+        // Initiator has to know the static key of the responder.
+        let (_, msg) = Initiator::new(&key1, &id2).unwrap();
+        assert!(respond(&key3, &id1, &msg).is_err());
+        // Responder verifies the static key of the initiator.
+        let (_, msg) = Initiator::new(&key3, &id2).unwrap();
+        assert!(respond(&key2, &id1, &msg).is_err());
+    }
+}
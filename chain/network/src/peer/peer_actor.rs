@@ -1,4 +1,5 @@
 use crate::accounts_data::AccountDataError;
+use crate::capture;
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
 use crate::config::PEERS_RESPONSE_MAX_PEERS;
@@ -9,13 +10,16 @@ use crate::network_protocol::{
     RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate, StateResponseInfo, SyncAccountsData,
     SyncSnapshotHosts,
 };
+use crate::noise;
 use crate::peer::stream;
 use crate::peer::tracker::Tracker;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, PRUNE_EDGES_AFTER};
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_manager_actor::MAX_TIER2_PEERS;
+use crate::peer_manager::reputation;
 use crate::private_actix::{RegisterPeerError, SendMessage};
+use crate::quic;
 use crate::routing::edge::verify_nonce;
 use crate::routing::NetworkTopologyChange;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
@@ -47,7 +51,7 @@ use unc_primitives::network::{AnnounceAccount, PeerId};
 use unc_primitives::types::EpochId;
 use unc_primitives::utils::DisplayOption;
 use unc_primitives::version::{
-    ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+    ProtocolFeature, ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
 
 /// How often to request peers from active peers.
@@ -114,6 +118,8 @@ pub(crate) enum ClosingReason {
     TooLargeClockSkew,
     #[error("owned_account.peer_id doesn't match handshake.sender_peer_id")]
     OwnedAccountMismatch,
+    #[error("peer doesn't support encryption, which is required by this node")]
+    EncryptionRequired,
     #[error("PeerActor stopped NOT via PeerActor::stop()")]
     Unknown,
 }
@@ -135,6 +141,7 @@ impl ClosingReason {
             ClosingReason::DisconnectMessage => false, // graceful disconnect
             ClosingReason::TooLargeClockSkew => true, // reconnect will fail for the same reason
             ClosingReason::OwnedAccountMismatch => true, // misbehaving peer
+            ClosingReason::EncryptionRequired => true, // reconnect will fail for the same reason
             ClosingReason::Unknown => false,        // only happens in tests
         }
     }
@@ -157,6 +164,8 @@ pub(crate) struct PeerActor {
 
     /// Framed wrapper to send messages through the TCP connection.
     framed: stream::FramedStream<PeerActor>,
+    /// Noise handshake started by the last Handshake sent to an outbound peer.
+    noise_initiator: Option<noise::Initiator>,
 
     /// Tracker for requests and responses.
     tracker: Arc<Mutex<Tracker>>,
@@ -316,6 +325,7 @@ impl PeerActor {
                     },
                     peer_status: PeerStatus::Connecting(send, connecting_status),
                     framed,
+                    noise_initiator: None,
                     tracker: Default::default(),
                     stats,
                     routed_message_cache: LruCache::new(ROUTED_MESSAGE_CACHE_SIZE),
@@ -381,7 +391,7 @@ impl PeerActor {
     }
 
     fn send_message_with_encoding(&self, msg: &PeerMessage, enc: Encoding) {
-        let msg_type: &str = msg.msg_variant();
+        let msg_type: &'static str = msg.msg_variant();
         let _span = tracing::trace_span!(
             target: "network",
             "send_message_with_encoding",
@@ -408,11 +418,49 @@ impl PeerActor {
             _ => (),
         };
 
+        self.send_frame(msg, msg.serialize(enc));
+    }
+
+    /// Sends a response to a request of the peer. Responses exceeding the bandwidth limit of
+    /// their type are delayed until the limit allows sending them, or dropped if that would
+    /// take too long (see `bandwidth::Limiter`).
+    fn send_response(&self, ctx: &mut actix::Context<Self>, msg: &PeerMessage) {
+        // TIER1 connections are used for consensus messages and are not subject to the limits.
+        let (tcp::Tier::T2, Some(enc)) = (self.tier, self.encoding()) else {
+            return self.send_message_or_log(msg);
+        };
+        let msg_type = msg.msg_variant();
         let bytes = msg.serialize(enc);
-        self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
+        let bytes_len = bytes.len() as u64;
+        let delay = self.network_state.bandwidth.reserve(&self.clock, msg_type, bytes_len);
+        if delay == Some(time::Duration::ZERO) {
+            return self.send_frame(msg, bytes);
+        }
+        self.stats.bandwidth.record_throttled(msg_type, bytes_len);
+        metrics::PEER_MESSAGE_THROTTLED_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
+        metrics::PEER_MESSAGE_THROTTLED_BY_TYPE_BYTES
+            .with_label_values(&[msg_type])
+            .inc_by(bytes_len);
+        let Some(delay) = delay else {
+            tracing::debug!(target: "network", msg_type, msg_len = bytes_len, "dropping response exceeding the bandwidth limit");
+            metrics::PEER_RESPONSE_DROPPED_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
+            return;
+        };
+        tracing::trace!(target: "network", msg_type, msg_len = bytes_len, ?delay, "delaying response exceeding the bandwidth limit");
+        let msg = msg.clone();
+        unc_performance_metrics::actix::run_later(ctx, delay.try_into().unwrap(), move |act, _| {
+            act.send_frame(&msg, bytes)
+        });
+    }
+
+    fn send_frame(&self, msg: &PeerMessage, bytes: Vec<u8>) {
+        let msg_type: &'static str = msg.msg_variant();
         let bytes_len = bytes.len();
+        self.stats.bandwidth.record_sent(msg_type, bytes_len as u64);
+        self.capture(capture::Direction::Outbound, msg);
+        self.tracker.lock().increment_sent(&self.clock, bytes_len as u64);
         tracing::trace!(target: "network", msg_len = bytes_len);
-        self.framed.send(stream::Frame(bytes));
+        self.framed.send(quic::Lane::of(msg), stream::Frame(bytes));
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
         metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
         metrics::PEER_MESSAGE_SENT_BY_TYPE_BYTES
@@ -420,7 +468,32 @@ impl PeerActor {
             .inc_by(bytes_len as u64);
     }
 
-    fn send_handshake(&self, spec: HandshakeSpec) {
+    /// Records the message, if network capture is enabled.
+    /// Only messages exchanged after the handshake are captured.
+    fn capture(&self, direction: capture::Direction, msg: &PeerMessage) {
+        if let (Some(recorder), PeerStatus::Ready(conn)) =
+            (&self.network_state.capture, &self.peer_status)
+        {
+            recorder.record(&self.clock, &conn.peer_info.id, direction, msg);
+        }
+    }
+
+    /// Starts a Noise handshake with an outbound peer.
+    /// Returns the message to be included in the Handshake.
+    fn start_noise(&mut self, peer_id: &PeerId) -> Option<Vec<u8>> {
+        match noise::Initiator::new(&self.network_state.config.node_key, peer_id) {
+            Ok((initiator, msg)) => {
+                self.noise_initiator = Some(initiator);
+                Some(msg)
+            }
+            Err(err) => {
+                tracing::warn!(target: "network", ?err, "failed to start Noise handshake with {peer_id}");
+                None
+            }
+        }
+    }
+
+    fn send_handshake(&self, spec: HandshakeSpec, noise: Option<Vec<u8>>) {
         let (height, tracked_shards) =
             if let Some(chain_info) = self.network_state.chain_info.load().as_ref() {
                 (chain_info.block.header().height(), chain_info.tracked_shards.clone())
@@ -449,6 +522,7 @@ impl PeerActor {
                 }
                 .sign(vc.signer.as_ref())
             }),
+            noise,
         };
         let msg = match spec.tier {
             tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
@@ -482,6 +556,7 @@ impl PeerActor {
         handshake: Handshake,
     ) {
         tracing::debug!(target: "network", "{:?}: Received handshake {:?}", self.my_node_info.id, handshake);
+        let noise_initiator = self.noise_initiator.take();
         let cs = match &self.peer_status {
             PeerStatus::Connecting(_, it) => it,
             _ => panic!("process_handshake called in non-connecting state"),
@@ -588,6 +663,41 @@ impl PeerActor {
             }
         }
 
+        // Complete the Noise handshake, unless the peer doesn't support encryption.
+        // Inbound peer responds with the second message of the Noise handshake.
+        let noise = match (cs, noise_initiator, &handshake.noise) {
+            (ConnectingStatus::Outbound { .. }, Some(initiator), Some(msg)) => {
+                initiator.finish(msg).map(|cipher| (Some(cipher), None))
+            }
+            (ConnectingStatus::Inbound { .. }, _, Some(msg)) => {
+                noise::respond(&self.network_state.config.node_key, &handshake.sender_peer_id, msg)
+                    .map(|(cipher, resp)| (Some(cipher), Some(resp)))
+            }
+            _ => Ok((None, None)),
+        };
+        let (cipher, noise_response) = match noise {
+            Ok(it) => it,
+            Err(err) => {
+                tracing::warn!(target: "network", ?err, "Noise handshake failed. Disconnecting peer {}", handshake.sender_peer_id);
+                self.stop(ctx, ClosingReason::HandshakeFailed);
+                return;
+            }
+        };
+        if cipher.is_none() && self.network_state.config.require_encryption {
+            tracing::debug!(target: "network", "Peer {} doesn't support encryption. Disconnecting", handshake.sender_peer_id);
+            self.stop(ctx, ClosingReason::EncryptionRequired);
+            return;
+        }
+        // Handshake.noise is not signed, hence a peer which always encrypts its connections
+        // can't be told apart from a man-in-the-middle stripping the Noise message.
+        if cipher.is_none()
+            && handshake.protocol_version >= ProtocolFeature::PeerEncryption.protocol_version()
+        {
+            tracing::debug!(target: "network", "Peer {} didn't send the Noise handshake. Disconnecting", handshake.sender_peer_id);
+            self.stop(ctx, ClosingReason::HandshakeFailed);
+            return;
+        }
+
         // Merge partial edges.
         let nonce = handshake.partial_edge_info.nonce;
         let partial_edge_info = match cs {
@@ -694,7 +804,11 @@ impl PeerActor {
                                 tier,
                                 protocol_version: handshake.protocol_version,
                                 partial_edge_info: partial_edge_info,
-                            });
+                            }, noise_response);
+                        }
+                        // All the subsequent messages are encrypted.
+                        if let Some(cipher) = cipher {
+                            act.framed.set_cipher(cipher);
                         }
                         // TIER1 is strictly reserved for BFT consensensus messages,
                         // so all kinds of periodical syncs happen only on TIER2 connections.
@@ -854,7 +968,8 @@ impl PeerActor {
                         handshake_spec.protocol_version = common_version;
                         let spec = handshake_spec.clone();
                         ctx.wait(actix::fut::ready(()).then(move |_, act: &mut Self, _| {
-                            act.send_handshake(spec);
+                            let noise = act.start_noise(&spec.peer_id);
+                            act.send_handshake(spec, noise);
                             actix::fut::ready(())
                         }));
                     }
@@ -900,7 +1015,8 @@ impl PeerActor {
                 );
                 let spec = handshake_spec.clone();
                 ctx.wait(actix::fut::ready(()).then(move |_, act: &mut Self, _| {
-                    act.send_handshake(spec);
+                    let noise = act.start_noise(&spec.peer_id);
+                    act.send_handshake(spec, noise);
                     actix::fut::ready(())
                 }));
             }
@@ -1028,6 +1144,39 @@ impl PeerActor {
             }
             _ => false,
         };
+        match &msg {
+            PeerMessage::Block(block) if was_requested => self.network_state.record_response(
+                &self.clock,
+                &conn.peer_info.id,
+                reputation::Request::Block(*block.hash()),
+            ),
+            PeerMessage::VersionedStateResponse(info) => {
+                if let Some(part_id) = info.part_id() {
+                    self.network_state.record_response(
+                        &self.clock,
+                        &conn.peer_info.id,
+                        reputation::Request::StatePart {
+                            shard_id: info.shard_id(),
+                            sync_hash: info.sync_hash(),
+                            part_id,
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+        // Requests are not useful on their own; they are useful to the peer, not to us.
+        let useful = !matches!(
+            msg,
+            PeerMessage::BlockRequest(_)
+                | PeerMessage::BlockHeadersRequest(_)
+                | PeerMessage::BlockHeadersRangeRequest(..)
+                | PeerMessage::StateRequestHeader(..)
+                | PeerMessage::StateRequestPart(..)
+                | PeerMessage::EpochSyncRequest(_)
+                | PeerMessage::EpochSyncFinalizationRequest(_)
+        );
+        let sender_id = conn.peer_info.id.clone();
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
         let peer_id = conn.peer_info.id.clone();
@@ -1050,6 +1199,11 @@ impl PeerActor {
                 PeerMessage::BlockHeadersRequest(hashes) => {
                     network_state.client.block_headers_request(hashes).await.map(PeerMessage::BlockHeaders)
                 }
+                PeerMessage::BlockHeadersRangeRequest(start_height, end_height) => network_state
+                    .client
+                    .block_headers_range_request(start_height, end_height)
+                    .await
+                    .map(PeerMessage::BlockHeaders),
                 PeerMessage::Block(block) => {
                     network_state.client.block(block, peer_id, was_requested).await;
                     None
@@ -1081,15 +1235,43 @@ impl PeerActor {
                     network_state.client.state_response(info).await;
                     None
                 }
+                PeerMessage::EpochSyncRequest(epoch_id) => network_state
+                    .client
+                    .epoch_sync_request(epoch_id)
+                    .await
+                    .map(PeerMessage::EpochSyncResponse),
+                PeerMessage::EpochSyncResponse(response) => {
+                    network_state.client.epoch_sync_response(*response, peer_id).await?;
+                    None
+                }
+                PeerMessage::EpochSyncFinalizationRequest(epoch_id) => network_state
+                    .client
+                    .epoch_sync_finalization_request(epoch_id)
+                    .await
+                    .map(PeerMessage::EpochSyncFinalizationResponse),
+                PeerMessage::EpochSyncFinalizationResponse(response) => {
+                    network_state
+                        .client
+                        .epoch_sync_finalization_response(*response, peer_id)
+                        .await?;
+                    None
+                }
                 msg => {
                     tracing::error!(target: "network", "Peer received unexpected type: {:?}", msg);
                     None
                 }
             })}.in_current_span())
-            .map(|res, act: &mut PeerActor, ctx| {
+            .map(move |res, act: &mut PeerActor, ctx| {
+                if useful && res.is_ok() {
+                    act.network_state.record_behaviour(
+                        &act.clock,
+                        &sender_id,
+                        reputation::Behaviour::UsefulMessage,
+                    );
+                }
                 match res {
                     // TODO(gprusak): make sure that for routed messages we drop routeback info correctly.
-                    Ok(Some(resp)) => act.send_message_or_log(&resp),
+                    Ok(Some(resp)) => act.send_response(ctx, &resp),
                     Ok(None) => {}
                     Err(ban_reason) => act.stop(ctx, ClosingReason::Ban(ban_reason)),
                 }
@@ -1324,6 +1506,11 @@ impl PeerActor {
                 if let Some(&t) = self.routed_message_cache.get(&key) {
                     if now <= t + DROP_DUPLICATED_MESSAGES_PERIOD {
                         metrics::MessageDropped::Duplicate.inc(&msg.body);
+                        self.network_state.record_behaviour(
+                            &self.clock,
+                            &conn.peer_info.id,
+                            reputation::Behaviour::DuplicateMessage,
+                        );
                         self.network_state.config.event_sink.push(Event::RoutedMessageDropped);
                         tracing::debug!(target: "network", "Dropping duplicated message from {} to {:?}", msg.author, msg.target);
                         return;
@@ -1475,7 +1662,9 @@ impl actix::Actor for PeerActor {
         if let PeerStatus::Connecting(_, ConnectingStatus::Outbound { handshake_spec, .. }) =
             &self.peer_status
         {
-            self.send_handshake(handshake_spec.clone());
+            let spec = handshake_spec.clone();
+            let noise = self.start_noise(&spec.peer_id);
+            self.send_handshake(spec, noise);
         }
         self.network_state
             .config
@@ -1515,7 +1704,20 @@ impl actix::Actor for PeerActor {
             // If PeerActor is in Connecting state, then
             // it was not registered in the NetworkState,
             // so there is nothing to be done.
-            PeerStatus::Connecting(..) => {
+            PeerStatus::Connecting(_, cs) => {
+                // Failed handshakes count against the reputation of the peer. Only the outbound
+                // side knows for sure who the peer is, before the handshake is completed.
+                if let (
+                    ConnectingStatus::Outbound { handshake_spec, .. },
+                    Some(ClosingReason::HandshakeFailed),
+                ) = (cs, &self.closing_reason)
+                {
+                    self.network_state.record_behaviour(
+                        &self.clock,
+                        &handshake_spec.peer_id,
+                        reputation::Behaviour::HandshakeFailure,
+                    );
+                }
                 // TODO(gprusak): reporting ConnectionClosed event is quite scattered right now and
                 // it is very ugly: it may happen here, in spawn_inner, or in NetworkState::unregister().
                 // Centralize it, once we get rid of actix.
@@ -1606,6 +1808,13 @@ impl actix::Handler<stream::Frame> for PeerActor {
             Ok(msg) => msg,
             Err(err) => {
                 tracing::debug!(target: "network", "Received invalid data {} from {}: {}", unc_fmt::AbbrBytes(&msg), self.peer_info, err);
+                if let PeerStatus::Ready(conn) = &self.peer_status {
+                    self.network_state.record_behaviour(
+                        &self.clock,
+                        &conn.peer_info.id,
+                        reputation::Behaviour::InvalidMessage,
+                    );
+                }
                 return;
             }
         };
@@ -1614,6 +1823,8 @@ impl actix::Handler<stream::Frame> for PeerActor {
 
         {
             let labels = [peer_msg.msg_variant()];
+            self.stats.bandwidth.record_received(labels[0], msg.len() as u64);
+            self.capture(capture::Direction::Inbound, &peer_msg);
             metrics::PEER_MESSAGE_RECEIVED_BY_TYPE_TOTAL.with_label_values(&labels).inc();
             metrics::PEER_MESSAGE_RECEIVED_BY_TYPE_BYTES
                 .with_label_values(&labels)
@@ -1662,9 +1873,14 @@ impl actix::Handler<WithSpanContext<SendMessage>> for PeerActor {
     type Result = ();
 
     #[perf]
-    fn handle(&mut self, msg: WithSpanContext<SendMessage>, _: &mut Self::Context) {
+    fn handle(&mut self, msg: WithSpanContext<SendMessage>, ctx: &mut Self::Context) {
         let (_span, msg) = handler_debug_span!(target: "network", msg);
-        self.send_message_or_log(&msg.message);
+        match &*msg.message {
+            // Responses to routed requests (e.g. PartialEncodedChunkResponse) are routed back
+            // via PeerManagerActor.
+            PeerMessage::Routed(_) => self.send_response(ctx, &msg.message),
+            message => self.send_message_or_log(message),
+        }
     }
 }
 
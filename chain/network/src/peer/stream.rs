@@ -1,4 +1,6 @@
+use crate::noise;
 use crate::peer_manager::connection;
+use crate::quic;
 use crate::stats::metrics;
 use crate::tcp;
 use actix::fut::future::wrap_future;
@@ -6,19 +8,42 @@ use actix::AsyncContext as _;
 use bytesize::{GIB, MIB};
 use std::io;
 use std::net::SocketAddr;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::io::AsyncReadExt as _;
 use tokio::io::AsyncWriteExt as _;
 
 /// Maximum size of network message in encoded format.
 /// We encode length as `u32`, and therefore maximum size can't be larger than `u32::MAX`.
-const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
+pub(crate) const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
 /// Maximum capacity of write buffer in bytes.
 const MAX_WRITE_BUFFER_CAPACITY_BYTES: usize = GIB as usize;
+/// Bit of the frame length prefix, marking the encrypted frames.
+/// It is never set by peers which don't support encryption, since the frames are
+/// much smaller than 2^31 bytes.
+const ENCRYPTED_FRAME_FLAG: u32 = 1 << 31;
+/// Nonces of the Noise messages are split between the lanes: lane `i` uses the nonces
+/// starting at `i << LANE_NONCE_SHIFT`.
+const LANE_NONCE_SHIFT: u32 = 56;
 
-type ReadHalf = tokio::io::ReadHalf<tokio::net::TcpStream>;
-type WriteHalf = tokio::io::WriteHalf<tokio::net::TcpStream>;
+type ReadHalf = Box<dyn tokio::io::AsyncRead + Unpin + Send>;
+type WriteHalf = Box<dyn tokio::io::AsyncWrite + Unpin + Send>;
+
+/// Splits the transport into independent lanes, indexed by quic::Lane.
+/// TCP connection consists of a single lane, shared by all the messages.
+fn split(transport: tcp::Transport) -> Vec<(ReadHalf, WriteHalf)> {
+    match transport {
+        tcp::Transport::Tcp(stream) => {
+            let (read, write) = tokio::io::split(stream);
+            vec![(Box::new(read), Box::new(write))]
+        }
+        tcp::Transport::Quic(conn) => conn
+            .into_lanes()
+            .into_iter()
+            .map(|(read, write)| (Box::new(read) as ReadHalf, Box::new(write) as WriteHalf))
+            .collect(),
+    }
+}
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum SendError {
@@ -34,12 +59,23 @@ pub(crate) enum RecvError {
     IO(#[source] io::Error),
     #[error("message too large: got {got_bytes}B, want <={want_max_bytes}B")]
     MessageTooLarge { got_bytes: usize, want_max_bytes: usize },
+    #[error("decryption failed")]
+    Decrypt(#[source] snow::Error),
 }
 
 #[derive(actix::Message, PartialEq, Eq, Clone, Debug)]
 #[rtype(result = "()")]
 pub(crate) struct Frame(pub Vec<u8>);
 
+/// Frame waiting in the send queue.
+struct QueuedFrame {
+    frame: Frame,
+    encrypted: bool,
+}
+
+/// Cipher used to encrypt the connection, once negotiated by Actor.
+type CipherWatch = tokio::sync::watch::Receiver<Option<Arc<noise::Cipher>>>;
+
 /// Stream critical error.
 /// Actor is responsible for calling ctx.stop() after receiving stream::Error.
 /// Actor might receive more than 1 stream::Error, but should call ctx.stop() just after the
@@ -56,8 +92,21 @@ pub(crate) enum Error {
     Recv(#[source] RecvError),
 }
 
+/// Synchronizes the recv loops of the lanes of a single connection.
+enum HandshakeReceived {
+    /// Primary lane: notifies the other lanes after delivering the first frame.
+    Notify(tokio::sync::watch::Sender<bool>),
+    /// Secondary lane: waits for the primary lane to deliver the first frame.
+    Wait(tokio::sync::watch::Receiver<bool>),
+    Done,
+}
+
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    /// Send queues of the lanes, indexed by quic::Lane.
+    queue_send: Vec<tokio::sync::mpsc::UnboundedSender<QueuedFrame>>,
+    cipher: tokio::sync::watch::Sender<Option<Arc<noise::Cipher>>>,
+    /// Next nonce to use for encryption, per lane.
+    send_nonces: Vec<AtomicU64>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
@@ -74,42 +123,94 @@ where
         stream: tcp::Stream,
         stats: Arc<connection::Stats>,
     ) -> Self {
-        let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
-        let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
             &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
             vec![stream.peer_addr.to_string()],
         ));
-        ctx.spawn(wrap_future({
-            let addr = ctx.address();
-            let stats = stats.clone();
-            let m = send_buf_size_metric.clone();
-            async move {
-                if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
-                    addr.do_send(Error::Send(SendError::IO(err)));
+        // Frames received on the secondary lanes are delivered to Actor only after the first
+        // frame (i.e. Handshake) of the primary lane, so that Actor observes the same order of
+        // events as over a single TCP stream.
+        let (handshake_received_send, handshake_received_recv) = tokio::sync::watch::channel(false);
+        let mut handshake_received_send = Some(handshake_received_send);
+        let (cipher, cipher_recv) = tokio::sync::watch::channel(None);
+        let mut queue_send = vec![];
+        let mut send_nonces = vec![];
+        for (lane, (read, write)) in split(stream.stream).into_iter().enumerate() {
+            let (lane_queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+            queue_send.push(lane_queue_send);
+            send_nonces.push(AtomicU64::new((lane as u64) << LANE_NONCE_SHIFT));
+            ctx.spawn(wrap_future({
+                let addr = ctx.address();
+                let stats = stats.clone();
+                let m = send_buf_size_metric.clone();
+                async move {
+                    if let Err(err) = Self::run_send_loop(write, queue_recv, stats, m).await {
+                        addr.do_send(Error::Send(SendError::IO(err)));
+                    }
                 }
-            }
-        }));
-        ctx.spawn(wrap_future({
-            let addr = ctx.address();
-            let stats = stats.clone();
-            async move {
-                if let Err(err) =
-                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats).await
-                {
-                    addr.do_send(Error::Recv(err));
+            }));
+            let handshake_received = match lane {
+                0 => HandshakeReceived::Notify(handshake_received_send.take().unwrap()),
+                _ => HandshakeReceived::Wait(handshake_received_recv.clone()),
+            };
+            ctx.spawn(wrap_future({
+                let addr = ctx.address();
+                let stats = stats.clone();
+                let peer_addr = stream.peer_addr;
+                let cipher_recv = cipher_recv.clone();
+                async move {
+                    if let Err(err) = Self::run_recv_loop(
+                        peer_addr,
+                        read,
+                        (lane as u64) << LANE_NONCE_SHIFT,
+                        cipher_recv,
+                        handshake_received,
+                        addr.clone(),
+                        stats,
+                    )
+                    .await
+                    {
+                        addr.do_send(Error::Recv(err));
+                    }
                 }
-            }
-        }));
-        Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
+            }));
+        }
+        Self { queue_send, cipher, send_nonces, stats, send_buf_size_metric, addr: ctx.address() }
+    }
+
+    /// Enables encryption of the connection.
+    /// All the frames passed to send() afterwards are encrypted. Once the first encrypted
+    /// frame is received, plaintext frames are rejected.
+    pub fn set_cipher(&self, cipher: noise::Cipher) {
+        self.cipher.send_replace(Some(Arc::new(cipher)));
     }
 
-    /// Pushes `msg` to the send queue.
+    /// Pushes `msg` to the send queue of the given lane.
+    /// Connections with a single lane (i.e. TCP) send all messages via that lane.
     /// Silently drops message if the connection has been closed.
     /// If the message is too large, it will be silently dropped inside run_send_loop.
     /// Emits a critical error to Actor if send queue is full.
-    pub fn send(&self, frame: Frame) {
-        let msg = &frame.0;
+    pub fn send(&self, lane: quic::Lane, frame: Frame) {
+        let lane = if (lane as usize) < self.queue_send.len() { lane as usize } else { 0 };
+        let cipher = self.cipher.borrow().clone();
+        let frame = match cipher {
+            Some(_) if frame.0.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES => {
+                // Frame has to be dropped before encryption, so that the peer
+                // doesn't miss any nonces.
+                metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+                return;
+            }
+            Some(cipher) => {
+                // Nonces are reserved before encrypting, so that no nonce is ever used twice.
+                // Frames are sent by Actor only, so they are queued in the order of the nonces.
+                let n = noise::Cipher::messages(frame.0.len()) as u64;
+                let nonce = self.send_nonces[lane].fetch_add(n, Ordering::Relaxed);
+                let (msg, _) = cipher.encrypt(nonce, &frame.0);
+                QueuedFrame { frame: Frame(msg), encrypted: true }
+            }
+            None => QueuedFrame { frame, encrypted: false },
+        };
+        let msg = &frame.frame.0;
         let mut buf_size =
             self.stats.bytes_to_send.fetch_add(msg.len() as u64, Ordering::Acquire) as usize;
         buf_size += msg.len();
@@ -125,7 +226,7 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let _ = self.queue_send[lane].send(frame);
     }
 
     /// Event loop receiving and processing messages.
@@ -139,9 +240,18 @@ where
     async fn run_recv_loop(
         peer_addr: SocketAddr,
         read: ReadHalf,
+        mut nonce: u64,
+        mut cipher_recv: CipherWatch,
+        mut handshake_received: HandshakeReceived,
         addr: actix::Addr<Actor>,
         stats: Arc<connection::Stats>,
     ) -> Result<(), RecvError> {
+        if let HandshakeReceived::Wait(recv) = &mut handshake_received {
+            if recv.wait_for(|received| *received).await.is_err() {
+                // Primary lane has been closed before receiving the Handshake.
+                return Ok(());
+            }
+        }
         const READ_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut read = tokio::io::BufReader::with_capacity(READ_BUFFER_CAPACITY, read);
 
@@ -151,13 +261,17 @@ where
             &metrics::PEER_DATA_READ_BUFFER_SIZE,
             vec![peer_addr.to_string()],
         );
+        let mut encryption_started = false;
         loop {
-            let n = read.read_u32_le().await.map_err(RecvError::IO)? as usize;
-            if n > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                return Err(RecvError::MessageTooLarge {
-                    got_bytes: n,
-                    want_max_bytes: NETWORK_MESSAGE_MAX_SIZE_BYTES,
-                });
+            let n = read.read_u32_le().await.map_err(RecvError::IO)?;
+            let encrypted = n & ENCRYPTED_FRAME_FLAG != 0;
+            let n = (n & !ENCRYPTED_FRAME_FLAG) as usize;
+            let max_size = match encrypted {
+                true => noise::Cipher::encrypted_len(NETWORK_MESSAGE_MAX_SIZE_BYTES),
+                false => NETWORK_MESSAGE_MAX_SIZE_BYTES,
+            };
+            if n > max_size {
+                return Err(RecvError::MessageTooLarge { got_bytes: n, want_max_bytes: max_size });
             }
             msg_size_metric.observe(n as f64);
             buf_size_metric.set(n as i64);
@@ -168,27 +282,56 @@ where
             buf_size_metric.set(0);
             stats.received_messages.fetch_add(1, Ordering::Relaxed);
             stats.received_bytes.fetch_add(n as u64, Ordering::Relaxed);
+            let buf = if encrypted {
+                // The peer may start encrypting before Actor has completed the handshake
+                // on this side of the connection.
+                let cipher = match cipher_recv.wait_for(Option::is_some).await {
+                    Ok(cipher) => cipher.clone().unwrap(),
+                    // Actor has stopped.
+                    Err(_) => return Ok(()),
+                };
+                let (buf, used) = cipher.decrypt(nonce, &buf).map_err(RecvError::Decrypt)?;
+                nonce += used;
+                encryption_started = true;
+                buf
+            } else if encryption_started {
+                // Peer never sends plaintext frames after it has started encryption,
+                // so they must have been injected into the connection.
+                metrics::MessageDropped::Unencrypted.inc_unknown_msg();
+                continue;
+            } else {
+                buf
+            };
             if let Err(_) = addr.send(Frame(buf)).await {
                 // We got mailbox error, which means that Actor has stopped,
                 // so we should just close the stream.
                 return Ok(());
             }
+            if let HandshakeReceived::Notify(send) = &handshake_received {
+                send.send_replace(true);
+                handshake_received = HandshakeReceived::Done;
+            }
         }
     }
     async fn run_send_loop(
         tcp_send: WriteHalf,
-        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<QueuedFrame>,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
     ) -> io::Result<()> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
-        while let Some(Frame(mut msg)) = queue_recv.recv().await {
+        while let Some(QueuedFrame { frame: Frame(mut msg), mut encrypted }) =
+            queue_recv.recv().await
+        {
             // Try writing a batch of messages and flush once at the end.
             loop {
                 // TODO(gprusak): sending a too large message should probably be treated as a bug,
                 // since dropping messages may lead to hard-to-debug high-level issues.
-                if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
+                if encrypted {
+                    writer.write_u32_le(msg.len() as u32 | ENCRYPTED_FRAME_FLAG).await?;
+                    writer.write_all(&msg[..]).await?;
+                } else if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
                     metrics::MessageDropped::InputTooLong.inc_unknown_msg();
                 } else {
                     writer.write_u32_le(msg.len() as u32).await?;
@@ -197,8 +340,8 @@ where
                 stats.messages_to_send.fetch_sub(1, Ordering::Release);
                 stats.bytes_to_send.fetch_sub(msg.len() as u64, Ordering::Release);
                 buf_size_metric.sub(msg.len() as i64);
-                msg = match queue_recv.try_recv() {
-                    Ok(Frame(it)) => it,
+                (msg, encrypted) = match queue_recv.try_recv() {
+                    Ok(it) => (it.frame.0, it.encrypted),
                     Err(_) => break,
                 };
             }
@@ -3,6 +3,7 @@ use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerMessage, PeersRequest,
     PeersResponse, RoutedMessageBody,
 };
+use crate::noise;
 use crate::peer::testonly::{Event, PeerConfig, PeerHandle};
 use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::tcp;
@@ -14,7 +15,19 @@ use assert_matches::assert_matches;
 use std::sync::Arc;
 use unc_async::time;
 use unc_o11y::testonly::init_test_logger;
-use unc_primitives::version::{PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use unc_primitives::version::{
+    ProtocolFeature, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+
+/// Encodings to test. Borsh encoding can't carry the Noise handshake, hence it is not
+/// supported by the peers at ProtocolFeature::PeerEncryption.
+fn encodings() -> Vec<Option<Encoding>> {
+    let mut encodings = vec![None, Some(Encoding::Proto)];
+    if PROTOCOL_VERSION < ProtocolFeature::PeerEncryption.protocol_version() {
+        encodings.push(Some(Encoding::Borsh));
+    }
+    encodings
+}
 
 async fn test_peer_communication(
     outbound_encoding: Option<Encoding>,
@@ -163,7 +176,7 @@ async fn test_peer_communication(
 // Verifies that peers are able to establish a common encoding protocol.
 async fn peer_communication() -> anyhow::Result<()> {
     init_test_logger();
-    let encodings = [None, Some(Encoding::Proto), Some(Encoding::Borsh)];
+    let encodings = encodings();
     for outbound in &encodings {
         for inbound in &encodings {
             if let (Some(a), Some(b)) = (outbound, inbound) {
@@ -210,6 +223,9 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
         sender_chain_info: outbound_cfg.chain.get_peer_chain_info(),
         partial_edge_info: outbound_cfg.partial_edge_info(&inbound.cfg.id(), 1),
         owned_account: None,
+        noise: Some(
+            noise::Initiator::new(&outbound_cfg.network.node_key, &inbound.cfg.id()).unwrap().1,
+        ),
     };
     // We will also introduce chain_id mismatch, but ProtocolVersionMismatch is expected to take priority.
     handshake.sender_chain_info.genesis_id.chain_id = "unknown_chain".to_string();
@@ -252,7 +268,7 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
 // Verifies that HandshakeFailures are served correctly.
 async fn handshake() -> anyhow::Result<()> {
     init_test_logger();
-    let encodings = [None, Some(Encoding::Proto), Some(Encoding::Borsh)];
+    let encodings = encodings();
     for outbound in &encodings {
         for inbound in &encodings {
             println!("oubound = {:?}, inbound = {:?}", outbound, inbound);
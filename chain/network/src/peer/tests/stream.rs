@@ -1,6 +1,8 @@
 use crate::actix::ActixSystem;
 use crate::network_protocol::testonly as data;
+use crate::noise;
 use crate::peer::stream;
+use crate::quic;
 use crate::tcp;
 use crate::testonly::make_rng;
 use actix::Actor as _;
@@ -8,6 +10,7 @@ use actix::ActorContext as _;
 use rand::Rng as _;
 use std::sync::Arc;
 use tokio::sync::mpsc;
+use unc_primitives::network::PeerId;
 
 struct Actor {
     stream: stream::FramedStream<Actor>,
@@ -20,12 +23,23 @@ impl actix::Actor for Actor {
 
 #[derive(actix::Message)]
 #[rtype("()")]
-struct SendFrame(stream::Frame);
+struct SendFrame(quic::Lane, stream::Frame);
 
 impl actix::Handler<SendFrame> for Actor {
     type Result = ();
-    fn handle(&mut self, SendFrame(frame): SendFrame, _ctx: &mut Self::Context) {
-        self.stream.send(frame);
+    fn handle(&mut self, SendFrame(lane, frame): SendFrame, _ctx: &mut Self::Context) {
+        self.stream.send(lane, frame);
+    }
+}
+
+#[derive(actix::Message)]
+#[rtype("()")]
+struct SetCipher(noise::Cipher);
+
+impl actix::Handler<SetCipher> for Actor {
+    type Result = ();
+    fn handle(&mut self, SetCipher(cipher): SetCipher, _ctx: &mut Self::Context) {
+        self.stream.set_cipher(cipher);
     }
 }
 
@@ -82,7 +96,7 @@ async fn send_recv() {
             })
             .collect();
         for msg in &msgs {
-            a1.system.addr.send(SendFrame(msg.clone())).await.unwrap();
+            a1.system.addr.send(SendFrame(quic::Lane::Gossip, msg.clone())).await.unwrap();
         }
         for want in &msgs {
             let got = a2.queue_recv.recv().await.unwrap();
@@ -90,3 +104,83 @@ async fn send_recv() {
         }
     }
 }
+
+#[tokio::test]
+async fn send_recv_quic() {
+    let mut rng = make_rng(98324532);
+    let (s1, s2) = tcp::Stream::loopback_quic(data::make_peer_id(&mut rng), tcp::Tier::T2).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+
+    let lanes = [quic::Lane::Gossip, quic::Lane::Consensus, quic::Lane::Bulk];
+    let mut make_frame = || {
+        let mut msg = vec![0; rng.gen_range(1..10000)];
+        rng.fill(&mut msg[..]);
+        stream::Frame(msg)
+    };
+    // Frames from the secondary lanes are not delivered before the first frame of the
+    // primary lane.
+    let consensus = make_frame();
+    let bulk = make_frame();
+    let handshake = make_frame();
+    a1.system.addr.send(SendFrame(quic::Lane::Consensus, consensus.clone())).await.unwrap();
+    a1.system.addr.send(SendFrame(quic::Lane::Bulk, bulk.clone())).await.unwrap();
+    a1.system.addr.send(SendFrame(quic::Lane::Gossip, handshake.clone())).await.unwrap();
+    assert_eq!(a2.queue_recv.recv().await.unwrap(), handshake);
+    let mut got = vec![a2.queue_recv.recv().await.unwrap(), a2.queue_recv.recv().await.unwrap()];
+    let mut want = vec![consensus, bulk];
+    got.sort_by(|a, b| a.0.cmp(&b.0));
+    want.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(got, want);
+
+    // Frames are delivered in order within each lane.
+    let msgs: Vec<_> = (0..30).map(|i| (lanes[i % lanes.len()], make_frame())).collect();
+    for (lane, msg) in &msgs {
+        a1.system.addr.send(SendFrame(*lane, msg.clone())).await.unwrap();
+    }
+    let mut got = vec![];
+    for _ in &msgs {
+        got.push(a2.queue_recv.recv().await.unwrap());
+    }
+    for lane in lanes {
+        let want: Vec<_> = msgs.iter().filter(|(l, _)| *l == lane).map(|(_, m)| m).collect();
+        let got: Vec<_> = got.iter().filter(|m| want.contains(m)).collect();
+        assert_eq!(got, want);
+    }
+}
+
+#[tokio::test]
+async fn send_recv_encrypted() {
+    let mut rng = make_rng(98324532);
+    let key1 = data::make_secret_key(&mut rng);
+    let key2 = data::make_secret_key(&mut rng);
+    let id1 = PeerId::new(key1.public_key());
+    let id2 = PeerId::new(key2.public_key());
+    let (s1, s2) = tcp::Stream::loopback(id2.clone(), tcp::Tier::T2).await;
+    let a1 = Actor::spawn(s1).await;
+    let mut a2 = Actor::spawn(s2).await;
+
+    let mut make_frame = |size| {
+        let mut msg = vec![0; size];
+        rng.fill(&mut msg[..]);
+        stream::Frame(msg)
+    };
+    // Handshake is sent in plaintext.
+    let (initiator, msg) = noise::Initiator::new(&key1, &id2).unwrap();
+    let (cipher2, resp) = noise::respond(&key2, &id1, &msg).unwrap();
+    let cipher1 = initiator.finish(&resp).unwrap();
+    let handshake = make_frame(100);
+    a1.system.addr.send(SendFrame(quic::Lane::Gossip, handshake.clone())).await.unwrap();
+    assert_eq!(a2.queue_recv.recv().await.unwrap(), handshake);
+
+    // Encrypted frames sent before the receiver has set the cipher are not lost.
+    a1.system.addr.send(SetCipher(cipher1)).await.unwrap();
+    let msgs: Vec<_> = [0, 1, 1000, 100000, 300000].map(&mut make_frame).into();
+    for msg in &msgs {
+        a1.system.addr.send(SendFrame(quic::Lane::Gossip, msg.clone())).await.unwrap();
+    }
+    a2.system.addr.send(SetCipher(cipher2)).await.unwrap();
+    for want in &msgs {
+        assert_eq!(&a2.queue_recv.recv().await.unwrap(), want);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bandwidth;
 use crate::concurrency::arc_mutex::ArcMutex;
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
@@ -67,6 +68,9 @@ pub(crate) struct Stats {
     pub messages_to_send: AtomicU64,
     /// Number of bytes (sum of message sizes) in the buffer to send.
     pub bytes_to_send: AtomicU64,
+
+    /// Messages and bytes sent and received, per message type.
+    pub bandwidth: bandwidth::Counters,
 }
 
 /// Contains information relevant to a connected peer.
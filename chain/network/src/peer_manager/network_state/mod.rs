@@ -1,5 +1,7 @@
 use crate::accounts_data::{AccountDataCache, AccountDataError};
 use crate::announce_accounts::AnnounceAccountCache;
+use crate::bandwidth;
+use crate::capture;
 use crate::client;
 use crate::concurrency::demux;
 use crate::concurrency::runtime::Runtime;
@@ -14,7 +16,9 @@ use crate::peer_manager::connection;
 use crate::peer_manager::connection_store;
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_store;
+use crate::peer_manager::reputation;
 use crate::private_actix::RegisterPeerError;
+use crate::quic;
 use crate::routing::route_back_cache::RouteBackCache;
 use crate::routing::NetworkTopologyChange;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
@@ -26,6 +30,7 @@ use crate::types::{ChainInfo, PeerType, ReasonForBan};
 use anyhow::Context;
 use arc_swap::ArcSwap;
 use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
@@ -92,6 +97,11 @@ pub(crate) struct NetworkState {
     /// WARNING: actix actors can be spawned only when actix::System::current() is set.
     /// DO NOT spawn actors from a task on this runtime.
     runtime: Runtime,
+    /// QUIC endpoint, driven by `runtime`. None iff QUIC transport is disabled.
+    pub quic: Option<quic::Endpoint>,
+    /// Peers which we failed to connect to over QUIC, together with the time until which
+    /// we should connect to them over TCP instead.
+    quic_fallback: Mutex<HashMap<PeerId, time::Instant>>,
     /// PeerManager config.
     pub config: config::VerifiedConfig,
     /// When network state has been constructed.
@@ -114,6 +124,12 @@ pub(crate) struct NetworkState {
     pub inbound_handshake_permits: Arc<tokio::sync::Semaphore>,
     /// Peer store that provides read/write access to peers.
     pub peer_store: peer_store::PeerStore,
+    /// Reputation of the peers, based on their recent behaviour.
+    pub reputation: reputation::Reputation,
+    /// Limits of the outbound bandwidth of TIER2 connections, per message type.
+    pub bandwidth: bandwidth::Limiter,
+    /// Recorder of the network traffic, if capture is enabled.
+    pub capture: Option<capture::Recorder>,
     /// Information about state snapshots hosted by network peers.
     pub snapshot_hosts: Arc<SnapshotHostsCache>,
     /// Connection store that provides read/write access to stored connections.
@@ -170,8 +186,36 @@ impl NetworkState {
         shards_manager_adapter: Sender<ShardsManagerRequestFromNetwork>,
         whitelist_nodes: Vec<WhitelistNode>,
     ) -> Self {
+        let runtime = Runtime::new();
+        let quic = config.quic.as_ref().and_then(|cfg| {
+            // Endpoint I/O is driven by the runtime it has been created in.
+            let _guard = runtime.handle.enter();
+            let addr = config.node_addr.as_ref().map_or(
+                SocketAddr::new(std::net::Ipv6Addr::UNSPECIFIED.into(), 0),
+                |addr| **addr,
+            );
+            let accept_inbound = cfg.enable_inbound && config.node_addr.is_some();
+            match quic::Endpoint::new(addr, accept_inbound) {
+                Ok(endpoint) => Some(endpoint),
+                Err(err) => {
+                    tracing::error!(target: "network", ?err, %addr, "failed to start QUIC endpoint, falling back to TCP");
+                    None
+                }
+            }
+        });
+        let capture = config.capture.as_ref().and_then(|cfg| {
+            match capture::Recorder::new(cfg.clone()) {
+                Ok(recorder) => Some(recorder),
+                Err(err) => {
+                    tracing::error!(target: "network", ?err, dir = %cfg.dir.display(), "failed to start network capture");
+                    None
+                }
+            }
+        });
         Self {
-            runtime: Runtime::new(),
+            runtime,
+            quic,
+            quic_fallback: Mutex::new(HashMap::new()),
             graph: Arc::new(crate::routing::Graph::new(
                 crate::routing::GraphConfig {
                     node_id: config.node_id(),
@@ -192,6 +236,9 @@ impl NetworkState {
             tier1: connection::Pool::new(config.node_id()),
             inbound_handshake_permits: Arc::new(tokio::sync::Semaphore::new(LIMIT_PENDING_PEERS)),
             peer_store,
+            reputation: reputation::Reputation::new(config.reputation.clone()),
+            bandwidth: bandwidth::Limiter::new(clock, &config.bandwidth_limits),
+            capture,
             snapshot_hosts: Arc::new(SnapshotHostsCache::new(config.snapshot_hosts.clone())),
             connection_store: connection_store::ConnectionStore::new(store.clone()).unwrap(),
             pending_reconnect: Mutex::new(Vec::<PeerInfo>::new()),
@@ -246,6 +293,43 @@ impl NetworkState {
         }
     }
 
+    /// Updates the reputation of the peer and bans it if its score dropped too low.
+    pub fn record_behaviour(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        behaviour: reputation::Behaviour,
+    ) {
+        if self.reputation.record(clock, peer_id, behaviour) {
+            self.ban_for_low_reputation(clock, peer_id);
+        }
+    }
+
+    /// Updates the reputation of the peer based on how fast it responded to our request.
+    pub fn record_response(
+        &self,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        request: reputation::Request,
+    ) {
+        self.reputation.response_received(clock, peer_id, request);
+    }
+
+    /// Penalizes the peers which didn't respond to our requests in time.
+    pub fn expire_requests(&self, clock: &time::Clock) {
+        self.reputation.expire_requests(clock);
+    }
+
+    fn ban_for_low_reputation(&self, clock: &time::Clock, peer_id: &PeerId) {
+        // Whitelisted nodes are trusted by the operator, so we never ban them.
+        if self.whitelist_nodes.iter().any(|wn| &wn.id == peer_id) {
+            return;
+        }
+        tracing::info!(target: "network", %peer_id, "banning peer with low reputation");
+        metrics::PEER_REPUTATION_BANS.inc();
+        self.disconnect_and_ban(clock, peer_id, ReasonForBan::LowReputation);
+    }
+
     /// is_peer_whitelisted checks whether a peer is a whitelisted node.
     /// whitelisted nodes are allowed to connect, even if the inbound connections limit has
     /// been reached. This predicate should be evaluated AFTER the Handshake.
@@ -412,6 +496,38 @@ impl NetworkState {
         });
     }
 
+    /// Establishes an outbound connection to the peer.
+    /// If QUIC is enabled, tries to connect over QUIC first and falls back to TCP if the peer
+    /// is not reachable that way. Such a peer is then contacted only over TCP for
+    /// `config.quic.fallback_period`.
+    pub async fn connect(
+        &self,
+        clock: &time::Clock,
+        peer_info: &PeerInfo,
+        tier: tcp::Tier,
+    ) -> anyhow::Result<tcp::Stream> {
+        if let (Some(endpoint), Some(cfg)) = (&self.quic, &self.config.quic) {
+            let fallback = {
+                let mut fallback = self.quic_fallback.lock();
+                fallback.retain(|_, until| *until > clock.now());
+                fallback.contains_key(&peer_info.id)
+            };
+            if cfg.enable_outbound && !fallback {
+                let timeout: std::time::Duration = cfg.connect_timeout.try_into()?;
+                match tcp::Stream::connect_quic(endpoint, peer_info, tier, timeout).await {
+                    Ok(stream) => return Ok(stream),
+                    Err(err) => {
+                        tracing::debug!(target: "network", ?err, "failed to connect to {peer_info} over QUIC, falling back to TCP");
+                        self.quic_fallback
+                            .lock()
+                            .insert(peer_info.id.clone(), clock.now() + cfg.fallback_period);
+                    }
+                }
+            }
+        }
+        tcp::Stream::connect(peer_info, tier).await.context("tcp::Stream::connect()")
+    }
+
     /// Attempt to connect to the given peer until successful, up to max_attempts times
     pub async fn reconnect(
         self: &Arc<Self>,
@@ -424,9 +540,7 @@ impl NetworkState {
             interval.tick(&clock).await;
 
             let result = async {
-                let stream = tcp::Stream::connect(&peer_info, tcp::Tier::T2)
-                    .await
-                    .context("tcp::Stream::connect()")?;
+                let stream = self.connect(&clock, &peer_info, tcp::Tier::T2).await?;
                 PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone())
                     .await
                     .context("PeerActor::spawn()")?;
@@ -47,15 +47,17 @@ impl super::NetworkState {
             }
             handles.push(async move {
                 let res = async {
-                    let stream = tcp::Stream::connect(
-                        &PeerInfo {
-                            id: proxy.peer_id.clone(),
-                            addr: Some(proxy.addr),
-                            account_id: None,
-                        },
-                        tcp::Tier::T1,
-                    )
-                    .await?;
+                    let stream = self
+                        .connect(
+                            clock,
+                            &PeerInfo {
+                                id: proxy.peer_id.clone(),
+                                addr: Some(proxy.addr),
+                                account_id: None,
+                            },
+                            tcp::Tier::T1,
+                        )
+                        .await?;
                     anyhow::Ok(PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await?)
                 }.await;
                 if let Err(err) = res {
@@ -320,15 +322,17 @@ impl super::NetworkState {
                 if let Some(proxy) = proxy {
                     let proxy = (*proxy).clone();
                     handles.push(async move {
-                        let stream = tcp::Stream::connect(
-                            &PeerInfo {
-                                id: proxy.peer_id,
-                                addr: Some(proxy.addr),
-                                account_id: None,
-                            },
-                            tcp::Tier::T1,
-                        )
-                        .await?;
+                        let stream = self
+                            .connect(
+                                clock,
+                                &PeerInfo {
+                                    id: proxy.peer_id,
+                                    addr: Some(proxy.addr),
+                                    account_id: None,
+                                },
+                                tcp::Tier::T1,
+                            )
+                            .await?;
                         PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone())
                             .await
                     });
@@ -8,6 +8,7 @@ use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, WhitelistNode};
 use crate::peer_manager::peer_store;
+use crate::peer_manager::reputation;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::stats::metrics;
 use crate::store;
@@ -37,7 +38,8 @@ use unc_performance_metrics_macros::perf;
 use unc_primitives::block::GenesisId;
 use unc_primitives::network::{AnnounceAccount, PeerId};
 use unc_primitives::views::{
-    ConnectionInfoView, EdgeView, KnownPeerStateView, NetworkGraphView, PeerStoreView,
+    BandwidthLimitView, ConnectionInfoView, EdgeView, KnownPeerStateView, MessageTrafficView,
+    NetworkBandwidthView, NetworkGraphView, PeerBandwidthView, PeerStoreView,
     RecentOutboundConnectionsView, SnapshotHostInfoView, SnapshotHostsView,
 };
 
@@ -274,6 +276,40 @@ impl PeerManagerActor {
                         }
                     });
                 }
+                // Accept QUIC connections on the UDP port of the server address.
+                let quic_inbound = state.config.node_addr.is_some()
+                    && state.config.quic.as_ref().map_or(false, |cfg| cfg.enable_inbound);
+                if let (Some(endpoint), true) = (state.quic.clone(), quic_inbound) {
+                    arbiter.spawn({
+                        let arbiter = arbiter.clone();
+                        let clock = clock.clone();
+                        let state = state.clone();
+                        async move {
+                            while let Some(incoming) = endpoint.accept().await {
+                                tracing::debug!(target: "network", from = ?incoming.remote_addr(), "got new QUIC connection");
+                                // Complete the QUIC handshake in the background, so that a slow
+                                // peer doesn't block accepting other connections.
+                                arbiter.spawn({
+                                    let clock = clock.clone();
+                                    let state = state.clone();
+                                    async move {
+                                        let conn = match incoming.establish().await {
+                                            Ok(conn) => conn,
+                                            Err(err) => {
+                                                tracing::info!(target:"network", ?err, "quic::Incoming::establish()");
+                                                return;
+                                            }
+                                        };
+                                        let stream = tcp::Stream::from_quic(conn, tcp::StreamType::Inbound);
+                                        if let Err(err) = PeerActor::spawn(clock, stream, None, state) {
+                                            tracing::info!(target:"network", ?err, "PeerActor::spawn()");
+                                        }
+                                    }
+                                });
+                            }
+                        }
+                    });
+                }
                 if let Some(cfg) = state.config.tier1.clone() {
                     // Connect to TIER1 proxies and broadcast the list those connections periodically.
                     arbiter.spawn({
@@ -417,13 +453,22 @@ impl PeerManagerActor {
             None => return vec![],
         };
         // Find all peers whose height is within `highest_peer_horizon` from max height peer(s).
-        infos
+        let mut infos: Vec<_> = infos
             .into_iter()
             .filter(|i| {
                 i.highest_block_height.saturating_add(self.state.config.highest_peer_horizon)
                     >= max_height
             })
-            .collect()
+            .map(|i| (self.state.reputation.score(&self.clock, &i.peer_info.id), i))
+            .collect();
+        // Prefer peers with the highest reputation. Peers with a low reputation are used
+        // only if there are no other peers to choose from.
+        infos.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        let threshold = self.state.reputation.config().deprioritize_threshold;
+        if infos.iter().any(|(score, _)| *score >= threshold) {
+            infos.retain(|(score, _)| *score >= threshold);
+        }
+        infos.into_iter().map(|(_, i)| i).collect()
     }
 
     // Get peers that are potentially unreliable and we should avoid routing messages through them.
@@ -593,7 +638,7 @@ impl PeerManagerActor {
                     let clock = self.clock.clone();
                     async move {
                         let result = async {
-                            let stream = tcp::Stream::connect(&peer_info, tcp::Tier::T2).await.context("tcp::Stream::connect()")?;
+                            let stream = state.connect(&clock, &peer_info, tcp::Tier::T2).await?;
                             PeerActor::spawn_and_handshake(clock.clone(),stream,None,state.clone()).await.context("PeerActor::spawn()")?;
                             anyhow::Ok(())
                         }.await;
@@ -612,6 +657,9 @@ impl PeerManagerActor {
         // If there are too many active connections try to remove some connections
         self.maybe_stop_active_connection();
 
+        // Penalize peers which didn't respond to our requests in time.
+        self.state.expire_requests(&self.clock);
+
         // Find peers that are not reliable (too much behind) - and make sure that we're not routing messages through them.
         let unreliable_peers = self.unreliable_peers();
         metrics::PEER_UNRELIABLE.set(unreliable_peers.len() as i64);
@@ -660,6 +708,7 @@ impl PeerManagerActor {
             last_time_received_message: cp.last_time_received_message.load(),
             connection_established_time: cp.established_time,
             peer_type: cp.peer_type,
+            reputation_score: self.state.reputation.score(&self.clock, &cp.peer_info.id),
             nonce: match graph.local_edges.get(&cp.peer_info.id) {
                 Some(e) => e.nonce(),
                 None => 0,
@@ -705,6 +754,12 @@ impl PeerManagerActor {
         let _timer = metrics::PEER_MANAGER_TRIGGER_TIME
             .with_label_values(&["push_network_info"])
             .start_timer();
+        metrics::BANDWIDTH_BYTES_PER_SEC
+            .with_label_values(&["inbound"])
+            .set(network_info.received_bytes_per_sec as i64);
+        metrics::BANDWIDTH_BYTES_PER_SEC
+            .with_label_values(&["outbound"])
+            .set(network_info.sent_bytes_per_sec as i64);
         // TODO(gprusak): just spawn a loop.
         let state = self.state.clone();
         ctx.spawn(wrap_future(
@@ -747,8 +802,16 @@ impl PeerManagerActor {
                 NetworkResponses::NoResponse
             }
             NetworkRequests::BlockRequest { hash, peer_id } => {
-                if self.state.tier2.send_message(peer_id, Arc::new(PeerMessage::BlockRequest(hash)))
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id.clone(), Arc::new(PeerMessage::BlockRequest(hash)))
                 {
+                    self.state.reputation.request_sent(
+                        &self.clock,
+                        &peer_id,
+                        reputation::Request::Block(hash),
+                    );
                     NetworkResponses::NoResponse
                 } else {
                     NetworkResponses::RouteNotFound
@@ -765,6 +828,16 @@ impl PeerManagerActor {
                     NetworkResponses::RouteNotFound
                 }
             }
+            NetworkRequests::BlockHeadersRangeRequest { start_height, end_height, peer_id } => {
+                if self.state.tier2.send_message(
+                    peer_id,
+                    Arc::new(PeerMessage::BlockHeadersRangeRequest(start_height, end_height)),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
             NetworkRequests::StateRequestHeader { shard_id, sync_hash, peer_id } => {
                 if self.state.tier2.send_message(
                     peer_id,
@@ -777,8 +850,34 @@ impl PeerManagerActor {
             }
             NetworkRequests::StateRequestPart { shard_id, sync_hash, part_id, peer_id } => {
                 if self.state.tier2.send_message(
-                    peer_id,
+                    peer_id.clone(),
                     Arc::new(PeerMessage::StateRequestPart(shard_id, sync_hash, part_id)),
+                ) {
+                    self.state.reputation.request_sent(
+                        &self.clock,
+                        &peer_id,
+                        reputation::Request::StatePart { shard_id, sync_hash, part_id },
+                    );
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::EpochSyncRequest { epoch_id, peer_id } => {
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id, Arc::new(PeerMessage::EpochSyncRequest(epoch_id)))
+                {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::EpochSyncFinalizationRequest { epoch_id, peer_id } => {
+                if self.state.tier2.send_message(
+                    peer_id,
+                    Arc::new(PeerMessage::EpochSyncFinalizationRequest(epoch_id)),
                 ) {
                     NetworkResponses::NoResponse
                 } else {
@@ -1140,6 +1239,49 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                     })
                     .collect::<Vec<_>>(),
             }),
+            GetDebugStatus::Bandwidth => DebugStatus::Bandwidth(NetworkBandwidthView {
+                limits: self
+                    .state
+                    .config
+                    .bandwidth_limits
+                    .iter()
+                    .map(|(msg_type, limit)| BandwidthLimitView {
+                        msg_type: msg_type.clone(),
+                        bytes_per_sec: limit.qps as u64,
+                        burst_bytes: limit.burst,
+                    })
+                    .collect(),
+                peers: self
+                    .state
+                    .tier2
+                    .load()
+                    .ready
+                    .values()
+                    .map(|conn| PeerBandwidthView {
+                        peer_id: conn.peer_info.id.clone(),
+                        received_bytes_per_sec: conn
+                            .stats
+                            .received_bytes_per_sec
+                            .load(Ordering::Relaxed),
+                        sent_bytes_per_sec: conn.stats.sent_bytes_per_sec.load(Ordering::Relaxed),
+                        traffic: conn
+                            .stats
+                            .bandwidth
+                            .load()
+                            .into_iter()
+                            .map(|(msg_type, t)| MessageTrafficView {
+                                msg_type: msg_type.to_string(),
+                                received_messages: t.received_messages,
+                                received_bytes: t.received_bytes,
+                                sent_messages: t.sent_messages,
+                                sent_bytes: t.sent_bytes,
+                                throttled_messages: t.throttled_messages,
+                                throttled_bytes: t.throttled_bytes,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            }),
         }
     }
 }
@@ -0,0 +1,205 @@
+//! Reputation of the peers, based on their recent behaviour.
+//!
+//! Every peer has a score, which is increased for useful messages and quick responses to our
+//! requests, and decreased for invalid or duplicated messages, slow or missing responses and
+//! failed handshakes. The score decays exponentially towards 0, so that a peer is judged by its
+//! recent behaviour rather than its whole history. Peers with higher scores are preferred when
+//! requesting blocks and state parts. Peers are banned only for provable misbehaviour: slow or
+//! missing responses and failed handshakes may be caused by the network rather than by the peer,
+//! so they lower the score at most to `Config::deprioritize_threshold`, while invalid and
+//! duplicated messages can drop it below `Config::ban_threshold`, which gets the peer banned.
+use lru::LruCache;
+use parking_lot::Mutex;
+use unc_async::time;
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::network::PeerId;
+use unc_primitives::types::ShardId;
+
+#[cfg(test)]
+mod tests;
+
+/// Scores are kept within [-MAX_SCORE, MAX_SCORE], so that neither a long history of good
+/// behaviour can hide a sudden misbehaviour, nor a peer can get stuck with a low score forever.
+pub(crate) const MAX_SCORE: f64 = 100.;
+
+/// Reward for a response received immediately. The reward decreases linearly with the latency
+/// and becomes a penalty for responses slower than `Config::slow_response`.
+const RESPONSE_REWARD: f64 = 5.;
+
+#[derive(Clone)]
+pub struct Config {
+    /// Time after which the score of the peer is halved.
+    pub half_life: time::Duration,
+    /// Responses received later than that are penalized rather than rewarded.
+    pub slow_response: time::Duration,
+    /// Requests not responded within that time are considered failed.
+    pub response_timeout: time::Duration,
+    /// Peers with a score below this threshold are not used for requests,
+    /// unless there are no other peers to choose from.
+    pub deprioritize_threshold: f64,
+    /// Peers with a score below this threshold are banned.
+    pub ban_threshold: f64,
+    /// Maximal number of peers (and separately: pending requests) to keep track of.
+    pub cache_size: usize,
+}
+
+/// Behaviour of a peer affecting its reputation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Behaviour {
+    /// Peer sent a valid message, which we haven't seen before.
+    UsefulMessage,
+    /// Peer sent again a message that it has already sent us recently.
+    DuplicateMessage,
+    /// Peer sent a message which we failed to parse.
+    InvalidMessage,
+    /// Handshake with the peer failed.
+    HandshakeFailure,
+    /// Peer responded to our request after `latency`.
+    Response { latency: time::Duration },
+    /// Peer didn't respond to our request within `Config::response_timeout`.
+    Timeout,
+}
+
+impl Behaviour {
+    fn delta(&self, config: &Config) -> f64 {
+        match self {
+            Self::UsefulMessage => 1.,
+            Self::DuplicateMessage => -2.,
+            Self::InvalidMessage => -20.,
+            Self::HandshakeFailure => -10.,
+            Self::Response { latency } => {
+                let ratio = latency.as_seconds_f64() / config.slow_response.as_seconds_f64();
+                (RESPONSE_REWARD * (1. - ratio)).max(-RESPONSE_REWARD)
+            }
+            Self::Timeout => -10.,
+        }
+    }
+
+    /// Whether the behaviour is provably caused by the peer, rather than by the network.
+    /// Only such behaviour can get the peer banned.
+    fn is_misbehaviour(&self) -> bool {
+        match self {
+            Self::DuplicateMessage | Self::InvalidMessage => true,
+            Self::UsefulMessage
+            | Self::HandshakeFailure
+            | Self::Response { .. }
+            | Self::Timeout => false,
+        }
+    }
+}
+
+/// Request which we expect a peer to respond to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Request {
+    Block(CryptoHash),
+    StatePart { shard_id: ShardId, sync_hash: CryptoHash, part_id: u64 },
+}
+
+struct Score {
+    value: f64,
+    updated: time::Instant,
+}
+
+impl Score {
+    /// Score decayed up to `now`.
+    fn at(&self, config: &Config, now: time::Instant) -> f64 {
+        let elapsed = (now - self.updated).as_seconds_f64().max(0.);
+        self.value * 0.5f64.powf(elapsed / config.half_life.as_seconds_f64())
+    }
+}
+
+struct Inner {
+    scores: LruCache<PeerId, Score>,
+    /// Requests sent to peers, which haven't been responded yet, with the time they were sent.
+    pending: LruCache<(PeerId, Request), time::Instant>,
+}
+
+pub(crate) struct Reputation {
+    config: Config,
+    inner: Mutex<Inner>,
+}
+
+impl Reputation {
+    pub fn new(config: Config) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                scores: LruCache::new(config.cache_size),
+                pending: LruCache::new(config.cache_size),
+            }),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Current score of the peer. Unknown peers have score 0.
+    pub fn score(&self, clock: &time::Clock, peer_id: &PeerId) -> f64 {
+        let inner = self.inner.lock();
+        inner.scores.peek(peer_id).map_or(0., |s| s.at(&self.config, clock.now()))
+    }
+
+    /// Updates the score of the peer according to its behaviour.
+    /// Returns true iff the peer should be banned, in which case its score is reset.
+    pub fn record(&self, clock: &time::Clock, peer_id: &PeerId, behaviour: Behaviour) -> bool {
+        let mut inner = self.inner.lock();
+        Self::record_inner(&self.config, &mut inner, clock.now(), peer_id, behaviour)
+    }
+
+    fn record_inner(
+        config: &Config,
+        inner: &mut Inner,
+        now: time::Instant,
+        peer_id: &PeerId,
+        behaviour: Behaviour,
+    ) -> bool {
+        let old = inner.scores.get(peer_id).map_or(0., |s| s.at(config, now));
+        let mut value = (old + behaviour.delta(config)).clamp(-MAX_SCORE, MAX_SCORE);
+        if !behaviour.is_misbehaviour() {
+            // Floor the score at the deprioritize threshold, without raising the score of
+            // a peer which is already below it.
+            value = value.max(old.min(config.deprioritize_threshold));
+        }
+        if value < config.ban_threshold {
+            inner.scores.pop(peer_id);
+            return true;
+        }
+        inner.scores.put(peer_id.clone(), Score { value, updated: now });
+        false
+    }
+
+    /// Records that `request` has been sent to the peer.
+    pub fn request_sent(&self, clock: &time::Clock, peer_id: &PeerId, request: Request) {
+        self.inner.lock().pending.put((peer_id.clone(), request), clock.now());
+    }
+
+    /// Records a response of the peer. Responses to requests we haven't sent are ignored.
+    /// Slow responses deprioritize the peer, but never get it banned.
+    pub fn response_received(&self, clock: &time::Clock, peer_id: &PeerId, request: Request) {
+        let mut inner = self.inner.lock();
+        let now = clock.now();
+        let Some(sent) = inner.pending.pop(&(peer_id.clone(), request)) else {
+            return;
+        };
+        let behaviour = Behaviour::Response { latency: now - sent };
+        Self::record_inner(&self.config, &mut inner, now, peer_id, behaviour);
+    }
+
+    /// Penalizes the peers for the requests they haven't responded within
+    /// `Config::response_timeout`. Timeouts deprioritize the peers, but never get them banned.
+    pub fn expire_requests(&self, clock: &time::Clock) {
+        let mut inner = self.inner.lock();
+        let now = clock.now();
+        let expired: Vec<_> = inner
+            .pending
+            .iter()
+            .filter(|(_, sent)| now - **sent > self.config.response_timeout)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in expired {
+            inner.pending.pop(&key);
+            Self::record_inner(&self.config, &mut inner, now, &key.0, Behaviour::Timeout);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+
+fn make_config() -> Config {
+    Config {
+        half_life: time::Duration::minutes(10),
+        slow_response: time::Duration::seconds(2),
+        response_timeout: time::Duration::seconds(10),
+        deprioritize_threshold: -10.,
+        ban_threshold: -50.,
+        cache_size: 100,
+    }
+}
+
+#[test]
+fn score_decays() {
+    let clock = time::FakeClock::default();
+    let mut rng = make_rng(921853233);
+    let rep = Reputation::new(make_config());
+    let peer_id = data::make_peer_id(&mut rng);
+    assert_eq!(0., rep.score(&clock.clock(), &peer_id));
+    for _ in 0..20 {
+        assert!(!rep.record(&clock.clock(), &peer_id, Behaviour::UsefulMessage));
+    }
+    assert_eq!(20., rep.score(&clock.clock(), &peer_id));
+    clock.advance(time::Duration::minutes(10));
+    assert!((rep.score(&clock.clock(), &peer_id) - 10.).abs() < 1e-9);
+    clock.advance(time::Duration::minutes(20));
+    assert!((rep.score(&clock.clock(), &peer_id) - 2.5).abs() < 1e-9);
+}
+
+#[test]
+fn score_is_bounded() {
+    let clock = time::FakeClock::default();
+    let mut rng = make_rng(921853233);
+    let rep = Reputation::new(make_config());
+    let peer_id = data::make_peer_id(&mut rng);
+    for _ in 0..1000 {
+        rep.record(&clock.clock(), &peer_id, Behaviour::UsefulMessage);
+    }
+    assert_eq!(MAX_SCORE, rep.score(&clock.clock(), &peer_id));
+    // A good history doesn't protect the peer from getting banned for long.
+    let mut invalid = 0;
+    while !rep.record(&clock.clock(), &peer_id, Behaviour::InvalidMessage) {
+        invalid += 1;
+    }
+    assert_eq!(7, invalid);
+    // Score is reset after ban.
+    assert_eq!(0., rep.score(&clock.clock(), &peer_id));
+}
+
+#[test]
+fn ban_threshold() {
+    let clock = time::FakeClock::default();
+    let mut rng = make_rng(921853233);
+    let rep = Reputation::new(make_config());
+    let peer_id = data::make_peer_id(&mut rng);
+    let other_id = data::make_peer_id(&mut rng);
+    for _ in 0..2 {
+        assert!(!rep.record(&clock.clock(), &peer_id, Behaviour::InvalidMessage));
+    }
+    assert!(rep.record(&clock.clock(), &peer_id, Behaviour::InvalidMessage));
+    // Misbehaviour spread over time decays, so that it doesn't lead to a ban.
+    for _ in 0..10 {
+        assert!(!rep.record(&clock.clock(), &other_id, Behaviour::InvalidMessage));
+        clock.advance(time::Duration::minutes(10));
+    }
+}
+
+#[test]
+fn no_ban_for_network_failures() {
+    let clock = time::FakeClock::default();
+    let mut rng = make_rng(921853233);
+    let config = make_config();
+    let rep = Reputation::new(config.clone());
+    let peer_id = data::make_peer_id(&mut rng);
+    for _ in 0..100 {
+        assert!(!rep.record(&clock.clock(), &peer_id, Behaviour::HandshakeFailure));
+    }
+    assert_eq!(config.deprioritize_threshold, rep.score(&clock.clock(), &peer_id));
+    // Network failures don't make up for the misbehaviour.
+    for _ in 0..2 {
+        assert!(!rep.record(&clock.clock(), &peer_id, Behaviour::InvalidMessage));
+    }
+    assert!(!rep.record(&clock.clock(), &peer_id, Behaviour::HandshakeFailure));
+    assert_eq!(-50., rep.score(&clock.clock(), &peer_id));
+    assert!(rep.record(&clock.clock(), &peer_id, Behaviour::DuplicateMessage));
+}
+
+#[test]
+fn response_latency() {
+    let clock = time::FakeClock::default();
+    let mut rng = make_rng(921853233);
+    let rep = Reputation::new(make_config());
+    let fast = data::make_peer_id(&mut rng);
+    let slow = data::make_peer_id(&mut rng);
+    let hash = data::make_hash(&mut rng);
+    let req = Request::StatePart { shard_id: 0, sync_hash: hash, part_id: 3 };
+
+    rep.request_sent(&clock.clock(), &fast, req.clone());
+    rep.request_sent(&clock.clock(), &slow, req.clone());
+    clock.advance(time::Duration::milliseconds(500));
+    rep.response_received(&clock.clock(), &fast, req.clone());
+    clock.advance(time::Duration::seconds(3));
+    rep.response_received(&clock.clock(), &slow, req.clone());
+    let fast_score = rep.score(&clock.clock(), &fast);
+    let slow_score = rep.score(&clock.clock(), &slow);
+    assert!(fast_score > 0., "{fast_score}");
+    assert!(slow_score < 0., "{slow_score}");
+
+    // Unsolicited responses don't affect the score.
+    rep.response_received(&clock.clock(), &fast, Request::Block(hash));
+    assert_eq!(fast_score, rep.score(&clock.clock(), &fast));
+}
+
+#[test]
+fn expire_requests() {
+    let clock = time::FakeClock::default();
+    let mut rng = make_rng(921853233);
+    let config = make_config();
+    let rep = Reputation::new(config.clone());
+    let peer_id = data::make_peer_id(&mut rng);
+    let blocks: Vec<_> = (0..6).map(|_| Request::Block(data::make_hash(&mut rng))).collect();
+    for req in &blocks {
+        rep.request_sent(&clock.clock(), &peer_id, req.clone());
+    }
+    clock.advance(time::Duration::seconds(5));
+    rep.expire_requests(&clock.clock());
+    assert_eq!(0., rep.score(&clock.clock(), &peer_id));
+    clock.advance(time::Duration::seconds(6));
+    // Timeouts deprioritize the peer, but never get it banned.
+    rep.expire_requests(&clock.clock());
+    assert_eq!(config.deprioritize_threshold, rep.score(&clock.clock(), &peer_id));
+    // Expired requests are forgotten.
+    rep.response_received(&clock.clock(), &peer_id, blocks[0].clone());
+    assert_eq!(config.deprioritize_threshold, rep.score(&clock.clock(), &peer_id));
+}
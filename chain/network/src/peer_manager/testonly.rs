@@ -64,6 +64,7 @@ pub enum Event {
 
 pub(crate) struct ActorHandler {
     pub cfg: config::NetworkConfig,
+    pub clock: time::Clock,
     pub events: broadcast::Receiver<Event>,
     pub actix: ActixSystem<PeerManagerActor>,
 }
@@ -164,10 +165,32 @@ impl ActorHandler {
         }
     }
 
-    pub async fn send_outbound_connect(&self, peer_info: &PeerInfo, tier: tcp::Tier) {
+    /// Establishes an outbound connection over the transport selected by the PeerManager
+    /// (QUIC or TCP). The connection is not yet handed over to the PeerManager.
+    fn outbound_stream(
+        &self,
+        peer_info: &PeerInfo,
+        tier: tcp::Tier,
+    ) -> impl 'static + Send + Future<Output = tcp::Stream> {
         let addr = self.actix.addr.clone();
+        let clock = self.clock.clone();
         let peer_info = peer_info.clone();
-        let stream = tcp::Stream::connect(&peer_info, tier).await.unwrap();
+        async move {
+            let (send, recv) = tokio::sync::oneshot::channel();
+            addr.send(WithNetworkState(Box::new(move |s| {
+                Box::pin(async move {
+                    send.send(s.connect(&clock, &peer_info, tier).await.unwrap()).ok().unwrap()
+                })
+            })))
+            .await
+            .unwrap();
+            recv.await.unwrap()
+        }
+    }
+
+    pub async fn send_outbound_connect(&self, peer_info: &PeerInfo, tier: tcp::Tier) {
+        let addr = self.actix.addr.clone();
+        let stream = self.outbound_stream(peer_info, tier).await;
         addr.do_send(PeerManagerMessageRequest::OutboundTcpConnect(stream).with_span_context());
     }
 
@@ -178,9 +201,9 @@ impl ActorHandler {
     ) -> impl 'static + Send + Future<Output = tcp::StreamId> {
         let addr = self.actix.addr.clone();
         let events = self.events.clone();
-        let peer_info = peer_info.clone();
+        let stream = self.outbound_stream(peer_info, tier);
         async move {
-            let stream = tcp::Stream::connect(&peer_info, tier).await.unwrap();
+            let stream = stream.await;
             let mut events = events.from_now();
             let stream_id = stream.id();
             addr.do_send(PeerManagerMessageRequest::OutboundTcpConnect(stream).with_span_context());
@@ -556,6 +579,7 @@ pub(crate) async fn start(
     let actix = ActixSystem::spawn({
         let mut cfg = cfg.clone();
         let chain = chain.clone();
+        let clock = clock.clone();
         move || {
             let genesis_id = chain.genesis_id.clone();
             let fc = Arc::new(fake_client::Fake { event_sink: send.sink().compose(Event::Client) });
@@ -565,7 +589,7 @@ pub(crate) async fn start(
         }
     })
     .await;
-    let h = ActorHandler { cfg, actix, events: recv.clone() };
+    let h = ActorHandler { cfg, clock, actix, events: recv.clone() };
     // Wait for the server to start.
     recv.recv_until(|ev| match ev {
         Event::PeerManager(PME::ServerStarted) => Some(()),
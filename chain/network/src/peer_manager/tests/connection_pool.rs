@@ -1,6 +1,8 @@
+use crate::config;
 use crate::network_protocol::testonly as data;
 use crate::network_protocol::PeerMessage;
 use crate::network_protocol::{Encoding, Handshake, OwnedAccount, PartialEdgeInfo};
+use crate::noise;
 use crate::peer::peer_actor::ClosingReason;
 use crate::peer_manager;
 use crate::peer_manager::connection;
@@ -103,6 +105,7 @@ async fn loop_connection() {
                 &pm.cfg.node_key,
             ),
             owned_account: None,
+            noise: None,
         }))
         .await;
     let reason = events
@@ -171,6 +174,7 @@ async fn owned_account_mismatch() {
                 }
                 .sign(vc.signer.as_ref()),
             ),
+            noise: None,
         }))
         .await;
     let reason = events
@@ -187,6 +191,72 @@ async fn owned_account_mismatch() {
     assert_eq!(ClosingReason::OwnedAccountMismatch, reason);
 }
 
+#[tokio::test]
+async fn encryption_required() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let start_pm = |mut cfg: config::NetworkConfig| {
+        cfg.require_encryption = true;
+        peer_manager::testonly::start(
+            clock.clock(),
+            unc_store::db::TestDB::new(),
+            cfg,
+            chain.clone(),
+        )
+    };
+    let pm0 = start_pm(chain.make_config(rng)).await;
+    let pm1 = start_pm(chain.make_config(rng)).await;
+
+    // Peers supporting encryption are accepted and communicate over the encrypted connection.
+    pm0.connect_to(&pm1.peer_info(), tcp::Tier::T2).await;
+    let id0 = pm0.cfg.node_id();
+    let id1 = pm1.cfg.node_id();
+    pm0.wait_for_routing_table(&[(id1.clone(), vec![id1.clone()])]).await;
+    pm1.wait_for_routing_table(&[(id0.clone(), vec![id0.clone()])]).await;
+
+    // A handshake without the Noise message should be rejected.
+    let stream = tcp::Stream::connect(&pm0.peer_info(), tcp::Tier::T2).await.unwrap();
+    let stream_id = stream.id();
+    let port = stream.local_addr.port();
+    let mut events = pm0.events.from_now();
+    let mut stream = Stream::new(Some(Encoding::Proto), stream);
+    let cfg = chain.make_config(rng);
+    stream
+        .write(&PeerMessage::Tier2Handshake(Handshake {
+            protocol_version: PROTOCOL_VERSION,
+            oldest_supported_version: PROTOCOL_VERSION,
+            sender_peer_id: cfg.node_id(),
+            target_peer_id: pm0.cfg.node_id(),
+            sender_listen_port: Some(port),
+            sender_chain_info: chain.get_peer_chain_info(),
+            partial_edge_info: PartialEdgeInfo::new(
+                &cfg.node_id(),
+                &pm0.cfg.node_id(),
+                1,
+                &cfg.node_key,
+            ),
+            owned_account: None,
+            noise: None,
+        }))
+        .await;
+    let reason = events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::ConnectionClosed(ev)) if ev.stream_id == stream_id => {
+                Some(ev.reason)
+            }
+            Event::PeerManager(PME::HandshakeCompleted(ev)) if ev.stream_id == stream_id => {
+                panic!("PeerManager accepted the handshake")
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(ClosingReason::EncryptionRequired, reason);
+}
+
 #[tokio::test]
 async fn owned_account_conflict() {
     init_test_logger();
@@ -284,6 +354,7 @@ async fn invalid_edge() {
                     }
                     .sign(vc.signer.as_ref()),
                 ),
+                noise: Some(noise::Initiator::new(&cfg.node_key, &pm.cfg.node_id()).unwrap().1),
             };
             let handshake = match tier {
                 tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
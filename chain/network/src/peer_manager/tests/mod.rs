@@ -1,6 +1,7 @@
 mod accounts_data;
 mod connection_pool;
 mod nonce;
+mod reputation;
 mod routing;
 mod snapshot_hosts;
 mod tier1;
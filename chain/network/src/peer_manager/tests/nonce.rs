@@ -2,6 +2,7 @@ use crate::network_protocol::testonly as data;
 use crate::network_protocol::{
     Encoding, Handshake, PartialEdgeInfo, PeerMessage, EDGE_MIN_TIMESTAMP_NONCE,
 };
+use crate::noise;
 use crate::peer_manager::testonly::{ActorHandler, Event};
 use crate::peer_manager::{self, peer_manager_actor};
 use crate::tcp;
@@ -75,6 +76,7 @@ async fn test_nonces() {
             sender_chain_info: chain.get_peer_chain_info(),
             partial_edge_info: PartialEdgeInfo::new(&peer_id, &pm.cfg.node_id(), test.0, &peer_key),
             owned_account: None,
+            noise: Some(noise::Initiator::new(&peer_key, &pm.cfg.node_id()).unwrap().1),
         });
         stream.write(&handshake).await;
         if test.1 {
@@ -0,0 +1,57 @@
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::{Encoding, PeerMessage, Ping, RoutedMessageBody};
+use crate::peer;
+use crate::peer::peer_actor::ClosingReason;
+use crate::peer_manager;
+use crate::peer_manager::peer_manager_actor::Event as PME;
+use crate::peer_manager::testonly::Event;
+use crate::tcp;
+use crate::testonly::make_rng;
+use crate::types::ReasonForBan;
+use std::sync::Arc;
+use unc_async::time;
+use unc_o11y::testonly::init_test_logger;
+
+#[tokio::test]
+async fn ban_for_duplicated_messages() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let pm = peer_manager::testonly::start(
+        clock.clock(),
+        unc_store::db::TestDB::new(),
+        chain.make_config(rng),
+        chain.clone(),
+    )
+    .await;
+    let cfg = peer::testonly::PeerConfig {
+        network: chain.make_config(rng),
+        chain,
+        force_encoding: Some(Encoding::Proto),
+    };
+    let stream = tcp::Stream::connect(&pm.peer_info(), tcp::Tier::T2).await.unwrap();
+    let stream_id = stream.id();
+    let mut peer = peer::testonly::PeerHandle::start_endpoint(clock.clock(), cfg, stream).await;
+    peer.complete_handshake().await;
+    pm.wait_for_routing_table(&[(peer.cfg.id(), vec![peer.cfg.id()])]).await;
+
+    let mut events = pm.events.from_now();
+    let ping = RoutedMessageBody::Ping(Ping { nonce: 1, source: peer.cfg.id() });
+    let msg = Box::new(peer.routed_message(ping, pm.cfg.node_id(), 1, Some(clock.now_utc())));
+    // Every duplicate costs 2 points, so the 26th duplicate drops the score of the peer
+    // below the ban threshold (-50).
+    for _ in 0..27 {
+        peer.send(PeerMessage::Routed(msg.clone())).await;
+    }
+    let reason = events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::ConnectionClosed(ev)) if ev.stream_id == stream_id => {
+                Some(ev.reason)
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(ClosingReason::Ban(ReasonForBan::LowReputation), reason);
+}
@@ -0,0 +1,277 @@
+//! QUIC transport for peer connections.
+//!
+//! A QUIC connection carries the same length-prefixed frames as a TCP connection, but splits
+//! them over independent streams ("lanes"), so that a large state sync response doesn't delay
+//! consensus messages queued after it (head-of-line blocking).
+//!
+//! TLS is required by QUIC, but it authenticates nothing: the server presents a self-signed
+//! certificate, which the client doesn't verify, so TLS protects only against passive
+//! eavesdroppers and an active attacker can terminate it on both sides. The TLS session is not
+//! bound to the peer identity in any way. Peers are authenticated by the Handshake messages and,
+//! if both peers support it, the traffic is encrypted end-to-end with Noise, exactly as over TCP.
+use crate::network_protocol::{PeerMessage, RoutedMessageBody};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Application protocol negotiated via ALPN.
+const ALPN: &[u8] = b"unc-p2p/1";
+/// Interval between keep-alive packets, so that idle connections are not dropped by NATs.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+/// Connection is closed if no packet is received from the peer for that long.
+const MAX_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Independent stream within a QUIC connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Lane {
+    /// Handshake and everything which is neither consensus nor bulk traffic.
+    Gossip = 0,
+    /// Messages critical for the chain progress: approvals, endorsements, chunks.
+    Consensus = 1,
+    /// Large, latency insensitive responses: state parts, block headers.
+    Bulk = 2,
+}
+
+impl Lane {
+    pub const COUNT: usize = 3;
+
+    /// Lane on which `msg` should be sent.
+    pub fn of(msg: &PeerMessage) -> Self {
+        match msg {
+            PeerMessage::Routed(r) if r.msg.body.is_important() => Lane::Consensus,
+            PeerMessage::Routed(r) => match &r.msg.body {
+                RoutedMessageBody::StateResponse(_) => Lane::Bulk,
+                _ => Lane::Gossip,
+            },
+            PeerMessage::VersionedStateResponse(_)
+            | PeerMessage::BlockHeaders(_)
+            | PeerMessage::EpochSyncFinalizationResponse(_) => Lane::Bulk,
+            _ => Lane::Gossip,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Lane::Gossip),
+            1 => Some(Lane::Consensus),
+            2 => Some(Lane::Bulk),
+            _ => None,
+        }
+    }
+}
+
+/// Certificate verifier accepting any server certificate.
+/// The TLS handshake signatures are still checked, which proves only that the server owns the
+/// key of the certificate it presented, not who the server is.
+#[derive(Debug)]
+struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
+
+impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn transport_config() -> Arc<quinn::TransportConfig> {
+    let mut cfg = quinn::TransportConfig::default();
+    cfg.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
+    cfg.max_idle_timeout(Some(MAX_IDLE_TIMEOUT.try_into().unwrap()));
+    Arc::new(cfg)
+}
+
+fn server_config() -> anyhow::Result<quinn::ServerConfig> {
+    let cert = rcgen::generate_simple_self_signed(vec!["unc-node".to_string()])?;
+    let key = rustls::pki_types::PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der());
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut tls = rustls::ServerConfig::builder_with_provider(provider)
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.cert.der().clone()], key.into())?;
+    tls.alpn_protocols = vec![ALPN.to_vec()];
+    let mut cfg = quinn::ServerConfig::with_crypto(Arc::new(
+        quinn::crypto::rustls::QuicServerConfig::try_from(tls)?,
+    ));
+    cfg.transport_config(transport_config());
+    Ok(cfg)
+}
+
+fn client_config() -> anyhow::Result<quinn::ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut tls = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+        .with_no_client_auth();
+    tls.alpn_protocols = vec![ALPN.to_vec()];
+    let mut cfg =
+        quinn::ClientConfig::new(Arc::new(quinn::crypto::rustls::QuicClientConfig::try_from(tls)?));
+    cfg.transport_config(transport_config());
+    Ok(cfg)
+}
+
+/// QUIC endpoint, used both for accepting and for establishing connections.
+/// Has to be constructed within a tokio runtime, which then drives the endpoint I/O.
+#[derive(Clone)]
+pub struct Endpoint(quinn::Endpoint);
+
+impl Endpoint {
+    /// Binds a UDP socket at `addr`.
+    /// If `accept_inbound` is false, the endpoint will only establish outbound connections.
+    pub fn new(addr: SocketAddr, accept_inbound: bool) -> anyhow::Result<Self> {
+        let server = if accept_inbound { Some(server_config()?) } else { None };
+        let socket = std::net::UdpSocket::bind(addr)?;
+        let mut endpoint = quinn::Endpoint::new(
+            quinn::EndpointConfig::default(),
+            server,
+            socket,
+            Arc::new(quinn::TokioRuntime),
+        )?;
+        endpoint.set_default_client_config(client_config()?);
+        Ok(Self(endpoint))
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Establishes a connection to `addr` and opens all the lanes.
+    pub async fn connect(&self, addr: SocketAddr) -> anyhow::Result<Connection> {
+        let conn = self.0.connect(addr, "unc-node")?.await?;
+        let mut lanes = vec![];
+        for lane in [Lane::Gossip, Lane::Consensus, Lane::Bulk] {
+            let (mut send, recv) = conn.open_bi().await?;
+            // Streams are announced to the peer lazily, so the tag is sent right away.
+            send.write_all(&[lane as u8]).await?;
+            lanes.push((send, recv));
+        }
+        Ok(Connection { conn, local_addr: self.local_addr()?, lanes })
+    }
+
+    /// Waits for the next inbound connection.
+    /// Returns None if the endpoint has been closed.
+    pub async fn accept(&self) -> Option<Incoming> {
+        Some(Incoming { incoming: self.0.accept().await?, local_addr: self.local_addr().ok()? })
+    }
+}
+
+/// Inbound connection which hasn't completed the QUIC handshake yet.
+pub struct Incoming {
+    incoming: quinn::Incoming,
+    local_addr: SocketAddr,
+}
+
+impl Incoming {
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.incoming.remote_address()
+    }
+
+    /// Completes the QUIC handshake and accepts all the lanes opened by the peer.
+    pub async fn establish(self) -> anyhow::Result<Connection> {
+        let conn = self.incoming.await?;
+        let mut lanes: Vec<Option<(quinn::SendStream, quinn::RecvStream)>> =
+            (0..Lane::COUNT).map(|_| None).collect();
+        for _ in 0..Lane::COUNT {
+            let (send, mut recv) = conn.accept_bi().await?;
+            let mut tag = [0u8];
+            recv.read_exact(&mut tag).await?;
+            let lane =
+                Lane::from_tag(tag[0]).ok_or_else(|| anyhow::anyhow!("unknown lane {}", tag[0]))?;
+            anyhow::ensure!(lanes[lane as usize].is_none(), "duplicate lane {lane:?}");
+            lanes[lane as usize] = Some((send, recv));
+        }
+        let lanes = lanes.into_iter().map(Option::unwrap).collect();
+        Ok(Connection { conn, local_addr: self.local_addr, lanes })
+    }
+}
+
+/// QUIC connection with all its lanes opened, indexed by `Lane`.
+#[derive(Debug)]
+pub struct Connection {
+    conn: quinn::Connection,
+    local_addr: SocketAddr,
+    lanes: Vec<(quinn::SendStream, quinn::RecvStream)>,
+}
+
+impl Connection {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.conn.remote_address()
+    }
+
+    pub(crate) fn gossip_lane(&mut self) -> &mut (quinn::SendStream, quinn::RecvStream) {
+        &mut self.lanes[Lane::Gossip as usize]
+    }
+
+    /// Splits the connection into lanes, indexed by `Lane`.
+    /// The connection stays open until all the receiving halves are dropped.
+    pub(crate) fn into_lanes(self) -> Vec<(RecvStream, quinn::SendStream)> {
+        let conn = self.conn;
+        self.lanes
+            .into_iter()
+            .map(|(send, recv)| (RecvStream { recv, _conn: conn.clone() }, send))
+            .collect()
+    }
+}
+
+/// Receiving half of a lane.
+/// Dropping all the handles to a quinn::Connection closes it, hence each lane keeps one.
+#[derive(Debug)]
+pub(crate) struct RecvStream {
+    recv: quinn::RecvStream,
+    _conn: quinn::Connection,
+}
+
+impl tokio::io::AsyncRead for RecvStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        tokio::io::AsyncRead::poll_read(Pin::new(&mut self.recv), cx, buf)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::capture;
 use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash,
     PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
@@ -214,6 +215,7 @@ fn new_handshake(
         },
         partial_edge_info: PartialEdgeInfo::new(my_peer_id, target_peer_id, nonce, secret_key),
         owned_account: None,
+        noise: None,
     })
 }
 
@@ -415,6 +417,38 @@ impl Connection {
         self.stream.write_message(&PeerMessage::Routed(Box::new(msg))).await
     }
 
+    /// Sends a message recorded in a network capture (see `crate::capture`).
+    /// Routed messages are signed again and addressed to the connected peer, because
+    /// the original author and target are not part of the replay. Messages specific to
+    /// the original connection (handshakes, edges, routing table updates) are skipped.
+    /// Returns false iff the message has been skipped.
+    pub async fn replay(&mut self, record: &capture::Record, ttl: u8) -> io::Result<bool> {
+        let msg =
+            record.peer_message().map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let msg = match msg {
+            PeerMessage::Tier1Handshake(_)
+            | PeerMessage::Tier2Handshake(_)
+            | PeerMessage::HandshakeFailure(..)
+            | PeerMessage::LastEdge(_)
+            | PeerMessage::SyncRoutingTable(_)
+            | PeerMessage::DistanceVector(_)
+            | PeerMessage::RequestUpdateNonce(_)
+            | PeerMessage::Disconnect(_) => return Ok(false),
+            PeerMessage::Routed(msg) => {
+                let msg = RawRoutedMessage {
+                    target: PeerIdOrHash::PeerId(self.peer_id.clone()),
+                    body: msg.msg.body,
+                }
+                .sign(&self.secret_key, ttl, Some(Utc::now_utc()));
+                self.route_cache.put(msg.hash(), ());
+                PeerMessage::Routed(Box::new(msg))
+            }
+            msg => msg,
+        };
+        self.stream.write_message(&msg).await?;
+        Ok(true)
+    }
+
     fn target_is_for_me(&mut self, target: &PeerIdOrHash) -> bool {
         match target {
             PeerIdOrHash::PeerId(peer_id) => peer_id == &self.my_peer_id,
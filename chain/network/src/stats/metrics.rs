@@ -192,6 +192,45 @@ pub(crate) static PEER_MESSAGE_SENT_BY_TYPE_TOTAL: Lazy<IntCounterVec> = Lazy::n
     )
     .unwrap()
 });
+pub(crate) static PEER_MESSAGE_THROTTLED_BY_TYPE_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_peer_message_throttled_by_type_bytes",
+        "Total size of responses delayed or dropped because of the bandwidth limits by message types",
+        &["type"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_MESSAGE_THROTTLED_BY_TYPE_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_peer_message_throttled_by_type_total",
+        "Number of responses delayed or dropped because of the bandwidth limits by message types",
+        &["type"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_RESPONSE_DROPPED_BY_TYPE_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "unc_peer_response_dropped_by_type_total",
+        "Number of responses dropped because the bandwidth limits would delay them too long",
+        &["type"],
+    )
+    .unwrap()
+});
+pub(crate) static NETWORK_CAPTURE_DROPPED_RECORDS: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "unc_network_capture_dropped_records",
+        "Number of captured messages dropped, because they couldn't be written to disk fast enough",
+    )
+    .unwrap()
+});
+pub(crate) static BANDWIDTH_BYTES_PER_SEC: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "unc_network_bandwidth_bytes_per_sec",
+        "Avg bytes/s exchanged with all TIER2 peers, based on the last few minutes of traffic",
+        &["direction"],
+    )
+    .unwrap()
+});
 pub(crate) static SYNC_ACCOUNTS_DATA: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "unc_sync_accounts_data",
@@ -269,6 +308,13 @@ pub(crate) static PEER_UNRELIABLE: Lazy<IntGauge> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub(crate) static PEER_REPUTATION_BANS: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "unc_peer_reputation_bans",
+        "Total peers banned because their reputation score dropped below the threshold",
+    )
+    .unwrap()
+});
 pub(crate) static PEER_MANAGER_TRIGGER_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "unc_peer_manager_trigger_time",
@@ -446,6 +492,7 @@ pub(crate) enum MessageDropped {
     MaxCapacityExceeded,
     TransactionsPerBlockExceeded,
     Duplicate,
+    Unencrypted,
 }
 
 impl MessageDropped {
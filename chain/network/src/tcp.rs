@@ -1,9 +1,12 @@
 use crate::network_protocol::PeerInfo;
+use crate::quic;
 use anyhow::{anyhow, Context as _};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::fmt;
+use std::pin::Pin;
 use std::sync::Mutex;
+use std::task::{Context, Poll};
 use unc_primitives::network::PeerId;
 
 const LISTENER_BACKLOG: u32 = 128;
@@ -33,9 +36,66 @@ pub(crate) enum StreamType {
     Outbound { peer_id: PeerId, tier: Tier },
 }
 
+/// Connection underlying a Stream.
+#[derive(Debug)]
+pub(crate) enum Transport {
+    Tcp(tokio::net::TcpStream),
+    /// QUIC connection, with frames split over independent lanes.
+    /// When used directly as AsyncRead/AsyncWrite, only the quic::Lane::Gossip is used.
+    Quic(quic::Connection),
+}
+
+impl tokio::io::AsyncRead for Transport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) => tokio::io::AsyncRead::poll_read(Pin::new(s), cx, buf),
+            Self::Quic(c) => {
+                tokio::io::AsyncRead::poll_read(Pin::new(&mut c.gossip_lane().1), cx, buf)
+            }
+        }
+    }
+}
+
+impl tokio::io::AsyncWrite for Transport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(s) => tokio::io::AsyncWrite::poll_write(Pin::new(s), cx, buf),
+            Self::Quic(c) => {
+                tokio::io::AsyncWrite::poll_write(Pin::new(&mut c.gossip_lane().0), cx, buf)
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) => tokio::io::AsyncWrite::poll_flush(Pin::new(s), cx),
+            Self::Quic(c) => {
+                tokio::io::AsyncWrite::poll_flush(Pin::new(&mut c.gossip_lane().0), cx)
+            }
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) => tokio::io::AsyncWrite::poll_shutdown(Pin::new(s), cx),
+            Self::Quic(c) => {
+                tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut c.gossip_lane().0), cx)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Stream {
-    pub(crate) stream: tokio::net::TcpStream,
+    pub(crate) stream: Transport,
     pub(crate) type_: StreamType,
     /// cached stream.local_addr()
     pub(crate) local_addr: std::net::SocketAddr,
@@ -50,6 +110,8 @@ pub struct Stream {
 /// network interface, so that both inbound and outbound IP is always 127.0.0.1.
 /// To create a reliable StreamId for a distributed, we would have to transmit it over the connection itself,
 /// which is doable, but not yet needed in our testing unc-infra.
+/// Note that all QUIC connections of a node share its UDP port, so StreamId doesn't distinguish
+/// QUIC connections between the same pair of nodes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct StreamId {
     inbound: std::net::SocketAddr,
@@ -79,7 +141,36 @@ impl Socket {
 
 impl Stream {
     fn new(stream: tokio::net::TcpStream, type_: StreamType) -> std::io::Result<Self> {
-        Ok(Self { peer_addr: stream.peer_addr()?, local_addr: stream.local_addr()?, stream, type_ })
+        Ok(Self {
+            peer_addr: stream.peer_addr()?,
+            local_addr: stream.local_addr()?,
+            stream: Transport::Tcp(stream),
+            type_,
+        })
+    }
+
+    pub(crate) fn from_quic(conn: quic::Connection, type_: StreamType) -> Self {
+        Self {
+            peer_addr: conn.remote_addr(),
+            local_addr: conn.local_addr(),
+            stream: Transport::Quic(conn),
+            type_,
+        }
+    }
+
+    /// Establishes a QUIC connection to the peer via `endpoint`.
+    pub(crate) async fn connect_quic(
+        endpoint: &quic::Endpoint,
+        peer_info: &PeerInfo,
+        tier: Tier,
+        timeout: std::time::Duration,
+    ) -> anyhow::Result<Stream> {
+        let addr =
+            peer_info.addr.ok_or(anyhow!("Trying to connect to peer with no public address"))?;
+        let conn = tokio::time::timeout(timeout, endpoint.connect(addr))
+            .await?
+            .context("quic::Endpoint::connect()")?;
+        Ok(Stream::from_quic(conn, StreamType::Outbound { peer_id: peer_info.id.clone(), tier }))
     }
 
     pub async fn connect(peer_info: &PeerInfo, tier: Tier) -> anyhow::Result<Stream> {
@@ -114,6 +205,22 @@ impl Stream {
         (outbound.unwrap(), inbound.unwrap())
     }
 
+    /// Establishes a loopback QUIC connection to localhost with random ports.
+    /// Returns a pair of streams: (outbound,inbound).
+    #[cfg(test)]
+    pub async fn loopback_quic(peer_id: PeerId, tier: Tier) -> (Stream, Stream) {
+        let server = quic::Endpoint::new("[::1]:0".parse().unwrap(), true).unwrap();
+        let client = quic::Endpoint::new("[::1]:0".parse().unwrap(), false).unwrap();
+        let peer_info =
+            PeerInfo { id: peer_id, addr: Some(server.local_addr().unwrap()), account_id: None };
+        let timeout = std::time::Duration::from_secs(5);
+        let (outbound, inbound) =
+            tokio::join!(Stream::connect_quic(&client, &peer_info, tier, timeout), async {
+                server.accept().await.unwrap().establish().await
+            },);
+        (outbound.unwrap(), Stream::from_quic(inbound.unwrap(), StreamType::Inbound))
+    }
+
     // TEST-ONLY used in reporting test events.
     pub(crate) fn id(&self) -> StreamId {
         match self.type_ {
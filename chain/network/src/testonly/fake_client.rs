@@ -7,12 +7,13 @@ use unc_async::messaging;
 use unc_primitives::block::{Approval, Block, BlockHeader};
 use unc_primitives::challenge::Challenge;
 use unc_primitives::chunk_validation::{ChunkEndorsement, ChunkStateWitness};
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::network::{AnnounceAccount, PeerId};
 use unc_primitives::sharding::{ChunkHash, PartialEncodedChunkPart};
 use unc_primitives::state_sync::{ShardStateSyncResponse, ShardStateSyncResponseV2};
 use unc_primitives::transaction::SignedTransaction;
-use unc_primitives::types::{AccountId, EpochId, ShardId};
+use unc_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
 use unc_primitives::views::FinalExecutionOutcomeView;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -23,12 +24,17 @@ pub enum Event {
     BlockApproval(Approval, PeerId),
     BlockHeaders(Vec<BlockHeader>),
     BlockHeadersRequest(Vec<CryptoHash>),
+    BlockHeadersRangeRequest(BlockHeight, BlockHeight),
     BlockRequest(CryptoHash),
     Challenge(Challenge),
     Chunk(Vec<PartialEncodedChunkPart>),
     ChunkEndorsement(ChunkEndorsement),
     ChunkRequest(ChunkHash),
     ChunkStateWitness(ChunkStateWitness),
+    EpochSyncRequest(EpochId),
+    EpochSyncResponse(EpochSyncResponse),
+    EpochSyncFinalizationRequest(EpochId),
+    EpochSyncFinalizationResponse(EpochSyncFinalizationResponse),
     Transaction(SignedTransaction),
 }
 
@@ -95,6 +101,15 @@ impl client::Client for Fake {
         None
     }
 
+    async fn block_headers_range_request(
+        &self,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> Option<Vec<BlockHeader>> {
+        self.event_sink.push(Event::BlockHeadersRangeRequest(start_height, end_height));
+        None
+    }
+
     async fn block(&self, block: Block, _peer_id: PeerId, _was_requested: bool) {
         self.event_sink.push(Event::Block(block));
     }
@@ -129,6 +144,37 @@ impl client::Client for Fake {
     async fn chunk_endorsement(&self, endorsement: ChunkEndorsement) {
         self.event_sink.push(Event::ChunkEndorsement(endorsement));
     }
+
+    async fn epoch_sync_request(&self, epoch_id: EpochId) -> Option<Box<EpochSyncResponse>> {
+        self.event_sink.push(Event::EpochSyncRequest(epoch_id));
+        None
+    }
+
+    async fn epoch_sync_response(
+        &self,
+        response: EpochSyncResponse,
+        _peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        self.event_sink.push(Event::EpochSyncResponse(response));
+        Ok(())
+    }
+
+    async fn epoch_sync_finalization_request(
+        &self,
+        epoch_id: EpochId,
+    ) -> Option<Box<EpochSyncFinalizationResponse>> {
+        self.event_sink.push(Event::EpochSyncFinalizationRequest(epoch_id));
+        None
+    }
+
+    async fn epoch_sync_finalization_response(
+        &self,
+        response: EpochSyncFinalizationResponse,
+        _peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        self.event_sink.push(Event::EpochSyncFinalizationResponse(response));
+        Ok(())
+    }
 }
 
 impl messaging::CanSend<ShardsManagerRequestFromNetwork> for Fake {
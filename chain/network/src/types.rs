@@ -27,7 +27,7 @@ use unc_primitives::hash::CryptoHash;
 use unc_primitives::network::{AnnounceAccount, PeerId};
 use unc_primitives::sharding::PartialEncodedChunkWithArcReceipts;
 use unc_primitives::transaction::SignedTransaction;
-use unc_primitives::types::{AccountId, BlockHeight, EpochHeight, ShardId};
+use unc_primitives::types::{AccountId, BlockHeight, EpochHeight, EpochId, ShardId};
 
 /// Number of hops a message is allowed to travel before being dropped.
 /// This is used to avoid infinite loop because of inconsistent view of the network
@@ -69,6 +69,8 @@ pub enum ReasonForBan {
     InvalidDistanceVector = 11,
     Blacklisted = 14,
     ProvidedNotEnoughHeaders = 15,
+    BadEpochSyncResponse = 16,
+    LowReputation = 17,
 }
 
 /// Banning signal sent from Peer instance to PeerManager
@@ -225,10 +227,16 @@ pub enum NetworkRequests {
     BlockRequest { hash: CryptoHash, peer_id: PeerId },
     /// Request given block headers.
     BlockHeadersRequest { hashes: Vec<CryptoHash>, peer_id: PeerId },
+    /// Request headers of the canonical chain with heights in `start_height..=end_height`.
+    BlockHeadersRangeRequest { start_height: BlockHeight, end_height: BlockHeight, peer_id: PeerId },
     /// Request state header for given shard at given state root.
     StateRequestHeader { shard_id: ShardId, sync_hash: CryptoHash, peer_id: PeerId },
     /// Request state part for given shard at given state root.
     StateRequestPart { shard_id: ShardId, sync_hash: CryptoHash, part_id: u64, peer_id: PeerId },
+    /// Request light client block of given epoch from given peer for Epoch Sync.
+    EpochSyncRequest { epoch_id: EpochId, peer_id: PeerId },
+    /// Request data finishing Epoch Sync at the first block of given epoch from given peer.
+    EpochSyncFinalizationRequest { epoch_id: EpochId, peer_id: PeerId },
     /// Ban given peer.
     BanPeer { peer_id: PeerId, ban_reason: ReasonForBan },
     /// Announce account
@@ -341,6 +349,8 @@ pub struct ConnectedPeerInfo {
     pub connection_established_time: time::Instant,
     /// Who started connection. Inbound (other) or Outbound (us).
     pub peer_type: PeerType,
+    /// Reputation of the peer, see `peer_manager::reputation`.
+    pub reputation_score: f64,
     /// Nonce used for the connection with the peer.
     pub nonce: u64,
 }
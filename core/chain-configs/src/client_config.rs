@@ -6,7 +6,12 @@ use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
-use unc_primitives::types::{BlockHeight, BlockHeightDelta, Gas, NumBlocks, NumSeats, ShardId};
+use unc_primitives::shard_layout::{
+    account_id_to_shard_id, account_prefix_to_shard_ids, ShardLayout, ShardUId,
+};
+use unc_primitives::types::{
+    AccountId, BlockHeight, BlockHeightDelta, Gas, NumBlocks, NumSeats, ShardId,
+};
 use unc_primitives::version::Version;
 
 pub const TEST_STATE_SYNC_TIMEOUT: u64 = 5;
@@ -43,6 +48,15 @@ pub struct GCConfig {
 
     /// Number of epochs for which we keep store data.
     pub gc_num_epochs_to_keep: u64,
+
+    /// Accounts whose history is kept forever ("partial archive" mode).
+    ///
+    /// When non-empty, garbage collection keeps `State`, `StateChanges` and
+    /// execution outcomes of matching accounts, as well as `ChunkExtra` of the
+    /// shards which may contain them, and the cold storage loop only copies
+    /// those.  Queries at garbage collected
+    /// heights keep working for matching accounts.
+    pub archived_accounts: Vec<AccountPattern>,
 }
 
 impl Default for GCConfig {
@@ -51,6 +65,7 @@ impl Default for GCConfig {
             gc_blocks_limit: 2,
             gc_fork_clean_step: 100,
             gc_num_epochs_to_keep: DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+            archived_accounts: vec![],
         }
     }
 }
@@ -59,6 +74,118 @@ impl GCConfig {
     pub fn gc_num_epochs_to_keep(&self) -> u64 {
         max(MIN_GC_NUM_EPOCHS_TO_KEEP, self.gc_num_epochs_to_keep)
     }
+
+    /// Whether history of given account is kept past the garbage collection
+    /// horizon.
+    pub fn is_archived_account(&self, account_id: &str) -> bool {
+        self.archived_accounts.iter().any(|pattern| pattern.matches(account_id))
+    }
+}
+
+/// Pattern selecting accounts in [`GCConfig::archived_accounts`].
+///
+/// Written either as an account id, which matches only that account, or as an
+/// account id prefix followed by `*`, e.g. `app.*` matches `app.unc` and
+/// `app.explorer.unc`.  Only prefixes are supported since state is keyed by
+/// account id and prefixes map to contiguous ranges of the trie.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AccountPattern {
+    prefix: String,
+    exact: bool,
+}
+
+impl AccountPattern {
+    /// Account id or account id prefix matched by the pattern.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn matches(&self, account_id: &str) -> bool {
+        if self.exact {
+            account_id == self.prefix
+        } else {
+            account_id.starts_with(&self.prefix)
+        }
+    }
+
+    /// Shards of `shard_layout` which may contain accounts matching the pattern.
+    pub fn shard_uids(&self, shard_layout: &ShardLayout) -> Vec<ShardUId> {
+        let shard_ids = if self.exact {
+            let account_id = self.prefix.parse().expect("pattern is a valid account id");
+            vec![account_id_to_shard_id(&account_id, shard_layout)]
+        } else {
+            account_prefix_to_shard_ids(&self.prefix, shard_layout)
+        };
+        shard_ids
+            .into_iter()
+            .map(|shard_id| ShardUId::from_shard_id_and_layout(shard_id, shard_layout))
+            .collect()
+    }
+}
+
+impl TryFrom<String> for AccountPattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        if let Some(prefix) = pattern.strip_suffix('*') {
+            let is_valid_char = |c: char| matches!(c, 'a'..='z' | '0'..='9' | '-' | '_' | '.');
+            if prefix.is_empty() || !prefix.chars().all(is_valid_char) {
+                return Err(format!("invalid account pattern {pattern:?}"));
+            }
+            Ok(Self { prefix: prefix.to_string(), exact: false })
+        } else {
+            AccountId::validate(&pattern)
+                .map_err(|err| format!("invalid account pattern {pattern:?}: {err}"))?;
+            Ok(Self { prefix: pattern, exact: true })
+        }
+    }
+}
+
+impl From<AccountPattern> for String {
+    fn from(pattern: AccountPattern) -> String {
+        if pattern.exact {
+            pattern.prefix
+        } else {
+            pattern.prefix + "*"
+        }
+    }
+}
+
+/// Configuration of the background store scrubber.
+///
+/// The scrubber continuously walks the hot (and cold if configured) database
+/// checking that entries decode, that hashes match keys, that references
+/// between columns resolve, that `State` reference counts are positive, that
+/// flat storage agrees with the trie and that data present in both hot and
+/// cold storage is identical.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StoreScrubberConfig {
+    /// Maximum number of entries checked per second, summed over all columns.
+    pub keys_per_second: u64,
+    /// Pause after a full pass over all columns before starting the next one.
+    pub pass_interval: Duration,
+    /// Whether corrupt entries of the hot database are moved out of it.
+    ///
+    /// Quarantined entries are written to `quarantine_dir` and deleted from
+    /// the database so that the node fails on a missing entry, which it may
+    /// recover from by syncing, rather than on a corrupt one.
+    pub quarantine: bool,
+    /// Directory, relative to the home directory, quarantined entries are
+    /// written to.
+    pub quarantine_dir: PathBuf,
+}
+
+impl Default for StoreScrubberConfig {
+    fn default() -> Self {
+        Self {
+            keys_per_second: 1000,
+            pass_interval: Duration::from_secs(60 * 60),
+            quarantine: false,
+            quarantine_dir: PathBuf::from("quarantine"),
+        }
+    }
 }
 
 fn default_num_concurrent_requests() -> u32 {
@@ -81,6 +208,10 @@ pub struct ExternalStorageConfig {
     /// to reduce the performance impact of state sync.
     #[serde(default = "default_num_concurrent_requests_during_catchup")]
     pub num_concurrent_requests_during_catchup: u32,
+    /// Whether to also request state parts from peers, in parallel with
+    /// downloading them from external storage.
+    #[serde(default)]
+    pub request_parts_from_peers: bool,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
@@ -97,6 +228,17 @@ pub enum ExternalStorageLocation {
     GCS {
         bucket: String,
     },
+    /// Any HTTP(S) server serving state dumps as static files.
+    /// Parts are listed using the manifest files published next to them.
+    Http {
+        /// Url of the root directory of state dumps.
+        url: String,
+    },
+    /// A node serving its state dumps on the RPC port, see `rpc.state_parts_mirror_dir`.
+    Mirror {
+        /// Url of the RPC server of the node, for example `http://127.0.0.1:3030`.
+        rpc_url: String,
+    },
 }
 
 /// Configures how to dump state to external storage.
@@ -235,6 +377,10 @@ pub fn default_header_sync_expected_height_per_second() -> u64 {
     10
 }
 
+pub fn default_header_sync_max_parallel_ranges() -> usize {
+    8
+}
+
 pub fn default_sync_check_period() -> Duration {
     Duration::from_secs(10 * 30)
 }
@@ -255,6 +401,7 @@ pub fn default_state_sync() -> Option<StateSyncConfig> {
             num_concurrent_requests: DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL,
             num_concurrent_requests_during_catchup:
                 DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL,
+            request_parts_from_peers: false,
         }),
     })
 }
@@ -330,6 +477,9 @@ pub struct ClientConfig {
     pub header_sync_stall_ban_timeout: Duration,
     /// Expected increase of header head height per second during header sync
     pub header_sync_expected_height_per_second: u64,
+    /// Maximum number of header ranges requested from other peers in parallel with the main batch.
+    /// Zero disables parallel range fetching.
+    pub header_sync_max_parallel_ranges: usize,
     /// How long to wait for a response during state sync
     pub state_sync_timeout: Duration,
     /// Minimum number of peers to start syncing.
@@ -386,6 +536,9 @@ pub struct ClientConfig {
     /// Whether to use the State Sync mechanism.
     /// If disabled, the node will do Block Sync instead of State Sync.
     pub state_sync_enabled: bool,
+    /// Whether to sync epochs over light client blocks before State Sync.
+    /// Only has effect on a node with an empty chain that is far behind its peers.
+    pub epoch_sync_enabled: bool,
     /// Options for syncing state.
     pub state_sync: StateSyncConfig,
     /// Limit of the size of per-shard transaction pool measured in bytes. If not set, the size
@@ -442,6 +595,7 @@ impl ClientConfig {
             header_sync_stall_ban_timeout: Duration::from_secs(30),
             state_sync_timeout: Duration::from_secs(TEST_STATE_SYNC_TIMEOUT),
             header_sync_expected_height_per_second: 1,
+            header_sync_max_parallel_ranges: 0,
             min_num_peers: 1,
             log_summary_period: Duration::from_secs(10),
             produce_empty_blocks: true,
@@ -469,6 +623,7 @@ impl ClientConfig {
             flat_storage_creation_enabled: true,
             flat_storage_creation_period: Duration::from_secs(1),
             state_sync_enabled,
+            epoch_sync_enabled: false,
             state_sync: StateSyncConfig::default(),
             transaction_pool_size_limit: None,
             enable_multiline_logging: false,
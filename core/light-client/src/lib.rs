@@ -0,0 +1,420 @@
+//! Verification of the unc chain for light clients.
+//!
+//! A light client follows the chain by validating light client blocks (as returned by the
+//! `next_light_client_block` RPC method), starting from a trusted checkpoint. A light client
+//! block is valid if the block after next endorses it with more than 2/3 of the pledge of the
+//! block producers of its epoch, the same threshold as the one checked by the consensus. Every
+//! light client block carries the block producers of the next epoch, so that the light client
+//! can move from one epoch to the next.
+//!
+//! Once the light client knows a head, it can verify that a transaction or a receipt has been
+//! executed with a given outcome (as returned by the `light_client_proof` RPC method): the
+//! outcome is proven to be a part of a block, which is proven to be an ancestor of the head.
+//!
+//! The verifier does no I/O: the caller is responsible for fetching the blocks and proofs
+//! and for persisting `LightClient` (which is borsh serializable) between runs. It is
+//! `#![no_std]` without the `std` feature and only depends on borsh, sha2 and ed25519-dalek,
+//! so that it can be embedded in constrained environments such as contracts. Hence it has its
+//! own types rather than the views of unc-primitives: they only keep what the verification
+//! needs and are hashed exactly as the corresponding chain types. Only ed25519 keys are
+//! supported, which is the only key type of block producers.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+use ed25519_dalek::Verifier;
+use sha2::Digest;
+
+#[cfg(test)]
+mod tests;
+
+pub type BlockHeight = u64;
+pub type Balance = u128;
+pub type Power = u128;
+
+/// SHA-256 hash, as `unc_primitives::hash::CryptoHash`.
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub struct CryptoHash(pub [u8; 32]);
+
+impl CryptoHash {
+    pub fn hash_bytes(bytes: &[u8]) -> Self {
+        CryptoHash(sha2::Sha256::digest(bytes).into())
+    }
+
+    /// Hash of the borsh serialization of `value`.
+    pub fn hash_borsh<T: BorshSerialize>(value: T) -> Self {
+        Self::hash_bytes(&borsh::to_vec(&value).expect("Failed to serialize"))
+    }
+}
+
+/// Hash of the concatenation of two hashes, as `unc_primitives::merkle::combine_hash`.
+pub fn combine_hash(hash1: &CryptoHash, hash2: &CryptoHash) -> CryptoHash {
+    CryptoHash::hash_borsh((hash1, hash2))
+}
+
+/// Ed25519 public key.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub [u8; 32]);
+
+/// Ed25519 signature.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Signature(pub [u8; 64]);
+
+impl PublicKey {
+    pub fn verify(&self, data: &[u8], signature: &Signature) -> bool {
+        let Ok(public_key) = ed25519_dalek::VerifyingKey::from_bytes(&self.0) else {
+            return false;
+        };
+        public_key.verify(data, &ed25519_dalek::Signature::from_bytes(&signature.0)).is_ok()
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MerklePathItem {
+    pub hash: CryptoHash,
+    pub direction: Direction,
+}
+
+pub type MerklePath = Vec<MerklePathItem>;
+
+/// Root of the merkle tree with `item_hash` at the position described by `path`, as
+/// `unc_primitives::merkle::compute_root_from_path`.
+pub fn compute_root_from_path(path: &[MerklePathItem], item_hash: CryptoHash) -> CryptoHash {
+    path.iter().fold(item_hash, |hash, item| match item.direction {
+        Direction::Left => combine_hash(&item.hash, &hash),
+        Direction::Right => combine_hash(&hash, &item.hash),
+    })
+}
+
+/// Block producer, as `unc_primitives::views::validator_power_and_pledge_view::ValidatorPowerAndPledgeView`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorPowerAndPledge {
+    pub account_id: String,
+    pub public_key: PublicKey,
+    pub power: Power,
+    pub pledge: Balance,
+}
+
+/// Hash of `block_producers` as committed to by `next_bp_hash`: the hash of the borsh
+/// serialization of the `unc_primitives::types::validator_power_and_pledge::ValidatorPowerAndPledge`s,
+/// which only have a `V1` version (tag 0) and whose ed25519 public keys have key type 0.
+pub fn block_producers_hash(block_producers: &[ValidatorPowerAndPledge]) -> CryptoHash {
+    const V1: u8 = 0;
+    const ED25519: u8 = 0;
+    CryptoHash::hash_borsh(
+        block_producers
+            .iter()
+            .map(|bp| (V1, &bp.account_id, ED25519, &bp.public_key, bp.power, bp.pledge))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Fields of a block header covered by the light client block hash, as
+/// `unc_primitives::block_header::BlockHeaderInnerLite`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeaderInnerLite {
+    pub height: BlockHeight,
+    pub epoch_id: CryptoHash,
+    pub next_epoch_id: CryptoHash,
+    pub prev_state_root: CryptoHash,
+    pub outcome_root: CryptoHash,
+    /// Timestamp in nanoseconds.
+    pub timestamp: u64,
+    pub next_bp_hash: CryptoHash,
+    pub block_merkle_root: CryptoHash,
+}
+
+/// Header of a block, as `unc_primitives::views::LightClientBlockLiteView`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LightClientBlockLite {
+    pub prev_block_hash: CryptoHash,
+    pub inner_rest_hash: CryptoHash,
+    pub inner_lite: BlockHeaderInnerLite,
+}
+
+impl LightClientBlockLite {
+    pub fn hash(&self) -> CryptoHash {
+        let inner_lite_hash = CryptoHash::hash_borsh(&self.inner_lite);
+        let inner_hash = combine_hash(&inner_lite_hash, &self.inner_rest_hash);
+        combine_hash(&inner_hash, &self.prev_block_hash)
+    }
+}
+
+/// Light client block, as `unc_primitives::views::LightClientBlockView`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LightClientBlock {
+    pub prev_block_hash: CryptoHash,
+    pub next_block_inner_hash: CryptoHash,
+    pub inner_lite: BlockHeaderInnerLite,
+    pub inner_rest_hash: CryptoHash,
+    pub next_bps: Option<Vec<ValidatorPowerAndPledge>>,
+    pub approvals_after_next: Vec<Option<Signature>>,
+}
+
+impl LightClientBlock {
+    pub fn lite(&self) -> LightClientBlockLite {
+        LightClientBlockLite {
+            prev_block_hash: self.prev_block_hash,
+            inner_rest_hash: self.inner_rest_hash,
+            inner_lite: self.inner_lite.clone(),
+        }
+    }
+
+    pub fn hash(&self) -> CryptoHash {
+        self.lite().hash()
+    }
+
+    /// Message signed by the approvals of the block after next, as
+    /// `unc_primitives::block_header::Approval::get_data_for_sig` of an endorsement of the
+    /// next block.
+    fn approval_message(&self) -> Vec<u8> {
+        const ENDORSEMENT: u8 = 0;
+        let next_block_hash = combine_hash(&self.next_block_inner_hash, &self.hash());
+        borsh::to_vec(&(ENDORSEMENT, next_block_hash, self.inner_lite.height + 2))
+            .expect("Failed to serialize")
+    }
+}
+
+/// Outcome of a transaction or receipt with the proof of its inclusion in the outcome root of
+/// its shard, as `unc_primitives::views::ExecutionOutcomeWithIdView`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionOutcomeProof {
+    pub proof: MerklePath,
+    pub block_hash: CryptoHash,
+    /// Hashes of the outcome, as `ExecutionOutcomeWithIdView::to_hashes`: the id of the
+    /// transaction or receipt, the hash of the outcome and the hashes of its logs.
+    pub outcome_hashes: Vec<CryptoHash>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    NotAfterHead { height: BlockHeight, head_height: BlockHeight },
+    UnexpectedEpoch(CryptoHash),
+    UnknownBlockProducers(CryptoHash),
+    MissingNextBlockProducers,
+    InvalidNextBlockProducers,
+    InvalidApprovalSignature(String),
+    NotEnoughApprovals { approved: Balance, total: Balance },
+    InvalidOutcomeRootProof,
+    InvalidBlockHash,
+    InvalidBlockProof(CryptoHash),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::NotAfterHead { height, head_height } => {
+                write!(f, "Block at height {height} is not after the head at height {head_height}")
+            }
+            Error::UnexpectedEpoch(_) => {
+                write!(f, "Block is neither from the epoch of the head nor from the next one")
+            }
+            Error::UnknownBlockProducers(_) => {
+                write!(f, "Block producers of the epoch of the block are not known")
+            }
+            Error::MissingNextBlockProducers => write!(
+                f,
+                "Block from the next epoch doesn't contain block producers of the epoch after"
+            ),
+            Error::InvalidNextBlockProducers => {
+                write!(f, "Next block producers don't match next_bp_hash of the block")
+            }
+            Error::InvalidApprovalSignature(account_id) => {
+                write!(f, "Invalid approval signature of {account_id}")
+            }
+            Error::NotEnoughApprovals { approved, total } => write!(
+                f,
+                "Approvals carry {approved} of the {total} total pledge, at most 2/3 of it"
+            ),
+            Error::InvalidOutcomeRootProof => {
+                write!(f, "Outcome proof doesn't match the outcome root of the block")
+            }
+            Error::InvalidBlockHash => {
+                write!(f, "Block header doesn't match the block of the outcome")
+            }
+            Error::InvalidBlockProof(_) => write!(f, "Block is not an ancestor of the head"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Checks that `approvals` of `message` are valid signatures of `block_producers` and that they
+/// carry more than 2/3 of the pledge of `block_producers`.
+///
+/// Approvals are ordered as the block producers of the approved block's epoch, they may be
+/// followed by approvals of the next epoch's block producers, which are not counted.
+fn verify_approvals(
+    message: &[u8],
+    block_producers: &[ValidatorPowerAndPledge],
+    approvals: &[Option<Signature>],
+) -> Result<(), Error> {
+    let mut approved: Balance = 0;
+    let mut total: Balance = 0;
+    for (block_producer, approval) in block_producers
+        .iter()
+        .zip(approvals.iter().map(Option::as_ref).chain(core::iter::repeat(None)))
+    {
+        total += block_producer.pledge;
+        let Some(signature) = approval else {
+            continue;
+        };
+        if !block_producer.public_key.verify(message, signature) {
+            return Err(Error::InvalidApprovalSignature(block_producer.account_id.clone()));
+        }
+        approved += block_producer.pledge;
+    }
+    // Same as `can_approved_block_be_produced` of the consensus.
+    let threshold = total * 2 / 3;
+    if approved <= threshold && threshold > 0 {
+        return Err(Error::NotEnoughApprovals { approved, total });
+    }
+    Ok(())
+}
+
+/// State of a light client: the most recent validated block and the block producers needed to
+/// validate the blocks which follow it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LightClient {
+    head: LightClientBlockLite,
+    /// Ordered block producers of the epoch of `head`. Unknown if `head` is the checkpoint,
+    /// in which case the light client waits for a block of the next epoch.
+    block_producers: Option<Vec<ValidatorPowerAndPledge>>,
+    /// Ordered block producers of the epoch after the epoch of `head`.
+    next_block_producers: Vec<ValidatorPowerAndPledge>,
+}
+
+impl LightClient {
+    /// Starts a light client from a trusted checkpoint. The checkpoint itself is not validated
+    /// (besides its next block producers matching `next_bp_hash`): it has to come from a trusted
+    /// source.
+    pub fn new(checkpoint: &LightClientBlock) -> Result<Self, Error> {
+        let next_block_producers =
+            checkpoint.next_bps.clone().ok_or(Error::MissingNextBlockProducers)?;
+        check_next_block_producers(checkpoint, &next_block_producers)?;
+        Ok(Self { head: checkpoint.lite(), block_producers: None, next_block_producers })
+    }
+
+    pub fn head(&self) -> &LightClientBlockLite {
+        &self.head
+    }
+
+    pub fn head_hash(&self) -> CryptoHash {
+        self.head.hash()
+    }
+
+    /// Validates `block` as a successor of the head.
+    pub fn validate(&self, block: &LightClientBlock) -> Result<(), Error> {
+        if block.inner_lite.height <= self.head.inner_lite.height {
+            return Err(Error::NotAfterHead {
+                height: block.inner_lite.height,
+                head_height: self.head.inner_lite.height,
+            });
+        }
+        let epoch_id = block.inner_lite.epoch_id;
+        let block_producers = if epoch_id == self.head.inner_lite.epoch_id {
+            self.block_producers.as_ref().ok_or(Error::UnknownBlockProducers(epoch_id))?
+        } else if epoch_id == self.head.inner_lite.next_epoch_id {
+            &self.next_block_producers
+        } else {
+            return Err(Error::UnexpectedEpoch(epoch_id));
+        };
+        verify_approvals(&block.approval_message(), block_producers, &block.approvals_after_next)?;
+
+        match &block.next_bps {
+            Some(next_bps) => check_next_block_producers(block, next_bps),
+            // The next block producers are needed to validate the blocks of the next epoch.
+            None if epoch_id != self.head.inner_lite.epoch_id => {
+                Err(Error::MissingNextBlockProducers)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Validates `block` and makes it the new head.
+    pub fn update(&mut self, block: &LightClientBlock) -> Result<(), Error> {
+        self.validate(block)?;
+        if block.inner_lite.epoch_id == self.head.inner_lite.next_epoch_id {
+            let next_block_producers =
+                block.next_bps.clone().ok_or(Error::MissingNextBlockProducers)?;
+            self.block_producers =
+                Some(core::mem::replace(&mut self.next_block_producers, next_block_producers));
+        }
+        self.head = block.lite();
+        Ok(())
+    }
+
+    /// Verifies a proof (as returned by the `light_client_proof` RPC method, with the head of
+    /// this light client as `light_client_head`) of the execution of a transaction or receipt.
+    pub fn verify_execution_proof(
+        &self,
+        outcome_proof: &ExecutionOutcomeProof,
+        outcome_root_proof: &[MerklePathItem],
+        block_header_lite: &LightClientBlockLite,
+        block_proof: &[MerklePathItem],
+    ) -> Result<(), Error> {
+        verify_execution_proof(
+            outcome_proof,
+            outcome_root_proof,
+            block_header_lite,
+            block_proof,
+            &self.head,
+        )
+    }
+}
+
+fn check_next_block_producers(
+    block: &LightClientBlock,
+    next_block_producers: &[ValidatorPowerAndPledge],
+) -> Result<(), Error> {
+    if block_producers_hash(next_block_producers) != block.inner_lite.next_bp_hash {
+        return Err(Error::InvalidNextBlockProducers);
+    }
+    Ok(())
+}
+
+/// Verifies that the outcome of a transaction or receipt is a part of the block described by
+/// `block_header_lite` and that this block is `head` or one of its ancestors.
+pub fn verify_execution_proof(
+    outcome_proof: &ExecutionOutcomeProof,
+    outcome_root_proof: &[MerklePathItem],
+    block_header_lite: &LightClientBlockLite,
+    block_proof: &[MerklePathItem],
+    head: &LightClientBlockLite,
+) -> Result<(), Error> {
+    let outcome_hash = CryptoHash::hash_borsh(&outcome_proof.outcome_hashes);
+    let shard_outcome_root = compute_root_from_path(&outcome_proof.proof, outcome_hash);
+    let block_outcome_root =
+        compute_root_from_path(outcome_root_proof, CryptoHash::hash_borsh(shard_outcome_root));
+    if block_outcome_root != block_header_lite.inner_lite.outcome_root {
+        return Err(Error::InvalidOutcomeRootProof);
+    }
+
+    let block_hash = block_header_lite.hash();
+    if block_hash != outcome_proof.block_hash {
+        return Err(Error::InvalidBlockHash);
+    }
+
+    // The block merkle root of the head covers all its ancestors, but not the head itself,
+    // which is proven with an empty path.
+    let head_hash = head.hash();
+    if block_proof.is_empty() && block_hash == head_hash {
+        return Ok(());
+    }
+    if compute_root_from_path(block_proof, block_hash) != head.inner_lite.block_merkle_root {
+        return Err(Error::InvalidBlockProof(block_hash));
+    }
+    Ok(())
+}
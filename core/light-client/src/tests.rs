@@ -0,0 +1,201 @@
+use super::*;
+use alloc::vec;
+use ed25519_dalek::{Signer, SigningKey};
+
+fn hash(bytes: &[u8]) -> CryptoHash {
+    CryptoHash::hash_bytes(bytes)
+}
+
+fn signing_key(name: &str) -> SigningKey {
+    SigningKey::from_bytes(&hash(name.as_bytes()).0)
+}
+
+fn block_producers(names: &[&str], pledge: Balance) -> Vec<ValidatorPowerAndPledge> {
+    names
+        .iter()
+        .map(|name| ValidatorPowerAndPledge {
+            account_id: String::from(*name),
+            public_key: PublicKey(signing_key(name).verifying_key().to_bytes()),
+            power: 1,
+            pledge,
+        })
+        .collect()
+}
+
+fn epoch(n: u64) -> CryptoHash {
+    hash(&n.to_le_bytes())
+}
+
+/// Light client block at `height` of epoch number `epoch_n`, approved by `signers`.
+fn light_client_block(
+    height: BlockHeight,
+    epoch_n: u64,
+    signers: &[&str],
+    next_block_producers: &[ValidatorPowerAndPledge],
+) -> LightClientBlock {
+    let mut block = LightClientBlock {
+        prev_block_hash: hash(&height.to_le_bytes()),
+        next_block_inner_hash: hash(b"next"),
+        inner_lite: BlockHeaderInnerLite {
+            height,
+            epoch_id: epoch(epoch_n),
+            next_epoch_id: epoch(epoch_n + 1),
+            prev_state_root: CryptoHash::default(),
+            outcome_root: CryptoHash::default(),
+            timestamp: 0,
+            next_bp_hash: block_producers_hash(next_block_producers),
+            block_merkle_root: CryptoHash::default(),
+        },
+        inner_rest_hash: hash(b"rest"),
+        next_bps: Some(next_block_producers.to_vec()),
+        approvals_after_next: vec![],
+    };
+    let message = block.approval_message();
+    block.approvals_after_next = signers
+        .iter()
+        .map(|name| Some(Signature(signing_key(name).sign(&message).to_bytes())))
+        .collect();
+    block
+}
+
+#[test]
+fn follow_chain() {
+    let bps1 = block_producers(&["test0", "test1", "test2"], 10);
+    let bps2 = block_producers(&["test3", "test4"], 10);
+    let bps3 = block_producers(&["test5"], 10);
+
+    let checkpoint = light_client_block(10, 0, &[], &bps1);
+    let mut client = LightClient::new(&checkpoint).unwrap();
+    assert_eq!(client.head_hash(), checkpoint.hash());
+
+    // Block producers of the checkpoint's epoch are not known.
+    let block = light_client_block(11, 0, &["test0", "test1", "test2"], &bps1);
+    assert_eq!(client.validate(&block), Err(Error::UnknownBlockProducers(epoch(0))));
+
+    let block = light_client_block(20, 1, &["test0", "test1", "test2"], &bps2);
+    client.update(&block).unwrap();
+    assert_eq!(client.head_hash(), block.hash());
+
+    // Blocks of the head's epoch are validated against the same block producers.
+    let block = light_client_block(25, 1, &["test0", "test1", "test2"], &bps2);
+    client.update(&block).unwrap();
+
+    let block = light_client_block(30, 2, &["test3", "test4"], &bps3);
+    client.update(&block).unwrap();
+
+    // The client survives a round trip through its serialized form.
+    let client: LightClient = borsh::from_slice(&borsh::to_vec(&client).unwrap()).unwrap();
+    let block = light_client_block(40, 3, &["test5"], &bps1);
+    client.validate(&block).unwrap();
+}
+
+#[test]
+fn approvals_are_weighted_by_pledge() {
+    let mut bps = block_producers(&["test0", "test1", "test2"], 10);
+    bps[0].power = 100;
+    bps[2].pledge = 30;
+    let client = LightClient::new(&light_client_block(10, 0, &[], &bps)).unwrap();
+
+    // The power of the block producers doesn't matter.
+    let block = light_client_block(20, 1, &["test0", "test1"], &bps);
+    assert_eq!(client.validate(&block), Err(Error::NotEnoughApprovals { approved: 20, total: 50 }));
+    let mut block = light_client_block(20, 1, &["test0", "test1", "test2"], &bps);
+    block.approvals_after_next[0] = None;
+    client.validate(&block).unwrap();
+}
+
+#[test]
+fn invalid_blocks() {
+    let bps1 = block_producers(&["test0", "test1", "test2"], 10);
+    let bps2 = block_producers(&["test3"], 10);
+    let client = LightClient::new(&light_client_block(10, 0, &[], &bps1)).unwrap();
+
+    let block = light_client_block(10, 1, &["test0", "test1", "test2"], &bps2);
+    assert_eq!(client.validate(&block), Err(Error::NotAfterHead { height: 10, head_height: 10 }));
+
+    let block = light_client_block(20, 2, &["test0", "test1", "test2"], &bps2);
+    assert_eq!(client.validate(&block), Err(Error::UnexpectedEpoch(epoch(2))));
+
+    // Exactly 2/3 of the pledge is not enough.
+    let block = light_client_block(20, 1, &["test0", "test1"], &bps2);
+    assert_eq!(client.validate(&block), Err(Error::NotEnoughApprovals { approved: 20, total: 30 }));
+
+    let mut block = light_client_block(20, 1, &["test0", "test1", "test2"], &bps2);
+    block.inner_lite.prev_state_root = hash(b"tampered");
+    assert_eq!(client.validate(&block), Err(Error::InvalidApprovalSignature("test0".into())));
+
+    let mut block = light_client_block(20, 1, &["test0", "test1", "test2"], &bps2);
+    block.next_bps = Some(bps1);
+    assert_eq!(client.validate(&block), Err(Error::InvalidNextBlockProducers));
+
+    let mut block = light_client_block(20, 1, &["test0", "test1", "test2"], &bps2);
+    block.next_bps = None;
+    assert_eq!(client.validate(&block), Err(Error::MissingNextBlockProducers));
+}
+
+/// Root of the merkle tree of two items and the paths of both items.
+fn merklize_pair(left: CryptoHash, right: CryptoHash) -> (CryptoHash, [MerklePath; 2]) {
+    (
+        combine_hash(&left, &right),
+        [
+            vec![MerklePathItem { hash: right, direction: Direction::Right }],
+            vec![MerklePathItem { hash: left, direction: Direction::Left }],
+        ],
+    )
+}
+
+#[test]
+fn execution_proof() {
+    let mut outcome = ExecutionOutcomeProof {
+        proof: vec![],
+        block_hash: CryptoHash::default(),
+        outcome_hashes: vec![hash(b"tx"), hash(b"outcome"), hash(b"log")],
+    };
+    let (shard_outcome_root, [outcome_path, _]) = merklize_pair(
+        CryptoHash::hash_borsh(&outcome.outcome_hashes),
+        CryptoHash::hash_borsh(vec![hash(b"other outcome")]),
+    );
+    outcome.proof = outcome_path;
+    let (outcome_root, paths) = merklize_pair(
+        CryptoHash::hash_borsh(hash(b"other shard")),
+        CryptoHash::hash_borsh(shard_outcome_root),
+    );
+    let outcome_root_proof = paths[1].clone();
+
+    let mut block_header_lite = light_client_block(10, 0, &[], &[]).lite();
+    block_header_lite.inner_lite.outcome_root = outcome_root;
+    outcome.block_hash = block_header_lite.hash();
+
+    // The leaves of the block merkle tree are the block hashes themselves.
+    let (block_merkle_root, paths) = merklize_pair(hash(b"genesis"), outcome.block_hash);
+    let block_proof = paths[1].clone();
+    let mut head = light_client_block(20, 0, &[], &[]).lite();
+    head.inner_lite.block_merkle_root = block_merkle_root;
+
+    verify_execution_proof(&outcome, &outcome_root_proof, &block_header_lite, &block_proof, &head)
+        .unwrap();
+    // The head itself is proven with an empty path.
+    verify_execution_proof(
+        &outcome,
+        &outcome_root_proof,
+        &block_header_lite,
+        &[],
+        &block_header_lite,
+    )
+    .unwrap();
+
+    assert_eq!(
+        verify_execution_proof(&outcome, &paths[0], &block_header_lite, &block_proof, &head),
+        Err(Error::InvalidOutcomeRootProof)
+    );
+    let mut other_header = light_client_block(11, 0, &[], &[]).lite();
+    other_header.inner_lite.outcome_root = outcome_root;
+    assert_eq!(
+        verify_execution_proof(&outcome, &outcome_root_proof, &other_header, &block_proof, &head),
+        Err(Error::InvalidBlockHash)
+    );
+    assert_eq!(
+        verify_execution_proof(&outcome, &outcome_root_proof, &block_header_lite, &paths[0], &head),
+        Err(Error::InvalidBlockProof(outcome.block_hash))
+    );
+}
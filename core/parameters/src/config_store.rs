@@ -40,6 +40,10 @@ static CONFIG_DIFFS: &[(ProtocolVersion, &str)] = &[
     (129, include_config!("129.yaml")),
     // Introduce ETH-implicit accounts.
     (138, include_config!("138.yaml")),
+    // Enable the bulk memory and multi-value WebAssembly proposals.
+    (139, include_config!("139.yaml")),
+    // Enable yield/resume of function calls.
+    (140, include_config!("140.yaml")),
 ];
 
 /// Testnet parameters for versions <= 29, which (incorrectly) differed from mainnet parameters
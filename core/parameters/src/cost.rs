@@ -261,6 +261,7 @@ pub enum ActionCosts {
     delegate = 15,
     register_rsa2048_keys = 16,
     create_rsa2048_challenge = 17,
+    use_global_contract = 18,
 }
 
 impl ExtCosts {
@@ -470,6 +471,11 @@ impl RuntimeFeesConfig {
                     send_not_sir: 115123062500,
                     execution: 115123062500,
                 },
+                ActionCosts::use_global_contract => Fee {
+                    send_sir: 115123062500,
+                    send_not_sir: 115123062500,
+                    execution: 115123062500,
+                },
             },
         }
     }
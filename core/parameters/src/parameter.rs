@@ -146,6 +146,8 @@ pub enum Parameter {
     Wasmer2StackLimit,
     MaxLocalsPerContract,
     AccountIdValidityRulesVersion,
+    YieldTimeoutLengthInBlocks,
+    MaxYieldPayloadSize,
 
     // Contract runtime features
     #[strum(serialize = "disable_9393_fix")]
@@ -159,9 +161,13 @@ pub enum Parameter {
     FunctionCallWeight,
     VmKind,
     EthAccounts,
+    WasmBulkMemory,
+    WasmMultiValue,
+    YieldResume,
 
     ActionRegisterRSA2048Keys,
     ActionCreateRSA2048Challenge,
+    ActionUseGlobalContract,
 }
 
 #[derive(
@@ -196,6 +202,7 @@ pub enum FeeParameter {
     ActionDelegate,
     ActionRegisterRSA2048Keys,
     ActionCreateRSA2048Challenge,
+    ActionUseGlobalContract,
 }
 
 impl Parameter {
@@ -229,6 +236,8 @@ impl Parameter {
             Parameter::Wasmer2StackLimit,
             Parameter::MaxLocalsPerContract,
             Parameter::AccountIdValidityRulesVersion,
+            Parameter::YieldTimeoutLengthInBlocks,
+            Parameter::MaxYieldPayloadSize,
         ]
         .iter()
     }
@@ -257,6 +266,7 @@ impl From<ActionCosts> for FeeParameter {
             ActionCosts::new_data_receipt_byte => Self::DataReceiptCreationPerByte,
             ActionCosts::register_rsa2048_keys => Self::ActionRegisterRSA2048Keys,
             ActionCosts::create_rsa2048_challenge => Self::ActionCreateRSA2048Challenge,
+            ActionCosts::use_global_contract => Self::ActionUseGlobalContract,
         }
     }
 }
@@ -325,6 +325,9 @@ impl TryFrom<&ParameterTable> for RuntimeConfig {
                 alt_bn128: params.get(Parameter::AltBn128)?,
                 function_call_weight: params.get(Parameter::FunctionCallWeight)?,
                 eth_accounts: params.get(Parameter::EthAccounts)?,
+                bulk_memory: params.get(Parameter::WasmBulkMemory)?,
+                multi_value: params.get(Parameter::WasmMultiValue)?,
+                yield_resume_host_functions: params.get(Parameter::YieldResume)?,
             },
             account_creation_config: AccountCreationConfig {
                 min_allowed_top_level_account_length: params
@@ -221,6 +221,12 @@ pub struct VMConfigView {
     pub function_call_weight: bool,
     /// See [`VMConfig::eth_accounts`].
     pub eth_accounts: bool,
+    /// See [`VMConfig::bulk_memory`].
+    pub bulk_memory: bool,
+    /// See [`VMConfig::multi_value`].
+    pub multi_value: bool,
+    /// See [`VMConfig::yield_resume_host_functions`].
+    pub yield_resume_host_functions: bool,
 
     /// Describes limits for VM and Runtime.
     ///
@@ -246,6 +252,9 @@ impl From<crate::vm::Config> for VMConfigView {
             function_call_weight: config.function_call_weight,
             vm_kind: config.vm_kind,
             eth_accounts: config.eth_accounts,
+            bulk_memory: config.bulk_memory,
+            multi_value: config.multi_value,
+            yield_resume_host_functions: config.yield_resume_host_functions,
         }
     }
 }
@@ -267,6 +276,9 @@ impl From<VMConfigView> for crate::vm::Config {
             function_call_weight: view.function_call_weight,
             vm_kind: view.vm_kind,
             eth_accounts: view.eth_accounts,
+            bulk_memory: view.bulk_memory,
+            multi_value: view.multi_value,
+            yield_resume_host_functions: view.yield_resume_host_functions,
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::cost::{ExtCostsConfig, ParameterCost};
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use unc_primitives_core::config::AccountIdValidityRulesVersion;
@@ -17,6 +17,7 @@ use unc_primitives_core::types::Gas;
     Debug,
     Hash,
     BorshSerialize,
+    BorshDeserialize,
     PartialEq,
     Eq,
     strum::EnumString,
@@ -132,6 +133,10 @@ pub struct LimitConfig {
     /// historically.
     #[serde(default = "AccountIdValidityRulesVersion::v0")]
     pub account_id_validity_rules_version: AccountIdValidityRulesVersion,
+    /// Number of blocks after which a yielded promise that was not resumed times out.
+    pub yield_timeout_length_in_blocks: u64,
+    /// Max length of the payload a yielded promise can be resumed with.
+    pub max_yield_payload_size: u64,
 }
 
 /// Dynamic configuration parameters required for the WASM runtime to
@@ -183,6 +188,15 @@ pub struct Config {
     /// Enable the `EthAccounts` protocol feature.
     pub eth_accounts: bool,
 
+    /// Enable the `WasmBulkMemory` protocol feature.
+    pub bulk_memory: bool,
+
+    /// Enable the `WasmMultiValue` protocol feature.
+    pub multi_value: bool,
+
+    /// Enable the host functions added by the `YieldExecution` protocol feature.
+    pub yield_resume_host_functions: bool,
+
     /// Describes limits for VM and Runtime.
     pub limit_config: LimitConfig,
 }
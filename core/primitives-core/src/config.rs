@@ -26,7 +26,18 @@ impl AccountIdValidityRulesVersion {
 }
 
 /// Configuration of view methods execution, during which no costs should be charged.
-#[derive(Default, Clone, serde::Serialize, serde::Deserialize, Debug, Hash, PartialEq, Eq)]
+#[derive(
+    Default,
+    Clone,
+    serde::Serialize,
+    serde::Deserialize,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    Debug,
+    Hash,
+    PartialEq,
+    Eq,
+)]
 pub struct ViewConfig {
     /// If specified, defines max burnt gas per view method.
     pub max_gas_burnt: Gas,
@@ -121,6 +121,20 @@ pub enum ProtocolFeature {
     /// Enables chunk validation which is introduced with stateless validation.
     ChunkValidation,
     EthAccounts,
+    /// Allow contracts to use the WebAssembly bulk memory operations proposal. Bulk memory
+    /// instructions are charged in proportion to the memory they touch.
+    WasmBulkMemory,
+    /// Allow contracts to use the WebAssembly multi-value proposal.
+    WasmMultiValue,
+    /// Allow contracts to suspend a function call until it is resumed with a payload or times
+    /// out, via the `promise_yield_create` and `promise_yield_resume` host functions.
+    YieldExecution,
+    /// Allow contract code to be deployed once, keyed by its hash, and referenced by any number
+    /// of accounts via the `DeployGlobalContract` and `UseGlobalContract` actions.
+    GlobalContracts,
+    /// Peers always encrypt their connections with Noise: a Handshake without the Noise message
+    /// is rejected, so that it can't be stripped to downgrade the connection to plaintext.
+    PeerEncryption,
 }
 
 impl ProtocolFeature {
@@ -177,6 +191,10 @@ impl ProtocolFeature {
             ProtocolFeature::RejectBlocksWithOutdatedProtocolVersions => 132,
             ProtocolFeature::ChunkValidation => 137,
             ProtocolFeature::EthAccounts => 138,
+            ProtocolFeature::WasmBulkMemory | ProtocolFeature::WasmMultiValue => 139,
+            ProtocolFeature::YieldExecution => 140,
+            ProtocolFeature::GlobalContracts => 141,
+            ProtocolFeature::PeerEncryption => 142,
         }
     }
 }
@@ -189,7 +207,7 @@ const STABLE_PROTOCOL_VERSION: ProtocolVersion = 64;
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
     // On nightly, pick big enough version to support all features.
-    139
+    142
 } else {
     // Enable all stable features.
     STABLE_PROTOCOL_VERSION
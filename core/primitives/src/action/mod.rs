@@ -7,6 +7,7 @@ use std::fmt;
 use unc_crypto::PublicKey;
 use unc_primitives_core::{
     account::AccessKey,
+    hash::CryptoHash,
     serialize::dec_format,
     types::{AccountId, Balance, Gas},
 };
@@ -202,6 +203,44 @@ impl fmt::Debug for CreateRsa2048ChallengeAction {
     }
 }
 
+/// Deploy a contract globally, storing the code once under its hash.
+///
+/// The code is stored in the state of the shard the receiver belongs to and the receiver pays
+/// for its storage. Any account of that shard can then run it via `UseGlobalContractAction`.
+#[serde_as]
+#[derive(
+    BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize, PartialEq, Eq, Clone,
+)]
+pub struct DeployGlobalContractAction {
+    /// WebAssembly binary
+    #[serde_as(as = "Base64")]
+    pub code: Vec<u8>,
+}
+
+impl fmt::Debug for DeployGlobalContractAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeployGlobalContractAction")
+            .field("code", &format_args!("{}", base64(&self.code)))
+            .finish()
+    }
+}
+
+/// Make the receiver run a globally deployed contract, replacing the code deployed on it.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct UseGlobalContractAction {
+    /// Hash of the code previously deployed with `DeployGlobalContractAction`.
+    pub code_hash: CryptoHash,
+}
+
 #[derive(
     BorshSerialize,
     BorshDeserialize,
@@ -229,6 +268,10 @@ pub enum Action {
     Delegate(Box<delegate::SignedDelegateAction>),
     RegisterRsa2048Keys(Box<RegisterRsa2048KeysAction>),
     CreateRsa2048Challenge(Box<CreateRsa2048ChallengeAction>),
+    /// Stores a Wasm code once, keyed by its hash
+    DeployGlobalContract(DeployGlobalContractAction),
+    /// Makes the receiver_id run a globally deployed Wasm code
+    UseGlobalContract(Box<UseGlobalContractAction>),
 }
 
 const _: () = assert!(
@@ -314,3 +357,15 @@ impl From<CreateRsa2048ChallengeAction> for Action {
         Self::CreateRsa2048Challenge(Box::new(create_rsa2048_challenge_action))
     }
 }
+
+impl From<DeployGlobalContractAction> for Action {
+    fn from(deploy_global_contract_action: DeployGlobalContractAction) -> Self {
+        Self::DeployGlobalContract(deploy_global_contract_action)
+    }
+}
+
+impl From<UseGlobalContractAction> for Action {
+    fn from(use_global_contract_action: UseGlobalContractAction) -> Self {
+        Self::UseGlobalContract(Box::new(use_global_contract_action))
+    }
+}
@@ -1,15 +1,23 @@
-use crate::block_header::BlockHeader;
+use crate::block_header::{Approval, ApprovalInner, BlockHeader, BlockHeaderInnerLite};
 use crate::epoch_manager::block_info::BlockInfo;
 use crate::epoch_manager::epoch_info::EpochInfo;
-use crate::merkle::PartialMerkleTree;
+use crate::hash::{hash, CryptoHash};
+use crate::merkle::{combine_hash, PartialMerkleTree};
+use crate::types::validator_power_and_pledge::ValidatorPowerAndPledge;
+use crate::types::{AccountId, Balance, BlockHeight, EpochId};
 use crate::views::LightClientBlockView;
 use borsh::{BorshDeserialize, BorshSerialize};
+use unc_crypto::Signature;
 
 #[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
 pub struct EpochSyncFinalizationResponse {
+    /// Header of the first block of the epoch Epoch Sync finishes in.
     pub cur_epoch_header: BlockHeader,
+    /// Headers from the light client block of the previous epoch up to the last block of the
+    /// previous epoch, ordered by height. They link `cur_epoch_header` to the last light client
+    /// block validated during Epoch Sync.
     pub prev_epoch_headers: Vec<BlockHeader>,
-    pub header_sync_init_header: BlockHeader,
+    /// Block merkle tree of `cur_epoch_header`. Header sync continues from that header.
     pub header_sync_init_header_tree: PartialMerkleTree,
     // This Block Info is required by Epoch Manager when it checks if it's a good time to start a new Epoch.
     // Epoch Manager asks for height difference by obtaining first Block Info of the Epoch.
@@ -32,3 +40,306 @@ pub enum EpochSyncResponse {
     UpToDate,
     Advance { light_client_block_view: Box<LightClientBlockView> },
 }
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum EpochSyncError {
+    #[error("Light client block is from epoch {actual:?}, expected {expected:?}")]
+    UnexpectedEpoch { expected: EpochId, actual: EpochId },
+    #[error("Light client block doesn't contain block producers of the next epoch")]
+    MissingNextBlockProducers,
+    #[error("Next block producers don't match next_bp_hash of the light client block")]
+    InvalidNextBlockProducers,
+    #[error("Approval of {0} has invalid signature")]
+    InvalidApprovalSignature(AccountId),
+    #[error("Approvals carry {approved} of total pledge {total}, more than 2/3 is required")]
+    NotEnoughApprovals { approved: Balance, total: Balance },
+    #[error("Header at height {0} doesn't link to the previous header")]
+    BrokenHeaderChain(BlockHeight),
+    #[error("Block info of {0} doesn't match the headers")]
+    InvalidBlockInfo(CryptoHash),
+    #[error("Epoch sync data doesn't match epoch_sync_data_hash of {0}")]
+    InvalidEpochSyncData(CryptoHash),
+}
+
+/// Hash of the block described by a light client block.
+pub fn light_client_block_hash(block: &LightClientBlockView) -> CryptoHash {
+    let inner_lite = BlockHeaderInnerLite::from(block.inner_lite.clone());
+    let inner_lite_hash = hash(&borsh::to_vec(&inner_lite).expect("Failed to serialize"));
+    let inner_hash = combine_hash(&inner_lite_hash, &block.inner_rest_hash);
+    combine_hash(&inner_hash, &block.prev_block_hash)
+}
+
+/// Checks that `approvals` of `message` are valid signatures of `block_producers` and that they
+/// carry more than 2/3 of the pledge of `block_producers`, the same threshold as the one
+/// checked by the consensus.
+///
+/// Approvals are ordered as the block producers of the approved block's epoch, they may be
+/// followed by approvals of the next epoch's block producers, which are not counted.
+pub fn verify_approvals(
+    message: &[u8],
+    block_producers: &[ValidatorPowerAndPledge],
+    approvals: &[Option<Box<Signature>>],
+) -> Result<(), EpochSyncError> {
+    let mut approved: Balance = 0;
+    let mut total: Balance = 0;
+    for (block_producer, approval) in block_producers
+        .iter()
+        .zip(approvals.iter().map(|approval| approval.as_deref()).chain(std::iter::repeat(None)))
+    {
+        total += block_producer.pledge();
+        let Some(signature) = approval else {
+            continue;
+        };
+        if !signature.verify(message, block_producer.public_key()) {
+            return Err(EpochSyncError::InvalidApprovalSignature(
+                block_producer.account_id().clone(),
+            ));
+        }
+        approved += block_producer.pledge();
+    }
+    let threshold = total * 2 / 3;
+    if approved <= threshold && threshold > 0 {
+        return Err(EpochSyncError::NotEnoughApprovals { approved, total });
+    }
+    Ok(())
+}
+
+/// Validates the light client block of `epoch_id`, given the ordered block producers of that
+/// epoch. On success returns the block producers of the next epoch carried by the block.
+///
+/// The block is valid if the block after next endorses it with more than 2/3 of the pledge of
+/// the epoch's block producers and the next block producers match `next_bp_hash` of the block.
+pub fn validate_light_client_block(
+    epoch_id: &EpochId,
+    block_producers: &[ValidatorPowerAndPledge],
+    block: &LightClientBlockView,
+) -> Result<Vec<ValidatorPowerAndPledge>, EpochSyncError> {
+    if block.inner_lite.epoch_id != epoch_id.0 {
+        return Err(EpochSyncError::UnexpectedEpoch {
+            expected: epoch_id.clone(),
+            actual: EpochId(block.inner_lite.epoch_id),
+        });
+    }
+
+    let block_hash = light_client_block_hash(block);
+    let next_block_hash = combine_hash(&block.next_block_inner_hash, &block_hash);
+    let message = Approval::get_data_for_sig(
+        &ApprovalInner::Endorsement(next_block_hash),
+        block.inner_lite.height + 2,
+    );
+    verify_approvals(&message, block_producers, &block.approvals_after_next)?;
+    next_block_producers(block)
+}
+
+/// Block producers of the epoch after the light client block's epoch, checked against
+/// `next_bp_hash` of the block.
+fn next_block_producers(
+    block: &LightClientBlockView,
+) -> Result<Vec<ValidatorPowerAndPledge>, EpochSyncError> {
+    let next_block_producers: Vec<ValidatorPowerAndPledge> = block
+        .next_bps
+        .as_ref()
+        .ok_or(EpochSyncError::MissingNextBlockProducers)?
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect();
+    if CryptoHash::hash_borsh_iter(next_block_producers.iter().cloned())
+        != block.inner_lite.next_bp_hash
+    {
+        return Err(EpochSyncError::InvalidNextBlockProducers);
+    }
+    Ok(next_block_producers)
+}
+
+impl EpochSyncFinalizationResponse {
+    /// Validates the response against the light client block of the previous epoch and the
+    /// ordered block producers of the previous epoch.
+    ///
+    /// Headers have to link the light client block to `cur_epoch_header`, each of them endorsed
+    /// by more than 2/3 of the pledge of the block producers of its epoch, and block and epoch
+    /// infos have to match `epoch_sync_data_hash` of `cur_epoch_header`. Block producers of the
+    /// current epoch are the next block producers proven by the light client block.
+    pub fn validate(
+        &self,
+        prev_epoch_light_client_block: &LightClientBlockView,
+        prev_epoch_block_producers: &[ValidatorPowerAndPledge],
+    ) -> Result<(), EpochSyncError> {
+        let expected_epoch_id = EpochId(prev_epoch_light_client_block.inner_lite.next_epoch_id);
+        if self.cur_epoch_header.epoch_id() != &expected_epoch_id {
+            return Err(EpochSyncError::UnexpectedEpoch {
+                expected: expected_epoch_id,
+                actual: self.cur_epoch_header.epoch_id().clone(),
+            });
+        }
+
+        let cur_epoch_block_producers = next_block_producers(prev_epoch_light_client_block)?;
+
+        let mut prev_hash = light_client_block_hash(prev_epoch_light_client_block);
+        let mut prev_height = prev_epoch_light_client_block.inner_lite.height;
+        let mut headers = self
+            .prev_epoch_headers
+            .iter()
+            .map(|header| (header, prev_epoch_block_producers))
+            .chain(Some((&self.cur_epoch_header, cur_epoch_block_producers.as_slice())));
+        // The first header is the header of the light client block itself.
+        if let Some(first) = self.prev_epoch_headers.first() {
+            if first.hash() != &prev_hash {
+                return Err(EpochSyncError::BrokenHeaderChain(first.height()));
+            }
+            headers.next();
+        }
+        for (header, block_producers) in headers {
+            if header.prev_hash() != &prev_hash {
+                return Err(EpochSyncError::BrokenHeaderChain(header.height()));
+            }
+            let message = Approval::get_data_for_sig(
+                &ApprovalInner::new(&prev_hash, prev_height, header.height()),
+                header.height(),
+            );
+            verify_approvals(&message, block_producers, header.approvals())?;
+            prev_hash = *header.hash();
+            prev_height = header.height();
+        }
+
+        if self.prev_epoch_last_block_info.hash() != self.cur_epoch_header.prev_hash() {
+            return Err(EpochSyncError::InvalidBlockInfo(*self.prev_epoch_last_block_info.hash()));
+        }
+        if self.prev_epoch_prev_last_block_info.hash()
+            != self.prev_epoch_last_block_info.prev_hash()
+        {
+            return Err(EpochSyncError::InvalidBlockInfo(
+                *self.prev_epoch_prev_last_block_info.hash(),
+            ));
+        }
+        if self.prev_epoch_first_block_info.hash()
+            != self.prev_epoch_last_block_info.epoch_first_block()
+        {
+            return Err(EpochSyncError::InvalidBlockInfo(*self.prev_epoch_first_block_info.hash()));
+        }
+        if &self.header_sync_init_header_tree.root() != self.cur_epoch_header.block_merkle_root() {
+            return Err(EpochSyncError::InvalidEpochSyncData(*self.cur_epoch_header.hash()));
+        }
+
+        let epoch_sync_data_hash = CryptoHash::hash_borsh(&(
+            &self.prev_epoch_first_block_info,
+            &self.prev_epoch_prev_last_block_info,
+            &self.prev_epoch_last_block_info,
+            &self.prev_epoch_info,
+            &self.cur_epoch_info,
+            &self.next_epoch_info,
+        ));
+        if self.cur_epoch_header.epoch_sync_data_hash() != Some(epoch_sync_data_hash) {
+            return Err(EpochSyncError::InvalidEpochSyncData(*self.cur_epoch_header.hash()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_signer;
+    use crate::validator_signer::ValidatorSigner;
+    use crate::views::BlockHeaderInnerLiteView;
+
+    fn block_producers(names: &[&str], pledge: Balance) -> Vec<ValidatorPowerAndPledge> {
+        names
+            .iter()
+            .map(|name| {
+                let signer = create_test_signer(name);
+                ValidatorPowerAndPledge::new(
+                    signer.validator_id().clone(),
+                    signer.public_key(),
+                    1,
+                    pledge,
+                )
+            })
+            .collect()
+    }
+
+    fn light_client_block(
+        epoch_id: &EpochId,
+        signers: &[&str],
+        next_block_producers: &[ValidatorPowerAndPledge],
+    ) -> LightClientBlockView {
+        let mut block = LightClientBlockView {
+            prev_block_hash: hash(b"prev"),
+            next_block_inner_hash: hash(b"next"),
+            inner_lite: BlockHeaderInnerLiteView {
+                height: 10,
+                epoch_id: epoch_id.0,
+                next_epoch_id: hash(b"next epoch"),
+                prev_state_root: CryptoHash::default(),
+                outcome_root: CryptoHash::default(),
+                timestamp: 0,
+                timestamp_nanosec: 0,
+                next_bp_hash: CryptoHash::hash_borsh_iter(next_block_producers.iter().cloned()),
+                block_merkle_root: CryptoHash::default(),
+            },
+            inner_rest_hash: hash(b"rest"),
+            next_bps: Some(next_block_producers.iter().cloned().map(Into::into).collect()),
+            approvals_after_next: vec![],
+        };
+        let next_block_hash =
+            combine_hash(&block.next_block_inner_hash, &light_client_block_hash(&block));
+        block.approvals_after_next = signers
+            .iter()
+            .map(|name| {
+                Some(Box::new(create_test_signer(name).sign_approval(
+                    &ApprovalInner::Endorsement(next_block_hash),
+                    block.inner_lite.height + 2,
+                )))
+            })
+            .collect();
+        block
+    }
+
+    #[test]
+    fn test_validate_light_client_block() {
+        let epoch_id = EpochId(hash(b"epoch"));
+        let bps = block_producers(&["test0", "test1", "test2"], 10);
+        let next_bps = block_producers(&["test3"], 10);
+
+        let block = light_client_block(&epoch_id, &["test0", "test1", "test2"], &next_bps);
+        assert_eq!(validate_light_client_block(&epoch_id, &bps, &block), Ok(next_bps.clone()));
+
+        let other_epoch_id = EpochId(hash(b"other epoch"));
+        assert!(matches!(
+            validate_light_client_block(&other_epoch_id, &bps, &block),
+            Err(EpochSyncError::UnexpectedEpoch { .. })
+        ));
+
+        let mut tampered = block.clone();
+        tampered.inner_lite.prev_state_root = hash(b"tampered");
+        assert!(matches!(
+            validate_light_client_block(&epoch_id, &bps, &tampered),
+            Err(EpochSyncError::InvalidApprovalSignature(_))
+        ));
+
+        let mut tampered = block;
+        tampered.next_bps = Some(bps.iter().cloned().map(Into::into).collect());
+        assert_eq!(
+            validate_light_client_block(&epoch_id, &bps, &tampered),
+            Err(EpochSyncError::InvalidNextBlockProducers)
+        );
+    }
+
+    #[test]
+    fn test_approvals_are_weighted_by_pledge() {
+        let epoch_id = EpochId(hash(b"epoch"));
+        let mut bps = block_producers(&["test0", "test1", "test2"], 10);
+        let next_bps = block_producers(&["test3"], 10);
+
+        // Two out of three equal block producers is exactly 2/3, which is not enough.
+        let block = light_client_block(&epoch_id, &["test0", "test1"], &next_bps);
+        assert_eq!(
+            validate_light_client_block(&epoch_id, &bps, &block),
+            Err(EpochSyncError::NotEnoughApprovals { approved: 20, total: 30 })
+        );
+
+        // The same approvals are enough when the missing one has less pledge.
+        bps[2] = block_producers(&["test2"], 5).pop().unwrap();
+        assert!(validate_light_client_block(&epoch_id, &bps, &block).is_ok());
+    }
+}
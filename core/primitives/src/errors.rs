@@ -509,6 +509,8 @@ pub enum ActionErrorKind {
 
     /// The public key used for an  not existed  rsa key
     RsaKeysNotFound { account_id: AccountId, public_key: Box<PublicKey> },
+    /// The code hash used in `UseGlobalContract` doesn't refer to a globally deployed contract
+    GlobalContractDoesNotExist { code_hash: CryptoHash },
 }
 
 impl From<ActionErrorKind> for ActionError {
@@ -833,6 +835,9 @@ impl Display for ActionErrorKind {
                 "The public key {:?} is doesn't exist rsa key",
                 public_key
             ),
+            ActionErrorKind::GlobalContractDoesNotExist { code_hash } => {
+                write!(f, "Global contract code with hash {} doesn't exist", code_hash)
+            }
         }
     }
 }
@@ -1224,6 +1229,10 @@ pub enum HostError {
     /// Invalid input to ed25519 signature verification function (e.g. signature cannot be
     /// derived from bytes).
     Ed25519VerifyInvalidInput { msg: String },
+    /// Yield payload length exceeds the maximum permitted.
+    YieldPayloadLength { length: u64, limit: u64 },
+    /// The `data_id` passed to `promise_yield_resume` is not a 32-byte hash.
+    DataIdMalformed,
 }
 
 #[derive(
@@ -1,7 +1,7 @@
 use crate::hash::CryptoHash;
 use crate::serialize::dec_format;
 use crate::transaction::{Action, TransferAction};
-use crate::types::{AccountId, Balance, ShardId};
+use crate::types::{AccountId, Balance, BlockHeight, ShardId};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde_with::base64::Base64;
 use serde_with::serde_as;
@@ -204,5 +204,31 @@ impl DelayedReceiptIndices {
     }
 }
 
+/// Stores indices for a persistent queue of PromiseYield timeouts.
+#[derive(Default, BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct PromiseYieldIndices {
+    // First inclusive index in the queue.
+    pub first_index: u64,
+    // Exclusive end index of the queue
+    pub next_available_index: u64,
+}
+
+impl PromiseYieldIndices {
+    pub fn len(&self) -> u64 {
+        self.next_available_index - self.first_index
+    }
+}
+
+/// Entry in the queue of PromiseYield timeouts.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct PromiseYieldTimeout {
+    /// Account on which the yielded promise was created
+    pub account_id: AccountId,
+    /// `data_id` of the data receipt which resumes the yielded promise
+    pub data_id: CryptoHash,
+    /// Block height at which the yielded promise times out
+    pub expires_at: BlockHeight,
+}
+
 /// Map of shard to list of receipts to send to it.
 pub type ReceiptResult = HashMap<ShardId, Vec<Receipt>>;
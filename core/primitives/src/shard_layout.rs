@@ -231,6 +231,23 @@ pub fn account_id_to_shard_id(account_id: &AccountId, shard_layout: &ShardLayout
     }
 }
 
+/// Shards of `shard_layout` which may contain accounts whose ids start with `prefix`.
+pub fn account_prefix_to_shard_ids(prefix: &str, shard_layout: &ShardLayout) -> Vec<ShardId> {
+    match shard_layout {
+        // Accounts are spread over all shards by the hash of their ids.
+        ShardLayout::V0(_) => shard_layout.shard_ids().collect(),
+        ShardLayout::V1(ShardLayoutV1 { boundary_accounts, .. }) => {
+            // Accounts starting with `prefix` are not lower than `prefix` and only reach
+            // past the boundary accounts which are lower than `prefix` or start with it.
+            let first = boundary_accounts.iter().filter(|account| account.as_str() <= prefix);
+            let last = boundary_accounts.iter().filter(|account| {
+                account.as_str() <= prefix || account.as_str().starts_with(prefix)
+            });
+            (first.count() as ShardId..=last.count() as ShardId).collect()
+        }
+    }
+}
+
 /// Maps an account to the shard that it belongs to given a shard_layout
 pub fn account_id_to_shard_uid(account_id: &AccountId, shard_layout: &ShardLayout) -> ShardUId {
     ShardUId::from_shard_id_and_layout(
@@ -422,7 +439,9 @@ impl<'de> serde::de::Visitor<'de> for ShardUIdVisitor {
 
 #[cfg(test)]
 mod tests {
-    use crate::shard_layout::{account_id_to_shard_id, ShardLayout, ShardLayoutV1, ShardUId};
+    use crate::shard_layout::{
+        account_id_to_shard_id, account_prefix_to_shard_ids, ShardLayout, ShardLayoutV1, ShardUId,
+    };
     use rand::distributions::Alphanumeric;
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
@@ -506,6 +525,15 @@ mod tests {
         assert_eq!(account_id_to_shard_id(&"foo.goo".parse().unwrap(), &shard_layout), 4);
         assert_eq!(account_id_to_shard_id(&"goo".parse().unwrap(), &shard_layout), 4);
         assert_eq!(account_id_to_shard_id(&"zoo".parse().unwrap(), &shard_layout), 5);
+
+        assert_eq!(account_prefix_to_shard_ids("a", &shard_layout), vec![0, 1]);
+        assert_eq!(account_prefix_to_shard_ids("aurora.", &shard_layout), vec![1]);
+        assert_eq!(account_prefix_to_shard_ids("b", &shard_layout), vec![1, 2]);
+        assert_eq!(account_prefix_to_shard_ids("foo", &shard_layout), vec![3, 4]);
+        assert_eq!(account_prefix_to_shard_ids("foo.", &shard_layout), vec![3, 4]);
+        assert_eq!(account_prefix_to_shard_ids("foo.c", &shard_layout), vec![4]);
+        assert_eq!(account_prefix_to_shard_ids("z", &shard_layout), vec![5]);
+        assert_eq!(account_prefix_to_shard_ids("a", &ShardLayout::v0(3, 0)), vec![0, 1, 2]);
     }
 
     // check that after removing the fixed shards from the shard layout v1
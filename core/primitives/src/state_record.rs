@@ -4,8 +4,9 @@ use crate::receipt::{Receipt, ReceivedData};
 use crate::trie_key::trie_key_parsers::{
     parse_account_id_from_access_key_key, parse_account_id_from_account_key,
     parse_account_id_from_contract_code_key, parse_account_id_from_contract_data_key,
-    parse_account_id_from_received_data_key, parse_data_id_from_received_data_key,
-    parse_data_key_from_contract_data_key, parse_public_key_from_access_key_key,
+    parse_account_id_from_received_data_key, parse_code_hash_from_global_contract_code_key,
+    parse_data_id_from_received_data_key, parse_data_key_from_contract_data_key,
+    parse_public_key_from_access_key_key,
 };
 use crate::trie_key::{col, TrieKey};
 use crate::types::{AccountId, StoreKey, StoreValue};
@@ -43,6 +44,13 @@ pub enum StateRecord {
     /// Delayed Receipt.
     /// The receipt was delayed because the shard was overwhelmed.
     DelayedReceipt(Box<Receipt>),
+    /// Globally deployed contract code encoded in base64. It is not attached to any account and
+    /// is stored in every shard that has it.
+    GlobalContractCode {
+        code_hash: CryptoHash,
+        #[serde_as(as = "Base64")]
+        code: Vec<u8>,
+    },
 }
 
 impl StateRecord {
@@ -103,6 +111,13 @@ impl StateRecord {
                 let receipt = Receipt::try_from_slice(&value)?;
                 Some(StateRecord::DelayedReceipt(Box::new(receipt)))
             }
+            col::PROMISE_YIELD_INDICES => None,
+            col::PROMISE_YIELD_TIMEOUT => None,
+            col::PROMISE_YIELD_RECEIPT => None,
+            col::GLOBAL_CONTRACT_CODE => Some(StateRecord::GlobalContractCode {
+                code_hash: parse_code_hash_from_global_contract_code_key(&key)?,
+                code: value,
+            }),
             _ => {
                 println!("key[0]: {} is unreachable", key[0]);
                 None
@@ -119,6 +134,7 @@ impl StateRecord {
             StateRecord::PostponedReceipt { .. } => "PostponedReceipt",
             StateRecord::ReceivedData { .. } => "ReceivedData",
             StateRecord::DelayedReceipt { .. } => "DelayedReceipt",
+            StateRecord::GlobalContractCode { .. } => "GlobalContractCode",
         }
         .to_string()
     }
@@ -152,6 +168,9 @@ impl Display for StateRecord {
             ),
             StateRecord::PostponedReceipt(receipt) => write!(f, "Postponed receipt {:?}", receipt),
             StateRecord::DelayedReceipt(receipt) => write!(f, "Delayed receipt {:?}", receipt),
+            StateRecord::GlobalContractCode { code_hash, code: _ } => {
+                write!(f, "Global code {:?}: ...", code_hash)
+            }
         }
     }
 }
@@ -171,16 +190,19 @@ fn to_printable(blob: &[u8]) -> String {
     }
 }
 
-pub fn state_record_to_account_id(state_record: &StateRecord) -> &AccountId {
+/// Returns the account the record belongs to, or `None` for global contract code which is not
+/// attached to any account.
+pub fn state_record_to_account_id(state_record: &StateRecord) -> Option<&AccountId> {
     match state_record {
         StateRecord::Account { account_id, .. }
         | StateRecord::AccessKey { account_id, .. }
         | StateRecord::Contract { account_id, .. }
         | StateRecord::ReceivedData { account_id, .. }
-        | StateRecord::Data { account_id, .. } => account_id,
+        | StateRecord::Data { account_id, .. } => Some(account_id),
         StateRecord::PostponedReceipt(receipt) | StateRecord::DelayedReceipt(receipt) => {
-            &receipt.receiver_id
+            Some(&receipt.receiver_id)
         }
+        StateRecord::GlobalContractCode { .. } => None,
     }
 }
 
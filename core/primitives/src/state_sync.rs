@@ -52,6 +52,16 @@ pub enum CachedParts {
     BitArray(BitArray),
 }
 
+impl CachedParts {
+    pub fn contains(&self, part_id: u64) -> bool {
+        match self {
+            Self::AllParts => true,
+            Self::NoParts => false,
+            Self::BitArray(bit_array) => bit_array.get_bit(part_id),
+        }
+    }
+}
+
 /// Represents an array of boolean values in a compact form.
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct BitArray {
@@ -69,6 +79,10 @@ impl BitArray {
         assert!(bit < self.capacity);
         self.data[(bit / 8) as usize] |= 1 << (bit % 8);
     }
+
+    pub fn get_bit(&self, bit: u64) -> bool {
+        bit < self.capacity && self.data[(bit / 8) as usize] & (1 << (bit % 8)) != 0
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
@@ -16,8 +16,8 @@ use unc_vm_runner::{ProfileDataV2, ProfileDataV3};
 
 pub use crate::action::{
     Action, AddKeyAction, CreateAccountAction, CreateRsa2048ChallengeAction, DeleteAccountAction,
-    DeleteKeyAction, DeployContractAction, FunctionCallAction, PledgeAction,
-    RegisterRsa2048KeysAction, TransferAction,
+    DeleteKeyAction, DeployContractAction, DeployGlobalContractAction, FunctionCallAction,
+    PledgeAction, RegisterRsa2048KeysAction, TransferAction, UseGlobalContractAction,
 };
 
 pub type LogEntry = String;
@@ -47,8 +47,19 @@ pub mod col {
     pub const CONTRACT_DATA: u8 = 9;
 
     pub const RSA2048_KEY: u8 = 10;
+    /// This column id is used when storing the indices of the PromiseYield timeout queue.
+    /// NOTE: It is a singleton per shard.
+    pub const PROMISE_YIELD_INDICES: u8 = 11;
+    /// This column id is used when storing the PromiseYield timeouts.
+    pub const PROMISE_YIELD_TIMEOUT: u8 = 12;
+    /// This column id is used when storing the postponed PromiseYield receipts
+    /// which are still awaiting their resume data for a given `data_id`.
+    pub const PROMISE_YIELD_RECEIPT: u8 = 13;
+    /// This column id is used when storing contract blobs deployed globally, keyed by the hash
+    /// of the code rather than by an `account_id`.
+    pub const GLOBAL_CONTRACT_CODE: u8 = 14;
     /// All columns
-    pub const NON_DELAYED_RECEIPT_COLUMNS: [(u8, &str); 8] = [
+    pub const NON_DELAYED_RECEIPT_COLUMNS: [(u8, &str); 9] = [
         (ACCOUNT, "Account"),
         (CONTRACT_CODE, "ContractCode"),
         (ACCESS_KEY, "AccessKey"),
@@ -57,6 +68,7 @@ pub mod col {
         (PENDING_DATA_COUNT, "PendingDataCount"),
         (POSTPONED_RECEIPT, "PostponedReceipt"),
         (CONTRACT_DATA, "ContractData"),
+        (PROMISE_YIELD_RECEIPT, "PromiseYieldReceipt"),
     ];
 }
 
@@ -98,6 +110,22 @@ pub enum TrieKey {
 
     ///ca rsakeys
     Rsa2048Keys { account_id: AccountId, public_key: PublicKey },
+    /// Used to store indices of the PromiseYield timeout queue
+    /// (`primitives::receipt::PromiseYieldIndices`).
+    /// NOTE: It is a singleton per shard.
+    PromiseYieldIndices,
+    /// Used to store a PromiseYield timeout `primitives::receipt::PromiseYieldTimeout` for a
+    /// given index `u64` in the timeout queue. The queue is unique per shard.
+    PromiseYieldTimeout { index: u64 },
+    /// Used to mark a postponed PromiseYield receipt of a given receiver's `AccountId` as still
+    /// awaiting its resume data for a given `data_id`. The value is the `BlockHeight` at which
+    /// the yield times out.
+    PromiseYieldReceipt { receiver_id: AccountId, data_id: CryptoHash },
+    /// Used to store `Vec<u8>` contract code deployed globally for a given code hash. Accounts
+    /// refer to it through their `code_hash` after a `UseGlobalContract` action.
+    /// NOTE: Despite the name, it is shard-local: only accounts of the shard the code was
+    /// deployed to can use it. Resharding copies it into every child shard.
+    GlobalContractCode { code_hash: CryptoHash },
 }
 
 /// Provides `len` function.
@@ -160,6 +188,19 @@ impl TrieKey {
             TrieKey::Rsa2048Keys { account_id, public_key } => {
                 col::RSA2048_KEY.len() * 2 + account_id.len() + public_key.len()
             }
+            TrieKey::PromiseYieldIndices => col::PROMISE_YIELD_INDICES.len(),
+            TrieKey::PromiseYieldTimeout { .. } => {
+                col::PROMISE_YIELD_TIMEOUT.len() + size_of::<u64>()
+            }
+            TrieKey::PromiseYieldReceipt { receiver_id, data_id } => {
+                col::PROMISE_YIELD_RECEIPT.len()
+                    + receiver_id.len()
+                    + ACCOUNT_DATA_SEPARATOR.len()
+                    + data_id.as_ref().len()
+            }
+            TrieKey::GlobalContractCode { code_hash } => {
+                col::GLOBAL_CONTRACT_CODE.len() + code_hash.as_ref().len()
+            }
         }
     }
 
@@ -225,6 +266,23 @@ impl TrieKey {
                 buf.push(RSA2048_KEY_SEPARATOR);
                 buf.extend(borsh::to_vec(&public_key).unwrap());
             }
+            TrieKey::PromiseYieldIndices => {
+                buf.push(col::PROMISE_YIELD_INDICES);
+            }
+            TrieKey::PromiseYieldTimeout { index } => {
+                buf.push(col::PROMISE_YIELD_TIMEOUT);
+                buf.extend(&index.to_le_bytes());
+            }
+            TrieKey::PromiseYieldReceipt { receiver_id, data_id } => {
+                buf.push(col::PROMISE_YIELD_RECEIPT);
+                buf.extend(receiver_id.as_bytes());
+                buf.push(ACCOUNT_DATA_SEPARATOR);
+                buf.extend(data_id.as_ref());
+            }
+            TrieKey::GlobalContractCode { code_hash } => {
+                buf.push(col::GLOBAL_CONTRACT_CODE);
+                buf.extend(code_hash.as_ref());
+            }
         };
         debug_assert_eq!(expected_len, buf.len() - start_len);
     }
@@ -249,6 +307,10 @@ impl TrieKey {
             TrieKey::DelayedReceipt { .. } => None,
             TrieKey::ContractData { account_id, .. } => Some(account_id.clone()),
             TrieKey::Rsa2048Keys { account_id, .. } => Some(account_id.clone()),
+            TrieKey::PromiseYieldIndices => None,
+            TrieKey::PromiseYieldTimeout { .. } => None,
+            TrieKey::PromiseYieldReceipt { receiver_id, .. } => Some(receiver_id.clone()),
+            TrieKey::GlobalContractCode { .. } => None,
         }
     }
 }
@@ -439,6 +501,17 @@ pub mod trie_key_parsers {
         })
     }
 
+    pub fn parse_code_hash_from_global_contract_code_key(
+        raw_key: &[u8],
+    ) -> Result<CryptoHash, std::io::Error> {
+        CryptoHash::try_from(&raw_key[col::GLOBAL_CONTRACT_CODE.len()..]).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Can't parse CryptoHash for TrieKey::GlobalContractCode",
+            )
+        })
+    }
+
     pub fn get_raw_prefix_for_rsa_keys(account_id: &AccountId) -> Vec<u8> {
         let mut res = Vec::with_capacity(col::RSA2048_KEY.len() * 2 + account_id.len());
         res.push(col::RSA2048_KEY);
@@ -696,6 +769,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_key_for_promise_yield_receipt_consistency() {
+        for account_id in OK_ACCOUNT_IDS.iter().map(|x| x.parse::<AccountId>().unwrap()) {
+            let key = TrieKey::PromiseYieldReceipt {
+                receiver_id: account_id.clone(),
+                data_id: CryptoHash::default(),
+            };
+            let raw_key = key.to_vec();
+            assert_eq!(raw_key.len(), key.len());
+            assert_eq!(
+                trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().unwrap(),
+                account_id
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_for_promise_yield_timeouts_consistency() {
+        let key = TrieKey::PromiseYieldIndices;
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        let key = TrieKey::PromiseYieldTimeout { index: 0 };
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_key_for_global_contract_code_consistency() {
+        let code_hash = CryptoHash::hash_bytes(b"code");
+        let key = TrieKey::GlobalContractCode { code_hash };
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert_eq!(raw_key[0], col::GLOBAL_CONTRACT_CODE);
+        assert_eq!(
+            trie_key_parsers::parse_code_hash_from_global_contract_code_key(&raw_key).unwrap(),
+            code_hash
+        );
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        assert_eq!(key.get_account_id(), None);
+    }
+
     #[test]
     fn test_key_for_delayed_receipts_consistency() {
         let key = TrieKey::DelayedReceiptIndices;
@@ -764,6 +880,19 @@ mod tests {
                 None
             );
             assert_eq!(TrieKey::DelayedReceiptIndices.get_account_id(), None);
+            assert_eq!(
+                TrieKey::PromiseYieldReceipt {
+                    receiver_id: account_id.clone(),
+                    data_id: Default::default()
+                }
+                .get_account_id(),
+                Some(account_id.clone())
+            );
+            assert_eq!(
+                TrieKey::PromiseYieldTimeout { index: Default::default() }.get_account_id(),
+                None
+            );
+            assert_eq!(TrieKey::PromiseYieldIndices.get_account_id(), None);
             assert_eq!(
                 TrieKey::ContractData { account_id: account_id.clone(), key: Default::default() }
                     .get_account_id(),
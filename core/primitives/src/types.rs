@@ -195,6 +195,9 @@ pub enum StateChangeCause {
     Migration,
     /// State changes for building states for re-sharding
     Resharding,
+    /// Updated PromiseYield timeouts queue in the state.
+    /// We either processed expired timeouts or added more yielded promises to the queue.
+    UpdatedPromiseYieldTimeouts,
 }
 
 /// This represents the committed changes in the Trie with a change cause.
@@ -413,6 +416,12 @@ impl StateChanges {
                 TrieKey::PostponedReceipt { .. } => {}
                 TrieKey::DelayedReceiptIndices => {}
                 TrieKey::DelayedReceipt { .. } => {}
+                TrieKey::PromiseYieldIndices => {}
+                TrieKey::PromiseYieldTimeout { .. } => {}
+                TrieKey::PromiseYieldReceipt { .. } => {}
+                // Global contract code is not attached to any account, accounts switching to it
+                // are reported through their `AccountUpdate` with the new `code_hash`.
+                TrieKey::GlobalContractCode { .. } => {}
                 TrieKey::Rsa2048Keys { account_id, public_key } => {
                     state_changes.extend(changes.into_iter().map(
                         |RawStateChange { cause, data }| StateChangeWithCause {
@@ -256,6 +256,17 @@ pub fn create_receipt_id_from_receipt(
     )
 }
 
+/// Creates a new receipt ID for the data receipt which times out a yielded promise waiting for
+/// the given `data_id`.
+pub fn create_receipt_id_from_promise_yield_timeout(
+    protocol_version: ProtocolVersion,
+    data_id: &CryptoHash,
+    prev_block_hash: &CryptoHash,
+    block_hash: &CryptoHash,
+) -> CryptoHash {
+    create_hash_upgradable(protocol_version, data_id, prev_block_hash, block_hash, 0)
+}
+
 /// Creates a new action_hash from a given receipt, a block hash and an action index.
 /// This method is backward compatible, so it takes the current protocol version.
 pub fn create_action_hash(
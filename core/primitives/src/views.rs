@@ -36,7 +36,10 @@ use crate::types::{
     ValidatorKickoutReason,
 };
 
-use crate::action::{CreateRsa2048ChallengeAction, RegisterRsa2048KeysAction};
+use crate::action::{
+    CreateRsa2048ChallengeAction, DeployGlobalContractAction, RegisterRsa2048KeysAction,
+    UseGlobalContractAction,
+};
 use crate::types::validator_power_and_pledge::{
     ValidatorPowerAndPledge, ValidatorPowerAndPledgeIter,
 };
@@ -411,6 +414,8 @@ pub struct PeerInfoView {
     pub last_time_received_message_millis: u64,
     pub connection_established_time_millis: u64,
     pub is_outbound_peer: bool,
+    /// Reputation score of the peer, rounded to an integer.
+    pub reputation_score: i64,
     /// Connection nonce.
     pub nonce: u64,
 }
@@ -492,6 +497,38 @@ pub struct SnapshotHostsView {
     pub hosts: Vec<SnapshotHostInfoView>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct BandwidthLimitView {
+    pub msg_type: String,
+    pub bytes_per_sec: u64,
+    pub burst_bytes: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct MessageTrafficView {
+    pub msg_type: String,
+    pub received_messages: u64,
+    pub received_bytes: u64,
+    pub sent_messages: u64,
+    pub sent_bytes: u64,
+    pub throttled_messages: u64,
+    pub throttled_bytes: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct PeerBandwidthView {
+    pub peer_id: PeerId,
+    pub received_bytes_per_sec: u64,
+    pub sent_bytes_per_sec: u64,
+    pub traffic: Vec<MessageTrafficView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct NetworkBandwidthView {
+    pub limits: Vec<BandwidthLimitView>,
+    pub peers: Vec<PeerBandwidthView>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
 pub struct EdgeView {
     pub peer0: PeerId,
@@ -536,6 +573,12 @@ pub struct NetworkRoutesView {
 pub struct ShardSyncDownloadView {
     pub downloads: Vec<DownloadStatusView>,
     pub status: String,
+    /// Parts downloaded before the node restarted, which didn't have to be downloaded again.
+    #[serde(default)]
+    pub num_parts_resumed: u64,
+    /// Estimated time to download the remaining parts, in seconds.
+    #[serde(default)]
+    pub eta_secs: Option<u64>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
@@ -1332,6 +1375,13 @@ pub enum ActionView {
         #[serde_as(as = "Base64")]
         args: Vec<u8>,
     },
+    DeployGlobalContract {
+        #[serde_as(as = "Base64")]
+        code: Vec<u8>,
+    },
+    UseGlobalContract {
+        code_hash: CryptoHash,
+    },
 }
 
 impl From<Action> for ActionView {
@@ -1374,6 +1424,13 @@ impl From<Action> for ActionView {
                 challenge_key: action.challenge_key,
                 args: action.args.into(),
             },
+            Action::DeployGlobalContract(action) => {
+                let code = hash(&action.code).as_ref().to_vec();
+                ActionView::DeployGlobalContract { code }
+            }
+            Action::UseGlobalContract(action) => {
+                ActionView::UseGlobalContract { code_hash: action.code_hash }
+            }
         }
     }
 }
@@ -1425,6 +1482,12 @@ impl TryFrom<ActionView> for Action {
                     args: args.into(),
                 }))
             }
+            ActionView::DeployGlobalContract { code } => {
+                Action::DeployGlobalContract(DeployGlobalContractAction { code })
+            }
+            ActionView::UseGlobalContract { code_hash } => {
+                Action::UseGlobalContract(Box::new(UseGlobalContractAction { code_hash }))
+            }
         })
     }
 }
@@ -2534,6 +2597,7 @@ pub enum StateChangeCauseView {
     ValidatorAccountsUpdate,
     Migration,
     Resharding,
+    UpdatedPromiseYieldTimeouts,
 }
 
 impl From<StateChangeCause> for StateChangeCauseView {
@@ -2560,6 +2624,7 @@ impl From<StateChangeCause> for StateChangeCauseView {
             StateChangeCause::ValidatorAccountsUpdate => Self::ValidatorAccountsUpdate,
             StateChangeCause::Migration => Self::Migration,
             StateChangeCause::Resharding => Self::Resharding,
+            StateChangeCause::UpdatedPromiseYieldTimeouts => Self::UpdatedPromiseYieldTimeouts,
         }
     }
 }
@@ -2683,6 +2748,55 @@ pub struct SplitStorageInfoView {
     pub hot_db_kind: Option<String>,
 }
 
+/// Breakdown of state bytes attributed to an account at a block. Sizes of
+/// contract data, access key and RSA key records include both keys and values.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountStorageFootprintView {
+    pub account_id: AccountId,
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub code_bytes: u64,
+    pub contract_data_bytes: u64,
+    pub contract_data_records: u64,
+    pub access_key_bytes: u64,
+    pub access_key_records: u64,
+    pub rsa_key_bytes: u64,
+    pub rsa_key_records: u64,
+    pub total_bytes: u64,
+}
+
+/// Progress and recent findings of the background store scrubber.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+pub struct StoreScrubberStatusView {
+    pub columns: Vec<ScrubbedColumnView>,
+    /// Most recent findings, oldest first.
+    pub findings: Vec<StoreScrubberFindingView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ScrubbedColumnView {
+    pub column: String,
+    pub cold: bool,
+    /// Number of completed passes over the column.
+    pub passes: u64,
+    /// Number of entries checked in the current pass.
+    pub keys_checked: u64,
+    /// Hex encoded key the current pass will resume from.
+    pub cursor: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct StoreScrubberFindingView {
+    pub column: String,
+    pub cold: bool,
+    /// Hex encoded key of the entry.
+    pub key: String,
+    pub check: String,
+    pub error: String,
+    pub quarantined: bool,
+    pub timestamp: DateTime<chrono::Utc>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::ExecutionMetadataView;
@@ -1,16 +1,19 @@
 use crate::columns::DBKeyType;
 use crate::db::{ColdDB, COLD_HEAD_KEY, HEAD_KEY};
+use crate::trie::archived_accounts::{archived_trie_nodes, is_archived_trie_key};
 use crate::trie::TrieRefcountAddition;
 use crate::{metrics, DBCol, DBTransaction, Database, Store, TrieChanges};
 
 use borsh::BorshDeserialize;
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::io;
 use strum::IntoEnumIterator;
+use unc_chain_configs::AccountPattern;
 use unc_primitives::block::{Block, BlockHeader, Tip};
 use unc_primitives::hash::CryptoHash;
-use unc_primitives::shard_layout::ShardLayout;
+use unc_primitives::shard_layout::{get_block_shard_uid_rev, ShardLayout, ShardUId};
 use unc_primitives::sharding::ShardChunk;
+use unc_primitives::transaction::ExecutionOutcomeWithProof;
 use unc_primitives::types::BlockHeight;
 
 type StoreKey = Vec<u8>;
@@ -54,11 +57,16 @@ struct BatchTransaction {
 /// 1. add it to `DBCol::is_cold` list
 /// 2. define `DBCol::key_type` for it (if it isn't already defined)
 /// 3. add new clause in `get_keys_from_store` for new key types used for this column (if there are any)
+///
+/// If `archived_accounts` is non-empty, `State`, `StateChanges` and
+/// `TransactionResultForBlock` are only copied for accounts matching it and
+/// `ChunkExtra` only for the shards which may contain them.
 pub fn update_cold_db(
     cold_db: &ColdDB,
     hot_store: &Store,
     shard_layout: &ShardLayout,
     height: &BlockHeight,
+    archived_accounts: &[AccountPattern],
 ) -> io::Result<bool> {
     let _span = tracing::debug_span!(target: "cold_store", "update cold db", height = height);
     let _timer = metrics::COLD_COPY_DURATION.start_timer();
@@ -81,11 +89,26 @@ pub fn update_cold_db(
         }
 
         if col == DBCol::State {
-            copy_state_from_store(shard_layout, block_hash_key, cold_db, &mut store_with_cache)?;
+            copy_state_from_store(
+                shard_layout,
+                block_hash_key,
+                cold_db,
+                &mut store_with_cache,
+                archived_accounts,
+            )?;
             continue;
         }
 
-        let keys = combine_keys(&key_type_to_keys, &col.key_type());
+        let mut keys = combine_keys(&key_type_to_keys, &col.key_type());
+        if !archived_accounts.is_empty() {
+            keys = filter_archived_keys(
+                &mut store_with_cache,
+                shard_layout,
+                col,
+                keys,
+                archived_accounts,
+            )?;
+        }
         copy_from_store(cold_db, &mut store_with_cache, col, keys)?;
     }
 
@@ -117,6 +140,56 @@ fn rc_aware_set(
     };
 }
 
+// Keeps only the keys of data belonging to archived accounts for the columns
+// which partial archive restricts. Keys of other columns are returned as is.
+fn filter_archived_keys(
+    hot_store: &mut StoreWithCache,
+    shard_layout: &ShardLayout,
+    col: DBCol,
+    keys: Vec<StoreKey>,
+    archived_accounts: &[AccountPattern],
+) -> io::Result<Vec<StoreKey>> {
+    match col {
+        DBCol::StateChanges => Ok(keys
+            .into_iter()
+            .filter(|key| {
+                key.len() >= CryptoHash::LENGTH
+                    && is_archived_trie_key(archived_accounts, &key[CryptoHash::LENGTH..])
+            })
+            .collect()),
+        DBCol::TransactionResultForBlock => {
+            let mut archived_keys = Vec::new();
+            for key in keys {
+                let Some(outcome) = hot_store.get_ser::<ExecutionOutcomeWithProof>(col, &key)?
+                else {
+                    continue;
+                };
+                let executor_id = outcome.outcome.executor_id.as_str();
+                if archived_accounts.iter().any(|pattern| pattern.matches(executor_id)) {
+                    archived_keys.push(key);
+                }
+            }
+            Ok(archived_keys)
+        }
+        // Chunk extras are needed to find the state roots of the shards which may
+        // contain archived accounts.
+        DBCol::ChunkExtra => {
+            let archived_shard_uids: HashSet<ShardUId> = archived_accounts
+                .iter()
+                .flat_map(|pattern| pattern.shard_uids(shard_layout))
+                .collect();
+            Ok(keys
+                .into_iter()
+                .filter(|key| {
+                    get_block_shard_uid_rev(key)
+                        .is_ok_and(|(_, shard_uid)| archived_shard_uids.contains(&shard_uid))
+                })
+                .collect())
+        }
+        _ => Ok(keys),
+    }
+}
+
 // A specialized version of copy_from_store for the State column. Finds all the
 // State nodes that were inserted at given height by reading from the
 // TrieChanges and inserts them into the cold store.
@@ -124,11 +197,15 @@ fn rc_aware_set(
 // The generic implementation is not efficient for State because it would
 // attempt to read every node from every shard. Here we know exactly what shard
 // the node belongs to.
+//
+// With non-empty `archived_accounts` only nodes needed to look up state of
+// matching accounts in the new state root are copied.
 fn copy_state_from_store(
     shard_layout: &ShardLayout,
     block_hash_key: &[u8],
     cold_db: &ColdDB,
     hot_store: &mut StoreWithCache,
+    archived_accounts: &[AccountPattern],
 ) -> io::Result<()> {
     let col = DBCol::State;
     let _span = tracing::debug_span!(target: "cold_store", "copy_state_from_store", %col);
@@ -147,7 +224,23 @@ fn copy_state_from_store(
             hot_store.get_ser::<TrieChanges>(DBCol::TrieChanges, &key)?;
 
         let Some(trie_changes) = trie_changes else { continue };
+        let archived_nodes = if archived_accounts.is_empty() {
+            None
+        } else {
+            let nodes: HashMap<CryptoHash, &[u8]> =
+                trie_changes.insertions().iter().map(|op| (*op.hash(), op.payload())).collect();
+            let candidates = nodes.keys().copied().collect();
+            Some(archived_trie_nodes(
+                &trie_changes.new_root,
+                archived_accounts,
+                &candidates,
+                |hash| Ok(nodes[hash].into()),
+            )?)
+        };
         for op in trie_changes.insertions() {
+            if archived_nodes.as_ref().is_some_and(|nodes| !nodes.contains(op.hash())) {
+                continue;
+            }
             hot_store.insert_state_to_cache_from_op(op, &shard_uid_key);
 
             let key = join_two_keys(&shard_uid_key, op.hash().as_bytes());
@@ -270,6 +270,16 @@ pub enum DBCol {
     /// - *Rows*: `KeyForFlatStateDelta { shard_uid, block_hash }`
     /// - *Column type*: `FlatStateDeltaMetadata`
     FlatStateDeltaMetadata,
+    /// Values which flat state keys had before the flat head moved over a block, kept for
+    /// `flat_storage_history_window` blocks to serve queries at blocks behind the flat head.
+    /// - *Rows*: `shard_uid` + block height (big-endian u64)
+    /// - *Column type*: `FlatStateHistoryEntry`
+    FlatStateHistory,
+    /// Breakdown of state bytes attributed to each account at flat head, maintained
+    /// if `account_storage_footprints` is enabled in the store config.
+    /// - *Rows*: `shard_uid` + account id
+    /// - *Column type*: `AccountStorageFootprint`
+    AccountStorageFootprint,
     /// Flat storage status for the corresponding shard.
     /// - *Rows*: `shard_uid`
     /// - *Column type*: `FlatStorageStatus`
@@ -478,6 +488,8 @@ impl DBCol {
             | DBCol::FlatState
             | DBCol::FlatStateChanges
             | DBCol::FlatStateDeltaMetadata
+            | DBCol::FlatStateHistory
+            | DBCol::AccountStorageFootprint
             | DBCol::FlatStorageStatus  => false,
             #[cfg(feature = "new_epoch_sync")]
             DBCol::EpochSyncInfo => false
@@ -548,6 +560,8 @@ impl DBCol {
             DBCol::FlatState => &[DBKeyType::ShardUId, DBKeyType::TrieKey],
             DBCol::FlatStateChanges => &[DBKeyType::ShardUId, DBKeyType::BlockHash],
             DBCol::FlatStateDeltaMetadata => &[DBKeyType::ShardUId, DBKeyType::BlockHash],
+            DBCol::FlatStateHistory => &[DBKeyType::ShardUId, DBKeyType::BlockHeight],
+            DBCol::AccountStorageFootprint => &[DBKeyType::ShardUId, DBKeyType::AccountId],
             DBCol::FlatStorageStatus => &[DBKeyType::ShardUId],
             #[cfg(feature = "new_epoch_sync")]
             DBCol::EpochSyncInfo => &[DBKeyType::EpochId],
@@ -4,6 +4,7 @@ use crate::trie::{
 use crate::DBCol;
 use std::time::Duration;
 use std::{collections::HashMap, iter::FromIterator};
+use strum::IntoEnumIterator;
 use unc_primitives::shard_layout::ShardUId;
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -45,6 +46,17 @@ pub struct StoreConfig {
     /// the performance of the storage
     pub block_size: bytesize::ByteSize,
 
+    /// Per-column overrides of RocksDB tuning options, keyed by column name,
+    /// e.g. `"Chunks"`.  Options which are not overridden keep their defaults.
+    /// Effective options of all columns can be listed with
+    /// `unc-node database column-options`.
+    ///
+    /// Changes apply to files written after restart.  To rewrite existing data
+    /// with new options, e.g. to move large values to blob files, run
+    /// `unc-node database recompact`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub column_options: HashMap<String, ColumnOptionsConfig>,
+
     /// Trie cache configuration per shard for normal (non-view) caches.
     pub trie_cache: TrieCacheConfig,
     /// Trie cache configuration per shard for view caches.
@@ -60,6 +72,37 @@ pub struct StoreConfig {
     /// If true, load mem tries for all shards; this has priority over `load_mem_tries_for_shards`.
     pub load_mem_tries_for_all_shards: bool,
 
+    /// Directory to save in-memory tries to on shutdown, so that on the next
+    /// start they can be mapped back into memory rather than rebuilt from flat
+    /// storage.  If relative, resolved relative to the database path.  `None`
+    /// (the default) disables memtrie snapshots.
+    ///
+    /// A snapshot is used only if it contains the trie at the current flat
+    /// storage head; otherwise the tries are rebuilt as usual.  Deleting the
+    /// directory is always safe.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mem_trie_snapshot_path: Option<std::path::PathBuf>,
+
+    /// Number of blocks behind the flat storage head for which flat storage
+    /// keeps previous values of changed keys, so that `query` requests for
+    /// accounts and contract state at these blocks are served from flat storage
+    /// instead of the trie.  Zero (the default) disables the history.
+    ///
+    /// The window should be shorter than the garbage collection period,
+    /// because values which are not inlined are read from the trie storage.
+    pub flat_storage_history_window: unc_primitives::types::BlockHeight,
+
+    /// Maintain a breakdown of state bytes per account (contract code, contract
+    /// data, access keys and RSA keys) for tracked shards, served by the
+    /// `EXPERIMENTAL_account_storage_footprint` RPC method.  The index is
+    /// updated when flat storage head moves.  When enabled on an existing
+    /// database, the index is built in background by scanning flat state,
+    /// which may take a while; until then footprints are unavailable.
+    pub account_storage_footprints: bool,
+    /// Number of largest accounts per shard exported as
+    /// `unc_account_storage_footprint_bytes` metrics, at most 100.
+    pub account_storage_footprints_top_n: usize,
+
     /// Path where to create RocksDB checkpoints during database migrations or
     /// `false` to disable that feature.
     ///
@@ -108,6 +151,130 @@ pub struct StoreConfig {
 
     // TODO (#9989): To be phased out in favor of state_snapshot_config
     pub state_snapshot_compaction_enabled: bool,
+
+    /// Serve the database from immutable segment files rather than RocksDB.
+    ///
+    /// Only supported for the cold database.  Blocks copied by the cold store
+    /// loop are accumulated in a local journal and sealed into a segment file
+    /// once they span `segment_height_span` heights.  See
+    /// [`crate::db::SegmentDB`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_store: Option<SegmentStoreConfig>,
+
+    /// Storage engine the database is kept in.
+    ///
+    /// The backend of an existing database cannot be changed in place; the
+    /// database has to be created anew, e.g. by syncing from scratch.  For the
+    /// cold database, `segment_store` takes precedence over this setting.
+    #[serde(skip_serializing_if = "StoreBackend::is_default")]
+    pub backend: StoreBackend,
+}
+
+/// Compression algorithm of RocksDB data blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionType {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+/// Overrides of RocksDB tuning options for a single column.  See
+/// [`ColumnOptions`] for the meaning of the fields.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ColumnOptionsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bottommost_compression: Option<CompressionType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bottommost_compression_level: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bloom_filter_bits_per_key: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_size: Option<bytesize::ByteSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_size: Option<bytesize::ByteSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_files: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_blob_size: Option<bytesize::ByteSize>,
+}
+
+/// Effective RocksDB tuning options of a column.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct ColumnOptions {
+    /// Compression of all levels but the bottommost one.  `None` keeps the
+    /// level style compaction default: levels 0 and 1 are not compressed and
+    /// the remaining ones use LZ4.
+    pub compression: Option<CompressionType>,
+    /// Compression of the bottommost level, which holds most of the data.
+    /// Also used for blob files.
+    pub bottommost_compression: CompressionType,
+    /// Level of the bottommost compression.  `None` uses the default level of
+    /// the algorithm.
+    pub bottommost_compression_level: Option<i32>,
+    /// Number of bloom filter bits per key.  Zero disables bloom filters.
+    pub bloom_filter_bits_per_key: f64,
+    /// Size of data blocks.
+    pub block_size: bytesize::ByteSize,
+    /// Size of the block cache.
+    pub cache_size: bytesize::ByteSize,
+    /// Whether values of at least `min_blob_size` bytes are stored in separate
+    /// blob files, which makes compactions of columns with large values, such
+    /// as `Chunks` or `CachedContractCode`, much cheaper.
+    pub blob_files: bool,
+    pub min_blob_size: bytesize::ByteSize,
+}
+
+/// Embedded storage engine backing a database.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    /// RocksDB, see [`crate::db::RocksDB`].
+    #[default]
+    RocksDB,
+    /// A single-file copy-on-write B-tree, see [`crate::db::RedbDB`].
+    Redb,
+}
+
+impl StoreBackend {
+    /// Checks whether the object equals its default value.
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Configuration of the segment file storage.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SegmentStoreConfig {
+    /// Directory sealed segments are stored in.  If relative, resolved
+    /// relative to the database path.  Defaults to `segments`.
+    pub segments_path: Option<std::path::PathBuf>,
+
+    /// Number of block heights covered by a single segment.
+    pub segment_height_span: u64,
+
+    /// Number of bloom filter bits per key.  Ten bits give false positive
+    /// rate of about 1%.
+    pub bloom_bits_per_key: u32,
+
+    /// Number of data blocks cached in memory per segment.
+    pub block_cache_capacity: usize,
+}
+
+impl Default for SegmentStoreConfig {
+    fn default() -> Self {
+        Self {
+            segments_path: None,
+            segment_height_span: 100_000,
+            bloom_bits_per_key: 10,
+            block_cache_capacity: 1024,
+        }
+    }
 }
 
 /// Config used to control state snapshot creation. This is used for state sync and resharding.
@@ -197,6 +364,34 @@ impl StoreConfig {
             _ => bytesize::ByteSize::mib(32),
         }
     }
+
+    /// Returns tuning options of given column, with overrides from
+    /// `column_options` applied.
+    pub fn col_options(&self, col: DBCol) -> ColumnOptions {
+        let config = self.column_options.get(<&str>::from(col)).cloned().unwrap_or_default();
+        ColumnOptions {
+            compression: config.compression,
+            bottommost_compression: config.bottommost_compression.unwrap_or(CompressionType::Zstd),
+            bottommost_compression_level: config.bottommost_compression_level,
+            bloom_filter_bits_per_key: config.bloom_filter_bits_per_key.unwrap_or(10.0),
+            block_size: config.block_size.unwrap_or(self.block_size),
+            cache_size: config.cache_size.unwrap_or_else(|| self.col_cache_size(col)),
+            blob_files: config.blob_files.unwrap_or(false),
+            min_blob_size: config.min_blob_size.unwrap_or(bytesize::ByteSize::kib(4)),
+        }
+    }
+
+    /// Returns keys of `column_options` which don't name any column.
+    pub fn unknown_column_options(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .column_options
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !DBCol::iter().any(|col| <&str>::from(col) == *name))
+            .collect();
+        names.sort();
+        names
+    }
 }
 
 impl Default for StoreConfig {
@@ -232,6 +427,8 @@ impl Default for StoreConfig {
             // we use it since then.
             block_size: bytesize::ByteSize::kib(16),
 
+            column_options: Default::default(),
+
             trie_cache: TrieCacheConfig {
                 default_max_bytes: 500_000_000,
                 // TODO(resharding) The cache size needs to adjusted for every resharding.
@@ -267,6 +464,10 @@ impl Default for StoreConfig {
             // requires more RAM and takes several minutes on startup.
             load_mem_tries_for_shards: Default::default(),
             load_mem_tries_for_all_shards: false,
+            mem_trie_snapshot_path: None,
+            flat_storage_history_window: 0,
+            account_storage_footprints: false,
+            account_storage_footprints_top_n: 20,
 
             migration_snapshot: Default::default(),
 
@@ -289,6 +490,10 @@ impl Default for StoreConfig {
 
             // TODO: To be phased out in favor of state_snapshot_config
             state_snapshot_compaction_enabled: false,
+
+            segment_store: None,
+
+            backend: StoreBackend::default(),
         }
     }
 }
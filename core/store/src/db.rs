@@ -5,6 +5,8 @@ use unc_fmt::{AbbrBytes, StorageKey};
 pub(crate) mod rocksdb;
 
 mod colddb;
+pub(crate) mod redb;
+mod segmentdb;
 mod splitdb;
 
 pub mod refcount;
@@ -14,7 +16,9 @@ mod testdb;
 mod database_tests;
 
 pub use self::colddb::ColdDB;
+pub use self::redb::RedbDB;
 pub use self::rocksdb::RocksDB;
+pub use self::segmentdb::{BlobBackend, LocalDirBackend, SegmentDB};
 pub use self::splitdb::SplitDB;
 
 pub use self::slice::DBSlice;
@@ -38,6 +42,10 @@ pub const STATE_SNAPSHOT_KEY: &[u8; 18] = b"STATE_SNAPSHOT_KEY";
 // `DBCol::Misc` keys
 pub const FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY: &[u8] =
     b"FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS";
+pub const STORE_SCRUBBER_STATUS_KEY: &[u8] = b"STORE_SCRUBBER_STATUS";
+/// Followed by `shard_uid`, stores the flat head account storage footprints
+/// of the shard were last updated to.
+pub const ACCOUNT_STORAGE_FOOTPRINTS_HEAD_KEY: &[u8] = b"ACCOUNT_STORAGE_FOOTPRINTS_HEAD";
 
 #[derive(Default, Debug)]
 pub struct DBTransaction {
@@ -211,6 +219,33 @@ pub trait Database: Sync + Send {
     /// want this method.
     fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a>;
 
+    /// Iterate over items in given column whose keys are between
+    /// [lower_bound, upper_bound) bypassing reference count decoding if any.
+    ///
+    /// This is to [`Self::iter_range`] what [`Self::iter_raw_bytes`] is to
+    /// [`Self::iter`].  The default implementation filters the whole column
+    /// so databases which can seek should override it.
+    fn iter_range_raw_bytes<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        let lower_bound = lower_bound.map(<[u8]>::to_vec);
+        let upper_bound = upper_bound.map(<[u8]>::to_vec);
+        Box::new(
+            self.iter_raw_bytes(col)
+                .skip_while(move |item| match (item, &lower_bound) {
+                    (Ok((key, _)), Some(lower_bound)) => key.as_ref() < lower_bound.as_slice(),
+                    _ => false,
+                })
+                .take_while(move |item| match (item, &upper_bound) {
+                    (Ok((key, _)), Some(upper_bound)) => key.as_ref() < upper_bound.as_slice(),
+                    _ => true,
+                }),
+        )
+    }
+
     /// Atomically apply all operations in given batch at once.
     fn write(&self, batch: DBTransaction) -> io::Result<()>;
 
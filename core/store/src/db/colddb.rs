@@ -71,6 +71,18 @@ impl Database for ColdDB {
         self.cold.iter_raw_bytes(col)
     }
 
+    /// Iterate over items in given column whose keys are between [lower_bound, upper_bound)
+    /// bypassing reference count decoding if any.
+    fn iter_range_raw_bytes<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        Self::log_assert_is_in_colddb(col);
+        self.cold.iter_range_raw_bytes(col, lower_bound, upper_bound)
+    }
+
     /// Iterate over items in given column whose keys are between [lower_bound, upper_bound)
     fn iter_range<'a>(
         &'a self,
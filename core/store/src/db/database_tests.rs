@@ -6,21 +6,35 @@ mod tests {
     use std::sync::Arc;
 
     use crate::{
+        config::StoreBackend,
         db::{DBTransaction, Database, TestDB},
-        DBCol, NodeStorage,
+        DBCol, NodeStorage, StoreConfig,
     };
 
-    // Returns test & rocksDB databases.
-    fn test_and_rocksdb() -> Vec<Arc<dyn Database>> {
-        let (_tmp_dir, opener) = NodeStorage::test_opener();
-        let store = opener.open().unwrap().get_hot_store();
-        vec![TestDB::new(), store.storage.clone()]
+    /// Returns an instance of every database implementation.  On-disk
+    /// databases live in the returned temporary directories which must be kept
+    /// alive for as long as the databases are used.
+    fn all_databases() -> (Vec<tempfile::TempDir>, Vec<Arc<dyn Database>>) {
+        let mut dirs = Vec::new();
+        let mut dbs: Vec<Arc<dyn Database>> = vec![TestDB::new()];
+        for backend in [StoreBackend::RocksDB, StoreBackend::Redb] {
+            let dir = tempfile::tempdir().unwrap();
+            let config = StoreConfig { backend, ..StoreConfig::test_config() };
+            let store = NodeStorage::opener(dir.path(), false, &config, None)
+                .open()
+                .unwrap()
+                .get_hot_store();
+            dirs.push(dir);
+            dbs.push(store.storage.clone());
+        }
+        (dirs, dbs)
     }
 
     /// Tests the behavior of the iterators. Iterators don't really work over cold storage, so we're not testing it here.
     #[test]
     fn test_db_iter() {
-        for db in test_and_rocksdb() {
+        let (_dirs, dbs) = all_databases();
+        for db in dbs {
             let mut transaction = DBTransaction::new();
             transaction.insert(DBCol::Block, "a".into(), "val_a".into());
             transaction.insert(DBCol::Block, "aa".into(), "val_aa".into());
@@ -40,6 +54,80 @@ mod tests {
                 .map(|data| String::from_utf8(data.unwrap().0.to_vec()).unwrap())
                 .collect();
             assert_eq!(keys, vec!["aa", "aa1"]);
+
+            let keys: Vec<_> = db
+                .iter_range_raw_bytes(DBCol::Block, Some("aa1".as_bytes()), None)
+                .map(|data| String::from_utf8(data.unwrap().0.to_vec()).unwrap())
+                .collect();
+            assert_eq!(keys, vec!["aa1", "bb1", "cc1"]);
+
+            let keys: Vec<_> = db
+                .iter_prefix(DBCol::Block, "aa".as_bytes())
+                .map(|data| String::from_utf8(data.unwrap().0.to_vec()).unwrap())
+                .collect();
+            assert_eq!(keys, vec!["aa", "aa1"]);
+        }
+    }
+
+    /// Tests that reference counts of `DBCol::State` entries are merged and
+    /// that entries disappear once their reference count drops to zero.
+    #[test]
+    fn test_db_refcount() {
+        let (_dirs, dbs) = all_databases();
+        let value_with_rc = |rc: i64| [b"value".as_slice(), &rc.to_le_bytes()].concat();
+        let negative_rc = |rc: i64| rc.to_le_bytes().to_vec();
+        let get = |db: &dyn Database| {
+            db.get_with_rc_stripped(DBCol::State, b"key").unwrap().map(|value| value.to_vec())
+        };
+
+        for db in dbs {
+            let mut transaction = DBTransaction::new();
+            transaction.update_refcount(DBCol::State, b"key".to_vec(), value_with_rc(1));
+            transaction.update_refcount(DBCol::State, b"key".to_vec(), value_with_rc(1));
+            db.write(transaction).unwrap();
+            assert_eq!(get(db.as_ref()), Some(b"value".to_vec()));
+            assert_eq!(
+                db.get_raw_bytes(DBCol::State, b"key").unwrap().as_deref(),
+                Some(value_with_rc(2).as_slice())
+            );
+
+            let mut transaction = DBTransaction::new();
+            transaction.update_refcount(DBCol::State, b"key".to_vec(), negative_rc(-1));
+            db.write(transaction).unwrap();
+            assert_eq!(get(db.as_ref()), Some(b"value".to_vec()));
+            assert_eq!(db.iter(DBCol::State).count(), 1);
+
+            let mut transaction = DBTransaction::new();
+            transaction.update_refcount(DBCol::State, b"key".to_vec(), negative_rc(-1));
+            db.write(transaction).unwrap();
+            assert_eq!(get(db.as_ref()), None);
+            assert_eq!(db.iter(DBCol::State).count(), 0);
+        }
+    }
+
+    /// Tests deleting ranges and whole columns.
+    #[test]
+    fn test_db_delete() {
+        let (_dirs, dbs) = all_databases();
+        for db in dbs {
+            let mut transaction = DBTransaction::new();
+            for key in ["a", "b", "c", "d"] {
+                transaction.set(DBCol::BlockMisc, key.into(), "value".into());
+                transaction.set(DBCol::Misc, key.into(), "value".into());
+            }
+            db.write(transaction).unwrap();
+
+            let mut transaction = DBTransaction::new();
+            transaction.delete_range(DBCol::BlockMisc, "b".into(), "d".into());
+            transaction.delete_all(DBCol::Misc);
+            db.write(transaction).unwrap();
+
+            let keys: Vec<_> = db
+                .iter(DBCol::BlockMisc)
+                .map(|data| String::from_utf8(data.unwrap().0.to_vec()).unwrap())
+                .collect();
+            assert_eq!(keys, vec!["a", "d"]);
+            assert_eq!(db.iter(DBCol::Misc).count(), 0);
         }
     }
 }
@@ -0,0 +1,272 @@
+use std::collections::hash_map::{Entry, HashMap};
+use std::io;
+use std::ops::Bound;
+use std::path::Path;
+
+use ::redb::{ReadOnlyTable, ReadableTable, TableDefinition, TableError};
+use strum::IntoEnumIterator;
+
+use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database};
+use crate::{metadata, DBCol, Mode, StoreConfig, StoreStatistics};
+
+type Table = TableDefinition<'static, &'static [u8], &'static [u8]>;
+
+/// A database kept in a single [redb](https://docs.rs/redb) file.
+///
+/// Every [`DBCol`] is a separate table named after the column.  Reference
+/// counted columns are merged on write with the same rules RocksDB’s merge
+/// operator uses, so cells with non-positive reference count may be left in
+/// the database and are skipped by [`Database::iter`] and friends.
+///
+/// Writes are committed without waiting for the data to reach the disk which
+/// matches how RocksDB treats its write-ahead log.  A crash may lose the most
+/// recent transactions but never leaves the database in an inconsistent
+/// state.  [`Database::flush`] makes all committed transactions durable.
+pub struct RedbDB {
+    db: ::redb::Database,
+    mode: Mode,
+}
+
+impl RedbDB {
+    /// Name of the database file inside the database directory.
+    pub(crate) const FILE_NAME: &'static str = "store.redb";
+
+    /// Opens the database in given directory.
+    pub fn open(path: &Path, config: &StoreConfig, mode: Mode) -> io::Result<Self> {
+        let exists = Self::exists(path);
+        if exists && mode.must_create() {
+            let msg = format!("{}: database already exists", path.display());
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, msg));
+        }
+        if !exists && !mode.can_create() {
+            let msg = format!("{}: database does not exist", path.display());
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+        }
+        std::fs::create_dir_all(path)?;
+
+        let cache_size = config.col_state_cache_size + config.col_flat_state_cache_size;
+        let mut builder = ::redb::Database::builder();
+        builder.set_cache_size(cache_size.as_u64() as usize);
+        let file = path.join(Self::FILE_NAME);
+        let db = if exists { builder.open(file) } else { builder.create(file) };
+        let db = db.map_err(io::Error::other)?;
+        let this = Self { db, mode };
+        if mode.read_write() {
+            // Create tables up front so that readers never need to tell
+            // a missing table from an empty one.
+            let transaction = this.db.begin_write().map_err(io::Error::other)?;
+            for col in DBCol::iter() {
+                transaction.open_table(table_definition(col)).map_err(io::Error::other)?;
+            }
+            transaction.commit().map_err(io::Error::other)?;
+        }
+        Ok(this)
+    }
+
+    /// Returns whether a database exists in given directory.
+    pub(crate) fn exists(path: &Path) -> bool {
+        path.join(Self::FILE_NAME).is_file()
+    }
+
+    /// Returns version and kind of the database or `None` if it doesn’t exist.
+    pub(crate) fn get_metadata(
+        path: &Path,
+        config: &StoreConfig,
+    ) -> io::Result<Option<metadata::DbMetadata>> {
+        if !Self::exists(path) {
+            return Ok(None);
+        }
+        let db = Self::open(path, config, Mode::ReadOnly)?;
+        Some(metadata::DbMetadata::read(&db)).transpose()
+    }
+
+    /// Opens the table for given column in a new read transaction.
+    ///
+    /// The table keeps the transaction alive so reads through it observe
+    /// a consistent snapshot.  Returns `None` if the table doesn’t exist which
+    /// may happen for columns added after a read-only database was created.
+    fn read_table(
+        &self,
+        col: DBCol,
+    ) -> io::Result<Option<ReadOnlyTable<&'static [u8], &'static [u8]>>> {
+        let transaction = self.db.begin_read().map_err(io::Error::other)?;
+        match transaction.open_table(table_definition(col)) {
+            Ok(table) => Ok(Some(table)),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    fn iter_raw_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        let lower = lower_bound.map_or(Bound::Unbounded, Bound::Included);
+        let upper = upper_bound.map_or(Bound::Unbounded, Bound::Excluded);
+        let range = match self.read_table(col) {
+            Ok(Some(table)) => table.range::<&[u8]>((lower, upper)).map_err(io::Error::other),
+            Ok(None) => return Box::new(std::iter::empty()),
+            Err(err) => Err(err),
+        };
+        match range {
+            Ok(range) => Box::new(range.map(|item| {
+                let (key, value) = item.map_err(io::Error::other)?;
+                Ok((key.value().into(), value.value().into()))
+            })),
+            Err(err) => Box::new(std::iter::once(Err(err))),
+        }
+    }
+}
+
+fn table_definition(col: DBCol) -> Table {
+    TableDefinition::new(<&str>::from(col))
+}
+
+impl Database for RedbDB {
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        let Some(table) = self.read_table(col)? else {
+            return Ok(None);
+        };
+        let value = table.get(key).map_err(io::Error::other)?;
+        Ok(value.map(|value| DBSlice::from_vec(value.value().to_vec())))
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        refcount::iter_with_rc_logic(col, self.iter_raw_range(col, None, None))
+    }
+
+    fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        let iterator =
+            self.iter_raw_range(col, Some(key_prefix), None).take_while(move |item| match item {
+                Ok((key, _)) => key.starts_with(key_prefix),
+                Err(_) => true,
+            });
+        refcount::iter_with_rc_logic(col, iterator)
+    }
+
+    fn iter_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        refcount::iter_with_rc_logic(col, self.iter_raw_range(col, lower_bound, upper_bound))
+    }
+
+    fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.iter_raw_range(col, None, None)
+    }
+
+    fn iter_range_raw_bytes<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        self.iter_raw_range(col, lower_bound, upper_bound)
+    }
+
+    fn write(&self, batch: DBTransaction) -> io::Result<()> {
+        if self.mode.read_only() {
+            let msg = "cannot write to a database opened in read-only mode";
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, msg));
+        }
+        let mut transaction = self.db.begin_write().map_err(io::Error::other)?;
+        transaction.set_durability(::redb::Durability::Eventual);
+        let mut tables = HashMap::new();
+        for op in batch.ops {
+            let table = match tables.entry(op.col()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    transaction.open_table(table_definition(op.col())).map_err(io::Error::other)?,
+                ),
+            };
+            match op {
+                DBOp::Set { key, value, .. } => {
+                    table.insert(key.as_slice(), value.as_slice()).map_err(io::Error::other)?;
+                }
+                DBOp::Insert { col, key, value } => {
+                    let old_value =
+                        table.insert(key.as_slice(), value.as_slice()).map_err(io::Error::other)?;
+                    if cfg!(debug_assertions) {
+                        if let Some(old_value) = old_value {
+                            super::assert_no_overwrite(col, &key, &value, old_value.value())
+                        }
+                    }
+                }
+                DBOp::UpdateRefcount { key, value, .. } => {
+                    let existing = table.get(key.as_slice()).map_err(io::Error::other)?;
+                    let existing = existing.map(|existing| existing.value().to_vec());
+                    let merged = refcount::refcount_merge(existing.as_deref(), [value.as_slice()]);
+                    if merged.is_empty() {
+                        table.remove(key.as_slice()).map_err(io::Error::other)?;
+                    } else {
+                        table
+                            .insert(key.as_slice(), merged.as_slice())
+                            .map_err(io::Error::other)?;
+                    }
+                }
+                DBOp::Delete { key, .. } => {
+                    table.remove(key.as_slice()).map_err(io::Error::other)?;
+                }
+                DBOp::DeleteAll { .. } => {
+                    table.retain(|_, _| false).map_err(io::Error::other)?;
+                }
+                DBOp::DeleteRange { from, to, .. } => {
+                    table
+                        .retain_in(from.as_slice()..to.as_slice(), |_, _| false)
+                        .map_err(io::Error::other)?;
+                }
+            }
+        }
+        drop(tables);
+        transaction.commit().map_err(io::Error::other)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        if self.mode.read_only() {
+            return Ok(());
+        }
+        // An empty transaction committed with immediate durability persists
+        // all the transactions committed before it.
+        let transaction = self.db.begin_write().map_err(io::Error::other)?;
+        transaction.commit().map_err(io::Error::other)
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        // redb reuses freed pages on its own and compacting the file requires
+        // exclusive access to the database so there’s nothing to do here.
+        Ok(())
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        None
+    }
+
+    /// Copies a consistent snapshot of all columns into a new database in
+    /// given directory.  Unlike RocksDB checkpoints, this copies the data.
+    fn create_checkpoint(&self, path: &Path) -> anyhow::Result<()> {
+        anyhow::ensure!(!path.exists(), "{}: checkpoint path already exists", path.display());
+        std::fs::create_dir_all(path)?;
+        let checkpoint = ::redb::Database::create(path.join(Self::FILE_NAME))?;
+        let source = self.db.begin_read()?;
+        let mut transaction = checkpoint.begin_write()?;
+        transaction.set_durability(::redb::Durability::Immediate);
+        for col in DBCol::iter() {
+            let mut target = transaction.open_table(table_definition(col))?;
+            let source = match source.open_table(table_definition(col)) {
+                Ok(source) => source,
+                Err(TableError::TableDoesNotExist(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            for item in source.iter()? {
+                let (key, value) = item?;
+                target.insert(key.value(), value.value())?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
-use crate::config::Mode;
+use crate::config::{ColumnOptions, CompressionType, Mode};
 use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, StatsValue};
 use crate::{metadata, metrics, DBCol, StoreConfig, StoreStatistics, Temperature};
 use ::rocksdb::{
-    BlockBasedOptions, Cache, ColumnFamily, Env, IteratorMode, Options, ReadOptions, WriteBatch, DB,
+    BlockBasedOptions, BottommostLevelCompaction, Cache, ColumnFamily, CompactOptions, Env,
+    IteratorMode, Options, ReadOptions, WriteBatch, DB,
 };
 use once_cell::sync::Lazy;
 use std::io;
@@ -304,6 +305,17 @@ impl RocksDB {
         self.db.compact_range_cf(self.cf_handle(col)?, none, none);
         Ok(())
     }
+
+    /// Rewrites all data of the column, including the bottommost level, so
+    /// that it's stored according to the current column options.
+    pub fn recompact_column(&self, col: DBCol) -> io::Result<()> {
+        let none = Option::<&[u8]>::None;
+        tracing::info!(target: "db", column = %col, "Recompact column");
+        let mut compact_opts = CompactOptions::default();
+        compact_opts.set_bottommost_level_compaction(BottommostLevelCompaction::Force);
+        self.db.compact_range_cf_opt(self.cf_handle(col)?, none, none, &compact_opts);
+        Ok(())
+    }
 }
 
 impl Database for RocksDB {
@@ -324,6 +336,15 @@ impl Database for RocksDB {
         Box::new(self.iter_raw_bytes_internal(col, None, None, None))
     }
 
+    fn iter_range_raw_bytes<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        Box::new(self.iter_raw_bytes_internal(col, None, lower_bound, upper_bound))
+    }
+
     fn iter(&self, col: DBCol) -> DBIterator {
         refcount::iter_with_rc_logic(col, self.iter_raw_bytes_internal(col, None, None, None))
     }
@@ -428,7 +449,7 @@ impl Database for RocksDB {
 fn rocksdb_options(store_config: &StoreConfig, mode: Mode) -> Options {
     let mut opts = Options::default();
 
-    set_compression_options(&mut opts);
+    set_compression_options(&mut opts, CompressionType::Zstd, None);
     opts.create_missing_column_families(mode.read_write());
     opts.create_if_missing(mode.can_create());
     opts.set_use_fsync(false);
@@ -480,11 +501,11 @@ fn use_block_cache_for_index_and_filter_blocks(db_col: DBCol) -> bool {
     }
 }
 
-fn rocksdb_block_based_options(store_config: &StoreConfig, db_col: DBCol) -> BlockBasedOptions {
-    let cache_size = store_config.col_cache_size(db_col);
+fn rocksdb_block_based_options(col_options: &ColumnOptions, db_col: DBCol) -> BlockBasedOptions {
+    let cache_size = col_options.cache_size;
 
     let mut block_opts = BlockBasedOptions::default();
-    block_opts.set_block_size(store_config.block_size.as_u64().try_into().unwrap());
+    block_opts.set_block_size(col_options.block_size.as_u64().try_into().unwrap());
     // We create block_cache for each of the columns, so the total cache size is (num_of_columns - 2) * 32MiB
     // Plus the 128MiB from FlatState and 512MiB from State columns
     block_opts.set_block_cache(&Cache::new_lru_cache(cache_size.as_u64().try_into().unwrap()));
@@ -494,16 +515,23 @@ fn rocksdb_block_based_options(store_config: &StoreConfig, db_col: DBCol) -> Blo
     } else {
         block_opts.set_cache_index_and_filter_blocks(false);
     }
-    block_opts.set_bloom_filter(10.0, true);
+    if col_options.bloom_filter_bits_per_key > 0.0 {
+        block_opts.set_bloom_filter(col_options.bloom_filter_bits_per_key, true);
+    }
 
     block_opts
 }
 
 fn rocksdb_column_options(col: DBCol, store_config: &StoreConfig, temp: Temperature) -> Options {
+    let col_options = store_config.col_options(col);
     let mut opts = Options::default();
-    set_compression_options(&mut opts);
+    set_compression_options(
+        &mut opts,
+        col_options.bottommost_compression,
+        col_options.bottommost_compression_level,
+    );
     opts.set_level_compaction_dynamic_level_bytes(true);
-    opts.set_block_based_table_factory(&rocksdb_block_based_options(store_config, col));
+    opts.set_block_based_table_factory(&rocksdb_block_based_options(&col_options, col));
 
     // Note that this function changes a lot of rustdb parameters including:
     //      write_buffer_size = memtable_memory_budget / 4
@@ -519,6 +547,20 @@ fn rocksdb_column_options(col: DBCol, store_config: &StoreConfig, temp: Temperat
     //      https://github.com/facebook/rocksdb/blob/c18c4a081c74251798ad2a1abf83bad417518481/options/options.cc#L588.
     let memtable_memory_budget = 128 * bytesize::MIB as usize;
     opts.optimize_level_style_compaction(memtable_memory_budget);
+    if let Some(compression) = col_options.compression {
+        // Without per level compression, `compression_type` applies to all
+        // levels but the bottommost one.
+        opts.set_compression_per_level(&[]);
+        opts.set_compression_type(rocksdb_compression_type(compression));
+    }
+    if col_options.blob_files {
+        opts.set_enable_blob_files(true);
+        opts.set_min_blob_size(col_options.min_blob_size.as_u64());
+        opts.set_blob_compression_type(rocksdb_compression_type(
+            col_options.bottommost_compression,
+        ));
+        opts.set_enable_blob_gc(true);
+    }
 
     opts.set_target_file_size_base(64 * bytesize::MIB);
     if temp == Temperature::Hot && col.is_rc() {
@@ -528,23 +570,40 @@ fn rocksdb_column_options(col: DBCol, store_config: &StoreConfig, temp: Temperat
     opts
 }
 
-fn set_compression_options(opts: &mut Options) {
+fn rocksdb_compression_type(compression: CompressionType) -> rocksdb::DBCompressionType {
+    match compression {
+        CompressionType::None => rocksdb::DBCompressionType::None,
+        CompressionType::Snappy => rocksdb::DBCompressionType::Snappy,
+        CompressionType::Lz4 => rocksdb::DBCompressionType::Lz4,
+        CompressionType::Zstd => rocksdb::DBCompressionType::Zstd,
+    }
+}
+
+fn set_compression_options(
+    opts: &mut Options,
+    bottommost_compression: CompressionType,
+    bottommost_compression_level: Option<i32>,
+) {
     opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
-    opts.set_bottommost_compression_type(rocksdb::DBCompressionType::Zstd);
+    opts.set_bottommost_compression_type(rocksdb_compression_type(bottommost_compression));
     // RocksDB documenation says that 16KB is a typical dictionary size.
     // We've empirically tuned the dicionary size to twice of that 'typical' size.
     // Having train data size x100 from dictionary size is a recommendation from RocksDB.
     // See: https://rocksdb.org/blog/2021/05/31/dictionary-compression.html?utm_source=dbplatz
     let dict_size = 2 * 16384;
     let max_train_bytes = dict_size * 100;
-    // We use default parameters of RocksDB here:
+    // We use default parameters of RocksDB here unless the level is configured:
     //      window_bits is -14 and is unused (Zlib-specific parameter),
     //      compression_level is 32767 meaning the default compression level for ZSTD,
     //      compression_strategy is 0 and is unused (Zlib-specific parameter).
     // See: https://github.com/facebook/rocksdb/blob/main/include/rocksdb/advanced_options.h#L176:
+    let compression_level = bottommost_compression_level.unwrap_or(32767);
     opts.set_bottommost_compression_options(
-        /*window_bits */ -14, /*compression_level */ 32767,
-        /*compression_strategy */ 0, dict_size, /*enabled */ true,
+        /*window_bits */ -14,
+        compression_level,
+        /*compression_strategy */ 0,
+        dict_size,
+        /*enabled */ true,
     );
     opts.set_bottommost_zstd_max_train_bytes(max_train_bytes, true);
 }
@@ -826,4 +885,40 @@ mod tests {
         assert_matches!(store.exists(column, &keys[2]), Ok(false));
         assert_matches!(store.exists(column, &keys[3]), Ok(true));
     }
+
+    #[test]
+    fn test_column_options() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let mut config = StoreConfig::test_config();
+        config.column_options.insert(
+            "Chunks".to_string(),
+            crate::config::ColumnOptionsConfig {
+                compression: Some(CompressionType::Zstd),
+                bloom_filter_bits_per_key: Some(0.0),
+                blob_files: Some(true),
+                min_blob_size: Some(bytesize::ByteSize::b(16)),
+                ..Default::default()
+            },
+        );
+        let options = config.col_options(DBCol::Chunks);
+        assert_eq!(options.compression, Some(CompressionType::Zstd));
+        assert!(options.blob_files);
+        assert_eq!(config.col_options(DBCol::State).cache_size, config.col_state_cache_size);
+
+        let db = RocksDB::open(tmp_dir.path(), &config, Mode::ReadWrite, Temperature::Hot).unwrap();
+        let mut transaction = DBTransaction::new();
+        transaction.set(DBCol::Chunks, vec![1], vec![42; 1024]);
+        transaction.set(DBCol::Chunks, vec![2], vec![42; 8]);
+        db.write(transaction).unwrap();
+        db.flush().unwrap();
+        // single_thread_rocksdb makes compact hang forever
+        if !cfg!(feature = "single_thread_rocksdb") {
+            db.recompact_column(DBCol::Chunks).unwrap();
+        }
+        assert_eq!(
+            db.get_raw_bytes(DBCol::Chunks, &[1]).unwrap().as_deref(),
+            Some(&[42; 1024][..])
+        );
+        assert_eq!(db.get_raw_bytes(DBCol::Chunks, &[2]).unwrap().as_deref(), Some(&[42; 8][..]));
+    }
 }
@@ -0,0 +1,916 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, Write};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use borsh::BorshDeserialize;
+use itertools::Itertools;
+use strum::IntoEnumIterator;
+use unc_primitives::block::Tip;
+use unc_primitives::types::BlockHeight;
+
+use crate::config::SegmentStoreConfig;
+use crate::db::{
+    refcount, DBIterator, DBIteratorItem, DBOp, DBSlice, DBTransaction, Database, COLD_HEAD_KEY,
+};
+use crate::metadata::DbMetadata;
+use crate::{DBCol, Mode, StoreConfig, StoreStatistics};
+
+mod backend;
+mod segment;
+
+pub use self::backend::{BlobBackend, LocalDirBackend};
+use self::segment::{Reader, Segment};
+
+/// A database serving the cold storage from immutable segment files.
+///
+/// Data is partitioned by block height.  Writes go to the open segment which
+/// is an append-only journal in the database directory; only locations of the
+/// values in the journal are kept in memory.  Once the cold head (see
+/// [`crate::cold_storage::update_cold_head`]) moves `segment_height_span`
+/// heights past the start of the open segment, the journal is set aside and
+/// further writes go to a new one.  Contents of the set aside journal are
+/// then sealed into a segment file with a sparse index and a bloom filter,
+/// published to a [`BlobBackend`] and the journal is removed.  Sealed segments
+/// are never modified so they can be served from a local directory or an
+/// object store and shipped between archival nodes as static files.
+///
+/// Reads consult the open segment first, then the segment being sealed, if
+/// any, and then sealed segments from the newest to the oldest.  Values
+/// written at greater heights thus shadow older ones which matters only for
+/// the few mutable keys such as `HEAD` in [`DBCol::BlockMisc`].
+///
+/// Just like [`super::ColdDB`], the database doesn’t track reference counts
+/// (they are always set to one) and rejects any deletions.
+pub struct SegmentDB {
+    /// Directory holding the journals of the segments which aren’t sealed yet.
+    path: PathBuf,
+    mode: Mode,
+    config: SegmentStoreConfig,
+    /// Target size of data blocks in sealed segments.
+    block_size: usize,
+    backend: Arc<dyn BlobBackend>,
+    /// Sealed segments ordered by height, oldest first.
+    segments: RwLock<Arc<Vec<Arc<Segment>>>>,
+    /// Journals of the segments which aren’t sealed yet, oldest first: the
+    /// one being sealed, if any, and the open one which receives writes.
+    journals: RwLock<Vec<Arc<Journal>>>,
+    /// Serialises sealing of the segments.
+    seal_lock: Mutex<()>,
+}
+
+/// Location of a value in a journal file.
+#[derive(Clone, Copy)]
+struct ValueRef {
+    offset: u64,
+    len: u32,
+}
+
+/// Journal of a segment which isn’t sealed yet.
+///
+/// The journal is a sequence of length-prefixed frames each holding records
+/// of a single transaction.
+struct Journal {
+    /// Height the segment starts at.
+    start_height: BlockHeight,
+    index: RwLock<JournalIndex>,
+    /// Handle values are read through; `None` if the journal file doesn’t
+    /// exist.
+    reader: Option<Mutex<std::fs::File>>,
+    /// Handle frames are appended through; `None` if the journal is read-only.
+    writer: Mutex<Option<std::fs::File>>,
+}
+
+#[derive(Default)]
+struct JournalIndex {
+    /// Location of the most recent value of every key.
+    values: enum_map::EnumMap<DBCol, BTreeMap<Vec<u8>, ValueRef>>,
+    /// Height of the cold head most recently written to the journal.
+    head_height: Option<BlockHeight>,
+    /// Length of the prefix of the journal file holding complete frames.
+    len: u64,
+}
+
+impl JournalIndex {
+    fn insert(&mut self, col: DBCol, key: Vec<u8>, value: &[u8], offset: u64) -> io::Result<()> {
+        if let Some(height) = cold_head_height(col, &key, value)? {
+            self.head_height = Some(height);
+        }
+        self.values[col].insert(key, ValueRef { offset, len: value.len() as u32 });
+        Ok(())
+    }
+
+    /// Indexes the journal reading it one frame at a time.
+    ///
+    /// Anything past the last complete frame is a torn write which is ignored.
+    fn replay(&mut self, file: &std::fs::File) -> io::Result<()> {
+        let file_len = file.metadata()?.len();
+        let mut reader = io::BufReader::new(file);
+        let mut frame = Vec::new();
+        loop {
+            let mut len = [0; 4];
+            if self.len + 4 > file_len {
+                break;
+            }
+            reader.read_exact(&mut len)?;
+            let frame_len = u32::from_le_bytes(len) as usize;
+            let frame_offset = self.len + 4;
+            if frame_offset + frame_len as u64 > file_len {
+                break;
+            }
+            frame.resize(frame_len, 0);
+            reader.read_exact(&mut frame)?;
+            let mut records = Reader::new(&frame);
+            while !records.is_empty() {
+                let name =
+                    std::str::from_utf8(records.bytes16()?).map_err(segment::invalid_data)?;
+                let col =
+                    DBCol::iter().find(|col| <&str>::from(*col) == name).ok_or_else(|| {
+                        segment::invalid_data(format!("journal: unknown column {name}"))
+                    })?;
+                let key = records.bytes32()?.to_vec();
+                let value = records.bytes32()?;
+                let value_offset = frame_len - records.remaining() - value.len();
+                self.insert(col, key, value, frame_offset + value_offset as u64)?;
+            }
+            self.len = frame_offset + frame_len as u64;
+        }
+        Ok(())
+    }
+}
+
+impl Journal {
+    /// Opens the journal at given path.  A missing journal is created if
+    /// `writable` or treated as empty otherwise.
+    fn open(path: &Path, start_height: BlockHeight, writable: bool) -> io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(writable)
+            .create(writable)
+            .truncate(false)
+            .open(path);
+        let file = match file {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !writable => {
+                return Ok(Self {
+                    start_height,
+                    index: Default::default(),
+                    reader: None,
+                    writer: Mutex::new(None),
+                });
+            }
+            Err(err) => return Err(err),
+        };
+        let mut index = JournalIndex::default();
+        index.replay(&file)?;
+        let writer = if writable {
+            // Drop torn writes from the end of the journal.
+            file.set_len(index.len)?;
+            Some(std::fs::OpenOptions::new().append(true).open(path)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            start_height,
+            index: RwLock::new(index),
+            reader: Some(Mutex::new(file)),
+            writer: Mutex::new(writer),
+        })
+    }
+
+    fn head_height(&self) -> Option<BlockHeight> {
+        self.index.read().unwrap().head_height
+    }
+
+    /// Forgets contents of the journal.
+    fn clear(&self) -> io::Result<()> {
+        if let Some(writer) = &*self.writer.lock().unwrap() {
+            writer.set_len(0)?;
+        }
+        *self.index.write().unwrap() = JournalIndex::default();
+        Ok(())
+    }
+
+    /// Appends a frame with given records to the journal.
+    fn append(&self, records: Vec<(DBCol, Vec<u8>, Vec<u8>)>) -> io::Result<()> {
+        let mut frame = Vec::new();
+        let mut value_offsets = Vec::with_capacity(records.len());
+        for (col, key, value) in records.iter() {
+            encode_record(&mut frame, *col, key, value);
+            value_offsets.push(frame.len() - value.len());
+        }
+        let mut entry = Vec::with_capacity(frame.len() + 4);
+        segment::put_bytes32(&mut entry, &frame);
+
+        let mut writer = self.writer.lock().unwrap();
+        let writer =
+            writer.as_mut().ok_or_else(|| io::Error::other("segment journal is read-only"))?;
+        let mut index = self.index.write().unwrap();
+        if let Err(err) = writer.write_all(&entry) {
+            // Don’t leave a partial frame in front of the following ones.
+            let _ = writer.set_len(index.len);
+            return Err(err);
+        }
+        let frame_offset = index.len + 4;
+        index.len += entry.len() as u64;
+        for ((col, key, value), value_offset) in records.into_iter().zip(value_offsets) {
+            index.insert(col, key, &value, frame_offset + value_offset as u64)?;
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        if let Some(writer) = &*self.writer.lock().unwrap() {
+            writer.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn read_value(&self, value: ValueRef) -> io::Result<Vec<u8>> {
+        let reader = self.reader.as_ref().expect("journal with values has a file");
+        let mut reader = reader.lock().unwrap();
+        reader.seek(io::SeekFrom::Start(value.offset))?;
+        let mut buf = vec![0; value.len as usize];
+        reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn get(&self, col: DBCol, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        let value = self.index.read().unwrap().values[col].get(key).copied();
+        value.map(|value| self.read_value(value)).transpose()
+    }
+
+    /// Copies complete frames of the journal to a new file at given path.
+    fn copy_to(&self, path: &Path) -> io::Result<()> {
+        let len = self.index.read().unwrap().len;
+        let mut out = std::fs::File::create(path)?;
+        if let Some(reader) = &self.reader {
+            let mut reader = reader.lock().unwrap();
+            reader.seek(io::SeekFrom::Start(0))?;
+            io::copy(&mut (&mut *reader).take(len), &mut out)?;
+        }
+        out.sync_all()
+    }
+}
+
+/// Iterator over a key range of a column in a journal.
+///
+/// Rather than holding the lock of the journal index or copying the range,
+/// every step looks up the key following the one returned previously.
+struct JournalIter {
+    journal: Arc<Journal>,
+    col: DBCol,
+    lower_bound: Bound<Vec<u8>>,
+    upper_bound: Option<Vec<u8>>,
+}
+
+impl Iterator for JournalIter {
+    type Item = DBIteratorItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = {
+            let index = self.journal.index.read().unwrap();
+            let lower = self.lower_bound.as_ref().map(Vec::as_slice);
+            let upper = self.upper_bound.as_deref().map_or(Bound::Unbounded, Bound::Excluded);
+            let (key, value) = index.values[self.col].range::<[u8], _>((lower, upper)).next()?;
+            (key.clone(), *value)
+        };
+        self.lower_bound = Bound::Excluded(key.clone());
+        Some(self.journal.read_value(value).map(|value| (key.into(), value.into())))
+    }
+}
+
+impl SegmentDB {
+    const JOURNAL_FILE: &'static str = "journal";
+    /// Journal of the segment being sealed.
+    const SEALING_JOURNAL_FILE: &'static str = "journal.sealing";
+    /// Segment file being encoded before it is published to the backend.
+    const SEGMENT_TMP_FILE: &'static str = "segment.tmp";
+    const SEGMENTS_DIR: &'static str = "segments";
+
+    /// Opens the database keeping sealed segments in a local directory.
+    ///
+    /// The directory is `config.segment_store.segments_path` resolved relative
+    /// to `path`.
+    pub fn open(path: &Path, config: &StoreConfig, mode: Mode) -> io::Result<Self> {
+        Self::open_with_backend(path, config, mode, Self::local_backend(path, config))
+    }
+
+    /// Opens the database keeping sealed segments in given backend.
+    ///
+    /// `path` is a local directory holding the journals of the segments which
+    /// aren’t sealed yet.
+    pub fn open_with_backend(
+        path: &Path,
+        config: &StoreConfig,
+        mode: Mode,
+        backend: Arc<dyn BlobBackend>,
+    ) -> io::Result<Self> {
+        let segment_config = config.segment_store.clone().unwrap_or_default();
+        let exists = Self::exists(path, backend.as_ref())?;
+        if exists && mode.must_create() {
+            let msg = format!("{}: database already exists", path.display());
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, msg));
+        }
+        if !exists && !mode.can_create() {
+            let msg = format!("{}: database does not exist", path.display());
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+        }
+
+        let mut names = backend
+            .list()?
+            .into_iter()
+            .filter_map(|name| Some((segment::parse_segment_name(&name)?, name)))
+            .collect::<Vec<_>>();
+        names.sort();
+        let mut segments: Vec<Arc<Segment>> = Vec::with_capacity(names.len());
+        for ((start_height, end_height), name) in names {
+            let segment =
+                Segment::open(backend.clone(), &name, segment_config.block_cache_capacity)?;
+            let want_start = segments.last().map_or(start_height, |prev| prev.end_height() + 1);
+            if (segment.start_height(), segment.end_height()) != (start_height, end_height)
+                || start_height != want_start
+            {
+                let msg = format!("{name}: expected segment starting at height {want_start}");
+                return Err(segment::invalid_data(msg));
+            }
+            segments.push(Arc::new(segment));
+        }
+
+        // If the node crashed after publishing a segment but before removing
+        // its journal, the journal holds data which is already sealed.
+        let last_end = segments.last().map(|segment| segment.end_height());
+        let is_stale = |journal: &Journal| match (journal.head_height(), last_end) {
+            (Some(head), Some(end)) => head <= end,
+            _ => false,
+        };
+        let mut journals = Vec::new();
+        let next_start = last_end.map_or(0, |end| end + 1);
+        let sealing_path = path.join(Self::SEALING_JOURNAL_FILE);
+        if sealing_path.is_file() {
+            let sealing = Journal::open(&sealing_path, next_start, false)?;
+            if !is_stale(&sealing) {
+                journals.push(Arc::new(sealing));
+            } else if mode.read_write() {
+                std::fs::remove_file(&sealing_path)?;
+            }
+        }
+        let open_start = journals.last().and_then(|journal| journal.head_height());
+        let open_start = open_start.map_or(next_start, |head| head + 1);
+        if mode.read_write() {
+            std::fs::create_dir_all(path)?;
+        }
+        let open = Journal::open(&path.join(Self::JOURNAL_FILE), open_start, mode.read_write())?;
+        if is_stale(&open) {
+            open.clear()?;
+        }
+        journals.push(Arc::new(open));
+
+        let db = Self {
+            path: path.to_path_buf(),
+            mode,
+            config: segment_config,
+            block_size: config.block_size.as_u64() as usize,
+            backend,
+            segments: RwLock::new(Arc::new(segments)),
+            journals: RwLock::new(journals),
+            seal_lock: Mutex::new(()),
+        };
+        // Finish sealing interrupted by a crash.
+        if mode.read_write() {
+            let journals = db.journals.read().unwrap().clone();
+            if let [sealing, _] = journals.as_slice() {
+                db.publish(sealing)?;
+            }
+        }
+        Ok(db)
+    }
+
+    /// Returns version and kind of the database or `None` if it doesn’t exist.
+    pub(crate) fn get_metadata(
+        path: &Path,
+        config: &StoreConfig,
+    ) -> io::Result<Option<DbMetadata>> {
+        let backend = Self::local_backend(path, config);
+        if !Self::exists(path, backend.as_ref())? {
+            return Ok(None);
+        }
+        let db = Self::open_with_backend(path, config, Mode::ReadOnly, backend)?;
+        Some(DbMetadata::read(&db)).transpose()
+    }
+
+    /// Returns heights covered by sealed segments as `(start, end)` inclusive
+    /// ranges, oldest first.
+    pub fn sealed_ranges(&self) -> Vec<(BlockHeight, BlockHeight)> {
+        let segments = self.segments.read().unwrap();
+        segments.iter().map(|segment| (segment.start_height(), segment.end_height())).collect()
+    }
+
+    fn local_backend(path: &Path, config: &StoreConfig) -> Arc<dyn BlobBackend> {
+        let segments_path = config
+            .segment_store
+            .as_ref()
+            .and_then(|config| config.segments_path.as_deref())
+            .unwrap_or(Path::new(Self::SEGMENTS_DIR));
+        Arc::new(LocalDirBackend::new(&path.join(segments_path)))
+    }
+
+    fn exists(path: &Path, backend: &dyn BlobBackend) -> io::Result<bool> {
+        if path.join(Self::JOURNAL_FILE).is_file()
+            || path.join(Self::SEALING_JOURNAL_FILE).is_file()
+        {
+            return Ok(true);
+        }
+        Ok(backend.list()?.iter().any(|name| segment::parse_segment_name(name).is_some()))
+    }
+
+    /// Seals the segment of given journal if it is still the open one.
+    ///
+    /// The journal is set aside and further writes go to a new one, so that
+    /// neither reads nor writes wait for the segment to be encoded and
+    /// published.
+    fn seal(&self, journal: &Arc<Journal>, end_height: BlockHeight) -> io::Result<()> {
+        let _seal_guard = self.seal_lock.lock().unwrap();
+        // Publish a segment whose sealing failed previously before its
+        // journal is replaced by the one being set aside.
+        let pending = self.journals.read().unwrap().clone();
+        if let [sealing, open] = pending.as_slice() {
+            if Arc::ptr_eq(open, journal) {
+                self.publish(sealing)?;
+            }
+        }
+        {
+            let mut journals = self.journals.write().unwrap();
+            if !journals.last().is_some_and(|open| Arc::ptr_eq(open, journal)) {
+                return Ok(());
+            }
+            journal.sync()?;
+            std::fs::rename(
+                self.path.join(Self::JOURNAL_FILE),
+                self.path.join(Self::SEALING_JOURNAL_FILE),
+            )?;
+            journal.writer.lock().unwrap().take();
+            let open = Journal::open(&self.path.join(Self::JOURNAL_FILE), end_height + 1, true)?;
+            journals.push(Arc::new(open));
+        }
+        self.publish(journal)
+    }
+
+    /// Encodes contents of the journal set aside for sealing into a segment,
+    /// publishes it to the backend and removes the journal.
+    fn publish(&self, journal: &Arc<Journal>) -> io::Result<()> {
+        let start_height = journal.start_height;
+        let end_height = journal
+            .head_height()
+            .ok_or_else(|| segment::invalid_data("journal being sealed has no cold head"))?;
+        let _span =
+            tracing::info_span!(target: "cold_store", "seal_segment", start_height, end_height)
+                .entered();
+        let name = segment::segment_name(start_height, end_height);
+        let tmp_path = self.path.join(Self::SEGMENT_TMP_FILE);
+        let size = {
+            // The journal is no longer written to, so holding its index
+            // doesn’t block anyone.
+            let index = journal.index.read().unwrap();
+            let num_entries = index.values.values().map(BTreeMap::len).sum();
+            let entries = DBCol::iter()
+                .flat_map(|col| index.values[col].iter().map(move |entry| (col, entry)))
+                .map(|(col, (key, value))| -> io::Result<_> {
+                    Ok((col, key.as_slice(), journal.read_value(*value)?))
+                });
+            let mut out = io::BufWriter::new(std::fs::File::create(&tmp_path)?);
+            let size = segment::encode(
+                &mut out,
+                start_height,
+                end_height,
+                num_entries,
+                entries,
+                self.block_size,
+                self.config.bloom_bits_per_key,
+            )?;
+            out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+            size
+        };
+        self.backend.put(&name, &mut std::fs::File::open(&tmp_path)?)?;
+        std::fs::remove_file(&tmp_path)?;
+        let segment = Segment::open(self.backend.clone(), &name, self.config.block_cache_capacity)?;
+        tracing::info!(target: "cold_store", %name, entries = segment.num_entries(), size, "Sealed segment");
+        {
+            let mut segments = self.segments.write().unwrap();
+            let mut updated = (**segments).clone();
+            updated.push(Arc::new(segment));
+            *segments = Arc::new(updated);
+        }
+        // The segment is visible to readers before the journal goes away.
+        self.journals.write().unwrap().retain(|other| !Arc::ptr_eq(other, journal));
+        std::fs::remove_file(self.path.join(Self::SEALING_JOURNAL_FILE))
+    }
+
+    /// Iterates over a key range of a column merging the journals with all
+    /// the sealed segments.
+    fn iter_merged<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        if let (Some(lower), Some(upper)) = (lower_bound, upper_bound) {
+            if lower > upper {
+                return Box::new(std::iter::empty());
+            }
+        }
+        // Journals are taken before segments, so that a segment published in
+        // between is seen at least once.
+        let journals = self.journals.read().unwrap().clone();
+        let segments = self.segments.read().unwrap().clone();
+
+        // Sources are ranked from the newest to the oldest so that for equal
+        // keys the newest value comes first in the merged iterator.
+        let mut sources: Vec<DBIterator<'a>> = Vec::new();
+        for journal in journals.into_iter().rev() {
+            sources.push(Box::new(JournalIter {
+                journal,
+                col,
+                lower_bound: lower_bound
+                    .map_or(Bound::Unbounded, |lower| Bound::Included(lower.to_vec())),
+                upper_bound: upper_bound.map(<[u8]>::to_vec),
+            }));
+        }
+        for segment in segments.iter().rev() {
+            sources.push(Box::new(segment.iter_range(col, lower_bound, upper_bound)));
+        }
+        let sources =
+            sources.into_iter().enumerate().map(|(rank, iter)| iter.map(move |item| (rank, item)));
+        let merged = itertools::kmerge_by(
+            sources,
+            |(a_rank, a): &(usize, DBIteratorItem), (b_rank, b): &(usize, DBIteratorItem)| {
+                match (a, b) {
+                    // Always put errors first.
+                    (Err(_), _) => true,
+                    (_, Err(_)) => false,
+                    (Ok((a_key, _)), Ok((b_key, _))) => (a_key, a_rank) < (b_key, b_rank),
+                }
+            },
+        );
+        let iter = merged.map(|(_, item)| item).coalesce(|prev, next| match (&prev, &next) {
+            (Ok((prev_key, _)), Ok((next_key, _))) if prev_key == next_key => Ok(prev),
+            _ => Err((prev, next)),
+        });
+        Box::new(iter)
+    }
+}
+
+impl Database for SegmentDB {
+    /// Returns raw bytes for given `key` ignoring any reference count decoding
+    /// if any.
+    ///
+    /// Reads from the journals first and then from sealed segments, newest
+    /// first.  Bloom filters let most lookups skip segments without fetching
+    /// any data blocks from the backend.
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        let journals = self.journals.read().unwrap().clone();
+        for journal in journals.iter().rev() {
+            if let Some(value) = journal.get(col, key)? {
+                return Ok(Some(DBSlice::from_vec(value)));
+            }
+        }
+        let segments = self.segments.read().unwrap().clone();
+        for segment in segments.iter().rev() {
+            if let Some(value) = segment.get(col, key)? {
+                return Ok(Some(DBSlice::from_vec(value)));
+            }
+        }
+        Ok(None)
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        refcount::iter_with_rc_logic(col, self.iter_merged(col, None, None))
+    }
+
+    fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        let iter = self.iter_merged(col, Some(key_prefix), None).take_while(move |item| {
+            item.as_ref().map_or(true, |(key, _)| key.starts_with(key_prefix))
+        });
+        refcount::iter_with_rc_logic(col, iter)
+    }
+
+    fn iter_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        refcount::iter_with_rc_logic(col, self.iter_merged(col, lower_bound, upper_bound))
+    }
+
+    fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.iter_merged(col, None, None)
+    }
+
+    /// Appends the transaction to the open segment.
+    ///
+    /// Fails if the transaction contains any deletions or decreases reference
+    /// count of a value.  If the transaction moves the cold head far enough,
+    /// the open segment is sealed.
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        if self.mode.read_only() {
+            let msg = format!("{}: segment database opened in read-only mode", self.path.display());
+            return Err(io::Error::other(msg));
+        }
+
+        let mut records = Vec::with_capacity(transaction.ops.len());
+        for op in transaction.ops {
+            let (col, key, value) = match op {
+                DBOp::Set { col, key, value } | DBOp::Insert { col, key, value } => {
+                    (col, key, value)
+                }
+                DBOp::UpdateRefcount { col, key, mut value } => {
+                    if refcount::decode_value_with_rc(&value).1 <= 0 {
+                        let msg = format!("cannot decrease refcount in {col} of segment database");
+                        return Err(io::Error::other(msg));
+                    }
+                    refcount::set_refcount(&mut value, 1)?;
+                    (col, key, value)
+                }
+                DBOp::Delete { col, .. }
+                | DBOp::DeleteAll { col }
+                | DBOp::DeleteRange { col, .. } => {
+                    let msg = format!("cannot delete from {col} of segment database");
+                    return Err(io::Error::other(msg));
+                }
+            };
+            // Validate the value before it hits the journal.
+            cold_head_height(col, &key, &value)?;
+            records.push((col, key, value));
+        }
+
+        let open = {
+            // Holding the lock makes sure the journal isn’t set aside for
+            // sealing in the middle of the write.
+            let journals = self.journals.read().unwrap();
+            let open = journals.last().expect("open journal is always present").clone();
+            open.append(records)?;
+            open
+        };
+        if let Some(end_height) = open.head_height() {
+            if end_height >= open.start_height
+                && end_height - open.start_height + 1 >= self.config.segment_height_span
+            {
+                self.seal(&open, end_height)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let journals = self.journals.read().unwrap().clone();
+        journals.last().map_or(Ok(()), |open| open.sync())
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        None
+    }
+
+    /// Copies the journals and sealed segments to given directory laid out
+    /// such that it can be opened with the default segment store config.
+    ///
+    /// Journals are captured before segments so that a segment published in
+    /// between ends up in the checkpoint twice rather than not at all.  Its
+    /// journal is then recognised as stale when the checkpoint is opened.
+    fn create_checkpoint(&self, path: &Path) -> anyhow::Result<()> {
+        anyhow::ensure!(!path.exists(), "{}: checkpoint path already exists", path.display());
+        let journals = self.journals.read().unwrap().clone();
+        let segments = self.segments.read().unwrap().clone();
+        let segments_path = path.join(Self::SEGMENTS_DIR);
+        std::fs::create_dir_all(&segments_path)?;
+        for segment in segments.iter() {
+            segment.copy_to(&segments_path.join(segment.name()))?;
+        }
+        let names = [Self::JOURNAL_FILE, Self::SEALING_JOURNAL_FILE];
+        for (journal, name) in journals.iter().rev().zip(names) {
+            journal.copy_to(&path.join(name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns height of the cold head if given entry sets it.
+fn cold_head_height(col: DBCol, key: &[u8], value: &[u8]) -> io::Result<Option<BlockHeight>> {
+    if col != DBCol::BlockMisc || key != COLD_HEAD_KEY {
+        return Ok(None);
+    }
+    Ok(Some(Tip::try_from_slice(value)?.height))
+}
+
+/// Appends a journal record for given entry.
+fn encode_record(out: &mut Vec<u8>, col: DBCol, key: &[u8], value: &[u8]) {
+    let name = <&str>::from(col);
+    out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    out.extend_from_slice(name.as_bytes());
+    segment::put_bytes32(out, key);
+    segment::put_bytes32(out, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unc_primitives::hash::CryptoHash;
+    use unc_primitives::types::EpochId;
+
+    const SPAN: u64 = 10;
+
+    fn test_config() -> StoreConfig {
+        let segment_store = SegmentStoreConfig {
+            segment_height_span: SPAN,
+            block_cache_capacity: 4,
+            ..SegmentStoreConfig::default()
+        };
+        // Small blocks so that segments hold more than one block per column.
+        StoreConfig {
+            block_size: bytesize::ByteSize::b(64),
+            segment_store: Some(segment_store),
+            ..StoreConfig::default()
+        }
+    }
+
+    fn block_key(height: BlockHeight) -> Vec<u8> {
+        format!("block-{height:04}").into_bytes()
+    }
+
+    /// Writes data of a single height the way cold store loop does: first the
+    /// block and then the cold head.
+    fn write_height(db: &SegmentDB, height: BlockHeight) {
+        let mut transaction = DBTransaction::new();
+        transaction.set(DBCol::Block, block_key(height), height.to_le_bytes().to_vec());
+        transaction.set(DBCol::BlockMisc, b"HEAD".to_vec(), height.to_le_bytes().to_vec());
+        db.write(transaction).unwrap();
+
+        let tip = Tip {
+            height,
+            last_block_hash: CryptoHash::default(),
+            prev_block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+        };
+        let mut transaction = DBTransaction::new();
+        transaction.set(DBCol::BlockMisc, COLD_HEAD_KEY.to_vec(), borsh::to_vec(&tip).unwrap());
+        db.write(transaction).unwrap();
+    }
+
+    fn get(db: &SegmentDB, col: DBCol, key: &[u8]) -> Option<Vec<u8>> {
+        db.get_raw_bytes(col, key).unwrap().map(|value| value.to_vec())
+    }
+
+    fn keys(iter: DBIterator) -> Vec<String> {
+        iter.map(|item| String::from_utf8(item.unwrap().0.into_vec()).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_seal_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::Create).unwrap();
+        for height in 0..25 {
+            write_height(&db, height);
+        }
+        assert_eq!(db.sealed_ranges(), vec![(0, 9), (10, 19)]);
+
+        for height in 0..25 {
+            let value = get(&db, DBCol::Block, &block_key(height));
+            assert_eq!(value, Some(height.to_le_bytes().to_vec()), "height {height}");
+        }
+        assert_eq!(get(&db, DBCol::Block, b"block-0100"), None);
+        assert_eq!(get(&db, DBCol::Block, b"a"), None);
+        assert_eq!(get(&db, DBCol::Chunks, &block_key(3)), None);
+        // Mutable keys are shadowed by the most recent write.
+        assert_eq!(get(&db, DBCol::BlockMisc, b"HEAD"), Some(24u64.to_le_bytes().to_vec()));
+
+        let all = keys(db.iter(DBCol::Block));
+        assert_eq!(all, (0..25).map(|height| format!("block-{height:04}")).collect::<Vec<_>>());
+        assert_eq!(keys(db.iter(DBCol::BlockMisc)), vec!["COLD_HEAD", "HEAD"]);
+
+        let range = keys(db.iter_range(DBCol::Block, Some(b"block-0008"), Some(b"block-0021")));
+        assert_eq!(range, (8..21).map(|height| format!("block-{height:04}")).collect::<Vec<_>>());
+        let prefix = keys(db.iter_prefix(DBCol::Block, b"block-001"));
+        assert_eq!(prefix, (10..20).map(|height| format!("block-{height:04}")).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = SegmentDB::open(dir.path(), &test_config(), Mode::Create).unwrap();
+            for height in 0..15 {
+                write_height(&db, height);
+            }
+        }
+        // Simulate a torn write at the end of the journal.
+        {
+            let mut journal = std::fs::OpenOptions::new()
+                .append(true)
+                .open(dir.path().join(SegmentDB::JOURNAL_FILE))
+                .unwrap();
+            journal.write_all(&[42, 0, 0, 0, 1, 2]).unwrap();
+        }
+
+        assert!(SegmentDB::open(dir.path(), &test_config(), Mode::Create).is_err());
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::ReadWriteExisting).unwrap();
+        assert_eq!(db.sealed_ranges(), vec![(0, 9)]);
+        assert_eq!(keys(db.iter(DBCol::Block)).len(), 15);
+        for height in 15..20 {
+            write_height(&db, height);
+        }
+        assert_eq!(db.sealed_ranges(), vec![(0, 9), (10, 19)]);
+        drop(db);
+
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::ReadOnly).unwrap();
+        assert_eq!(keys(db.iter(DBCol::Block)).len(), 20);
+        assert_eq!(get(&db, DBCol::Block, &block_key(12)), Some(12u64.to_le_bytes().to_vec()));
+        assert!(db.write(DBTransaction::new()).is_err());
+
+        let missing = dir.path().join("missing");
+        assert!(SegmentDB::open(&missing, &test_config(), Mode::ReadOnly).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::Create).unwrap();
+        for height in 0..15 {
+            write_height(&db, height);
+        }
+        let checkpoint = dir.path().join("checkpoint");
+        db.create_checkpoint(&checkpoint).unwrap();
+        assert!(db.create_checkpoint(&checkpoint).is_err());
+        // Writes following the checkpoint don’t affect it.
+        for height in 15..20 {
+            write_height(&db, height);
+        }
+        drop(db);
+
+        let db = SegmentDB::open(&checkpoint, &test_config(), Mode::ReadOnly).unwrap();
+        assert_eq!(db.sealed_ranges(), vec![(0, 9)]);
+        assert_eq!(keys(db.iter(DBCol::Block)).len(), 15);
+        assert_eq!(get(&db, DBCol::Block, &block_key(12)), Some(12u64.to_le_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_interrupted_seal() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = SegmentDB::open(dir.path(), &test_config(), Mode::Create).unwrap();
+            for height in 0..15 {
+                write_height(&db, height);
+            }
+        }
+        // Simulate a crash after the journal was set aside for sealing but
+        // before the segment was published.
+        std::fs::rename(
+            dir.path().join(SegmentDB::JOURNAL_FILE),
+            dir.path().join(SegmentDB::SEALING_JOURNAL_FILE),
+        )
+        .unwrap();
+
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::ReadOnly).unwrap();
+        assert_eq!(db.sealed_ranges(), vec![(0, 9)]);
+        assert_eq!(keys(db.iter(DBCol::Block)).len(), 15);
+        assert_eq!(get(&db, DBCol::Block, &block_key(14)), Some(14u64.to_le_bytes().to_vec()));
+        drop(db);
+
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::ReadWriteExisting).unwrap();
+        assert_eq!(db.sealed_ranges(), vec![(0, 9), (10, 14)]);
+        assert!(!dir.path().join(SegmentDB::SEALING_JOURNAL_FILE).exists());
+        for height in 15..25 {
+            write_height(&db, height);
+        }
+        assert_eq!(db.sealed_ranges(), vec![(0, 9), (10, 14), (15, 24)]);
+        assert_eq!(keys(db.iter(DBCol::Block)).len(), 25);
+        assert_eq!(get(&db, DBCol::BlockMisc, b"HEAD"), Some(24u64.to_le_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_refcount_and_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = SegmentDB::open(dir.path(), &test_config(), Mode::Create).unwrap();
+
+        let mut transaction = DBTransaction::new();
+        let value = [b"value".as_slice(), &2i64.to_le_bytes()].concat();
+        transaction.update_refcount(DBCol::State, b"key".to_vec(), value);
+        db.write(transaction).unwrap();
+        let want = [b"value".as_slice(), &1i64.to_le_bytes()].concat();
+        assert_eq!(get(&db, DBCol::State, b"key"), Some(want));
+        let values: Vec<_> = db.iter(DBCol::State).map(|item| item.unwrap().1).collect();
+        assert_eq!(values, vec![b"value".to_vec().into_boxed_slice()]);
+
+        let mut transaction = DBTransaction::new();
+        transaction.update_refcount(DBCol::State, b"key".to_vec(), (-1i64).to_le_bytes().to_vec());
+        assert!(db.write(transaction).is_err());
+        let mut transaction = DBTransaction::new();
+        transaction.delete(DBCol::Block, b"key".to_vec());
+        assert!(db.write(transaction).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use std::io::{self, Read, Seek};
+use std::path::{Path, PathBuf};
+
+/// Object store holding sealed segment files.
+///
+/// Segments are written once and never modified afterwards so the interface
+/// mirrors what object stores offer: atomic whole-object puts and ranged
+/// reads.  [`LocalDirBackend`] keeps the objects in a local directory; other
+/// storages (e.g. a bucket served over HTTP) can be plugged in by implementing
+/// this trait and opening the database with
+/// [`super::SegmentDB::open_with_backend`].
+pub trait BlobBackend: Send + Sync {
+    /// Returns names of all objects stored in the backend.
+    fn list(&self) -> io::Result<Vec<String>>;
+
+    /// Returns size of the object in bytes.
+    fn size(&self, name: &str) -> io::Result<u64>;
+
+    /// Reads `len` bytes of the object starting at `offset`.
+    ///
+    /// Fails if the object is shorter than `offset + len` bytes.
+    fn read_range(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+
+    /// Stores an object with contents read from `data` under given name.
+    ///
+    /// The write must be atomic, i.e. readers must never observe a partially
+    /// written object.
+    fn put(&self, name: &str, data: &mut dyn Read) -> io::Result<()>;
+}
+
+/// Backend keeping segment files in a local directory.
+pub struct LocalDirBackend {
+    dir: PathBuf,
+}
+
+impl LocalDirBackend {
+    /// Suffix of files being written.  Such files are renamed once fully
+    /// written and are never listed.
+    const TMP_SUFFIX: &'static str = ".tmp";
+
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+}
+
+impl BlobBackend for LocalDirBackend {
+    fn list(&self) -> io::Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let Ok(name) = entry.file_name().into_string() else { continue };
+            if !name.ends_with(Self::TMP_SUFFIX) {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    fn size(&self, name: &str) -> io::Result<u64> {
+        Ok(std::fs::metadata(self.dir.join(name))?.len())
+    }
+
+    fn read_range(&self, name: &str, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut file = std::fs::File::open(self.dir.join(name))?;
+        file.seek(io::SeekFrom::Start(offset))?;
+        let mut buf = vec![0; len];
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn put(&self, name: &str, data: &mut dyn Read) -> io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(name);
+        let tmp_path = self.dir.join(format!("{name}{}", Self::TMP_SUFFIX));
+        {
+            let mut file = std::fs::File::create(&tmp_path)?;
+            io::copy(data, &mut file)?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &path)
+    }
+}
@@ -0,0 +1,498 @@
+//! Format of sealed segment files.
+//!
+//! ```text
+//! segment = block* meta footer
+//! block   = (u32 key_len, key, u32 value_len, value)*
+//! meta    = u32 num_columns, (u16 name_len, name)*,
+//!           u32 num_blocks, (u16 column, u32 key_len, first_key, u64 offset, u32 len)*,
+//!           u32 num_hashes, u32 bloom_len, bloom
+//! footer  = u64 start_height, u64 end_height, u64 meta_offset, u64 meta_len,
+//!           u64 num_entries, magic
+//! ```
+//!
+//! All integers are little-endian.  Each block holds entries of a single
+//! column sorted by key.  Columns are identified by their names rather than
+//! by position in [`DBCol`] so that segments stay readable when columns are
+//! added or removed.  The meta section is a sparse index with the first key of
+//! every block and a bloom filter over all the keys in the segment; it is
+//! loaded into memory when the segment is opened while blocks are fetched from
+//! the backend on demand.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use strum::IntoEnumIterator;
+use unc_primitives::types::BlockHeight;
+
+use super::backend::BlobBackend;
+use crate::db::DBIteratorItem;
+use crate::DBCol;
+
+const MAGIC: &[u8; 8] = b"UNCSEG01";
+const FOOTER_LEN: usize = 5 * 8 + MAGIC.len();
+const SEGMENT_SUFFIX: &str = ".seg";
+
+/// Returns name of the segment file covering given heights.
+///
+/// Heights are zero-padded so that names sort the same way as the heights.
+pub(super) fn segment_name(start_height: BlockHeight, end_height: BlockHeight) -> String {
+    format!("{start_height:020}-{end_height:020}{SEGMENT_SUFFIX}")
+}
+
+/// Parses name of a segment file returning heights it covers or `None` if the
+/// name isn’t one of a segment file.
+pub(super) fn parse_segment_name(name: &str) -> Option<(BlockHeight, BlockHeight)> {
+    let (start, end) = name.strip_suffix(SEGMENT_SUFFIX)?.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
+
+/// Encodes contents of a segment writing it to `out`.
+///
+/// `entries` must yield `num_entries` entries sorted by column and then by
+/// key.  Entries of a column are split into blocks of roughly `block_size`
+/// bytes; only the first key of every block is kept in memory.  Returns
+/// number of bytes written.
+pub(super) fn encode<'a>(
+    out: &mut impl io::Write,
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+    num_entries: usize,
+    entries: impl Iterator<Item = io::Result<(DBCol, &'a [u8], Vec<u8>)>>,
+    block_size: usize,
+    bloom_bits_per_key: u32,
+) -> io::Result<u64> {
+    let mut bloom = BloomFilter::new(num_entries, bloom_bits_per_key);
+    let mut columns = Vec::<DBCol>::new();
+    let mut blocks = BlockWriter { block: Vec::with_capacity(block_size), ..Default::default() };
+    let mut count = 0;
+    for entry in entries {
+        let (col, key, value) = entry?;
+        if columns.last() != Some(&col) {
+            blocks.flush(out, columns.len().saturating_sub(1))?;
+            columns.push(col);
+        }
+        blocks.first_key.get_or_insert(key);
+        put_bytes32(&mut blocks.block, key);
+        put_bytes32(&mut blocks.block, &value);
+        bloom.insert(col, key);
+        count += 1;
+        if blocks.block.len() >= block_size {
+            blocks.flush(out, columns.len() - 1)?;
+        }
+    }
+    blocks.flush(out, columns.len().saturating_sub(1))?;
+    if count != num_entries {
+        return Err(invalid_data(format!("expected {num_entries} entries, got {count}")));
+    }
+    let BlockWriter { index, written, .. } = blocks;
+
+    let meta_offset = written;
+    let mut meta = Vec::new();
+    meta.extend_from_slice(&(columns.len() as u32).to_le_bytes());
+    for col in columns.iter() {
+        let name = <&str>::from(*col);
+        meta.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        meta.extend_from_slice(name.as_bytes());
+    }
+    meta.extend_from_slice(&(index.len() as u32).to_le_bytes());
+    for (ordinal, first_key, offset, len) in index {
+        meta.extend_from_slice(&(ordinal as u16).to_le_bytes());
+        put_bytes32(&mut meta, first_key);
+        meta.extend_from_slice(&(offset as u64).to_le_bytes());
+        meta.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+    meta.extend_from_slice(&bloom.num_hashes.to_le_bytes());
+    put_bytes32(&mut meta, &bloom.bits);
+    let meta_len = meta.len();
+
+    meta.extend_from_slice(&start_height.to_le_bytes());
+    meta.extend_from_slice(&end_height.to_le_bytes());
+    meta.extend_from_slice(&(meta_offset as u64).to_le_bytes());
+    meta.extend_from_slice(&(meta_len as u64).to_le_bytes());
+    meta.extend_from_slice(&(num_entries as u64).to_le_bytes());
+    meta.extend_from_slice(MAGIC);
+    out.write_all(&meta)?;
+    Ok((written + meta.len()) as u64)
+}
+
+/// Data blocks of a segment being encoded.
+#[derive(Default)]
+struct BlockWriter<'a> {
+    /// Entries of the current block.
+    block: Vec<u8>,
+    /// First key of the current block; `None` if the block is empty.
+    first_key: Option<&'a [u8]>,
+    /// Column ordinal, first key, offset and length of every written block.
+    index: Vec<(usize, &'a [u8], usize, usize)>,
+    /// Number of bytes written so far.
+    written: usize,
+}
+
+impl<'a> BlockWriter<'a> {
+    /// Writes the current block, if any, as a block of given column.
+    fn flush(&mut self, out: &mut impl io::Write, ordinal: usize) -> io::Result<()> {
+        let Some(first_key) = self.first_key.take() else { return Ok(()) };
+        out.write_all(&self.block)?;
+        self.index.push((ordinal, first_key, self.written, self.block.len()));
+        self.written += self.block.len();
+        self.block.clear();
+        Ok(())
+    }
+}
+
+/// Location of a data block within a segment file.
+struct BlockHandle {
+    first_key: Box<[u8]>,
+    offset: u64,
+    len: u32,
+}
+
+/// A sealed segment opened for reading.
+pub(super) struct Segment {
+    name: String,
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+    num_entries: u64,
+    backend: Arc<dyn BlobBackend>,
+    /// Sparse index of every column present in the segment.
+    blocks: HashMap<DBCol, Vec<BlockHandle>>,
+    bloom: BloomFilter,
+    /// Recently read blocks keyed by their offset.
+    cache: Mutex<lru::LruCache<u64, Arc<[u8]>>>,
+}
+
+impl Segment {
+    /// Opens segment with given name reading its footer and meta section.
+    pub fn open(
+        backend: Arc<dyn BlobBackend>,
+        name: &str,
+        cache_capacity: usize,
+    ) -> io::Result<Self> {
+        let size = backend.size(name)?;
+        if size < FOOTER_LEN as u64 {
+            return Err(invalid_data(format!("{name}: file too short")));
+        }
+        let footer = backend.read_range(name, size - FOOTER_LEN as u64, FOOTER_LEN)?;
+        if &footer[FOOTER_LEN - MAGIC.len()..] != MAGIC {
+            return Err(invalid_data(format!("{name}: bad magic")));
+        }
+        let mut reader = Reader::new(&footer);
+        let start_height = reader.u64()?;
+        let end_height = reader.u64()?;
+        let meta_offset = reader.u64()?;
+        let meta_len = reader.u64()?;
+        let num_entries = reader.u64()?;
+        if meta_offset.checked_add(meta_len) != Some(size - FOOTER_LEN as u64) {
+            return Err(invalid_data(format!("{name}: bad meta section location")));
+        }
+
+        let meta = backend.read_range(name, meta_offset, meta_len as usize)?;
+        let mut reader = Reader::new(&meta);
+        let mut columns = Vec::new();
+        for _ in 0..reader.u32()? {
+            let name = std::str::from_utf8(reader.bytes16()?).map_err(invalid_data)?;
+            // A column unknown to this binary has been removed.  Its blocks
+            // are simply not indexed.
+            columns.push(DBCol::iter().find(|col| <&str>::from(*col) == name));
+        }
+        let mut blocks = HashMap::<DBCol, Vec<BlockHandle>>::new();
+        for _ in 0..reader.u32()? {
+            let ordinal = reader.u16()? as usize;
+            let first_key = reader.bytes32()?.into();
+            let offset = reader.u64()?;
+            let len = reader.u32()?;
+            let col = columns
+                .get(ordinal)
+                .ok_or_else(|| invalid_data(format!("{name}: bad column ordinal {ordinal}")))?;
+            if let Some(col) = col {
+                blocks.entry(*col).or_default().push(BlockHandle { first_key, offset, len });
+            }
+        }
+        let num_hashes = reader.u32()?;
+        let bits = reader.bytes32()?.to_vec();
+        let bloom = BloomFilter { bits, num_hashes };
+
+        Ok(Self {
+            name: name.to_string(),
+            start_height,
+            end_height,
+            num_entries,
+            backend,
+            blocks,
+            bloom,
+            cache: Mutex::new(lru::LruCache::new(cache_capacity)),
+        })
+    }
+
+    pub fn start_height(&self) -> BlockHeight {
+        self.start_height
+    }
+
+    pub fn end_height(&self) -> BlockHeight {
+        self.end_height
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Copies the segment file to a new file at given path.
+    pub fn copy_to(&self, path: &Path) -> io::Result<()> {
+        const CHUNK_LEN: u64 = 1 << 20;
+        let size = self.backend.size(&self.name)?;
+        let mut out = std::fs::File::create(path)?;
+        let mut offset = 0;
+        while offset < size {
+            let len = CHUNK_LEN.min(size - offset);
+            out.write_all(&self.backend.read_range(&self.name, offset, len as usize)?)?;
+            offset += len;
+        }
+        out.sync_all()
+    }
+
+    /// Returns value stored under given key.
+    pub fn get(&self, col: DBCol, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        if !self.bloom.may_contain(col, key) {
+            return Ok(None);
+        }
+        let Some(blocks) = self.blocks.get(&col) else { return Ok(None) };
+        let idx = blocks.partition_point(|block| &*block.first_key <= key);
+        if idx == 0 {
+            return Ok(None);
+        }
+        let block = self.read_block(&blocks[idx - 1])?;
+        let mut reader = Reader::new(&block);
+        while !reader.is_empty() {
+            let (entry_key, value) = (reader.bytes32()?, reader.bytes32()?);
+            match entry_key.cmp(key) {
+                std::cmp::Ordering::Less => continue,
+                std::cmp::Ordering::Equal => return Ok(Some(value.to_vec())),
+                std::cmp::Ordering::Greater => break,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Iterates over entries of a column whose keys are within
+    /// `[lower_bound, upper_bound)` range.
+    pub fn iter_range(
+        self: &Arc<Self>,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> SegmentIter {
+        let next_block = match (lower_bound, self.blocks.get(&col)) {
+            (Some(lower), Some(blocks)) => {
+                blocks.partition_point(|block| &*block.first_key <= lower).saturating_sub(1)
+            }
+            _ => 0,
+        };
+        SegmentIter {
+            segment: self.clone(),
+            col,
+            next_block,
+            lower_bound: lower_bound.map(<[u8]>::to_vec),
+            upper_bound: upper_bound.map(<[u8]>::to_vec),
+            pending: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    fn read_block(&self, handle: &BlockHandle) -> io::Result<Arc<[u8]>> {
+        if let Some(block) = self.cache.lock().unwrap().get(&handle.offset) {
+            return Ok(block.clone());
+        }
+        let block: Arc<[u8]> =
+            self.backend.read_range(&self.name, handle.offset, handle.len as usize)?.into();
+        self.cache.lock().unwrap().put(handle.offset, block.clone());
+        Ok(block)
+    }
+}
+
+/// Iterator over a key range of a single column in a segment.
+///
+/// Blocks are read lazily as the iterator advances.
+pub(super) struct SegmentIter {
+    segment: Arc<Segment>,
+    col: DBCol,
+    next_block: usize,
+    lower_bound: Option<Vec<u8>>,
+    upper_bound: Option<Vec<u8>>,
+    /// Remaining entries of the most recently read block.
+    pending: std::vec::IntoIter<(Box<[u8]>, Box<[u8]>)>,
+    done: bool,
+}
+
+impl SegmentIter {
+    fn read_next_block(&mut self) -> io::Result<bool> {
+        let Some(handle) =
+            self.segment.blocks.get(&self.col).and_then(|blocks| blocks.get(self.next_block))
+        else {
+            return Ok(false);
+        };
+        self.next_block += 1;
+        let block = self.segment.read_block(handle)?;
+        let mut reader = Reader::new(&block);
+        let mut entries = Vec::new();
+        while !reader.is_empty() {
+            entries.push((reader.bytes32()?.into(), reader.bytes32()?.into()));
+        }
+        self.pending = entries.into_iter();
+        Ok(true)
+    }
+}
+
+impl Iterator for SegmentIter {
+    type Item = DBIteratorItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let Some((key, value)) = self.pending.next() else {
+                match self.read_next_block() {
+                    Ok(true) => continue,
+                    Ok(false) => self.done = true,
+                    Err(err) => {
+                        self.done = true;
+                        return Some(Err(err));
+                    }
+                }
+                continue;
+            };
+            if self.lower_bound.as_deref().is_some_and(|lower| &*key < lower) {
+                continue;
+            }
+            if self.upper_bound.as_deref().is_some_and(|upper| &*key >= upper) {
+                self.done = true;
+                continue;
+            }
+            return Some(Ok((key, value)));
+        }
+        None
+    }
+}
+
+/// Bloom filter over `(column, key)` pairs stored in a segment.
+struct BloomFilter {
+    bits: Vec<u8>,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    fn new(num_keys: usize, bits_per_key: u32) -> Self {
+        let num_bits = (num_keys * bits_per_key as usize).max(64);
+        // ln(2) * bits_per_key minimises the false positive rate.
+        let num_hashes = (bits_per_key * 69 / 100).clamp(1, 30);
+        Self { bits: vec![0; (num_bits + 7) / 8], num_hashes }
+    }
+
+    fn insert(&mut self, col: DBCol, key: &[u8]) {
+        let num_bits = self.bits.len() as u64 * 8;
+        for bit in Self::bit_positions(col, key, self.num_hashes, num_bits) {
+            self.bits[(bit / 8) as usize] |= 1 << (bit % 8);
+        }
+    }
+
+    fn may_contain(&self, col: DBCol, key: &[u8]) -> bool {
+        let num_bits = self.bits.len() as u64 * 8;
+        if num_bits == 0 {
+            return true;
+        }
+        Self::bit_positions(col, key, self.num_hashes, num_bits)
+            .all(|bit| self.bits[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+    }
+
+    /// Returns bits to set for given key using double hashing.
+    fn bit_positions(
+        col: DBCol,
+        key: &[u8],
+        num_hashes: u32,
+        num_bits: u64,
+    ) -> impl Iterator<Item = u64> {
+        let name = <&str>::from(col);
+        let mut hash = fnv1a(fnv1a(FNV_OFFSET_BASIS, name.as_bytes()), key);
+        let delta = hash.rotate_right(17);
+        (0..num_hashes).map(move |_| {
+            let bit = hash % num_bits;
+            hash = hash.wrapping_add(delta);
+            bit
+        })
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+/// 64-bit FNV-1a hash.  Used by bloom filter since, unlike hashers from the
+/// standard library, its output is guaranteed to be stable.
+fn fnv1a(mut hash: u64, data: &[u8]) -> u64 {
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub(super) fn put_bytes32(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+pub(super) fn invalid_data(err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+/// Decoder of little-endian integers and length-prefixed byte strings.
+pub(super) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated segment data"));
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn bytes16(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    pub fn bytes32(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
@@ -31,6 +31,19 @@ pub struct FlatStateDeltaMetadata {
     pub prev_block_with_changes: Option<BlockWithChangesInfo>,
 }
 
+/// Undo record written when flat head moves over `block`. Applying `prev_values`
+/// of all records above some height, from the highest to the lowest, to the flat
+/// state at flat head gives the flat state at that height.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct FlatStateHistoryEntry {
+    pub block: BlockInfo,
+    /// Previous flat head and blocks without flat state changes after it.
+    /// Flat state at each of them is the one before `block` was applied.
+    pub blocks_before: Vec<CryptoHash>,
+    /// Values which keys changed by `block` had before it was applied.
+    pub prev_values: FlatStateChanges,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
 pub struct KeyForFlatStateDelta {
     pub shard_uid: ShardUId,
@@ -0,0 +1,402 @@
+//! Index of state bytes attributed to each account, broken down by kind of
+//! trie record. It is maintained together with flat state when flat head moves,
+//! so it always describes the state at flat head.
+
+use std::collections::HashMap;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::shard_layout::ShardUId;
+use unc_primitives::trie_key::{col, trie_key_parsers};
+use unc_primitives::types::AccountId;
+
+use crate::db::ACCOUNT_STORAGE_FOOTPRINTS_HEAD_KEY;
+use crate::metrics::flat_state_metrics::ACCOUNT_STORAGE_FOOTPRINT_BYTES;
+use crate::{DBCol, Store, StoreUpdate};
+
+use super::store_helper;
+use super::types::{FlatStorageError, FlatStorageResult};
+use super::FlatStateChanges;
+
+/// Upper bound on the number of largest accounts exported as metrics, which
+/// keeps cardinality of the account label bounded.
+const MAX_TOP_ACCOUNTS: usize = 100;
+
+/// Breakdown of state bytes attributed to an account. Sizes of contract data,
+/// access key and RSA key records include both keys and values.
+#[derive(
+    BorshSerialize, BorshDeserialize, serde::Serialize, Debug, Default, Clone, Copy, PartialEq, Eq,
+)]
+pub struct AccountStorageFootprint {
+    pub code_bytes: u64,
+    pub contract_data_bytes: u64,
+    pub contract_data_records: u64,
+    pub access_key_bytes: u64,
+    pub access_key_records: u64,
+    pub rsa_key_bytes: u64,
+    pub rsa_key_records: u64,
+}
+
+impl AccountStorageFootprint {
+    pub fn total_bytes(&self) -> u64 {
+        self.code_bytes + self.contract_data_bytes + self.access_key_bytes + self.rsa_key_bytes
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.code_bytes += other.code_bytes;
+        self.contract_data_bytes += other.contract_data_bytes;
+        self.contract_data_records += other.contract_data_records;
+        self.access_key_bytes += other.access_key_bytes;
+        self.access_key_records += other.access_key_records;
+        self.rsa_key_bytes += other.rsa_key_bytes;
+        self.rsa_key_records += other.rsa_key_records;
+    }
+
+    fn apply(&mut self, category: Category, bytes: i64, records: i64) {
+        let (bytes_field, records_field) = match category {
+            Category::Code => (&mut self.code_bytes, None),
+            Category::ContractData => {
+                (&mut self.contract_data_bytes, Some(&mut self.contract_data_records))
+            }
+            Category::AccessKey => (&mut self.access_key_bytes, Some(&mut self.access_key_records)),
+            Category::RsaKey => (&mut self.rsa_key_bytes, Some(&mut self.rsa_key_records)),
+        };
+        *bytes_field = bytes_field.saturating_add_signed(bytes);
+        if let Some(records_field) = records_field {
+            *records_field = records_field.saturating_add_signed(records);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Category {
+    Code,
+    ContractData,
+    AccessKey,
+    RsaKey,
+}
+
+impl Category {
+    const ALL: [(Category, u8, &'static str); 4] = [
+        (Category::Code, col::CONTRACT_CODE, "code"),
+        (Category::ContractData, col::CONTRACT_DATA, "contract_data"),
+        (Category::AccessKey, col::ACCESS_KEY, "access_keys"),
+        (Category::RsaKey, col::RSA2048_KEY, "rsa_keys"),
+    ];
+
+    /// Returns the account and the category a raw trie key is attributed to.
+    fn classify(key: &[u8]) -> Option<(AccountId, Category)> {
+        let (category, _, _) =
+            Self::ALL.into_iter().find(|(_, col, _)| key.first() == Some(col))?;
+        let account_id = match category {
+            Category::Code => trie_key_parsers::parse_account_id_from_contract_code_key(key),
+            Category::ContractData => {
+                trie_key_parsers::parse_account_id_from_contract_data_key(key)
+            }
+            Category::AccessKey => trie_key_parsers::parse_account_id_from_access_key_key(key),
+            Category::RsaKey => trie_key_parsers::parse_account_id_from_rsa_key_key(key),
+        };
+        account_id.ok().map(|account_id| (account_id, category))
+    }
+
+    /// Bytes attributed to a record. Only the size of the code itself is
+    /// counted for contracts.
+    fn record_size(self, key: &[u8], value_len: usize) -> i64 {
+        match self {
+            Category::Code => value_len as i64,
+            _ => (key.len() + value_len) as i64,
+        }
+    }
+}
+
+fn footprint_key(shard_uid: ShardUId, account_id: &AccountId) -> Vec<u8> {
+    store_helper::encode_flat_state_db_key(shard_uid, account_id.as_bytes())
+}
+
+fn footprints_head_key(shard_uid: ShardUId) -> Vec<u8> {
+    [ACCOUNT_STORAGE_FOOTPRINTS_HEAD_KEY, &shard_uid.to_bytes()].concat()
+}
+
+fn storage_error(err: std::io::Error) -> FlatStorageError {
+    FlatStorageError::StorageInternalError(format!(
+        "failed to read account storage footprint: {err}"
+    ))
+}
+
+pub fn get_account_storage_footprint(
+    store: &Store,
+    shard_uid: ShardUId,
+    account_id: &AccountId,
+) -> FlatStorageResult<Option<AccountStorageFootprint>> {
+    store
+        .get_ser(DBCol::AccountStorageFootprint, &footprint_key(shard_uid, account_id))
+        .map_err(storage_error)
+}
+
+fn set_account_storage_footprint(
+    store_update: &mut StoreUpdate,
+    shard_uid: ShardUId,
+    account_id: &AccountId,
+    footprint: &AccountStorageFootprint,
+) {
+    let key = footprint_key(shard_uid, account_id);
+    if footprint == &AccountStorageFootprint::default() {
+        store_update.delete(DBCol::AccountStorageFootprint, &key);
+    } else {
+        store_update
+            .set_ser(DBCol::AccountStorageFootprint, &key, footprint)
+            .expect("Borsh should not have failed here");
+    }
+}
+
+/// Returns the flat head the index of the shard was last updated to.
+pub(crate) fn get_footprints_head(
+    store: &Store,
+    shard_uid: ShardUId,
+) -> FlatStorageResult<Option<CryptoHash>> {
+    store.get_ser(DBCol::Misc, &footprints_head_key(shard_uid)).map_err(storage_error)
+}
+
+pub(crate) fn set_footprints_head(
+    store_update: &mut StoreUpdate,
+    shard_uid: ShardUId,
+    block_hash: &CryptoHash,
+) {
+    store_update
+        .set_ser(DBCol::Misc, &footprints_head_key(shard_uid), block_hash)
+        .expect("Borsh should not have failed here");
+}
+
+pub(crate) fn remove_all_footprints(store_update: &mut StoreUpdate, shard_uid: ShardUId) {
+    let key_from = shard_uid.to_bytes();
+    let key_to = ShardUId::next_shard_prefix(&key_from);
+    store_update.delete_range(DBCol::AccountStorageFootprint, &key_from, &key_to);
+    store_update.delete(DBCol::Misc, &footprints_head_key(shard_uid));
+}
+
+/// Updates footprints of accounts touched by `changes`. `prev_values` must
+/// contain values of all changed keys before the changes. Returns the new
+/// footprints of the touched accounts.
+pub(crate) fn update_footprints(
+    store: &Store,
+    store_update: &mut StoreUpdate,
+    shard_uid: ShardUId,
+    prev_values: &FlatStateChanges,
+    changes: &FlatStateChanges,
+) -> FlatStorageResult<Vec<(AccountId, AccountStorageFootprint)>> {
+    let mut deltas: HashMap<AccountId, Vec<(Category, i64, i64)>> = HashMap::new();
+    for (key, value) in changes.0.iter() {
+        let Some((account_id, category)) = Category::classify(key) else {
+            continue;
+        };
+        let prev_value = prev_values.get(key).flatten();
+        let size = |value_len: Option<usize>| {
+            value_len.map_or(0, |value_len| category.record_size(key, value_len))
+        };
+        let bytes = size(value.as_ref().map(|value| value.value_len()))
+            - size(prev_value.as_ref().map(|value| value.value_len()));
+        let records = value.is_some() as i64 - prev_value.is_some() as i64;
+        deltas.entry(account_id).or_default().push((category, bytes, records));
+    }
+
+    let mut updated = Vec::with_capacity(deltas.len());
+    for (account_id, account_deltas) in deltas {
+        let mut footprint =
+            get_account_storage_footprint(store, shard_uid, &account_id)?.unwrap_or_default();
+        for (category, bytes, records) in account_deltas {
+            footprint.apply(category, bytes, records);
+        }
+        set_account_storage_footprint(store_update, shard_uid, &account_id, &footprint);
+        updated.push((account_id, footprint));
+    }
+    Ok(updated)
+}
+
+/// Progress of rebuilding footprints of a shard from flat state.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum RebuildProgress {
+    #[default]
+    Started,
+    /// Records up to and including this raw trie key are accounted for.
+    Scanned(Vec<u8>),
+    Finished,
+}
+
+impl RebuildProgress {
+    /// Whether the record with the raw trie key is accounted for, so that its
+    /// changes must be applied to the footprints.
+    pub(crate) fn is_scanned(&self, key: &[u8]) -> bool {
+        match self {
+            RebuildProgress::Started => false,
+            RebuildProgress::Scanned(last) => key <= last.as_slice(),
+            RebuildProgress::Finished => true,
+        }
+    }
+}
+
+/// Accounts for the next `batch_size` flat state records in footprints being
+/// rebuilt from scratch and returns the progress after them. Flat state must
+/// not change during the call. It may change between calls, as long as the
+/// changes of already scanned records are applied with `update_footprints`.
+///
+/// Records are scanned in the order of raw trie keys. Footprints of accounts
+/// in the batch are committed at its end, so that stored footprints always
+/// include all scanned records.
+pub(crate) fn rebuild_footprints_batch(
+    store: &Store,
+    shard_uid: ShardUId,
+    progress: &RebuildProgress,
+    batch_size: usize,
+) -> FlatStorageResult<RebuildProgress> {
+    if progress == &RebuildProgress::Finished {
+        return Ok(RebuildProgress::Finished);
+    }
+    let mut cols: Vec<u8> = Category::ALL.into_iter().map(|(_, col, _)| col).collect();
+    cols.sort();
+    let mut footprints: HashMap<AccountId, AccountStorageFootprint> = HashMap::new();
+    let mut num_records = 0;
+    let mut last_key = None;
+    'scan: for col in cols {
+        let mut from = vec![col];
+        if let RebuildProgress::Scanned(last) = progress {
+            from = from.max([last.as_slice(), &[0]].concat());
+        }
+        for item in
+            store_helper::iter_flat_state_entries(shard_uid, store, Some(&from), Some(&[col + 1]))
+        {
+            if num_records == batch_size {
+                break 'scan;
+            }
+            let (key, value) = item?;
+            num_records += 1;
+            if let Some((account_id, category)) = Category::classify(&key) {
+                footprints.entry(account_id).or_default().apply(
+                    category,
+                    category.record_size(&key, value.value_len()),
+                    1,
+                );
+            }
+            last_key = Some(key);
+        }
+    }
+
+    let mut store_update = store.store_update();
+    for (account_id, batch_footprint) in footprints {
+        let mut footprint =
+            get_account_storage_footprint(store, shard_uid, &account_id)?.unwrap_or_default();
+        footprint.merge(&batch_footprint);
+        set_account_storage_footprint(&mut store_update, shard_uid, &account_id, &footprint);
+    }
+    store_update.commit().map_err(|err| {
+        FlatStorageError::StorageInternalError(format!(
+            "failed to write account storage footprints: {err}"
+        ))
+    })?;
+    Ok(match last_key {
+        Some(key) if num_records == batch_size => RebuildProgress::Scanned(key),
+        _ => RebuildProgress::Finished,
+    })
+}
+
+/// Largest accounts of a shard by total footprint, exported as metrics.
+///
+/// The set is computed exactly on startup and then updated from the accounts
+/// touched by each block. An account which shrinks stays in the set, with its
+/// current footprint, until a larger account replaces it.
+pub(crate) struct TopAccountFootprints {
+    shard_id_label: String,
+    limit: usize,
+    accounts: HashMap<AccountId, AccountStorageFootprint>,
+}
+
+impl TopAccountFootprints {
+    pub(crate) fn load(
+        store: &Store,
+        shard_uid: ShardUId,
+        limit: usize,
+    ) -> FlatStorageResult<Self> {
+        let mut all = vec![];
+        for item in store.iter_prefix_ser::<AccountStorageFootprint>(
+            DBCol::AccountStorageFootprint,
+            &shard_uid.to_bytes(),
+        ) {
+            let (key, footprint) = item.map_err(storage_error)?;
+            let account_id = std::str::from_utf8(&key[8..])
+                .ok()
+                .and_then(|account_id| account_id.parse::<AccountId>().ok())
+                .ok_or_else(|| {
+                    FlatStorageError::StorageInternalError(format!(
+                        "invalid account storage footprint key {key:?}"
+                    ))
+                })?;
+            all.push((account_id, footprint));
+        }
+        let limit = limit.min(MAX_TOP_ACCOUNTS);
+        all.sort_by_key(|(_, footprint)| std::cmp::Reverse(footprint.total_bytes()));
+        all.truncate(limit);
+
+        let mut this = Self {
+            shard_id_label: shard_uid.shard_id().to_string(),
+            limit,
+            accounts: HashMap::new(),
+        };
+        for (account_id, footprint) in all {
+            this.set_metrics(&account_id, &footprint);
+            this.accounts.insert(account_id, footprint);
+        }
+        Ok(this)
+    }
+
+    pub(crate) fn update(&mut self, account_id: AccountId, footprint: AccountStorageFootprint) {
+        if self.accounts.contains_key(&account_id) || self.accounts.len() < self.limit {
+            self.set_metrics(&account_id, &footprint);
+            self.accounts.insert(account_id, footprint);
+            return;
+        }
+        let Some((smallest_id, smallest)) =
+            self.accounts.iter().min_by_key(|(_, footprint)| footprint.total_bytes())
+        else {
+            return;
+        };
+        if footprint.total_bytes() <= smallest.total_bytes() {
+            return;
+        }
+        let smallest_id = smallest_id.clone();
+        self.accounts.remove(&smallest_id);
+        self.remove_metrics(&smallest_id);
+        self.set_metrics(&account_id, &footprint);
+        self.accounts.insert(account_id, footprint);
+    }
+
+    fn set_metrics(&self, account_id: &AccountId, footprint: &AccountStorageFootprint) {
+        let values = [
+            footprint.code_bytes,
+            footprint.contract_data_bytes,
+            footprint.access_key_bytes,
+            footprint.rsa_key_bytes,
+        ];
+        for ((_, _, category_label), value) in Category::ALL.into_iter().zip(values) {
+            ACCOUNT_STORAGE_FOOTPRINT_BYTES
+                .with_label_values(&[&self.shard_id_label, account_id.as_str(), category_label])
+                .set(value as i64);
+        }
+    }
+
+    fn remove_metrics(&self, account_id: &AccountId) {
+        for (_, _, category_label) in Category::ALL {
+            let _ = ACCOUNT_STORAGE_FOOTPRINT_BYTES.remove_label_values(&[
+                &self.shard_id_label,
+                account_id.as_str(),
+                category_label,
+            ]);
+        }
+    }
+}
+
+impl Drop for TopAccountFootprints {
+    fn drop(&mut self) {
+        for account_id in self.accounts.keys() {
+            self.remove_metrics(account_id);
+        }
+    }
+}
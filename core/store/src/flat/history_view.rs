@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use unc_primitives::shard_layout::ShardUId;
+use unc_primitives::state::FlatStateValue;
+
+use crate::flat::store_helper;
+use crate::{DBCol, Store, TrieCachingStorage};
+
+use super::types::{FlatStorageError, FlatStorageResult};
+use super::FlatStateChanges;
+
+/// Read-only view of flat state at a block behind the flat head, for which
+/// `FlatStateHistory` entries are retained.
+///
+/// Values are taken from `overrides`, which holds values of keys changed between
+/// the block and the flat head, and from the flat state at the flat head otherwise.
+/// Views are created by `FlatStorage::with_historical_view`, which discards
+/// results of reads during which flat head moved.
+pub struct FlatStateHistoryView<'a> {
+    store: &'a Store,
+    shard_uid: ShardUId,
+    overrides: FlatStateChanges,
+}
+
+impl<'a> FlatStateHistoryView<'a> {
+    pub(crate) fn new(store: &'a Store, shard_uid: ShardUId, overrides: FlatStateChanges) -> Self {
+        Self { store, shard_uid, overrides }
+    }
+
+    pub fn shard_uid(&self) -> ShardUId {
+        self.shard_uid
+    }
+
+    /// Returns value for the raw trie key at the block of the view.
+    pub fn get_value(&self, key: &[u8]) -> FlatStorageResult<Option<FlatStateValue>> {
+        match self.overrides.get(key) {
+            Some(value) => Ok(value),
+            None => store_helper::get_flat_state_value(self.store, self.shard_uid, key),
+        }
+    }
+
+    /// Returns all key-value pairs which raw trie keys start with `prefix`, ordered by key.
+    pub fn iter_prefix(&self, prefix: &[u8]) -> FlatStorageResult<Vec<(Vec<u8>, FlatStateValue)>> {
+        let mut entries = BTreeMap::new();
+        for item in
+            store_helper::iter_flat_state_entries(self.shard_uid, self.store, Some(prefix), None)
+        {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            entries.insert(key, value);
+        }
+        for (key, value) in self.overrides.0.iter().filter(|(key, _)| key.starts_with(prefix)) {
+            match value {
+                Some(value) => entries.insert(key.clone(), value.clone()),
+                None => entries.remove(key),
+            };
+        }
+        Ok(entries.into_iter().collect())
+    }
+
+    /// Resolves flat state value to the raw value bytes, reading `DBCol::State`
+    /// if the value is not inlined.
+    pub fn value_bytes(&self, value: FlatStateValue) -> FlatStorageResult<Vec<u8>> {
+        let value_ref = match value {
+            FlatStateValue::Inlined(bytes) => return Ok(bytes),
+            FlatStateValue::Ref(value_ref) => value_ref,
+        };
+        let key =
+            TrieCachingStorage::get_key_from_shard_uid_and_hash(self.shard_uid, &value_ref.hash);
+        match self.store.get(DBCol::State, &key) {
+            Ok(Some(bytes)) => Ok(bytes.to_vec()),
+            Ok(None) => Err(FlatStorageError::StorageInternalError(format!(
+                "missing value {} in shard {}",
+                value_ref.hash, self.shard_uid
+            ))),
+            Err(err) => Err(FlatStorageError::StorageInternalError(format!(
+                "failed to read value {}: {err}",
+                value_ref.hash
+            ))),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::flat::{
-    store_helper, BlockInfo, FlatStorageReadyStatus, FlatStorageStatus, POISONED_LOCK_ERR,
+    store_helper, BlockInfo, FlatStorageOptions, FlatStorageReadyStatus, FlatStorageStatus,
+    POISONED_LOCK_ERR,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -34,11 +35,21 @@ pub struct FlatStorageManagerInner {
     /// this epoch can share the same `head` and `tail`, similar for shards for the next epoch,
     /// but such overhead is negligible comparing the delta sizes, so we think it's ok.
     flat_storages: Mutex<HashMap<ShardUId, FlatStorage>>,
+    /// Optional features enabled for all flat storages.
+    options: FlatStorageOptions,
 }
 
 impl FlatStorageManager {
     pub fn new(store: Store) -> Self {
-        Self(Arc::new(FlatStorageManagerInner { store, flat_storages: Default::default() }))
+        Self::with_options(store, Default::default())
+    }
+
+    pub fn with_options(store: Store, options: FlatStorageOptions) -> Self {
+        Self(Arc::new(FlatStorageManagerInner {
+            store,
+            flat_storages: Default::default(),
+            options,
+        }))
     }
 
     /// When a node starts from an empty database, this function must be called to ensure
@@ -70,8 +81,8 @@ impl FlatStorageManager {
     /// and resharding.
     pub fn create_flat_storage_for_shard(&self, shard_uid: ShardUId) -> Result<(), StorageError> {
         let mut flat_storages = self.0.flat_storages.lock().expect(POISONED_LOCK_ERR);
-        let original_value =
-            flat_storages.insert(shard_uid, FlatStorage::new(self.0.store.clone(), shard_uid)?);
+        let original_value = flat_storages
+            .insert(shard_uid, FlatStorage::new(self.0.store.clone(), shard_uid, self.0.options)?);
         // TODO (#7327): maybe we should propagate the error instead of assert here
         // assert is fine now because this function is only called at construction time, but we
         // will need to be more careful when we want to implement flat storage for resharding
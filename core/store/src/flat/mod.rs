@@ -27,6 +27,8 @@
 
 mod chunk_view;
 pub mod delta;
+pub mod footprint;
+mod history_view;
 mod inlining_migration;
 mod manager;
 mod metrics;
@@ -37,14 +39,15 @@ pub mod test_utils;
 mod types;
 
 pub use chunk_view::FlatStorageChunkView;
-pub use delta::{FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata};
+pub use delta::{FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata, FlatStateHistoryEntry};
+pub use history_view::FlatStateHistoryView;
 pub use inlining_migration::{inline_flat_state_values, FlatStateValuesInliningMigrationHandle};
 pub use manager::FlatStorageManager;
 pub use metrics::FlatStorageCreationMetrics;
 pub use storage::FlatStorage;
 pub use types::{
     BlockInfo, FetchingStateStatus, FlatStateIterator, FlatStorageCreationStatus, FlatStorageError,
-    FlatStorageReadyStatus, FlatStorageStatus,
+    FlatStorageOptions, FlatStorageReadyStatus, FlatStorageStatus,
 };
 
 pub(crate) const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
@@ -1,23 +1,35 @@
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 
 use tracing::{debug, warn};
 use unc_primitives::errors::StorageError;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::shard_layout::ShardUId;
 use unc_primitives::state::FlatStateValue;
-use unc_primitives::types::BlockHeight;
+use unc_primitives::types::{AccountId, BlockHeight};
 
-use crate::flat::delta::{BlockWithChangesInfo, CachedFlatStateChanges};
+use crate::flat::delta::{
+    BlockWithChangesInfo, CachedFlatStateChanges, FlatStateChanges, FlatStateHistoryEntry,
+};
 use crate::flat::BlockInfo;
-use crate::flat::{FlatStorageReadyStatus, FlatStorageStatus};
+use crate::flat::{FlatStorageOptions, FlatStorageReadyStatus, FlatStorageStatus};
 use crate::{Store, StoreUpdate};
 
 use super::delta::{CachedFlatStateDelta, FlatStateDelta};
+use super::footprint::{self, AccountStorageFootprint, RebuildProgress, TopAccountFootprints};
+use super::history_view::FlatStateHistoryView;
 use super::metrics::FlatStorageMetrics;
 use super::store_helper;
 use super::types::FlatStorageError;
 
+/// Number of times `FlatStorage::with_historical_view` reads the view again
+/// because flat head moved during the read.
+const HISTORICAL_VIEW_ATTEMPTS: usize = 3;
+
+/// Number of flat state records accounted for in account storage footprints
+/// while holding the flat storage lock during a rebuild.
+const FOOTPRINTS_REBUILD_BATCH_SIZE: usize = 10_000;
+
 /// FlatStorage stores information on which blocks flat storage current supports key lookups on.
 /// Note that this struct is shared by multiple threads, the chain thread, threads that apply chunks,
 /// and view client, so the implementation here must be thread safe and must have interior mutability,
@@ -48,9 +60,27 @@ pub(crate) struct FlatStorageInner {
     /// The flag has a numerical value and not a bool, to let us detect attempts
     /// to disable move head multiple times.
     move_head_enabled: bool,
+    /// Number of blocks behind flat head for which `FlatStateHistory` entries
+    /// are kept. Zero disables history.
+    history_window: BlockHeight,
+    footprints: FootprintIndex,
     metrics: FlatStorageMetrics,
 }
 
+/// State of the account storage footprints of the shard.
+enum FootprintIndex {
+    /// Footprints are not maintained.
+    Disabled,
+    /// Footprints are rebuilt from flat state in background and reported as
+    /// unavailable until that finishes. When flat head moves, only changes of
+    /// the records already scanned are applied to them.
+    Rebuilding {
+        top_n: usize,
+        progress: Mutex<RebuildProgress>,
+    },
+    Ready(TopAccountFootprints),
+}
+
 impl FlatStorageInner {
     /// Expected limits for in-memory stored changes, under which flat storage must keep working.
     /// If they are exceeded, warnings are displayed. Flat storage still will work, but its
@@ -221,6 +251,77 @@ impl FlatStorageInner {
         Ok(new_head)
     }
 
+    /// Reads values which keys changed by `changes` have at the current flat head.
+    fn get_prev_values(
+        &self,
+        changes: &FlatStateChanges,
+    ) -> Result<FlatStateChanges, FlatStorageError> {
+        let mut prev_values = FlatStateChanges::default();
+        for key in changes.0.keys() {
+            let value = store_helper::get_flat_state_value(&self.store, self.shard_uid, key)?;
+            prev_values.insert(key.clone(), value);
+        }
+        Ok(prev_values)
+    }
+
+    /// Creates history entry for `block` which is about to be applied on top
+    /// of the current flat head.
+    fn create_history_entry(
+        &self,
+        block: BlockInfo,
+        prev_values: FlatStateChanges,
+    ) -> Result<FlatStateHistoryEntry, FlatStorageError> {
+        let mut blocks_before = vec![];
+        let mut hash = block.prev_hash;
+        while hash != self.flat_head.hash {
+            blocks_before.push(hash);
+            match self.deltas.get(&hash) {
+                Some(delta) => hash = delta.metadata.block.prev_hash,
+                None => return Err(missing_delta_error(&hash)),
+            }
+        }
+        blocks_before.push(self.flat_head.hash);
+        Ok(FlatStateHistoryEntry { block, blocks_before, prev_values })
+    }
+
+    /// Returns values which keys changed between the given block and the flat
+    /// head had at the block, or `None` if the block is not covered by history.
+    fn get_history_overrides(
+        &self,
+        block_hash: &CryptoHash,
+        block_height: BlockHeight,
+    ) -> Result<Option<FlatStateChanges>, FlatStorageError> {
+        if self.history_window == 0 {
+            return Ok(None);
+        }
+        let mut overrides = FlatStateChanges::default();
+        if block_hash == &self.flat_head.hash {
+            return Ok(Some(overrides));
+        }
+        if block_height >= self.flat_head.height
+            || block_height + self.history_window < self.flat_head.height
+        {
+            return Ok(None);
+        }
+        let entries = store_helper::get_flat_state_history_entries(
+            &self.store,
+            self.shard_uid,
+            block_height + 1,
+            self.flat_head.height,
+        )?;
+        // The block must precede the lowest entry above it, otherwise it is
+        // on a fork or older than the retained history.
+        match entries.first() {
+            Some(entry) if entry.blocks_before.contains(block_hash) => {}
+            _ => return Ok(None),
+        }
+        // Undo changes from the flat head down to the block.
+        for entry in entries.into_iter().rev() {
+            overrides.merge(entry.prev_values);
+        }
+        Ok(Some(overrides))
+    }
+
     #[cfg(test)]
     pub fn test_get_new_flat_head(
         &self,
@@ -235,7 +336,11 @@ impl FlatStorage {
     /// Create a new FlatStorage for `shard_uid` using flat head if it is stored on storage.
     /// We also load all blocks with height between flat head to `latest_block_height`
     /// including those on forks into the returned FlatStorage.
-    pub fn new(store: Store, shard_uid: ShardUId) -> Result<Self, StorageError> {
+    pub fn new(
+        store: Store,
+        shard_uid: ShardUId,
+        options: FlatStorageOptions,
+    ) -> Result<Self, StorageError> {
         let shard_id = shard_uid.shard_id();
         let flat_head = match store_helper::get_flat_storage_status(&store, shard_uid) {
             Ok(FlatStorageStatus::Ready(ready_status)) => ready_status.flat_head,
@@ -268,16 +373,102 @@ impl FlatStorage {
             );
         }
 
+        let footprints = match options.account_footprints_top_n {
+            Some(top_n)
+                if footprint::get_footprints_head(&store, shard_uid)? == Some(flat_head.hash) =>
+            {
+                FootprintIndex::Ready(TopAccountFootprints::load(&store, shard_uid, top_n)?)
+            }
+            Some(top_n) => {
+                tracing::info!(target: "store", %shard_uid, "Rebuilding account storage footprints");
+                let mut store_update = store.store_update();
+                footprint::remove_all_footprints(&mut store_update, shard_uid);
+                store_update.commit().map_err(|err| {
+                    FlatStorageError::StorageInternalError(format!(
+                        "failed to remove account storage footprints: {err}"
+                    ))
+                })?;
+                FootprintIndex::Rebuilding { top_n, progress: Default::default() }
+            }
+            None => FootprintIndex::Disabled,
+        };
+        if options.history_window == 0 {
+            // Clean up after a previous configuration which kept history.
+            let mut store_update = store.store_update();
+            store_helper::remove_all_flat_state_history(&mut store_update, shard_uid);
+            store_update.commit().map_err(|err| {
+                FlatStorageError::StorageInternalError(format!(
+                    "failed to remove flat state history: {err}"
+                ))
+            })?;
+        }
+
         let inner = FlatStorageInner {
             store,
             shard_uid,
             flat_head,
             deltas,
             move_head_enabled: true,
+            history_window: options.history_window,
+            footprints,
             metrics,
         };
         inner.update_delta_metrics();
-        Ok(Self(Arc::new(RwLock::new(inner))))
+        let rebuild_footprints = matches!(inner.footprints, FootprintIndex::Rebuilding { .. });
+        let flat_storage = Self(Arc::new(RwLock::new(inner)));
+        if rebuild_footprints {
+            let inner = Arc::downgrade(&flat_storage.0);
+            std::thread::spawn(move || {
+                if let Err(err) = Self::rebuild_footprints(inner) {
+                    tracing::error!(target: "store", %shard_uid, ?err, "Failed to rebuild account storage footprints");
+                }
+            });
+        }
+        Ok(flat_storage)
+    }
+
+    /// Rebuilds account storage footprints one batch of flat state records at
+    /// a time. Each batch holds the flat storage lock, so that flat head can't
+    /// move during it. Stops early if flat storage is dropped or cleared.
+    fn rebuild_footprints(inner: Weak<RwLock<FlatStorageInner>>) -> Result<(), FlatStorageError> {
+        let top = loop {
+            let Some(inner) = inner.upgrade() else {
+                return Ok(());
+            };
+            let guard = inner.read().expect(super::POISONED_LOCK_ERR);
+            let FootprintIndex::Rebuilding { top_n, progress } = &guard.footprints else {
+                return Ok(());
+            };
+            let mut progress = progress.lock().expect(super::POISONED_LOCK_ERR);
+            *progress = footprint::rebuild_footprints_batch(
+                &guard.store,
+                guard.shard_uid,
+                &progress,
+                FOOTPRINTS_REBUILD_BATCH_SIZE,
+            )?;
+            if *progress == RebuildProgress::Finished {
+                break TopAccountFootprints::load(&guard.store, guard.shard_uid, *top_n)?;
+            }
+        };
+        let Some(inner) = inner.upgrade() else {
+            return Ok(());
+        };
+        let mut guard = inner.write().expect(super::POISONED_LOCK_ERR);
+        if !matches!(guard.footprints, FootprintIndex::Rebuilding { .. }) {
+            return Ok(());
+        }
+        // Accounts changed since the largest ones were loaded are picked up
+        // when they are changed again.
+        let mut store_update = guard.store.store_update();
+        footprint::set_footprints_head(&mut store_update, guard.shard_uid, &guard.flat_head.hash);
+        store_update.commit().map_err(|err| {
+            FlatStorageError::StorageInternalError(format!(
+                "failed to write account storage footprints head: {err}"
+            ))
+        })?;
+        guard.footprints = FootprintIndex::Ready(top);
+        tracing::info!(target: "store", shard_uid = %guard.shard_uid, "Rebuilt account storage footprints");
+        Ok(())
     }
 
     /// Get sequence of blocks `target_block_hash` (inclusive) to flat head (exclusive)
@@ -364,7 +555,6 @@ impl FlatStorage {
             // path from old to new head. Otherwise we return internal error.
             let changes = store_helper::get_delta_changes(&guard.store, shard_uid, block_hash)?
                 .ok_or_else(|| missing_delta_error(&block_hash))?;
-            changes.apply_to_flat_state(&mut store_update, guard.shard_uid);
             let metadata = guard
                 .deltas
                 .get(&block_hash)
@@ -372,6 +562,63 @@ impl FlatStorage {
                 .metadata;
             let block = metadata.block;
             let block_height = block.height;
+            let footprints_enabled = !matches!(guard.footprints, FootprintIndex::Disabled);
+            if guard.history_window > 0 || footprints_enabled {
+                let prev_values = guard.get_prev_values(&changes)?;
+                let inner = &mut *guard;
+                match &mut inner.footprints {
+                    FootprintIndex::Disabled => {}
+                    FootprintIndex::Rebuilding { progress, .. } => {
+                        // Records not scanned yet are read by the rebuild as of
+                        // the new flat head.
+                        let progress = progress.lock().expect(super::POISONED_LOCK_ERR);
+                        let changes = FlatStateChanges(
+                            changes
+                                .0
+                                .iter()
+                                .filter(|(key, _)| progress.is_scanned(key))
+                                .map(|(key, value)| (key.clone(), value.clone()))
+                                .collect(),
+                        );
+                        footprint::update_footprints(
+                            &inner.store,
+                            &mut store_update,
+                            shard_uid,
+                            &prev_values,
+                            &changes,
+                        )?;
+                    }
+                    FootprintIndex::Ready(top_footprints) => {
+                        let updated = footprint::update_footprints(
+                            &inner.store,
+                            &mut store_update,
+                            shard_uid,
+                            &prev_values,
+                            &changes,
+                        )?;
+                        footprint::set_footprints_head(&mut store_update, shard_uid, &block.hash);
+                        for (account_id, footprint) in updated {
+                            top_footprints.update(account_id, footprint);
+                        }
+                    }
+                }
+                if guard.history_window > 0 {
+                    let entry = guard.create_history_entry(block, prev_values)?;
+                    store_helper::set_flat_state_history_entry(
+                        &mut store_update,
+                        shard_uid,
+                        &entry,
+                    );
+                }
+            }
+            if guard.history_window > 0 {
+                store_helper::remove_flat_state_history_below(
+                    &mut store_update,
+                    shard_uid,
+                    (block_height + 1).saturating_sub(guard.history_window),
+                );
+            }
+            changes.apply_to_flat_state(&mut store_update, guard.shard_uid);
             store_helper::set_flat_storage_status(
                 &mut store_update,
                 shard_uid,
@@ -437,15 +684,68 @@ impl FlatStorage {
 
     /// Clears all State key-value pairs from flat storage.
     pub fn clear_state(&self, store_update: &mut StoreUpdate) -> Result<(), StorageError> {
-        let guard = self.0.write().expect(super::POISONED_LOCK_ERR);
+        let mut guard = self.0.write().expect(super::POISONED_LOCK_ERR);
         let shard_uid = guard.shard_uid;
         store_helper::remove_all_flat_state_values(store_update, shard_uid);
         store_helper::remove_all_deltas(store_update, shard_uid);
+        store_helper::remove_all_flat_state_history(store_update, shard_uid);
+        footprint::remove_all_footprints(store_update, shard_uid);
+        guard.footprints = FootprintIndex::Disabled;
         store_helper::set_flat_storage_status(store_update, shard_uid, FlatStorageStatus::Empty);
         guard.update_delta_metrics();
         Ok(())
     }
 
+    /// Calls `f` with a view of flat state at the given block, if the block is
+    /// the flat head or one of `history_window` blocks behind it on the chain
+    /// of the flat head. Returns `None` if the block is not covered by history
+    /// or history is disabled.
+    ///
+    /// The flat storage lock is not held while `f` runs, so that a slow query
+    /// doesn't block the flat head from moving. Flat state only changes when
+    /// the head moves, so if it moved meanwhile, the result of `f` is dropped
+    /// and `f` is called again. After `HISTORICAL_VIEW_ATTEMPTS` such attempts
+    /// `None` is returned.
+    pub fn with_historical_view<R>(
+        &self,
+        block_hash: &CryptoHash,
+        block_height: BlockHeight,
+        mut f: impl FnMut(&FlatStateHistoryView) -> R,
+    ) -> Result<Option<R>, FlatStorageError> {
+        for _ in 0..HISTORICAL_VIEW_ATTEMPTS {
+            let (store, shard_uid, flat_head, overrides) = {
+                let guard = self.0.read().expect(super::POISONED_LOCK_ERR);
+                let Some(overrides) = guard.get_history_overrides(block_hash, block_height)? else {
+                    return Ok(None);
+                };
+                (guard.store.clone(), guard.shard_uid, guard.flat_head.hash, overrides)
+            };
+            let view = FlatStateHistoryView::new(&store, shard_uid, overrides);
+            let result = f(&view);
+            if self.get_head_hash() == flat_head {
+                return Ok(Some(result));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns storage footprint of the account at flat head together with the
+    /// flat head, or `None` if footprints are not maintained or are still
+    /// being rebuilt.
+    pub fn get_account_storage_footprint(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<Option<(BlockInfo, AccountStorageFootprint)>, FlatStorageError> {
+        let guard = self.0.read().expect(super::POISONED_LOCK_ERR);
+        if !matches!(guard.footprints, FootprintIndex::Ready(_)) {
+            return Ok(None);
+        }
+        let footprint =
+            footprint::get_account_storage_footprint(&guard.store, guard.shard_uid, account_id)?
+                .unwrap_or_default();
+        Ok(Some((guard.flat_head, footprint)))
+    }
+
     pub(crate) fn get_head_hash(&self) -> CryptoHash {
         let guard = self.0.read().expect(super::POISONED_LOCK_ERR);
         guard.flat_head.hash
@@ -477,22 +777,27 @@ mod tests {
     use crate::flat::delta::{
         BlockWithChangesInfo, FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata,
     };
+    use crate::flat::footprint::{self, AccountStorageFootprint};
     use crate::flat::manager::FlatStorageManager;
-    use crate::flat::storage::FlatStorageInner;
+    use crate::flat::storage::{FlatStorage, FlatStorageInner};
     use crate::flat::test_utils::MockChain;
     use crate::flat::types::FlatStorageError;
-    use crate::flat::{store_helper, FlatStorageReadyStatus, FlatStorageStatus};
+    use crate::flat::{
+        store_helper, BlockInfo, FlatStorageOptions, FlatStorageReadyStatus, FlatStorageStatus,
+    };
     use crate::test_utils::create_test_store;
     use crate::StorageError;
     use assert_matches::assert_matches;
 
     use rand::{thread_rng, Rng};
     use std::collections::HashMap;
+    use unc_crypto::{KeyType, PublicKey};
     use unc_o11y::testonly::init_test_logger;
     use unc_primitives::hash::{hash, CryptoHash};
     use unc_primitives::shard_layout::ShardUId;
     use unc_primitives::state::FlatStateValue;
-    use unc_primitives::types::BlockHeight;
+    use unc_primitives::trie_key::TrieKey;
+    use unc_primitives::types::{AccountId, BlockHeight};
 
     #[test]
     fn flat_storage_errors() {
@@ -654,6 +959,287 @@ mod tests {
     // We created a linear chain with no forks, start with flat head at the genesis block, then
     // moves the flat head forward, which checking that chunk_view.get_ref() still returns the correct
     // values and the state is being updated in store.
+    #[test]
+    fn flat_storage_history() {
+        // 1. Create a chain with 10 blocks with no forks. Set flat head to be at block 0.
+        //    Block i sets value for key &[1] to &[i], except block 5 which has no changes.
+        //    Block 6 also adds key &[2].
+        let chain = MockChain::liunc_chain(10);
+        let shard_uid = ShardUId::single_shard();
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        store_helper::set_flat_storage_status(
+            &mut store_update,
+            shard_uid,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head: chain.get_block(0) }),
+        );
+        store_helper::set_flat_state_value(
+            &mut store_update,
+            shard_uid,
+            vec![1],
+            Some(FlatStateValue::value_ref(&[0])),
+        );
+        for i in 1..10 {
+            let (changes, prev_block_with_changes) = match i {
+                5 => (
+                    FlatStateChanges::default(),
+                    Some(BlockWithChangesInfo { hash: chain.get_block_hash(4), height: 4 }),
+                ),
+                6 => (
+                    FlatStateChanges::from([
+                        (vec![1], Some(FlatStateValue::value_ref(&[6]))),
+                        (vec![2], Some(FlatStateValue::value_ref(&[6]))),
+                    ]),
+                    None,
+                ),
+                _ => (
+                    FlatStateChanges::from([(
+                        vec![1],
+                        Some(FlatStateValue::value_ref(&[i as u8])),
+                    )]),
+                    None,
+                ),
+            };
+            let delta = FlatStateDelta {
+                changes,
+                metadata: FlatStateDeltaMetadata {
+                    block: chain.get_block(i),
+                    prev_block_with_changes,
+                },
+            };
+            store_helper::set_delta(&mut store_update, shard_uid, &delta);
+        }
+        store_update.commit().unwrap();
+
+        let flat_storage_manager = FlatStorageManager::with_options(
+            store.clone(),
+            FlatStorageOptions { history_window: 4, account_footprints_top_n: None },
+        );
+        flat_storage_manager.create_flat_storage_for_shard(shard_uid).unwrap();
+        let flat_storage = flat_storage_manager.get_flat_storage_for_shard(shard_uid).unwrap();
+
+        // 2. Move flat head to block 9, once while a view is read. The view is
+        //    read again at the new head. Only history for blocks 6..=9 is kept.
+        flat_storage.update_flat_head(&chain.get_block_hash(8), true).unwrap();
+        let mut num_reads = 0;
+        let value = flat_storage
+            .with_historical_view(&chain.get_block_hash(7), 7, |view| {
+                num_reads += 1;
+                if num_reads == 1 {
+                    flat_storage.update_flat_head(&chain.get_block_hash(9), true).unwrap();
+                }
+                view.get_value(&[1]).unwrap()
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(value, Some(FlatStateValue::value_ref(&[7])));
+        assert_eq!(num_reads, 2);
+        let heights: Vec<_> = store_helper::get_flat_state_history_entries(&store, shard_uid, 0, 9)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.block.height)
+            .collect();
+        assert_eq!(heights, vec![6, 7, 8, 9]);
+
+        // 3. Check values at blocks in the window, including block 5 without changes.
+        for i in 5..10 {
+            let expected_value = if i == 5 { 4 } else { i as u8 };
+            let (value1, value2) = flat_storage
+                .with_historical_view(&chain.get_block_hash(i), i, |view| {
+                    (view.get_value(&[1]).unwrap(), view.get_value(&[2]).unwrap())
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(value1, Some(FlatStateValue::value_ref(&[expected_value])));
+            assert_eq!(value2, (i >= 6).then(|| FlatStateValue::value_ref(&[6])));
+        }
+        let entries = flat_storage
+            .with_historical_view(&chain.get_block_hash(5), 5, |view| view.iter_prefix(&[]))
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(entries, vec![(vec![1], FlatStateValue::value_ref(&[4]))]);
+
+        // 4. Blocks out of the window or not on the chain of flat head are not supported.
+        assert_eq!(
+            flat_storage.with_historical_view(&chain.get_block_hash(4), 4, |_| ()),
+            Ok(None)
+        );
+        assert_eq!(flat_storage.with_historical_view(&hash(&[7]), 7, |_| ()), Ok(None));
+
+        // 5. Flat storage with history disabled removes history left by a
+        //    previous configuration.
+        FlatStorage::new(
+            store.clone(),
+            shard_uid,
+            FlatStorageOptions { history_window: 0, account_footprints_top_n: None },
+        )
+        .unwrap();
+        assert!(store_helper::get_flat_state_history_entries(&store, shard_uid, 0, 9)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            flat_storage.with_historical_view(&chain.get_block_hash(7), 7, |_| ()),
+            Ok(None)
+        );
+
+        // 6. Clearing the state removes history.
+        let mut store_update = store.store_update();
+        flat_storage.clear_state(&mut store_update).unwrap();
+        store_update.commit().unwrap();
+        assert!(store_helper::get_flat_state_history_entries(&store, shard_uid, 0, 9)
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Waits until account storage footprints are rebuilt in background and
+    /// returns the footprint of the account.
+    fn wait_for_footprint(
+        flat_storage: &FlatStorage,
+        account_id: &AccountId,
+    ) -> (BlockInfo, AccountStorageFootprint) {
+        for _ in 0..1000 {
+            if let Some(result) = flat_storage.get_account_storage_footprint(account_id).unwrap() {
+                return result;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("account storage footprints were not rebuilt");
+    }
+
+    #[test]
+    fn flat_storage_account_footprints() {
+        // 1. Create a chain with 4 blocks and flat head at block 0, where alice has
+        //    a contract with one data record and bob has one access key.
+        //    Block 1 adds a data record to alice and deletes bob's key.
+        //    Block 2 deploys a larger contract to alice.
+        //    Block 3 deletes the first data record of alice.
+        let chain = MockChain::liunc_chain(4);
+        let shard_uid = ShardUId::single_shard();
+        let store = create_test_store();
+        let alice: AccountId = "alice".parse().unwrap();
+        let bob: AccountId = "bob".parse().unwrap();
+        let code_key = TrieKey::ContractCode { account_id: alice.clone() }.to_vec();
+        let data_key1 = TrieKey::ContractData { account_id: alice.clone(), key: vec![1] }.to_vec();
+        let data_key2 = TrieKey::ContractData { account_id: alice.clone(), key: vec![2] }.to_vec();
+        let access_key = TrieKey::AccessKey {
+            account_id: bob.clone(),
+            public_key: PublicKey::empty(KeyType::ED25519),
+        }
+        .to_vec();
+
+        let mut store_update = store.store_update();
+        store_helper::set_flat_storage_status(
+            &mut store_update,
+            shard_uid,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head: chain.get_block(0) }),
+        );
+        for (key, value) in
+            [(&code_key, vec![0; 10]), (&data_key1, vec![0; 5]), (&access_key, vec![0; 3])]
+        {
+            store_helper::set_flat_state_value(
+                &mut store_update,
+                shard_uid,
+                key.clone(),
+                Some(FlatStateValue::inlined(&value)),
+            );
+        }
+        let block_changes = [
+            FlatStateChanges::from([
+                (data_key2.clone(), Some(FlatStateValue::inlined(&[0; 4]))),
+                (access_key.clone(), None),
+            ]),
+            FlatStateChanges::from([(code_key.clone(), Some(FlatStateValue::value_ref(&[0; 20])))]),
+            FlatStateChanges::from([(data_key1.clone(), None)]),
+        ];
+        for (i, changes) in block_changes.into_iter().enumerate() {
+            let delta = FlatStateDelta {
+                changes,
+                metadata: FlatStateDeltaMetadata {
+                    block: chain.get_block(i as BlockHeight + 1),
+                    prev_block_with_changes: None,
+                },
+            };
+            store_helper::set_delta(&mut store_update, shard_uid, &delta);
+        }
+        store_update.commit().unwrap();
+
+        // 2. Footprints are built from flat state in background when flat
+        //    storage is created.
+        let options = FlatStorageOptions { history_window: 0, account_footprints_top_n: Some(1) };
+        let flat_storage_manager = FlatStorageManager::with_options(store.clone(), options);
+        flat_storage_manager.create_flat_storage_for_shard(shard_uid).unwrap();
+        let flat_storage = flat_storage_manager.get_flat_storage_for_shard(shard_uid).unwrap();
+        let (block, footprint) = wait_for_footprint(&flat_storage, &alice);
+        assert_eq!(block, chain.get_block(0));
+        assert_eq!(
+            footprint,
+            AccountStorageFootprint {
+                code_bytes: 10,
+                contract_data_bytes: data_key1.len() as u64 + 5,
+                contract_data_records: 1,
+                ..Default::default()
+            }
+        );
+        let (_, footprint) = flat_storage.get_account_storage_footprint(&bob).unwrap().unwrap();
+        assert_eq!(footprint.access_key_bytes, access_key.len() as u64 + 3);
+        assert_eq!(footprint.access_key_records, 1);
+
+        // 3. Moving flat head updates footprints incrementally.
+        flat_storage.update_flat_head(&chain.get_block_hash(2), true).unwrap();
+        let (block, alice_footprint) =
+            flat_storage.get_account_storage_footprint(&alice).unwrap().unwrap();
+        assert_eq!(block, chain.get_block(2));
+        assert_eq!(
+            alice_footprint,
+            AccountStorageFootprint {
+                code_bytes: 20,
+                contract_data_bytes: data_key1.len() as u64 + data_key2.len() as u64 + 9,
+                contract_data_records: 2,
+                ..Default::default()
+            }
+        );
+        let (_, bob_footprint) = flat_storage.get_account_storage_footprint(&bob).unwrap().unwrap();
+        assert_eq!(bob_footprint, AccountStorageFootprint::default());
+        assert_eq!(
+            footprint::get_footprints_head(&store, shard_uid).unwrap(),
+            Some(chain.get_block_hash(2))
+        );
+
+        // 4. Rebuilding from flat state while flat head moves gives the same
+        //    result as updating footprints incrementally.
+        let mut store_update = store.store_update();
+        footprint::remove_all_footprints(&mut store_update, shard_uid);
+        store_update.commit().unwrap();
+        let flat_storage = FlatStorage::new(store.clone(), shard_uid, options).unwrap();
+        flat_storage.update_flat_head(&chain.get_block_hash(3), true).unwrap();
+        let (block, footprint) = wait_for_footprint(&flat_storage, &alice);
+        assert_eq!(block, chain.get_block(3));
+        assert_eq!(
+            footprint,
+            AccountStorageFootprint {
+                code_bytes: 20,
+                contract_data_bytes: data_key2.len() as u64 + 4,
+                contract_data_records: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            footprint::get_account_storage_footprint(&store, shard_uid, &bob).unwrap(),
+            None
+        );
+        assert_eq!(
+            footprint::get_footprints_head(&store, shard_uid).unwrap(),
+            Some(chain.get_block_hash(3))
+        );
+
+        // 5. Footprints are not available if they are not maintained.
+        let flat_storage_manager = FlatStorageManager::new(store.clone());
+        flat_storage_manager.create_flat_storage_for_shard(shard_uid).unwrap();
+        let flat_storage = flat_storage_manager.get_flat_storage_for_shard(shard_uid).unwrap();
+        assert_eq!(flat_storage.get_account_storage_footprint(&alice), Ok(None));
+    }
+
     #[test]
     fn flat_storage_sanity() {
         // 1. Create a chain with 10 blocks with no forks. Set flat head to be at block 0.
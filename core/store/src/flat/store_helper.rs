@@ -6,7 +6,9 @@ use super::types::{
     FlatStateIterator, FlatStateValuesInliningMigrationStatus, FlatStorageResult, FlatStorageStatus,
 };
 use crate::db::FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY;
-use crate::flat::delta::{BlockWithChangesInfo, FlatStateChanges, KeyForFlatStateDelta};
+use crate::flat::delta::{
+    BlockWithChangesInfo, FlatStateChanges, FlatStateHistoryEntry, KeyForFlatStateDelta,
+};
 use crate::flat::types::FlatStorageError;
 use crate::flat::FlatStorageReadyStatus;
 use crate::{DBCol, Store, StoreUpdate};
@@ -15,6 +17,7 @@ use std::io;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::shard_layout::ShardUId;
 use unc_primitives::state::FlatStateValue;
+use unc_primitives::types::BlockHeight;
 
 pub fn get_delta_changes(
     store: &Store,
@@ -122,6 +125,68 @@ pub fn remove_all_deltas(store_update: &mut StoreUpdate, shard_uid: ShardUId) {
     remove_range_by_shard_uid(store_update, shard_uid, DBCol::FlatStateDeltaMetadata);
 }
 
+fn encode_flat_state_history_key(shard_uid: ShardUId, height: BlockHeight) -> [u8; 16] {
+    let mut res = [0; 16];
+    res[..8].copy_from_slice(&shard_uid.to_bytes());
+    res[8..].copy_from_slice(&height.to_be_bytes());
+    res
+}
+
+pub fn set_flat_state_history_entry(
+    store_update: &mut StoreUpdate,
+    shard_uid: ShardUId,
+    entry: &FlatStateHistoryEntry,
+) {
+    let key = encode_flat_state_history_key(shard_uid, entry.block.height);
+    store_update
+        .set_ser(DBCol::FlatStateHistory, &key, entry)
+        .expect("Borsh should not have failed here");
+}
+
+/// Returns history entries with heights in `[from_height, to_height]` in increasing order.
+pub fn get_flat_state_history_entries(
+    store: &Store,
+    shard_uid: ShardUId,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> FlatStorageResult<Vec<FlatStateHistoryEntry>> {
+    let key_from = encode_flat_state_history_key(shard_uid, from_height);
+    let key_to = match to_height.checked_add(1) {
+        Some(height) => encode_flat_state_history_key(shard_uid, height).to_vec(),
+        None => ShardUId::next_shard_prefix(&shard_uid.to_bytes()).to_vec(),
+    };
+    store
+        .iter_range(DBCol::FlatStateHistory, Some(&key_from), Some(&key_to))
+        .map(|res| {
+            let (_, value) = res.map_err(|err| {
+                FlatStorageError::StorageInternalError(format!(
+                    "FlatStateHistory iterator error: {err}"
+                ))
+            })?;
+            FlatStateHistoryEntry::try_from_slice(&value).map_err(|err| {
+                FlatStorageError::StorageInternalError(format!(
+                    "invalid FlatStateHistory value format: {err}"
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Removes history entries with heights below `height`.
+pub fn remove_flat_state_history_below(
+    store_update: &mut StoreUpdate,
+    shard_uid: ShardUId,
+    height: BlockHeight,
+) {
+    let key_from = encode_flat_state_history_key(shard_uid, 0);
+    let key_to = encode_flat_state_history_key(shard_uid, height);
+    store_update.delete_range(DBCol::FlatStateHistory, &key_from, &key_to);
+}
+
+pub fn remove_all_flat_state_history(store_update: &mut StoreUpdate, shard_uid: ShardUId) {
+    remove_range_by_shard_uid(store_update, shard_uid, DBCol::FlatStateHistory);
+}
+
 pub fn remove_all_flat_state_values(store_update: &mut StoreUpdate, shard_uid: ShardUId) {
     remove_range_by_shard_uid(store_update, shard_uid, DBCol::FlatState);
 }
@@ -17,6 +17,17 @@ impl BlockInfo {
     }
 }
 
+/// Optional features of flat storage, configured in `StoreConfig`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FlatStorageOptions {
+    /// Number of blocks behind flat head for which `FlatStateHistory` entries
+    /// are kept. Zero disables history.
+    pub history_window: BlockHeight,
+    /// If set, `AccountStorageFootprint` index is maintained and this many
+    /// largest accounts of each shard are exported as metrics.
+    pub account_footprints_top_n: Option<usize>,
+}
+
 #[derive(strum::AsRefStr, strum::Display, Debug, PartialEq, Eq, thiserror::Error)]
 pub enum FlatStorageError {
     /// This means we can't find a path from `flat_head` to the block. Includes
@@ -23,10 +23,12 @@ use unc_fmt::{AbbrBytes, StorageKey};
 use unc_primitives::account::{AccessKey, Account};
 pub use unc_primitives::errors::{MissingTrieValueContext, StorageError};
 use unc_primitives::hash::CryptoHash;
-use unc_primitives::receipt::{DelayedReceiptIndices, Receipt, ReceivedData};
+use unc_primitives::receipt::{
+    DelayedReceiptIndices, PromiseYieldIndices, PromiseYieldTimeout, Receipt, ReceivedData,
+};
 pub use unc_primitives::shard_layout::ShardUId;
 use unc_primitives::trie_key::{trie_key_parsers, TrieKey};
-use unc_primitives::types::{AccountId, StateRoot};
+use unc_primitives::types::{AccountId, BlockHeight, StateRoot};
 use unc_vm_runner::logic::{CompiledContract, CompiledContractCache};
 use unc_vm_runner::ContractCode;
 
@@ -120,14 +122,11 @@ impl NodeStorage {
         StoreOpener::new(home_dir, archive, config, cold_config)
     }
 
-    /// Constructs new object backed by given database.
-    fn from_rocksdb(
-        hot_storage: crate::db::RocksDB,
-        cold_storage: Option<crate::db::RocksDB>,
+    /// Constructs new object backed by given databases.
+    fn from_databases(
+        hot_storage: Arc<dyn Database>,
+        cold_storage: Option<Arc<dyn Database>>,
     ) -> Self {
-        let hot_storage = Arc::new(hot_storage);
-        let cold_storage = cold_storage.map(|storage| Arc::new(storage));
-
         let cold_db = if let Some(cold_storage) = cold_storage {
             Some(Arc::new(crate::db::ColdDB::new(cold_storage)))
         } else {
@@ -328,6 +327,17 @@ impl Store {
         self.storage.iter_range(col, lower_bound, upper_bound)
     }
 
+    /// Iterates over a range of keys fetching raw key/value pairs, see
+    /// [`Self::iter_raw_bytes`].  Upper bound key is not included.
+    pub fn iter_range_raw_bytes<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        self.storage.iter_range_raw_bytes(col, lower_bound, upper_bound)
+    }
+
     pub fn iter_prefix_ser<'a, T: BorshDeserialize>(
         &'a self,
         col: DBCol,
@@ -389,6 +399,14 @@ impl Store {
     pub fn get_store_statistics(&self) -> Option<StoreStatistics> {
         self.storage.get_store_statistics()
     }
+
+    /// Creates a consistent on-disk checkpoint of the storage at `path`.
+    ///
+    /// The directory must not exist yet.  Fails if the storage does not
+    /// support checkpoints.
+    pub fn create_checkpoint(&self, path: &Path) -> anyhow::Result<()> {
+        self.storage.create_checkpoint(path)
+    }
 }
 
 impl Store {
@@ -744,6 +762,67 @@ pub fn set_delayed_receipt(
         .expect("Next available index for delayed receipt exceeded the integer limit");
 }
 
+pub fn get_promise_yield_indices(
+    trie: &dyn TrieAccess,
+) -> Result<PromiseYieldIndices, StorageError> {
+    Ok(get(trie, &TrieKey::PromiseYieldIndices)?.unwrap_or_default())
+}
+
+// Adds the given timeout into the end of the PromiseYield timeouts queue in the state.
+pub fn enqueue_promise_yield_timeout(
+    state_update: &mut TrieUpdate,
+    timeout: &PromiseYieldTimeout,
+) -> Result<(), StorageError> {
+    let mut promise_yield_indices = get_promise_yield_indices(state_update)?;
+    set(
+        state_update,
+        TrieKey::PromiseYieldTimeout { index: promise_yield_indices.next_available_index },
+        timeout,
+    );
+    promise_yield_indices.next_available_index =
+        promise_yield_indices.next_available_index.checked_add(1).ok_or_else(|| {
+            StorageError::StorageInconsistentState(
+                "Next available index for PromiseYield timeout exceeded the integer limit"
+                    .to_string(),
+            )
+        })?;
+    set(state_update, TrieKey::PromiseYieldIndices, &promise_yield_indices);
+    Ok(())
+}
+
+pub fn set_promise_yield_receipt(
+    state_update: &mut TrieUpdate,
+    receiver_id: &AccountId,
+    data_id: CryptoHash,
+    expires_at: BlockHeight,
+) {
+    set(
+        state_update,
+        TrieKey::PromiseYieldReceipt { receiver_id: receiver_id.clone(), data_id },
+        &expires_at,
+    );
+}
+
+pub fn remove_promise_yield_receipt(
+    state_update: &mut TrieUpdate,
+    receiver_id: &AccountId,
+    data_id: CryptoHash,
+) {
+    state_update.remove(TrieKey::PromiseYieldReceipt { receiver_id: receiver_id.clone(), data_id });
+}
+
+pub fn has_promise_yield_receipt(
+    trie: &dyn TrieAccess,
+    receiver_id: &AccountId,
+    data_id: CryptoHash,
+) -> Result<bool, StorageError> {
+    Ok(get::<BlockHeight>(
+        trie,
+        &TrieKey::PromiseYieldReceipt { receiver_id: receiver_id.clone(), data_id },
+    )?
+    .is_some())
+}
+
 pub fn set_access_key(
     state_update: &mut TrieUpdate,
     account_id: AccountId,
@@ -826,6 +905,10 @@ pub fn set_code(state_update: &mut TrieUpdate, account_id: AccountId, code: &Con
     state_update.set(TrieKey::ContractCode { account_id }, code.code().to_vec());
 }
 
+pub fn remove_code(state_update: &mut TrieUpdate, account_id: &AccountId) {
+    state_update.remove(TrieKey::ContractCode { account_id: account_id.clone() });
+}
+
 pub fn get_code(
     trie: &dyn TrieAccess,
     account_id: &AccountId,
@@ -835,6 +918,41 @@ pub fn get_code(
     trie.get(&key).map(|opt| opt.map(|code| ContractCode::new(code, code_hash)))
 }
 
+pub fn set_global_code(state_update: &mut TrieUpdate, code: &ContractCode) {
+    state_update.set(TrieKey::GlobalContractCode { code_hash: *code.hash() }, code.code().to_vec());
+}
+
+pub fn get_global_code(
+    trie: &dyn TrieAccess,
+    code_hash: CryptoHash,
+) -> Result<Option<ContractCode>, StorageError> {
+    let key = TrieKey::GlobalContractCode { code_hash };
+    trie.get(&key).map(|opt| opt.map(|code| ContractCode::new(code, Some(code_hash))))
+}
+
+/// Checks whether a global contract with the given hash exists, without reading its code.
+pub fn has_global_code(
+    state_update: &TrieUpdate,
+    code_hash: CryptoHash,
+) -> Result<bool, StorageError> {
+    let key = TrieKey::GlobalContractCode { code_hash };
+    Ok(state_update.get_ref(&key, KeyLookupMode::FlatStorage)?.is_some())
+}
+
+/// Returns the code the given account runs: the code deployed on the account itself or, if
+/// there is none, the global contract code the account refers to with its `code_hash`.
+pub fn get_code_or_global_code(
+    trie: &dyn TrieAccess,
+    account_id: &AccountId,
+    code_hash: CryptoHash,
+) -> Result<Option<ContractCode>, StorageError> {
+    match get_code(trie, account_id, Some(code_hash))? {
+        Some(code) => Ok(Some(code)),
+        None if code_hash == CryptoHash::default() => Ok(None),
+        None => get_global_code(trie, code_hash),
+    }
+}
+
 /// Removes account, code and all access keys associated to it.
 pub fn remove_account(
     state_update: &mut TrieUpdate,
@@ -469,6 +469,14 @@ pub mod flat_state_metrics {
         )
         .unwrap()
     });
+    pub static ACCOUNT_STORAGE_FOOTPRINT_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
+        try_create_int_gauge_vec(
+            "unc_account_storage_footprint_bytes",
+            "State bytes attributed to the largest accounts at flat storage head, by kind of record",
+            &["shard_id", "account_id", "category"],
+        )
+        .unwrap()
+    });
 
     pub mod inlining_migration {
         use once_cell::sync::Lazy;
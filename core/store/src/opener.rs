@@ -1,5 +1,7 @@
+use crate::config::StoreBackend;
 use crate::db::rocksdb::snapshot::{Snapshot, SnapshotError, SnapshotRemoveError};
 use crate::db::rocksdb::RocksDB;
+use crate::db::{Database, RedbDB, SegmentDB};
 use crate::metadata::{DbKind, DbMetadata, DbVersion, DB_VERSION};
 use crate::{DBCol, DBTransaction, Mode, NodeStorage, Store, StoreConfig, Temperature};
 use std::sync::Arc;
@@ -280,7 +282,7 @@ impl<'a> StoreOpener<'a> {
             .transpose()?
             .map(|(db, _)| db);
 
-        let storage = NodeStorage::from_rocksdb(hot_db, cold_db);
+        let storage = NodeStorage::from_databases(hot_db, cold_db);
 
         hot_snapshot.remove()?;
         cold_snapshot.remove()?;
@@ -339,8 +341,7 @@ impl<'a> StoreOpener<'a> {
             None if mode.can_create() => {
                 tracing::info!(target: "db_opener", path=%opener.path.display(), "The database doesn't exist, creating it.");
 
-                let db = opener.create()?;
-                let store = Store { storage: Arc::new(db) };
+                let store = Store { storage: opener.create()? };
                 store.set_db_version(DB_VERSION)?;
                 return Ok(());
             }
@@ -468,13 +469,13 @@ impl<'a> StoreOpener<'a> {
         version: DbVersion,
     ) -> Result<Store, StoreOpenerError> {
         let (db, _) = opener.open(mode, version)?;
-        let store = Store { storage: Arc::new(db) };
+        let store = Store { storage: db };
         Ok(store)
     }
 
     fn open_store_unsafe(mode: Mode, opener: &DBOpener) -> Result<Store, StoreOpenerError> {
         let db = opener.open_unsafe(mode)?;
-        let store = Store { storage: Arc::new(db) };
+        let store = Store { storage: db };
         Ok(store)
     }
 }
@@ -500,7 +501,13 @@ impl<'a> DBOpener<'a> {
     /// introduced, the kind is returned as `None`.  Otherwise, it’s also
     /// fetched and if it’s not there error is returned.
     fn get_metadata(&self) -> std::io::Result<Option<DbMetadata>> {
-        RocksDB::get_metadata(&self.path, self.config)
+        if self.is_segment_store() {
+            return SegmentDB::get_metadata(&self.path, self.config);
+        }
+        match self.config.backend {
+            StoreBackend::RocksDB => RocksDB::get_metadata(&self.path, self.config),
+            StoreBackend::Redb => RedbDB::get_metadata(&self.path, self.config),
+        }
     }
 
     /// Opens the database in given mode checking expected version and kind.
@@ -519,9 +526,13 @@ impl<'a> DBOpener<'a> {
     /// new version.
     ///
     /// Use [`Self::create`] to create a new database.
-    fn open(&self, mode: Mode, want_version: DbVersion) -> std::io::Result<(RocksDB, DbMetadata)> {
-        let db = RocksDB::open(&self.path, &self.config, mode, self.temp)?;
-        let metadata = DbMetadata::read(&db)?;
+    fn open(
+        &self,
+        mode: Mode,
+        want_version: DbVersion,
+    ) -> std::io::Result<(Arc<dyn Database>, DbMetadata)> {
+        let db = self.open_unsafe(mode)?;
+        let metadata = DbMetadata::read(db.as_ref())?;
         if want_version != metadata.version {
             let msg = format!("unexpected DbVersion {}; expected {want_version}", metadata.version);
             Err(std::io::Error::other(msg))
@@ -534,20 +545,40 @@ impl<'a> DBOpener<'a> {
     ///
     /// This is only suitable when creating the database or setting the version
     /// and kind for the first time.
-    fn open_unsafe(&self, mode: Mode) -> std::io::Result<RocksDB> {
-        let db = RocksDB::open(&self.path, &self.config, mode, self.temp)?;
-        Ok(db)
+    fn open_unsafe(&self, mode: Mode) -> std::io::Result<Arc<dyn Database>> {
+        if self.is_segment_store() {
+            return Ok(Arc::new(SegmentDB::open(&self.path, self.config, mode)?));
+        }
+        match self.config.backend {
+            StoreBackend::RocksDB => {
+                Ok(Arc::new(RocksDB::open(&self.path, &self.config, mode, self.temp)?))
+            }
+            StoreBackend::Redb => Ok(Arc::new(RedbDB::open(&self.path, self.config, mode)?)),
+        }
     }
 
     /// Creates a new database.
-    fn create(&self) -> std::io::Result<RocksDB> {
-        RocksDB::open(&self.path, &self.config, Mode::Create, self.temp)
+    fn create(&self) -> std::io::Result<Arc<dyn Database>> {
+        self.open_unsafe(Mode::Create)
     }
 
     /// Creates a new snapshot for the database.
+    ///
+    /// Snapshots rely on RocksDB checkpoints so none is created for databases
+    /// served from segment files (sealed segments are immutable anyway) or
+    /// kept in other backends.
     fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        if self.is_segment_store() || self.config.backend != StoreBackend::RocksDB {
+            return Ok(Snapshot::none());
+        }
         Snapshot::new(&self.path, &self.config, self.temp)
     }
+
+    /// Returns whether the database is served from segment files rather than
+    /// RocksDB.  This is only supported for the cold database.
+    fn is_segment_store(&self) -> bool {
+        self.temp == Temperature::Cold && self.config.segment_store.is_some()
+    }
 }
 
 pub trait StoreMigrator {
@@ -599,8 +630,10 @@ pub fn checkpoint_hot_storage_and_cleanup_columns(
         .create_checkpoint(&checkpoint_path)
         .map_err(StoreOpenerError::CheckpointError)?;
 
-    // As only path from config is used in StoreOpener, default config with custom path will do.
+    // As only path and backend from config are used in StoreOpener, default
+    // config with custom path will do.
     let mut config = StoreConfig::default();
+    config.backend = checkpoint_backend(&checkpoint_path);
     config.path = Some(checkpoint_path);
     let archive = hot_store.get_db_kind()? == Some(DbKind::Archive);
     let opener = StoreOpener::new(checkpoint_base_path, archive, &config, None);
@@ -631,6 +664,17 @@ pub fn checkpoint_hot_storage_and_cleanup_columns(
     Ok(node_storage)
 }
 
+/// Returns the backend of a checkpoint created with
+/// [`Database::create_checkpoint`] so that it can be opened without knowing
+/// the configuration of the database it was created from.
+pub(crate) fn checkpoint_backend(path: &std::path::Path) -> StoreBackend {
+    if RedbDB::exists(path) {
+        StoreBackend::Redb
+    } else {
+        StoreBackend::RocksDB
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,166 @@
+//! Selection of state kept for archived accounts, see
+//! [`unc_chain_configs::GCConfig::archived_accounts`].
+
+use std::collections::HashSet;
+use std::io;
+use std::sync::Arc;
+
+use borsh::BorshDeserialize;
+use unc_chain_configs::AccountPattern;
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::trie_key::col;
+
+use super::{NibbleSlice, RawTrieNode, RawTrieNodeWithSize};
+
+/// Trie columns whose keys start with an account id.
+const ACCOUNT_COLUMNS: [u8; 10] = [
+    col::ACCOUNT,
+    col::CONTRACT_CODE,
+    col::ACCESS_KEY,
+    col::RECEIVED_DATA,
+    col::POSTPONED_RECEIPT_ID,
+    col::PENDING_DATA_COUNT,
+    col::POSTPONED_RECEIPT,
+    col::CONTRACT_DATA,
+    col::RSA2048_KEY,
+    col::PROMISE_YIELD_RECEIPT,
+];
+
+/// Returns whether raw trie key belongs to an account matching one of the
+/// patterns.
+pub fn is_archived_trie_key(patterns: &[AccountPattern], raw_key: &[u8]) -> bool {
+    let Some((column, rest)) = raw_key.split_first() else { return false };
+    if !ACCOUNT_COLUMNS.contains(column) {
+        return false;
+    }
+    // All separators used after the account id are bytes which cannot appear
+    // in an account id.
+    let len = rest
+        .iter()
+        .position(|&b| !matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.'))
+        .unwrap_or(rest.len());
+    let Ok(account_id) = std::str::from_utf8(&rest[..len]) else { return false };
+    patterns.iter().any(|pattern| pattern.matches(account_id))
+}
+
+/// Returns whether a subtree at given nibble path may contain keys of an
+/// account matching one of the patterns.
+fn may_contain_archived_keys(patterns: &[AccountPattern], path: &[u8]) -> bool {
+    ACCOUNT_COLUMNS.iter().any(|&column| {
+        patterns.iter().any(|pattern| {
+            let prefix = std::iter::once(column).chain(pattern.prefix().bytes());
+            prefix.flat_map(|byte| [byte >> 4, byte & 0x0f]).zip(path.iter()).all(|(a, &b)| a == b)
+        })
+    })
+}
+
+/// Walks the trie with given root and returns those of `candidates` which are
+/// needed to look up state of accounts matching `patterns`.
+///
+/// `candidates` are nodes and values whose refcount is changed by a single
+/// block, i.e. insertions or deletions of its `TrieChanges`.  A node which
+/// isn’t a candidate has its whole subtree unchanged so the walk never
+/// descends below it and its cost is proportional to the size of the change
+/// rather than the size of the state.  `get_node` is called for candidate
+/// nodes only.
+pub fn archived_trie_nodes(
+    root: &CryptoHash,
+    patterns: &[AccountPattern],
+    candidates: &HashSet<CryptoHash>,
+    mut get_node: impl FnMut(&CryptoHash) -> io::Result<Arc<[u8]>>,
+) -> io::Result<HashSet<CryptoHash>> {
+    let mut retained = HashSet::new();
+    if patterns.is_empty() || !candidates.contains(root) {
+        return Ok(retained);
+    }
+    let mut stack: Vec<(CryptoHash, Vec<u8>)> = vec![(*root, Vec::new())];
+    while let Some((hash, path)) = stack.pop() {
+        if !may_contain_archived_keys(patterns, &path) {
+            continue;
+        }
+        let bytes = get_node(&hash)?;
+        let node = RawTrieNodeWithSize::try_from_slice(&bytes)?.node;
+        retained.insert(hash);
+        let mut retain_value = |key: &[u8], value_hash: CryptoHash| {
+            if key.len() % 2 == 0
+                && candidates.contains(&value_hash)
+                && is_archived_trie_key(patterns, &NibbleSlice::nibbles_to_bytes(key))
+            {
+                retained.insert(value_hash);
+            }
+        };
+        let (value, children) = match node {
+            RawTrieNode::Leaf(key, value) => {
+                let (key, _) = NibbleSlice::from_encoded(&key);
+                let mut key_path = path;
+                key_path.extend(key.iter());
+                retain_value(&key_path, value.hash);
+                continue;
+            }
+            RawTrieNode::Extension(key, child) => {
+                if candidates.contains(&child) {
+                    let (key, _) = NibbleSlice::from_encoded(&key);
+                    let mut child_path = path;
+                    child_path.extend(key.iter());
+                    stack.push((child, child_path));
+                }
+                continue;
+            }
+            RawTrieNode::BranchNoValue(children) => (None, children),
+            RawTrieNode::BranchWithValue(value, children) => (Some(value), children),
+        };
+        if let Some(value) = value {
+            retain_value(&path, value.hash);
+        }
+        for (index, child) in children.iter() {
+            if candidates.contains(child) {
+                let mut child_path = path.clone();
+                child_path.push(index);
+                stack.push((*child, child_path));
+            }
+        }
+    }
+    Ok(retained)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unc_primitives::trie_key::TrieKey;
+
+    fn patterns(patterns: &[&str]) -> Vec<AccountPattern> {
+        patterns.iter().map(|pattern| pattern.to_string().try_into().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_is_archived_trie_key() {
+        let patterns = patterns(&["alice.unc", "app.*"]);
+        let key = |account_id: &str| {
+            TrieKey::ContractData { account_id: account_id.parse().unwrap(), key: b"key".to_vec() }
+                .to_vec()
+        };
+        assert!(is_archived_trie_key(&patterns, &key("alice.unc")));
+        assert!(is_archived_trie_key(&patterns, &key("app.explorer.unc")));
+        assert!(!is_archived_trie_key(&patterns, &key("alice.unc.sub")));
+        assert!(!is_archived_trie_key(&patterns, &key("bob.unc")));
+        let account = TrieKey::Account { account_id: "alice.unc".parse().unwrap() };
+        assert!(is_archived_trie_key(&patterns, &account.to_vec()));
+        assert!(!is_archived_trie_key(&patterns, &TrieKey::DelayedReceiptIndices.to_vec()));
+    }
+
+    #[test]
+    fn test_may_contain_archived_keys() {
+        let patterns = patterns(&["alice.unc"]);
+        let nibbles = |bytes: &[u8]| -> Vec<u8> {
+            bytes.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+        };
+        assert!(may_contain_archived_keys(&patterns, &[]));
+        assert!(may_contain_archived_keys(&patterns, &nibbles(&[col::CONTRACT_DATA, b'a'])));
+        assert!(may_contain_archived_keys(&patterns, &nibbles(b"\x09alice.unc,key")));
+        assert!(!may_contain_archived_keys(&patterns, &nibbles(&[col::CONTRACT_DATA, b'b'])));
+        assert!(!may_contain_archived_keys(
+            &patterns,
+            &nibbles(&[col::DELAYED_RECEIPT_OR_INDICES])
+        ));
+    }
+}
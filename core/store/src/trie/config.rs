@@ -1,5 +1,7 @@
 use crate::config::TrieCacheConfig;
+use crate::flat::FlatStorageOptions;
 use crate::StoreConfig;
+use std::path::PathBuf;
 use unc_primitives::shard_layout::ShardUId;
 /// Default memory limit, if nothing else is configured.
 /// It is chosen to correspond roughly to the old limit, which was
@@ -26,6 +28,10 @@ pub struct TrieConfig {
     /// List of shards we will load into memory.
     pub load_mem_tries_for_shards: Vec<ShardUId>,
     pub load_mem_tries_for_all_shards: bool,
+    /// Directory memtrie snapshots are saved to and loaded from, if enabled.
+    /// Unlike `StoreConfig::mem_trie_snapshot_path`, this is resolved already.
+    pub mem_trie_snapshot_dir: Option<PathBuf>,
+    pub flat_storage_options: FlatStorageOptions,
 }
 
 impl TrieConfig {
@@ -39,6 +45,12 @@ impl TrieConfig {
         this.enable_receipt_prefetching = config.enable_receipt_prefetching;
         this.load_mem_tries_for_shards.clone_from(&config.load_mem_tries_for_shards);
         this.load_mem_tries_for_all_shards = config.load_mem_tries_for_all_shards;
+        this.flat_storage_options = FlatStorageOptions {
+            history_window: config.flat_storage_history_window,
+            account_footprints_top_n: config
+                .account_storage_footprints
+                .then_some(config.account_storage_footprints_top_n),
+        };
 
         this
     }
@@ -1,7 +1,8 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use unc_o11y::metrics::IntGauge;
 
 use super::metrics::MEM_TRIE_ARENA_ACTIVE_ALLOCS_COUNT;
-use super::{ArenaMemory, ArenaPos, ArenaSliceMut};
+use super::{ArenaChunk, ArenaMemory, ArenaPos, ArenaSliceMut};
 use crate::trie::mem::arena::metrics::{
     MEM_TRIE_ARENA_ACTIVE_ALLOCS_BYTES, MEM_TRIE_ARENA_MEMORY_USAGE_BYTES,
 };
@@ -43,10 +44,20 @@ pub struct Allocator {
     memory_usage_gauge: IntGauge,
 }
 
+/// The part of `Allocator` that is saved in memtrie snapshots, which together
+/// with the arena memory is enough to continue allocating where we left off.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct AllocatorState {
+    freelists: Vec<ArenaPos>,
+    next_alloc_pos: ArenaPos,
+    active_allocs_bytes: u64,
+    active_allocs_count: u64,
+}
+
 const MAX_ALLOC_SIZE: usize = 16 * 1024;
 const ROUND_UP_TO_8_BYTES_UNDER: usize = 256;
 const ROUND_UP_TO_64_BYTES_UNDER: usize = 1024;
-const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+pub(crate) const CHUNK_SIZE: usize = 4 * 1024 * 1024;
 
 /// Calculates the allocation class (an index from 0 to NUM_ALLOCATION_CLASSES)
 /// for the given size that we wish to allocate.
@@ -89,10 +100,56 @@ impl Allocator {
         }
     }
 
+    /// Recreates the allocator from state saved in a memtrie snapshot.
+    /// Positions in the state are checked to lie within the arena.
+    pub fn from_state(
+        name: String,
+        state: AllocatorState,
+        arena: &ArenaMemory,
+    ) -> Result<Self, String> {
+        let in_arena = |pos: ArenaPos, len: usize| {
+            pos.is_invalid()
+                || arena.chunks.get(pos.chunk()).is_some_and(|chunk| pos.pos() + len <= chunk.len())
+        };
+        let freelists = <[ArenaPos; NUM_ALLOCATION_CLASSES]>::try_from(state.freelists).map_err(
+            |freelists| {
+                format!("expected {NUM_ALLOCATION_CLASSES} freelists, got {}", freelists.len())
+            },
+        )?;
+        if let Some(pos) = freelists.iter().find(|pos| !in_arena(**pos, ArenaPos::SERIALIZED_SIZE))
+        {
+            return Err(format!("freelist head {pos} lies outside of the arena"));
+        }
+        if !in_arena(state.next_alloc_pos, 0) {
+            return Err(format!(
+                "next allocation {} lies outside of the arena",
+                state.next_alloc_pos
+            ));
+        }
+        let mut this = Self::new(name);
+        this.freelists = freelists;
+        this.next_alloc_pos = state.next_alloc_pos;
+        this.active_allocs_bytes = state.active_allocs_bytes as usize;
+        this.active_allocs_count = state.active_allocs_count as usize;
+        this.active_allocs_bytes_gauge.set(this.active_allocs_bytes as i64);
+        this.active_allocs_count_gauge.set(this.active_allocs_count as i64);
+        this.memory_usage_gauge.set(arena.chunks.len() as i64 * CHUNK_SIZE as i64);
+        Ok(this)
+    }
+
+    pub fn state(&self) -> AllocatorState {
+        AllocatorState {
+            freelists: self.freelists.to_vec(),
+            next_alloc_pos: self.next_alloc_pos,
+            active_allocs_bytes: self.active_allocs_bytes as u64,
+            active_allocs_count: self.active_allocs_count as u64,
+        }
+    }
+
     /// Adds a new chunk to the arena, and updates the next_alloc_pos to the beginning of
     /// the new chunk.
     fn new_chunk(&mut self, arena: &mut ArenaMemory) {
-        arena.chunks.push(vec![0; CHUNK_SIZE]);
+        arena.chunks.push(ArenaChunk::Heap(vec![0; CHUNK_SIZE]));
         self.next_alloc_pos =
             ArenaPos { chunk: u32::try_from(arena.chunks.len() - 1).unwrap(), pos: 0 };
         self.memory_usage_gauge.set(arena.chunks.len() as i64 * CHUNK_SIZE as i64);
@@ -1,10 +1,12 @@
 mod alloc;
 mod metrics;
 use self::alloc::Allocator;
+pub(crate) use self::alloc::{AllocatorState, CHUNK_SIZE};
 use borsh::{BorshDeserialize, BorshSerialize};
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
 use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
 
 use super::flexible_data::encoding::BorshFixedSize;
 
@@ -26,7 +28,37 @@ pub struct Arena {
 /// or `ArenaSlice` (range of bytes) to read the actual memory, and the
 /// mutable versions `ArenaPtrMut` and `ArenaSliceMut` to write memory.
 pub struct ArenaMemory {
-    chunks: Vec<Vec<u8>>,
+    chunks: Vec<ArenaChunk>,
+}
+
+/// A chunk of arena memory.
+///
+/// Chunks are allocated on the heap, except when the arena is loaded from
+/// a memtrie snapshot, in which case the chunks are private copy-on-write
+/// mappings of the snapshot file.  Writes to such chunks never reach the file.
+pub(crate) enum ArenaChunk {
+    Heap(Vec<u8>),
+    Mapped(memmap2::MmapMut),
+}
+
+impl Deref for ArenaChunk {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Heap(chunk) => chunk,
+            Self::Mapped(chunk) => chunk,
+        }
+    }
+}
+
+impl DerefMut for ArenaChunk {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Self::Heap(chunk) => chunk,
+            Self::Mapped(chunk) => chunk,
+        }
+    }
 }
 
 #[derive(
@@ -122,6 +154,30 @@ impl Arena {
         Self { memory: ArenaMemory::new(), allocator: Allocator::new(name) }
     }
 
+    /// Recreates an arena from chunks and allocator state saved in a memtrie
+    /// snapshot.  Fails if the allocator state doesn’t fit the chunks.
+    pub(crate) fn from_snapshot(
+        name: String,
+        chunks: Vec<ArenaChunk>,
+        state: AllocatorState,
+    ) -> Result<Self, String> {
+        let memory = ArenaMemory { chunks };
+        let allocator = Allocator::from_state(name, state, &memory)?;
+        Ok(Self { memory, allocator })
+    }
+
+    /// Returns the allocator state to be saved in a memtrie snapshot along
+    /// with the chunks.
+    pub(crate) fn allocator_state(&self) -> AllocatorState {
+        self.allocator.state()
+    }
+
+    /// Returns the chunks of memory, in order, for saving in a memtrie
+    /// snapshot.
+    pub(crate) fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.memory.chunks.iter().map(|chunk| &chunk[..])
+    }
+
     /// Allocates a slice of the given size in the arena.
     pub fn alloc<'a>(&'a mut self, size: usize) -> ArenaSliceMut<'a> {
         self.allocator.allocate(&mut self.memory, size)
@@ -325,8 +381,8 @@ mod tests {
     #[test]
     fn test_arena_ptr_and_slice() {
         let mut arena = super::ArenaMemory::new();
-        arena.chunks.push(vec![0; 1000]);
-        arena.chunks.push(vec![0; 1000]);
+        arena.chunks.push(super::ArenaChunk::Heap(vec![0; 1000]));
+        arena.chunks.push(super::ArenaChunk::Heap(vec![0; 1000]));
 
         let chunk1 = ArenaPos { chunk: 1, pos: 0 };
 
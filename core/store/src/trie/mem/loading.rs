@@ -1,4 +1,5 @@
 use super::node::MemTrieNodeId;
+use super::snapshot::load_mem_trie_snapshot;
 use super::MemTries;
 use crate::flat::store_helper::{
     decode_flat_state_db_key, get_all_deltas_metadata, get_delta_changes, get_flat_storage_status,
@@ -9,8 +10,9 @@ use crate::trie::mem::updating::apply_memtrie_changes;
 use crate::{DBCol, Store};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::collections::BTreeSet;
+use std::path::Path;
 use std::time::Instant;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use unc_primitives::errors::StorageError;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::shard_layout::{get_block_shard_uid, ShardUId};
@@ -113,9 +115,14 @@ fn get_state_root(
 /// deltas. The returned tries would contain a root for each block that the
 /// flat storage currently has, i.e. one for the final block, and one for each
 /// block that flat storage has a delta for, possibly in more than one fork.
+///
+/// If `snapshot_dir` is given and holds a memtrie snapshot of the shard which
+/// contains the trie at the flat storage head, the base trie is mapped from the
+/// snapshot rather than built from flat state.  See [`super::snapshot`].
 pub fn load_trie_from_flat_state_and_delta(
     store: &Store,
     shard_uid: ShardUId,
+    snapshot_dir: Option<&Path>,
 ) -> Result<MemTries, StorageError> {
     debug!(target: "memtrie", %shard_uid, "Loading base trie from flat state...");
     let flat_head = match get_flat_storage_status(&store, shard_uid)? {
@@ -128,13 +135,27 @@ pub fn load_trie_from_flat_state_and_delta(
         }
     };
 
-    let mut mem_tries = load_trie_from_flat_state(
-        &store,
-        shard_uid,
-        get_state_root(store, flat_head.hash, shard_uid)?,
-        flat_head.height,
-    )
-    .unwrap();
+    let state_root = get_state_root(store, flat_head.hash, shard_uid)?;
+    let snapshot = snapshot_dir.filter(|_| state_root != CryptoHash::default()).and_then(|dir| {
+        match load_mem_trie_snapshot(dir, shard_uid, state_root, flat_head.height) {
+            Ok(Some(mem_tries)) => {
+                info!(target: "memtrie", %shard_uid, "Loaded base trie from memtrie snapshot");
+                Some(mem_tries)
+            }
+            Ok(None) => {
+                info!(target: "memtrie", %shard_uid, %state_root, "No memtrie snapshot with the flat head state root");
+                None
+            }
+            Err(err) => {
+                warn!(target: "memtrie", %shard_uid, ?err, "Failed to load memtrie snapshot");
+                None
+            }
+        }
+    });
+    let mut mem_tries = match snapshot {
+        Some(mem_tries) => mem_tries,
+        None => load_trie_from_flat_state(&store, shard_uid, state_root, flat_head.height).unwrap(),
+    };
 
     debug!(target: "memtrie", %shard_uid, "Loading flat state deltas...");
     // We load the deltas in order of height, so that we always have the previous state root
@@ -184,6 +205,7 @@ mod tests {
     };
     use crate::trie::mem::loading::load_trie_from_flat_state;
     use crate::trie::mem::lookup::memtrie_lookup;
+    use crate::trie::mem::snapshot::save_mem_trie_snapshot;
     use crate::trie::OptimizedValueRef;
     use crate::{DBCol, KeyLookupMode, NibbleSlice, ShardTries, Store, Trie, TrieUpdate};
     use rand::rngs::StdRng;
@@ -462,7 +484,7 @@ mod tests {
         // Load into memory. It should load the base flat state (block 0), plus all
         // four deltas. We'll check against the state roots at each block; they should
         // all exist in the loaded memtrie.
-        let mem_tries = load_trie_from_flat_state_and_delta(&store, shard_uid).unwrap();
+        let mem_tries = load_trie_from_flat_state_and_delta(&store, shard_uid, None).unwrap();
 
         assert_eq!(
             memtrie_lookup(mem_tries.get_root(&state_root_0).unwrap(), &test_key.to_vec(), None),
@@ -484,6 +506,31 @@ mod tests {
             memtrie_lookup(mem_tries.get_root(&state_root_4).unwrap(), &test_key.to_vec(), None),
             Some(FlatStateValue::inlined(&test_val4))
         );
+
+        // Save a snapshot and load again. Flat state is removed, so that the
+        // base trie can only come from the snapshot; the deltas are applied on
+        // top of it as before.
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        save_mem_trie_snapshot(&mem_tries, snapshot_dir.path()).unwrap();
+        let mut store_update = shard_tries.store_update();
+        store_helper::set_flat_state_value(&mut store_update, shard_uid, test_key.to_vec(), None);
+        store_update.commit().unwrap();
+        let mem_tries =
+            load_trie_from_flat_state_and_delta(&store, shard_uid, Some(snapshot_dir.path()))
+                .unwrap();
+        assert_eq!(mem_tries.num_roots(), 5);
+        for (state_root, value) in [
+            (state_root_0, &test_val0),
+            (state_root_1, &test_val1),
+            (state_root_2, &test_val2),
+            (state_root_3, &test_val3),
+            (state_root_4, &test_val4),
+        ] {
+            assert_eq!(
+                memtrie_lookup(mem_tries.get_root(&state_root).unwrap(), &test_key.to_vec(), None),
+                Some(FlatStateValue::inlined(value))
+            );
+        }
     }
 
     /// Makes the given changes to both the trie and flat storage.
@@ -15,6 +15,7 @@ pub mod loading;
 pub mod lookup;
 pub mod metrics;
 pub mod node;
+pub mod snapshot;
 pub mod updating;
 
 /// Check this, because in the code we conveniently assume usize is 8 bytes.
@@ -0,0 +1,326 @@
+//! Saving in-memory tries to disk and mapping them back into memory.
+//!
+//! Loading in-memory tries from flat storage requires reading the whole shard
+//! state and hashing every trie node, which for large shards takes a long
+//! time.  To avoid doing that on every restart, the arena backing the tries
+//! can be saved to a snapshot file on shutdown and mapped back into memory on
+//! startup.
+//!
+//! A snapshot file consists of:
+//! * a prefix with the magic bytes, the format version, the length and the
+//!   hash of the Borsh-serialised [`SnapshotHeader`] followed by the header
+//!   itself,
+//! * padding up to a multiple of [`CHUNKS_ALIGNMENT`] and
+//! * the arena chunks, [`CHUNK_SIZE`] bytes each.
+//!
+//! The header holds hashes of all the chunks.  Both the header and the chunks
+//! are verified on load, so that a corrupted snapshot is rejected rather than
+//! turned into a corrupted trie.
+//! The chunks are mapped into memory as private copy-on-write mappings so
+//! changes made to the tries never reach the file.  Snapshot files are therefore never
+//! modified in place; a new snapshot is written to a temporary file which is
+//! then renamed over the old one.
+
+use super::arena::{AllocatorState, Arena, ArenaChunk, ArenaPos, CHUNK_SIZE};
+use super::node::MemTrieNodeId;
+use super::MemTries;
+use borsh::{BorshDeserialize, BorshSerialize};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::shard_layout::ShardUId;
+use unc_primitives::types::{BlockHeight, StateRoot};
+
+const MAGIC: [u8; 8] = *b"MEMTRIE\0";
+const FORMAT_VERSION: u32 = 2;
+
+/// Length of the magic bytes, format version, header length and header hash.
+const PREFIX_LEN: u64 = MAGIC.len() as u64 + 4 + 8 + 32;
+
+/// Offset of the first chunk in the file is a multiple of this so that the
+/// chunks can be mapped into memory.  Mapping offsets must be multiples of
+/// the page size and 64 KiB is a multiple of all page sizes in common use.
+const CHUNKS_ALIGNMENT: u64 = 64 * 1024;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SnapshotHeader {
+    shard_uid: ShardUId,
+    num_chunks: u32,
+    allocator: AllocatorState,
+    /// Same as `MemTries::roots`.
+    roots: Vec<(StateRoot, Vec<ArenaPos>)>,
+    /// Same as `MemTries::heights`.
+    heights: Vec<(BlockHeight, Vec<StateRoot>)>,
+    /// Hash of every chunk.
+    chunk_hashes: Vec<CryptoHash>,
+}
+
+/// Returns path of the snapshot file of the given shard's tries.
+pub fn snapshot_path(dir: &Path, shard_uid: ShardUId) -> PathBuf {
+    dir.join(format!("{shard_uid}.memtrie"))
+}
+
+fn chunks_offset(header_len: u64) -> u64 {
+    (PREFIX_LEN + header_len).next_multiple_of(CHUNKS_ALIGNMENT)
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Saves all tries of the shard to a snapshot file in the given directory,
+/// replacing any previous snapshot of the shard.
+pub fn save_mem_trie_snapshot(mem_tries: &MemTries, dir: &Path) -> io::Result<()> {
+    let chunks: Vec<_> = mem_tries.arena.chunks().collect();
+    let header = SnapshotHeader {
+        shard_uid: mem_tries.shard_uid,
+        num_chunks: u32::try_from(chunks.len()).unwrap(),
+        allocator: mem_tries.arena.allocator_state(),
+        roots: mem_tries
+            .roots
+            .iter()
+            .map(|(state_root, ids)| (*state_root, ids.iter().map(|id| id.pos).collect()))
+            .collect(),
+        heights: mem_tries
+            .heights
+            .iter()
+            .map(|(height, state_roots)| (*height, state_roots.clone()))
+            .collect(),
+        chunk_hashes: chunks.par_iter().map(|chunk| CryptoHash::hash_bytes(chunk)).collect(),
+    };
+    let header = borsh::to_vec(&header)?;
+    let padding = chunks_offset(header.len() as u64) - PREFIX_LEN - header.len() as u64;
+
+    std::fs::create_dir_all(dir)?;
+    let path = snapshot_path(dir, mem_tries.shard_uid);
+    let tmp_path = path.with_extension("memtrie.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(CryptoHash::hash_bytes(&header).as_bytes())?;
+    writer.write_all(&header)?;
+    writer.write_all(&vec![0; padding as usize])?;
+    for chunk in chunks {
+        assert_eq!(chunk.len(), CHUNK_SIZE);
+        writer.write_all(chunk)?;
+    }
+    let file = writer.into_inner().map_err(|err| err.into_error())?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&tmp_path, &path)
+}
+
+/// Loads the shard’s trie with the given state root from its snapshot file in
+/// the given directory.  The returned `MemTries` contains exactly one root,
+/// at `block_height`.
+///
+/// Returns `Ok(None)` if there’s no snapshot or if the snapshot doesn’t
+/// contain the state root, and an error if the snapshot is malformed.
+pub fn load_mem_trie_snapshot(
+    dir: &Path,
+    shard_uid: ShardUId,
+    state_root: StateRoot,
+    block_height: BlockHeight,
+) -> io::Result<Option<MemTries>> {
+    let path = snapshot_path(dir, shard_uid);
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let file_len = file.metadata()?.len();
+
+    let mut prefix = [0; PREFIX_LEN as usize];
+    file.read_exact(&mut prefix)?;
+    let (magic, rest) = prefix.split_at(MAGIC.len());
+    let (version, rest) = rest.split_at(4);
+    let (header_len, header_hash) = rest.split_at(8);
+    if magic != MAGIC {
+        return Err(invalid_data("not a memtrie snapshot"));
+    }
+    let version = u32::from_le_bytes(version.try_into().unwrap());
+    if version != FORMAT_VERSION {
+        return Err(invalid_data(format!("unsupported snapshot format version {version}")));
+    }
+    let header_len = u64::from_le_bytes(header_len.try_into().unwrap());
+    if PREFIX_LEN + header_len > file_len {
+        return Err(invalid_data("snapshot header is truncated"));
+    }
+    let mut header = vec![0; header_len as usize];
+    file.read_exact(&mut header)?;
+    if CryptoHash::hash_bytes(&header).as_bytes() != header_hash {
+        return Err(invalid_data("snapshot header is corrupted"));
+    }
+    let header = SnapshotHeader::try_from_slice(&header)?;
+
+    if header.shard_uid != shard_uid {
+        return Err(invalid_data(format!("snapshot is of shard {}", header.shard_uid)));
+    }
+    let chunks_offset = chunks_offset(header_len);
+    let expected_len = chunks_offset + u64::from(header.num_chunks) * CHUNK_SIZE as u64;
+    if file_len != expected_len {
+        return Err(invalid_data(format!(
+            "snapshot is {file_len} bytes long but should be {expected_len} bytes"
+        )));
+    }
+    if header.chunk_hashes.len() != header.num_chunks as usize {
+        return Err(invalid_data("snapshot header has wrong number of chunk hashes"));
+    }
+    let in_arena =
+        |pos: &ArenaPos| pos.chunk() < header.num_chunks as usize && pos.pos() < CHUNK_SIZE;
+    if let Some((root, _)) =
+        header.roots.iter().find(|(_, ids)| ids.is_empty() || !ids.iter().all(in_arena))
+    {
+        return Err(invalid_data(format!("invalid nodes of state root {root}")));
+    }
+    if !header.roots.iter().any(|(root, _)| *root == state_root) {
+        return Ok(None);
+    }
+
+    let chunks = (0..u64::from(header.num_chunks))
+        .map(|index| {
+            // SAFETY: The mapping is private so the tries’ changes never reach
+            // the file, and snapshot files are replaced by renaming rather than
+            // modified in place so the file doesn’t change under us.
+            unsafe {
+                memmap2::MmapOptions::new()
+                    .offset(chunks_offset + index * CHUNK_SIZE as u64)
+                    .len(CHUNK_SIZE)
+                    .map_copy(&file)
+            }
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    // Verifying the chunks reads the whole file, which is still much faster
+    // than loading the tries from flat storage.
+    let corrupted = chunks
+        .par_iter()
+        .zip(&header.chunk_hashes)
+        .position_any(|(chunk, hash)| CryptoHash::hash_bytes(chunk) != *hash);
+    if let Some(index) = corrupted {
+        return Err(invalid_data(format!("snapshot chunk {index} is corrupted")));
+    }
+    let chunks = chunks.into_iter().map(ArenaChunk::Mapped).collect();
+    let arena = Arena::from_snapshot(shard_uid.to_string(), chunks, header.allocator)
+        .map_err(invalid_data)?;
+    let roots: HashMap<_, _> = header
+        .roots
+        .into_iter()
+        .map(|(root, ids)| (root, ids.into_iter().map(|pos| MemTrieNodeId { pos }).collect()))
+        .collect();
+    let heights: BTreeMap<_, _> = header.heights.into_iter().collect();
+    if heights.values().flatten().any(|root| !roots.contains_key(root)) {
+        return Err(invalid_data("snapshot heights refer to unknown state roots"));
+    }
+    let mut mem_tries = MemTries { arena, roots, heights, shard_uid };
+
+    // The stored hash is checked to catch a header which doesn’t match the
+    // chunks.  Recomputing hashes of the whole trie would defeat the purpose
+    // of the snapshot.
+    let root = mem_tries.roots[&state_root][0];
+    let root_hash = root.as_ptr(mem_tries.arena.memory()).view().node_hash();
+    if root_hash != state_root {
+        return Err(invalid_data(format!("state root {state_root} has node of hash {root_hash}")));
+    }
+
+    // Keep only the requested root.  Adding a reference to it first makes
+    // sure it survives deleting all the roots from the snapshot.
+    let old_heights = std::mem::take(&mut mem_tries.heights);
+    mem_tries.insert_root(state_root, root, block_height);
+    for old_root in old_heights.into_values().flatten() {
+        mem_tries.delete_root(&old_root);
+    }
+    Ok(Some(mem_tries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_mem_trie_snapshot, save_mem_trie_snapshot, snapshot_path};
+    use crate::trie::mem::lookup::memtrie_lookup;
+    use crate::trie::mem::node::{InputMemTrieNode, MemTrieNodeId};
+    use crate::trie::mem::MemTries;
+    use crate::NibbleSlice;
+    use unc_primitives::hash::CryptoHash;
+    use unc_primitives::shard_layout::ShardUId;
+    use unc_primitives::state::FlatStateValue;
+
+    /// Adds a root with a single leaf holding the given value.
+    fn add_leaf_root(tries: &mut MemTries, height: u64, value: &[u8]) -> CryptoHash {
+        tries
+            .construct_root(height, |arena| -> Result<_, ()> {
+                let leaf = MemTrieNodeId::new(
+                    arena,
+                    InputMemTrieNode::Leaf {
+                        value: FlatStateValue::inlined(value),
+                        extension: NibbleSlice::new(b"key")
+                            .encoded(true)
+                            .to_vec()
+                            .into_boxed_slice(),
+                    },
+                );
+                leaf.as_ptr_mut(arena.memory_mut()).compute_hash_recursively();
+                Ok(Some(leaf))
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let shard_uid = ShardUId::single_shard();
+        let mut tries = MemTries::new(shard_uid);
+        let root_1 = add_leaf_root(&mut tries, 1, b"one");
+        let root_2 = add_leaf_root(&mut tries, 2, b"two");
+        save_mem_trie_snapshot(&tries, dir.path()).unwrap();
+
+        let mut loaded = load_mem_trie_snapshot(dir.path(), shard_uid, root_2, 5).unwrap().unwrap();
+        assert_eq!(loaded.num_roots(), 1);
+        assert!(loaded.get_root(&root_1).is_none());
+        let root = loaded.get_root(&root_2).unwrap();
+        assert_eq!(memtrie_lookup(root, b"key", None), Some(FlatStateValue::inlined(b"two")));
+
+        // The loaded tries can be modified and garbage collected as usual.
+        let root_3 = add_leaf_root(&mut loaded, 6, b"three");
+        loaded.delete_until_height(6);
+        assert_eq!(loaded.num_roots(), 1);
+        let root = loaded.get_root(&root_3).unwrap();
+        assert_eq!(memtrie_lookup(root, b"key", None), Some(FlatStateValue::inlined(b"three")));
+
+        // Unknown state roots and shards without a snapshot aren’t loaded.
+        let other_root = CryptoHash::hash_bytes(b"other");
+        assert!(load_mem_trie_snapshot(dir.path(), shard_uid, other_root, 5).unwrap().is_none());
+        let other_shard = ShardUId { version: 1, shard_id: 1 };
+        assert!(load_mem_trie_snapshot(dir.path(), other_shard, root_2, 5).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_corrupted() {
+        let dir = tempfile::tempdir().unwrap();
+        let shard_uid = ShardUId::single_shard();
+        let mut tries = MemTries::new(shard_uid);
+        let root = add_leaf_root(&mut tries, 1, b"one");
+        save_mem_trie_snapshot(&tries, dir.path()).unwrap();
+
+        let path = snapshot_path(dir.path(), shard_uid);
+        let data = std::fs::read(&path).unwrap();
+        // Flip a byte of the header and of the last chunk.
+        for offset in [super::PREFIX_LEN as usize, data.len() - 1] {
+            let mut corrupted = data.clone();
+            corrupted[offset] ^= 1;
+            std::fs::write(&path, corrupted).unwrap();
+            assert!(load_mem_trie_snapshot(dir.path(), shard_uid, root, 1).is_err());
+        }
+        std::fs::write(&path, &data).unwrap();
+        assert!(load_mem_trie_snapshot(dir.path(), shard_uid, root, 1).unwrap().is_some());
+
+        let len = data.len() as u64;
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 1).unwrap();
+        assert!(load_mem_trie_snapshot(dir.path(), shard_uid, root, 1).is_err());
+
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(load_mem_trie_snapshot(dir.path(), shard_uid, root, 1).is_err());
+    }
+}
@@ -39,6 +39,7 @@ use unc_primitives::types::{AccountId, StateRoot, StateRootNode};
 use unc_vm_runner::ContractCode;
 
 pub mod accounting_cache;
+pub mod archived_accounts;
 mod config;
 mod from_flat;
 mod insert_delete;
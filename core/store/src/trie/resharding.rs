@@ -1,15 +1,18 @@
 use crate::flat::FlatStateChanges;
-use crate::{get, get_delayed_receipt_indices, set, ShardTries, StoreUpdate, Trie, TrieUpdate};
+use crate::{
+    get, get_delayed_receipt_indices, get_promise_yield_indices, set, ShardTries, StoreUpdate,
+    Trie, TrieUpdate,
+};
 use borsh::BorshDeserialize;
 use bytesize::ByteSize;
 use std::collections::HashMap;
 use unc_primitives::account::id::AccountId;
 use unc_primitives::errors::StorageError;
-use unc_primitives::receipt::Receipt;
+use unc_primitives::receipt::{PromiseYieldTimeout, Receipt};
 use unc_primitives::shard_layout::ShardUId;
 use unc_primitives::state_part::PartId;
 use unc_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
-use unc_primitives::trie_key::TrieKey;
+use unc_primitives::trie_key::{col, TrieKey};
 use unc_primitives::types::{
     ConsolidatedStateChange, StateChangeCause, StateChangesForResharding, StateRoot,
 };
@@ -40,6 +43,7 @@ impl ShardTries {
     ) -> Result<HashMap<ShardUId, TrieUpdate>, StorageError> {
         let mut trie_updates: HashMap<_, _> = self.get_trie_updates(state_roots);
         let mut insert_receipts = Vec::new();
+        let mut insert_timeouts = Vec::new();
         for ConsolidatedStateChange { trie_key, value } in changes.changes {
             match &trie_key {
                 TrieKey::DelayedReceiptIndices => {}
@@ -55,6 +59,32 @@ impl ShardTries {
                     }
                     None => {}
                 },
+                // Removed timeouts don't need to be propagated: every timeout is checked
+                // against its `PromiseYieldReceipt` marker when it is popped from the queue.
+                TrieKey::PromiseYieldIndices => {}
+                TrieKey::PromiseYieldTimeout { index } => match value {
+                    Some(value) => {
+                        let timeout =
+                            PromiseYieldTimeout::try_from_slice(&value).map_err(|err| {
+                                StorageError::StorageInconsistentState(format!(
+                                    "invalid promise yield timeout {:?}, err: {}",
+                                    value, err,
+                                ))
+                            })?;
+                        insert_timeouts.push((*index, timeout));
+                    }
+                    None => {}
+                },
+                // Global contract code is not attached to any account, so every child shard
+                // keeps its own copy for the accounts that refer to it.
+                TrieKey::GlobalContractCode { .. } => {
+                    for trie_update in trie_updates.values_mut() {
+                        match &value {
+                            Some(value) => trie_update.set(trie_key.clone(), value.clone()),
+                            None => trie_update.remove(trie_key.clone()),
+                        }
+                    }
+                }
                 TrieKey::Account { account_id }
                 | TrieKey::ContractCode { account_id }
                 | TrieKey::AccessKey { account_id, .. }
@@ -62,6 +92,7 @@ impl ShardTries {
                 | TrieKey::PostponedReceiptId { receiver_id: account_id, .. }
                 | TrieKey::PendingDataCount { receiver_id: account_id, .. }
                 | TrieKey::PostponedReceipt { receiver_id: account_id, .. }
+                | TrieKey::PromiseYieldReceipt { receiver_id: account_id, .. }
                 | TrieKey::Rsa2048Keys { account_id, .. }
                 | TrieKey::ContractData { account_id, .. } => {
                     let new_shard_uid = account_id_to_shard_uid(account_id);
@@ -94,6 +125,17 @@ impl ShardTries {
             account_id_to_shard_uid,
         )?;
 
+        insert_timeouts.sort_by_key(|it| it.0);
+
+        let insert_timeouts: Vec<_> =
+            insert_timeouts.into_iter().map(|(_, timeout)| timeout).collect();
+
+        apply_promise_yield_timeouts_to_children_states_impl(
+            &mut trie_updates,
+            &insert_timeouts,
+            account_id_to_shard_uid,
+        )?;
+
         Ok(trie_updates)
     }
 
@@ -134,6 +176,16 @@ impl ShardTries {
     ) -> Result<(StoreUpdate, HashMap<ShardUId, StateRoot>), StorageError> {
         let mut changes_by_shard: HashMap<_, Vec<_>> = HashMap::new();
         for (raw_key, value) in values.into_iter() {
+            // Global contract code doesn't belong to any account, copy it to every child shard.
+            if raw_key.first() == Some(&col::GLOBAL_CONTRACT_CODE) {
+                for shard_uid in state_roots.keys() {
+                    changes_by_shard
+                        .entry(*shard_uid)
+                        .or_default()
+                        .push((raw_key.clone(), value.clone()));
+                }
+                continue;
+            }
             if let Some(new_shard_uid) = key_to_shard_id(&raw_key)? {
                 changes_by_shard.entry(new_shard_uid).or_default().push((raw_key, value));
             }
@@ -180,6 +232,21 @@ impl ShardTries {
         self.finalize_and_apply_trie_updates(trie_updates)
     }
 
+    pub fn apply_promise_yield_timeouts_to_children_states(
+        &self,
+        state_roots: &HashMap<ShardUId, StateRoot>,
+        timeouts: &[PromiseYieldTimeout],
+        account_id_to_shard_uid: &dyn Fn(&AccountId) -> ShardUId,
+    ) -> Result<(StoreUpdate, HashMap<ShardUId, StateRoot>), StorageError> {
+        let mut trie_updates: HashMap<_, _> = self.get_trie_updates(state_roots);
+        apply_promise_yield_timeouts_to_children_states_impl(
+            &mut trie_updates,
+            timeouts,
+            account_id_to_shard_uid,
+        )?;
+        self.finalize_and_apply_trie_updates(trie_updates)
+    }
+
     fn finalize_and_apply_trie_updates(
         &self,
         updates: HashMap<ShardUId, TrieUpdate>,
@@ -277,6 +344,55 @@ fn apply_delayed_receipts_to_children_states_impl(
     Ok(())
 }
 
+fn apply_promise_yield_timeouts_to_children_states_impl(
+    trie_updates: &mut HashMap<ShardUId, TrieUpdate>,
+    insert_timeouts: &[PromiseYieldTimeout],
+    account_id_to_shard_uid: &dyn Fn(&AccountId) -> ShardUId,
+) -> Result<(), StorageError> {
+    let mut promise_yield_indices_by_shard = HashMap::new();
+    for (shard_uid, update) in trie_updates.iter() {
+        promise_yield_indices_by_shard.insert(*shard_uid, get_promise_yield_indices(update)?);
+    }
+
+    for timeout in insert_timeouts {
+        let new_shard_uid: ShardUId = account_id_to_shard_uid(&timeout.account_id);
+        if !trie_updates.contains_key(&new_shard_uid) {
+            let err = format!(
+                "Account {} is in new shard {:?} but state_roots only contains {:?}",
+                timeout.account_id,
+                new_shard_uid,
+                trie_updates.keys(),
+            );
+            return Err(StorageError::StorageInconsistentState(err));
+        }
+        // we already checked that new_shard_uid is in trie_updates and promise_yield_indices
+        // so we can safely unwrap here
+        let promise_yield_indices = promise_yield_indices_by_shard.get_mut(&new_shard_uid).unwrap();
+        set(
+            trie_updates.get_mut(&new_shard_uid).unwrap(),
+            TrieKey::PromiseYieldTimeout { index: promise_yield_indices.next_available_index },
+            timeout,
+        );
+        promise_yield_indices.next_available_index =
+            promise_yield_indices.next_available_index.checked_add(1).ok_or_else(|| {
+                StorageError::StorageInconsistentState(
+                    "Next available index for PromiseYield timeout exceeded the integer limit"
+                        .to_string(),
+                )
+            })?;
+    }
+
+    for (shard_uid, trie_update) in trie_updates {
+        set(
+            trie_update,
+            TrieKey::PromiseYieldIndices,
+            promise_yield_indices_by_shard.get(shard_uid).unwrap(),
+        );
+        trie_update.commit(StateChangeCause::Resharding);
+    }
+    Ok(())
+}
+
 /// Retrieve delayed receipts starting with `start_index` until `memory_limit` is hit
 /// return None if there is no delayed receipts with index >= start_index
 pub fn get_delayed_receipts(
@@ -315,6 +431,44 @@ pub fn get_delayed_receipts(
     Ok(Some((delayed_receipt_indices.first_index, receipts)))
 }
 
+/// Retrieve PromiseYield timeouts starting with `start_index` until `memory_limit` is hit
+/// return None if there is no timeouts with index >= start_index
+pub fn get_promise_yield_timeouts(
+    state_update: &TrieUpdate,
+    start_index: Option<u64>,
+    memory_limit: ByteSize,
+) -> Result<Option<(u64, Vec<PromiseYieldTimeout>)>, StorageError> {
+    let mut promise_yield_indices = get_promise_yield_indices(state_update)?;
+    if let Some(start_index) = start_index {
+        if start_index >= promise_yield_indices.next_available_index {
+            return Ok(None);
+        }
+        promise_yield_indices.first_index = start_index.max(promise_yield_indices.first_index);
+    }
+    let mut used_memory = 0;
+    let mut timeouts = vec![];
+
+    while used_memory < memory_limit.as_u64()
+        && promise_yield_indices.first_index < promise_yield_indices.next_available_index
+    {
+        let key = TrieKey::PromiseYieldTimeout { index: promise_yield_indices.first_index };
+        let data = state_update.get(&key)?.ok_or_else(|| {
+            StorageError::StorageInconsistentState(format!(
+                "PromiseYield timeout #{} should be in the state",
+                promise_yield_indices.first_index
+            ))
+        })?;
+        used_memory += data.len() as u64;
+        promise_yield_indices.first_index += 1;
+
+        let timeout = PromiseYieldTimeout::try_from_slice(&data).map_err(|_| {
+            StorageError::StorageInconsistentState("Failed to deserialize".to_string())
+        })?;
+        timeouts.push(timeout);
+    }
+    Ok(Some((promise_yield_indices.first_index, timeouts)))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::resharding::{apply_delayed_receipts_to_children_states_impl, get_delayed_receipts};
@@ -1,3 +1,4 @@
+use super::archived_accounts::archived_trie_nodes;
 use super::mem::MemTries;
 use super::state_snapshot::{StateSnapshot, StateSnapshotConfig};
 use super::TrieRefcountSubtraction;
@@ -5,6 +6,7 @@ use crate::flat::store_helper::remove_all_state_values;
 use crate::flat::{FlatStorageManager, FlatStorageStatus};
 use crate::trie::config::TrieConfig;
 use crate::trie::mem::loading::load_trie_from_flat_state_and_delta;
+use crate::trie::mem::snapshot::save_mem_trie_snapshot;
 use crate::trie::mem::updating::apply_memtrie_changes;
 use crate::trie::prefetching_trie_storage::PrefetchingThreadsHandle;
 use crate::trie::trie_storage::{TrieCache, TrieCachingStorage};
@@ -12,10 +14,11 @@ use crate::trie::{TrieRefcountAddition, POISONED_LOCK_ERR};
 use crate::{metrics, DBCol, PrefetchApi};
 use crate::{Store, StoreUpdate, Trie, TrieChanges, TrieUpdate};
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::{Arc, RwLock};
 use tracing::info;
+use unc_chain_configs::AccountPattern;
 use unc_primitives::errors::StorageError;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::shard_layout::{self, ShardUId};
@@ -303,6 +306,42 @@ impl ShardTries {
         self.apply_deletions_inner(&trie_changes.deletions, shard_uid, store_update)
     }
 
+    /// Like [`Self::apply_deletions`] but keeps nodes and values needed to
+    /// look up state of accounts matching `archived_accounts` in
+    /// `trie_changes.old_root`.  Kept nodes are never deleted.
+    pub fn apply_deletions_keeping_archived(
+        &self,
+        trie_changes: &TrieChanges,
+        shard_uid: ShardUId,
+        archived_accounts: &[AccountPattern],
+        store_update: &mut StoreUpdate,
+    ) -> std::io::Result<()> {
+        let candidates: HashSet<CryptoHash> =
+            trie_changes.deletions.iter().map(|op| op.trie_node_or_value_hash).collect();
+        let retained =
+            archived_trie_nodes(&trie_changes.old_root, archived_accounts, &candidates, |hash| {
+                let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(shard_uid, hash);
+                let value = self.0.store.get(DBCol::State, &key)?;
+                value.map(Into::into).ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, hash.to_string())
+                })
+            })?;
+        let deletions = trie_changes
+            .deletions
+            .iter()
+            .filter(|op| !retained.contains(&op.trie_node_or_value_hash))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut buffer = itoa::Buffer::new();
+        let shard_id = buffer.format(shard_uid.shard_id);
+        metrics::APPLIED_TRIE_DELETIONS
+            .with_label_values(&[&shard_id])
+            .inc_by(deletions.len() as u64);
+        self.apply_deletions_inner(&deletions, shard_uid, store_update);
+        Ok(())
+    }
+
     pub fn revert_insertions(
         &self,
         trie_changes: &TrieChanges,
@@ -395,7 +434,11 @@ impl ShardTries {
         shard_uids_to_load
             .par_iter()
             .map(|shard_uid| -> Result<(), StorageError> {
-                let mem_tries = load_trie_from_flat_state_and_delta(&store, *shard_uid)?;
+                let mem_tries = load_trie_from_flat_state_and_delta(
+                    &store,
+                    *shard_uid,
+                    trie_config.mem_trie_snapshot_dir.as_deref(),
+                )?;
                 self.0
                     .mem_tries
                     .write()
@@ -411,6 +454,23 @@ impl ShardTries {
         Ok(())
     }
 
+    /// Saves in-memory tries of all loaded shards to the memtrie snapshot
+    /// directory, if one is configured, so that the next startup can load
+    /// them quickly.  Meant to be called on shutdown.
+    pub fn save_mem_trie_snapshots(&self) -> std::io::Result<()> {
+        let Some(dir) = &self.0.trie_config.mem_trie_snapshot_dir else {
+            return Ok(());
+        };
+        let mem_tries = self.0.mem_tries.read().unwrap().clone();
+        for (shard_uid, mem_tries) in mem_tries {
+            info!(target: "memtrie", %shard_uid, "Saving memtrie snapshot...");
+            let start = std::time::Instant::now();
+            save_mem_trie_snapshot(&mem_tries.read().unwrap(), dir)?;
+            info!(target: "memtrie", %shard_uid, "Saved memtrie snapshot, took {:?}", start.elapsed());
+        }
+        Ok(())
+    }
+
     /// Retrieves the in-memory tries for the shard.
     pub fn get_mem_tries(&self, shard_uid: ShardUId) -> Option<Arc<RwLock<MemTries>>> {
         let guard = self.0.mem_tries.write().unwrap();
@@ -698,6 +758,8 @@ mod test {
             enable_receipt_prefetching: false,
             load_mem_tries_for_shards: Vec::new(),
             load_mem_tries_for_all_shards: false,
+            mem_trie_snapshot_dir: None,
+            flat_storage_options: Default::default(),
         };
         let shard_uids = Vec::from([ShardUId::single_shard()]);
         ShardTries::new(
@@ -816,6 +878,8 @@ mod test {
             enable_receipt_prefetching: false,
             load_mem_tries_for_shards: Vec::new(),
             load_mem_tries_for_all_shards: false,
+            mem_trie_snapshot_dir: None,
+            flat_storage_options: Default::default(),
         };
         let shard_uids = Vec::from([ShardUId { shard_id: 0, version: 0 }]);
         let shard_uid = *shard_uids.first().unwrap();
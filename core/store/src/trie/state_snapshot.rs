@@ -366,7 +366,8 @@ impl ShardTries {
             .ok_or(anyhow::anyhow!("{snapshot_path:?} needs to have a parent dir"))?;
         tracing::debug!(target: "state_snapshot", ?snapshot_path, ?parent_path);
 
-        let store_config = StoreConfig::default();
+        let mut store_config = StoreConfig::default();
+        store_config.backend = crate::opener::checkpoint_backend(&snapshot_path.join("data"));
 
         let opener = NodeStorage::opener(&snapshot_path, false, &store_config, None);
         let storage = opener.open_in_mode(Mode::ReadOnly)?;
@@ -1,6 +1,7 @@
 use std::sync::{atomic::AtomicBool, Arc};
 
 use unc_chain::types::Tip;
+use unc_chain_configs::AccountPattern;
 use unc_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
 use unc_primitives::{hash::CryptoHash, types::BlockHeight};
 use unc_store::cold_storage::{copy_all_data_to_cold, CopyAllDataToColdStatus};
@@ -55,6 +56,7 @@ fn cold_store_copy(
     cold_db: &Arc<ColdDB>,
     genesis_height: BlockHeight,
     epoch_manager: &EpochManagerHandle,
+    archived_accounts: &[AccountPattern],
 ) -> anyhow::Result<ColdStoreCopyResult> {
     // If COLD_HEAD is not set for hot storage we default it to genesis_height.
     let cold_head = cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?;
@@ -107,7 +109,7 @@ fn cold_store_copy(
     let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
 
     let mut next_height = cold_head_height + 1;
-    while !update_cold_db(cold_db, hot_store, &shard_layout, &next_height)? {
+    while !update_cold_db(cold_db, hot_store, &shard_layout, &next_height, archived_accounts)? {
         next_height += 1;
         if next_height > hot_final_head_height {
             return Err(anyhow::anyhow!(
@@ -256,6 +258,7 @@ fn cold_store_loop(
     cold_db: Arc<ColdDB>,
     genesis_height: BlockHeight,
     epoch_manager: &EpochManagerHandle,
+    archived_accounts: &[AccountPattern],
 ) {
     tracing::info!(target : "cold_store", "Starting the cold store loop");
 
@@ -266,8 +269,14 @@ fn cold_store_loop(
         }
 
         let instant = std::time::Instant::now();
-        let result =
-            cold_store_copy(&hot_store, &cold_store, &cold_db, genesis_height, epoch_manager);
+        let result = cold_store_copy(
+            &hot_store,
+            &cold_store,
+            &cold_db,
+            genesis_height,
+            epoch_manager,
+            archived_accounts,
+        );
         let duration = instant.elapsed();
 
         let result_string = cold_store_copy_result_to_string(&result);
@@ -339,6 +348,7 @@ pub fn spawn_cold_store_loop(
     let keep_going_clone = keep_going.clone();
 
     let split_storage_config = config.config.split_storage.clone().unwrap_or_default();
+    let archived_accounts = config.config.gc.archived_accounts.clone();
 
     tracing::info!(target : "cold_store", "Spawning the cold store loop");
     let join_handle =
@@ -358,6 +368,7 @@ pub fn spawn_cold_store_loop(
                 cold_db,
                 genesis_height,
                 epoch_manager.as_ref(),
+                &archived_accounts,
             )
         })?;
 
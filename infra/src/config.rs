@@ -13,15 +13,16 @@ use tempfile::tempdir;
 use tracing::{info, warn};
 use unc_chain_configs::{
     default_enable_multiline_logging, default_header_sync_expected_height_per_second,
-    default_header_sync_initial_timeout, default_header_sync_progress_timeout,
-    default_header_sync_stall_ban_timeout, default_log_summary_period,
-    default_produce_chunk_add_transactions_time_limit, default_state_sync,
-    default_state_sync_enabled, default_state_sync_timeout, default_sync_check_period,
-    default_sync_height_threshold, default_sync_step_period, default_transaction_pool_size_limit,
-    default_trie_viewer_state_size_limit, default_tx_routing_height_horizon,
-    default_view_client_threads, default_view_client_throttle_period, get_initial_supply,
-    ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode, LogSummaryStyle,
-    MutableConfigValue, ReshardingConfig, StateSyncConfig,
+    default_header_sync_initial_timeout, default_header_sync_max_parallel_ranges,
+    default_header_sync_progress_timeout, default_header_sync_stall_ban_timeout,
+    default_log_summary_period, default_produce_chunk_add_transactions_time_limit,
+    default_state_sync, default_state_sync_enabled, default_state_sync_timeout,
+    default_sync_check_period, default_sync_height_threshold, default_sync_step_period,
+    default_transaction_pool_size_limit, default_trie_viewer_state_size_limit,
+    default_tx_routing_height_horizon, default_view_client_threads,
+    default_view_client_throttle_period, get_initial_supply, ClientConfig, GCConfig, Genesis,
+    GenesisConfig, GenesisValidationMode, LogSummaryStyle, MutableConfigValue, ReshardingConfig,
+    StateSyncConfig, StoreScrubberConfig,
 };
 use unc_config_utils::{ValidationError, ValidationErrors};
 use unc_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey, Signer};
@@ -236,6 +237,9 @@ pub struct Consensus {
     /// Expected increase of header head weight per second during header sync
     #[serde(default = "default_header_sync_expected_height_per_second")]
     pub header_sync_expected_height_per_second: u64,
+    /// Maximum number of header ranges fetched from other peers in parallel during header sync
+    #[serde(default = "default_header_sync_max_parallel_ranges")]
+    pub header_sync_max_parallel_ranges: usize,
     /// How frequently we check whether we need to sync
     #[serde(default = "default_sync_check_period")]
     pub sync_check_period: Duration,
@@ -268,6 +272,7 @@ impl Default for Consensus {
             state_sync_timeout: default_state_sync_timeout(),
             header_sync_expected_height_per_second: default_header_sync_expected_height_per_second(
             ),
+            header_sync_max_parallel_ranges: default_header_sync_max_parallel_ranges(),
             sync_check_period: default_sync_check_period(),
             sync_step_period: default_sync_step_period(),
             doomslug_step_period: default_doomslug_step_period(),
@@ -322,12 +327,18 @@ pub struct Config {
     /// Configuration for the split storage.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub split_storage: Option<SplitStorageConfig>,
+    /// Configuration of the background store scrubber.  The scrubber runs
+    /// only if this is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_scrubber: Option<StoreScrubberConfig>,
     /// The node will stop after the head exceeds this height.
     /// The node usually stops within several seconds after reaching the target height.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expected_shutdown: Option<BlockHeight>,
     /// Whether to use state sync (unreliable and corrupts the DB if fails) or do a block sync instead.
     pub state_sync_enabled: bool,
+    /// Whether to sync epochs over light client blocks before state sync.
+    pub epoch_sync_enabled: bool,
     /// Options for syncing state.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state_sync: Option<StateSyncConfig>,
@@ -378,9 +389,11 @@ impl Default for Config {
             store: unc_store::StoreConfig::default(),
             cold_store: None,
             split_storage: None,
+            store_scrubber: None,
             expected_shutdown: None,
             state_sync: default_state_sync(),
             state_sync_enabled: default_state_sync_enabled(),
+            epoch_sync_enabled: false,
             transaction_pool_size_limit: default_transaction_pool_size_limit(),
             enable_multiline_logging: default_enable_multiline_logging(),
             resharding_config: ReshardingConfig::default(),
@@ -631,6 +644,7 @@ impl UncConfig {
                 header_sync_expected_height_per_second: config
                     .consensus
                     .header_sync_expected_height_per_second,
+                header_sync_max_parallel_ranges: config.consensus.header_sync_max_parallel_ranges,
                 state_sync_timeout: config.consensus.state_sync_timeout,
                 min_num_peers: config.consensus.min_num_peers,
                 log_summary_period: config.log_summary_period,
@@ -657,6 +671,7 @@ impl UncConfig {
                 flat_storage_creation_enabled: config.store.flat_storage_creation_enabled,
                 flat_storage_creation_period: config.store.flat_storage_creation_period,
                 state_sync_enabled: config.state_sync_enabled,
+                epoch_sync_enabled: config.epoch_sync_enabled,
                 state_sync: config.state_sync.unwrap_or_default(),
                 transaction_pool_size_limit: config.transaction_pool_size_limit,
                 enable_multiline_logging: config.enable_multiline_logging.unwrap_or(true),
@@ -1577,9 +1592,19 @@ fn test_config_from_file_skip_validation() {
         // values is probably not worth it but there may be some other defaults
         // we want to ensure that they happen.
         let want_gc = if has_gc {
-            GCConfig { gc_blocks_limit: 42, gc_fork_clean_step: 420, gc_num_epochs_to_keep: 24 }
+            GCConfig {
+                gc_blocks_limit: 42,
+                gc_fork_clean_step: 420,
+                gc_num_epochs_to_keep: 24,
+                archived_accounts: vec![],
+            }
         } else {
-            GCConfig { gc_blocks_limit: 2, gc_fork_clean_step: 100, gc_num_epochs_to_keep: 5 }
+            GCConfig {
+                gc_blocks_limit: 2,
+                gc_fork_clean_step: 100,
+                gc_num_epochs_to_keep: 5,
+                archived_accounts: vec![],
+            }
         };
         assert_eq!(want_gc, config.gc);
 
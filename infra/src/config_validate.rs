@@ -79,6 +79,27 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        let store_configs =
+            [("store", Some(&self.config.store)), ("cold_store", self.config.cold_store.as_ref())];
+        for (name, store_config) in store_configs {
+            let Some(store_config) = store_config else { continue };
+            let unknown_columns = store_config.unknown_column_options();
+            if !unknown_columns.is_empty() {
+                let error_message = format!(
+                    "'config.{name}.column_options' contains unknown columns: {}.",
+                    unknown_columns.join(", ")
+                );
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+
+        if let Some(store_scrubber) = &self.config.store_scrubber {
+            if store_scrubber.keys_per_second == 0 {
+                let error_message = "store_scrubber.keys_per_second should not be 0".to_string();
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+
         if let Some(state_sync) = &self.config.state_sync {
             if let Some(dump_config) = &state_sync.dump {
                 if let Some(restart_dump_for_shards) = &dump_config.restart_dump_for_shards {
@@ -108,6 +129,11 @@ impl<'a> ConfigValidator<'a> {
                             self.validation_errors.push_config_semantics_error(error_message);
                         }
                     }
+                    ExternalStorageLocation::Http { .. }
+                    | ExternalStorageLocation::Mirror { .. } => {
+                        let error_message = format!("'config.state_sync.dump.location' can't be 'Http' or 'Mirror', they are read-only.");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
                 }
 
                 if let Some(credentials_file) = &dump_config.credentials_file {
@@ -139,6 +165,18 @@ impl<'a> ConfigValidator<'a> {
                                 self.validation_errors.push_config_semantics_error(error_message);
                             }
                         }
+                        ExternalStorageLocation::Http { url } => {
+                            if url.is_empty() {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.Http.url' needs to be specified when 'config.state_sync.sync.ExternalStorage.location.Http' is present.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
+                        ExternalStorageLocation::Mirror { rpc_url } => {
+                            if rpc_url.is_empty() {
+                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.Mirror.rpc_url' needs to be specified when 'config.state_sync.sync.ExternalStorage.location.Mirror' is present.");
+                                self.validation_errors.push_config_semantics_error(error_message);
+                            }
+                        }
                     }
                     if config.num_concurrent_requests == 0 {
                         let error_message = format!("'config.state_sync.sync.ExternalStorage.num_concurrent_requests' needs to be greater than 0");
@@ -183,6 +221,17 @@ mod test {
         validate_config(&config).unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "'config.store.column_options' contains unknown columns: Foo.")]
+    fn test_unknown_column_options() {
+        let mut config = Config::default();
+        config.store.column_options.insert("Chunks".to_string(), Default::default());
+        config.store.column_options.insert("Foo".to_string(), Default::default());
+        // set tracked_shards to be non-empty
+        config.tracked_shards.push(20);
+        validate_config(&config).unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "Configuration with archive = false and save_trie_changes = false is not supported"
@@ -250,4 +299,22 @@ mod test {
         config.tx_routing_height_horizon = 1_000_000_000;
         validate_config(&config).unwrap();
     }
+
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.state_sync.dump.location' can't be 'Http' or 'Mirror', they are read-only."
+    )]
+    fn test_state_sync_dump_to_http() {
+        let mut config = Config::default();
+        config.state_sync = Some(unc_chain_configs::StateSyncConfig {
+            dump: Some(unc_chain_configs::DumpConfig {
+                location: ExternalStorageLocation::Http { url: "http://localhost".to_string() },
+                restart_dump_for_shards: None,
+                iteration_delay: None,
+                credentials_file: None,
+            }),
+            sync: SyncConfig::Peers,
+        });
+        validate_config(&config).unwrap();
+    }
 }
@@ -19,6 +19,7 @@ use unc_async::time;
 use unc_chain::state_snapshot_actor::{
     get_delete_snapshot_callback, get_make_snapshot_callback, SnapshotCallbacks, StateSnapshotActor,
 };
+use unc_chain::store_scrubber::{spawn_store_scrubber, StoreScrubberHandle};
 use unc_chain::types::RuntimeAdapter;
 use unc_chain::{Chain, ChainGenesis};
 use unc_chain_configs::ReshardingHandle;
@@ -34,7 +35,7 @@ use unc_store::flat::FlatStateValuesInliningMigrationHandle;
 use unc_store::genesis::initialize_genesis_state;
 use unc_store::metadata::DbKind;
 use unc_store::metrics::spawn_db_metrics_loop;
-use unc_store::{DBCol, Mode, NodeStorage, Store, StoreOpenerError};
+use unc_store::{DBCol, Mode, NodeStorage, ShardTries, Store, StoreOpenerError};
 use unc_telemetry::TelemetryActor;
 
 pub mod append_only_map;
@@ -209,6 +210,9 @@ pub struct UncNode {
     /// The cold_store_loop_handle will only be set if the cold store is configured.
     /// It's a handle to a background thread that copies data from the hot store to the cold store.
     pub cold_store_loop_handle: Option<ColdStoreLoopHandle>,
+    /// Handle to the background thread checking consistency of the databases,
+    /// set only if the store scrubber is configured.
+    pub store_scrubber_handle: Option<StoreScrubberHandle>,
     /// Contains handles to background threads that may be dumping state to S3.
     pub state_sync_dump_handle: Option<StateSyncDumpHandle>,
     /// A handle to control background flat state values inlining migration.
@@ -217,6 +221,8 @@ pub struct UncNode {
     // A handle that allows the main process to interrupt resharding if needed.
     // This typically happens when the main process is interrupted.
     pub resharding_handle: ReshardingHandle,
+    /// Tries of the node, used to save memtrie snapshots on shutdown.
+    pub shard_tries: ShardTries,
 }
 
 pub fn start_with_config(home_dir: &Path, config: UncConfig) -> anyhow::Result<UncNode> {
@@ -282,6 +288,16 @@ pub fn start_with_config_and_synchronization(
 
     let cold_store_loop_handle = spawn_cold_store_loop(&config, &storage, epoch_manager.clone())?;
 
+    let store_scrubber_handle = match &config.config.store_scrubber {
+        Some(scrubber_config) => Some(spawn_store_scrubber(
+            scrubber_config.clone(),
+            storage.get_hot_store(),
+            storage.get_cold_store(),
+            home_dir.join(&scrubber_config.quarantine_dir),
+        )?),
+        None => None,
+    };
+
     let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
     let chain_genesis = ChainGenesis::new(&config.genesis);
     let genesis_block =
@@ -368,6 +384,7 @@ pub fn start_with_config_and_synchronization(
             config.client_config.client_background_migration_threads,
         );
 
+    let shard_tries = runtime.get_tries();
     let state_sync_dump_handle = spawn_state_sync_dump(
         &config.client_config,
         chain_genesis,
@@ -426,9 +443,11 @@ pub fn start_with_config_and_synchronization(
         rpc_servers,
         arbiters,
         cold_store_loop_handle,
+        store_scrubber_handle,
         state_sync_dump_handle,
         flat_state_migration_handle,
         resharding_handle,
+        shard_tries,
     })
 }
 
@@ -5,6 +5,7 @@ use crate::UncConfig;
 use borsh::BorshDeserialize;
 use errors::FromStateViewerErrors;
 use node_runtime::adapter::ViewRuntimeAdapter;
+use node_runtime::state_viewer::errors::{ViewAccountError, ViewStateError};
 use node_runtime::state_viewer::TrieViewer;
 use unc_chain::types::{
     ApplyChunkBlockContext, ApplyChunkResult, ApplyChunkShardContext, ApplyResultForResharding,
@@ -40,7 +41,7 @@ use unc_primitives::views::{
     ViewApplyState, ViewStateResult,
 };
 use unc_store::config::StateSnapshotType;
-use unc_store::flat::FlatStorageManager;
+use unc_store::flat::{FlatStateHistoryView, FlatStorageManager};
 use unc_store::metadata::DbKind;
 use unc_store::{
     ApplyStatePartResult, DBCol, ShardTries, StateSnapshotConfig, Store,
@@ -96,10 +97,18 @@ impl NightshadeRuntime {
         // TODO (#9989): directly use the new state snapshot config once the migration is done.
         let compaction_enabled = config.config.store.state_snapshot_compaction_enabled
             || config.config.store.state_snapshot_config.compaction_enabled;
+        let hot_store_path = config.config.store.path.clone().unwrap_or(PathBuf::from("data"));
+        let mut trie_config = TrieConfig::from_store_config(&config.config.store);
+        trie_config.mem_trie_snapshot_dir = config
+            .config
+            .store
+            .mem_trie_snapshot_path
+            .as_ref()
+            .map(|path| home_dir.join(&hot_store_path).join(path));
         let state_snapshot_config = StateSnapshotConfig {
             state_snapshot_type,
             home_dir: home_dir.to_path_buf(),
-            hot_store_path: config.config.store.path.clone().unwrap_or(PathBuf::from("data")),
+            hot_store_path,
             state_snapshot_subdir: PathBuf::from("state_snapshot"),
             compaction_enabled,
         };
@@ -111,7 +120,7 @@ impl NightshadeRuntime {
             config.client_config.max_gas_burnt_view,
             None,
             config.config.gc.gc_num_epochs_to_keep(),
-            TrieConfig::from_store_config(&config.config.store),
+            trie_config,
             state_snapshot_config,
         )
     }
@@ -134,7 +143,8 @@ impl NightshadeRuntime {
 
         let runtime = Runtime::new();
         let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, max_gas_burnt_view);
-        let flat_storage_manager = FlatStorageManager::new(store.clone());
+        let flat_storage_manager =
+            FlatStorageManager::with_options(store.clone(), trie_config.flat_storage_options);
         let shard_uids: Vec<_> = genesis_config.shard_layout.shard_uids().collect();
         let tries = ShardTries::new(
             store.clone(),
@@ -619,6 +629,27 @@ impl NightshadeRuntime {
 
         Ok(state_part)
     }
+
+    /// Runs `f` on flat state of the shard at the given block, if flat storage
+    /// keeps history for it. Returns `None` if the block is out of the history
+    /// window, so that the caller falls back to reading the trie.
+    fn with_flat_state_history<R>(
+        &self,
+        shard_uid: ShardUId,
+        block_hash: &CryptoHash,
+        block_height: BlockHeight,
+        f: impl FnMut(&FlatStateHistoryView) -> R,
+    ) -> Option<R> {
+        let flat_storage =
+            self.tries.get_flat_storage_manager().get_flat_storage_for_shard(shard_uid)?;
+        match flat_storage.with_historical_view(block_hash, block_height, f) {
+            Ok(result) => result,
+            Err(err) => {
+                tracing::debug!(target: "runtime", ?err, ?shard_uid, %block_hash, "Cannot read flat state history");
+                None
+            }
+        }
+    }
 }
 
 fn format_total_gas_burnt(gas: Gas) -> String {
@@ -927,8 +958,15 @@ impl RuntimeAdapter for NightshadeRuntime {
     ) -> Result<QueryResponse, unc_chain::unc_chain_primitives::error::QueryError> {
         match request {
             QueryRequest::ViewAccount { account_id } => {
-                let account =
-                    self.view_account(&shard_uid, *state_root, account_id).map_err(|err| {
+                // Internal errors, e.g. values already removed by GC, are
+                // retried against the trie.
+                let account = self
+                    .with_flat_state_history(shard_uid, block_hash, block_height, |view| {
+                        self.trie_viewer.view_account_at(view, account_id)
+                    })
+                    .filter(|result| !matches!(result, Err(ViewAccountError::InternalError { .. })))
+                    .unwrap_or_else(|| self.view_account(&shard_uid, *state_root, account_id))
+                    .map_err(|err| {
                         unc_chain::unc_chain_primitives::error::QueryError::from_view_account_error(
                             err,
                             block_height,
@@ -993,14 +1031,24 @@ impl RuntimeAdapter for NightshadeRuntime {
                 })
             }
             QueryRequest::ViewState { account_id, prefix, include_proof } => {
-                let view_state_result = self
-                    .view_state(
-                        &shard_uid,
-                        *state_root,
-                        account_id,
-                        prefix.as_ref(),
-                        *include_proof,
-                    )
+                // Flat storage can't produce proofs, so such queries always read the trie.
+                let view_state_result = (!*include_proof)
+                    .then(|| {
+                        self.with_flat_state_history(shard_uid, block_hash, block_height, |view| {
+                            self.trie_viewer.view_state_at(view, account_id, prefix.as_ref())
+                        })
+                    })
+                    .flatten()
+                    .filter(|result| !matches!(result, Err(ViewStateError::InternalError { .. })))
+                    .unwrap_or_else(|| {
+                        self.view_state(
+                            &shard_uid,
+                            *state_root,
+                            account_id,
+                            prefix.as_ref(),
+                            *include_proof,
+                        )
+                    })
                     .map_err(|err| {
                         unc_chain::unc_chain_primitives::error::QueryError::from_view_state_error(
                             err,
@@ -150,6 +150,22 @@ impl TestEnv {
         epoch_length: BlockHeightDelta,
         has_reward: bool,
         minimum_pledge_divisor: Option<u64>,
+    ) -> Self {
+        Self::new_with_options(
+            validators,
+            epoch_length,
+            has_reward,
+            minimum_pledge_divisor,
+            Default::default(),
+        )
+    }
+
+    fn new_with_options(
+        validators: Vec<Vec<AccountId>>,
+        epoch_length: BlockHeightDelta,
+        has_reward: bool,
+        minimum_pledge_divisor: Option<u64>,
+        trie_config: TrieConfig,
     ) -> Self {
         let (dir, opener) = NodeStorage::test_opener();
         let store = opener.open().unwrap().get_hot_store();
@@ -185,7 +201,7 @@ impl TestEnv {
             None,
             Some(RuntimeConfigStore::free()),
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
-            Default::default(),
+            trie_config,
             StateSnapshotConfig {
                 state_snapshot_type: StateSnapshotType::EveryEpoch,
                 home_dir: PathBuf::from(dir.path()),
@@ -1419,6 +1435,61 @@ fn test_trie_and_flat_state_equality() {
     assert_eq!(state_value, view_state_value);
 }
 
+/// Check that account queries at blocks behind the flat head are served from
+/// flat storage history and match the trie at these blocks.
+#[test]
+fn test_query_flat_state_history() {
+    let validators: Vec<AccountId> = vec!["test1".parse().unwrap(), "test2".parse().unwrap()];
+    let mut trie_config = TrieConfig::default();
+    trie_config.flat_storage_options.history_window = 5;
+    let mut env = TestEnv::new_with_options(vec![validators.clone()], 4, false, None, trie_config);
+    let signer =
+        InMemorySigner::from_seed(validators[0].clone(), KeyType::ED25519, validators[0].as_ref());
+    let transfer_tx = SignedTransaction::from_actions(
+        4,
+        signer.account_id.clone(),
+        validators[1].clone(),
+        &signer as &dyn Signer,
+        vec![Action::Transfer(TransferAction { deposit: 10 })],
+        // runtime does not validate block history
+        CryptoHash::default(),
+    );
+    let mut expected = vec![];
+    for i in 0..4 {
+        env.step_default(if i == 0 { vec![transfer_tx.clone()] } else { vec![] });
+        let amount = env.view_account(&validators[1]).amount;
+        expected.push((env.head.last_block_hash, env.head.height, amount));
+    }
+    assert_ne!(expected[0].2, expected[3].2);
+
+    let shard_uid = env.epoch_manager.shard_id_to_uid(0, &env.head.epoch_id).unwrap();
+    let flat_storage =
+        env.runtime.get_flat_storage_manager().get_flat_storage_for_shard(shard_uid).unwrap();
+    flat_storage.update_flat_head(&env.head.last_block_hash, true).unwrap();
+
+    let request = QueryRequest::ViewAccount { account_id: validators[1].clone() };
+    for (block_hash, block_height, amount) in expected {
+        // Reading the empty trie would fail, so the account must come from flat storage.
+        let response = env
+            .runtime
+            .query(
+                shard_uid,
+                &Trie::EMPTY_ROOT,
+                block_height,
+                0,
+                &CryptoHash::default(),
+                &block_hash,
+                &env.head.epoch_id,
+                &request,
+            )
+            .unwrap();
+        match response.kind {
+            QueryResponseKind::ViewAccount(account) => assert_eq!(account.amount, amount),
+            kind => panic!("unexpected query response {kind:?}"),
+        }
+    }
+}
+
 /// Check that mainnet genesis hash still matches, to make sure that we're still backwards compatible.
 #[test]
 fn test_genesis_hash() {
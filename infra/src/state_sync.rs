@@ -11,16 +11,16 @@ use unc_chain_configs::{ClientConfig, ExternalStorageLocation};
 use unc_client::sync::external::{create_bucket_readwrite, external_storage_location};
 use unc_client::sync::external::{
     external_storage_location_directory, get_part_id_from_filename, is_part_filename,
-    ExternalConnection,
+    ExternalConnection, StatePartsManifest,
 };
 use unc_client::sync::state::{StateSync, STATE_DUMP_ITERATION_TIME_LIMIT_SECS};
 use unc_epoch_manager::shard_tracker::ShardTracker;
 use unc_epoch_manager::EpochManagerAdapter;
-use unc_primitives::hash::CryptoHash;
+use unc_primitives::hash::{hash, CryptoHash};
 use unc_primitives::state_part::PartId;
 use unc_primitives::state_sync::{StatePartKey, StateSyncDumpProgress};
 use unc_primitives::types::{AccountId, EpochHeight, EpochId, ShardId, StateRoot};
-use unc_store::DBCol;
+use unc_store::{DBCol, Store};
 
 /// Starts one a thread per tracked shard.
 /// Each started thread will be dumping state parts of a single epoch to external storage.
@@ -62,6 +62,9 @@ pub fn spawn_state_sync_dump(
                 bucket
             }
         },
+        ExternalStorageLocation::Http { .. } | ExternalStorageLocation::Mirror { .. } => {
+            anyhow::bail!("State parts can't be dumped to a read-only HTTP location");
+        }
     };
 
     // Determine how many threads to start.
@@ -233,6 +236,7 @@ async fn state_sync_dump(
     keep_running: Arc<AtomicBool>,
 ) {
     tracing::info!(target: "state_sync_dump", shard_id, "Running StateSyncDump loop");
+    let store = chain.chain_store().store().clone();
 
     if restart_dump_for_shards.contains(&shard_id) {
         tracing::debug!(target: "state_sync_dump", shard_id, "Dropped existing progress");
@@ -286,7 +290,27 @@ async fn state_sync_dump(
                                     num_parts,
                                     num_parts,
                                 );
-                                Some(StateSyncDumpProgress::AllDumped { epoch_id, epoch_height })
+                                match put_manifest(
+                                    shard_id,
+                                    &chain_id,
+                                    &epoch_id,
+                                    epoch_height,
+                                    sync_hash,
+                                    num_parts,
+                                    &store,
+                                    &external,
+                                )
+                                .await
+                                {
+                                    Ok(()) => Some(StateSyncDumpProgress::AllDumped {
+                                        epoch_id,
+                                        epoch_height,
+                                    }),
+                                    Err(err) => {
+                                        tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to put the manifest into external storage.");
+                                        None
+                                    }
+                                }
                             }
                             Ok(missing_parts) => {
                                 let mut parts_to_dump = missing_parts.clone();
@@ -357,7 +381,26 @@ async fn state_sync_dump(
                                     );
                                     dumped_any_state_part = true;
                                 }
-                                if parts_to_dump.is_empty() {
+                                let all_dumped = parts_to_dump.is_empty()
+                                    && match put_manifest(
+                                        shard_id,
+                                        &chain_id,
+                                        &epoch_id,
+                                        epoch_height,
+                                        sync_hash,
+                                        num_parts,
+                                        &store,
+                                        &external,
+                                    )
+                                    .await
+                                    {
+                                        Ok(()) => true,
+                                        Err(err) => {
+                                            tracing::warn!(target: "state_sync_dump", shard_id, epoch_height, ?err, "Failed to put the manifest into external storage.");
+                                            false
+                                        }
+                                    };
+                                if all_dumped {
                                     Some(StateSyncDumpProgress::AllDumped {
                                         epoch_id,
                                         epoch_height,
@@ -449,6 +492,45 @@ fn update_dumped_size_and_cnt_metrics(
         .set(num_parts as i64);
 }
 
+/// Publishes checksums of all state parts of the epoch.
+/// Nodes syncing from external storage verify state parts against them.
+async fn put_manifest(
+    shard_id: ShardId,
+    chain_id: &str,
+    epoch_id: &EpochId,
+    epoch_height: EpochHeight,
+    sync_hash: CryptoHash,
+    num_parts: u64,
+    store: &Store,
+    external: &ExternalConnection,
+) -> anyhow::Result<()> {
+    let mut part_hashes = Vec::with_capacity(num_parts as usize);
+    for part_id in 0..num_parts {
+        // Parts dumped by this node are stored locally, others are downloaded back.
+        let key = borsh::to_vec(&StatePartKey(sync_hash, shard_id, part_id))?;
+        let part_hash = match store.get(DBCol::StateParts, &key)? {
+            Some(state_part) => hash(&state_part),
+            None => {
+                let location = external_storage_location(
+                    chain_id,
+                    epoch_id,
+                    epoch_height,
+                    shard_id,
+                    part_id,
+                    num_parts,
+                );
+                hash(&external.get_part(shard_id, &location).await?)
+            }
+        };
+        part_hashes.push(part_hash);
+    }
+    let directory_path =
+        external_storage_location_directory(chain_id, epoch_id, epoch_height, shard_id);
+    external.put_manifest(&StatePartsManifest::new(part_hashes), shard_id, &directory_path).await?;
+    tracing::debug!(target: "state_sync_dump", shard_id, epoch_height, num_parts, "Wrote the manifest");
+    Ok(())
+}
+
 /// Obtains and then saves the part data.
 fn obtain_and_store_state_part(
     runtime: &dyn RuntimeAdapter,
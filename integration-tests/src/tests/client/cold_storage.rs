@@ -6,8 +6,9 @@ use unc_chain::{ChainGenesis, Provenance};
 use unc_chain_configs::Genesis;
 use unc_client::test_utils::TestEnv;
 use unc_client::ProcessTxResponse;
+use unc_cold_store_tool::repair;
 use unc_crypto::{InMemorySigner, KeyType};
-use unc_epoch_manager::EpochManager;
+use unc_epoch_manager::{EpochManager, EpochManagerAdapter};
 use unc_infra::config::GenesisExt;
 use unc_infra::test_utils::TestEnvNightshadeSetupExt;
 use unc_infra::{cold_storage::spawn_cold_store_loop, UncConfig};
@@ -157,6 +158,7 @@ fn test_storage_after_commit_of_cold_update() {
                 )
                 .unwrap(),
             &h,
+            &[],
         )
         .unwrap();
 
@@ -329,6 +331,7 @@ fn test_cold_db_copy_with_height_skips() {
                 )
                 .unwrap(),
             &h,
+            &[],
         )
         .unwrap();
 
@@ -585,3 +588,77 @@ fn test_cold_loop_on_gc_boundary() {
         end_cold_head
     );
 }
+
+/// Test that the `repair` command of the cold-store tool copies the blocks
+/// missing in cold storage from hot storage, reports the blocks which hot
+/// storage doesn't have anymore and moves cold HEAD up to the first gap it
+/// couldn't fill.
+#[test]
+fn test_repair_cold_db_with_missing_blocks() {
+    init_test_logger();
+
+    let epoch_length = 5;
+    let max_height = epoch_length * 3;
+
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = epoch_length;
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = epoch_length;
+
+    let (store, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+    let hot_store = &store.get_hot_store();
+    let cold_store = &store.get_cold_store().unwrap();
+    let cold_db = &*store.cold_db().unwrap();
+    let mut env = TestEnv::builder(chain_genesis)
+        .archive(true)
+        .save_trie_changes(true)
+        .stores(vec![hot_store.clone()])
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build();
+    let epoch_manager = env.clients[0].epoch_manager.clone();
+    test_cold_genesis_update(cold_db, hot_store).unwrap();
+    for h in 1..=max_height {
+        env.produce_block(0, h);
+    }
+
+    // Blocks 7..=9 and 12 never make it to cold storage.
+    for h in (1..=max_height).filter(|h| !(7..=9).contains(h) && *h != 12) {
+        let block = env.clients[0].chain.get_block_by_height(h).unwrap();
+        let shard_layout = epoch_manager.get_shard_layout(block.header().epoch_id()).unwrap();
+        update_cold_db(cold_db, hot_store, &shard_layout, &h, &[]).unwrap();
+    }
+    update_cold_head(cold_db, hot_store, &6).unwrap();
+
+    // Block 12 is garbage collected in hot storage as well. `BlockHeight` is
+    // never garbage collected, so its hash is still known.
+    let block_hash_12 = *env.clients[0].chain.get_block_by_height(12).unwrap().hash();
+    let mut store_update = hot_store.store_update();
+    store_update.delete(DBCol::Block, block_hash_12.as_ref());
+    store_update.commit().unwrap();
+    assert!(hot_store.exists(DBCol::BlockHeight, &12u64.to_le_bytes()).unwrap());
+
+    let cold_head = || cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY).unwrap().unwrap();
+
+    let report = repair(&store, epoch_manager.as_ref(), 1, 12, &[], true).unwrap();
+    assert_eq!(report.recopied, vec![7, 8, 9]);
+    assert_eq!(report.unavailable, vec![12]);
+    assert_eq!(report.new_cold_head, None);
+    assert_eq!(cold_head().height, 6);
+    let block_hash_8 = *env.clients[0].chain.get_block_by_height(8).unwrap().hash();
+    assert!(!cold_store.exists(DBCol::Block, block_hash_8.as_ref()).unwrap());
+
+    let report = repair(&store, epoch_manager.as_ref(), 1, 12, &[], false).unwrap();
+    assert_eq!(report.recopied, vec![7, 8, 9]);
+    assert_eq!(report.unavailable, vec![12]);
+    assert_eq!(report.problems, Vec::<String>::new());
+    assert_eq!(report.new_cold_head, Some(11));
+    assert_eq!(cold_head().height, 11);
+    assert!(cold_store.exists(DBCol::Block, block_hash_8.as_ref()).unwrap());
+
+    // Once repaired, nothing is left to copy.
+    let report = repair(&store, epoch_manager.as_ref(), 1, 11, &[], false).unwrap();
+    assert_eq!(report.recopied, Vec::<u64>::new());
+    assert_eq!(report.unavailable, Vec::<u64>::new());
+    assert_eq!(report.problems, Vec::<String>::new());
+}
@@ -982,6 +982,7 @@ fn client_sync_headers() {
                     last_time_received_message: unc_async::time::Instant::now(),
                     connection_established_time: unc_async::time::Instant::now(),
                     peer_type: PeerType::Outbound,
+                    reputation_score: 0.,
                     nonce: 1,
                 }],
                 num_connected_peers: 1,
@@ -1506,7 +1507,7 @@ fn test_archival_gc_common(
         blocks.push(block);
 
         if i <= max_cold_head_height {
-            update_cold_db(storage.cold_db().unwrap(), hot_store, &shard_layout, &i).unwrap();
+            update_cold_db(storage.cold_db().unwrap(), hot_store, &shard_layout, &i, &[]).unwrap();
             update_cold_head(storage.cold_db().unwrap(), &hot_store, &i).unwrap();
         }
     }
@@ -3411,7 +3412,7 @@ mod contract_precompilation_tests {
             .epoch_id()
             .clone();
         let runtime_config = env.get_runtime_config(0, epoch_id);
-        let key = get_contract_cache_key(&contract_code, &runtime_config.wasm_config);
+        let key = get_contract_cache_key(*contract_code.hash(), &runtime_config.wasm_config);
         for i in 0..num_clients {
             caches[i]
                 .get(&key)
@@ -3519,11 +3520,11 @@ mod contract_precompilation_tests {
             .clone();
         let runtime_config = env.get_runtime_config(0, epoch_id);
         let tiny_contract_key = get_contract_cache_key(
-            &ContractCode::new(tiny_wasm_code.clone(), None),
+            *ContractCode::new(tiny_wasm_code.clone(), None).hash(),
             &runtime_config.wasm_config,
         );
         let test_contract_key = get_contract_cache_key(
-            &ContractCode::new(wasm_code.clone(), None),
+            *ContractCode::new(wasm_code.clone(), None).hash(),
             &runtime_config.wasm_config,
         );
 
@@ -3602,7 +3603,7 @@ mod contract_precompilation_tests {
             .clone();
         let runtime_config = env.get_runtime_config(0, epoch_id);
         let contract_key = get_contract_cache_key(
-            &ContractCode::new(wasm_code.clone(), None),
+            *ContractCode::new(wasm_code.clone(), None).hash(),
             &runtime_config.wasm_config,
         );
 
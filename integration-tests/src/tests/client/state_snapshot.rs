@@ -45,6 +45,8 @@ impl StateSnaptshotTestEnv {
             enable_receipt_prefetching: false,
             load_mem_tries_for_shards: Vec::new(),
             load_mem_tries_for_all_shards: false,
+            mem_trie_snapshot_dir: None,
+            flat_storage_options: Default::default(),
         };
         let flat_storage_manager = FlatStorageManager::new(store.clone());
         let shard_uids = [ShardUId::single_shard()];
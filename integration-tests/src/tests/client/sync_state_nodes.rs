@@ -24,7 +24,7 @@ use unc_primitives::shard_layout::ShardUId;
 use unc_primitives::state_part::PartId;
 use unc_primitives::state_sync::{CachedParts, StatePartKey};
 use unc_primitives::transaction::SignedTransaction;
-use unc_primitives::types::{BlockId, BlockReference, EpochId, EpochReference};
+use unc_primitives::types::{AccountId, BlockId, BlockReference, EpochId, EpochReference};
 use unc_primitives::utils::MaybeValidated;
 use unc_primitives_core::types::ShardId;
 use unc_store::DBCol;
@@ -489,6 +489,7 @@ fn sync_state_dump() {
                                         },
                                         num_concurrent_requests: 1,
                                         num_concurrent_requests_during_catchup: 1,
+                                        request_parts_from_peers: false,
                                     });
 
                                 let unc_infra::UncNode {
@@ -902,3 +903,93 @@ fn test_state_sync_headers() {
         });
     });
 }
+
+/// A node joining after the block producers changed has to validate the first block of the new
+/// epoch against the new block producers during Epoch Sync finalization.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn epoch_sync_across_block_producer_change() {
+    heavy_test(|| {
+        init_integration_logger();
+
+        let validators: Vec<AccountId> =
+            (1..=4).map(|i| format!("test{i}").parse().unwrap()).collect();
+        let mut genesis = Genesis::test(validators, 4);
+        genesis.config.epoch_length = 10;
+
+        run_actix(async move {
+            let ports: Vec<_> = (0..5).map(|_| tcp::ListenerAddr::reserve_for_test()).collect();
+            let dirs: Vec<_> = (0..5)
+                .map(|i| {
+                    tempfile::Builder::new().prefix(&format!("epoch_sync_{i}")).tempdir().unwrap()
+                })
+                .collect();
+
+            // `test4` never starts, so it gets kicked out and the block producers change.
+            let mut view_clients = vec![];
+            for i in 0..3 {
+                let mut unc =
+                    load_test_config(&format!("test{}", i + 1), ports[i], genesis.clone());
+                if i == 0 {
+                    unc.network_config.peer_store.boot_nodes = convert_boot_nodes(vec![]);
+                    unc.client_config.min_num_peers = 0;
+                } else {
+                    unc.network_config.peer_store.boot_nodes =
+                        convert_boot_nodes(vec![("test1", *ports[0])]);
+                }
+                let unc_infra::UncNode { view_client, .. } =
+                    start_with_config(dirs[i].path(), unc).expect("start_with_config");
+                view_clients.push(view_client);
+            }
+            let view_client1 = &view_clients[0];
+
+            // Wait for an epoch without `test4`, which is at least two epochs ahead of genesis.
+            let sync_height = wait_or_timeout(100, 120000, || async {
+                let Ok(Ok(block)) = view_client1.send(GetBlock::latest().with_span_context()).await
+                else {
+                    return ControlFlow::Continue(());
+                };
+                let Ok(Ok(validators)) = view_client1
+                    .send(
+                        GetValidatorInfo {
+                            epoch_reference: EpochReference::EpochId(EpochId(
+                                block.header.epoch_id,
+                            )),
+                        }
+                        .with_span_context(),
+                    )
+                    .await
+                else {
+                    return ControlFlow::Continue(());
+                };
+                if validators.current_validators.len() == 3 {
+                    ControlFlow::Break(block.header.height)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .await
+            .unwrap();
+
+            let mut unc5 = load_test_config("test5", ports[4], genesis.clone());
+            unc5.client_config.epoch_sync_enabled = true;
+            unc5.client_config.skip_sync_wait = false;
+            unc5.client_config.min_num_peers = 1;
+            unc5.network_config.peer_store.boot_nodes =
+                convert_boot_nodes(vec![("test1", *ports[0])]);
+            let unc_infra::UncNode { view_client: view_client5, .. } =
+                start_with_config(dirs[4].path(), unc5).expect("start_with_config");
+
+            // A peer rejecting the finalization would get banned and the node would never sync.
+            wait_or_timeout(100, 120000, || async {
+                match view_client5.send(GetBlock::latest().with_span_context()).await {
+                    Ok(Ok(block)) if block.header.height >= sync_height => ControlFlow::Break(()),
+                    _ => ControlFlow::Continue(()),
+                }
+            })
+            .await
+            .unwrap();
+            System::current().stop();
+        });
+    });
+}
@@ -1,4 +1,5 @@
 mod node_cluster;
+mod partial_archive;
 mod pledge_nodes;
 mod rpc_error_structs;
 mod rpc_nodes;
@@ -0,0 +1,83 @@
+use crate::test_helpers::heavy_test;
+use actix::{Actor, System};
+use unc_actix_test_utils::run_actix;
+use unc_chain_configs::Genesis;
+use unc_client::{GetBlock, Query, QueryError};
+use unc_infra::config::GenesisExt;
+use unc_infra::{load_test_config, start_with_config};
+use unc_network::tcp;
+use unc_network::test_utils::WaitOrTimeoutActor;
+use unc_o11y::testonly::init_integration_logger;
+use unc_o11y::WithSpanContextExt;
+use unc_primitives::types::{BlockId, BlockReference};
+use unc_primitives::views::{QueryRequest, QueryResponseKind};
+
+/// With `gc.archived_accounts` set, once a block is garbage collected the node
+/// still answers queries at its height for the archived accounts and answers
+/// `GarbageCollectedBlock` for the other accounts.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn query_archived_accounts_at_garbage_collected_height() {
+    heavy_test(|| {
+        init_integration_logger();
+
+        let mut genesis = Genesis::test(vec!["unc0".parse().unwrap(), "unc1".parse().unwrap()], 1);
+        genesis.config.epoch_length = 5;
+        let mut unc_config =
+            load_test_config("unc0", tcp::ListenerAddr::reserve_for_test(), genesis);
+        unc_config.client_config.gc.archived_accounts =
+            vec!["unc0".to_string().try_into().unwrap()];
+
+        run_actix(async move {
+            let dir = tempfile::Builder::new().prefix("partial_archive").tempdir().unwrap();
+            let unc_infra::UncNode { view_client, .. } =
+                start_with_config(dir.path(), unc_config).expect("start_with_config");
+
+            WaitOrTimeoutActor::new(
+                Box::new(move |_ctx| {
+                    let view_client = view_client.clone();
+                    actix::spawn(async move {
+                        // Wait until the block at height 1 is garbage collected.
+                        let block = view_client
+                            .send(GetBlock(BlockId::Height(1).into()).with_span_context())
+                            .await
+                            .unwrap();
+                        if block.is_ok() {
+                            return;
+                        }
+
+                        let query = |account_id: &str| {
+                            view_client.send(
+                                Query::new(
+                                    BlockReference::BlockId(BlockId::Height(1)),
+                                    QueryRequest::ViewAccount {
+                                        account_id: account_id.parse().unwrap(),
+                                    },
+                                )
+                                .with_span_context(),
+                            )
+                        };
+                        let archived = query("unc0").await.unwrap();
+                        assert!(
+                            matches!(
+                                archived,
+                                Ok(ref response)
+                                    if matches!(response.kind, QueryResponseKind::ViewAccount(_))
+                            ),
+                            "{archived:?}"
+                        );
+                        let not_archived = query("unc1").await.unwrap();
+                        assert!(
+                            matches!(not_archived, Err(QueryError::GarbageCollectedBlock { .. })),
+                            "{not_archived:?}"
+                        );
+                        System::current().stop();
+                    });
+                }),
+                100,
+                60000,
+            )
+            .start();
+        });
+    });
+}
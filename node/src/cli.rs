@@ -20,6 +20,7 @@ use unc_epoch_sync_tool::EpochSyncCommand;
 use unc_flat_storage::commands::FlatStorageCommand;
 use unc_fork_network::cli::ForkNetworkCommand;
 use unc_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse;
+use unc_light_client_tool::LightClientCommand;
 use unc_mirror::MirrorCommand;
 use unc_network::tcp;
 use unc_o11y::tracing_subscriber::EnvFilter;
@@ -111,6 +112,9 @@ impl UncdCmd {
             UncdSubCommand::Ping(cmd) => {
                 cmd.run()?;
             }
+            UncdSubCommand::LightClient(cmd) => {
+                cmd.run()?;
+            }
             UncdSubCommand::Mirror(cmd) => {
                 cmd.run()?;
             }
@@ -248,6 +252,10 @@ pub(super) enum UncdSubCommand {
     /// us after the handshake is completed, printing stats to stdout.
     Ping(PingCommand),
 
+    /// Follows the chain as a light client from a trusted checkpoint, validating the
+    /// responses of an untrusted RPC node, and verifies execution proofs.
+    LightClient(LightClientCommand),
+
     /// Mirror transactions from a source chain to a test chain with state forked
     /// from it, reproducing traffic and state as closely as possible.
     Mirror(MirrorCommand),
@@ -554,9 +562,11 @@ impl RunCmd {
             let unc_infra::UncNode {
                 rpc_servers,
                 cold_store_loop_handle,
+                store_scrubber_handle,
                 state_sync_dump_handle,
                 flat_state_migration_handle,
                 resharding_handle,
+                shard_tries,
                 ..
             } = unc_infra::start_with_config_and_synchronization(
                 home_dir,
@@ -580,6 +590,9 @@ impl RunCmd {
             if let Some(handle) = cold_store_loop_handle {
                 handle.stop()
             }
+            if let Some(handle) = store_scrubber_handle {
+                handle.stop()
+            }
             if let Some(handle) = state_sync_dump_handle {
                 handle.stop()
             }
@@ -590,6 +603,9 @@ impl RunCmd {
                 debug!(target: "unc-node", "{} server stopped", name);
             }))
             .await;
+            if let Err(err) = shard_tries.save_mem_trie_snapshots() {
+                warn!(target: "unc-node", ?err, "Failed to save memtrie snapshots");
+            }
             actix::System::current().stop();
             // Disable the subscriber to properly shutdown the tracer.
             unc_o11y::reload(Some("error"), None, Some(unc_o11y::OpenTelemetryLevel::OFF)).unwrap();
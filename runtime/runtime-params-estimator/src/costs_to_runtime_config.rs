@@ -72,6 +72,7 @@ fn runtime_fees_config(cost_table: &CostTable) -> anyhow::Result<RuntimeFeesConf
             ActionCosts::new_data_receipt_byte => fee(Cost::DataReceiptCreationPerByte)?,
             ActionCosts::register_rsa2048_keys => fee(Cost::RegisterRsa2048Keys)?,
             ActionCosts::create_rsa2048_challenge => fee(Cost::CreateRsa2048Challenge)?,
+            ActionCosts::use_global_contract => fee(Cost::UseGlobalContract)?,
         },
         ..actual_fees_config.clone()
     };
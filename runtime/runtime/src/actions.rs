@@ -7,29 +7,35 @@ use crate::receipt_manager::ReceiptManager;
 use crate::{metrics, ActionResult, ApplyState};
 
 use unc_crypto::PublicKey;
-use unc_parameters::{ActionCosts, RuntimeConfig, RuntimeFeesConfig};
+use unc_parameters::{ActionCosts, RuntimeConfig, RuntimeFeesConfig, StorageUsageConfig};
 use unc_primitives::account::{AccessKey, AccessKeyPermission, Account};
 use unc_primitives::action::delegate::{DelegateAction, SignedDelegateAction};
 use unc_primitives::checked_feature;
 use unc_primitives::config::ViewConfig;
 use unc_primitives::errors::{ActionError, ActionErrorKind, InvalidAccessKeyError, RuntimeError};
 use unc_primitives::hash::CryptoHash;
-use unc_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
+use unc_primitives::receipt::{
+    ActionReceipt, DataReceipt, PromiseYieldTimeout, Receipt, ReceiptEnum,
+};
 use unc_primitives::transaction::{
     Action, AddKeyAction, CreateRsa2048ChallengeAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, FunctionCallAction, PledgeAction, RegisterRsa2048KeysAction,
-    TransferAction,
+    DeployContractAction, DeployGlobalContractAction, FunctionCallAction, PledgeAction,
+    RegisterRsa2048KeysAction, TransferAction, UseGlobalContractAction,
 };
 use unc_primitives::types::validator_power::ValidatorPower;
-use unc_primitives::types::{AccountId, BlockHeight, EpochInfoProvider, Gas, TrieCacheMode};
+use unc_primitives::types::{
+    AccountId, BlockHeight, EpochInfoProvider, Gas, StorageUsage, TrieCacheMode,
+};
 use unc_primitives::utils::{account_is_valid, create_random_seed};
 use unc_primitives::version::{
     ProtocolFeature, ProtocolVersion, DELETE_KEY_STORAGE_USAGE_PROTOCOL_VERSION,
 };
 use unc_primitives_core::account::id::AccountType;
 use unc_store::{
-    get_access_key, get_code, get_rsa2048_keys, remove_access_key, remove_account,
-    remove_rsa2048_keys, set_access_key, set_code, set_rsa2048_keys, StorageError, TrieUpdate,
+    enqueue_promise_yield_timeout, get_access_key, get_code, get_rsa2048_keys, has_global_code,
+    remove_access_key, remove_account, remove_code, remove_promise_yield_receipt,
+    remove_rsa2048_keys, set_access_key, set_code, set_global_code, set_promise_yield_receipt,
+    set_rsa2048_keys, StorageError, TrieUpdate,
 };
 use unc_vm_runner::logic::errors::{
     CompilationError, FunctionCallError, InconsistentStateError, VMRunnerError,
@@ -186,6 +192,22 @@ pub(crate) fn execute_function_call(
     Ok(outcome)
 }
 
+/// Returns storage usage charged to an account for the marker of a yielded promise which is
+/// awaiting its resume data.
+pub(crate) fn promise_yield_receipt_storage_usage(config: &StorageUsageConfig) -> StorageUsage {
+    (std::mem::size_of::<CryptoHash>() + std::mem::size_of::<BlockHeight>()) as u64
+        + config.num_extra_bytes_record
+}
+
+/// Returns storage usage charged to an account for the entry of its yielded promise in the
+/// timeout queue.
+pub(crate) fn promise_yield_timeout_storage_usage(
+    config: &StorageUsageConfig,
+    timeout: &PromiseYieldTimeout,
+) -> StorageUsage {
+    borsh::object_length(timeout).unwrap() as u64 + config.num_extra_bytes_record
+}
+
 pub(crate) fn action_function_call(
     state_update: &mut TrieUpdate,
     apply_state: &ApplyState,
@@ -308,10 +330,52 @@ pub(crate) fn action_function_call(
             })
             .collect();
 
+        // Yielded receipts are marked as awaiting their resume data until they are either
+        // resumed or time out.  The account pays for the storage of the marker until the yield
+        // is resumed and for its entry in the timeout queue until the yield times out.
+        let storage_config = &config.fees.storage_usage_config;
+        let mut storage_usage = outcome.storage_usage;
+        let expires_at = apply_state
+            .block_height
+            .saturating_add(config.wasm_config.limit_config.yield_timeout_length_in_blocks);
+        for data_id in receipt_manager.promise_yields {
+            let timeout =
+                PromiseYieldTimeout { account_id: account_id.clone(), data_id, expires_at };
+            set_promise_yield_receipt(state_update, account_id, data_id, expires_at);
+            enqueue_promise_yield_timeout(state_update, &timeout)?;
+            storage_usage = storage_usage
+                .checked_add(
+                    promise_yield_receipt_storage_usage(storage_config)
+                        + promise_yield_timeout_storage_usage(storage_config, &timeout),
+                )
+                .ok_or_else(|| {
+                    StorageError::StorageInconsistentState(format!(
+                        "Storage usage integer overflow for account {}",
+                        account_id
+                    ))
+                })?;
+        }
+        let resume_receipts: Vec<_> = receipt_manager
+            .promise_resumes
+            .into_iter()
+            .map(|(data_id, data)| {
+                remove_promise_yield_receipt(state_update, account_id, data_id);
+                storage_usage = storage_usage
+                    .saturating_sub(promise_yield_receipt_storage_usage(storage_config));
+                Receipt {
+                    predecessor_id: account_id.clone(),
+                    receiver_id: account_id.clone(),
+                    receipt_id: CryptoHash::default(),
+                    receipt: ReceiptEnum::Data(DataReceipt { data_id, data: Some(data) }),
+                }
+            })
+            .collect();
+
         account.set_amount(outcome.balance);
-        account.set_storage_usage(outcome.storage_usage);
+        account.set_storage_usage(storage_usage);
         result.result = Ok(outcome.return_data);
         result.new_receipts.extend(new_receipts);
+        result.new_receipts.extend(resume_receipts);
     }
 
     Ok(())
@@ -536,6 +600,57 @@ pub(crate) fn action_deploy_contract(
     Ok(())
 }
 
+pub(crate) fn action_deploy_global_contract(
+    state_update: &mut TrieUpdate,
+    account: &mut Account,
+    account_id: &AccountId,
+    deploy_global_contract: &DeployGlobalContractAction,
+    apply_state: &ApplyState,
+) -> Result<(), StorageError> {
+    let _span = tracing::debug_span!(target: "runtime", "action_deploy_global_contract").entered();
+    let code = ContractCode::new(deploy_global_contract.code.clone(), None);
+    // The code is stored once per hash, only the first deployer pays for its storage.
+    if !has_global_code(state_update, *code.hash())? {
+        account.set_storage_usage(
+            account.storage_usage().checked_add(code.code().len() as u64).ok_or_else(|| {
+                StorageError::StorageInconsistentState(format!(
+                    "Storage usage integer overflow for account {}",
+                    account_id
+                ))
+            })?,
+        );
+        set_global_code(state_update, &code);
+    }
+    // The compiled contract is cached by code hash, so every account using this global contract
+    // shares the compilation done here.
+    precompile_contract(&code, &apply_state.config.wasm_config, apply_state.cache.as_deref()).ok();
+    Ok(())
+}
+
+pub(crate) fn action_use_global_contract(
+    state_update: &mut TrieUpdate,
+    account: &mut Account,
+    result: &mut ActionResult,
+    account_id: &AccountId,
+    use_global_contract: &UseGlobalContractAction,
+) -> Result<(), StorageError> {
+    let _span = tracing::debug_span!(target: "runtime", "action_use_global_contract").entered();
+    let code_hash = use_global_contract.code_hash;
+    if !has_global_code(state_update, code_hash)? {
+        result.result = Err(ActionErrorKind::GlobalContractDoesNotExist { code_hash }.into());
+        return Ok(());
+    }
+    // The account stops paying for the code deployed on it, the global code is paid for by its
+    // deployer.
+    if let Some(prev_code) = get_code(state_update, account_id, Some(account.code_hash()))? {
+        let prev_code_length = prev_code.code().len() as u64;
+        account.set_storage_usage(account.storage_usage().saturating_sub(prev_code_length));
+        remove_code(state_update, account_id);
+    }
+    account.set_code_hash(code_hash);
+    Ok(())
+}
+
 pub(crate) fn action_delete_account(
     state_update: &mut TrieUpdate,
     account: &mut Option<Account>,
@@ -1013,7 +1128,9 @@ pub(crate) fn check_actor_permissions(
         Action::DeployContract(_)
         | Action::Pledge(_)
         | Action::AddKey(_)
-        | Action::DeleteKey(_) => {
+        | Action::DeleteKey(_)
+        | Action::DeployGlobalContract(_)
+        | Action::UseGlobalContract(_) => {
             if actor_id != account_id {
                 return Err(ActionErrorKind::ActorNoPermission {
                     account_id: account_id.clone(),
@@ -1120,7 +1237,9 @@ pub(crate) fn check_account_existence(
         | Action::DeleteKey(_)
         | Action::DeleteAccount(_)
         | Action::RegisterRsa2048Keys(_)
-        | Action::CreateRsa2048Challenge(_) => {
+        | Action::CreateRsa2048Challenge(_)
+        | Action::DeployGlobalContract(_)
+        | Action::UseGlobalContract(_) => {
             if account.is_none() {
                 return Err(ActionErrorKind::AccountDoesNotExist {
                     account_id: account_id.clone(),
@@ -1154,8 +1273,8 @@ mod tests {
     use unc_primitives::transaction::CreateAccountAction;
     use unc_primitives::trie_key::TrieKey;
     use unc_primitives::types::{EpochId, StateChangeCause};
-    use unc_store::set_account;
     use unc_store::test_utils::TestTriesBuilder;
+    use unc_store::{get_code_or_global_code, set_account};
 
     fn test_action_create_account(
         account_id: AccountId,
@@ -1311,6 +1430,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_use_global_contract() {
+        let tries = TestTriesBuilder::new().build();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let apply_state = create_apply_state(1);
+        let global_code = [1; 100].to_vec();
+        let global_code_hash = hash(&global_code);
+        let deploy_action = DeployGlobalContractAction { code: global_code.clone() };
+
+        let deployer_id = "alice".parse::<AccountId>().unwrap();
+        let mut deployer = Account::new(100, 0, 0, CryptoHash::default(), 100);
+        action_deploy_global_contract(
+            &mut state_update,
+            &mut deployer,
+            &deployer_id,
+            &deploy_action,
+            &apply_state,
+        )
+        .unwrap();
+        assert_eq!(deployer.storage_usage(), 200);
+        assert_eq!(deployer.code_hash(), CryptoHash::default());
+
+        // The code is stored once, deploying it again doesn't charge for its storage.
+        let other_deployer_id = "bob".parse::<AccountId>().unwrap();
+        let mut other_deployer = Account::new(100, 0, 0, CryptoHash::default(), 100);
+        action_deploy_global_contract(
+            &mut state_update,
+            &mut other_deployer,
+            &other_deployer_id,
+            &deploy_action,
+            &apply_state,
+        )
+        .unwrap();
+        assert_eq!(other_deployer.storage_usage(), 100);
+
+        let user_id = "carol".parse::<AccountId>().unwrap();
+        let own_code = [2; 50].to_vec();
+        state_update.set(TrieKey::ContractCode { account_id: user_id.clone() }, own_code.clone());
+        let mut user = Account::new(100, 0, 0, hash(&own_code), 150);
+        let mut action_result = ActionResult::default();
+        action_use_global_contract(
+            &mut state_update,
+            &mut user,
+            &mut action_result,
+            &user_id,
+            &UseGlobalContractAction { code_hash: global_code_hash },
+        )
+        .unwrap();
+        assert!(action_result.result.is_ok());
+        assert_eq!(user.storage_usage(), 100);
+        assert_eq!(user.code_hash(), global_code_hash);
+        assert!(get_code(&state_update, &user_id, None).unwrap().is_none());
+        let code = get_code_or_global_code(&state_update, &user_id, user.code_hash()).unwrap();
+        assert_eq!(code.unwrap().code(), global_code.as_slice());
+    }
+
+    #[test]
+    fn test_use_global_contract_does_not_exist() {
+        let tries = TestTriesBuilder::new().build();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let account_id = "alice".parse::<AccountId>().unwrap();
+        let mut account = Account::new(100, 0, 0, CryptoHash::default(), 100);
+        let code_hash = hash(b"missing");
+        let mut action_result = ActionResult::default();
+        action_use_global_contract(
+            &mut state_update,
+            &mut account,
+            &mut action_result,
+            &account_id,
+            &UseGlobalContractAction { code_hash },
+        )
+        .unwrap();
+        assert_eq!(
+            action_result.result,
+            Err(ActionError {
+                index: None,
+                kind: ActionErrorKind::GlobalContractDoesNotExist { code_hash }
+            })
+        );
+        assert_eq!(account.code_hash(), CryptoHash::default());
+    }
+
     fn create_delegate_action_receipt() -> (ActionReceipt, SignedDelegateAction) {
         let signed_delegate_action = SignedDelegateAction {
             delegate_action: DelegateAction {
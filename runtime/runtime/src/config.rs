@@ -8,7 +8,9 @@ use unc_primitives::errors::IntegerOverflowError;
 // Just re-exporting RuntimeConfig for backwards compatibility.
 use unc_parameters::{transfer_exec_fee, transfer_send_fee, ActionCosts, RuntimeConfig};
 pub use unc_primitives::num_rational::Rational32;
-use unc_primitives::transaction::{Action, DeployContractAction, Transaction};
+use unc_primitives::transaction::{
+    Action, DeployContractAction, DeployGlobalContractAction, Transaction,
+};
 use unc_primitives::types::{AccountId, Balance, Compute, Gas};
 
 /// Describes the cost of converting this transaction into a receipt.
@@ -141,6 +143,15 @@ pub fn total_send_fees(
             CreateRsa2048Challenge(_) => {
                 fees.fee(ActionCosts::create_rsa2048_challenge).send_fee(sender_is_receiver)
             }
+            DeployGlobalContract(DeployGlobalContractAction { code }) => {
+                let num_bytes = code.len() as u64;
+                fees.fee(ActionCosts::deploy_contract_base).send_fee(sender_is_receiver)
+                    + fees.fee(ActionCosts::deploy_contract_byte).send_fee(sender_is_receiver)
+                        * num_bytes
+            }
+            UseGlobalContract(_) => {
+                fees.fee(ActionCosts::use_global_contract).send_fee(sender_is_receiver)
+            }
         };
         result = safe_add_gas(result, delta)?;
     }
@@ -224,6 +235,12 @@ pub fn exec_fee(config: &RuntimeConfig, action: &Action, receiver_id: &AccountId
         Delegate(_) => fees.fee(ActionCosts::delegate).exec_fee(),
         RegisterRsa2048Keys(_) => fees.fee(ActionCosts::register_rsa2048_keys).exec_fee(),
         CreateRsa2048Challenge(_) => fees.fee(ActionCosts::create_rsa2048_challenge).exec_fee(),
+        DeployGlobalContract(DeployGlobalContractAction { code }) => {
+            let num_bytes = code.len() as u64;
+            fees.fee(ActionCosts::deploy_contract_base).exec_fee()
+                + fees.fee(ActionCosts::deploy_contract_byte).exec_fee() * num_bytes
+        }
+        UseGlobalContract(_) => fees.fee(ActionCosts::use_global_contract).exec_fee(),
     }
 }
 
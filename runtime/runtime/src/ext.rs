@@ -1,4 +1,5 @@
 use crate::receipt_manager::ReceiptManager;
+use unc_primitives::checked_feature;
 use unc_primitives::errors::{EpochError, StorageError};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::trie_key::{trie_key_parsers, TrieKey};
@@ -8,7 +9,10 @@ use unc_primitives::types::{
 use unc_primitives::utils::create_data_id;
 use unc_primitives::version::ProtocolVersion;
 use unc_primitives_core::types::Power;
-use unc_store::{get_code, KeyLookupMode, TrieUpdate, TrieUpdateValuePtr};
+use unc_store::{
+    get_code, get_code_or_global_code, has_promise_yield_receipt, KeyLookupMode, TrieUpdate,
+    TrieUpdateValuePtr,
+};
 use unc_vm_runner::logic::errors::{AnyError, VMLogicError};
 use unc_vm_runner::logic::types::ReceiptIndex;
 use unc_vm_runner::logic::{External, StorageGetMode, ValuePtr};
@@ -87,7 +91,11 @@ impl<'a> RuntimeExt<'a> {
     }
 
     pub fn get_code(&self, code_hash: CryptoHash) -> Result<Option<ContractCode>, StorageError> {
-        get_code(self.trie_update, self.account_id, Some(code_hash))
+        if checked_feature!("stable", GlobalContracts, self.current_protocol_version) {
+            get_code_or_global_code(self.trie_update, self.account_id, code_hash)
+        } else {
+            get_code(self.trie_update, self.account_id, Some(code_hash))
+        }
     }
 
     pub fn create_storage_key(&self, key: &[u8]) -> TrieKey {
@@ -225,6 +233,32 @@ impl<'a> External for RuntimeExt<'a> {
         self.receipt_manager.create_receipt(data_ids, receipt_indices, receiver_id)
     }
 
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash), VMLogicError> {
+        let data_id = self.generate_data_id();
+        let receipt_index = self.receipt_manager.create_promise_yield_receipt(data_id, receiver_id);
+        Ok((receipt_index, data_id))
+    }
+
+    fn submit_promise_resume_data(
+        &mut self,
+        data_id: CryptoHash,
+        data: Vec<u8>,
+    ) -> Result<bool, VMLogicError> {
+        if self.receipt_manager.is_promise_resumed(&data_id) {
+            return Ok(false);
+        }
+        let is_yielded = self.receipt_manager.is_promise_yield(&data_id)
+            || has_promise_yield_receipt(self.trie_update, self.account_id, data_id)
+                .map_err(wrap_storage_error)?;
+        if is_yielded {
+            self.receipt_manager.submit_promise_resume_data(data_id, data);
+        }
+        Ok(is_yielded)
+    }
+
     fn append_action_create_account(
         &mut self,
         receipt_index: ReceiptIndex,
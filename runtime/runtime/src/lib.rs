@@ -18,7 +18,8 @@ use unc_primitives::checked_feature;
 use unc_primitives::errors::{ActionError, ActionErrorKind, RuntimeError, TxExecutionError};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::receipt::{
-    ActionReceipt, DataReceipt, DelayedReceiptIndices, Receipt, ReceiptEnum, ReceivedData,
+    ActionReceipt, DataReceipt, DelayedReceiptIndices, PromiseYieldIndices, PromiseYieldTimeout,
+    Receipt, ReceiptEnum, ReceivedData,
 };
 pub use unc_primitives::runtime::apply_state::ApplyState;
 use unc_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
@@ -39,15 +40,17 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::debug;
 use unc_primitives::utils::{
-    create_action_hash, create_receipt_id_from_receipt, create_receipt_id_from_transaction,
+    create_action_hash, create_receipt_id_from_promise_yield_timeout,
+    create_receipt_id_from_receipt, create_receipt_id_from_transaction,
 };
 use unc_primitives::version::{ProtocolFeature, ProtocolVersion};
 use unc_store::{
-    get, get_account, get_postponed_receipt, get_received_data, remove_postponed_receipt, set,
-    set_account, set_delayed_receipt, set_postponed_receipt, set_received_data, PartialStorage,
-    StorageError, Trie, TrieChanges, TrieUpdate,
+    get, get_account, get_postponed_receipt, get_received_data, has_promise_yield_receipt,
+    remove_postponed_receipt, remove_promise_yield_receipt, set, set_account, set_delayed_receipt,
+    set_postponed_receipt, set_received_data, PartialStorage, StorageError, Trie, TrieChanges,
+    TrieUpdate,
 };
-use unc_store::{set_access_key, set_code};
+use unc_store::{set_access_key, set_code, set_global_code};
 use unc_vm_runner::logic::types::PromiseResult;
 use unc_vm_runner::logic::ReturnData;
 pub use unc_vm_runner::with_ext_cost_counter;
@@ -473,6 +476,24 @@ impl Runtime {
                     create_rsa2048_challenge,
                 )?;
             }
+            Action::DeployGlobalContract(deploy_global_contract) => {
+                action_deploy_global_contract(
+                    state_update,
+                    account.as_mut().expect(EXPECT_ACCOUNT_EXISTS),
+                    account_id,
+                    deploy_global_contract,
+                    apply_state,
+                )?;
+            }
+            Action::UseGlobalContract(use_global_contract) => {
+                action_use_global_contract(
+                    state_update,
+                    account.as_mut().expect(EXPECT_ACCOUNT_EXISTS),
+                    &mut result,
+                    account_id,
+                    use_global_contract,
+                )?;
+            }
         };
         Ok(result)
     }
@@ -1212,6 +1233,81 @@ impl Runtime {
         Ok((gas_used, receipts_to_restore))
     }
 
+    /// Resolves the yielded promises whose timeout has expired by the current block height.
+    ///
+    /// Every yield which has not been resumed yet gets resolved with an outgoing `DataReceipt`
+    /// without data, so the yielded function call is executed with a failed promise result.
+    /// Each processed timeout is charged as sending a data receipt and processing stops once
+    /// the chunk runs out of compute; the remaining timeouts stay in the queue for the next
+    /// chunks.  The storage the yield occupied is released from the account's storage usage.
+    fn process_promise_yield_timeouts(
+        &self,
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        outgoing_receipts: &mut Vec<Receipt>,
+        total_gas_burnt: &mut Gas,
+        total_compute_usage: &mut Compute,
+        compute_limit: Compute,
+    ) -> Result<(), RuntimeError> {
+        let storage_config = &apply_state.config.fees.storage_usage_config;
+        let timeout_cost = apply_state.config.fees.fee(ActionCosts::new_data_receipt_base);
+        let timeout_gas = safe_add_gas(timeout_cost.send_fee(true), timeout_cost.exec_fee())?;
+        let mut promise_yield_indices: PromiseYieldIndices =
+            get(state_update, &TrieKey::PromiseYieldIndices)?.unwrap_or_default();
+        let initial_promise_yield_indices = promise_yield_indices.clone();
+        while promise_yield_indices.first_index < promise_yield_indices.next_available_index {
+            if *total_compute_usage >= compute_limit {
+                break;
+            }
+            let key = TrieKey::PromiseYieldTimeout { index: promise_yield_indices.first_index };
+            let timeout: PromiseYieldTimeout = get(state_update, &key)?.ok_or_else(|| {
+                StorageError::StorageInconsistentState(format!(
+                    "PromiseYield timeout #{} should be in the state",
+                    promise_yield_indices.first_index
+                ))
+            })?;
+            // The queue is ordered by expiration height.
+            if timeout.expires_at > apply_state.block_height {
+                break;
+            }
+            let mut released_storage =
+                promise_yield_timeout_storage_usage(storage_config, &timeout);
+            // The yield may have already been resumed, in which case the marker is gone.
+            if has_promise_yield_receipt(state_update, &timeout.account_id, timeout.data_id)? {
+                remove_promise_yield_receipt(state_update, &timeout.account_id, timeout.data_id);
+                released_storage += promise_yield_receipt_storage_usage(storage_config);
+                outgoing_receipts.push(Receipt {
+                    predecessor_id: timeout.account_id.clone(),
+                    receiver_id: timeout.account_id.clone(),
+                    receipt_id: create_receipt_id_from_promise_yield_timeout(
+                        apply_state.current_protocol_version,
+                        &timeout.data_id,
+                        &apply_state.prev_block_hash,
+                        &apply_state.block_hash,
+                    ),
+                    receipt: ReceiptEnum::Data(DataReceipt {
+                        data_id: timeout.data_id,
+                        data: None,
+                    }),
+                });
+            }
+            // The account may have been deleted in the meantime.
+            if let Some(mut account) = get_account(state_update, &timeout.account_id)? {
+                account.set_storage_usage(account.storage_usage().saturating_sub(released_storage));
+                set_account(state_update, timeout.account_id, &account);
+            }
+            state_update.remove(key);
+            promise_yield_indices.first_index += 1;
+            *total_gas_burnt = safe_add_gas(*total_gas_burnt, timeout_gas)?;
+            *total_compute_usage = safe_add_compute(*total_compute_usage, timeout_gas)?;
+        }
+        if promise_yield_indices != initial_promise_yield_indices {
+            set(state_update, TrieKey::PromiseYieldIndices, &promise_yield_indices);
+        }
+        state_update.commit(StateChangeCause::UpdatedPromiseYieldTimeouts);
+        Ok(())
+    }
+
     /// Applies new signed transactions and incoming receipts for some chunk/shard on top of
     /// given trie and the given state root.
     /// If the validator accounts update is provided, updates validators accounts.
@@ -1501,6 +1597,17 @@ impl Runtime {
             prefetcher.clear();
         }
 
+        if checked_feature!("stable", YieldExecution, apply_state.current_protocol_version) {
+            self.process_promise_yield_timeouts(
+                &mut state_update,
+                apply_state,
+                &mut outgoing_receipts,
+                &mut total_gas_burnt,
+                &mut total_compute_usage,
+                compute_limit,
+            )?;
+        }
+
         if delayed_receipts_indices != initial_delayed_receipt_indices {
             set(&mut state_update, TrieKey::DelayedReceiptIndices, &delayed_receipts_indices);
         }
@@ -1580,7 +1687,12 @@ impl Runtime {
                 StateRecord::AccessKey { account_id, public_key, access_key } => {
                     set_access_key(state_update, account_id, public_key, &access_key);
                 }
-                _ => unimplemented!("patch_state can only patch Account, AccessKey, Contract and Data kind of StateRecord")
+                StateRecord::GlobalContractCode { code_hash, code } => {
+                    let code = ContractCode::new(code, None);
+                    assert_eq!(*code.hash(), code_hash);
+                    set_global_code(state_update, &code);
+                }
+                _ => unimplemented!("patch_state can only patch Account, AccessKey, Contract, GlobalContractCode and Data kind of StateRecord")
             }
         }
         state_update.commit(StateChangeCause::Migration);
@@ -1592,7 +1704,7 @@ mod tests {
     use assert_matches::assert_matches;
     use testlib::runtime_utils::{alice_account, bob_account};
     use unc_crypto::{InMemorySigner, KeyType, PublicKey, Signer};
-    use unc_parameters::{ExtCosts, ParameterCost, RuntimeConfig};
+    use unc_parameters::{ExtCosts, ParameterCost, RuntimeConfig, RuntimeConfigStore};
     use unc_primitives::account::AccessKey;
     use unc_primitives::hash::hash;
     use unc_primitives::shard_layout::ShardUId;
@@ -2548,8 +2660,10 @@ mod tests {
         store_update.commit().unwrap();
 
         let contract_code = unc_vm_runner::ContractCode::new(wasm_code, None);
-        let key =
-            unc_vm_runner::get_contract_cache_key(&contract_code, &apply_state.config.wasm_config);
+        let key = unc_vm_runner::get_contract_cache_key(
+            *contract_code.hash(),
+            &apply_state.config.wasm_config,
+        );
         apply_state
             .cache
             .unwrap()
@@ -2699,6 +2813,170 @@ mod tests {
             assert_matches!(second.outcome.status, ExecutionStatus::Failure(_));
         });
     }
+
+    /// Contract yielding calls to `callback` which returns the payload the call was resumed
+    /// with or `timeout` if the yield has timed out.
+    fn yield_contract() -> Vec<u8> {
+        unc_test_contracts::wat_contract(
+            r#"(module
+              (import "env" "input" (func $input (param i64)))
+              (import "env" "value_return" (func $value_return (param i64 i64)))
+              (import "env" "promise_result" (func $promise_result (param i64 i64) (result i64)))
+              (import "env" "promise_yield_create"
+                (func $promise_yield_create (param i64 i64 i64 i64 i64 i64 i64) (result i64)))
+              (import "env" "promise_yield_resume"
+                (func $promise_yield_resume (param i64 i64 i64 i64) (result i32)))
+              (memory 1)
+              (data (i32.const 0) "callback")
+              (data (i32.const 8) "payload")
+              (data (i32.const 16) "timeout")
+              ;; Yields a call to `callback` and returns the data id resuming it.
+              (func (export "yield")
+                (drop (call $promise_yield_create
+                  (i64.const 8) (i64.const 0) (i64.const 0) (i64.const 0)
+                  (i64.const 10000000000000) (i64.const 0) (i64.const 0)))
+                (call $value_return (i64.const -1) (i64.const 0)))
+              ;; Resumes the yield with the data id given as input.
+              (func (export "resume")
+                (call $input (i64.const 0))
+                (drop (call $promise_yield_resume
+                  (i64.const -1) (i64.const 0) (i64.const 7) (i64.const 8))))
+              (func (export "callback")
+                (if (i64.eq (call $promise_result (i64.const 0) (i64.const 0)) (i64.const 1))
+                  (then (call $value_return (i64.const -1) (i64.const 0)))
+                  (else (call $value_return (i64.const 7) (i64.const 16))))))"#,
+        )
+    }
+
+    #[test]
+    fn test_promise_yield_resume_and_timeout() {
+        let (runtime, tries, mut root, mut apply_state, signer, epoch_info_provider) =
+            setup_runtime(to_atto(1_000_000), 0, 0, 10u64.pow(15));
+        let protocol_version = ProtocolFeature::YieldExecution.protocol_version();
+        let mut config = RuntimeConfig::test();
+        config.wasm_config =
+            RuntimeConfigStore::new(None).get_config(protocol_version).wasm_config.clone();
+        config.wasm_config.limit_config.yield_timeout_length_in_blocks = 3;
+        apply_state.config = Arc::new(config);
+        apply_state.current_protocol_version = protocol_version;
+
+        let mut apply = |apply_state: &ApplyState, receipts: &[Receipt]| {
+            let apply_result = runtime
+                .apply(
+                    tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                    &None,
+                    apply_state,
+                    receipts,
+                    &[],
+                    &epoch_info_provider,
+                    Default::default(),
+                )
+                .unwrap();
+            let mut store_update = tries.store_update();
+            root = tries.apply_all(
+                &apply_result.trie_changes,
+                ShardUId::single_shard(),
+                &mut store_update,
+            );
+            store_update.commit().unwrap();
+            let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+            let storage_usage =
+                get_account(&state_update, &alice_account()).unwrap().unwrap().storage_usage();
+            let indices: PromiseYieldIndices =
+                get(&state_update, &TrieKey::PromiseYieldIndices).unwrap().unwrap_or_default();
+            (apply_result, storage_usage, indices)
+        };
+        let call = |method_name: &str, args: Vec<u8>| {
+            create_receipt_with_actions(
+                alice_account(),
+                signer.clone(),
+                vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                    method_name: method_name.to_string(),
+                    args,
+                    gas: 100 * 10u64.pow(12),
+                    deposit: 0,
+                }))],
+            )
+        };
+        let returned = |apply_result: &ApplyResult, value: &[u8]| {
+            apply_result.outcomes.iter().any(|outcome| {
+                outcome.outcome.status == ExecutionStatus::SuccessValue(value.to_vec())
+            })
+        };
+
+        let deploy = create_receipt_with_actions(
+            alice_account(),
+            signer.clone(),
+            vec![Action::DeployContract(DeployContractAction { code: yield_contract() })],
+        );
+        let (_, initial_storage_usage, _) = apply(&apply_state, &[deploy]);
+
+        // Yield two calls; the first one gets resumed and the second one times out.
+        apply_state.block_height += 1;
+        let (first, second) = (call("yield", b"first".to_vec()), call("yield", b"second".to_vec()));
+        let (apply_result, storage_usage, indices) =
+            apply(&apply_state, &[first.clone(), second.clone()]);
+        let data_id = |receipt: &Receipt| {
+            let outcome =
+                apply_result.outcomes.iter().find(|outcome| outcome.id == receipt.receipt_id);
+            assert_matches!(
+                &outcome.unwrap().outcome.status,
+                ExecutionStatus::SuccessValue(data_id) => data_id.clone()
+            )
+        };
+        let (first_data_id, second_data_id) = (data_id(&first), data_id(&second));
+        let storage_config = &apply_state.config.fees.storage_usage_config;
+        let marker_storage_usage = promise_yield_receipt_storage_usage(storage_config);
+        let timeout_storage_usage = promise_yield_timeout_storage_usage(
+            storage_config,
+            &PromiseYieldTimeout {
+                account_id: alice_account(),
+                data_id: CryptoHash::try_from(first_data_id.as_slice()).unwrap(),
+                expires_at: apply_state.block_height + 3,
+            },
+        );
+        assert_eq!(
+            storage_usage,
+            initial_storage_usage + 2 * (marker_storage_usage + timeout_storage_usage)
+        );
+        assert_eq!(indices.len(), 2);
+
+        apply_state.block_height += 1;
+        let mut receipts = apply_result.outgoing_receipts;
+        receipts.push(call("resume", first_data_id));
+        let (apply_result, storage_usage, _) = apply(&apply_state, &receipts);
+        assert_eq!(
+            storage_usage,
+            initial_storage_usage + marker_storage_usage + 2 * timeout_storage_usage
+        );
+
+        apply_state.block_height += 1;
+        let (apply_result, _, _) = apply(&apply_state, &apply_result.outgoing_receipts);
+        assert!(returned(&apply_result, b"payload"));
+
+        // The yields expire, but the chunk has no compute left to process the timeouts.
+        apply_state.block_height += 1;
+        apply_state.gas_limit = Some(0);
+        let (apply_result, _, indices) = apply(&apply_state, &apply_result.outgoing_receipts);
+        assert_eq!(indices.len(), 2);
+        assert!(apply_result.outgoing_receipts.is_empty());
+
+        // The timeouts are carried over to the next chunk.
+        apply_state.block_height += 1;
+        apply_state.gas_limit = Some(10u64.pow(15));
+        let (apply_result, storage_usage, indices) = apply(&apply_state, &[]);
+        assert_eq!(indices.len(), 0);
+        assert_eq!(storage_usage, initial_storage_usage);
+        assert_matches!(
+            &apply_result.outgoing_receipts[..],
+            [Receipt { receipt: ReceiptEnum::Data(DataReceipt { data_id, data: None }), .. }]
+                if data_id.as_ref() == second_data_id.as_slice()
+        );
+
+        apply_state.block_height += 1;
+        let (apply_result, _, _) = apply(&apply_state, &apply_result.outgoing_receipts);
+        assert!(returned(&apply_result, b"timeout"));
+    }
 }
 
 /// Interface provided for gas cost estimations.
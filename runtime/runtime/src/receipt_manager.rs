@@ -36,6 +36,10 @@ pub struct ReceiptMetadata {
 pub struct ReceiptManager {
     pub(super) action_receipts: ActionReceipts,
     pub(super) gas_weights: Vec<(FunctionCallActionIndex, GasWeight)>,
+    /// `data_id`s of the yielded receipts created in this function call.
+    pub(super) promise_yields: Vec<CryptoHash>,
+    /// `data_id`s and payloads of the yielded receipts resumed in this function call.
+    pub(super) promise_resumes: Vec<(CryptoHash, Vec<u8>)>,
 }
 
 /// Indexes the [`ReceiptManager`]'s action receipts and actions.
@@ -104,6 +108,46 @@ impl ReceiptManager {
         Ok(new_receipt_index)
     }
 
+    /// Create a receipt to `receiver_id` which is postponed until the data with the given
+    /// `data_id` is submitted through [`ReceiptManager::submit_promise_resume_data`] or the
+    /// yield times out.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_id` - the data id the new receipt is waiting for
+    /// * `receiver_id` - account id of the receiver of the receipt created
+    pub(super) fn create_promise_yield_receipt(
+        &mut self,
+        data_id: CryptoHash,
+        receiver_id: AccountId,
+    ) -> ReceiptIndex {
+        let new_receipt = ReceiptMetadata {
+            output_data_receivers: vec![],
+            input_data_ids: vec![data_id],
+            actions: vec![],
+        };
+        let new_receipt_index = self.action_receipts.len() as ReceiptIndex;
+        self.action_receipts.push((receiver_id, new_receipt));
+        self.promise_yields.push(data_id);
+        new_receipt_index
+    }
+
+    /// Returns whether a yielded receipt waiting for `data_id` was created in this function call.
+    pub(super) fn is_promise_yield(&self, data_id: &CryptoHash) -> bool {
+        self.promise_yields.contains(data_id)
+    }
+
+    /// Returns whether the yielded receipt waiting for `data_id` was already resumed in this
+    /// function call.
+    pub(super) fn is_promise_resumed(&self, data_id: &CryptoHash) -> bool {
+        self.promise_resumes.iter().any(|(resumed, _)| resumed == data_id)
+    }
+
+    /// Records the payload resuming the yielded receipt waiting for `data_id`.
+    pub(super) fn submit_promise_resume_data(&mut self, data_id: CryptoHash, data: Vec<u8>) {
+        self.promise_resumes.push((data_id, data));
+    }
+
     /// Attach the [`CreateAccountAction`] action to an existing receipt.
     ///
     /// # Arguments
@@ -8,16 +8,18 @@ use unc_crypto::{KeyType, PublicKey};
 use unc_parameters::RuntimeConfigStore;
 use unc_primitives::account::{AccessKey, Account};
 use unc_primitives::borsh::BorshDeserialize;
+use unc_primitives::errors::StorageError;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::receipt::ActionReceipt;
 use unc_primitives::runtime::apply_state::ApplyState;
 use unc_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use unc_primitives::transaction::FunctionCallAction;
-use unc_primitives::trie_key::trie_key_parsers;
+use unc_primitives::trie_key::{trie_key_parsers, TrieKey};
 use unc_primitives::types::{AccountId, EpochInfoProvider, Gas};
 use unc_primitives::views::{ChipView, StateItem, ViewApplyState, ViewStateResult};
 use unc_primitives_core::config::ViewConfig;
-use unc_store::{get_access_key, get_account, get_code, TrieUpdate};
+use unc_store::flat::FlatStateHistoryView;
+use unc_store::{get_access_key, get_account, get_code, get_code_or_global_code, TrieUpdate};
 use unc_vm_runner::logic::ReturnData;
 use unc_vm_runner::ContractCode;
 
@@ -58,13 +60,27 @@ impl TrieViewer {
         })
     }
 
+    /// Same as `view_account`, but reads the account from flat storage history.
+    pub fn view_account_at(
+        &self,
+        view: &FlatStateHistoryView,
+        account_id: &AccountId,
+    ) -> Result<Account, errors::ViewAccountError> {
+        get_account_at(view, account_id)?.ok_or_else(|| {
+            errors::ViewAccountError::AccountDoesNotExist {
+                requested_account_id: account_id.clone(),
+            }
+        })
+    }
+
     pub fn view_contract_code(
         &self,
         state_update: &TrieUpdate,
         account_id: &AccountId,
     ) -> Result<ContractCode, errors::ViewContractCodeError> {
         let account = self.view_account(state_update, account_id)?;
-        get_code(state_update, account_id, Some(account.code_hash()))?.ok_or_else(|| {
+        // Accounts using a global contract have no code of their own.
+        get_code_or_global_code(state_update, account_id, account.code_hash())?.ok_or_else(|| {
             errors::ViewContractCodeError::NoContractCode {
                 contract_account_id: account_id.clone(),
             }
@@ -266,6 +282,47 @@ impl TrieViewer {
         Ok(ViewStateResult { values, proof })
     }
 
+    /// Same as `view_state` without proof, but reads the state from flat
+    /// storage history.
+    pub fn view_state_at(
+        &self,
+        view: &FlatStateHistoryView,
+        account_id: &AccountId,
+        prefix: &[u8],
+    ) -> Result<ViewStateResult, errors::ViewStateError> {
+        match get_account_at(view, account_id)? {
+            Some(account) => {
+                let code_key = TrieKey::ContractCode { account_id: account_id.clone() }.to_vec();
+                let code_len = view
+                    .get_value(&code_key)
+                    .map_err(StorageError::from)?
+                    .map(|value| value.value_len() as u64)
+                    .unwrap_or_default();
+                if let Some(limit) = self.state_size_limit {
+                    if account.storage_usage().saturating_sub(code_len) > limit {
+                        return Err(errors::ViewStateError::AccountStateTooLarge {
+                            requested_account_id: account_id.clone(),
+                        });
+                    }
+                }
+            }
+            None => {
+                return Err(errors::ViewStateError::AccountDoesNotExist {
+                    requested_account_id: account_id.clone(),
+                })
+            }
+        };
+
+        let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
+        let acc_sep_len = query.len() - prefix.len();
+        let mut values = vec![];
+        for (key, value) in view.iter_prefix(&query).map_err(StorageError::from)? {
+            let value = view.value_bytes(value).map_err(StorageError::from)?;
+            values.push(StateItem { key: key[acc_sep_len..].to_vec().into(), value: value.into() });
+        }
+        Ok(ViewStateResult { values, proof: vec![] })
+    }
+
     pub fn call_function(
         &self,
         mut state_update: TrieUpdate,
@@ -368,3 +425,18 @@ impl TrieViewer {
         }
     }
 }
+
+/// Reads the account from flat storage history, like `unc_store::get_account`.
+fn get_account_at(
+    view: &FlatStateHistoryView,
+    account_id: &AccountId,
+) -> Result<Option<Account>, StorageError> {
+    let key = TrieKey::Account { account_id: account_id.clone() }.to_vec();
+    let Some(value) = view.get_value(&key)? else {
+        return Ok(None);
+    };
+    let bytes = view.value_bytes(value)?;
+    Account::try_from_slice(&bytes).map(Some).map_err(|_| {
+        StorageError::StorageInconsistentState("Failed to deserialize account".to_string())
+    })
+}
@@ -13,8 +13,9 @@ use unc_primitives::errors::{
 use unc_primitives::receipt::{ActionReceipt, DataReceipt, Receipt, ReceiptEnum};
 use unc_primitives::transaction::DeleteAccountAction;
 use unc_primitives::transaction::{
-    Action, AddKeyAction, CreateRsa2048ChallengeAction, DeployContractAction, FunctionCallAction,
-    PledgeAction, RegisterRsa2048KeysAction, SignedTransaction,
+    Action, AddKeyAction, CreateRsa2048ChallengeAction, DeployContractAction,
+    DeployGlobalContractAction, FunctionCallAction, PledgeAction, RegisterRsa2048KeysAction,
+    SignedTransaction,
 };
 use unc_primitives::types::{AccountId, Balance};
 use unc_primitives::types::{BlockHeight, StorageUsage};
@@ -357,6 +358,14 @@ pub(crate) fn validate_actions(
                 return Err(ActionsValidationError::DeleteActionMustBeFinal);
             }
         } else {
+            if let Action::DeployGlobalContract(_) | Action::UseGlobalContract(_) = action {
+                if !checked_feature!("stable", GlobalContracts, current_protocol_version) {
+                    return Err(ActionsValidationError::UnsupportedProtocolFeature {
+                        protocol_feature: String::from("GlobalContracts"),
+                        version: ProtocolFeature::GlobalContracts.protocol_version(),
+                    });
+                }
+            }
             if let Action::Delegate(_) = action {
                 if !checked_feature!("stable", DelegateAction, current_protocol_version) {
                     return Err(ActionsValidationError::UnsupportedProtocolFeature {
@@ -403,6 +412,8 @@ pub fn validate_action(
         Action::Delegate(a) => validate_delegate_action(limit_config, a, current_protocol_version),
         Action::RegisterRsa2048Keys(a) => validate_register_rsa2048_keys_action(limit_config, a),
         Action::CreateRsa2048Challenge(a) => validate_create_rsa2048_challenge_action(a),
+        Action::DeployGlobalContract(a) => validate_deploy_global_contract_action(limit_config, a),
+        Action::UseGlobalContract(_) => Ok(()),
     }
 }
 
@@ -431,6 +442,22 @@ fn validate_deploy_contract_action(
     Ok(())
 }
 
+/// Validates `DeployGlobalContractAction`. Checks that the given contract size doesn't exceed the
+/// limit.
+fn validate_deploy_global_contract_action(
+    limit_config: &LimitConfig,
+    action: &DeployGlobalContractAction,
+) -> Result<(), ActionsValidationError> {
+    if action.code.len() as u64 > limit_config.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limit_config.max_contract_size,
+        });
+    }
+
+    Ok(())
+}
+
 /// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
 /// the length of the arguments doesn't exceed the limit.
 fn validate_function_call_action(
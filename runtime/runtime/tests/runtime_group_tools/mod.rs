@@ -78,7 +78,9 @@ impl StandaloneRuntime {
 
         let mut account_ids: HashSet<AccountId> = HashSet::new();
         genesis.for_each_record(|record: &StateRecord| {
-            account_ids.insert(state_record_to_account_id(record).clone());
+            if let Some(account_id) = state_record_to_account_id(record) {
+                account_ids.insert(account_id.clone());
+            }
         });
         let writers = std::sync::atomic::AtomicUsize::new(0);
         let root = GenesisStateApplier::apply(
@@ -1,28 +1,35 @@
 #![no_main]
 
 use unc_parameters::vm::VMKind;
-use unc_parameters::RuntimeConfigStore;
-use unc_primitives::version::PROTOCOL_VERSION;
-use unc_vm_runner::internal::VMKindExt;
-use unc_vm_runner::logic::errors::FunctionCallError;
+use unc_vm_runner::internal::{DifferentialResult, VMKindExt};
 use unc_vm_runner::logic::mocks::mock_external::MockedExternal;
-use unc_vm_runner::logic::VMOutcome;
 use unc_vm_runner::ContractCode;
-use unc_vm_runner_fuzz::{create_context, find_entry_point, ArbitraryModule};
+use unc_vm_runner_fuzz::{create_context, find_entry_point, runtime_config, ArbitraryModule};
 
 libfuzzer_sys::fuzz_target!(|module: ArbitraryModule| {
     let code = ContractCode::new(module.0.module.to_bytes(), None);
-    let unc_vm = run_fuzz(&code, VMKind::UncVm);
-    let wasmtime = run_fuzz(&code, VMKind::Wasmtime);
-    assert_eq!(unc_vm, wasmtime);
+    let mut results = Vec::new();
+    for &vm_kind in DifferentialResult::VM_KINDS {
+        if let Some(result) = run_fuzz(&code, vm_kind) {
+            results.push((vm_kind, result));
+        }
+    }
+    let Some(((expected_vm, expected), rest)) = results.split_first() else { return };
+    for (vm_kind, result) in rest {
+        assert!(
+            expected == result,
+            "{expected_vm:?} and {vm_kind:?} disagree:\n{expected:#?}\n{result:#?}"
+        );
+    }
 });
 
-fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMOutcome {
+/// Runs `code` on `vm_kind`, or returns `None` if that VM is not available on
+/// this platform.
+fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> Option<DifferentialResult> {
     let mut fake_external = MockedExternal::new();
     let mut context = create_context(vec![]);
     context.prepaid_gas = 10u64.pow(14);
-    let config_store = RuntimeConfigStore::new(None);
-    let config = config_store.get_config(PROTOCOL_VERSION);
+    let config = runtime_config();
     let fees = &config.fees;
     let mut wasm_config = config.wasm_config.clone();
     wasm_config.limit_config.contract_prepare_version =
@@ -31,7 +38,7 @@ fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMOutcome {
     let promise_results = vec![];
 
     let method_name = find_entry_point(code).unwrap_or_else(|| "main".to_string());
-    let res = vm_kind.runtime(wasm_config).unwrap().run(
+    let res = vm_kind.runtime(wasm_config)?.run(
         code,
         &method_name,
         &mut fake_external,
@@ -40,18 +47,6 @@ fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMOutcome {
         &promise_results,
         None,
     );
-
-    // Remove the VMError message details as they can differ between runtimes
-    // TODO: maybe there's actually things we could check for equality here too?
-    match res {
-        Ok(mut outcome) => {
-            if outcome.aborted.is_some() {
-                outcome.logs = vec!["[censored]".to_owned()];
-                outcome.aborted =
-                    Some(FunctionCallError::LinkError { msg: "[censored]".to_owned() });
-            }
-            outcome
-        }
-        Err(err) => panic!("fatal error: {err:?}"),
-    }
+    let outcome = res.unwrap_or_else(|err| panic!("fatal error on {vm_kind:?}: {err:?}"));
+    Some(DifferentialResult::new(outcome, fake_external))
 }
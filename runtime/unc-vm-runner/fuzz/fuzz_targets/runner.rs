@@ -1,19 +1,16 @@
 #![no_main]
 
 use std::sync::Arc;
-use unc_parameters::{RuntimeConfig, RuntimeConfigStore};
-use unc_primitives::version::PROTOCOL_VERSION;
+use unc_parameters::RuntimeConfig;
 use unc_vm_runner::internal::VMKindExt;
 use unc_vm_runner::logic::mocks::mock_external::MockedExternal;
 use unc_vm_runner::logic::VMOutcome;
 use unc_vm_runner::ContractCode;
-use unc_vm_runner_fuzz::{create_context, find_entry_point, ArbitraryModule};
+use unc_vm_runner_fuzz::{create_context, find_entry_point, runtime_config, ArbitraryModule};
 
 libfuzzer_sys::fuzz_target!(|module: ArbitraryModule| {
     let code = ContractCode::new(module.0.module.to_bytes(), None);
-    let config_store = RuntimeConfigStore::new(None);
-    let config = config_store.get_config(PROTOCOL_VERSION);
-    let _result = run_fuzz(&code, Arc::clone(config));
+    let _result = run_fuzz(&code, Arc::clone(runtime_config()));
 });
 
 fn run_fuzz(code: &ContractCode, config: Arc<RuntimeConfig>) -> VMOutcome {
@@ -1,4 +1,7 @@
 use core::fmt;
+use std::sync::{Arc, OnceLock};
+use unc_parameters::{RuntimeConfig, RuntimeConfigStore};
+use unc_primitives::version::PROTOCOL_VERSION;
 use unc_vm_runner::internal::wasmparser::{Export, ExternalKind, Parser, Payload};
 use unc_vm_runner::logic::VMContext;
 use unc_vm_runner::ContractCode;
@@ -28,6 +31,13 @@ pub fn find_entry_point(contract: &ContractCode) -> Option<String> {
     None
 }
 
+/// The runtime config of the current protocol version, which the modules are
+/// generated for and run with. Built once rather than for every fuzz input.
+pub fn runtime_config() -> &'static Arc<RuntimeConfig> {
+    static CONFIG: OnceLock<Arc<RuntimeConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| Arc::clone(RuntimeConfigStore::new(None).get_config(PROTOCOL_VERSION)))
+}
+
 pub fn create_context(input: Vec<u8>) -> VMContext {
     VMContext {
         current_account_id: "alice".parse().unwrap(),
@@ -52,10 +62,29 @@ pub fn create_context(input: Vec<u8>) -> VMContext {
 /// Define a configuration for which [`available_imports`] is implemented. This
 /// allows to specify the imports available in a [`ConfiguredModule`].
 ///
+/// The generated modules stay within the limits and the Wasm features accepted
+/// by `prepare_v2`, so that most of them get to be executed rather than being
+/// rejected during preparation.
+///
 /// [`available_imports`]: wasm_smith::Config::available_imports
 /// [`ConfiguredModule`]: wasm_smith::ConfiguredModule
-#[derive(arbitrary::Arbitrary, Debug)]
-pub struct ModuleConfig {}
+#[derive(Debug)]
+pub struct ModuleConfig {
+    max_funcs: usize,
+    max_memory_pages: u64,
+}
+
+impl<'a> arbitrary::Arbitrary<'a> for ModuleConfig {
+    fn arbitrary(_: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let limits = &runtime_config().wasm_config.limit_config;
+        let max_funcs = limits.max_functions_number_per_contract.unwrap_or(u64::MAX);
+        Ok(ModuleConfig {
+            // wasm-smith defaults to at most 100 functions.
+            max_funcs: usize::try_from(max_funcs).unwrap_or(usize::MAX).min(100),
+            max_memory_pages: u64::from(limits.max_memory_pages),
+        })
+    }
+}
 
 impl wasm_smith::Config for ModuleConfig {
     /// Returns a WebAssembly module which imports all unc host functions. The
@@ -66,6 +95,26 @@ impl wasm_smith::Config for ModuleConfig {
     fn available_imports(&self) -> Option<std::borrow::Cow<'_, [u8]>> {
         Some(unc_test_contracts::rs_contract().into())
     }
+
+    fn max_funcs(&self) -> usize {
+        self.max_funcs
+    }
+
+    fn max_memory_pages(&self, _is_64: bool) -> u64 {
+        self.max_memory_pages
+    }
+
+    fn multi_value_enabled(&self) -> bool {
+        false
+    }
+
+    fn saturating_float_to_int_enabled(&self) -> bool {
+        false
+    }
+
+    fn sign_extension_ops_enabled(&self) -> bool {
+        true
+    }
 }
 
 /// Wrapper to get more useful Debug.
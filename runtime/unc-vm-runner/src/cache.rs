@@ -44,10 +44,14 @@ fn vm_hash(vm_kind: VMKind) -> u64 {
     }
 }
 
-pub fn get_contract_cache_key(code: &ContractCode, config: &Config) -> CryptoHash {
+/// Returns the key of the compiled contract in the [`CompiledContractCache`].
+///
+/// The key only depends on the code hash, not on the account the code is deployed on, so the
+/// accounts sharing a globally deployed contract share its compiled artifact as well.
+pub fn get_contract_cache_key(code_hash: CryptoHash, config: &Config) -> CryptoHash {
     let _span = tracing::debug_span!(target: "vm", "get_key").entered();
     let key = ContractCacheKey::Version4 {
-        code_hash: *code.hash(),
+        code_hash,
         vm_config_non_crypto_hash: config.non_crypto_hash(),
         vm_kind: config.vm_kind,
         vm_hash: vm_hash(config.vm_kind),
@@ -102,7 +106,7 @@ pub fn precompile_contract(
         Some(it) => it,
         None => return Ok(Ok(ContractPrecompilatonResult::CacheNotAvailable)),
     };
-    let key = get_contract_cache_key(code, config);
+    let key = get_contract_cache_key(*code.hash(), config);
     // Check if we already cached with such a key.
     if cache.has(&key).map_err(CacheError::ReadError)? {
         return Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache));
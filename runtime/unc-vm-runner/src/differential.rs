@@ -0,0 +1,44 @@
+use crate::logic::errors::{CompilationError, FunctionCallError};
+use crate::logic::mocks::mock_external::{MockAction, MockedExternal};
+use crate::logic::VMOutcome;
+use unc_parameters::vm::VMKind;
+
+/// The part of an execution which all VMs must agree on.
+///
+/// Only the free-form messages of errors which are produced by the VM itself
+/// are removed; the kind of the error, the gas and everything the contract did
+/// to the external state must be the same.
+#[derive(Debug, PartialEq)]
+pub struct DifferentialResult {
+    pub outcome: VMOutcome,
+    pub state: Vec<(Vec<u8>, Vec<u8>)>,
+    pub actions: Vec<MockAction>,
+}
+
+impl DifferentialResult {
+    /// The VMs whose results are compared. The compiler of Wasmer0 trips the
+    /// debug assertions of the standard library on modules without any code, so
+    /// it's only compared in builds without debug assertions.
+    pub const VM_KINDS: &'static [VMKind] = if cfg!(debug_assertions) {
+        &[VMKind::Wasmtime, VMKind::Wasmer2, VMKind::UncVm]
+    } else {
+        &[VMKind::Wasmer0, VMKind::Wasmtime, VMKind::Wasmer2, VMKind::UncVm]
+    };
+
+    pub fn new(mut outcome: VMOutcome, ext: MockedExternal) -> Self {
+        outcome.aborted = outcome.aborted.map(|err| match err {
+            FunctionCallError::LinkError { .. } => {
+                FunctionCallError::LinkError { msg: "[censored]".to_owned() }
+            }
+            FunctionCallError::CompilationError(CompilationError::WasmerCompileError {
+                ..
+            }) => FunctionCallError::CompilationError(CompilationError::WasmerCompileError {
+                msg: "[censored]".to_owned(),
+            }),
+            err => err,
+        });
+        let mut state: Vec<_> = ext.fake_trie.into_iter().collect();
+        state.sort();
+        DifferentialResult { outcome, state, actions: ext.action_log }
+    }
+}
@@ -1,6 +1,4 @@
 const REFERENCE_TYPES: bool = false;
-const MULTI_VALUE: bool = false;
-const BULK_MEMORY: bool = false;
 const SIMD: bool = false;
 const THREADS: bool = false;
 const TAIL_CALL: bool = false;
@@ -15,19 +13,32 @@ const GC: bool = false;
 const FUNCTION_REFERENCES: bool = false;
 const MEMORY_CONTROL: bool = false;
 
+use unc_parameters::vm::Config;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(crate) struct WasmFeatures {
     sign_extension: bool,
+    bulk_memory: bool,
+    multi_value: bool,
 }
 
-impl From<crate::logic::ContractPrepareVersion> for WasmFeatures {
-    fn from(version: crate::logic::ContractPrepareVersion) -> Self {
-        let sign_extension = match version {
+impl WasmFeatures {
+    /// The enabled features only depend on the protocol config, never on the runtime executing
+    /// the contract, so that all nodes agree on which contracts are valid.
+    ///
+    /// Wasmer0 and Wasmer2 are only used by protocol versions that enable neither bulk memory
+    /// nor multi-value.
+    pub(crate) fn new(config: &Config) -> Self {
+        let v2 = match config.limit_config.contract_prepare_version {
             crate::logic::ContractPrepareVersion::V0 => false,
             crate::logic::ContractPrepareVersion::V1 => false,
             crate::logic::ContractPrepareVersion::V2 => true,
         };
-        WasmFeatures { sign_extension }
+        WasmFeatures {
+            sign_extension: v2,
+            bulk_memory: v2 && config.bulk_memory,
+            multi_value: v2 && config.multi_value,
+        }
     }
 }
 
@@ -37,11 +48,10 @@ impl From<WasmFeatures> for finite_wasm::wasmparser::WasmFeatures {
             floats: true,
             mutable_global: true,
             sign_extension: f.sign_extension,
+            bulk_memory: f.bulk_memory,
+            multi_value: f.multi_value,
 
             reference_types: REFERENCE_TYPES,
-            // wasmer singlepass compiler requires multi_value return values to be disabled.
-            multi_value: MULTI_VALUE,
-            bulk_memory: BULK_MEMORY,
             simd: SIMD,
             threads: THREADS,
             tail_call: TAIL_CALL,
@@ -76,8 +86,8 @@ impl From<WasmFeatures> for wasmparser::WasmFeatures {
         // /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\ /!\
         wasmparser::WasmFeatures {
             reference_types: REFERENCE_TYPES,
-            multi_value: MULTI_VALUE,
-            bulk_memory: BULK_MEMORY,
+            multi_value: false,
+            bulk_memory: false,
             simd: SIMD,
             threads: THREADS,
             tail_call: TAIL_CALL,
@@ -102,12 +112,12 @@ impl From<WasmFeatures> for unc_vm_types::Features {
         Self {
             mutable_global: true,
             sign_extension: f.sign_extension,
+            bulk_memory: f.bulk_memory,
+            multi_value: f.multi_value,
 
             threads: THREADS,
             reference_types: REFERENCE_TYPES,
             simd: SIMD,
-            bulk_memory: BULK_MEMORY,
-            multi_value: MULTI_VALUE,
             tail_call: TAIL_CALL,
             multi_memory: MULTI_MEMORY,
             memory64: MEMORY64,
@@ -138,8 +148,8 @@ impl From<WasmFeatures> for wasmer_types::Features {
             threads: THREADS,
             reference_types: REFERENCE_TYPES,
             simd: SIMD,
-            bulk_memory: BULK_MEMORY,
-            multi_value: MULTI_VALUE,
+            bulk_memory: false,
+            multi_value: false,
             tail_call: TAIL_CALL,
             multi_memory: MULTI_MEMORY,
             memory64: MEMORY64,
@@ -150,13 +160,13 @@ impl From<WasmFeatures> for wasmer_types::Features {
 
 #[cfg(feature = "wasmtime_vm")]
 impl From<WasmFeatures> for wasmtime::Config {
-    fn from(_: WasmFeatures) -> Self {
+    fn from(f: WasmFeatures) -> Self {
         let mut config = wasmtime::Config::default();
         config.wasm_threads(THREADS);
         config.wasm_reference_types(REFERENCE_TYPES);
         config.wasm_simd(SIMD);
-        config.wasm_bulk_memory(BULK_MEMORY);
-        config.wasm_multi_value(MULTI_VALUE);
+        config.wasm_bulk_memory(f.bulk_memory);
+        config.wasm_multi_value(f.multi_value);
         config.wasm_multi_memory(MULTI_MEMORY);
         config.wasm_memory64(MEMORY64);
         config
@@ -222,6 +222,21 @@ imports! {
         beneficiary_id_len: u64,
         beneficiary_id_ptr: u64
     ] -> []>,
+    #[yield_resume_host_functions] promise_yield_create<[
+        method_name_len: u64,
+        method_name_ptr: u64,
+        arguments_len: u64,
+        arguments_ptr: u64,
+        gas: u64,
+        gas_weight: u64,
+        register_id: u64
+    ] -> [u64]>,
+    #[yield_resume_host_functions] promise_yield_resume<[
+        data_id_len: u64,
+        data_id_ptr: u64,
+        payload_len: u64,
+        payload_ptr: u64
+    ] -> [u32]>,
     // #######################
     // # Promise API results #
     // #######################
@@ -298,7 +313,11 @@ pub(crate) mod wasmer {
                         Some(tracing::trace_span!(target: "host-function", stringify!($name)).entered())
                     };
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(ctx.data as *mut VMLogic<'_>) };
-                    logic.$func( $( $arg_name, )* )
+                    let result = logic.$func( $( $arg_name, )* );
+                    if !str_eq(stringify!($mod), "internal") {
+                        logic.trace_host_call(stringify!($name), &[$( (stringify!($arg_name), $arg_name as u64) ),*], &result);
+                    }
+                    result
                 }
 
                 match stringify!($mod) {
@@ -403,7 +422,13 @@ pub(crate) mod wasmer2 {
                             // lifetime and so it is safe to dereference the `env` pointer which is
                             // known to be derived from a valid `&'vmlogic mut VMLogic<'_>` in the
                             // first place.
-                            unsafe { (*env).$func( $( $arg_name, )* ) }
+                            unsafe {
+                                let result = (*env).$func( $( $arg_name, )* );
+                                if !str_eq(stringify!($mod), "internal") {
+                                    (*env).trace_host_call(stringify!($name), &[$( (stringify!($arg_name), $arg_name as u64) ),*], &result);
+                                }
+                                result
+                            }
                         }));
                         // We want to ensure that the only kind of error that host function calls
                         // return are VMLogicError. This is important because we later attempt to
@@ -556,7 +581,13 @@ pub(crate) mod unc_vm {
                             // lifetime and so it is safe to dereference the `env` pointer which is
                             // known to be derived from a valid `&'vmlogic mut VMLogic<'_>` in the
                             // first place.
-                            unsafe { (*env).$func( $( $arg_name, )* ) }
+                            unsafe {
+                                let result = (*env).$func( $( $arg_name, )* );
+                                if !str_eq(stringify!($mod), "internal") {
+                                    (*env).trace_host_call(stringify!($name), &[$( (stringify!($arg_name), $arg_name as u64) ),*], &result);
+                                }
+                                result
+                            }
                         }));
                         // We want to ensure that the only kind of error that host function calls
                         // return are VMLogicError. This is important because we later attempt to
@@ -691,7 +722,11 @@ pub(crate) mod wasmtime {
                         crate::wasmtime_runner::CALLER.with(|runner_caller| *runner_caller.borrow_mut() = std::mem::transmute(caller));
                     }
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(data as *mut VMLogic<'_>) };
-                    match logic.$func( $( $arg_name as $arg_type, )* ) {
+                    let result = logic.$func( $( $arg_name as $arg_type, )* );
+                    if !str_eq(stringify!($mod), "internal") {
+                        logic.trace_host_call(stringify!($name), &[$( (stringify!($arg_name), $arg_name as u64) ),*], &result);
+                    }
+                    match result {
                         Ok(result) => Ok(result as ($( $returns ),* ) ),
                         Err(err) => {
                             Err(ErrorContainer(std::sync::Mutex::new(Some(err))).into())
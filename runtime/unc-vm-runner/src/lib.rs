@@ -2,6 +2,7 @@
 
 mod cache;
 mod code;
+mod differential;
 mod errors;
 mod features;
 mod imports;
@@ -14,6 +15,7 @@ mod profile;
 mod runner;
 #[cfg(test)]
 mod tests;
+pub mod trace;
 #[cfg(all(feature = "unc_vm", target_arch = "x86_64"))]
 mod unc_vm_runner;
 mod utils;
@@ -35,6 +37,7 @@ pub use runner::{run, VM};
 /// implementation detail of `unc-vm-runner`.
 #[doc(hidden)]
 pub mod internal {
+    pub use crate::differential::DifferentialResult;
     pub use crate::runner::VMKindExt;
     pub use wasmparser;
 }
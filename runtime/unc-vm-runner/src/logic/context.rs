@@ -1,8 +1,9 @@
 use super::types::PublicKey;
+use borsh::{BorshDeserialize, BorshSerialize};
 use unc_primitives_core::config::ViewConfig;
 use unc_primitives_core::types::{AccountId, Balance, BlockHeight, EpochHeight, Gas, StorageUsage};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
 /// Context for the contract execution.
 pub struct VMContext {
     /// The account id of the current contract that we are executing.
@@ -270,6 +270,36 @@ pub trait External {
         receiver_id: AccountId,
     ) -> Result<ReceiptIndex, VMLogicError>;
 
+    /// Create a receipt on the current account which will be postponed until it is resumed
+    /// through [`External::submit_promise_resume_data`] or times out.
+    ///
+    /// Returns the index of the new receipt together with the `data_id` the receipt is waiting
+    /// for.
+    ///
+    /// # Arguments
+    ///
+    /// * `receiver_id` - account id of the receiver of the receipt created
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash), VMLogicError>;
+
+    /// Submit the data resuming a receipt previously created with
+    /// [`External::create_promise_yield_receipt`].
+    ///
+    /// Returns `false` if there is no yielded receipt awaiting `data_id` on the current account,
+    /// e.g. because it has already been resumed or timed out.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_id` - the `data_id` the yielded receipt is waiting for
+    /// * `data` - the payload passed to the yielded receipt
+    fn submit_promise_resume_data(
+        &mut self,
+        data_id: CryptoHash,
+        data: Vec<u8>,
+    ) -> Result<bool, VMLogicError>;
+
     /// Attach the [`CreateAccountAction`] action to an existing receipt.
     ///
     /// # Arguments
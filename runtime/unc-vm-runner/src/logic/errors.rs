@@ -206,6 +206,10 @@ pub enum HostError {
     /// Invalid input to ed25519 signature verification function (e.g. signature cannot be
     /// derived from bytes).
     Ed25519VerifyInvalidInput { msg: String },
+    /// Yield payload length exceeds the maximum permitted.
+    YieldPayloadLength { length: u64, limit: u64 },
+    /// The `data_id` passed to `promise_yield_resume` is not a 32-byte hash.
+    DataIdMalformed,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -435,6 +439,11 @@ impl std::fmt::Display for HostError {
             Ed25519VerifyInvalidInput { msg } => {
                 write!(f, "ED25519 signature verification error: {}", msg)
             }
+            YieldPayloadLength { length, limit } => write!(
+                f,
+                "Yield resume payload is {length} bytes which exceeds the {limit} byte limit"
+            ),
+            DataIdMalformed => write!(f, "Yield resume data id is malformed"),
         }
     }
 }
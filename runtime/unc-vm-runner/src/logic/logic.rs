@@ -6,6 +6,7 @@ use super::types::{PromiseIndex, PromiseResult, ReceiptIndex, ReturnData};
 use super::utils::split_method_names;
 use super::ValuePtr;
 use super::{HostError, VMLogicError};
+use crate::trace::{HostCall, HostCallReturn, TraceEvent, TraceRecorder};
 use crate::ProfileDataV3;
 use std::mem::size_of;
 use unc_crypto::Secp256K1Signature;
@@ -14,6 +15,7 @@ use unc_parameters::{
     transfer_exec_fee, transfer_send_fee, ActionCosts, ExtCosts, RuntimeFeesConfig,
 };
 use unc_primitives_core::config::ViewConfig;
+use unc_primitives_core::hash::CryptoHash;
 use unc_primitives_core::types::{
     AccountId, Balance, Compute, EpochHeight, Gas, GasWeight, StorageUsage,
 };
@@ -67,6 +69,9 @@ pub struct VMLogic<'a> {
 
     /// Stores the amount of stack space remaining
     remaining_stack: u64,
+
+    /// Where to record host function calls, if tracing is enabled.
+    trace: Option<TraceRecorder>,
 }
 
 /// Promises API allows to create a DAG-structure that defines dependencies between smart contract
@@ -166,6 +171,7 @@ impl<'a> VMLogic<'a> {
             promises: vec![],
             total_log_length: 0,
             remaining_stack: u64::from(config.limit_config.max_stack_height),
+            trace: TraceRecorder::active(),
         }
     }
 
@@ -174,6 +180,31 @@ impl<'a> VMLogic<'a> {
         &self.logs
     }
 
+    /// Records a host function call made by the contract if tracing is
+    /// enabled, see [`crate::trace`].
+    #[inline]
+    pub(crate) fn trace_host_call<R: HostCallReturn>(
+        &self,
+        name: &'static str,
+        args: &[(&'static str, u64)],
+        result: &Result<R>,
+    ) {
+        let Some(trace) = &self.trace else { return };
+        let register = args
+            .iter()
+            .find(|(arg_name, _)| *arg_name == "register_id")
+            .and_then(|(_, register_id)| self.registers.get_for_free(*register_id))
+            .map(|data| data.to_vec());
+        trace.push(TraceEvent::HostCall(HostCall {
+            name: name.to_string(),
+            args: args.iter().map(|(_, value)| *value).collect(),
+            result: result.as_ref().map(|value| value.traced()).map_err(|err| format!("{err:?}")),
+            register,
+            burnt_gas: self.gas_counter.burnt_gas(),
+            used_gas: self.gas_counter.used_gas(),
+        }));
+    }
+
     #[cfg(test)]
     pub(super) fn gas_counter(&self) -> &GasCounter {
         &self.gas_counter
@@ -2140,6 +2171,142 @@ impl<'a> VMLogic<'a> {
         }
     }
 
+    /// Creates a promise that will execute a method on the current account with given arguments.
+    /// The promise is not executed right away: it waits until it is resumed by a call to
+    /// `promise_yield_resume` with the `data_id` written into `register_id`, or until it times
+    /// out after `yield_timeout_length_in_blocks` blocks. The payload passed to
+    /// `promise_yield_resume` becomes the only promise result of the resumed call, and on a
+    /// timeout the promise result is failed.
+    ///
+    /// # Errors
+    ///
+    /// * If `method_name_len + method_name_ptr` or `arguments_len + arguments_ptr` points outside
+    /// the memory of the guest or host returns `MemoryAccessViolation`.
+    /// * If the method name is empty returns `EmptyMethodName`.
+    /// * If called as view function returns `ProhibitedInView`.
+    /// * If the total number of promises exceeds `max_promises_per_function_call_action` limit
+    ///   returns `NumPromisesExceeded`.
+    ///
+    /// # Returns
+    ///
+    /// Index of the new promise that uniquely identifies it within the current execution of the
+    /// method.
+    ///
+    /// # Cost
+    ///
+    /// `base + dispatch&execution cost of the receipt + dispatch&execution base cost of the data
+    ///  receipt + function call action base and per byte fees + cost of writing the data id into
+    ///  the register`
+    pub fn promise_yield_create(
+        &mut self,
+        method_name_len: u64,
+        method_name_ptr: u64,
+        arguments_len: u64,
+        arguments_ptr: u64,
+        gas: Gas,
+        gas_weight: u64,
+        register_id: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_yield_create".to_string(),
+            }
+            .into());
+        }
+        let method_name = get_memory_or_register!(self, method_name_ptr, method_name_len)?;
+        if method_name.is_empty() {
+            return Err(HostError::EmptyMethodName.into());
+        }
+        let arguments = get_memory_or_register!(self, arguments_ptr, arguments_len)?;
+        let method_name = method_name.into_owned();
+        let arguments = arguments.into_owned();
+
+        // The yielded receipt is sent to the current account and depends on a single data
+        // receipt carrying the resume payload.
+        self.pay_gas_for_new_receipt(true, &[true])?;
+        // Input can't be large enough to overflow
+        let num_bytes = method_name.len() as u64 + arguments.len() as u64;
+        self.pay_action_base(ActionCosts::function_call_base, true)?;
+        self.pay_action_per_byte(ActionCosts::function_call_byte, num_bytes, true)?;
+        // Prepaid gas
+        self.gas_counter.prepay_gas(gas)?;
+
+        let current_account_id = self.context.current_account_id.clone();
+        let (new_receipt_idx, data_id) =
+            self.ext.create_promise_yield_receipt(current_account_id)?;
+        self.ext.append_action_function_call_weight(
+            new_receipt_idx,
+            method_name,
+            arguments,
+            0,
+            gas,
+            GasWeight(gas_weight),
+        )?;
+
+        let new_promise_idx = self.checked_push_promise(Promise::Receipt(new_receipt_idx))?;
+        self.registers.set(
+            &mut self.gas_counter,
+            &self.config.limit_config,
+            register_id,
+            *data_id.as_bytes(),
+        )?;
+        Ok(new_promise_idx)
+    }
+
+    /// Resumes a promise created by `promise_yield_create` on the current account, passing it
+    /// the given payload.
+    ///
+    /// # Errors
+    ///
+    /// * If `data_id_len + data_id_ptr` or `payload_len + payload_ptr` points outside the memory
+    /// of the guest or host returns `MemoryAccessViolation`.
+    /// * If the data id is not 32 bytes long returns `DataIdMalformed`.
+    /// * If the payload is longer than `max_yield_payload_size` returns `YieldPayloadLength`.
+    /// * If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Returns
+    ///
+    /// `1` if the yielded promise is resumed with the payload, `0` if there is no yielded promise
+    /// waiting for `data_id`, e.g. because it has already been resumed or has timed out.
+    ///
+    /// # Cost
+    ///
+    /// `base + cost of reading the data id and the payload + dispatch&execution per byte cost of
+    ///  the data receipt`
+    pub fn promise_yield_resume(
+        &mut self,
+        data_id_len: u64,
+        data_id_ptr: u64,
+        payload_len: u64,
+        payload_ptr: u64,
+    ) -> Result<u32> {
+        self.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_yield_resume".to_string(),
+            }
+            .into());
+        }
+        let data_id = get_memory_or_register!(self, data_id_ptr, data_id_len)?;
+        let data_id = CryptoHash::try_from(&*data_id).map_err(|_| HostError::DataIdMalformed)?;
+        let payload = get_memory_or_register!(self, payload_ptr, payload_len)?;
+        let payload_len = payload.len() as u64;
+        if payload_len > self.config.limit_config.max_yield_payload_size {
+            return Err(HostError::YieldPayloadLength {
+                length: payload_len,
+                limit: self.config.limit_config.max_yield_payload_size,
+            }
+            .into());
+        }
+        let payload = payload.into_owned();
+        // The base cost of the data receipt was prepaid by `promise_yield_create`.
+        self.pay_action_per_byte(ActionCosts::new_data_receipt_byte, payload_len, true)?;
+
+        let resumed = self.ext.submit_promise_resume_data(data_id, payload)?;
+        Ok(u32::from(resumed))
+    }
+
     // #####################
     // # Miscellaneous API #
     // #####################
@@ -10,7 +10,7 @@ use unc_primitives_core::types::{AccountId, Balance, Gas, GasWeight, Power};
 #[allow(dead_code)] // The value is never read because this is a mock.
 struct GasWeightSer(u64);
 
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub enum MockAction {
     CreateReceipt {
         receipt_indices: Vec<ReceiptIndex>,
@@ -62,6 +62,14 @@ pub enum MockAction {
         public_key: unc_crypto::PublicKey,
         nonce: u64,
     },
+    YieldCreate {
+        data_id: CryptoHash,
+        receiver_id: AccountId,
+    },
+    YieldResume {
+        data_id: CryptoHash,
+        data: Vec<u8>,
+    },
 }
 
 #[derive(Default, Clone)]
@@ -179,6 +187,35 @@ impl External for MockedExternal {
         Ok(index as u64)
     }
 
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash), crate::logic::VMLogicError> {
+        let data_id = self.generate_data_id();
+        let index = self.action_log.len();
+        self.action_log.push(MockAction::YieldCreate { data_id, receiver_id });
+        Ok((index as u64, data_id))
+    }
+
+    fn submit_promise_resume_data(
+        &mut self,
+        data_id: CryptoHash,
+        data: Vec<u8>,
+    ) -> Result<bool, crate::logic::VMLogicError> {
+        let mut yielded = false;
+        for action in &self.action_log {
+            match action {
+                MockAction::YieldCreate { data_id: id, .. } if *id == data_id => yielded = true,
+                MockAction::YieldResume { data_id: id, .. } if *id == data_id => return Ok(false),
+                _ => {}
+            }
+        }
+        if yielded {
+            self.action_log.push(MockAction::YieldResume { data_id, data });
+        }
+        Ok(yielded)
+    }
+
     fn append_action_create_account(
         &mut self,
         receipt_index: ReceiptIndex,
@@ -283,6 +320,7 @@ impl External for MockedExternal {
     fn get_receipt_receiver(&self, receipt_index: ReceiptIndex) -> &AccountId {
         match &self.action_log[receipt_index as usize] {
             MockAction::CreateReceipt { receiver_id, .. } => receiver_id,
+            MockAction::YieldCreate { receiver_id, .. } => receiver_id,
             _ => panic!("not a valid receipt index!"),
         }
     }
@@ -2,6 +2,7 @@ use crate::logic::mocks::mock_external::MockedExternal;
 use crate::logic::tests::helpers::*;
 use crate::logic::tests::vm_logic_builder::VMLogicBuilder;
 use crate::logic::types::PromiseResult;
+use crate::logic::HostError;
 
 use serde_json;
 use unc_crypto::PublicKey;
@@ -630,3 +631,118 @@ fn test_promise_batch_then() {
         ]"#]]
     .assert_eq(&serde_json::to_string_pretty(&vm_receipts(&logic_builder.ext)).unwrap());
 }
+
+#[test]
+fn test_promise_yield_create_and_resume() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build();
+
+    let method = logic.internal_mem_write(b"callback");
+    let args = logic.internal_mem_write(b"args");
+    let index = logic
+        .promise_yield_create(method.len, method.ptr, args.len, args.ptr, 0, 1, 0)
+        .expect("should create a yielded promise");
+    assert_eq!(index, 0);
+    let data_id = logic.registers().get_for_free(0).unwrap().to_vec();
+    assert_eq!(data_id.len(), 32);
+
+    let data_id = logic.internal_mem_write(&data_id);
+    let payload = logic.internal_mem_write(b"payload");
+    assert_eq!(
+        logic.promise_yield_resume(data_id.len, data_id.ptr, payload.len, payload.ptr),
+        Ok(1)
+    );
+    // A yielded promise can be resumed only once.
+    assert_eq!(
+        logic.promise_yield_resume(data_id.len, data_id.ptr, payload.len, payload.ptr),
+        Ok(0)
+    );
+
+    expect_test::expect![[r#"
+        [
+          {
+            "YieldCreate": {
+              "data_id": "CoRutESHXR94goeNsP5Za7RKyKrK8AYLQa4PwvT2934w",
+              "receiver_id": "alice.unc"
+            }
+          },
+          {
+            "FunctionCallWeight": {
+              "receipt_index": 0,
+              "method_name": [
+                99,
+                97,
+                108,
+                108,
+                98,
+                97,
+                99,
+                107
+              ],
+              "args": [
+                97,
+                114,
+                103,
+                115
+              ],
+              "attached_deposit": 0,
+              "prepaid_gas": 0,
+              "gas_weight": 1
+            }
+          },
+          {
+            "YieldResume": {
+              "data_id": "CoRutESHXR94goeNsP5Za7RKyKrK8AYLQa4PwvT2934w",
+              "data": [
+                112,
+                97,
+                121,
+                108,
+                111,
+                97,
+                100
+              ]
+            }
+          }
+        ]"#]]
+    .assert_eq(&serde_json::to_string_pretty(&vm_receipts(&logic_builder.ext)).unwrap());
+}
+
+#[test]
+fn test_promise_yield_resume_errors() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build();
+
+    let unknown_data_id = logic.internal_mem_write(&[0; 32]);
+    let payload = logic.internal_mem_write(b"payload");
+    assert_eq!(
+        logic.promise_yield_resume(
+            unknown_data_id.len,
+            unknown_data_id.ptr,
+            payload.len,
+            payload.ptr
+        ),
+        Ok(0)
+    );
+
+    let short_data_id = logic.internal_mem_write(&[0; 31]);
+    assert_eq!(
+        logic.promise_yield_resume(short_data_id.len, short_data_id.ptr, payload.len, payload.ptr),
+        Err(HostError::DataIdMalformed.into())
+    );
+
+    let limit = logic_builder.config.limit_config.max_yield_payload_size;
+    let mut logic = logic_builder.build();
+    let data_id = logic.internal_mem_write(&[0; 32]);
+    let long_payload = logic.internal_mem_write(&vec![0; limit as usize + 1]);
+    assert_eq!(
+        logic.promise_yield_resume(data_id.len, data_id.ptr, long_payload.len, long_payload.ptr),
+        Err(HostError::YieldPayloadLength { length: limit + 1, limit }.into())
+    );
+
+    let empty_method = logic.internal_mem_write(b"");
+    assert_eq!(
+        logic.promise_yield_create(empty_method.len, empty_method.ptr, 0, 0, 0, 0, 0),
+        Err(HostError::EmptyMethodName.into())
+    );
+}
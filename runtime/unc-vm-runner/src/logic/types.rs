@@ -1,3 +1,4 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 pub use unc_primitives_core::types::*;
 
 pub type PublicKey = Vec<u8>;
@@ -5,7 +6,7 @@ pub type PromiseIndex = u64;
 pub type ReceiptIndex = u64;
 pub type IteratorIndex = u64;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, BorshSerialize, BorshDeserialize)]
 pub enum ReturnData {
     /// Method returned some value or data.
     Value(Vec<u8>),
@@ -30,7 +31,7 @@ impl ReturnData {
 
 /// When there is a callback attached to one or more contract calls the execution results of these
 /// calls are available to the contract invoked through the callback.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, BorshSerialize, BorshDeserialize)]
 pub enum PromiseResult {
     /// Current version of the protocol never returns `PromiseResult::NotReady`.
     NotReady,
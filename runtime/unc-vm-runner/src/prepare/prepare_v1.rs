@@ -242,7 +242,7 @@ mod test {
         let mut config = test_vm_config();
         let prepare_version = ContractPrepareVersion::V1;
         config.limit_config.contract_prepare_version = prepare_version;
-        let features = crate::features::WasmFeatures::from(prepare_version);
+        let features = crate::features::WasmFeatures::new(&config);
         bolero::check!().for_each(|input: &[u8]| {
             // DO NOT use ArbitraryModule. We do want modules that may be invalid here, if they pass our validation step!
             if let Ok(_) = super::validate_contract(input, features, &config) {
@@ -291,7 +291,191 @@ pub(crate) fn prepare_contract(
             tracing::error!(?err, ?kind, "Instrumentation failed");
             PrepareError::Serialization
         })?;
-    Ok(res)
+    if !config.bulk_memory {
+        return Ok(res);
+    }
+    let charged =
+        charge_bulk_memory_per_word(&res, u64::from(config.regular_op_cost)).map_err(|err| {
+            tracing::error!(?err, ?kind, "Bulk memory instrumentation failed");
+            PrepareError::Serialization
+        })?;
+    Ok(charged.unwrap_or(res))
+}
+
+/// Charge bulk memory operations for the amount of data they process.
+///
+/// finite-wasm charges a flat fee per instruction, whereas unc-vm additionally charges
+/// `word_cost` for every started 8 bytes of a memory operation and for every element of a table
+/// operation. To keep all the runtimes in agreement, a call to the gas intrinsic computing the
+/// same fee from the length operand is inserted in front of every such instruction. Returns
+/// `None` if the code does not use any of these instructions.
+fn charge_bulk_memory_per_word(
+    code: &[u8],
+    word_cost: u64,
+) -> Result<Option<Vec<u8>>, BulkMemoryError> {
+    let mut output = Vec::with_capacity(code.len());
+    let mut param_counts = Vec::new();
+    let mut function_types = Vec::new();
+    let mut gas_function = None;
+    let mut remaining_bodies = 0;
+    let mut changed = false;
+    let mut code_section = wasm_encoder::CodeSection::new();
+    for payload in wp::Parser::new(0).parse_all(code) {
+        let payload = payload?;
+        match &payload {
+            wp::Payload::Version { range, .. } => output.extend(&code[range.clone()]),
+            wp::Payload::TypeSection(reader) => {
+                for ty in reader.clone() {
+                    let wp::Type::Func(ty) = ty?;
+                    param_counts.push(ty.params().len() as u32);
+                }
+            }
+            wp::Payload::ImportSection(reader) => {
+                for import in reader.clone() {
+                    let import = import?;
+                    if let wp::TypeRef::Func(ty) = import.ty {
+                        if (import.module, import.name) == ("internal", "finite_wasm_gas") {
+                            gas_function = Some(function_types.len() as u32);
+                        }
+                        function_types.push(ty);
+                    }
+                }
+            }
+            wp::Payload::FunctionSection(reader) => {
+                for ty in reader.clone() {
+                    function_types.push(ty?);
+                }
+            }
+            wp::Payload::CodeSectionStart { count, .. } => {
+                remaining_bodies = *count;
+                if remaining_bodies == 0 {
+                    code_section.append_to(&mut output);
+                }
+            }
+            wp::Payload::CodeSectionEntry(body) => {
+                let index = function_types.len() - remaining_bodies as usize;
+                let params = function_types
+                    .get(index)
+                    .and_then(|ty| param_counts.get(*ty as usize))
+                    .copied()
+                    .unwrap_or(0);
+                match charge_function_body(code, body, params, gas_function, word_cost)? {
+                    Some(function) => {
+                        changed = true;
+                        code_section.function(&function)
+                    }
+                    None => code_section.raw(&code[body.range()]),
+                };
+                remaining_bodies -= 1;
+                if remaining_bodies == 0 {
+                    code_section.append_to(&mut output);
+                }
+            }
+            _ => {}
+        }
+        match payload.as_section() {
+            None => {}
+            Some((id, _)) if id == SectionId::Code as u8 => {}
+            Some((id, range)) => {
+                wasm_encoder::RawSection { id, data: &code[range] }.append_to(&mut output)
+            }
+        }
+    }
+    // Re-encoding may change how the section sizes are encoded, so contracts not using bulk memory
+    // instructions are kept byte for byte identical.
+    Ok(changed.then_some(output))
+}
+
+#[derive(Debug, thiserror::Error)]
+enum BulkMemoryError {
+    #[error(transparent)]
+    Parse(#[from] wp::BinaryReaderError),
+    #[error("unsupported local type in function body at offset {0}")]
+    UnsupportedLocal(usize),
+    #[error("gas intrinsic is not imported, bulk memory instruction at offset {0}")]
+    MissingGasIntrinsic(usize),
+}
+
+/// Rewrite a single function body for [`charge_bulk_memory_per_word`].
+///
+/// The length operand of the bulk memory instructions is always at the top of the stack, so it is
+/// saved to a fresh local and read back to compute the fee. Returns `None` if the function does
+/// not use any bulk memory instructions and can be kept as is.
+fn charge_function_body(
+    code: &[u8],
+    body: &wp::FunctionBody,
+    params: u32,
+    gas_function: Option<u32>,
+    word_cost: u64,
+) -> Result<Option<wasm_encoder::Function>, BulkMemoryError> {
+    let range = body.range();
+    let mut has_bulk_memory = false;
+    for operator in body.get_operators_reader()? {
+        has_bulk_memory |= bulk_memory_shift(&operator?).is_some();
+    }
+    if !has_bulk_memory {
+        return Ok(None);
+    }
+    let mut locals = Vec::new();
+    let mut scratch = params;
+    for local in body.get_locals_reader()? {
+        let (count, ty) = local?;
+        let ty = match ty {
+            wp::ValType::I32 => wasm_encoder::ValType::I32,
+            wp::ValType::I64 => wasm_encoder::ValType::I64,
+            wp::ValType::F32 => wasm_encoder::ValType::F32,
+            wp::ValType::F64 => wasm_encoder::ValType::F64,
+            wp::ValType::V128 | wp::ValType::Ref(_) => {
+                return Err(BulkMemoryError::UnsupportedLocal(range.start))
+            }
+        };
+        locals.push((count, ty));
+        scratch += count;
+    }
+    locals.push((1, wasm_encoder::ValType::I32));
+
+    let mut operators = body.get_operators_reader()?;
+    let mut copied = operators.original_position();
+    let mut instructions = Vec::with_capacity(range.end - copied);
+    while !operators.eof() {
+        let (operator, offset) = operators.read_with_offset()?;
+        let Some(shift) = bulk_memory_shift(&operator) else { continue };
+        let Some(gas_function) = gas_function else {
+            return Err(BulkMemoryError::MissingGasIntrinsic(offset));
+        };
+        instructions.extend(&code[copied..offset]);
+        copied = offset;
+        use wasm_encoder::Instruction as I;
+        let mut charge = vec![I::LocalTee(scratch), I::LocalGet(scratch), I::I64ExtendI32U];
+        if shift != 0 {
+            charge.extend([
+                I::I64Const((1 << shift) - 1),
+                I::I64Add,
+                I::I64Const(shift),
+                I::I64ShrU,
+            ]);
+        }
+        charge.extend([I::I64Const(word_cost as i64), I::I64Mul, I::Call(gas_function)]);
+        for instruction in charge {
+            instruction.encode(&mut instructions);
+        }
+    }
+    instructions.extend(&code[copied..range.end]);
+    let mut function = wasm_encoder::Function::new(locals);
+    function.raw(instructions);
+    Ok(Some(function))
+}
+
+/// Base 2 logarithm of the number of units processed by a bulk memory instruction per charge of
+/// the word cost, or `None` if the instruction is charged a flat fee.
+fn bulk_memory_shift(operator: &wp::Operator) -> Option<i64> {
+    match operator {
+        wp::Operator::MemoryInit { .. }
+        | wp::Operator::MemoryCopy { .. }
+        | wp::Operator::MemoryFill { .. } => Some(3),
+        wp::Operator::TableInit { .. } | wp::Operator::TableCopy { .. } => Some(0),
+        _ => None,
+    }
 }
 
 // TODO: refactor to avoid copy-paste with the ones currently defined in unc_vm_runner
@@ -370,7 +554,7 @@ mod test {
         let mut config = test_vm_config();
         let prepare_version = ContractPrepareVersion::V2;
         config.limit_config.contract_prepare_version = prepare_version;
-        let features = crate::features::WasmFeatures::from(prepare_version);
+        let features = crate::features::WasmFeatures::new(&config);
         bolero::check!().for_each(|input: &[u8]| {
             // DO NOT use ArbitraryModule. We do want modules that may be invalid here, if they pass our validation step!
             if let Ok(_) = crate::prepare::prepare_v1::validate_contract(input, features, &config) {
@@ -378,7 +562,7 @@ mod test {
                     Err(_e) => (), // TODO: this should be a panic, but for now it’d actually trigger
                     Ok(code) => {
                         let mut validator =
-                            wasmparser::Validator::new_with_features(features.into());
+                            super::wp::Validator::new_with_features(features.into());
                         match validator.validate_all(&code) {
                             Ok(_) => (),
                             Err(e) => panic!(
@@ -397,7 +581,7 @@ mod test {
         let mut config = test_vm_config();
         let prepare_version = ContractPrepareVersion::V2;
         config.limit_config.contract_prepare_version = prepare_version;
-        let features = crate::features::WasmFeatures::from(prepare_version);
+        let features = crate::features::WasmFeatures::new(&config);
 
         bolero::check!().for_each(|input: &[u8]| {
             // DO NOT use ArbitraryModule. We do want modules that may be invalid here, if they pass our validation step!
@@ -406,7 +590,7 @@ mod test {
                     Err(_e) => (), // TODO: this should be a panic, but for now it’d actually trigger
                     Ok(code) => {
                         let mut validator =
-                            wasmparser::Validator::new_with_features(features.into());
+                            super::wp::Validator::new_with_features(features.into());
                         match validator.validate_all(&code) {
                             Ok(_) => (),
                             Err(e) => panic!(
@@ -248,7 +248,7 @@ mod test {
         let pretty_debug_str = format!("{profile_data:#?}");
         expect_test::expect![[r#"
             ------------------------------
-            Action gas: 18153
+            Action gas: 19171
             ------ Host functions --------
             contract_loading_base -> 1 [0% host]
             contract_loading_bytes -> 2 [0% host]
@@ -331,6 +331,7 @@ mod test {
             delegate -> 1015
             register_rsa2048_keys -> 1016
             create_rsa2048_challenge -> 1017
+            use_global_contract -> 1018
             ------------------------------
         "#]]
         .assert_eq(&pretty_debug_str)
@@ -2,6 +2,7 @@ use crate::errors::ContractPrecompilatonResult;
 use crate::logic::errors::{CacheError, CompilationError, VMRunnerError};
 use crate::logic::types::PromiseResult;
 use crate::logic::{CompiledContractCache, External, VMContext, VMOutcome};
+use crate::trace::{TraceRecorder, TracingExternal};
 use crate::ContractCode;
 use unc_parameters::vm::{Config, VMKind};
 use unc_parameters::RuntimeFeesConfig;
@@ -39,6 +40,9 @@ pub(crate) type VMResult<T = VMOutcome> = Result<T, VMRunnerError>;
 ///
 /// The gas cost for contract preparation will be subtracted by the VM
 /// implementation.
+///
+/// If the call happens within [`crate::trace::record`], a trace of the
+/// execution is recorded.
 pub fn run(
     code: &ContractCode,
     method_name: &str,
@@ -64,8 +68,24 @@ pub fn run(
         .runtime(wasm_config.clone())
         .unwrap_or_else(|| panic!("the {vm_kind:?} runtime has not been enabled at compile time"));
 
-    let outcome =
-        runtime.run(code, method_name, ext, context, fees_config, promise_results, cache)?;
+    let outcome = match TraceRecorder::active() {
+        None => runtime.run(code, method_name, ext, context, fees_config, promise_results, cache),
+        Some(recorder) => {
+            recorder.begin(vm_kind, code, method_name, &context, promise_results);
+            let mut ext = TracingExternal::new(ext, recorder.clone());
+            let result = runtime.run(
+                code,
+                method_name,
+                &mut ext,
+                context,
+                fees_config,
+                promise_results,
+                cache,
+            );
+            recorder.finish(&result);
+            result
+        }
+    }?;
 
     span.record("burnt_gas", &outcome.burnt_gas);
     Ok(outcome)
@@ -5,6 +5,7 @@ mod regression_tests;
 mod rs_contract;
 mod runtime_errors;
 pub(crate) mod test_builder;
+mod trace;
 mod ts_contract;
 mod wasm_validation;
 
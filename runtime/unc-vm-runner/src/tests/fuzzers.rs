@@ -1,8 +1,9 @@
 use super::test_vm_config;
+use crate::differential::DifferentialResult;
 use crate::internal::wasmparser::{Export, ExternalKind, Parser, Payload, Type};
 use crate::logic::errors::FunctionCallError;
 use crate::logic::mocks::mock_external::MockedExternal;
-use crate::logic::VMContext;
+use crate::logic::{Config, VMContext};
 use crate::runner::VMKindExt;
 use crate::runner::VMResult;
 use crate::ContractCode;
@@ -63,10 +64,35 @@ pub fn create_context(input: Vec<u8>) -> VMContext {
 /// Define a configuration for which [`available_imports`] is implemented. This
 /// allows to specify the imports available in a [`ConfiguredModule`].
 ///
+/// The generated modules stay within the limits and the Wasm features accepted
+/// by `prepare_v2`, so that most of them get to be executed rather than being
+/// rejected during preparation.
+///
 /// [`available_imports`]: wasm_smith::Config::available_imports
 /// [`ConfiguredModule`]: wasm_smith::ConfiguredModule
-#[derive(arbitrary::Arbitrary, Debug)]
-pub struct ModuleConfig {}
+#[derive(Debug)]
+pub struct ModuleConfig {
+    max_funcs: usize,
+    max_memory_pages: u64,
+}
+
+impl ModuleConfig {
+    fn new(config: &Config) -> Self {
+        let limits = &config.limit_config;
+        let max_funcs = limits.max_functions_number_per_contract.unwrap_or(u64::MAX);
+        ModuleConfig {
+            // wasm-smith defaults to at most 100 functions.
+            max_funcs: usize::try_from(max_funcs).unwrap_or(usize::MAX).min(100),
+            max_memory_pages: u64::from(limits.max_memory_pages),
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for ModuleConfig {
+    fn arbitrary(_: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(ModuleConfig::new(&fuzz_vm_config()))
+    }
+}
 
 impl wasm_smith::Config for ModuleConfig {
     /// Returns a WebAssembly module which imports all unc host functions. The
@@ -84,6 +110,26 @@ impl wasm_smith::Config for ModuleConfig {
     fn canonicalize_nans(&self) -> bool {
         true
     }
+
+    fn max_funcs(&self) -> usize {
+        self.max_funcs
+    }
+
+    fn max_memory_pages(&self, _is_64: bool) -> u64 {
+        self.max_memory_pages
+    }
+
+    fn multi_value_enabled(&self) -> bool {
+        false
+    }
+
+    fn saturating_float_to_int_enabled(&self) -> bool {
+        false
+    }
+
+    fn sign_extension_ops_enabled(&self) -> bool {
+        true
+    }
 }
 
 /// Wrapper to get more useful Debug.
@@ -106,30 +152,41 @@ impl fmt::Debug for ArbitraryModule {
     }
 }
 
-fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMResult {
-    let mut fake_external = MockedExternal::new();
+fn fuzz_vm_config() -> Config {
+    let mut config = test_vm_config();
+    config.limit_config.contract_prepare_version = ContractPrepareVersion::V2;
+    config
+}
 
+fn run_fuzz_with_config(
+    code: &ContractCode,
+    vm_kind: VMKind,
+    config: Config,
+    fake_external: &mut MockedExternal,
+) -> VMResult {
     let mut context = create_context(vec![]);
     context.prepaid_gas = 10u64.pow(14);
 
-    let mut config = test_vm_config();
-    config.limit_config.wasmer2_stack_limit = i32::MAX; // If we can crash wasmer2 even without the secondary stack limit it's still good to know
-    config.limit_config.contract_prepare_version = ContractPrepareVersion::V2;
-
     let fees = RuntimeFeesConfig::test();
 
     let promise_results = vec![];
 
     let method_name = find_entry_point(code).unwrap_or_else(|| "main".to_string());
-    let mut res = vm_kind.runtime(config).unwrap().run(
+    vm_kind.runtime(config).unwrap().run(
         code,
         &method_name,
-        &mut fake_external,
+        fake_external,
         context,
         &fees,
         &promise_results,
         None,
-    );
+    )
+}
+
+fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMResult {
+    let mut config = fuzz_vm_config();
+    config.limit_config.wasmer2_stack_limit = i32::MAX; // If we can crash wasmer2 even without the secondary stack limit it's still good to know
+    let mut res = run_fuzz_with_config(code, vm_kind, config, &mut MockedExternal::new());
 
     // Remove the VMError message details as they can differ between runtimes
     // TODO: maybe there's actually things we could check for equality here too?
@@ -146,6 +203,29 @@ fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> VMResult {
     res
 }
 
+/// Runs `code` on every VM compiled into this build and panics if any two of
+/// them disagree on the outcome, gas usage, error kind or effects.
+fn check_vms_agree(code: &ContractCode) {
+    let config = fuzz_vm_config();
+    let mut results = Vec::new();
+    for &vm_kind in DifferentialResult::VM_KINDS {
+        if vm_kind.runtime(config.clone()).is_none() {
+            continue;
+        }
+        let mut ext = MockedExternal::new();
+        let outcome = run_fuzz_with_config(code, vm_kind, config.clone(), &mut ext)
+            .unwrap_or_else(|err| panic!("fatal error on {vm_kind:?}: {err:?}"));
+        results.push((vm_kind, DifferentialResult::new(outcome, ext)));
+    }
+    let Some(((expected_vm, expected), rest)) = results.split_first() else { return };
+    for (vm_kind, result) in rest {
+        assert!(
+            expected == result,
+            "{expected_vm:?} and {vm_kind:?} disagree:\n{expected:#?}\n{result:#?}"
+        );
+    }
+}
+
 #[test]
 fn current_vm_does_not_crash_fuzzer() {
     bolero::check!().with_arbitrary::<ArbitraryModule>().for_each(|module: &ArbitraryModule| {
@@ -166,6 +246,14 @@ fn unc_vm_and_wasmtime_agree_fuzzer() {
     });
 }
 
+#[test]
+fn all_vms_agree_fuzzer() {
+    bolero::check!().with_arbitrary::<ArbitraryModule>().for_each(|module: &ArbitraryModule| {
+        let code = ContractCode::new(module.0.module.to_bytes(), None);
+        check_vms_agree(&code);
+    });
+}
+
 #[test]
 #[cfg(all(feature = "unc_vm", target_arch = "x86_64"))]
 fn unc_vm_is_reproducible_fuzzer() {
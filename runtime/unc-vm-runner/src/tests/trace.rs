@@ -0,0 +1,130 @@
+use crate::logic::mocks::mock_external::MockedExternal;
+use crate::logic::Config;
+use crate::trace::{
+    record, replay, ExecutionTrace, ExternalEvent, ReplayError, TraceDivergence, TraceEvent,
+    TracedOutcome,
+};
+use crate::ContractCode;
+use unc_parameters::vm::VMKind;
+use unc_parameters::RuntimeFeesConfig;
+
+use super::{create_context, test_vm_config, with_vm_variants};
+
+fn encode(xs: &[u64]) -> Vec<u8> {
+    xs.iter().flat_map(|it| it.to_le_bytes()).collect()
+}
+
+/// Writes a key and reads it back, returning the trace of the read.
+fn record_read_value(config: &Config) -> (ContractCode, ExecutionTrace) {
+    let code = ContractCode::new(unc_test_contracts::rs_contract().to_vec(), None);
+    let fees = RuntimeFeesConfig::test();
+    let mut ext = MockedExternal::new();
+    let (result, traces) = record(|| {
+        crate::run(
+            &code,
+            "write_key_value",
+            &mut ext,
+            create_context(encode(&[10, 20])),
+            config,
+            &fees,
+            &[],
+            None,
+        )
+        .expect("write_key_value should not fail");
+        crate::run(
+            &code,
+            "read_value",
+            &mut ext,
+            create_context(encode(&[10])),
+            config,
+            &fees,
+            &[],
+            None,
+        )
+    });
+    assert!(result.unwrap().aborted.is_none());
+    let [_, read]: [ExecutionTrace; 2] = traces.try_into().unwrap();
+    (code, read)
+}
+
+#[test]
+fn test_trace_records_host_calls_and_storage() {
+    let (_, trace) = record_read_value(&test_vm_config());
+    assert_eq!(trace.method_name, "read_value");
+    let names: Vec<_> = trace
+        .events
+        .iter()
+        .filter_map(|event| match event {
+            TraceEvent::HostCall(call) => Some(call.name.as_str()),
+            TraceEvent::External(_) => None,
+        })
+        .collect();
+    assert!(names.contains(&"storage_read"), "{names:?}");
+    assert!(names.contains(&"value_return"), "{names:?}");
+    assert!(trace.events.contains(&TraceEvent::External(ExternalEvent::StorageValue {
+        value: 20u64.to_le_bytes().to_vec()
+    })));
+}
+
+#[test]
+fn test_trace_replay_on_all_vms() {
+    let config = test_vm_config();
+    let (code, trace) = record_read_value(&config);
+    let fees = RuntimeFeesConfig::test();
+    with_vm_variants(&config, |vm_kind: VMKind| {
+        let config = Config { vm_kind, ..config.clone() };
+        let replayed = replay(&trace, &code, &config, &fees).expect("replay should succeed");
+        assert_eq!(replayed.vm_kind, vm_kind);
+        assert_eq!(trace.first_divergence(&replayed), None);
+    });
+}
+
+#[test]
+fn test_trace_replay_detects_divergence() {
+    let config = test_vm_config();
+    let (code, mut trace) = record_read_value(&config);
+    for event in &mut trace.events {
+        if let TraceEvent::External(ExternalEvent::StorageGet { key, .. }) = event {
+            key.push(0);
+        }
+    }
+    let fees = RuntimeFeesConfig::test();
+    let err = replay(&trace, &code, &config, &fees).unwrap_err();
+    assert!(matches!(err, ReplayError::UnexpectedEvent { .. }), "{err:?}");
+
+    let (_, original) = record_read_value(&config);
+    let mut altered = original.clone();
+    altered.events.pop();
+    assert!(matches!(
+        original.first_divergence(&altered),
+        Some(TraceDivergence::Event { actual: None, .. })
+    ));
+}
+
+#[test]
+fn test_trace_replay_detects_unconsumed_events_and_outcome() {
+    let config = test_vm_config();
+    let fees = RuntimeFeesConfig::test();
+    let (code, trace) = record_read_value(&config);
+
+    let mut extra = trace.clone();
+    extra.events.push(TraceEvent::External(ExternalEvent::StorageRemove { key: vec![1] }));
+    let err = replay(&extra, &code, &config, &fees).unwrap_err();
+    assert_eq!(err, ReplayError::UnconsumedEvents { remaining: 1 });
+
+    let mut other_outcome = trace;
+    if let Some(TracedOutcome::Completed { burnt_gas, .. }) = &mut other_outcome.outcome {
+        *burnt_gas += 1;
+    }
+    let err = replay(&other_outcome, &code, &config, &fees).unwrap_err();
+    assert!(matches!(err, ReplayError::OutcomeMismatch { .. }), "{err:?}");
+}
+
+#[test]
+fn test_trace_file_roundtrip() {
+    let (_, trace) = record_read_value(&test_vm_config());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("read_value.trace");
+    trace.save(&path).unwrap();
+    assert_eq!(ExecutionTrace::load(&path).unwrap(), trace);
+}
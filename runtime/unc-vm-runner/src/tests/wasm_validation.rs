@@ -3,6 +3,7 @@ use super::test_vm_config;
 use crate::prepare::prepare_contract;
 use crate::tests::with_vm_variants;
 use expect_test::expect;
+use unc_parameters::vm::VMKind;
 
 static SIMD: &str = r#"
 (module
@@ -34,6 +35,7 @@ static REFERENCE_TYPES: &str = r#"
 
 static BULK_MEMORY: &str = r#"
 (module
+  (memory 1)
   (func $entry (result i32) i32.const 0)
   (func (export "memory.copy") (param i32 i32 i32)
     local.get 0
@@ -101,8 +103,9 @@ static EXPECTED_UNSUPPORTED: &[(&str, &str)] = &[
 
 #[test]
 fn ensure_fails_verification() {
-    let config = test_vm_config();
-    with_vm_variants(&config, |kind: unc_parameters::vm::VMKind| {
+    let config =
+        unc_parameters::vm::Config { bulk_memory: false, multi_value: false, ..test_vm_config() };
+    with_vm_variants(&config, |kind: VMKind| {
         for (feature_name, wat) in EXPECTED_UNSUPPORTED {
             let wasm = wat::parse_str(wat).expect("parsing test wat should succeed");
             if let Ok(_) = prepare_contract(&wasm, &config, kind) {
@@ -112,10 +115,52 @@ fn ensure_fails_verification() {
     });
 }
 
+#[test]
+fn ensure_gated_features_prepare() {
+    let config =
+        unc_parameters::vm::Config { bulk_memory: true, multi_value: true, ..test_vm_config() };
+    with_vm_variants(&config, |kind: VMKind| {
+        let v2 = config.limit_config.contract_prepare_version
+            == unc_parameters::vm::ContractPrepareVersion::V2;
+        for (feature_name, wat) in [("bulk_memory", BULK_MEMORY), ("multi_value", MULTI_VALUE)] {
+            let wasm = wat::parse_str(wat).expect("parsing test wat should succeed");
+            let result = prepare_contract(&wasm, &config, kind);
+            assert_eq!(result.is_ok(), v2, "{feature_name} on {kind:?}: {result:?}");
+        }
+    });
+}
+
+#[test]
+fn bulk_memory_charged_per_word() {
+    // Wasmer0 and Wasmer2 are not used by the protocol versions with bulk memory enabled.
+    test_builder()
+        .wat(
+            r#"
+(module
+  (memory 1)
+  (data $d "0123456789")
+  (func (export "main")
+    (memory.fill (i32.const 0) (i32.const 42) (i32.const 1000))
+    (memory.copy (i32.const 2000) (i32.const 0) (i32.const 1001))
+    (memory.init $d (i32.const 4000) (i32.const 1) (i32.const 9))))
+"#,
+        )
+        .skip_wasmer0()
+        .skip_wasmer2()
+        .expect(&expect![[r#"
+            VMOutcome: balance 4 storage_usage 12 return data None burnt gas 282383601 used gas 282383601
+        "#]]);
+}
+
 #[test]
 fn ensure_fails_execution() {
-    for (_feature_name, wat) in EXPECTED_UNSUPPORTED {
-        test_builder().wat(wat).opaque_error().opaque_outcome().expect(&expect![[r#"
+    for (feature_name, wat) in EXPECTED_UNSUPPORTED {
+        let mut builder = test_builder().wat(wat).opaque_error().opaque_outcome();
+        // Wasmer0 and Wasmer2 are not used by the protocol versions with these features enabled.
+        if matches!(*feature_name, "bulk_memory" | "multi_value") {
+            builder = builder.skip_wasmer0().skip_wasmer2();
+        }
+        builder.expect(&expect![[r#"
             Err: ...
         "#]]);
     }
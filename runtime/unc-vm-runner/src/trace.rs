@@ -0,0 +1,1122 @@
+//! Recording of contract executions and their deterministic replay.
+//!
+//! Tracing is opt-in: nothing is recorded unless the execution happens inside
+//! of [`record`]. While recording, every invocation of [`crate::run`] produces
+//! an [`ExecutionTrace`] which contains:
+//!
+//! * everything needed to start the execution again (method, context, promise
+//!   results and the hash of the code);
+//! * every host function call made by the contract, with its arguments, the
+//!   value it returned, the contents of the register named by its
+//!   `register_id` argument (if any) and the gas counters right after the call;
+//! * every interaction with [`External`], that is storage reads and writes,
+//!   data id generation, validator queries and created receipts.
+//!
+//! A trace can be stored in a compact borsh-encoded file and later re-executed
+//! with [`replay`]. The replay serves all [`External`] requests from the trace,
+//! so no access to the trie is necessary, and it checks that the contract asks
+//! for exactly the same data as it did originally. Since the VM used for the
+//! replay can be chosen freely, replays are also useful for differential
+//! testing between the VM implementations: [`ExecutionTrace::first_divergence`]
+//! points at the first event where the two executions differ.
+//!
+//! Internal host functions injected by the instrumentation (such as
+//! `finite_wasm_gas`) are not recorded, since their exact sequence is an
+//! implementation detail of the particular VM.
+
+use crate::logic::errors::{AnyError, VMRunnerError};
+use crate::logic::types::{PromiseResult, ReceiptIndex, ReturnData};
+use crate::logic::{
+    External, StorageGetMode, TrieNodesCount, VMContext, VMLogicError, VMOutcome, ValuePtr,
+};
+use crate::ContractCode;
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::rc::Rc;
+use unc_crypto::PublicKey;
+use unc_parameters::vm::{Config, VMKind};
+use unc_parameters::RuntimeFeesConfig;
+use unc_primitives_core::hash::CryptoHash;
+use unc_primitives_core::types::{AccountId, Balance, Gas, GasWeight, Nonce, Power};
+
+type Result<T, E = VMLogicError> = ::std::result::Result<T, E>;
+
+/// A recording of a single contract function call.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ExecutionTrace {
+    /// The VM that produced this trace.
+    pub vm_kind: VMKind,
+    /// Hash of the (unprepared) contract code that was executed.
+    pub code_hash: CryptoHash,
+    pub method_name: String,
+    pub context: VMContext,
+    pub promise_results: Vec<PromiseResult>,
+    /// Host function calls and [`External`] interactions in execution order.
+    pub events: Vec<TraceEvent>,
+    /// The result of the execution, `None` if the execution has not finished.
+    pub outcome: Option<TracedOutcome>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    HostCall(HostCall),
+    External(ExternalEvent),
+}
+
+/// A single call of a host function made by the contract.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HostCall {
+    pub name: String,
+    pub args: Vec<u64>,
+    /// The value returned to the contract, or the `Debug` representation of
+    /// the error that the host function failed with.
+    pub result: Result<Option<u64>, String>,
+    /// Contents of the register named by the `register_id` argument after the
+    /// call, if the function takes one. Depending on the function this is the
+    /// register it wrote to or the one it read from.
+    pub register: Option<Vec<u8>>,
+    /// Burnt gas right after the host function returned.
+    pub burnt_gas: Gas,
+    /// Used gas right after the host function returned.
+    pub used_gas: Gas,
+}
+
+/// An interaction with [`External`] together with the data it returned.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ExternalEvent {
+    StorageSet {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    /// A storage lookup; `value_len` is `None` if the key was not present.
+    StorageGet {
+        key: Vec<u8>,
+        value_len: Option<u32>,
+    },
+    /// The value behind the pointer returned by the preceding `StorageGet` was
+    /// read.
+    StorageValue {
+        value: Vec<u8>,
+    },
+    StorageRemove {
+        key: Vec<u8>,
+    },
+    StorageRemoveSubtree {
+        prefix: Vec<u8>,
+    },
+    StorageHasKey {
+        key: Vec<u8>,
+        result: bool,
+    },
+    GenerateDataId {
+        data_id: CryptoHash,
+    },
+    TrieNodesCount {
+        db_reads: u64,
+        mem_reads: u64,
+    },
+    ValidatorStake {
+        account_id: AccountId,
+        stake: Option<Balance>,
+    },
+    ValidatorPower {
+        account_id: AccountId,
+        power: Option<Power>,
+    },
+    ValidatorTotalStake {
+        stake: Balance,
+    },
+    ValidatorTotalPower {
+        power: Power,
+    },
+    CreateReceipt {
+        receipt_indices: Vec<ReceiptIndex>,
+        receiver_id: AccountId,
+        receipt_index: ReceiptIndex,
+    },
+    AppendAction {
+        receipt_index: ReceiptIndex,
+        action: TracedAction,
+    },
+    CreatePromiseYieldReceipt {
+        receiver_id: AccountId,
+        receipt_index: ReceiptIndex,
+        data_id: CryptoHash,
+    },
+    SubmitPromiseResumeData {
+        data_id: CryptoHash,
+        data: Vec<u8>,
+        result: bool,
+    },
+}
+
+/// An action attached to a receipt created by the contract.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TracedAction {
+    CreateAccount,
+    DeployContract {
+        code_hash: CryptoHash,
+    },
+    FunctionCall {
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+        gas_weight: u64,
+    },
+    Transfer {
+        deposit: Balance,
+    },
+    Pledge {
+        stake: Balance,
+        public_key: PublicKey,
+    },
+    AddKeyWithFullAccess {
+        public_key: PublicKey,
+        nonce: Nonce,
+    },
+    AddKeyWithFunctionCall {
+        public_key: PublicKey,
+        nonce: Nonce,
+        allowance: Option<Balance>,
+        receiver_id: AccountId,
+        method_names: Vec<Vec<u8>>,
+    },
+    DeleteKey {
+        public_key: PublicKey,
+    },
+    DeleteAccount {
+        beneficiary_id: AccountId,
+    },
+}
+
+/// The final result of a traced execution.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TracedOutcome {
+    Completed {
+        return_data: ReturnData,
+        burnt_gas: Gas,
+        used_gas: Gas,
+        logs: Vec<String>,
+        /// `Debug` representation of the `FunctionCallError`, if any.
+        aborted: Option<String>,
+    },
+    /// The execution failed with a [`VMRunnerError`], shown via `Debug`.
+    RunnerError(String),
+}
+
+impl TracedOutcome {
+    fn new(result: &Result<VMOutcome, VMRunnerError>) -> Self {
+        match result {
+            Ok(outcome) => TracedOutcome::Completed {
+                return_data: outcome.return_data.clone(),
+                burnt_gas: outcome.burnt_gas,
+                used_gas: outcome.used_gas,
+                logs: outcome.logs.clone(),
+                aborted: outcome.aborted.as_ref().map(|err| format!("{err:?}")),
+            },
+            Err(err) => TracedOutcome::RunnerError(format!("{err:?}")),
+        }
+    }
+}
+
+/// The first point at which two traces disagree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceDivergence {
+    /// The traces do not describe the same function call.
+    Header,
+    /// The events at `index` differ; `None` means the trace has ended.
+    Event {
+        index: usize,
+        expected: Option<TraceEvent>,
+        actual: Option<TraceEvent>,
+    },
+    Outcome {
+        expected: Option<TracedOutcome>,
+        actual: Option<TracedOutcome>,
+    },
+}
+
+impl ExecutionTrace {
+    /// Writes the trace into a borsh-encoded file.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, borsh::to_vec(self)?)
+    }
+
+    /// Reads a trace previously written with [`Self::save`].
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Self::try_from_slice(&std::fs::read(path)?)
+    }
+
+    /// Finds the first point at which the `other` trace differs from this one.
+    ///
+    /// The VM kind is not compared, so that traces produced by different VMs
+    /// for the same call can be checked against each other.
+    pub fn first_divergence(&self, other: &ExecutionTrace) -> Option<TraceDivergence> {
+        if self.code_hash != other.code_hash
+            || self.method_name != other.method_name
+            || self.context != other.context
+            || self.promise_results != other.promise_results
+        {
+            return Some(TraceDivergence::Header);
+        }
+        let len = self.events.len().max(other.events.len());
+        for index in 0..len {
+            let expected = self.events.get(index);
+            let actual = other.events.get(index);
+            if expected != actual {
+                return Some(TraceDivergence::Event {
+                    index,
+                    expected: expected.cloned(),
+                    actual: actual.cloned(),
+                });
+            }
+        }
+        if self.outcome != other.outcome {
+            return Some(TraceDivergence::Outcome {
+                expected: self.outcome.clone(),
+                actual: other.outcome.clone(),
+            });
+        }
+        None
+    }
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<TraceRecorder>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, recording a trace of every contract execution made through
+/// [`crate::run`] on the current thread while it runs.
+pub fn record<R>(f: impl FnOnce() -> R) -> (R, Vec<ExecutionTrace>) {
+    struct Restore(Option<TraceRecorder>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            RECORDER.with(|cell| *cell.borrow_mut() = self.0.take());
+        }
+    }
+
+    let recorder = TraceRecorder::default();
+    let previous = RECORDER.with(|cell| cell.borrow_mut().replace(recorder.clone()));
+    let result = {
+        let _restore = Restore(previous);
+        f()
+    };
+    let traces = std::mem::take(&mut recorder.0.borrow_mut().finished);
+    (result, traces)
+}
+
+#[derive(Default)]
+struct RecorderState {
+    current: Option<ExecutionTrace>,
+    finished: Vec<ExecutionTrace>,
+}
+
+/// Handle to the trace being recorded on the current thread.
+#[derive(Clone, Default)]
+pub(crate) struct TraceRecorder(Rc<RefCell<RecorderState>>);
+
+impl TraceRecorder {
+    /// Returns the recorder if tracing has been enabled with [`record`].
+    pub(crate) fn active() -> Option<Self> {
+        RECORDER.with(|cell| cell.borrow().clone())
+    }
+
+    pub(crate) fn begin(
+        &self,
+        vm_kind: VMKind,
+        code: &ContractCode,
+        method_name: &str,
+        context: &VMContext,
+        promise_results: &[PromiseResult],
+    ) {
+        self.0.borrow_mut().current = Some(ExecutionTrace {
+            vm_kind,
+            code_hash: *code.hash(),
+            method_name: method_name.to_string(),
+            context: context.clone(),
+            promise_results: promise_results.to_vec(),
+            events: vec![],
+            outcome: None,
+        });
+    }
+
+    pub(crate) fn finish(&self, result: &Result<VMOutcome, VMRunnerError>) {
+        let mut state = self.0.borrow_mut();
+        if let Some(mut trace) = state.current.take() {
+            trace.outcome = Some(TracedOutcome::new(result));
+            state.finished.push(trace);
+        }
+    }
+
+    pub(crate) fn push(&self, event: TraceEvent) {
+        if let Some(trace) = &mut self.0.borrow_mut().current {
+            trace.events.push(event);
+        }
+    }
+
+    fn push_external(&self, event: ExternalEvent) {
+        self.push(TraceEvent::External(event))
+    }
+}
+
+/// Conversion of host function return values into their traced form.
+pub(crate) trait HostCallReturn {
+    fn traced(&self) -> Option<u64>;
+}
+
+impl HostCallReturn for () {
+    fn traced(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl HostCallReturn for u32 {
+    fn traced(&self) -> Option<u64> {
+        Some(u64::from(*self))
+    }
+}
+
+impl HostCallReturn for u64 {
+    fn traced(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+/// An [`External`] which forwards to another implementation and records all
+/// interactions with it.
+pub(crate) struct TracingExternal<'a> {
+    inner: &'a mut dyn External,
+    recorder: TraceRecorder,
+}
+
+impl<'a> TracingExternal<'a> {
+    pub(crate) fn new(inner: &'a mut dyn External, recorder: TraceRecorder) -> Self {
+        Self { inner, recorder }
+    }
+
+    fn action(&self, receipt_index: ReceiptIndex, action: TracedAction) {
+        self.recorder.push_external(ExternalEvent::AppendAction { receipt_index, action });
+    }
+}
+
+struct TracingValuePtr<'a> {
+    inner: Box<dyn ValuePtr + 'a>,
+    recorder: TraceRecorder,
+}
+
+impl ValuePtr for TracingValuePtr<'_> {
+    fn len(&self) -> u32 {
+        self.inner.len()
+    }
+
+    fn deref(&self) -> Result<Vec<u8>> {
+        let value = self.inner.deref()?;
+        self.recorder.push_external(ExternalEvent::StorageValue { value: value.clone() });
+        Ok(value)
+    }
+}
+
+impl External for TracingExternal<'_> {
+    fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.inner.storage_set(key, value)?;
+        self.recorder
+            .push_external(ExternalEvent::StorageSet { key: key.to_vec(), value: value.to_vec() });
+        Ok(())
+    }
+
+    fn storage_get<'a>(
+        &'a self,
+        key: &[u8],
+        mode: StorageGetMode,
+    ) -> Result<Option<Box<dyn ValuePtr + 'a>>> {
+        let ptr = self.inner.storage_get(key, mode)?;
+        let value_len = ptr.as_ref().map(|ptr| ptr.len());
+        self.recorder.push_external(ExternalEvent::StorageGet { key: key.to_vec(), value_len });
+        Ok(ptr.map(|inner| {
+            Box::new(TracingValuePtr { inner, recorder: self.recorder.clone() }) as Box<_>
+        }))
+    }
+
+    fn storage_remove(&mut self, key: &[u8]) -> Result<()> {
+        self.inner.storage_remove(key)?;
+        self.recorder.push_external(ExternalEvent::StorageRemove { key: key.to_vec() });
+        Ok(())
+    }
+
+    fn storage_remove_subtree(&mut self, prefix: &[u8]) -> Result<()> {
+        self.inner.storage_remove_subtree(prefix)?;
+        self.recorder
+            .push_external(ExternalEvent::StorageRemoveSubtree { prefix: prefix.to_vec() });
+        Ok(())
+    }
+
+    fn storage_has_key(&mut self, key: &[u8], mode: StorageGetMode) -> Result<bool> {
+        let result = self.inner.storage_has_key(key, mode)?;
+        self.recorder.push_external(ExternalEvent::StorageHasKey { key: key.to_vec(), result });
+        Ok(result)
+    }
+
+    fn generate_data_id(&mut self) -> CryptoHash {
+        let data_id = self.inner.generate_data_id();
+        self.recorder.push_external(ExternalEvent::GenerateDataId { data_id });
+        data_id
+    }
+
+    fn get_trie_nodes_count(&self) -> TrieNodesCount {
+        let count = self.inner.get_trie_nodes_count();
+        self.recorder.push_external(ExternalEvent::TrieNodesCount {
+            db_reads: count.db_reads,
+            mem_reads: count.mem_reads,
+        });
+        count
+    }
+
+    fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>> {
+        let stake = self.inner.validator_stake(account_id)?;
+        self.recorder
+            .push_external(ExternalEvent::ValidatorStake { account_id: account_id.clone(), stake });
+        Ok(stake)
+    }
+
+    fn validator_power(&self, account_id: &AccountId) -> Result<Option<Power>> {
+        let power = self.inner.validator_power(account_id)?;
+        self.recorder
+            .push_external(ExternalEvent::ValidatorPower { account_id: account_id.clone(), power });
+        Ok(power)
+    }
+
+    fn validator_total_stake(&self) -> Result<Balance> {
+        let stake = self.inner.validator_total_stake()?;
+        self.recorder.push_external(ExternalEvent::ValidatorTotalStake { stake });
+        Ok(stake)
+    }
+
+    fn validator_total_power(&self) -> Result<Power> {
+        let power = self.inner.validator_total_power()?;
+        self.recorder.push_external(ExternalEvent::ValidatorTotalPower { power });
+        Ok(power)
+    }
+
+    fn create_receipt(
+        &mut self,
+        receipt_indices: Vec<ReceiptIndex>,
+        receiver_id: AccountId,
+    ) -> Result<ReceiptIndex> {
+        let receipt_index =
+            self.inner.create_receipt(receipt_indices.clone(), receiver_id.clone())?;
+        self.recorder.push_external(ExternalEvent::CreateReceipt {
+            receipt_indices,
+            receiver_id,
+            receipt_index,
+        });
+        Ok(receipt_index)
+    }
+
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash)> {
+        let (receipt_index, data_id) =
+            self.inner.create_promise_yield_receipt(receiver_id.clone())?;
+        self.recorder.push_external(ExternalEvent::CreatePromiseYieldReceipt {
+            receiver_id,
+            receipt_index,
+            data_id,
+        });
+        Ok((receipt_index, data_id))
+    }
+
+    fn submit_promise_resume_data(&mut self, data_id: CryptoHash, data: Vec<u8>) -> Result<bool> {
+        let result = self.inner.submit_promise_resume_data(data_id, data.clone())?;
+        self.recorder.push_external(ExternalEvent::SubmitPromiseResumeData {
+            data_id,
+            data,
+            result,
+        });
+        Ok(result)
+    }
+
+    fn append_action_create_account(&mut self, receipt_index: ReceiptIndex) -> Result<()> {
+        self.inner.append_action_create_account(receipt_index)?;
+        self.action(receipt_index, TracedAction::CreateAccount);
+        Ok(())
+    }
+
+    fn append_action_deploy_contract(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        code: Vec<u8>,
+    ) -> Result<()> {
+        let code_hash = unc_primitives_core::hash::hash(&code);
+        self.inner.append_action_deploy_contract(receipt_index, code)?;
+        self.action(receipt_index, TracedAction::DeployContract { code_hash });
+        Ok(())
+    }
+
+    fn append_action_function_call_weight(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+        gas_weight: GasWeight,
+    ) -> Result<()> {
+        let action = TracedAction::FunctionCall {
+            method_name: method_name.clone(),
+            args: args.clone(),
+            attached_deposit,
+            prepaid_gas,
+            gas_weight: gas_weight.0,
+        };
+        self.inner.append_action_function_call_weight(
+            receipt_index,
+            method_name,
+            args,
+            attached_deposit,
+            prepaid_gas,
+            gas_weight,
+        )?;
+        self.action(receipt_index, action);
+        Ok(())
+    }
+
+    fn append_action_transfer(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        deposit: Balance,
+    ) -> Result<()> {
+        self.inner.append_action_transfer(receipt_index, deposit)?;
+        self.action(receipt_index, TracedAction::Transfer { deposit });
+        Ok(())
+    }
+
+    fn append_action_pledge(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        stake: Balance,
+        public_key: PublicKey,
+    ) {
+        self.inner.append_action_pledge(receipt_index, stake, public_key.clone());
+        self.action(receipt_index, TracedAction::Pledge { stake, public_key });
+    }
+
+    fn append_action_add_key_with_full_access(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+    ) {
+        self.inner.append_action_add_key_with_full_access(receipt_index, public_key.clone(), nonce);
+        self.action(receipt_index, TracedAction::AddKeyWithFullAccess { public_key, nonce });
+    }
+
+    fn append_action_add_key_with_function_call(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+        allowance: Option<Balance>,
+        receiver_id: AccountId,
+        method_names: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let action = TracedAction::AddKeyWithFunctionCall {
+            public_key: public_key.clone(),
+            nonce,
+            allowance,
+            receiver_id: receiver_id.clone(),
+            method_names: method_names.clone(),
+        };
+        self.inner.append_action_add_key_with_function_call(
+            receipt_index,
+            public_key,
+            nonce,
+            allowance,
+            receiver_id,
+            method_names,
+        )?;
+        self.action(receipt_index, action);
+        Ok(())
+    }
+
+    fn append_action_delete_key(&mut self, receipt_index: ReceiptIndex, public_key: PublicKey) {
+        self.inner.append_action_delete_key(receipt_index, public_key.clone());
+        self.action(receipt_index, TracedAction::DeleteKey { public_key });
+    }
+
+    fn append_action_delete_account(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        beneficiary_id: AccountId,
+    ) -> Result<()> {
+        self.inner.append_action_delete_account(receipt_index, beneficiary_id.clone())?;
+        self.action(receipt_index, TracedAction::DeleteAccount { beneficiary_id });
+        Ok(())
+    }
+
+    fn get_receipt_receiver(&self, receipt_index: ReceiptIndex) -> &AccountId {
+        self.inner.get_receipt_receiver(receipt_index)
+    }
+}
+
+/// Error returned when a replayed execution does not match its trace.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ReplayError {
+    #[error("contract code hash {actual} does not match the traced code hash {expected}")]
+    CodeMismatch { expected: CryptoHash, actual: CryptoHash },
+    #[error(
+        "replay requested {actual:?} but the trace has {expected:?} at external event {index}"
+    )]
+    UnexpectedEvent { index: usize, expected: Option<Box<ExternalEvent>>, actual: String },
+    #[error("trace has no outcome, the original execution did not finish")]
+    Unfinished,
+    #[error("replay finished with {remaining} external events of the trace not requested")]
+    UnconsumedEvents { remaining: usize },
+    #[error("replay finished with outcome {actual:?} but the trace has {expected:?}")]
+    OutcomeMismatch { expected: Box<Option<TracedOutcome>>, actual: Box<Option<TracedOutcome>> },
+}
+
+/// An [`External`] which serves all requests from a recorded trace.
+///
+/// Every request is checked against the next recorded [`ExternalEvent`]; the
+/// first mismatch is remembered and reported by [`replay`]. After a mismatch no
+/// more events are consumed and every fallible request fails with the same
+/// error, but the infallible ones ([`External::generate_data_id`],
+/// [`External::get_trie_nodes_count`] and some of the actions) have to return
+/// something, so they return default values. The execution that follows a
+/// divergence is therefore meaningless and must be discarded.
+pub struct ReplayExternal {
+    events: RefCell<VecDeque<ExternalEvent>>,
+    consumed: Cell<usize>,
+    divergence: RefCell<Option<ReplayError>>,
+    receivers: Vec<(ReceiptIndex, AccountId)>,
+}
+
+impl ReplayExternal {
+    pub fn new(trace: &ExecutionTrace) -> Self {
+        let events = trace
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                TraceEvent::External(event) => Some(event.clone()),
+                TraceEvent::HostCall(_) => None,
+            })
+            .collect();
+        Self {
+            events: RefCell::new(events),
+            consumed: Cell::new(0),
+            divergence: RefCell::new(None),
+            receivers: vec![],
+        }
+    }
+
+    /// Returns the first mismatch between the requests and the trace, if any.
+    ///
+    /// Once this returns `Some`, the responses given to the contract no longer
+    /// come from the trace, see the type level documentation.
+    pub fn divergence(&self) -> Option<ReplayError> {
+        self.divergence.borrow().clone()
+    }
+
+    /// Number of recorded events which have not been requested yet.
+    pub fn remaining(&self) -> usize {
+        self.events.borrow().len()
+    }
+
+    /// Pops the next event, passing it to `matches` which extracts the
+    /// response; records a divergence if the event is not the expected one.
+    fn next<T>(
+        &self,
+        request: impl FnOnce() -> String,
+        matches: impl FnOnce(&ExternalEvent) -> Option<T>,
+    ) -> std::result::Result<T, ReplayError> {
+        if let Some(divergence) = self.divergence() {
+            return Err(divergence);
+        }
+        let index = self.consumed.get();
+        let event = self.events.borrow_mut().pop_front();
+        self.consumed.set(index + 1);
+        match event.as_ref().and_then(matches) {
+            Some(response) => Ok(response),
+            None => {
+                let error = ReplayError::UnexpectedEvent {
+                    index,
+                    expected: event.map(Box::new),
+                    actual: request(),
+                };
+                self.divergence.borrow_mut().get_or_insert(error.clone());
+                Err(error)
+            }
+        }
+    }
+
+    fn expect(&self, expected: ExternalEvent) -> Result<()> {
+        self.next(|| format!("{expected:?}"), |event| (*event == expected).then_some(()))
+            .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn expect_action(&self, receipt_index: ReceiptIndex, action: TracedAction) -> Result<()> {
+        self.expect(ExternalEvent::AppendAction { receipt_index, action })
+    }
+}
+
+struct ReplayValuePtr<'a> {
+    len: u32,
+    ext: &'a ReplayExternal,
+}
+
+impl ValuePtr for ReplayValuePtr<'_> {
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn deref(&self) -> Result<Vec<u8>> {
+        self.ext
+            .next(
+                || "StorageValue".to_string(),
+                |event| match event {
+                    ExternalEvent::StorageValue { value } => Some(value.clone()),
+                    _ => None,
+                },
+            )
+            .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+}
+
+impl External for ReplayExternal {
+    fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.expect(ExternalEvent::StorageSet { key: key.to_vec(), value: value.to_vec() })
+    }
+
+    fn storage_get<'a>(
+        &'a self,
+        key: &[u8],
+        _mode: StorageGetMode,
+    ) -> Result<Option<Box<dyn ValuePtr + 'a>>> {
+        let value_len = self
+            .next(
+                || format!("StorageGet {{ key: {key:?} }}"),
+                |event| match event {
+                    ExternalEvent::StorageGet { key: traced, value_len } if traced == key => {
+                        Some(*value_len)
+                    }
+                    _ => None,
+                },
+            )
+            .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))?;
+        Ok(value_len.map(|len| Box::new(ReplayValuePtr { len, ext: self }) as Box<_>))
+    }
+
+    fn storage_remove(&mut self, key: &[u8]) -> Result<()> {
+        self.expect(ExternalEvent::StorageRemove { key: key.to_vec() })
+    }
+
+    fn storage_remove_subtree(&mut self, prefix: &[u8]) -> Result<()> {
+        self.expect(ExternalEvent::StorageRemoveSubtree { prefix: prefix.to_vec() })
+    }
+
+    fn storage_has_key(&mut self, key: &[u8], _mode: StorageGetMode) -> Result<bool> {
+        self.next(
+            || format!("StorageHasKey {{ key: {key:?} }}"),
+            |event| match event {
+                ExternalEvent::StorageHasKey { key: traced, result } if traced == key => {
+                    Some(*result)
+                }
+                _ => None,
+            },
+        )
+        .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn generate_data_id(&mut self) -> CryptoHash {
+        self.next(
+            || "GenerateDataId".to_string(),
+            |event| match event {
+                ExternalEvent::GenerateDataId { data_id } => Some(*data_id),
+                _ => None,
+            },
+        )
+        .unwrap_or_default()
+    }
+
+    fn get_trie_nodes_count(&self) -> TrieNodesCount {
+        self.next(
+            || "TrieNodesCount".to_string(),
+            |event| match event {
+                ExternalEvent::TrieNodesCount { db_reads, mem_reads } => {
+                    Some(TrieNodesCount { db_reads: *db_reads, mem_reads: *mem_reads })
+                }
+                _ => None,
+            },
+        )
+        .unwrap_or(TrieNodesCount { db_reads: 0, mem_reads: 0 })
+    }
+
+    fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>> {
+        self.next(
+            || format!("ValidatorStake {{ account_id: {account_id} }}"),
+            |event| match event {
+                ExternalEvent::ValidatorStake { account_id: traced, stake }
+                    if traced == account_id =>
+                {
+                    Some(*stake)
+                }
+                _ => None,
+            },
+        )
+        .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn validator_power(&self, account_id: &AccountId) -> Result<Option<Power>> {
+        self.next(
+            || format!("ValidatorPower {{ account_id: {account_id} }}"),
+            |event| match event {
+                ExternalEvent::ValidatorPower { account_id: traced, power }
+                    if traced == account_id =>
+                {
+                    Some(*power)
+                }
+                _ => None,
+            },
+        )
+        .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn validator_total_stake(&self) -> Result<Balance> {
+        self.next(
+            || "ValidatorTotalStake".to_string(),
+            |event| match event {
+                ExternalEvent::ValidatorTotalStake { stake } => Some(*stake),
+                _ => None,
+            },
+        )
+        .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn validator_total_power(&self) -> Result<Power> {
+        self.next(
+            || "ValidatorTotalPower".to_string(),
+            |event| match event {
+                ExternalEvent::ValidatorTotalPower { power } => Some(*power),
+                _ => None,
+            },
+        )
+        .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn create_receipt(
+        &mut self,
+        receipt_indices: Vec<ReceiptIndex>,
+        receiver_id: AccountId,
+    ) -> Result<ReceiptIndex> {
+        let receipt_index = self
+            .next(
+                || format!("CreateReceipt {{ {receipt_indices:?}, {receiver_id} }}"),
+                |event| match event {
+                    ExternalEvent::CreateReceipt {
+                        receipt_indices: traced_indices,
+                        receiver_id: traced_receiver,
+                        receipt_index,
+                    } if *traced_indices == receipt_indices && *traced_receiver == receiver_id => {
+                        Some(*receipt_index)
+                    }
+                    _ => None,
+                },
+            )
+            .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))?;
+        self.receivers.push((receipt_index, receiver_id));
+        Ok(receipt_index)
+    }
+
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash)> {
+        let (receipt_index, data_id) = self
+            .next(
+                || format!("CreatePromiseYieldReceipt {{ {receiver_id} }}"),
+                |event| match event {
+                    ExternalEvent::CreatePromiseYieldReceipt {
+                        receiver_id: traced_receiver,
+                        receipt_index,
+                        data_id,
+                    } if *traced_receiver == receiver_id => Some((*receipt_index, *data_id)),
+                    _ => None,
+                },
+            )
+            .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))?;
+        self.receivers.push((receipt_index, receiver_id));
+        Ok((receipt_index, data_id))
+    }
+
+    fn submit_promise_resume_data(&mut self, data_id: CryptoHash, data: Vec<u8>) -> Result<bool> {
+        self.next(
+            || format!("SubmitPromiseResumeData {{ data_id: {data_id} }}"),
+            |event| match event {
+                ExternalEvent::SubmitPromiseResumeData {
+                    data_id: traced_id,
+                    data: traced_data,
+                    result,
+                } if *traced_id == data_id && *traced_data == data => Some(*result),
+                _ => None,
+            },
+        )
+        .map_err(|err| VMLogicError::ExternalError(AnyError::new(err)))
+    }
+
+    fn append_action_create_account(&mut self, receipt_index: ReceiptIndex) -> Result<()> {
+        self.expect_action(receipt_index, TracedAction::CreateAccount)
+    }
+
+    fn append_action_deploy_contract(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        code: Vec<u8>,
+    ) -> Result<()> {
+        let code_hash = unc_primitives_core::hash::hash(&code);
+        self.expect_action(receipt_index, TracedAction::DeployContract { code_hash })
+    }
+
+    fn append_action_function_call_weight(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+        gas_weight: GasWeight,
+    ) -> Result<()> {
+        self.expect_action(
+            receipt_index,
+            TracedAction::FunctionCall {
+                method_name,
+                args,
+                attached_deposit,
+                prepaid_gas,
+                gas_weight: gas_weight.0,
+            },
+        )
+    }
+
+    fn append_action_transfer(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        deposit: Balance,
+    ) -> Result<()> {
+        self.expect_action(receipt_index, TracedAction::Transfer { deposit })
+    }
+
+    fn append_action_pledge(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        stake: Balance,
+        public_key: PublicKey,
+    ) {
+        let _ = self.expect_action(receipt_index, TracedAction::Pledge { stake, public_key });
+    }
+
+    fn append_action_add_key_with_full_access(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+    ) {
+        let _ = self
+            .expect_action(receipt_index, TracedAction::AddKeyWithFullAccess { public_key, nonce });
+    }
+
+    fn append_action_add_key_with_function_call(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+        allowance: Option<Balance>,
+        receiver_id: AccountId,
+        method_names: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        self.expect_action(
+            receipt_index,
+            TracedAction::AddKeyWithFunctionCall {
+                public_key,
+                nonce,
+                allowance,
+                receiver_id,
+                method_names,
+            },
+        )
+    }
+
+    fn append_action_delete_key(&mut self, receipt_index: ReceiptIndex, public_key: PublicKey) {
+        let _ = self.expect_action(receipt_index, TracedAction::DeleteKey { public_key });
+    }
+
+    fn append_action_delete_account(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        beneficiary_id: AccountId,
+    ) -> Result<()> {
+        self.expect_action(receipt_index, TracedAction::DeleteAccount { beneficiary_id })
+    }
+
+    fn get_receipt_receiver(&self, receipt_index: ReceiptIndex) -> &AccountId {
+        self.receivers
+            .iter()
+            .find(|(index, _)| *index == receipt_index)
+            .map(|(_, receiver_id)| receiver_id)
+            .expect("receipt index must refer to a receipt created during the replay")
+    }
+}
+
+/// Re-executes a traced function call with the VM given in `config`.
+///
+/// All [`External`] requests are served from `trace`, so this does not need
+/// any access to state. Fails if the replay did not request exactly the
+/// recorded [`External`] events or if it finished with a different outcome.
+///
+/// Host function calls, including the gas after each of them, are not checked
+/// here. To compare those, use [`ExecutionTrace::first_divergence`] on the
+/// returned trace of the replayed execution.
+pub fn replay(
+    trace: &ExecutionTrace,
+    code: &ContractCode,
+    config: &Config,
+    fees_config: &RuntimeFeesConfig,
+) -> std::result::Result<ExecutionTrace, ReplayError> {
+    if trace.code_hash != *code.hash() {
+        return Err(ReplayError::CodeMismatch { expected: trace.code_hash, actual: *code.hash() });
+    }
+    if trace.outcome.is_none() {
+        return Err(ReplayError::Unfinished);
+    }
+    let mut ext = ReplayExternal::new(trace);
+    let (_, mut traces) = record(|| {
+        crate::run(
+            code,
+            &trace.method_name,
+            &mut ext,
+            trace.context.clone(),
+            config,
+            fees_config,
+            &trace.promise_results,
+            None,
+        )
+    });
+    if let Some(divergence) = ext.divergence() {
+        return Err(divergence);
+    }
+    let remaining = ext.remaining();
+    if remaining > 0 {
+        return Err(ReplayError::UnconsumedEvents { remaining });
+    }
+    let replayed = traces.pop().expect("replayed execution must have been traced");
+    if replayed.outcome != trace.outcome {
+        return Err(ReplayError::OutcomeMismatch {
+            expected: Box::new(trace.outcome.clone()),
+            actual: Box::new(replayed.outcome),
+        });
+    }
+    Ok(replayed)
+}
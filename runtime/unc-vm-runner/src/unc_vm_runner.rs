@@ -239,6 +239,11 @@ impl UncVM {
         assert_eq!(VM_CONFIG.compiler, UncVmCompiler::Singlepass);
         let mut compiler = Singlepass::new();
         compiler.set_9393_fix(!config.disable_9393_fix);
+        if config.bulk_memory {
+            // Bulk memory operations touch an arbitrary amount of memory, so charge for every
+            // word they process rather than only for the instruction.
+            compiler.bulk_memory_word_cost(config.regular_op_cost.into());
+        }
         // We only support universal engine at the moment.
         assert_eq!(VM_CONFIG.engine, UncVmEngine::Universal);
 
@@ -264,8 +269,7 @@ impl UncVM {
             })
             .clone();
 
-        let features =
-            crate::features::WasmFeatures::from(config.limit_config.contract_prepare_version);
+        let features = crate::features::WasmFeatures::new(&config);
         Self {
             config,
             engine: Universal::new(compiler)
@@ -323,7 +327,7 @@ impl UncVM {
         cache: Option<&dyn CompiledContractCache>,
     ) -> Result<Result<UniversalExecutable, CompilationError>, CacheError> {
         let executable_or_error = self.compile_uncached(code);
-        let key = get_contract_cache_key(code, &self.config);
+        let key = get_contract_cache_key(*code.hash(), &self.config);
 
         if let Some(cache) = cache {
             let record = match &executable_or_error {
@@ -352,7 +356,7 @@ impl UncVM {
         // re-parse invalid code (invalid code, in a sense, is a normal
         // outcome). And `cache`, being a database, can fail with an `io::Error`.
         let _span = tracing::debug_span!(target: "vm", "UncVM::compile_and_load").entered();
-        let key = get_contract_cache_key(code, &self.config);
+        let key = get_contract_cache_key(*code.hash(), &self.config);
         let cache_record = cache
             .map(|cache| cache.get(&key))
             .transpose()
@@ -241,8 +241,7 @@ impl Wasmer2VM {
         let compiler = Singlepass::new();
         // We only support universal engine at the moment.
         assert_eq!(WASMER2_CONFIG.engine, WasmerEngine::Universal);
-        let features =
-            crate::features::WasmFeatures::from(config.limit_config.contract_prepare_version);
+        let features = crate::features::WasmFeatures::new(&config);
         Self {
             config,
             engine: Universal::new(compiler).target(target).features(features.into()).engine(),
@@ -296,7 +295,7 @@ impl Wasmer2VM {
         cache: Option<&dyn CompiledContractCache>,
     ) -> Result<Result<UniversalExecutable, CompilationError>, CacheError> {
         let executable_or_error = self.compile_uncached(code);
-        let key = get_contract_cache_key(code, &self.config);
+        let key = get_contract_cache_key(*code.hash(), &self.config);
 
         if let Some(cache) = cache {
             let record = match &executable_or_error {
@@ -329,7 +328,7 @@ impl Wasmer2VM {
         // outcome). And `cache`, being a database, can fail with an `io::Error`.
         let _span = tracing::debug_span!(target: "vm", "Wasmer2VM::compile_and_load").entered();
 
-        let key = get_contract_cache_key(code, &self.config);
+        let key = get_contract_cache_key(*code.hash(), &self.config);
 
         let compile_or_read_from_cache = || -> VMResult<Result<VMArtifact, CompilationError>> {
             let _span = tracing::debug_span!(target: "vm", "Wasmer2VM::compile_or_read_from_cache")
@@ -226,9 +226,17 @@ pub(crate) struct ControlFrame {
 
     pub(crate) loop_like: bool,
     pub(crate) if_else: IfElseState,
+    pub(crate) params: SmallVec<[WpType; 1]>,
     pub(crate) returns: SmallVec<[WpType; 1]>,
     pub(crate) value_stack_depth: usize,
     pub(crate) fp_stack_depth: usize,
+    /// Machine stack offset of the block's value slots, for blocks taking parameters or returning
+    /// more than one value.
+    ///
+    /// Such blocks don't pass their value through `RAX`. Instead, the values passed to `br_label`
+    /// are stored in consecutive stack slots right above this offset, the first value in the
+    /// lowest slot.
+    pub(crate) slots_offset: Option<usize>,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -308,6 +316,8 @@ impl<'a> FuncGen<'a> {
             }
         }
 
+        let ret_area = self.reserve_ret_area(&params, return_types.len());
+
         let reloc_at = self.assembler.get_offset().0 + self.assembler.arch_mov64_imm_offset();
         // Imported functions are called through trampolines placed as custom sections.
         let reloc_target = match self.module.import_counts.local_function_index(function) {
@@ -327,14 +337,22 @@ impl<'a> FuncGen<'a> {
 
         self.emit_call_native(
             |this| {
+                if let Some((slots_offset, _)) = ret_area {
+                    this.emit_ret_area_pointer(slots_offset, param_types.len());
+                }
                 this.assembler.emit_call_location(Location::GPR(GPR::RAX));
             },
-            params.iter().copied(),
+            params.iter().copied().chain(ret_area.map(|_| Location::Imm32(0))),
         )?;
 
+        if let Some((_, extra)) = ret_area.filter(|&(_, extra)| extra != 0) {
+            self.machine.increase_rsp(self.assembler, extra);
+        }
         self.machine.release_locations_only_stack(self.assembler, &params);
 
-        if !return_types.is_empty() {
+        if ret_area.is_some() {
+            self.push_slots(&return_types);
+        } else if !return_types.is_empty() {
             let ret =
                 self.machine.acquire_locations(self.assembler, &[(return_types[0])], false)[0];
             self.value_stack.push(ret);
@@ -348,6 +366,242 @@ impl<'a> FuncGen<'a> {
         Ok(())
     }
 
+    /// Parameter and result types of a block.
+    fn block_signature(
+        &self,
+        blockty: WpBlockType,
+    ) -> (SmallVec<[WpType; 1]>, SmallVec<[WpType; 1]>) {
+        match blockty {
+            WpBlockType::Empty => (smallvec![], smallvec![]),
+            WpBlockType::Type(inner_ty) => (smallvec![], smallvec![inner_ty]),
+            WpBlockType::FuncType(index) => {
+                let sig = &self.module.signatures[SignatureIndex::new(index as usize)];
+                (
+                    sig.params().iter().cloned().map(type_to_wp_type).collect(),
+                    sig.results().iter().cloned().map(type_to_wp_type).collect(),
+                )
+            }
+        }
+    }
+
+    /// Machine stack offset once the value stack gets truncated to `depth`.
+    fn stack_offset_at_depth(&self, depth: usize) -> usize {
+        let stack_values = self.value_stack[depth..]
+            .iter()
+            .filter(|loc| matches!(loc, Location::Memory(GPR::RBP, x) if *x < 0))
+            .count();
+        self.machine.get_stack_offset() - stack_values * 8
+    }
+
+    /// Number of `fp_stack` entries describing values below `depth` in the value stack.
+    fn fp_stack_depth_at(&self, depth: usize) -> usize {
+        self.fp_stack.iter().take_while(|fp| fp.depth < depth).count()
+    }
+
+    /// Copies the values at the top of the value stack, of types `tys`, to the stack slots right
+    /// above the machine stack offset `slots_offset`, canonicalizing them if needed.
+    ///
+    /// The values may currently live in the destination slots, so they are staged on the native
+    /// stack first. The value stack, the machine state and `RSP` are left unchanged.
+    fn emit_copy_to_slots(&mut self, tys: &[WpType], slots_offset: usize) {
+        let current = self.machine.get_stack_offset();
+        let slots_end = slots_offset + tys.len() * 8;
+        // Keep the staging area clear of the destination slots.
+        let extra = slots_end.saturating_sub(current);
+        if extra != 0 {
+            self.assembler.emit_sub(
+                Size::S64,
+                Location::Imm32(extra as u32),
+                Location::GPR(GPR::RSP),
+            );
+        }
+
+        let first = self.value_stack.len() - tys.len();
+        let tmp = self.machine.acquire_temp_gpr().unwrap();
+        for (i, ty) in tys.iter().enumerate() {
+            let depth = first + i;
+            let loc = self.value_stack[depth];
+            let canonicalization = if ty.is_float() {
+                self.fp_stack
+                    .iter()
+                    .rev()
+                    .find(|fp| fp.depth == depth)
+                    .and_then(|fp| fp.canonicalization)
+            } else {
+                None
+            };
+            match canonicalization {
+                Some(cncl)
+                    if self.assembler.arch_supports_canonicalize_nan()
+                        && self.config.enable_nan_canonicalization =>
+                {
+                    self.canonicalize_nan(cncl.to_size(), loc, Location::GPR(tmp));
+                    self.assembler.emit_push(Size::S64, Location::GPR(tmp));
+                }
+                _ => match loc {
+                    Location::GPR(_) | Location::Memory(_, _) | Location::Imm32(_) => {
+                        self.assembler.emit_push(Size::S64, loc);
+                    }
+                    _ => {
+                        self.assembler.emit_mov(Size::S64, loc, Location::GPR(tmp));
+                        self.assembler.emit_push(Size::S64, Location::GPR(tmp));
+                    }
+                },
+            }
+        }
+        self.machine.release_temp_gpr(tmp);
+        for i in (0..tys.len()).rev() {
+            self.assembler.emit_pop(
+                Size::S64,
+                Location::Memory(GPR::RBP, -((slots_offset + (i + 1) * 8) as i32)),
+            );
+        }
+
+        if extra != 0 {
+            self.assembler.emit_add(
+                Size::S64,
+                Location::Imm32(extra as u32),
+                Location::GPR(GPR::RSP),
+            );
+        }
+    }
+
+    /// Adjusts `RSP` to correspond to the machine stack offset `offset`, without touching the
+    /// machine state. Used right before jumping to a label expecting a different stack height.
+    fn emit_rsp_fixup(&mut self, offset: usize) {
+        let current = self.machine.get_stack_offset();
+        if offset > current {
+            self.assembler.emit_sub(
+                Size::S64,
+                Location::Imm32((offset - current) as u32),
+                Location::GPR(GPR::RSP),
+            );
+        } else if offset < current {
+            self.assembler.emit_add(
+                Size::S64,
+                Location::Imm32((current - offset) as u32),
+                Location::GPR(GPR::RSP),
+            );
+        }
+    }
+
+    /// Whether the frame `relative_depth` levels up the control stack passes its values through
+    /// stack slots rather than `RAX`.
+    fn frame_has_slots(&self, relative_depth: u32) -> bool {
+        self.control_stack[self.control_stack.len() - 1 - (relative_depth as usize)]
+            .slots_offset
+            .is_some()
+    }
+
+    /// Branches to the frame at `frame_index` in the control stack, which must have value slots.
+    fn emit_br_to_slots(&mut self, frame_index: usize) {
+        let frame = &self.control_stack[frame_index];
+        let tys = if frame.loop_like { frame.params.clone() } else { frame.returns.clone() };
+        let slots_offset = frame.slots_offset.unwrap();
+        let br_label = frame.br_label;
+        self.emit_copy_to_slots(&tys, slots_offset);
+        self.emit_rsp_fixup(slots_offset + tys.len() * 8);
+        self.assembler.emit_jmp(Condition::None, br_label);
+    }
+
+    /// Pushes values of types `tys` living in freshly acquired consecutive stack slots.
+    fn push_slots(&mut self, tys: &[WpType]) {
+        let locs = self.machine.acquire_stack_locations(self.assembler, tys.len());
+        for (loc, ty) in locs.into_iter().zip(tys) {
+            self.value_stack.push(loc);
+            if ty.is_float() {
+                self.fp_stack.push(FloatValue::new(self.value_stack.len() - 1));
+            }
+        }
+    }
+
+    /// Moves the values at the top of the value stack, of types `tys`, to consecutive stack
+    /// slots, returning the machine stack offset right below them.
+    fn emit_move_to_slots(&mut self, tys: &[WpType]) -> usize {
+        let depth = self.value_stack.len() - tys.len();
+        let slots_offset = self.stack_offset_at_depth(depth);
+        self.emit_copy_to_slots(tys, slots_offset);
+        let released = &self.value_stack[depth..];
+        self.machine.release_locations(self.assembler, released);
+        self.value_stack.truncate(depth);
+        self.fp_stack.truncate(self.fp_stack_depth_at(depth));
+        self.push_slots(tys);
+        slots_offset
+    }
+
+    /// Reserves the stack slots a call returning more than one value writes its results to.
+    ///
+    /// Returns the machine stack offset right below the slots, which is where the stack will be
+    /// once the call `params` are released, and the amount of stack reserved in addition to them.
+    fn reserve_ret_area(&mut self, params: &[Location], n: usize) -> Option<(usize, usize)> {
+        if n <= 1 {
+            return None;
+        }
+        let current = self.machine.get_stack_offset();
+        let stack_params = params
+            .iter()
+            .filter(|loc| matches!(loc, Location::Memory(GPR::RBP, x) if *x < 0))
+            .count();
+        let slots_offset = current - stack_params * 8;
+        let extra = (slots_offset + n * 8).saturating_sub(current);
+        if extra != 0 {
+            self.machine.decrease_rsp(self.assembler, extra);
+        }
+        Some((slots_offset, extra))
+    }
+
+    /// Passes the pointer to the return area of a call as the argument following its `n_params`
+    /// parameters, in place of the placeholder given to `emit_call_native`.
+    ///
+    /// The callee writes its `i`-th result at `ptr - 8 * i`.
+    fn emit_ret_area_pointer(&mut self, slots_offset: usize, n_params: usize) {
+        let ptr = Location::Memory(GPR::RBP, -((slots_offset + 8) as i32));
+        match Machine::get_param_location(1 + n_params, self.calling_convention) {
+            Location::GPR(gpr) => self.assembler.emit_lea(Size::S64, ptr, Location::GPR(gpr)),
+            Location::Memory(_, offset) => {
+                // The callee will find the argument at `offset` from its `RBP`, which is 16 bytes
+                // below our `RSP` (return address and saved `RBP`).
+                self.assembler.emit_lea(Size::S64, ptr, Location::GPR(GPR::R10));
+                self.assembler.emit_mov(
+                    Size::S64,
+                    Location::GPR(GPR::R10),
+                    Location::Memory(GPR::RSP, offset - 16),
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Charges gas for the `len` operand of a bulk memory operation, one unit for every
+    /// `1 << shift` elements.
+    fn emit_bulk_memory_gas(&mut self, len: Location, shift: u8) {
+        let cost = self.config.bulk_memory_word_cost;
+        if cost == 0 {
+            return;
+        }
+        let units = self.machine.acquire_temp_gpr().unwrap();
+        self.assembler.emit_mov(Size::S32, len, Location::GPR(units));
+        if shift != 0 {
+            self.assembler.emit_add(
+                Size::S64,
+                Location::Imm32((1 << shift) - 1),
+                Location::GPR(units),
+            );
+            self.assembler.emit_shr(Size::S64, Location::Imm8(shift), Location::GPR(units));
+        }
+        if let Ok(cost) = i32::try_from(cost) {
+            self.assembler.emit_imul_imm32_gpr64(cost as u32, units);
+        } else {
+            let cost_reg = self.machine.acquire_temp_gpr().unwrap();
+            self.assembler.emit_mov(Size::S64, Location::Imm64(cost), Location::GPR(cost_reg));
+            self.assembler.emit_imul(Size::S64, Location::GPR(cost_reg), Location::GPR(units));
+            self.machine.release_temp_gpr(cost_reg);
+        }
+        self.assembler.emit_jmp(Condition::Overflow, self.special_labels.integer_overflow);
+        self.emit_gas(Location::GPR(units));
+        self.machine.release_temp_gpr(units);
+    }
+
     fn emit_gas_const(&mut self, cost: u64) {
         if self.config.disable_9393_fix {
             // emit_gas only supports Imm32 with an argument up-to i32::MAX, but we made *this*
@@ -1629,6 +1883,7 @@ impl<'a> FuncGen<'a> {
             self.assembler,
             local_count,
             self.signature.params().len() as u32,
+            self.signature.results().len() > 1,
             self.calling_convention,
         );
 
@@ -1657,18 +1912,56 @@ impl<'a> FuncGen<'a> {
 
         self.assembler.emit_sub(Size::S64, Location::Imm32(32), Location::GPR(GPR::RSP)); // simulate "red zone" if not supported by the platform
 
+        let returns: SmallVec<[WpType; 1]> =
+            self.signature.results().iter().map(|&x| type_to_wp_type(x)).collect();
+        let slots_offset = (returns.len() > 1).then(|| self.machine.get_stack_offset());
         self.control_stack.push(ControlFrame {
             br_label: self.assembler.get_label(),
             loop_like: false,
             if_else: IfElseState::None,
-            returns: self.signature.results().iter().map(|&x| type_to_wp_type(x)).collect(),
+            params: smallvec![],
+            returns,
             value_stack_depth: 0,
             fp_stack_depth: 0,
+            slots_offset,
         });
 
         Ok(())
     }
 
+    /// Emits the code returning from the function, once its result is in `RAX`.
+    fn emit_function_epilogue(&mut self) {
+        let local_count = self.local_count();
+        self.machine.finalize_locals(self.assembler);
+
+        // Restore stack height
+        self.assembler.emit_add(
+            Size::S32,
+            Location::Imm32(self.stack_size),
+            Location::Memory(
+                Machine::get_vmctx_reg(),
+                self.vmoffsets.vmctx_stack_limit_begin() as i32,
+            ),
+        );
+
+        self.machine.restore_registers(self.assembler, self.calling_convention, local_count);
+        self.assembler.emit_mov(Size::S64, Location::GPR(GPR::RBP), Location::GPR(GPR::RSP));
+        self.assembler.emit_pop(Size::S64, Location::GPR(GPR::RBP));
+
+        // Make a copy of the return value in XMM0, as required by the SysV CC.
+        match self.signature.results() {
+            [x] if *x == Type::F32 || *x == Type::F64 => {
+                self.assembler.emit_mov(
+                    Size::S64,
+                    Location::GPR(GPR::RAX),
+                    Location::XMM(XMM::XMM0),
+                );
+            }
+            _ => {}
+        }
+        self.assembler.emit_ret();
+    }
+
     /// Pushes the instruction to the address map, calculating the offset from a
     /// provided beginning address.
     fn mark_instruction_address_end(&mut self, begin: usize) {
@@ -4711,6 +5004,8 @@ impl<'a> FuncGen<'a> {
                     }
                 }
 
+                let ret_area = self.reserve_ret_area(&params, return_types.len());
+
                 let table_base = self.machine.acquire_temp_gpr().unwrap();
                 let table_count = self.machine.acquire_temp_gpr().unwrap();
                 let sigidx = self.machine.acquire_temp_gpr().unwrap();
@@ -4818,6 +5113,9 @@ impl<'a> FuncGen<'a> {
 
                 self.emit_call_native(
                     |this| {
+                        if let Some((slots_offset, _)) = ret_area {
+                            this.emit_ret_area_pointer(slots_offset, param_types.len());
+                        }
                         if this.assembler.arch_requires_indirect_call_trampoline() {
                             this.assembler.arch_emit_indirect_call_with_trampoline(
                                 Location::Memory(
@@ -4839,12 +5137,17 @@ impl<'a> FuncGen<'a> {
                             ));
                         }
                     },
-                    params.iter().copied(),
+                    params.iter().copied().chain(ret_area.map(|_| Location::Imm32(0))),
                 )?;
 
+                if let Some((_, extra)) = ret_area.filter(|&(_, extra)| extra != 0) {
+                    self.machine.increase_rsp(self.assembler, extra);
+                }
                 self.machine.release_locations_only_stack(self.assembler, &params);
 
-                if !return_types.is_empty() {
+                if ret_area.is_some() {
+                    self.push_slots(&return_types);
+                } else if !return_types.is_empty() {
                     let ret =
                         self.machine.acquire_locations(self.assembler, &[return_types[0]], false)
                             [0];
@@ -4861,27 +5164,68 @@ impl<'a> FuncGen<'a> {
                 let label_end = self.assembler.get_label();
                 let label_else = self.assembler.get_label();
 
-                let cond = self.pop_value_released();
+                let (params, returns) = self.block_signature(blockty);
+                let mut cond = self.pop_value_released();
+
+                // Both branches start with the parameters, so they're moved to slots that won't
+                // be touched by the code of the `then` branch.
+                let mut cond_tmp = None;
+                let slots_offset = if !params.is_empty() || returns.len() > 1 {
+                    // Moving the parameters around may overwrite the condition.
+                    let tmp = self.machine.acquire_temp_gpr().unwrap();
+                    self.assembler.emit_mov(Size::S32, cond, Location::GPR(tmp));
+                    cond = Location::GPR(tmp);
+                    cond_tmp = Some(tmp);
+                    Some(self.emit_move_to_slots(&params))
+                } else {
+                    None
+                };
+                let value_stack_depth = self.value_stack.len() - params.len();
 
                 let frame = ControlFrame {
                     br_label: label_end,
                     loop_like: false,
                     if_else: IfElseState::If(label_else),
-                    returns: match blockty {
-                        WpBlockType::Empty => smallvec![],
-                        WpBlockType::Type(inner_ty) => smallvec![inner_ty],
-                        WpBlockType::FuncType(_) => {
-                            return Err(CodegenError {
-                                message: "If: multi-value returns not yet implemented".to_string(),
-                            })
-                        }
-                    },
-                    value_stack_depth: self.value_stack.len(),
-                    fp_stack_depth: self.fp_stack.len(),
+                    params,
+                    returns,
+                    value_stack_depth,
+                    fp_stack_depth: self.fp_stack_depth_at(value_stack_depth),
+                    slots_offset,
                 };
                 self.control_stack.push(frame);
                 self.emit_relaxed_binop(Assembler::emit_cmp, Size::S32, Location::Imm32(0), cond);
                 self.assembler.emit_jmp(Condition::Equal, label_else);
+                if let Some(tmp) = cond_tmp {
+                    self.machine.release_temp_gpr(tmp);
+                }
+            }
+            Operator::Else if self.frame_has_slots(0) => {
+                let frame = self.control_stack.last().unwrap();
+                let label_else = match frame.if_else {
+                    IfElseState::If(label) => label,
+                    _ => {
+                        return Err(CodegenError {
+                            message: "Else: frame.if_else unreachable code".to_string(),
+                        })
+                    }
+                };
+                let params = frame.params.clone();
+                let value_stack_depth = frame.value_stack_depth;
+                let fp_stack_depth = frame.fp_stack_depth;
+
+                if !was_unreachable {
+                    self.emit_br_to_slots(self.control_stack.len() - 1);
+                }
+
+                // Restore the state the `If` left the parameters in.
+                let released = &self.value_stack[value_stack_depth..];
+                self.machine.release_locations(self.assembler, released);
+                self.value_stack.truncate(value_stack_depth);
+                self.fp_stack.truncate(fp_stack_depth);
+                self.push_slots(&params);
+                self.assembler.emit_label(label_else);
+
+                self.control_stack.last_mut().unwrap().if_else = IfElseState::Else;
             }
             Operator::Else => {
                 let frame = self.control_stack.last_mut().unwrap();
@@ -5000,26 +5344,29 @@ impl<'a> FuncGen<'a> {
                 self.assembler.emit_label(end_label);
             }
             Operator::Block { blockty } => {
+                let (params, returns) = self.block_signature(blockty);
+                let value_stack_depth = self.value_stack.len() - params.len();
+                let slots_offset = (!params.is_empty() || returns.len() > 1)
+                    .then(|| self.stack_offset_at_depth(value_stack_depth));
                 let frame = ControlFrame {
                     br_label: self.assembler.get_label(),
                     loop_like: false,
                     if_else: IfElseState::None,
-                    returns: match blockty {
-                        WpBlockType::Empty => smallvec![],
-                        WpBlockType::Type(inner_ty) => smallvec![inner_ty],
-                        WpBlockType::FuncType(_) => {
-                            return Err(CodegenError {
-                                message: "Block: multi-value returns not yet implemented"
-                                    .to_string(),
-                            })
-                        }
-                    },
-                    value_stack_depth: self.value_stack.len(),
-                    fp_stack_depth: self.fp_stack.len(),
+                    params,
+                    returns,
+                    value_stack_depth,
+                    fp_stack_depth: self.fp_stack_depth_at(value_stack_depth),
+                    slots_offset,
                 };
                 self.control_stack.push(frame);
             }
             Operator::Loop { blockty } => {
+                // Branches back to the loop deliver the parameters to fixed slots.
+                let (params, returns) = self.block_signature(blockty);
+                let slots_offset = (!params.is_empty() || returns.len() > 1)
+                    .then(|| self.emit_move_to_slots(&params));
+                let value_stack_depth = self.value_stack.len() - params.len();
+
                 // Pad with NOPs to the next 16-byte boundary.
                 // Here we don't use the dynasm `.align 16` attribute because it pads the alignment with single-byte nops
                 // which may lead to efficiency problems.
@@ -5038,18 +5385,11 @@ impl<'a> FuncGen<'a> {
                     br_label,
                     loop_like: true,
                     if_else: IfElseState::None,
-                    returns: match blockty {
-                        WpBlockType::Empty => smallvec![],
-                        WpBlockType::Type(inner_ty) => smallvec![inner_ty],
-                        WpBlockType::FuncType(_) => {
-                            return Err(CodegenError {
-                                message: "Loop: multi-value returns not yet implemented"
-                                    .to_string(),
-                            })
-                        }
-                    },
-                    value_stack_depth: self.value_stack.len(),
-                    fp_stack_depth: self.fp_stack.len(),
+                    params,
+                    returns,
+                    value_stack_depth,
+                    fp_stack_depth: self.fp_stack_depth_at(value_stack_depth),
+                    slots_offset,
                 });
                 self.assembler.emit_label(br_label);
 
@@ -5088,6 +5428,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src = self.value_stack.pop().unwrap();
                 let dst = self.value_stack.pop().unwrap();
+                self.emit_bulk_memory_gas(len, 3);
                 self.machine.release_locations_only_regs(&[len, src, dst]);
 
                 self.assembler.emit_mov(
@@ -5138,6 +5479,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src_pos = self.value_stack.pop().unwrap();
                 let dst_pos = self.value_stack.pop().unwrap();
+                self.emit_bulk_memory_gas(len, 3);
                 self.machine.release_locations_only_regs(&[len, src_pos, dst_pos]);
 
                 let memory_index = MemoryIndex::new(src_mem as usize);
@@ -5172,6 +5514,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let val = self.value_stack.pop().unwrap();
                 let dst = self.value_stack.pop().unwrap();
+                self.emit_bulk_memory_gas(len, 3);
                 self.machine.release_locations_only_regs(&[len, val, dst]);
 
                 let memory_index = MemoryIndex::new(mem as usize);
@@ -5638,6 +5981,10 @@ impl<'a> FuncGen<'a> {
                 self.mark_instruction_address_end(offset);
                 self.unreachable_depth = 1;
             }
+            Operator::Return if self.control_stack[0].slots_offset.is_some() => {
+                self.emit_br_to_slots(0);
+                self.unreachable_depth = 1;
+            }
             Operator::Return => {
                 let frame = &self.control_stack[0];
                 if !frame.returns.is_empty() {
@@ -5686,6 +6033,10 @@ impl<'a> FuncGen<'a> {
                 self.assembler.emit_jmp(Condition::None, frame.br_label);
                 self.unreachable_depth = 1;
             }
+            Operator::Br { relative_depth } if self.frame_has_slots(relative_depth) => {
+                self.emit_br_to_slots(self.control_stack.len() - 1 - (relative_depth as usize));
+                self.unreachable_depth = 1;
+            }
             Operator::Br { relative_depth } => {
                 let frame =
                     &self.control_stack[self.control_stack.len() - 1 - (relative_depth as usize)];
@@ -5733,6 +6084,14 @@ impl<'a> FuncGen<'a> {
                 self.assembler.emit_jmp(Condition::None, frame.br_label);
                 self.unreachable_depth = 1;
             }
+            Operator::BrIf { relative_depth } if self.frame_has_slots(relative_depth) => {
+                let after = self.assembler.get_label();
+                let cond = self.pop_value_released();
+                self.emit_relaxed_binop(Assembler::emit_cmp, Size::S32, Location::Imm32(0), cond);
+                self.assembler.emit_jmp(Condition::Equal, after);
+                self.emit_br_to_slots(self.control_stack.len() - 1 - (relative_depth as usize));
+                self.assembler.emit_label(after);
+            }
             Operator::BrIf { relative_depth } => {
                 let after = self.assembler.get_label();
                 let cond = self.pop_value_released();
@@ -5818,6 +6177,10 @@ impl<'a> FuncGen<'a> {
                     let label = self.assembler.get_label();
                     self.assembler.emit_label(label);
                     table.push(label);
+                    if self.frame_has_slots(*target) {
+                        self.emit_br_to_slots(self.control_stack.len() - 1 - (*target as usize));
+                        continue;
+                    }
                     let frame =
                         &self.control_stack[self.control_stack.len() - 1 - (*target as usize)];
                     if !frame.loop_like && !frame.returns.is_empty() {
@@ -5867,7 +6230,9 @@ impl<'a> FuncGen<'a> {
                 }
                 self.assembler.emit_label(default_br);
 
-                {
+                if self.frame_has_slots(default_target) {
+                    self.emit_br_to_slots(self.control_stack.len() - 1 - (default_target as usize));
+                } else {
                     let frame = &self.control_stack
                         [self.control_stack.len() - 1 - (default_target as usize)];
                     if !frame.loop_like && !frame.returns.is_empty() {
@@ -5927,6 +6292,47 @@ impl<'a> FuncGen<'a> {
                     }
                 }
             }
+            Operator::End if self.frame_has_slots(0) => {
+                let frame = self.control_stack.pop().unwrap();
+                let slots_offset = frame.slots_offset.unwrap();
+                if !was_unreachable {
+                    self.emit_copy_to_slots(&frame.returns, slots_offset);
+                }
+
+                if self.control_stack.is_empty() {
+                    self.assembler.emit_label(frame.br_label);
+                    // Write the results out to the caller's return area.
+                    let ret_area = self.machine.get_ret_area_location().unwrap();
+                    self.assembler.emit_mov(Size::S64, ret_area, Location::GPR(GPR::RAX));
+                    for i in 0..frame.returns.len() {
+                        self.assembler.emit_mov(
+                            Size::S64,
+                            Location::Memory(GPR::RBP, -((slots_offset + (i + 1) * 8) as i32)),
+                            Location::GPR(GPR::RCX),
+                        );
+                        self.assembler.emit_mov(
+                            Size::S64,
+                            Location::GPR(GPR::RCX),
+                            Location::Memory(GPR::RAX, -((i * 8) as i32)),
+                        );
+                    }
+                    self.emit_function_epilogue();
+                } else {
+                    let released = &self.value_stack[frame.value_stack_depth..];
+                    self.machine.release_locations(self.assembler, released);
+                    self.value_stack.truncate(frame.value_stack_depth);
+                    self.fp_stack.truncate(frame.fp_stack_depth);
+                    self.push_slots(&frame.returns);
+
+                    if !frame.loop_like {
+                        self.assembler.emit_label(frame.br_label);
+                    }
+
+                    if let IfElseState::If(label) = frame.if_else {
+                        self.assembler.emit_label(label);
+                    }
+                }
+            }
             Operator::End => {
                 let frame = self.control_stack.pop().unwrap();
 
@@ -5967,43 +6373,7 @@ impl<'a> FuncGen<'a> {
 
                 if self.control_stack.is_empty() {
                     self.assembler.emit_label(frame.br_label);
-                    let local_count = self.local_count();
-                    self.machine.finalize_locals(self.assembler);
-
-                    // Restore stack height
-                    self.assembler.emit_add(
-                        Size::S32,
-                        Location::Imm32(self.stack_size),
-                        Location::Memory(
-                            Machine::get_vmctx_reg(),
-                            self.vmoffsets.vmctx_stack_limit_begin() as i32,
-                        ),
-                    );
-
-                    self.machine.restore_registers(
-                        self.assembler,
-                        self.calling_convention,
-                        local_count,
-                    );
-                    self.assembler.emit_mov(
-                        Size::S64,
-                        Location::GPR(GPR::RBP),
-                        Location::GPR(GPR::RSP),
-                    );
-                    self.assembler.emit_pop(Size::S64, Location::GPR(GPR::RBP));
-
-                    // Make a copy of the return value in XMM0, as required by the SysV CC.
-                    match self.signature.results() {
-                        [x] if *x == Type::F32 || *x == Type::F64 => {
-                            self.assembler.emit_mov(
-                                Size::S64,
-                                Location::GPR(GPR::RAX),
-                                Location::XMM(XMM::XMM0),
-                            );
-                        }
-                        _ => {}
-                    }
-                    self.assembler.emit_ret();
+                    self.emit_function_epilogue();
                 } else {
                     let released = &self.value_stack[frame.value_stack_depth..];
                     self.machine.release_locations(self.assembler, released);
@@ -7534,6 +7904,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src = self.value_stack.pop().unwrap();
                 let dest = self.value_stack.pop().unwrap();
+                self.emit_bulk_memory_gas(len, 0);
                 self.machine.release_locations_only_regs(&[len, src, dest]);
 
                 self.assembler.emit_mov(
@@ -7593,6 +7964,7 @@ impl<'a> FuncGen<'a> {
                 let len = self.value_stack.pop().unwrap();
                 let src = self.value_stack.pop().unwrap();
                 let dest = self.value_stack.pop().unwrap();
+                self.emit_bulk_memory_gas(len, 0);
                 self.machine.release_locations_only_regs(&[len, src, dest]);
 
                 self.assembler.emit_mov(
@@ -7766,9 +8138,14 @@ pub(crate) fn gen_std_trampoline(
     calling_convention: CallingConvention,
     a: &mut Assembler,
 ) -> FunctionBody {
+    // Functions returning more than one value take a pointer to a return area as an extra
+    // trailing argument.
+    let multi_value = sig.results().len() > 1;
+    let n_args = sig.params().len() + multi_value as usize;
+
     // Calculate stack offset.
     let mut stack_offset: u32 = 0;
-    for (i, _param) in sig.params().iter().enumerate() {
+    for i in 0..n_args {
         if let Location::Memory(_, _) = Machine::get_param_location(1 + i, calling_convention) {
             stack_offset += 8;
         }
@@ -7783,6 +8160,12 @@ pub(crate) fn gen_std_trampoline(
         stack_offset += 8;
     }
 
+    // The return area lives right above the stack arguments.
+    let ret_area_size: u32 =
+        if multi_value { ((sig.results().len() as u32 * 8) + 15) & !15 } else { 0 };
+    let ret_area_ptr = (stack_padding + stack_offset + ret_area_size) as i32 - 8;
+    let stack_offset = stack_offset + ret_area_size;
+
     // Used callee-saved registers
     a.emit_push(Size::S64, Location::GPR(GPR::R15));
     a.emit_push(Size::S64, Location::GPR(GPR::R14));
@@ -7831,16 +8214,50 @@ pub(crate) fn gen_std_trampoline(
                 _ => unreachable!(),
             }
         }
+        if multi_value {
+            let ptr = Location::Memory(GPR::RSP, ret_area_ptr);
+            match Machine::get_param_location(n_args, calling_convention) {
+                dst_loc @ Location::GPR(_) => a.emit_lea(Size::S64, ptr, dst_loc),
+                Location::Memory(_, _) => {
+                    a.emit_lea(Size::S64, ptr, Location::GPR(GPR::RAX));
+                    a.emit_mov(
+                        Size::S64,
+                        Location::GPR(GPR::RAX),
+                        Location::Memory(
+                            GPR::RSP,
+                            (stack_padding as usize + n_stack_args * 8) as _,
+                        ),
+                    );
+                }
+                _ => unreachable!(),
+            }
+        }
     }
 
     // Call.
     a.emit_call_location(Location::GPR(GPR::R15));
 
+    // Write return values.
+    if multi_value {
+        for i in 0..sig.results().len() {
+            a.emit_mov(
+                Size::S64,
+                Location::Memory(GPR::RSP, ret_area_ptr - (i * 8) as i32),
+                Location::GPR(GPR::RAX),
+            );
+            a.emit_mov(
+                Size::S64,
+                Location::GPR(GPR::RAX),
+                Location::Memory(GPR::R14, (i * 16) as _),
+            );
+        }
+    }
+
     // Restore stack.
     a.emit_add(Size::S64, Location::Imm32(stack_offset + stack_padding), Location::GPR(GPR::RSP));
 
     // Write return value.
-    if !sig.results().is_empty() {
+    if sig.results().len() == 1 {
         a.emit_mov(Size::S64, Location::GPR(GPR::RAX), Location::Memory(GPR::R14, 0));
     }
 
@@ -61,9 +61,6 @@ impl Compiler for SinglepassCompiler {
         if !target.cpu_features().contains(CpuFeature::AVX) {
             return Err(CompileError::UnsupportedTarget("x86_64 without AVX".to_string()));
         }
-        if compile_info.features.multi_value {
-            return Err(CompileError::UnsupportedFeature("multivalue".to_string()));
-        }
         let calling_convention = match target.triple().default_calling_convention() {
             Ok(CallingConvention::WindowsFastcall) => CallingConvention::WindowsFastcall,
             Ok(CallingConvention::SystemV) => CallingConvention::SystemV,
@@ -73,6 +70,11 @@ impl Compiler for SinglepassCompiler {
 
         let table_styles = &compile_info.table_styles;
         let module = &compile_info.module;
+        // Host functions are called with the native calling convention, which has no way to
+        // return more than one value.
+        if module.imported_function_types().any(|ty| ty.results().len() > 1) {
+            return Err(CompileError::UnsupportedFeature("multi-value imports".to_string()));
+        }
         let pointer_width = target
             .triple()
             .pointer_width()
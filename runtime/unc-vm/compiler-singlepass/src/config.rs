@@ -25,6 +25,8 @@ pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
     pub(crate) enable_stack_check: bool,
     pub(crate) disable_9393_fix: bool,
+    /// Gas charged for every 8 bytes (or table element) processed by a bulk memory operation.
+    pub(crate) bulk_memory_word_cost: u64,
     /// Compiler intrinsics.
     pub(crate) intrinsics: Vec<Intrinsic>,
 }
@@ -37,6 +39,7 @@ impl Singlepass {
             enable_nan_canonicalization: true,
             enable_stack_check: false,
             disable_9393_fix: false,
+            bulk_memory_word_cost: 0,
             intrinsics: vec![Intrinsic {
                 kind: IntrinsicKind::Gas,
                 name: "gas".to_string(),
@@ -65,6 +68,15 @@ impl Singlepass {
         self.disable_9393_fix = !enable;
     }
 
+    /// Set the gas charged for every 8 bytes of memory (or every table element) processed by
+    /// the bulk memory operations, on top of the cost of the instruction itself.
+    ///
+    /// Zero, the default, disables the length-dependent charge.
+    pub fn bulk_memory_word_cost(&mut self, cost: u64) -> &mut Self {
+        self.bulk_memory_word_cost = cost;
+        self
+    }
+
     pub fn canonicalize_nans(&mut self, enable: bool) -> &mut Self {
         self.enable_nan_canonicalization = enable;
         self
@@ -84,9 +96,7 @@ impl CompilerConfig for Singlepass {
 
     /// Gets the default features for this compiler in the given target
     fn default_features_for_target(&self, _target: &Target) -> Features {
-        let mut features = Features::default();
-        features.multi_value(false);
-        features
+        Features::default()
     }
 }
 
@@ -15,6 +15,10 @@ pub(crate) struct Machine {
     used_xmms: u32, // Bitset for the used XMMs, 1 means used
     stack_offset: MachineStackOffset,
     save_area_offset: Option<MachineStackOffset>,
+    /// Memory location holding the pointer to the caller's return area.
+    ///
+    /// Populated in `setup_registers` for functions returning more than one value.
+    ret_area_offset: Option<MachineStackOffset>,
     /// Memory location at which local variables begin.
     ///
     /// Populated in `init_locals`.
@@ -42,6 +46,7 @@ impl Machine {
             used_xmms: 0,
             stack_offset: MachineStackOffset(0),
             save_area_offset: None,
+            ret_area_offset: None,
             locals_offset: MachineStackOffset(0),
         }
     }
@@ -184,12 +189,12 @@ impl Machine {
         self.set_xmm_unused(xmm);
     }
 
-    fn increase_rsp(&mut self, a: &mut impl Emitter, sz: usize) {
+    pub(crate) fn increase_rsp(&mut self, a: &mut impl Emitter, sz: usize) {
         a.emit_add(Size::S64, Location::Imm32(u32::try_from(sz).unwrap()), Location::GPR(GPR::RSP));
         self.stack_offset.0 -= sz;
     }
 
-    fn decrease_rsp(&mut self, a: &mut impl Emitter, sz: usize) {
+    pub(crate) fn decrease_rsp(&mut self, a: &mut impl Emitter, sz: usize) {
         a.emit_sub(Size::S64, Location::Imm32(u32::try_from(sz).unwrap()), Location::GPR(GPR::RSP));
         self.stack_offset.0 += sz;
     }
@@ -240,6 +245,24 @@ impl Machine {
         ret
     }
 
+    /// Acquires `n` consecutive stack slots, never handing out registers.
+    ///
+    /// The returned locations need to be released with `release_location` like any other stack
+    /// value.
+    pub(crate) fn acquire_stack_locations<E: Emitter>(
+        &mut self,
+        assembler: &mut E,
+        n: usize,
+    ) -> SmallVec<[Location; 1]> {
+        let ret = (1..=n)
+            .map(|i| Location::Memory(GPR::RBP, -((self.stack_offset.0 + i * 8) as i32)))
+            .collect();
+        if n != 0 {
+            self.decrease_rsp(assembler, n * 8);
+        }
+        ret
+    }
+
     /// Releases locations used for stack value.
     pub(crate) fn release_locations<E: Emitter>(&mut self, assembler: &mut E, locs: &[Location]) {
         let mut delta_stack_offset: usize = 0;
@@ -376,15 +399,20 @@ impl Machine {
     // |     Saved R15     |   8B   |
     // |  (Win FastC) RDI  |   8B   |
     // |  (Win FastC) RSI  |   8B   |  <-  save_area_offset
+    // | (Multi) Ret Area  |   8B   |  <-  ret_area_offset
     // |      Local 0      |   8B   |  <-  locals_offset
     // |        ...        |        |
     // |      Local n      |   8B   |  <-  RSP, stack_offset (at end of init_locals, will keep moving during fn codegen)
     // +-------------------+--------+
+    //
+    // Functions returning more than one value receive a pointer to the area the results should be
+    // written to as their last argument, which is saved to the "ret area" slot.
     pub(crate) fn setup_registers<E: Emitter>(
         &mut self,
         a: &mut E,
         n: u32,
         n_params: u32,
+        ret_area: bool,
         calling_convention: CallingConvention,
     ) {
         // Total size (in bytes) of the pre-allocated "static area" for this function's
@@ -427,6 +455,19 @@ impl Machine {
         // Save the offset of register save area.
         self.save_area_offset = Some(MachineStackOffset(self.stack_offset.0));
 
+        // Save the pointer to the return area, passed in after all the parameters.
+        if ret_area {
+            self.decrease_rsp(a, 8);
+            self.ret_area_offset = Some(MachineStackOffset(self.stack_offset.0));
+            let loc = Self::get_param_location((n_params + 1) as usize, calling_convention);
+            if let Location::Memory(_, _) = loc {
+                a.emit_mov(Size::S64, loc, Location::GPR(GPR::RAX));
+                a.emit_mov(Size::S64, Location::GPR(GPR::RAX), Location::Memory(GPR::RSP, 0));
+            } else {
+                a.emit_mov(Size::S64, loc, Location::Memory(GPR::RSP, 0));
+            }
+        }
+
         // Load in-register parameters into the allocated locations for register parameters.
         // Locals are allocated on the stack from higher address to lower address,
         // so we won't skip the stack guard page here.
@@ -517,6 +558,11 @@ impl Machine {
         }
     }
 
+    /// Location of the pointer to the return area of a function returning more than one value.
+    pub(crate) fn get_ret_area_location(&self) -> Option<Location> {
+        self.ret_area_offset.map(|o| Location::Memory(GPR::RBP, -(o.0 as i32)))
+    }
+
     pub(crate) fn finalize_locals<E: Emitter>(&mut self, a: &mut E) {
         // Unwind stack to the "save area".
         a.emit_lea(
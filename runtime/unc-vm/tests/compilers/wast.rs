@@ -28,9 +28,6 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
     if is_simd {
         features.simd(true);
     }
-    if config.compiler == crate::Compiler::Singlepass {
-        features.multi_value(false);
-    }
     config.set_features(features);
     config.set_nan_canonicalization(try_nan_canonicalization);
 
@@ -53,13 +50,6 @@ pub fn run_wast(mut config: crate::Config, wast_path: &str) -> anyhow::Result<()
             "Validation error: Invalid var_u32",
         ]);
     }
-    if config.compiler == crate::Compiler::Singlepass {
-        // We don't support multivalue yet in singlepass
-        wast.allow_instantiation_failures(&[
-            "Validation error: func type returns multiple values but the multi-value feature is not enabled",
-            "Validation error: blocks, loops, and ifs may only produce a resulttype when multi-value is not enabled",
-        ]);
-    }
     wast.fail_fast = false;
     let path = Path::new(wast_path);
     wast.run_file(path)
@@ -0,0 +1,81 @@
+;; Multi-value blocks, branches and calls in singlepass pass values through stack slots.
+(module
+  (type $pair (func (param i32 i32) (result i32 i32)))
+  (table 1 funcref)
+  (elem (i32.const 0) $swap)
+
+  (func $swap (type $pair) (local.get 1) (local.get 0))
+
+  ;; Enough parameters to spill the return area pointer to the stack.
+  (func $many-params
+    (param i64 i64 i64 i64 i64 i64 i64 i64) (result i64 i64 i64)
+    (i64.add (local.get 0) (local.get 7))
+    (i64.add (local.get 1) (local.get 6))
+    (i64.sub (local.get 2) (local.get 5)))
+
+  (func (export "swap") (param i32 i32) (result i32 i32)
+    (call $swap (local.get 0) (local.get 1)))
+
+  (func (export "swap-indirect") (param i32 i32) (result i32 i32)
+    (call_indirect (type $pair) (local.get 0) (local.get 1) (i32.const 0)))
+
+  (func (export "many-params") (result i64 i64 i64)
+    (call $many-params
+      (i64.const 1) (i64.const 2) (i64.const 3) (i64.const 4)
+      (i64.const 5) (i64.const 6) (i64.const 7) (i64.const 8)))
+
+  (func (export "sum-many-params") (result i64)
+    (call $many-params
+      (i64.const 1) (i64.const 2) (i64.const 3) (i64.const 4)
+      (i64.const 5) (i64.const 6) (i64.const 7) (i64.const 8))
+    (i64.add) (i64.add))
+
+  ;; Block parameters stay in place below values pushed inside the block.
+  (func (export "block-params") (param i32) (result i32 i32)
+    (i32.const 10) (local.get 0)
+    (block (param i32 i32) (result i32 i32)
+      (i32.const 1) (drop)
+      (i32.add (i32.const 1)))
+    (i32.const 5) (drop))
+
+  ;; Loop parameters are passed again on every back edge.
+  (func (export "loop-params") (param i32) (result i32 i32)
+    (i32.const 0) (local.get 0)
+    (loop $l (param i32 i32) (result i32 i32)
+      (local.set 0)
+      (i32.add (i32.const 1))
+      (i32.sub (local.get 0) (i32.const 1))
+      (local.tee 0)
+      (br_if $l (local.get 0))))
+
+  (func (export "if-params") (param i32) (result f32 i64)
+    (f32.const 1.5) (i64.const 7)
+    (if (param f32 i64) (result f32 i64) (local.get 0)
+      (then (i64.add (i64.const 1)))
+      (else (drop) (f32.neg) (i64.const -1))))
+
+  (func (export "br-table") (param i32) (result i32 i32)
+    (block $outer (result i32 i32)
+      (block $inner (result i32 i32)
+        (i32.const 100) (i32.const 200) (local.get 0)
+        (br_table $inner $outer))
+      (i32.add (i32.const 1))))
+
+  (func (export "return-early") (param i32) (result i64 i32)
+    (if (local.get 0)
+      (then (return (i64.const 1) (i32.const 2))))
+    (i64.const 3) (i32.const 4))
+)
+
+(assert_return (invoke "swap" (i32.const 1) (i32.const 2)) (i32.const 2) (i32.const 1))
+(assert_return (invoke "swap-indirect" (i32.const 3) (i32.const 4)) (i32.const 4) (i32.const 3))
+(assert_return (invoke "many-params") (i64.const 9) (i64.const 9) (i64.const -3))
+(assert_return (invoke "sum-many-params") (i64.const 15))
+(assert_return (invoke "block-params" (i32.const 4)) (i32.const 10) (i32.const 5))
+(assert_return (invoke "loop-params" (i32.const 3)) (i32.const 3) (i32.const 0))
+(assert_return (invoke "if-params" (i32.const 1)) (f32.const 1.5) (i64.const 8))
+(assert_return (invoke "if-params" (i32.const 0)) (f32.const -1.5) (i64.const -1))
+(assert_return (invoke "br-table" (i32.const 0)) (i32.const 100) (i32.const 201))
+(assert_return (invoke "br-table" (i32.const 1)) (i32.const 100) (i32.const 200))
+(assert_return (invoke "return-early" (i32.const 1)) (i64.const 1) (i32.const 2))
+(assert_return (invoke "return-early" (i32.const 0)) (i64.const 3) (i32.const 4))
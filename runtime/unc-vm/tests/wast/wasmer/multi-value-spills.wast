@@ -0,0 +1,98 @@
+;; Multi-value results that do not fit in registers, mixed value types and branches carrying
+;; several values out of nested control frames in singlepass.
+(module
+  (type $many (func (param i64) (result i64 f64 i32 i64 f32 i64 i32 f64 i64 i32)))
+  (table 1 funcref)
+  (elem (i32.const 0) $many)
+
+  (func $many (type $many)
+    (local.get 0)
+    (f64.convert_i64_s (i64.add (local.get 0) (i64.const 1)))
+    (i32.wrap_i64 (i64.add (local.get 0) (i64.const 2)))
+    (i64.add (local.get 0) (i64.const 3))
+    (f32.convert_i64_s (i64.add (local.get 0) (i64.const 4)))
+    (i64.add (local.get 0) (i64.const 5))
+    (i32.wrap_i64 (i64.add (local.get 0) (i64.const 6)))
+    (f64.convert_i64_s (i64.add (local.get 0) (i64.const 7)))
+    (i64.add (local.get 0) (i64.const 8))
+    (i32.wrap_i64 (i64.add (local.get 0) (i64.const 9))))
+
+  (func (export "many") (param i64) (result i64 f64 i32 i64 f32 i64 i32 f64 i64 i32)
+    (call $many (local.get 0)))
+
+  (func (export "many-indirect") (param i64) (result i64 f64 i32 i64 f32 i64 i32 f64 i64 i32)
+    (call_indirect (type $many) (local.get 0) (i32.const 0)))
+
+  ;; Fold all the results, so that every one of them is read back from its slot.
+  (func (export "many-sum") (param i64) (result i64)
+    (local i64 f64 i32 i64 f32 i64 i32 f64 i64 i32)
+    (call $many (local.get 0))
+    (local.set 10) (local.set 9) (local.set 8) (local.set 7) (local.set 6)
+    (local.set 5) (local.set 4) (local.set 3) (local.set 2) (local.set 1)
+    (i64.add (local.get 1) (i64.trunc_f64_s (local.get 2)))
+    (i64.add (i64.extend_i32_u (local.get 3)))
+    (i64.add (local.get 4))
+    (i64.add (i64.trunc_f32_s (local.get 5)))
+    (i64.add (local.get 6))
+    (i64.add (i64.extend_i32_u (local.get 7)))
+    (i64.add (i64.trunc_f64_s (local.get 8)))
+    (i64.add (local.get 9))
+    (i64.add (i64.extend_i32_u (local.get 10))))
+
+  ;; Values below the block must survive the block's results being written.
+  (func (export "under-block") (result i32 i64 i32 i64)
+    (i32.const 1) (i64.const 2)
+    (block (result i32 i64)
+      (i32.const 3) (i64.const 4)))
+
+  ;; Branch out of several nested frames with multiple values.
+  (func (export "br-nested") (param i32) (result i64 i32 f64)
+    (block $out (result i64 i32 f64)
+      (block $mid (result i64 i32 f64)
+        (block $in (result i64 i32 f64)
+          (i64.const 1) (i32.const 2) (f64.const 3)
+          (br_if $out (i32.eq (local.get 0) (i32.const 0)))
+          (br_if $mid (i32.eq (local.get 0) (i32.const 1))))
+        (drop) (drop) (drop)
+        (i64.const 10) (i32.const 20) (f64.const 30))
+      (f64.add (f64.const 0.5))))
+
+  ;; The values of a branch not taken stay on the stack.
+  (func (export "br-if-fallthrough") (param i32) (result i32 i32)
+    (block (result i32 i32)
+      (i32.const 7) (i32.const 8) (local.get 0)
+      (br_if 0)
+      (i32.add (i32.const 1))))
+
+  ;; Recursion returning a pair of values.
+  (func $fib-pair (param i32) (result i64 i64)
+    (if (result i64 i64) (i32.eqz (local.get 0))
+      (then (i64.const 0) (i64.const 1))
+      (else
+        (call $fib-pair (i32.sub (local.get 0) (i32.const 1)))
+        (local.set 0 (i32.const 0))
+        (call $swap-add))))
+  (func $swap-add (param i64 i64) (result i64 i64)
+    (local.get 1) (i64.add (local.get 0) (local.get 1)))
+
+  (func (export "fib") (param i32) (result i64)
+    (call $fib-pair (local.get 0))
+    (drop))
+)
+
+(assert_return (invoke "many" (i64.const 10))
+  (i64.const 10) (f64.const 11) (i32.const 12) (i64.const 13) (f32.const 14)
+  (i64.const 15) (i32.const 16) (f64.const 17) (i64.const 18) (i32.const 19))
+(assert_return (invoke "many-indirect" (i64.const 0))
+  (i64.const 0) (f64.const 1) (i32.const 2) (i64.const 3) (f32.const 4)
+  (i64.const 5) (i32.const 6) (f64.const 7) (i64.const 8) (i32.const 9))
+(assert_return (invoke "many-sum" (i64.const 100)) (i64.const 1045))
+(assert_return (invoke "under-block") (i32.const 1) (i64.const 2) (i32.const 3) (i64.const 4))
+(assert_return (invoke "br-nested" (i32.const 0)) (i64.const 1) (i32.const 2) (f64.const 3))
+(assert_return (invoke "br-nested" (i32.const 1)) (i64.const 1) (i32.const 2) (f64.const 3.5))
+(assert_return (invoke "br-nested" (i32.const 2)) (i64.const 10) (i32.const 20) (f64.const 30.5))
+(assert_return (invoke "br-if-fallthrough" (i32.const 1)) (i32.const 7) (i32.const 8))
+(assert_return (invoke "br-if-fallthrough" (i32.const 0)) (i32.const 7) (i32.const 9))
+(assert_return (invoke "fib" (i32.const 0)) (i64.const 0))
+(assert_return (invoke "fib" (i32.const 1)) (i64.const 1))
+(assert_return (invoke "fib" (i32.const 10)) (i64.const 55))
@@ -21,12 +21,13 @@ use unc_primitives::block::{Approval, Block, BlockHeader};
 use unc_primitives::challenge::Challenge;
 use unc_primitives::chunk_validation::ChunkEndorsement;
 use unc_primitives::chunk_validation::ChunkStateWitness;
+use unc_primitives::epoch_sync::{EpochSyncFinalizationResponse, EpochSyncResponse};
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::network::{AnnounceAccount, PeerId};
 use unc_primitives::sharding::ChunkHash;
 use unc_primitives::sharding::ShardChunkHeader;
 use unc_primitives::transaction::SignedTransaction;
-use unc_primitives::types::{AccountId, EpochId, ShardId};
+use unc_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
 use unc_primitives::views::FinalExecutionOutcomeView;
 
 #[derive(Default, Debug)]
@@ -268,6 +269,14 @@ impl unc_network::client::Client for Network {
         None
     }
 
+    async fn block_headers_range_request(
+        &self,
+        _start_height: BlockHeight,
+        _end_height: BlockHeight,
+    ) -> Option<Vec<BlockHeader>> {
+        None
+    }
+
     async fn block(&self, block: Block, _peer_id: PeerId, _was_requested: bool) {
         self.blocks.get(&block.hash().clone()).map(|p| p.set(block));
     }
@@ -308,4 +317,31 @@ impl unc_network::client::Client for Network {
     async fn chunk_state_witness(&self, _witness: ChunkStateWitness) {}
 
     async fn chunk_endorsement(&self, _endorsement: ChunkEndorsement) {}
+
+    async fn epoch_sync_request(&self, _epoch_id: EpochId) -> Option<Box<EpochSyncResponse>> {
+        None
+    }
+
+    async fn epoch_sync_response(
+        &self,
+        _response: EpochSyncResponse,
+        _peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        Ok(())
+    }
+
+    async fn epoch_sync_finalization_request(
+        &self,
+        _epoch_id: EpochId,
+    ) -> Option<Box<EpochSyncFinalizationResponse>> {
+        None
+    }
+
+    async fn epoch_sync_finalization_response(
+        &self,
+        _response: EpochSyncFinalizationResponse,
+        _peer_id: PeerId,
+    ) -> Result<(), ReasonForBan> {
+        Ok(())
+    }
 }
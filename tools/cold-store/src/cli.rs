@@ -1,4 +1,5 @@
 use crate::cli::SubCommand::CheckStateRoot;
+use crate::repair::{RepairCmd, VerifyRangeCmd};
 use anyhow;
 use anyhow::Context;
 use borsh::BorshDeserialize;
@@ -53,6 +54,15 @@ enum SubCommand {
     /// You can provide maximum depth and/or maximum number of vertices to traverse for each root.
     /// Trie is traversed using DFS with randomly shuffled kids for every node.
     CheckStateRoot(CheckStateRootCmd),
+    /// Find blocks in a height range which are missing or incomplete in cold
+    /// storage and copy them from hot storage again, reporting blocks which
+    /// hot storage doesn't have anymore.  Also checks that blocks link to each
+    /// other and that state roots are present.  Requires --readwrite unless
+    /// --dry-run is given.
+    Repair(RepairCmd),
+    /// Check that blocks, chunks and transactions in cold storage for a height
+    /// range match hashes committed to in block headers.
+    VerifyRange(VerifyRangeCmd),
 }
 
 impl ColdStoreCommand {
@@ -87,6 +97,10 @@ impl ColdStoreCommand {
             }
             SubCommand::PrepareHot(cmd) => cmd.run(&storage, &home_dir, &unc_config),
             SubCommand::CheckStateRoot(cmd) => cmd.run(&storage),
+            SubCommand::Repair(cmd) => {
+                cmd.run(&storage, &unc_config, epoch_manager.as_ref(), self.readwrite)
+            }
+            SubCommand::VerifyRange(cmd) => cmd.run(&storage),
         }
     }
 
@@ -226,6 +240,7 @@ fn copy_next_block(store: &NodeStorage, config: &UncConfig, epoch_manager: &Epoc
             .get_shard_layout(&epoch_manager.get_epoch_id_from_prev_block(&cold_head_hash).unwrap())
             .unwrap(),
         &next_height,
+        &config.config.gc.archived_accounts,
     )
     .unwrap_or_else(|_| panic!("Failed to copy block at height {} to cold db", next_height));
 
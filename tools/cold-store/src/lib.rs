@@ -1,2 +1,4 @@
 pub mod cli;
+mod repair;
 pub use cli::ColdStoreCommand;
+pub use repair::{repair, RepairReport};
@@ -0,0 +1,362 @@
+use anyhow::Context;
+use unc_chain_configs::AccountPattern;
+use unc_epoch_manager::EpochManagerAdapter;
+use unc_infra::UncConfig;
+use unc_primitives::block::{Block, BlockHeader, Tip};
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::merkle::merklize;
+use unc_primitives::sharding::ShardChunk;
+use unc_primitives::transaction::SignedTransaction;
+use unc_primitives::types::BlockHeight;
+use unc_store::cold_storage::{update_cold_db, update_cold_head};
+use unc_store::{DBCol, NodeStorage, Store};
+use unc_store::{FINAL_HEAD_KEY, HEAD_KEY};
+
+#[derive(clap::Args)]
+pub(crate) struct RepairCmd {
+    /// First height to check. Defaults to genesis height.
+    #[clap(long)]
+    from_height: Option<BlockHeight>,
+    /// Last height to check. Defaults to the final head of the hot storage, so
+    /// that a cold storage which fell behind is caught up as well.
+    #[clap(long)]
+    to_height: Option<BlockHeight>,
+    /// Only report problems, don't copy anything.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl RepairCmd {
+    pub fn run(
+        self,
+        storage: &NodeStorage,
+        unc_config: &UncConfig,
+        epoch_manager: &dyn EpochManagerAdapter,
+        readwrite: bool,
+    ) -> anyhow::Result<()> {
+        if !self.dry_run && !readwrite {
+            anyhow::bail!("Repair writes to the databases, run with --readwrite or use --dry-run");
+        }
+        let archived_accounts = &unc_config.config.gc.archived_accounts;
+        if !archived_accounts.is_empty() {
+            println!("Archived accounts are configured, state roots won't be checked");
+        }
+        let from_height = self.from_height.unwrap_or(unc_config.genesis.config.genesis_height);
+        let to_height = match self.to_height {
+            Some(height) => height,
+            None => hot_final_head_height(&storage.get_hot_store())?,
+        };
+        let cold_head_height = storage
+            .get_cold_store()
+            .ok_or(anyhow::anyhow!("Cold storage is not configured"))?
+            .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+            .map(|tip| tip.height);
+        println!(
+            "Checking heights {from_height}..={to_height}, cold HEAD is at {cold_head_height:?}"
+        );
+        let report = repair(
+            storage,
+            epoch_manager,
+            from_height,
+            to_height,
+            archived_accounts,
+            self.dry_run,
+        )?;
+
+        let action = if self.dry_run { "to be copied" } else { "copied" };
+        println!(
+            "Blocks missing in cold storage, {action} from hot: {}",
+            format_heights(&report.recopied)
+        );
+        println!(
+            "Blocks missing in both cold and hot storage: {}",
+            format_heights(&report.unavailable)
+        );
+        for problem in &report.problems {
+            println!("{problem}");
+        }
+        if let Some(height) = report.new_cold_head {
+            println!("Moved cold HEAD to {height}");
+        }
+
+        if !report.unavailable.is_empty() || !report.problems.is_empty() {
+            anyhow::bail!(
+                "Repair incomplete: {} blocks are unavailable, {} problems found",
+                report.unavailable.len(),
+                report.problems.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`repair`].
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// Heights of the blocks which were missing in cold storage and have been
+    /// copied from hot storage (or would have been, in a dry run).
+    pub recopied: Vec<BlockHeight>,
+    /// Heights of the blocks which are missing in both cold and hot storage.
+    pub unavailable: Vec<BlockHeight>,
+    /// Blocks which don't link to the previous block or whose state is missing.
+    pub problems: Vec<String>,
+    /// Height cold HEAD has been moved to, if it has been moved.
+    pub new_cold_head: Option<BlockHeight>,
+}
+
+/// Finds blocks in `from_height..=to_height` which are missing in cold storage
+/// or are stored incompletely and copies them from hot storage again.
+/// Afterwards checks that every block links to the previous one and that state
+/// roots its chunks were applied to are present.  Cold HEAD is moved forward if
+/// the range extends past it and has no unrecoverable gaps.  Nothing is written
+/// if `dry_run` is set.
+pub fn repair(
+    storage: &NodeStorage,
+    epoch_manager: &dyn EpochManagerAdapter,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+    archived_accounts: &[AccountPattern],
+    dry_run: bool,
+) -> anyhow::Result<RepairReport> {
+    let hot_store = storage.get_hot_store();
+    let cold_store =
+        storage.get_cold_store().ok_or(anyhow::anyhow!("Cold storage is not configured"))?;
+    let cold_db = storage.cold_db().ok_or(anyhow::anyhow!("Cold storage is not configured"))?;
+    // With archived accounts only a part of the state is copied to cold
+    // storage, so state roots can't be checked.
+    let check_state = archived_accounts.is_empty();
+    let cold_head_height =
+        cold_store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?.map(|tip| tip.height);
+
+    let mut report = RepairReport::default();
+    let mut prev_hash = None;
+    // Highest height up to which all blocks are in cold storage, counting
+    // from the current cold HEAD.
+    let mut complete_height = None;
+    let mut has_gap = cold_head_height.map_or(false, |height| from_height > height + 1);
+    for height in from_height..=to_height {
+        let Some(block_hash) = get_block_hash(&hot_store, height)? else {
+            continue;
+        };
+        if is_missing_in_cold(&cold_store, &block_hash)? {
+            if !hot_store.exists(DBCol::Block, block_hash.as_ref())? {
+                report.unavailable.push(height);
+                has_gap = true;
+                prev_hash = Some(block_hash);
+                continue;
+            }
+            report.recopied.push(height);
+            if dry_run {
+                has_gap = true;
+                prev_hash = Some(block_hash);
+                continue;
+            }
+            let header = hot_store
+                .get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_ref())?
+                .with_context(|| format!("No header of block {block_hash} in hot storage"))?;
+            let shard_layout = epoch_manager.get_shard_layout(header.epoch_id())?;
+            update_cold_db(cold_db, &hot_store, &shard_layout, &height, archived_accounts)
+                .with_context(|| format!("Failed to copy block at height {height}"))?;
+        }
+        report.problems.extend(check_linkage(
+            &cold_store,
+            height,
+            &block_hash,
+            prev_hash,
+            check_state,
+        )?);
+        prev_hash = Some(block_hash);
+        if !has_gap {
+            complete_height = Some(height);
+        }
+    }
+
+    if let Some(height) = complete_height {
+        if !dry_run && cold_head_height.map_or(true, |head| height > head) {
+            update_cold_head(cold_db, &hot_store, &height)
+                .with_context(|| format!("Failed to update cold HEAD to {height}"))?;
+            report.new_cold_head = Some(height);
+        }
+    }
+    Ok(report)
+}
+
+#[derive(clap::Args)]
+pub(crate) struct VerifyRangeCmd {
+    /// First height to verify.
+    #[clap(long)]
+    from_height: BlockHeight,
+    /// Last height to verify.
+    #[clap(long)]
+    to_height: BlockHeight,
+}
+
+impl VerifyRangeCmd {
+    /// Checks that blocks, chunks and transactions stored in cold storage for
+    /// the range hash to the values committed to in block headers.
+    pub fn run(self, storage: &NodeStorage) -> anyhow::Result<()> {
+        let hot_store = storage.get_hot_store();
+        let cold_store =
+            storage.get_cold_store().ok_or(anyhow::anyhow!("Cold storage is not configured"))?;
+
+        let mut num_blocks = 0;
+        let mut num_chunks = 0;
+        let mut problems = vec![];
+        for height in self.from_height..=self.to_height {
+            let Some(block_hash) = get_block_hash(&hot_store, height)? else {
+                continue;
+            };
+            let Some(block) = cold_store.get_ser::<Block>(DBCol::Block, block_hash.as_ref())?
+            else {
+                problems.push(format!("#{height}: block {block_hash} is missing"));
+                continue;
+            };
+            num_blocks += 1;
+            if block.hash() != &block_hash {
+                problems.push(format!(
+                    "#{height}: block {block_hash} has header with hash {}",
+                    block.hash()
+                ));
+            }
+            if let Err(err) = block.check_validity() {
+                problems.push(format!("#{height}: block {block_hash} is invalid: {err:?}"));
+            }
+            for chunk_header in block.chunks().iter() {
+                if chunk_header.height_included() != height {
+                    continue;
+                }
+                num_chunks += 1;
+                let chunk_hash = chunk_header.chunk_hash();
+                let Some(chunk) =
+                    cold_store.get_ser::<ShardChunk>(DBCol::Chunks, chunk_hash.as_bytes())?
+                else {
+                    problems.push(format!("#{height}: chunk {chunk_hash:?} is missing"));
+                    continue;
+                };
+                if chunk.compute_header_hash() != chunk_hash {
+                    problems.push(format!("#{height}: chunk {chunk_hash:?} has wrong header"));
+                }
+                if merklize(chunk.transactions()).0 != chunk.tx_root() {
+                    problems.push(format!(
+                        "#{height}: transactions of chunk {chunk_hash:?} don't match tx root"
+                    ));
+                }
+                for transaction in chunk.transactions() {
+                    let tx_hash = transaction.get_hash();
+                    let stored = cold_store
+                        .get_ser::<SignedTransaction>(DBCol::Transactions, tx_hash.as_ref())?;
+                    if stored.as_ref() != Some(transaction) {
+                        problems.push(format!(
+                            "#{height}: transaction {tx_hash} is missing or differs"
+                        ));
+                    }
+                }
+            }
+        }
+
+        println!("Verified {num_blocks} blocks and {num_chunks} chunks");
+        for problem in &problems {
+            println!("{problem}");
+        }
+        if !problems.is_empty() {
+            anyhow::bail!("Found {} problems", problems.len());
+        }
+        Ok(())
+    }
+}
+
+fn hot_final_head_height(hot_store: &Store) -> anyhow::Result<BlockHeight> {
+    let final_head = hot_store.get_ser::<Tip>(DBCol::BlockMisc, FINAL_HEAD_KEY)?;
+    Ok(final_head.ok_or(anyhow::anyhow!("Hot FINAL_HEAD is not set"))?.height)
+}
+
+/// Returns hash of the canonical block at `height` or `None` if the height
+/// was skipped.  `BlockHeight` is never garbage collected in hot storage.
+fn get_block_hash(hot_store: &Store, height: BlockHeight) -> anyhow::Result<Option<CryptoHash>> {
+    Ok(hot_store.get_ser::<CryptoHash>(DBCol::BlockHeight, &height.to_le_bytes())?)
+}
+
+/// Checks whether the block or chunks included in it are missing in cold storage.
+fn is_missing_in_cold(cold_store: &Store, block_hash: &CryptoHash) -> anyhow::Result<bool> {
+    let Some(block) = cold_store.get_ser::<Block>(DBCol::Block, block_hash.as_ref())? else {
+        return Ok(true);
+    };
+    if !cold_store.exists(DBCol::BlockInfo, block_hash.as_ref())? {
+        return Ok(true);
+    }
+    for chunk_header in block.chunks().iter() {
+        if chunk_header.height_included() == block.header().height()
+            && !cold_store.exists(DBCol::Chunks, chunk_header.chunk_hash().as_bytes())?
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Checks that the block links to the previous canonical block and that
+/// state roots its new chunks were applied to are in cold storage.
+fn check_linkage(
+    cold_store: &Store,
+    height: BlockHeight,
+    block_hash: &CryptoHash,
+    prev_hash: Option<CryptoHash>,
+    check_state: bool,
+) -> anyhow::Result<Vec<String>> {
+    let block = cold_store
+        .get_ser::<Block>(DBCol::Block, block_hash.as_ref())?
+        .with_context(|| format!("Block {block_hash} is missing after copying"))?;
+    let mut problems = vec![];
+    if let Some(prev_hash) = prev_hash {
+        if block.header().prev_hash() != &prev_hash {
+            problems.push(format!(
+                "#{height}: block {block_hash} doesn't link to previous block {prev_hash}"
+            ));
+        }
+    }
+    if !check_state {
+        return Ok(problems);
+    }
+    for chunk_header in block.chunks().iter() {
+        let state_root = chunk_header.prev_state_root();
+        if chunk_header.height_included() != height || state_root == CryptoHash::default() {
+            continue;
+        }
+        // Cold storage strips shard_uid from State keys, so any prefix works.
+        let key = [&[0; 8][..], state_root.as_ref()].concat();
+        if !cold_store.exists(DBCol::State, &key)? {
+            problems.push(format!(
+                "#{height}: state root {state_root} of shard {} is missing",
+                chunk_header.shard_id()
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+/// Formats sorted heights as a list of ranges.
+fn format_heights(heights: &[BlockHeight]) -> String {
+    if heights.is_empty() {
+        return "none".to_string();
+    }
+    let mut ranges: Vec<(BlockHeight, BlockHeight)> = vec![];
+    for &height in heights {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == height => *end = height,
+            _ => ranges.push((height, height)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(
+            |(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{start}..={end}")
+                }
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(", ")
+}
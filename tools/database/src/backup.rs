@@ -0,0 +1,525 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use unc_store::{Mode, NodeStorage, Store, StoreConfig, StoreOpener};
+
+/// Directory inside the backup destination holding content-addressed files.
+const OBJECTS_DIR: &str = "objects";
+/// Directory inside the backup destination holding one manifest per backup.
+const MANIFESTS_DIR: &str = "backups";
+/// Directory inside the backup destination used for in-progress checkpoints.
+const TMP_DIR: &str = "tmp";
+/// Directory the segment database keeps sealed segments in by default and in
+/// its checkpoints.
+const SEGMENTS_DIR: &str = "segments";
+/// Number of attempts at checkpointing the databases of a running node.
+const CHECKPOINT_ATTEMPTS: usize = 3;
+
+#[derive(clap::Args)]
+pub(crate) struct BackupCommand {
+    /// Backup directory.  Files already present in earlier backups stored in
+    /// the same directory are not copied again.
+    #[clap(long)]
+    destination: PathBuf,
+}
+
+#[derive(clap::Args)]
+pub(crate) struct RestoreCommand {
+    /// Backup directory previously populated with `backup`.
+    #[clap(long)]
+    source: PathBuf,
+    /// Backup to restore.  Defaults to the latest one.
+    #[clap(long)]
+    backup_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+enum BackupDb {
+    Hot,
+    Cold,
+}
+
+/// Description of a single backup.  Files are referenced by the SHA-256 hash
+/// of their contents which is also their name in the objects directory.
+#[derive(Serialize, Deserialize, Debug)]
+struct BackupManifest {
+    id: u64,
+    created_at: chrono::DateTime<chrono::Utc>,
+    databases: Vec<DatabaseManifest>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DatabaseManifest {
+    db: BackupDb,
+    files: Vec<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FileEntry {
+    /// Path of the file relative to the database directory, with `/` as the
+    /// separator.
+    name: String,
+    size: u64,
+    /// Hex-encoded SHA-256 of the file contents.
+    hash: String,
+}
+
+impl BackupCommand {
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        archive: bool,
+        store_config: &StoreConfig,
+        cold_store_config: Option<&StoreConfig>,
+    ) -> anyhow::Result<()> {
+        let opener = NodeStorage::opener(home_dir, archive, store_config, cold_store_config);
+        let manifest = take_backup(&opener, &self.destination)?;
+        verify_backup(&self.destination, &manifest)?;
+        let files: usize = manifest.databases.iter().map(|db| db.files.len()).sum();
+        println!(
+            "Created backup {} with {} files in {}",
+            manifest.id,
+            files,
+            self.destination.display()
+        );
+        Ok(())
+    }
+}
+
+impl RestoreCommand {
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        store_config: &StoreConfig,
+        cold_store_config: Option<&StoreConfig>,
+    ) -> anyhow::Result<()> {
+        let mut manifests = read_manifests(&self.source)?;
+        let manifest = match self.backup_id {
+            Some(id) => manifests.into_iter().find(|manifest| manifest.id == id),
+            None => manifests.pop(),
+        }
+        .with_context(|| format!("no such backup in {}", self.source.display()))?;
+        verify_backup(&self.source, &manifest)?;
+
+        let mut targets = Vec::with_capacity(manifest.databases.len());
+        for database in &manifest.databases {
+            let config = match database.db {
+                BackupDb::Hot => store_config,
+                BackupDb::Cold => cold_store_config
+                    .context("backup contains cold storage but cold_store is not configured")?,
+            };
+            let target =
+                home_dir.join(config.path.as_deref().unwrap_or(database.db.default_path()));
+            let segments_path = config
+                .segment_store
+                .as_ref()
+                .and_then(|config| config.segments_path.as_deref())
+                .map(|path| target.join(path));
+            for dir in std::iter::once(&target).chain(&segments_path) {
+                anyhow::ensure!(
+                    !dir.exists(),
+                    "refusing to restore into existing directory {}",
+                    dir.display()
+                );
+            }
+            targets.push((database, target, segments_path));
+        }
+        for (database, target, segments_path) in targets {
+            restore_database(&self.source, database, &target, segments_path.as_deref())?;
+        }
+        println!("Restored backup {} into {}", manifest.id, home_dir.display());
+        Ok(())
+    }
+}
+
+impl BackupDb {
+    /// Database directory used when the store config doesn’t set a path,
+    /// matching what the store opener does.
+    fn default_path(self) -> &'static Path {
+        Path::new(match self {
+            BackupDb::Hot => "data",
+            BackupDb::Cold => "cold-data",
+        })
+    }
+}
+
+/// Checkpoints hot and (if configured) cold storage and copies the files not
+/// present in any earlier backup into the objects directory.
+///
+/// The node may keep running while the backup is taken, so the databases are
+/// opened read-only.  Creating a checkpoint fails if the node deletes a file
+/// being linked, e.g. when a compaction finishes, in which case the databases
+/// are opened and checkpointed again.
+///
+/// Neither RocksDB SST files nor sealed segments are ever rewritten under the
+/// same name, so such a file with the same name and size as in an earlier
+/// backup is reused without hashing it again.  All other files are always
+/// hashed.
+fn take_backup(opener: &StoreOpener, destination: &Path) -> anyhow::Result<BackupManifest> {
+    let previous = read_manifests(destination)?;
+    let id = previous.last().map_or(0, |manifest| manifest.id + 1);
+    let mut known_files = HashMap::new();
+    for manifest in &previous {
+        for database in &manifest.databases {
+            for file in database.files.iter().filter(|file| is_immutable(&file.name)) {
+                known_files.insert((database.db, file.name.clone(), file.size), file.hash.clone());
+            }
+        }
+    }
+
+    let objects_dir = destination.join(OBJECTS_DIR);
+    let checkpoint_dir = destination.join(TMP_DIR).join(format!("checkpoint-{id}"));
+    std::fs::create_dir_all(&objects_dir)?;
+    std::fs::create_dir_all(destination.join(MANIFESTS_DIR))?;
+
+    let mut attempt = 1;
+    let databases = loop {
+        match checkpoint_databases(opener, &checkpoint_dir, &objects_dir, &known_files) {
+            Ok(databases) => break databases,
+            Err(err) if attempt < CHECKPOINT_ATTEMPTS => {
+                eprintln!("Checkpoint attempt {attempt} failed, retrying: {err:#}");
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    std::fs::remove_dir_all(&checkpoint_dir)?;
+
+    let manifest = BackupManifest { id, created_at: chrono::Utc::now(), databases };
+    write_manifest(destination, &manifest)?;
+    Ok(manifest)
+}
+
+/// Whether a database file is never modified once created.
+fn is_immutable(name: &str) -> bool {
+    name.ends_with(".sst") || name.ends_with(".seg")
+}
+
+/// Opens the databases read-only, checkpoints them into a fresh directory and
+/// copies their files into the objects directory.
+fn checkpoint_databases(
+    opener: &StoreOpener,
+    checkpoint_dir: &Path,
+    objects_dir: &Path,
+    known_files: &HashMap<(BackupDb, String, u64), String>,
+) -> anyhow::Result<Vec<DatabaseManifest>> {
+    if checkpoint_dir.exists() {
+        // Left over by an interrupted backup or a failed attempt.
+        std::fs::remove_dir_all(checkpoint_dir)?;
+    }
+    std::fs::create_dir_all(checkpoint_dir)?;
+
+    let node_storage = opener.open_in_mode(Mode::ReadOnly)?;
+    let mut stores = vec![(BackupDb::Hot, node_storage.get_hot_store())];
+    if let Some(cold_store) = node_storage.get_cold_store() {
+        stores.push((BackupDb::Cold, cold_store));
+    }
+
+    let mut databases = Vec::with_capacity(stores.len());
+    for (db, store) in stores {
+        let path = checkpoint_dir.join(db.default_path());
+        databases.push(backup_database(db, &store, &path, objects_dir, known_files)?);
+    }
+    Ok(databases)
+}
+
+fn backup_database(
+    db: BackupDb,
+    store: &Store,
+    checkpoint_path: &Path,
+    objects_dir: &Path,
+    known_files: &HashMap<(BackupDb, String, u64), String>,
+) -> anyhow::Result<DatabaseManifest> {
+    store
+        .create_checkpoint(checkpoint_path)
+        .with_context(|| format!("creating checkpoint of {db:?} storage"))?;
+
+    let mut files = Vec::new();
+    for (name, path) in list_files(checkpoint_path, "")? {
+        let size = std::fs::metadata(&path)?.len();
+        let hash = match known_files.get(&(db, name.clone(), size)) {
+            Some(hash) if objects_dir.join(hash).exists() => hash.clone(),
+            _ => store_object(&path, db, &name, objects_dir)?,
+        };
+        files.push(FileEntry { name, size, hash });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(DatabaseManifest { db, files })
+}
+
+/// Lists files in the directory and its subdirectories returning their paths
+/// relative to the directory prefixed by `prefix`.
+fn list_files(dir: &Path, prefix: &str) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().into_string().map_err(|name| {
+            anyhow::anyhow!("non UTF-8 file name in checkpoint: {}", name.to_string_lossy())
+        })?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            files.extend(list_files(&entry.path(), &format!("{prefix}{name}/"))?);
+        } else {
+            anyhow::ensure!(
+                file_type.is_file(),
+                "unexpected entry in checkpoint: {}",
+                entry.path().display()
+            );
+            files.push((format!("{prefix}{name}"), entry.path()));
+        }
+    }
+    Ok(files)
+}
+
+/// Copies the file into the objects directory under the hash of its contents
+/// and returns the hash.
+///
+/// The temporary file is named after the database and the path within it, so
+/// that files with the same name in different databases never share it.
+fn store_object(
+    path: &Path,
+    db: BackupDb,
+    name: &str,
+    objects_dir: &Path,
+) -> anyhow::Result<String> {
+    let tmp_path = objects_dir.join(format!("{db:?}-{}.tmp", name.replace('/', "-")));
+    let mut reader = File::open(path)?;
+    let mut writer = File::create(&tmp_path)?;
+    let mut hasher = sha2::Sha256::new();
+    let mut buf = vec![0; 1 << 20];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+    }
+    writer.sync_all()?;
+    let hash = hex::encode(hasher.finalize());
+    let object_path = objects_dir.join(&hash);
+    if object_path.exists() {
+        std::fs::remove_file(&tmp_path)?;
+    } else {
+        std::fs::rename(&tmp_path, &object_path)?;
+    }
+    Ok(hash)
+}
+
+fn hash_file(path: &Path) -> anyhow::Result<String> {
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn write_manifest(destination: &Path, manifest: &BackupManifest) -> anyhow::Result<()> {
+    let path = destination.join(MANIFESTS_DIR).join(format!("{:08}.json", manifest.id));
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&serde_json::to_vec_pretty(manifest)?)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, &path)?;
+    sync_dir(&destination.join(MANIFESTS_DIR))
+}
+
+/// Returns manifests of all complete backups in the directory sorted by id.
+fn read_manifests(destination: &Path) -> anyhow::Result<Vec<BackupManifest>> {
+    let dir = destination.join(MANIFESTS_DIR);
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut manifests = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new("json")) {
+            continue;
+        }
+        let manifest: BackupManifest = serde_json::from_slice(&std::fs::read(&path)?)
+            .with_context(|| format!("parsing {}", path.display()))?;
+        manifests.push(manifest);
+    }
+    manifests.sort_by_key(|manifest| manifest.id);
+    Ok(manifests)
+}
+
+/// Checks that every file referenced by the manifest is present in the objects
+/// directory and has the recorded size and hash.
+fn verify_backup(destination: &Path, manifest: &BackupManifest) -> anyhow::Result<()> {
+    let objects_dir = destination.join(OBJECTS_DIR);
+    for database in &manifest.databases {
+        for file in &database.files {
+            let path = objects_dir.join(&file.hash);
+            let size = std::fs::metadata(&path)
+                .with_context(|| format!("missing object for {:?}/{}", database.db, file.name))?
+                .len();
+            anyhow::ensure!(
+                size == file.size,
+                "size mismatch for {:?}/{}: expected {}, got {}",
+                database.db,
+                file.name,
+                file.size,
+                size
+            );
+            let hash = hash_file(&path)?;
+            anyhow::ensure!(
+                hash == file.hash,
+                "hash mismatch for {:?}/{}: expected {}, got {}",
+                database.db,
+                file.name,
+                file.hash,
+                hash
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Copies files of the database into the target directory.  Sealed segments
+/// go to `segments_path` instead if the cold store config sets one.
+///
+/// Every file and directory is synced, so that a node started right after
+/// restoring doesn’t find the database truncated after a crash.
+fn restore_database(
+    source: &Path,
+    database: &DatabaseManifest,
+    target: &Path,
+    segments_path: Option<&Path>,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(target)?;
+    let objects_dir = source.join(OBJECTS_DIR);
+    let mut dirs = BTreeSet::from([target.to_path_buf()]);
+    for file in &database.files {
+        let segment = file.name.strip_prefix(SEGMENTS_DIR).and_then(|name| name.strip_prefix('/'));
+        let path = match (segment, segments_path) {
+            (Some(name), Some(segments_path)) => segments_path.join(name),
+            _ => target.join(&file.name),
+        };
+        let dir = path.parent().context("restored file has no parent directory")?;
+        std::fs::create_dir_all(dir)?;
+        let mut writer = File::create(&path)?;
+        std::io::copy(&mut File::open(objects_dir.join(&file.hash))?, &mut writer)?;
+        writer.sync_all()?;
+        dirs.insert(dir.to_path_buf());
+    }
+    if let Some(parent) = target.parent() {
+        dirs.insert(parent.to_path_buf());
+    }
+    for dir in dirs {
+        sync_dir(&dir)?;
+    }
+    Ok(())
+}
+
+/// Makes creation and renames of the directory entries durable.
+fn sync_dir(dir: &Path) -> anyhow::Result<()> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_manifests, BackupCommand, RestoreCommand, OBJECTS_DIR};
+    use unc_store::{DBCol, Mode, NodeStorage, StoreConfig};
+
+    /// Takes two backups with a change in between, the second one of a running
+    /// node, checks that the second one reuses files of the first one and that
+    /// each of them restores the data as of the time it was taken.
+    #[test]
+    fn test_backup_and_restore() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let store_config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(home_dir.path(), false, &store_config, None);
+
+        {
+            let node_storage = opener.open().unwrap();
+            let mut store_update = node_storage.get_hot_store().store_update();
+            store_update.insert(DBCol::Block, vec![1], vec![42]);
+            store_update.commit().unwrap();
+            node_storage.get_hot_store().flush().unwrap();
+        }
+
+        let cmd = BackupCommand { destination: backup_dir.path().to_path_buf() };
+        cmd.run(home_dir.path(), false, &store_config, None).unwrap();
+        let objects = || std::fs::read_dir(backup_dir.path().join(OBJECTS_DIR)).unwrap().count();
+        let objects_after_first = objects();
+
+        {
+            // The second backup is taken while the node is running and the
+            // change is only in its write-ahead log.
+            let node_storage = opener.open().unwrap();
+            let mut store_update = node_storage.get_hot_store().store_update();
+            store_update.insert(DBCol::Block, vec![2], vec![43]);
+            store_update.commit().unwrap();
+            cmd.run(home_dir.path(), false, &store_config, None).unwrap();
+        }
+
+        let manifests = read_manifests(backup_dir.path()).unwrap();
+        assert_eq!(manifests.iter().map(|manifest| manifest.id).collect::<Vec<_>>(), [0, 1]);
+        let first_sst: Vec<_> = manifests[0].databases[0]
+            .files
+            .iter()
+            .filter(|file| file.name.ends_with(".sst"))
+            .map(|file| file.hash.clone())
+            .collect();
+        assert!(!first_sst.is_empty());
+        for hash in &first_sst {
+            assert!(manifests[1].databases[0].files.iter().any(|file| &file.hash == hash));
+        }
+        assert!(objects() < 2 * objects_after_first);
+
+        for (backup_id, expect_second) in [(0, false), (1, true)] {
+            let restore_home = tempfile::tempdir().unwrap();
+            let cmd = RestoreCommand {
+                source: backup_dir.path().to_path_buf(),
+                backup_id: Some(backup_id),
+            };
+            cmd.run(restore_home.path(), &store_config, None).unwrap();
+            // Restoring over an existing database is refused.
+            assert!(cmd.run(restore_home.path(), &store_config, None).is_err());
+
+            let node_storage = NodeStorage::opener(restore_home.path(), false, &store_config, None)
+                .open_in_mode(Mode::ReadOnly)
+                .unwrap();
+            let store = node_storage.get_hot_store();
+            assert!(store.exists(DBCol::Block, &[1]).unwrap());
+            assert_eq!(store.exists(DBCol::Block, &[2]).unwrap(), expect_second);
+        }
+    }
+
+    /// Checks that a corrupted object is detected before anything is restored.
+    #[test]
+    fn test_restore_detects_corruption() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let store_config = StoreConfig::test_config();
+        {
+            let node_storage =
+                NodeStorage::opener(home_dir.path(), false, &store_config, None).open().unwrap();
+            let mut store_update = node_storage.get_hot_store().store_update();
+            store_update.insert(DBCol::Block, vec![1], vec![42]);
+            store_update.commit().unwrap();
+        }
+        BackupCommand { destination: backup_dir.path().to_path_buf() }
+            .run(home_dir.path(), false, &store_config, None)
+            .unwrap();
+
+        let manifest = read_manifests(backup_dir.path()).unwrap().pop().unwrap();
+        let file = manifest.databases[0].files.iter().find(|file| file.size > 0).unwrap();
+        let object = backup_dir.path().join(OBJECTS_DIR).join(&file.hash);
+        let mut data = std::fs::read(&object).unwrap();
+        data[0] ^= 1;
+        std::fs::write(&object, data).unwrap();
+
+        let restore_home = tempfile::tempdir().unwrap();
+        let cmd = RestoreCommand { source: backup_dir.path().to_path_buf(), backup_id: None };
+        assert!(cmd.run(restore_home.path(), &store_config, None).is_err());
+        assert!(!restore_home.path().join("data").exists());
+    }
+}
@@ -0,0 +1,38 @@
+use crate::utils::resolve_column;
+use anyhow::anyhow;
+use clap::Parser;
+use std::path::PathBuf;
+use strum::IntoEnumIterator;
+use unc_store::DBCol;
+
+#[derive(Parser)]
+pub(crate) struct ColumnOptionsCommand {
+    /// If specified only options of this column will be shown
+    #[arg(short, long)]
+    column: Option<String>,
+
+    /// Show options of the cold database rather than the hot one
+    #[arg(long)]
+    cold: bool,
+}
+
+impl ColumnOptionsCommand {
+    pub(crate) fn run(&self, home: &PathBuf) -> anyhow::Result<()> {
+        let config = unc_infra::config::Config::from_file_skip_validation(
+            &home.join(unc_infra::config::CONFIG_FILENAME),
+        )?;
+        let store_config = if self.cold {
+            config.cold_store.as_ref().ok_or_else(|| anyhow!("cold_store is not configured"))?
+        } else {
+            &config.store
+        };
+        let columns = match &self.column {
+            Some(col_name) => vec![resolve_column(col_name)?],
+            None => DBCol::iter().collect(),
+        };
+        for col in columns {
+            println!("{col}: {}", serde_json::to_string(&store_config.col_options(col))?);
+        }
+        Ok(())
+    }
+}
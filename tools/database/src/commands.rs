@@ -1,7 +1,9 @@
 use crate::adjust_database::ChangeDbKindCommand;
 use crate::analyse_data_size_distribution::AnalyseDataSizeDistributionCommand;
 use crate::analyse_gas_usage::AnalyseGasUsageCommand;
-use crate::compact::RunCompactionCommand;
+use crate::backup::{BackupCommand, RestoreCommand};
+use crate::column_options::ColumnOptionsCommand;
+use crate::compact::{RunCompactionCommand, RunRecompactionCommand};
 use crate::corrupt::CorruptStateSnapshotCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::memtrie::LoadMemTrieCommand;
@@ -25,9 +27,16 @@ enum SubCommand {
     /// Analyse gas usage in a chosen sequnce of blocks
     AnalyseGasUsage(AnalyseGasUsageCommand),
 
+    /// Take an incremental backup of hot and cold databases.
+    /// Needs exclusive access to the database, so the node must be stopped.
+    Backup(BackupCommand),
+
     /// Change DbKind of hot or cold db.
     ChangeDbKind(ChangeDbKindCommand),
 
+    /// Print effective RocksDB tuning options of columns
+    ColumnOptions(ColumnOptionsCommand),
+
     /// Run SST file compaction on database
     CompactDatabase(RunCompactionCommand),
 
@@ -37,6 +46,13 @@ enum SubCommand {
     /// Make snapshot of the database
     MakeSnapshot(MakeSnapshotCommand),
 
+    /// Rewrite all SST files of columns, so that changed column options
+    /// apply to existing data
+    Recompact(RunRecompactionCommand),
+
+    /// Restore a backup into a home directory without a database.
+    Restore(RestoreCommand),
+
     /// Run migrations,
     RunMigrations(RunMigrationsCommand),
 
@@ -53,7 +69,21 @@ impl DatabaseCommand {
         match &self.subcmd {
             SubCommand::AnalyseDataSizeDistribution(cmd) => cmd.run(home),
             SubCommand::AnalyseGasUsage(cmd) => cmd.run(home),
+            SubCommand::Backup(cmd) => {
+                let unc_config = unc_infra::config::load_config(
+                    &home,
+                    unc_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(
+                    home,
+                    unc_config.config.archive,
+                    &unc_config.config.store,
+                    unc_config.config.cold_store.as_ref(),
+                )
+            }
             SubCommand::ChangeDbKind(cmd) => cmd.run(home),
+            SubCommand::ColumnOptions(cmd) => cmd.run(home),
             SubCommand::CompactDatabase(cmd) => cmd.run(home),
             SubCommand::CorruptStateSnapshot(cmd) => cmd.run(home),
             SubCommand::MakeSnapshot(cmd) => {
@@ -64,6 +94,15 @@ impl DatabaseCommand {
                 .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
                 cmd.run(home, unc_config.config.archive, &unc_config.config.store)
             }
+            SubCommand::Recompact(cmd) => cmd.run(home),
+            SubCommand::Restore(cmd) => {
+                let unc_config = unc_infra::config::load_config(
+                    &home,
+                    unc_chain_configs::GenesisValidationMode::UnsafeFast,
+                )
+                .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+                cmd.run(home, &unc_config.config.store, unc_config.config.cold_store.as_ref())
+            }
             SubCommand::RunMigrations(cmd) => cmd.run(home),
             SubCommand::StatePerf(cmd) => cmd.run(home),
             SubCommand::LoadMemTrie(cmd) => {
@@ -1,7 +1,9 @@
-use crate::utils::{open_rocksdb, resolve_column};
+use crate::utils::{open_cold_rocksdb, open_rocksdb, resolve_column};
 use clap::Parser;
 use std::path::PathBuf;
+use strum::IntoEnumIterator;
 use unc_store::db::Database;
+use unc_store::DBCol;
 
 #[derive(Parser)]
 pub(crate) struct RunCompactionCommand {
@@ -22,3 +24,37 @@ impl RunCompactionCommand {
         Ok(())
     }
 }
+
+#[derive(Parser)]
+pub(crate) struct RunRecompactionCommand {
+    /// Columns to rewrite, all columns are rewritten if none is specified
+    #[arg(short, long)]
+    column: Vec<String>,
+
+    /// Rewrite columns of the cold database rather than the hot one
+    #[arg(long)]
+    cold: bool,
+}
+
+impl RunRecompactionCommand {
+    pub(crate) fn run(&self, home: &PathBuf) -> anyhow::Result<()> {
+        let db = if self.cold {
+            open_cold_rocksdb(home, unc_store::Mode::ReadWrite)?
+        } else {
+            open_rocksdb(home, unc_store::Mode::ReadWrite)?
+        };
+        let columns: Vec<DBCol> = if self.column.is_empty() {
+            DBCol::iter().filter(|col| !self.cold || col.is_cold()).collect()
+        } else {
+            self.column.iter().map(|col_name| resolve_column(col_name)).collect::<Result<_, _>>()?
+        };
+        if let Some(col) = columns.iter().find(|col| self.cold && !col.is_cold()) {
+            anyhow::bail!("Column {col} is not stored in the cold database");
+        }
+        for col in columns {
+            db.recompact_column(col)?;
+        }
+        eprintln!("Recompaction is finished!");
+        Ok(())
+    }
+}
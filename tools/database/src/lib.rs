@@ -1,7 +1,9 @@
 mod adjust_database;
 mod analyse_data_size_distribution;
 mod analyse_gas_usage;
+mod backup;
 mod block_iterators;
+mod column_options;
 pub mod commands;
 mod compact;
 mod corrupt;
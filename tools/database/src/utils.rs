@@ -22,6 +22,21 @@ pub(crate) fn open_rocksdb(
     Ok(rocksdb)
 }
 
+pub(crate) fn open_cold_rocksdb(
+    home: &Path,
+    mode: unc_store::Mode,
+) -> anyhow::Result<unc_store::db::RocksDB> {
+    let config = unc_infra::config::Config::from_file_skip_validation(
+        &home.join(unc_infra::config::CONFIG_FILENAME),
+    )?;
+    let store_config =
+        config.cold_store.as_ref().ok_or_else(|| anyhow!("cold_store is not configured"))?;
+    let db_path = home.join(store_config.path.as_deref().unwrap_or(Path::new("cold-data")));
+    let rocksdb =
+        unc_store::db::RocksDB::open(&db_path, store_config, mode, unc_store::Temperature::Cold)?;
+    Ok(rocksdb)
+}
+
 pub(crate) fn open_state_snapshot(home: &Path, mode: unc_store::Mode) -> anyhow::Result<Store> {
     let config = unc_infra::config::Config::from_file_skip_validation(
         &home.join(unc_infra::config::CONFIG_FILENAME),
@@ -136,6 +136,8 @@ export type SyncStatusView =
 export interface ShardSyncDownloadView {
     downloads: { error: boolean; done: boolean }[];
     status: string;
+    num_parts_resumed: number;
+    eta_secs: number | null;
 }
 
 export interface DebugBlockStatusData {
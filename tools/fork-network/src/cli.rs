@@ -595,6 +595,8 @@ impl ForkNetworkCommand {
                         }
                         index_delayed_receipt += 1;
                     }
+                    // Global contract code isn't attached to any account, nothing to remap.
+                    StateRecord::GlobalContractCode { .. } => {}
                 }
                 records_parsed += 1;
             } else {
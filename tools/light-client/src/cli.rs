@@ -0,0 +1,224 @@
+use crate::convert;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use unc_jsonrpc_client::{new_client, JsonRpcClient};
+use unc_jsonrpc_primitives::types::light_client::{
+    RpcLightClientExecutionProofRequest, RpcLightClientNextBlockRequest,
+};
+use unc_light_client::{Error, LightClient};
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::types::{AccountId, TransactionOrReceiptId};
+use unc_primitives::views::LightClientBlockView;
+
+/// Follows the chain as a light client, using only the responses of an RPC node, which
+/// doesn't have to be trusted.
+#[derive(clap::Parser)]
+pub struct LightClientCommand {
+    /// Address of the RPC node.
+    #[clap(long)]
+    rpc_url: String,
+    /// File storing the state of the light client between runs.
+    #[clap(long)]
+    state_file: PathBuf,
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(clap::Parser)]
+#[clap(subcommand_required = true, arg_required_else_help = true)]
+enum SubCommand {
+    /// Starts the light client from a trusted checkpoint.
+    Init(InitCmd),
+    /// Validates the light client blocks following the head of the light client.
+    Sync(SyncCmd),
+    /// Verifies the execution of a transaction.
+    VerifyTx(VerifyTxCmd),
+    /// Verifies the execution of a receipt.
+    VerifyReceipt(VerifyReceiptCmd),
+}
+
+impl LightClientCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        let client = new_client(&self.rpc_url);
+        let state_file = self.state_file;
+        let sys = actix::System::new();
+        sys.block_on(async move {
+            match self.subcmd {
+                SubCommand::Init(cmd) => cmd.run(&state_file),
+                SubCommand::Sync(cmd) => cmd.run(&client, &state_file).await,
+                SubCommand::VerifyTx(cmd) => {
+                    let id = TransactionOrReceiptId::Transaction {
+                        transaction_hash: cmd.tx_hash,
+                        sender_id: cmd.sender_id,
+                    };
+                    verify_execution(&client, &state_file, id).await
+                }
+                SubCommand::VerifyReceipt(cmd) => {
+                    let id = TransactionOrReceiptId::Receipt {
+                        receipt_id: cmd.receipt_id,
+                        receiver_id: cmd.receiver_id,
+                    };
+                    verify_execution(&client, &state_file, id).await
+                }
+            }
+        })
+    }
+}
+
+#[derive(clap::Parser)]
+struct InitCmd {
+    /// JSON file with the trusted light client block to start from, e.g. the result of the
+    /// `next_light_client_block` RPC method of a node you trust.
+    #[clap(long)]
+    checkpoint: PathBuf,
+    /// Hash of the checkpoint block, obtained from an independent trusted source.
+    /// If set, the checkpoint is rejected unless its hash matches.
+    #[clap(long)]
+    block_hash: Option<CryptoHash>,
+}
+
+impl InitCmd {
+    fn run(self, state_file: &Path) -> anyhow::Result<()> {
+        let checkpoint: LightClientBlockView = serde_json::from_slice(
+            &std::fs::read(&self.checkpoint)
+                .with_context(|| format!("failed to read {}", self.checkpoint.display()))?,
+        )
+        .context("failed to parse the checkpoint")?;
+        let light_client = LightClient::new(&convert::light_client_block(&checkpoint)?)?;
+        let head_hash = convert::chain_hash(&light_client.head_hash());
+        if let Some(block_hash) = self.block_hash {
+            anyhow::ensure!(
+                head_hash == block_hash,
+                "checkpoint has hash {head_hash}, expected {block_hash}"
+            );
+        }
+        save_state(state_file, &light_client)?;
+        println!(
+            "Initialized the light client at block {head_hash} (height {})",
+            light_client.head().inner_lite.height
+        );
+        Ok(())
+    }
+}
+
+#[derive(clap::Parser)]
+struct SyncCmd {
+    /// Keep following the chain after catching up with the RPC node.
+    #[clap(long)]
+    follow: bool,
+    /// Seconds to wait before asking the RPC node for new blocks, after catching up with it.
+    #[clap(long, default_value = "10")]
+    poll_interval_secs: u64,
+}
+
+impl SyncCmd {
+    async fn run(self, client: &JsonRpcClient, state_file: &Path) -> anyhow::Result<()> {
+        let mut light_client = load_state(state_file)?;
+        loop {
+            let response = client
+                .next_light_client_block(RpcLightClientNextBlockRequest {
+                    last_block_hash: convert::chain_hash(&light_client.head_hash()),
+                })
+                .await
+                .map_err(|err| anyhow::anyhow!("next_light_client_block failed: {err}"))?;
+            let caught_up = match response.light_client_block {
+                None => true,
+                Some(block) => match light_client.update(&convert::light_client_block(&block)?) {
+                    Ok(()) => {
+                        save_state(state_file, &light_client)?;
+                        tracing::info!(
+                            target: "light-client",
+                            head = %convert::chain_hash(&light_client.head_hash()),
+                            height = light_client.head().inner_lite.height,
+                            "updated head"
+                        );
+                        false
+                    }
+                    // The RPC node has no newer block.
+                    Err(Error::NotAfterHead { .. }) => true,
+                    // The head is the checkpoint and the chain hasn't moved to the next epoch
+                    // yet, so the blocks of the checkpoint's epoch can't be validated.
+                    Err(Error::UnknownBlockProducers(_)) => true,
+                    Err(err) => anyhow::bail!("RPC node returned an invalid block: {err}"),
+                },
+            };
+            if caught_up {
+                if !self.follow {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_secs(self.poll_interval_secs)).await;
+            }
+        }
+        println!(
+            "Light client head is block {} (height {})",
+            convert::chain_hash(&light_client.head_hash()),
+            light_client.head().inner_lite.height
+        );
+        Ok(())
+    }
+}
+
+#[derive(clap::Parser)]
+struct VerifyTxCmd {
+    #[clap(long)]
+    tx_hash: CryptoHash,
+    #[clap(long)]
+    sender_id: AccountId,
+}
+
+#[derive(clap::Parser)]
+struct VerifyReceiptCmd {
+    #[clap(long)]
+    receipt_id: CryptoHash,
+    #[clap(long)]
+    receiver_id: AccountId,
+}
+
+/// Asks the RPC node for a proof of the execution against the head of the light client.
+/// The head has to be synced past the block including the outcome.
+async fn verify_execution(
+    client: &JsonRpcClient,
+    state_file: &Path,
+    id: TransactionOrReceiptId,
+) -> anyhow::Result<()> {
+    let light_client = load_state(state_file)?;
+    let proof = client
+        .light_client_proof(RpcLightClientExecutionProofRequest {
+            id,
+            light_client_head: convert::chain_hash(&light_client.head_hash()),
+        })
+        .await
+        .map_err(|err| anyhow::anyhow!("light_client_proof failed: {err}"))?;
+    light_client
+        .verify_execution_proof(
+            &convert::execution_outcome_proof(&proof.outcome_proof),
+            &convert::merkle_path(&proof.outcome_root_proof),
+            &convert::light_client_block_lite(&proof.block_header_lite),
+            &convert::merkle_path(&proof.block_proof),
+        )
+        .context("invalid proof")?;
+    println!(
+        "Verified execution of {} in block {} (height {})",
+        proof.outcome_proof.id,
+        proof.outcome_proof.block_hash,
+        proof.block_header_lite.inner_lite.height
+    );
+    println!("{}", serde_json::to_string_pretty(&proof.outcome_proof.outcome)?);
+    Ok(())
+}
+
+fn load_state(state_file: &Path) -> anyhow::Result<LightClient> {
+    let data = std::fs::read(state_file).with_context(|| {
+        format!("failed to read {}, run the init command first", state_file.display())
+    })?;
+    borsh::from_slice(&data).context("failed to parse the light client state")
+}
+
+fn save_state(state_file: &Path, light_client: &LightClient) -> anyhow::Result<()> {
+    // Write to a temporary file first, so that the state isn't lost if we get interrupted.
+    let tmp = state_file.with_extension("tmp");
+    std::fs::write(&tmp, borsh::to_vec(light_client)?)?;
+    std::fs::rename(&tmp, state_file)?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+//! Conversions from the views returned by the RPC node to the types of the verifier, which
+//! doesn't depend on unc-primitives.
+use unc_crypto::{PublicKey, Signature};
+use unc_light_client as lc;
+use unc_primitives::hash::CryptoHash;
+use unc_primitives::merkle::{Direction, MerklePath};
+use unc_primitives::views::validator_power_and_pledge_view::ValidatorPowerAndPledgeView;
+use unc_primitives::views::{
+    BlockHeaderInnerLiteView, ExecutionOutcomeWithIdView, LightClientBlockLiteView,
+    LightClientBlockView,
+};
+
+pub(crate) fn hash(hash: &CryptoHash) -> lc::CryptoHash {
+    lc::CryptoHash(hash.0)
+}
+
+pub(crate) fn chain_hash(hash: &lc::CryptoHash) -> CryptoHash {
+    CryptoHash(hash.0)
+}
+
+fn public_key(public_key: &PublicKey) -> anyhow::Result<lc::PublicKey> {
+    match public_key {
+        PublicKey::ED25519(public_key) => Ok(lc::PublicKey(public_key.0)),
+        _ => anyhow::bail!("unsupported block producer key {public_key}"),
+    }
+}
+
+fn signature(signature: &Signature) -> anyhow::Result<lc::Signature> {
+    match signature {
+        Signature::ED25519(signature) => Ok(lc::Signature(signature.to_bytes())),
+        _ => anyhow::bail!("unsupported approval signature {signature}"),
+    }
+}
+
+fn block_producer(
+    view: &ValidatorPowerAndPledgeView,
+) -> anyhow::Result<lc::ValidatorPowerAndPledge> {
+    let block_producer = view.clone().into_validator_power_and_pledge();
+    Ok(lc::ValidatorPowerAndPledge {
+        account_id: block_producer.account_id().to_string(),
+        public_key: public_key(block_producer.public_key())?,
+        power: block_producer.power(),
+        pledge: block_producer.pledge(),
+    })
+}
+
+fn inner_lite(view: &BlockHeaderInnerLiteView) -> lc::BlockHeaderInnerLite {
+    lc::BlockHeaderInnerLite {
+        height: view.height,
+        epoch_id: hash(&view.epoch_id),
+        next_epoch_id: hash(&view.next_epoch_id),
+        prev_state_root: hash(&view.prev_state_root),
+        outcome_root: hash(&view.outcome_root),
+        timestamp: view.timestamp_nanosec,
+        next_bp_hash: hash(&view.next_bp_hash),
+        block_merkle_root: hash(&view.block_merkle_root),
+    }
+}
+
+pub(crate) fn light_client_block(
+    view: &LightClientBlockView,
+) -> anyhow::Result<lc::LightClientBlock> {
+    Ok(lc::LightClientBlock {
+        prev_block_hash: hash(&view.prev_block_hash),
+        next_block_inner_hash: hash(&view.next_block_inner_hash),
+        inner_lite: inner_lite(&view.inner_lite),
+        inner_rest_hash: hash(&view.inner_rest_hash),
+        next_bps: view
+            .next_bps
+            .as_ref()
+            .map(|next_bps| next_bps.iter().map(block_producer).collect())
+            .transpose()?,
+        approvals_after_next: view
+            .approvals_after_next
+            .iter()
+            .map(|approval| approval.as_deref().map(signature).transpose())
+            .collect::<anyhow::Result<_>>()?,
+    })
+}
+
+pub(crate) fn light_client_block_lite(view: &LightClientBlockLiteView) -> lc::LightClientBlockLite {
+    lc::LightClientBlockLite {
+        prev_block_hash: hash(&view.prev_block_hash),
+        inner_rest_hash: hash(&view.inner_rest_hash),
+        inner_lite: inner_lite(&view.inner_lite),
+    }
+}
+
+pub(crate) fn merkle_path(path: &MerklePath) -> lc::MerklePath {
+    path.iter()
+        .map(|item| lc::MerklePathItem {
+            hash: hash(&item.hash),
+            direction: match item.direction {
+                Direction::Left => lc::Direction::Left,
+                Direction::Right => lc::Direction::Right,
+            },
+        })
+        .collect()
+}
+
+pub(crate) fn execution_outcome_proof(
+    view: &ExecutionOutcomeWithIdView,
+) -> lc::ExecutionOutcomeProof {
+    lc::ExecutionOutcomeProof {
+        proof: merkle_path(&view.proof),
+        block_hash: hash(&view.block_hash),
+        outcome_hashes: view.to_hashes().iter().map(hash).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unc_primitives::block_header::ApprovalInner;
+    use unc_primitives::epoch_sync::light_client_block_hash;
+    use unc_primitives::hash::hash as sha256;
+    use unc_primitives::merkle::{combine_hash, compute_root_from_path, merklize};
+    use unc_primitives::test_utils::create_test_signer;
+    use unc_primitives::transaction::ExecutionOutcome;
+    use unc_primitives::types::validator_power_and_pledge::ValidatorPowerAndPledge;
+    use unc_primitives::validator_signer::ValidatorSigner;
+
+    fn block_producers(names: &[&str]) -> Vec<ValidatorPowerAndPledgeView> {
+        names
+            .iter()
+            .map(|name| {
+                let signer = create_test_signer(name);
+                ValidatorPowerAndPledge::new(
+                    signer.validator_id().clone(),
+                    signer.public_key(),
+                    3,
+                    7,
+                )
+                .into()
+            })
+            .collect()
+    }
+
+    fn block_view(
+        height: u64,
+        epoch_id: CryptoHash,
+        next_epoch_id: CryptoHash,
+        signers: &[&str],
+        next_bps: Vec<ValidatorPowerAndPledgeView>,
+    ) -> LightClientBlockView {
+        let mut block = LightClientBlockView {
+            prev_block_hash: sha256(&height.to_le_bytes()),
+            next_block_inner_hash: sha256(b"next"),
+            inner_lite: BlockHeaderInnerLiteView {
+                height,
+                epoch_id,
+                next_epoch_id,
+                prev_state_root: sha256(b"state"),
+                outcome_root: sha256(b"outcome"),
+                timestamp: 1,
+                timestamp_nanosec: 1_000_000_123,
+                next_bp_hash: CryptoHash::hash_borsh_iter(
+                    next_bps.iter().cloned().map(ValidatorPowerAndPledge::from),
+                ),
+                block_merkle_root: sha256(b"blocks"),
+            },
+            inner_rest_hash: sha256(b"rest"),
+            next_bps: Some(next_bps),
+            approvals_after_next: vec![],
+        };
+        let next_block_hash =
+            combine_hash(&block.next_block_inner_hash, &light_client_block_hash(&block));
+        block.approvals_after_next = signers
+            .iter()
+            .map(|name| {
+                Some(Box::new(
+                    create_test_signer(name)
+                        .sign_approval(&ApprovalInner::Endorsement(next_block_hash), height + 2),
+                ))
+            })
+            .collect();
+        block
+    }
+
+    /// The verifier hashes and signs its own types exactly as the chain does with the views.
+    #[test]
+    fn verifier_matches_chain_encodings() {
+        let (epoch0, epoch1, epoch2) = (sha256(b"epoch0"), sha256(b"epoch1"), sha256(b"epoch2"));
+        let checkpoint_view =
+            block_view(10, epoch0, epoch1, &[], block_producers(&["test0", "test1"]));
+        let checkpoint = light_client_block(&checkpoint_view).unwrap();
+        assert_eq!(chain_hash(&checkpoint.hash()), light_client_block_hash(&checkpoint_view));
+        let mut light_client = lc::LightClient::new(&checkpoint).unwrap();
+
+        let block_view =
+            block_view(20, epoch1, epoch2, &["test0", "test1"], block_producers(&["test2"]));
+        light_client.update(&light_client_block(&block_view).unwrap()).unwrap();
+        let lite_view = LightClientBlockLiteView {
+            prev_block_hash: block_view.prev_block_hash,
+            inner_rest_hash: block_view.inner_rest_hash,
+            inner_lite: block_view.inner_lite.clone(),
+        };
+        assert_eq!(chain_hash(&light_client_block_lite(&lite_view).hash()), lite_view.hash());
+        assert_eq!(chain_hash(&light_client.head_hash()), light_client_block_hash(&block_view));
+
+        let (_, paths) = merklize(&[sha256(b"a"), sha256(b"b"), sha256(b"c")]);
+        let outcome = ExecutionOutcomeWithIdView {
+            proof: paths[2].clone(),
+            block_hash: sha256(b"block"),
+            id: sha256(b"tx"),
+            outcome: ExecutionOutcome { logs: vec!["log".to_string()], ..Default::default() }
+                .into(),
+        };
+        let proof = execution_outcome_proof(&outcome);
+        assert_eq!(
+            lc::CryptoHash::hash_borsh(&proof.outcome_hashes),
+            hash(&CryptoHash::hash_borsh(outcome.to_hashes()))
+        );
+        assert_eq!(
+            lc::compute_root_from_path(&proof.proof, hash(&outcome.id)),
+            hash(&compute_root_from_path(&outcome.proof, outcome.id))
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod cli;
+mod convert;
+pub use cli::LightClientCommand;
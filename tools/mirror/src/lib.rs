@@ -1017,7 +1017,7 @@ impl<T: ChainAccess> TxMirror<T> {
                 }
                 // We don't want to mess with the set of validators in the target chain
                 Action::Pledge(_) => {}
-                Action::DeployContract(_) => {
+                Action::DeployContract(_) | Action::DeployGlobalContract(_) => {
                     // if we're getting transactions from a ViewClient instead of directly from the DB,
                     // DeployContract actions are silently mangled, so we can't recover the original contract code here
                     if !tx.is_view() {
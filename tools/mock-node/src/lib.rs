@@ -4,7 +4,7 @@
 use anyhow::{anyhow, Context as AnyhowContext};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::task::Poll;
 use std::time::Duration;
@@ -12,7 +12,8 @@ use unc_async::time;
 use unc_chain::{Block, Chain, ChainStoreAccess, Error};
 use unc_client::sync::header::MAX_BLOCK_HEADERS;
 use unc_crypto::SecretKey;
-use unc_network::raw::{DirectMessage, Listener, Message, RoutedMessage};
+use unc_network::capture;
+use unc_network::raw::{Connection, DirectMessage, Listener, Message, RoutedMessage};
 use unc_network::tcp;
 use unc_network::types::{PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg};
 use unc_primitives::sharding::ChunkHash;
@@ -42,6 +43,10 @@ pub struct MockNetworkConfig {
     // How long we'll wait until sending replies to the client
     pub response_delay: Duration,
     pub incoming_requests: Option<MockIncomingRequestsConfig>,
+    // Directory with a network capture recorded by a node (see unc_network::capture),
+    // whose inbound messages will be replayed to the client
+    #[serde(default)]
+    pub replay: Option<PathBuf>,
 }
 
 impl MockNetworkConfig {
@@ -65,7 +70,7 @@ fn default_delay() -> Duration {
 
 impl Default for MockNetworkConfig {
     fn default() -> Self {
-        Self { response_delay: default_delay(), incoming_requests: None }
+        Self { response_delay: default_delay(), incoming_requests: None, replay: None }
     }
 }
 
@@ -213,6 +218,59 @@ impl IncomingRequests {
     }
 }
 
+// Replays the inbound messages of a network capture to the client, preserving
+// the intervals between them. Messages of all the captured peers are replayed
+// over the connection of the mock peer, and the replay continues over the next
+// connection if the client reconnects.
+struct CaptureReplay {
+    reader: capture::Reader,
+    // the timestamp of the first replayed message, and when it was replayed
+    start: Option<(time::Utc, tokio::time::Instant)>,
+    // the next message to replay, kept here so that it is not lost if
+    // next() is cancelled while waiting
+    pending: Option<capture::Record>,
+}
+
+impl CaptureReplay {
+    fn new(dir: &Path) -> anyhow::Result<Self> {
+        let reader = capture::Reader::new(dir)
+            .with_context(|| format!("failed opening network capture {}", dir.display()))?;
+        Ok(Self { reader, start: None, pending: None })
+    }
+
+    // returns the next captured inbound message once it's due, or never returns if
+    // the whole capture has been replayed already
+    async fn next(&mut self) -> anyhow::Result<capture::Record> {
+        while self.pending.is_none() {
+            match self.reader.next() {
+                Some(record) => {
+                    let record = record.context("failed reading network capture")?;
+                    if record.direction == capture::Direction::Inbound {
+                        self.pending = Some(record);
+                    }
+                }
+                None => {
+                    tracing::info!(target: "mock_node", "network capture replayed");
+                    return futures::future::pending().await;
+                }
+            }
+        }
+        let timestamp = self.pending.as_ref().unwrap().timestamp()?;
+        let (start_timestamp, start) =
+            *self.start.get_or_insert((timestamp, tokio::time::Instant::now()));
+        let offset = Duration::try_from(timestamp - start_timestamp).unwrap_or(Duration::ZERO);
+        tokio::time::sleep_until(start + offset).await;
+        Ok(self.pending.take().unwrap())
+    }
+}
+
+async fn next_replayed(r: Option<&mut CaptureReplay>) -> anyhow::Result<capture::Record> {
+    match r {
+        Some(r) => r.next().await,
+        None => futures::future::pending().await,
+    }
+}
+
 struct InFlightMessage {
     message: Message,
     sent_at: tokio::time::Instant,
@@ -283,6 +341,7 @@ struct MockPeer {
     network_config: MockNetworkConfig,
     block_production: tokio::time::Interval,
     incoming_requests: IncomingRequests,
+    replay: Option<CaptureReplay>,
 }
 
 impl MockPeer {
@@ -310,6 +369,7 @@ impl MockPeer {
         .await?;
         let incoming_requests =
             IncomingRequests::new(&network_config.incoming_requests, &chain, network_start_height);
+        let replay = network_config.replay.as_deref().map(CaptureReplay::new).transpose()?;
         // make sure we start at a height that actually exists, because we want self.produce_block()
         // to give the first block immediately. Otherwise the node won't even try asking us for block headers
         // until we give it a block.
@@ -336,6 +396,7 @@ impl MockPeer {
             network_config,
             block_production: tokio::time::interval(block_production_delay),
             incoming_requests,
+            replay,
         })
     }
 
@@ -425,30 +486,71 @@ impl MockPeer {
     }
 
     // listen on the addr passed to MockPeer::new() and wait til someone connects.
-    // Then respond to messages indefinitely until an error occurs
+    // Then respond to messages indefinitely until an error occurs. If the connection
+    // is lost, wait for the next one and continue from where we left off.
     async fn run(mut self, target_height: BlockHeight) -> anyhow::Result<()> {
-        let mut conn = self.listener.accept().await?;
+        let mut replay = self.replay.take();
+        loop {
+            let conn = self.listener.accept().await?;
+            self.serve(conn, target_height, replay.as_mut()).await?;
+        }
+    }
+
+    // responds to the messages received over `conn`, and returns once the connection
+    // is lost
+    async fn serve(
+        &mut self,
+        mut conn: Connection,
+        target_height: BlockHeight,
+        mut replay: Option<&mut CaptureReplay>,
+    ) -> anyhow::Result<()> {
         let messages = InFlightMessages::new(self.network_config.response_delay);
         tokio::pin!(messages);
 
         loop {
             tokio::select! {
                 res = conn.recv() => {
-                    let (msg, _timestamp) = res.with_context(|| format!("failed receiving message from {:?}", &conn))?;
+                    let (msg, _timestamp) = match res {
+                        Ok(msg) => msg,
+                        Err(err) => {
+                            tracing::warn!(target: "mock_node", ?err, "mock peer failed receiving message from {:?}", &conn);
+                            return Ok(());
+                        }
+                    };
 
                     self.handle_message(msg, messages.as_mut())?;
                 }
                 msg = &mut messages => {
                     tracing::debug!("mock peer sending message {}", &msg);
-                    match msg {
-                        Message::Direct(msg) => conn.send_message(msg).await?,
-                        Message::Routed(msg) => conn.send_routed_message(msg, conn.peer_id().clone(), 100).await?,
+                    let res = match msg {
+                        Message::Direct(msg) => conn.send_message(msg).await,
+                        Message::Routed(msg) => conn.send_routed_message(msg, conn.peer_id().clone(), 100).await,
                     };
+                    if let Err(err) = res {
+                        tracing::warn!(target: "mock_node", ?err, "mock peer failed sending message to {:?}", &conn);
+                        return Ok(());
+                    }
                 }
                 msg = self.incoming_message(target_height) => {
                     let msg = msg?;
                     messages.as_mut().queue_message(msg);
                 }
+                record = next_replayed(replay.as_deref_mut()) => {
+                    let record = record?;
+                    let msg_type = record.msg_type()?;
+                    match conn.replay(&record, 100).await {
+                        Ok(true) => tracing::debug!(target: "mock_node", msg_type, "mock peer replayed captured message"),
+                        Ok(false) => tracing::debug!(target: "mock_node", msg_type, "mock peer skipped captured message"),
+                        Err(err) => {
+                            tracing::warn!(target: "mock_node", ?err, "mock peer failed replaying captured message to {:?}", &conn);
+                            // replay it again over the next connection
+                            if let Some(replay) = replay {
+                                replay.pending = Some(record);
+                            }
+                            return Ok(());
+                        }
+                    }
+                }
             }
         }
     }
@@ -488,3 +590,56 @@ fn retrieve_partial_encoded_chunk(
 
     Ok(PartialEncodedChunkResponseMsg { chunk_hash: request.chunk_hash.clone(), parts, receipts })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::CaptureReplay;
+    use std::time::Duration;
+    use unc_network::capture;
+    use unc_primitives::network::PeerId;
+
+    fn record(offset_millis: i128, direction: capture::Direction) -> capture::Record {
+        capture::Record {
+            timestamp_nanos: 1_000_000_000 + offset_millis * 1_000_000,
+            peer_id: PeerId::random(),
+            direction,
+            message: vec![],
+        }
+    }
+
+    // Test that the inbound messages of a capture are replayed in order and at
+    // their intervals, and that a replay interrupted while waiting for the next
+    // message (as when the connection is lost) doesn't lose that message.
+    #[tokio::test]
+    async fn test_capture_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = vec![
+            record(0, capture::Direction::Inbound),
+            record(10, capture::Direction::Outbound),
+            record(100, capture::Direction::Inbound),
+            record(300, capture::Direction::Inbound),
+        ];
+        let config = capture::Config {
+            dir: dir.path().to_path_buf(),
+            max_file_size: 1_000_000,
+            max_files: 1,
+        };
+        let mut writer = capture::Writer::new(config).unwrap();
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let mut replay = CaptureReplay::new(dir.path()).unwrap();
+        let start = tokio::time::Instant::now();
+        assert_eq!(records[0], replay.next().await.unwrap());
+        // the outbound message is skipped
+        assert_eq!(records[2], replay.next().await.unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(tokio::time::timeout(Duration::from_millis(50), replay.next()).await.is_err());
+        assert_eq!(records[3], replay.next().await.unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        // once the whole capture has been replayed, nothing more is returned
+        assert!(tokio::time::timeout(Duration::from_millis(100), replay.next()).await.is_err());
+    }
+}
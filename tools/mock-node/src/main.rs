@@ -46,6 +46,9 @@ use unc_primitives::types::BlockHeight;
 ///
 /// # Mixed: client starts at genesis and tries to catch up with the network, which starts at height 20.
 /// $ mock-node ~/.unc/localnet/node0 --network-height 20
+///
+/// # Replay the messages that a node received, as recorded in its network capture.
+/// $ mock-node ~/.unc/localnet/node0 --start-height 61 --replay ~/.unc/localnet/node0/capture
 /// ```
 #[derive(clap::Parser)]
 struct Cli {
@@ -83,6 +86,11 @@ struct Cli {
     /// port the mock node should listen on
     #[clap(long)]
     mock_port: Option<u16>,
+    /// Directory with a network capture (see `experimental.capture` in the network config).
+    /// The messages received by the captured node are replayed to the client, preserving
+    /// the intervals between them.
+    #[clap(long)]
+    replay: Option<PathBuf>,
 }
 
 async fn target_height_reached(client: &JsonRpcClient, target_height: BlockHeight) -> bool {
@@ -134,6 +142,9 @@ fn main() -> anyhow::Result<()> {
     if let Some(delay) = args.network_delay {
         network_config.response_delay = Duration::from_millis(delay);
     }
+    if let Some(replay) = args.replay {
+        network_config.replay = Some(replay);
+    }
 
     let client_height = args.start_height.unwrap_or(args.client_height);
     let network_height = args.start_height.or(args.network_height);
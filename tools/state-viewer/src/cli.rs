@@ -7,11 +7,13 @@ use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use unc_chain_configs::{GenesisChangeConfig, GenesisValidationMode};
 use unc_infra::{load_config, UncConfig};
+use unc_parameters::vm::VMKind;
 use unc_primitives::account::id::AccountId;
 use unc_primitives::hash::CryptoHash;
 use unc_primitives::sharding::ChunkHash;
 use unc_primitives::trie_key::col;
 use unc_primitives::types::{BlockHeight, ShardId};
+use unc_primitives::version::ProtocolVersion;
 use unc_store::{Mode, NodeStorage, Store, Temperature};
 
 #[derive(clap::Subcommand)]
@@ -71,6 +73,9 @@ pub enum StateViewerSubCommand {
     Receipts(ReceiptsCmd),
     /// Replay headers from chain.
     Replay(ReplayCmd),
+    /// Re-execute a contract call recorded with `--trace-dir` of `apply-receipt`
+    /// or `apply-tx` and compare it with the recording.
+    ReplayContractTrace(ReplayContractTraceCmd),
     /// Dump stats for the RocksDB storage.
     #[clap(name = "rocksdb-stats", alias = "rocksdb_stats")]
     RocksDBStats(RocksDBStatsCmd),
@@ -142,6 +147,7 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(unc_config, store),
             StateViewerSubCommand::Receipts(cmd) => cmd.run(unc_config, store),
             StateViewerSubCommand::Replay(cmd) => cmd.run(unc_config, store),
+            StateViewerSubCommand::ReplayContractTrace(cmd) => cmd.run(unc_config),
             StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(store_opener.path()),
             StateViewerSubCommand::ScanDbColumn(cmd) => cmd.run(store),
             StateViewerSubCommand::State => state(home_dir, unc_config, store),
@@ -241,12 +247,18 @@ pub struct ApplyReceiptCmd {
     hash: String,
     #[clap(long)]
     use_flat_storage: bool,
+    /// Record traces of the contract executions into this directory.
+    #[clap(long)]
+    trace_dir: Option<PathBuf>,
 }
 
 impl ApplyReceiptCmd {
     pub fn run(self, home_dir: &Path, unc_config: UncConfig, store: Store) {
         let hash = CryptoHash::from_str(&self.hash).unwrap();
-        apply_receipt(home_dir, unc_config, store, hash, self.use_flat_storage).unwrap();
+        with_contract_traces(self.trace_dir.as_deref(), &hash, || {
+            apply_receipt(home_dir, unc_config, store, hash, self.use_flat_storage)
+        })
+        .unwrap();
     }
 }
 
@@ -256,12 +268,47 @@ pub struct ApplyTxCmd {
     hash: String,
     #[clap(long)]
     use_flat_storage: bool,
+    /// Record traces of the contract executions into this directory.
+    #[clap(long)]
+    trace_dir: Option<PathBuf>,
 }
 
 impl ApplyTxCmd {
     pub fn run(self, home_dir: &Path, unc_config: UncConfig, store: Store) {
         let hash = CryptoHash::from_str(&self.hash).unwrap();
-        apply_tx(home_dir, unc_config, store, hash, self.use_flat_storage).unwrap();
+        with_contract_traces(self.trace_dir.as_deref(), &hash, || {
+            apply_tx(home_dir, unc_config, store, hash, self.use_flat_storage)
+        })
+        .unwrap();
+    }
+}
+
+#[derive(clap::Parser)]
+pub struct ReplayContractTraceCmd {
+    /// Trace file written by `--trace-dir`.
+    #[clap(long)]
+    trace: PathBuf,
+    /// Wasm code of the contract, as written by `dump-code`.
+    #[clap(long)]
+    code: PathBuf,
+    /// VM to replay with, defaults to the VM of the protocol version.
+    #[clap(long, value_enum)]
+    vm_kind: Option<VMKind>,
+    /// Protocol version whose runtime config is used, defaults to the latest.
+    #[clap(long)]
+    protocol_version: Option<ProtocolVersion>,
+}
+
+impl ReplayContractTraceCmd {
+    pub fn run(self, unc_config: UncConfig) {
+        replay_contract_trace(
+            &self.trace,
+            &self.code,
+            self.vm_kind,
+            self.protocol_version,
+            &unc_config,
+        )
+        .unwrap();
     }
 }
 
@@ -679,6 +726,7 @@ pub enum RecordType {
     PostponedReceipt = col::POSTPONED_RECEIPT,
     DelayedReceiptOrIndices = col::DELAYED_RECEIPT_OR_INDICES,
     ContractData = col::CONTRACT_DATA,
+    GlobalContractCode = col::GLOBAL_CONTRACT_CODE,
 }
 
 impl clap::ValueEnum for RecordType {
@@ -693,6 +741,7 @@ impl clap::ValueEnum for RecordType {
             Self::PostponedReceipt,
             Self::DelayedReceiptOrIndices,
             Self::ContractData,
+            Self::GlobalContractCode,
         ]
     }
 
@@ -711,6 +760,9 @@ impl clap::ValueEnum for RecordType {
                 Some(clap::builder::PossibleValue::new("delayed-receipt-or-indices"))
             }
             Self::ContractData => Some(clap::builder::PossibleValue::new("contract-data")),
+            Self::GlobalContractCode => {
+                Some(clap::builder::PossibleValue::new("global-contract-code"))
+            }
         }
     }
 }
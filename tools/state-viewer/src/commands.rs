@@ -31,6 +31,8 @@ use unc_epoch_manager::types::BlockHeaderInfo;
 use unc_epoch_manager::EpochManagerHandle;
 use unc_epoch_manager::{EpochManager, EpochManagerAdapter};
 use unc_infra::{NightshadeRuntime, UncConfig};
+use unc_parameters::vm::VMKind;
+use unc_parameters::RuntimeConfigStore;
 use unc_primitives::account::id::AccountId;
 use unc_primitives::block::{Block, BlockHeader};
 use unc_primitives::hash::CryptoHash;
@@ -43,12 +45,15 @@ use unc_primitives::state_record::StateRecord;
 use unc_primitives::trie_key::col::NON_DELAYED_RECEIPT_COLUMNS;
 use unc_primitives::trie_key::TrieKey;
 use unc_primitives::types::{chunk_extra::ChunkExtra, BlockHeight, ShardId, StateRoot};
+use unc_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use unc_primitives_core::types::Gas;
 use unc_store::flat::FlatStorageChunkView;
 use unc_store::flat::FlatStorageManager;
 use unc_store::test_utils::create_test_store;
 use unc_store::TrieStorage;
 use unc_store::{DBCol, Store, Trie, TrieCache, TrieCachingStorage, TrieConfig, TrieDBStorage};
+use unc_vm_runner::trace::ExecutionTrace;
+use unc_vm_runner::ContractCode;
 use yansi::Color::Red;
 
 pub(crate) fn apply_block(
@@ -273,6 +278,56 @@ pub(crate) fn apply_tx(
     .map(|_| ())
 }
 
+/// Runs `f`, recording traces of all contract executions it makes and saving
+/// them into `trace_dir` as `<hash>.<index>.trace` if a directory is given.
+pub(crate) fn with_contract_traces<T>(
+    trace_dir: Option<&Path>,
+    hash: &CryptoHash,
+    f: impl FnOnce() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let Some(trace_dir) = trace_dir else { return f() };
+    let (result, traces) = unc_vm_runner::trace::record(f);
+    fs::create_dir_all(trace_dir)?;
+    for (index, trace) in traces.iter().enumerate() {
+        let path = trace_dir.join(format!("{hash}.{index}.trace"));
+        trace.save(&path)?;
+        println!(
+            "Saved trace of {}::{} to {}",
+            trace.context.current_account_id,
+            trace.method_name,
+            path.display()
+        );
+    }
+    result
+}
+
+pub(crate) fn replay_contract_trace(
+    trace_path: &Path,
+    code_path: &Path,
+    vm_kind: Option<VMKind>,
+    protocol_version: Option<ProtocolVersion>,
+    unc_config: &UncConfig,
+) -> anyhow::Result<()> {
+    let trace = ExecutionTrace::load(trace_path)?;
+    let code = ContractCode::new(fs::read(code_path)?, None);
+    let config_store = RuntimeConfigStore::for_chain_id(&unc_config.genesis.config.chain_id);
+    let runtime_config = config_store.get_config(protocol_version.unwrap_or(PROTOCOL_VERSION));
+    let mut wasm_config = runtime_config.wasm_config.clone();
+    if let Some(vm_kind) = vm_kind {
+        wasm_config.vm_kind = vm_kind;
+    }
+    println!(
+        "Replaying {}::{} recorded with {:?} on {:?}",
+        trace.context.current_account_id, trace.method_name, trace.vm_kind, wasm_config.vm_kind
+    );
+    let replayed = unc_vm_runner::trace::replay(&trace, &code, &wasm_config, &runtime_config.fees)?;
+    match trace.first_divergence(&replayed) {
+        None => println!("Replay matches the trace: {:?}", replayed.outcome),
+        Some(divergence) => println!("{}", Red.paint(format!("Replay diverges: {divergence:?}"))),
+    }
+    Ok(())
+}
+
 pub(crate) fn dump_account_storage(
     account_id: String,
     storage_key: String,
@@ -1144,12 +1199,10 @@ fn get_state_stats_group_by<'a>(
                 let key_size = key.len() as u64;
                 let value_size = value.len() as u64;
                 let size = ByteSize::b(key_size + value_size);
-                let state_record = StateRecord::from_raw_key_value(key, value);
-                state_record.map(|state_record| StateStatsStateRecord {
-                    account_id: state_record_to_account_id(&state_record).clone(),
-                    state_record,
-                    size,
-                })
+                let state_record = StateRecord::from_raw_key_value(key, value)?;
+                // Global contract code doesn't belong to any account.
+                let account_id = state_record_to_account_id(&state_record)?.clone();
+                Some(StateStatsStateRecord { account_id, state_record, size })
             })
         })
         .into_iter();
@@ -136,6 +136,8 @@ pub(crate) enum ActionType {
     Delegate,
     RegisterRsa2048Keys,
     CreateRsa2048Challenge,
+    DeployGlobalContract,
+    UseGlobalContract,
 }
 
 impl ContractAccount {
@@ -346,6 +348,10 @@ fn try_find_actions_spawned_by_receipt(
                                     Action::CreateRsa2048Challenge(_) => {
                                         ActionType::CreateRsa2048Challenge
                                     }
+                                    Action::DeployGlobalContract(_) => {
+                                        ActionType::DeployGlobalContract
+                                    }
+                                    Action::UseGlobalContract(_) => ActionType::UseGlobalContract,
                                 };
                                 entry
                                     .actions
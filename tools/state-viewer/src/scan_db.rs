@@ -22,7 +22,8 @@ use unc_primitives::utils::{get_block_shard_id_rev, get_outcome_id_block_hash_re
 use unc_primitives_core::hash::CryptoHash;
 use unc_primitives_core::types::BlockHeight;
 use unc_store::flat::delta::KeyForFlatStateDelta;
-use unc_store::flat::{FlatStateChanges, FlatStateDeltaMetadata};
+use unc_store::flat::footprint::AccountStorageFootprint;
+use unc_store::flat::{FlatStateChanges, FlatStateDeltaMetadata, FlatStateHistoryEntry};
 use unc_store::{DBCol, RawTrieNodeWithSize, Store, TrieChanges};
 
 fn find_db_col(col: &str) -> DBCol {
@@ -147,6 +148,26 @@ fn format_key_and_value<'a>(
             Box::new(KeyForFlatStateDelta::try_from_slice(key).unwrap()),
             Box::new(FlatStateDeltaMetadata::try_from_slice(value).unwrap()),
         ),
+        DBCol::FlatStateHistory => {
+            let (shard_uid, height) = key.split_at(8);
+            (
+                Box::new((
+                    ShardUId::try_from_slice(shard_uid).unwrap(),
+                    BlockHeight::from_be_bytes(height.try_into().unwrap()),
+                )),
+                Box::new(FlatStateHistoryEntry::try_from_slice(value).unwrap()),
+            )
+        }
+        DBCol::AccountStorageFootprint => {
+            let (shard_uid, account_id) = key.split_at(8);
+            (
+                Box::new((
+                    ShardUId::try_from_slice(shard_uid).unwrap(),
+                    String::from_utf8_lossy(account_id).to_string(),
+                )),
+                Box::new(AccountStorageFootprint::try_from_slice(value).unwrap()),
+            )
+        }
         DBCol::FlatStorageStatus => (
             // TODO: Format keys as nibbles.
             Box::new(ShardUId::try_from_slice(key).unwrap()),
@@ -206,10 +206,11 @@ fn should_include_record(
 ) -> bool {
     match account_allowlist {
         None => true,
-        Some(allowlist) => {
-            let current_account_id = state_record_to_account_id(record);
-            allowlist.contains(current_account_id)
-        }
+        // Global contract code may be used by any of the selected accounts.
+        Some(allowlist) => match state_record_to_account_id(record) {
+            Some(current_account_id) => allowlist.contains(current_account_id),
+            None => true,
+        },
     }
 }
 
@@ -233,6 +234,8 @@ fn iterate_over_records(
         }
     };
     let mut total_supply = 0;
+    // Every shard has its own copy of the global contract code, dump it only once.
+    let mut global_code_hashes = HashSet::new();
     for (shard_id, state_root) in state_roots.iter().enumerate() {
         let trie = runtime
             .get_trie_for_shard(shard_id as u64, last_block_header.prev_hash(), *state_root, false)
@@ -243,6 +246,11 @@ fn iterate_over_records(
                 if !should_include_record(&sr, &account_allowlist) {
                     continue;
                 }
+                if let StateRecord::GlobalContractCode { code_hash, .. } = &sr {
+                    if !global_code_hashes.insert(*code_hash) {
+                        continue;
+                    }
+                }
                 if let StateRecord::Account { account_id, account } = &mut sr {
                     if account.pledging() > 0 {
                         let pledge = *validators.get(account_id).map(|(_, _, s)| s).unwrap_or(&0);
@@ -47,6 +47,10 @@ impl ColumnCountMap {
             col::POSTPONED_RECEIPT_ID => "POSTPONED_RECEIPT_ID",
             col::PENDING_DATA_COUNT => "PENDING_DATA_COUNT",
             col::POSTPONED_RECEIPT => "POSTPONED_RECEIPT",
+            col::PROMISE_YIELD_INDICES => "PROMISE_YIELD_INDICES",
+            col::PROMISE_YIELD_TIMEOUT => "PROMISE_YIELD_TIMEOUT",
+            col::PROMISE_YIELD_RECEIPT => "PROMISE_YIELD_RECEIPT",
+            col::GLOBAL_CONTRACT_CODE => "GLOBAL_CONTRACT_CODE",
             _ => unreachable!(),
         }
     }
@@ -197,7 +201,7 @@ impl TrieIterationBenchmarkCmd {
             };
             tracing::trace!(
                 target: "trie-iteration-benchmark",
-                "visiting column {} account id {}",
+                "visiting column {} account id {:?}",
                 &state_record.get_type_string(),state_record_to_account_id(&state_record)
             );
 
@@ -235,6 +239,12 @@ impl TrieIterationBenchmarkCmd {
             // key for delayed receipt only contains account id, nothing to prune
             // key for delayed receipt indices is a shard singleton, nothing to prune
             col::DELAYED_RECEIPT_OR_INDICES => false,
+            // key for promise yield indices is a shard singleton, nothing to prune
+            col::PROMISE_YIELD_INDICES => false,
+            // key for promise yield timeout only contains the index, nothing to prune
+            col::PROMISE_YIELD_TIMEOUT => false,
+            // key for global contract code only contains the code hash, nothing to prune
+            col::GLOBAL_CONTRACT_CODE => false,
 
             // Most columns use the ACCOUNT_DATA_SEPARATOR to indicate the end
             // of the accound id in the trie key. For those columns the
@@ -253,6 +263,9 @@ impl TrieIterationBenchmarkCmd {
             col::POSTPONED_RECEIPT => {
                 Self::partial_parse_account_id(col, &key, "POSTPONED RECEIPT")
             }
+            col::PROMISE_YIELD_RECEIPT => {
+                Self::partial_parse_account_id(col, &key, "PROMISE YIELD RECEIPT")
+            }
             _ => unreachable!(),
         };
 